@@ -0,0 +1,90 @@
+//! Pulling user funds through the allowance manager
+//!
+//! A user who has approved the allowance manager on a token once, and
+//! granted a contract an allowance in it, no longer has to sign a token
+//! transfer for every deposit or swap with that contract. [`pull`] moves
+//! the funds through the manager when the user's allowance covers them
+//! and falls back to a transfer the user authorizes otherwise, so users
+//! without an allowance keep working as before.
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contractclient, symbol_short, token, vec, Address, Env, IntoVal, Symbol,
+};
+
+use crate::ownable::require_admin;
+
+// Storage keys
+pub const ALLOWANCE_MANAGER: Symbol = symbol_short!("ALLOW_MGR");
+
+/// Side of the allowance manager spenders use
+#[contractclient(name = "AllowanceManagerClient")]
+pub trait AllowanceManagerInterface {
+    fn covers(env: Env, owner: Address, token: Address, spender: Address, amount: i128) -> bool;
+    fn transfer_from(
+        env: Env,
+        spender: Address,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: i128,
+    );
+}
+
+/// Pull funds through an allowance manager, or stop (only admin)
+pub fn set_manager(env: &Env, manager: Option<Address>) {
+    require_admin(env);
+    match &manager {
+        Some(manager) => env.storage().instance().set(&ALLOWANCE_MANAGER, manager),
+        None => env.storage().instance().remove(&ALLOWANCE_MANAGER),
+    }
+
+    env.events().publish(
+        (symbol_short!("ACCESS"), symbol_short!("ALLOW_MGR")),
+        manager,
+    );
+}
+
+pub fn get_manager(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&ALLOWANCE_MANAGER)
+}
+
+/// Move `amount` of `token` from `from` to the calling contract through
+/// the manager, if `from`'s allowance covers it, returning whether it did
+pub fn pull_permitted(env: &Env, token: &Address, from: &Address, amount: i128) -> bool {
+    let Some(manager) = get_manager(env) else {
+        return false;
+    };
+
+    let manager = AllowanceManagerClient::new(env, &manager);
+    let spender = env.current_contract_address();
+    if !manager.covers(from, token, &spender, &amount) {
+        return false;
+    }
+    manager.transfer_from(&spender, token, from, &spender, &amount);
+    true
+}
+
+/// Move `amount` of `token` from `from` to the calling contract, through
+/// the manager where `from`'s allowance covers it
+pub fn pull(env: &Env, token: &Address, from: &Address, amount: i128) {
+    if !pull_permitted(env, token, from, amount) {
+        token::Client::new(env, token).transfer(from, &env.current_contract_address(), &amount);
+    }
+}
+
+/// Let the next call out, to a contract other than `token`, move `amount`
+/// of the calling contract's `token` to `to`
+pub fn authorize_transfer(env: &Env, token: &Address, to: &Address, amount: i128) {
+    env.authorize_as_current_contract(vec![
+        env,
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+                args: (env.current_contract_address(), to.clone(), amount).into_val(env),
+            },
+            sub_invocations: vec![env],
+        }),
+    ]);
+}
@@ -0,0 +1,45 @@
+//! Reentrancy lock and invariant checks for entry points calling out to
+//! other contracts
+//!
+//! The Soroban host already refuses to re-enter a contract within one call
+//! stack, but a contract handing control to a venue, token or callback can
+//! still be reached through a different entry point of a contract the
+//! callee controls. [`non_reentrant`] holds a flag in the calling
+//! contract's storage for the length of a call, so any nested call into a
+//! guarded entry point of the same contract panics. A panic rolls the flag
+//! back with the rest of the call, so a failed call never leaves the
+//! contract locked.
+//!
+//! [`invariant!`](crate::invariant) and
+//! [`invariant_covered!`](crate::invariant_covered) check the state an entry
+//! point leaves behind, after its calls out have returned.
+
+use soroban_sdk::{symbol_short, Env, Symbol};
+
+// Storage keys
+pub const ENTERED: Symbol = symbol_short!("ENTERED");
+
+pub fn is_entered(env: &Env) -> bool {
+    env.storage().instance().get(&ENTERED).unwrap_or(false)
+}
+
+/// Take the lock, panicking if a guarded call is already in progress
+pub fn enter(env: &Env) {
+    if is_entered(env) {
+        panic!("Reentrant call");
+    }
+    env.storage().instance().set(&ENTERED, &true);
+}
+
+/// Release the lock taken by [`enter`]
+pub fn exit(env: &Env) {
+    env.storage().instance().remove(&ENTERED);
+}
+
+/// Run `f` holding the lock
+pub fn non_reentrant<T>(env: &Env, f: impl FnOnce() -> T) -> T {
+    enter(env);
+    let result = f();
+    exit(env);
+    result
+}
@@ -0,0 +1,594 @@
+#![no_std]
+
+//! Admin, pause and role-based access control shared by the StellHydra
+//! contracts
+//!
+//! Each module keeps its state in the calling contract's own storage, so a
+//! contract adopts it by calling the helpers from its entry points. The
+//! [`ownable!`], [`pausable!`], [`roles!`] and [`upgradeable!`] macros add
+//! the matching public entry points to a contract type, so every contract
+//! exposes the same admin handover, pause switch, role management and
+//! upgrade path.
+//!
+//! [`guard`] holds the reentrancy lock and, with the [`invariant!`] and
+//! [`invariant_covered!`] macros, the state checks for entry points that
+//! call out to other contracts.
+//!
+//! [`allowance`] pulls user funds through the allowance manager, and the
+//! [`allowance!`] macro adds the entry points pointing a contract at it.
+
+pub mod allowance;
+pub mod guard;
+pub mod ownable;
+pub mod pausable;
+pub mod roles;
+pub mod upgradeable;
+
+pub use guard::non_reentrant;
+pub use ownable::require_admin;
+pub use pausable::require_not_paused;
+pub use roles::{require_role, PAUSER};
+pub use upgradeable::Upgradeable;
+
+/// Panic with `Invariant violated: <message>` unless a condition holds
+///
+/// ```ignore
+/// invariant!(k_after >= k_before, "constant product decreased");
+/// ```
+#[macro_export]
+macro_rules! invariant {
+    ($cond:expr, $msg:literal) => {
+        if !$cond {
+            panic!(concat!("Invariant violated: ", $msg));
+        }
+    };
+}
+
+/// Panic unless the calling contract holds at least `amount` of `token`,
+/// e.g. the reserves or deposits it has on its books
+#[macro_export]
+macro_rules! invariant_covered {
+    ($env:expr, $token:expr, $amount:expr) => {
+        $crate::invariant!(
+            soroban_sdk::token::TokenClient::new(&$env, &$token)
+                .balance(&$env.current_contract_address())
+                >= $amount,
+            "token balance below recorded amount"
+        );
+    };
+}
+
+/// Add `get_admin`, `propose_admin`, `accept_admin` and
+/// `get_pending_admin` to a contract
+#[macro_export]
+macro_rules! ownable {
+    ($contract:ident) => {
+        #[soroban_sdk::contractimpl]
+        impl $contract {
+            pub fn get_admin(env: soroban_sdk::Env) -> soroban_sdk::Address {
+                $crate::ownable::get_admin(&env).unwrap_or_else(|| panic!("Not initialized"))
+            }
+
+            /// Propose a new admin (only admin)
+            ///
+            /// The admin only changes once the proposed address accepts, so a
+            /// mistyped address cannot lock the contract.
+            pub fn propose_admin(env: soroban_sdk::Env, new_admin: soroban_sdk::Address) {
+                $crate::ownable::propose_admin(&env, &new_admin);
+            }
+
+            /// Take over as admin (only the proposed admin)
+            pub fn accept_admin(env: soroban_sdk::Env) {
+                $crate::ownable::accept_admin(&env);
+            }
+
+            pub fn get_pending_admin(env: soroban_sdk::Env) -> Option<soroban_sdk::Address> {
+                $crate::ownable::get_pending_admin(&env)
+            }
+        }
+    };
+}
+
+/// Add `pause`, `unpause`, `is_paused`, `set_guardian` and `get_guardian`
+/// to a contract
+#[macro_export]
+macro_rules! pausable {
+    ($contract:ident) => {
+        #[soroban_sdk::contractimpl]
+        impl $contract {
+            /// Pause the contract (admin or pauser)
+            pub fn pause(env: soroban_sdk::Env, caller: soroban_sdk::Address) {
+                $crate::pausable::pause(&env, &caller);
+            }
+
+            /// Resume the contract (only admin)
+            pub fn unpause(env: soroban_sdk::Env) {
+                $crate::pausable::unpause(&env);
+            }
+
+            pub fn is_paused(env: soroban_sdk::Env) -> bool {
+                $crate::pausable::is_paused(&env)
+            }
+
+            /// Follow a pause guardian, or stop following one (only admin)
+            pub fn set_guardian(env: soroban_sdk::Env, guardian: Option<soroban_sdk::Address>) {
+                $crate::pausable::set_guardian(&env, guardian);
+            }
+
+            pub fn get_guardian(env: soroban_sdk::Env) -> Option<soroban_sdk::Address> {
+                $crate::pausable::get_guardian(&env)
+            }
+        }
+    };
+}
+
+/// Add `set_allowance_manager` and `get_allowance_manager` to a contract
+#[macro_export]
+macro_rules! allowance {
+    ($contract:ident) => {
+        #[soroban_sdk::contractimpl]
+        impl $contract {
+            /// Pull funds through an allowance manager, or stop (only admin)
+            pub fn set_allowance_manager(
+                env: soroban_sdk::Env,
+                manager: Option<soroban_sdk::Address>,
+            ) {
+                $crate::allowance::set_manager(&env, manager);
+            }
+
+            pub fn get_allowance_manager(env: soroban_sdk::Env) -> Option<soroban_sdk::Address> {
+                $crate::allowance::get_manager(&env)
+            }
+        }
+    };
+}
+
+/// Add `grant_role`, `revoke_role` and `has_role` to a contract
+#[macro_export]
+macro_rules! roles {
+    ($contract:ident) => {
+        #[soroban_sdk::contractimpl]
+        impl $contract {
+            /// Grant a role to an account (only admin)
+            pub fn grant_role(
+                env: soroban_sdk::Env,
+                role: soroban_sdk::Symbol,
+                account: soroban_sdk::Address,
+            ) {
+                $crate::roles::grant_role(&env, &role, &account);
+            }
+
+            /// Revoke a role from an account (only admin)
+            pub fn revoke_role(
+                env: soroban_sdk::Env,
+                role: soroban_sdk::Symbol,
+                account: soroban_sdk::Address,
+            ) {
+                $crate::roles::revoke_role(&env, &role, &account);
+            }
+
+            pub fn has_role(
+                env: soroban_sdk::Env,
+                role: soroban_sdk::Symbol,
+                account: soroban_sdk::Address,
+            ) -> bool {
+                $crate::roles::has_role(&env, &role, &account)
+            }
+        }
+    };
+}
+
+/// Add the upgrade entry points to a contract implementing
+/// [`Upgradeable`]
+#[macro_export]
+macro_rules! upgradeable {
+    ($contract:ident) => {
+        #[soroban_sdk::contractimpl]
+        impl $contract {
+            /// Schedule an upgrade to uploaded wasm (only upgrade authority)
+            ///
+            /// Returns when the upgrade can be executed.
+            pub fn propose_upgrade(
+                env: soroban_sdk::Env,
+                wasm_hash: soroban_sdk::BytesN<32>,
+            ) -> u64 {
+                <$contract as $crate::Upgradeable>::require_upgrade_authority(&env);
+                $crate::upgradeable::propose_upgrade(&env, &wasm_hash)
+            }
+
+            /// Drop the pending upgrade (only upgrade authority)
+            pub fn cancel_upgrade(env: soroban_sdk::Env) {
+                <$contract as $crate::Upgradeable>::require_upgrade_authority(&env);
+                $crate::upgradeable::cancel_upgrade(&env);
+            }
+
+            /// Swap in the proposed wasm once its delay has passed (only
+            /// upgrade authority)
+            pub fn execute_upgrade(env: soroban_sdk::Env) {
+                <$contract as $crate::Upgradeable>::require_upgrade_authority(&env);
+                $crate::upgradeable::execute_upgrade(&env);
+            }
+
+            /// Bring storage up to the version of the running code (only
+            /// upgrade authority)
+            pub fn migrate(env: soroban_sdk::Env) -> u32 {
+                <$contract as $crate::Upgradeable>::require_upgrade_authority(&env);
+                $crate::upgradeable::migrate::<$contract>(&env)
+            }
+
+            /// Raise the upgrade delay, in seconds (only upgrade authority)
+            pub fn set_upgrade_delay(env: soroban_sdk::Env, delay: u64) {
+                <$contract as $crate::Upgradeable>::require_upgrade_authority(&env);
+                $crate::upgradeable::set_upgrade_delay(&env, delay);
+            }
+
+            pub fn get_upgrade_delay(env: soroban_sdk::Env) -> u64 {
+                $crate::upgradeable::get_upgrade_delay(&env)
+            }
+
+            pub fn get_pending_upgrade(
+                env: soroban_sdk::Env,
+            ) -> Option<$crate::upgradeable::PendingUpgrade> {
+                $crate::upgradeable::get_pending_upgrade(&env)
+            }
+
+            pub fn get_version(env: soroban_sdk::Env) -> u32 {
+                $crate::upgradeable::get_version(&env)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use soroban_sdk::{
+        contract, contractimpl, symbol_short,
+        testutils::{Address as _, Ledger},
+        Address, Bytes, Env, Symbol,
+    };
+
+    use crate::upgradeable::DEFAULT_UPGRADE_DELAY;
+    use crate::Upgradeable;
+
+    const STEPS: Symbol = symbol_short!("STEPS");
+
+    /// Smallest wasm the host accepts: just the env meta custom section
+    const EMPTY_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x00, 0x1e, 0x11, // custom section, 30 bytes, 17-byte name
+        b'c', b'o', b'n', b't', b'r', b'a', b'c', b't', b'e', b'n', b'v', b'm', b'e', b't', b'a',
+        b'v', b'0', // "contractenvmetav0"
+        0x00, 0x00, 0x00, 0x00, // interface version entry
+        0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x00, // protocol 21
+    ];
+
+    #[contract]
+    pub struct Guarded;
+
+    #[contractimpl]
+    impl Guarded {
+        pub fn initialize(env: Env, admin: Address) {
+            crate::ownable::init_admin(&env, &admin);
+        }
+
+        pub fn act(env: Env) -> u32 {
+            crate::require_not_paused(&env);
+            1
+        }
+
+        pub fn admin_only(env: Env) -> Address {
+            crate::require_admin(&env)
+        }
+
+        pub fn keeper_only(env: Env, keeper: Address) {
+            crate::require_role(&env, &symbol_short!("KEEPER"), &keeper);
+        }
+
+        pub fn locked(env: Env, nested: bool) -> bool {
+            crate::non_reentrant(&env, || {
+                if nested {
+                    crate::non_reentrant(&env, || ());
+                }
+                crate::guard::is_entered(&env)
+            })
+        }
+
+        pub fn backed(env: Env, token: Address, amount: i128) {
+            invariant!(amount >= 0, "negative amount");
+            invariant_covered!(env, token, amount);
+        }
+
+        pub fn deposit(env: Env, from: Address, token: Address, amount: i128) {
+            from.require_auth();
+            crate::allowance::pull(&env, &token, &from, amount);
+        }
+    }
+
+    #[contract]
+    pub struct MockManager;
+
+    #[contractimpl]
+    impl MockManager {
+        pub fn set_allowance(env: Env, owner: Address, amount: i128) {
+            env.storage().instance().set(&owner, &amount);
+        }
+
+        pub fn covers(
+            env: Env,
+            owner: Address,
+            _token: Address,
+            _spender: Address,
+            amount: i128,
+        ) -> bool {
+            env.storage().instance().get(&owner).unwrap_or(0) >= amount
+        }
+
+        pub fn transfer_from(
+            env: Env,
+            spender: Address,
+            token: Address,
+            from: Address,
+            to: Address,
+            amount: i128,
+        ) {
+            spender.require_auth();
+            let allowance: i128 = env.storage().instance().get(&from).unwrap_or(0);
+            env.storage().instance().set(&from, &(allowance - amount));
+            soroban_sdk::token::Client::new(&env, &token).transfer_from(
+                &env.current_contract_address(),
+                &from,
+                &to,
+                &amount,
+            );
+        }
+    }
+
+    impl Upgradeable for Guarded {
+        const VERSION: u32 = 3;
+
+        fn migrate_step(env: &Env, from: u32) {
+            let steps: u32 = env.storage().instance().get(&STEPS).unwrap_or(0);
+            env.storage().instance().set(&STEPS, &(steps * 10 + from));
+        }
+    }
+
+    allowance!(Guarded);
+    ownable!(Guarded);
+    pausable!(Guarded);
+    roles!(Guarded);
+    upgradeable!(Guarded);
+
+    fn setup(env: &Env) -> (GuardedClient<'_>, Address) {
+        env.mock_all_auths();
+        let admin = Address::generate(env);
+        let client = GuardedClient::new(env, &env.register_contract(None, Guarded));
+        client.initialize(&admin);
+        (client, admin)
+    }
+
+    #[test]
+    fn test_admin_handover() {
+        let env = Env::default();
+        let (client, admin) = setup(&env);
+        let new_admin = Address::generate(&env);
+
+        assert_eq!(client.get_admin(), admin);
+        assert_eq!(client.admin_only(), admin);
+
+        client.propose_admin(&new_admin);
+        assert_eq!(client.get_pending_admin(), Some(new_admin.clone()));
+        assert_eq!(client.get_admin(), admin);
+
+        client.accept_admin();
+        assert_eq!(client.get_admin(), new_admin);
+        assert_eq!(client.get_pending_admin(), None);
+        assert!(client.try_accept_admin().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Already initialized")]
+    fn test_initialize_twice() {
+        let env = Env::default();
+        let (client, admin) = setup(&env);
+        client.initialize(&admin);
+    }
+
+    #[test]
+    fn test_pauser_can_pause_but_not_unpause() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+        let pauser = Address::generate(&env);
+        let stranger = Address::generate(&env);
+
+        assert!(client.try_pause(&stranger).is_err());
+
+        client.grant_role(&crate::PAUSER, &pauser);
+        assert!(client.has_role(&crate::PAUSER, &pauser));
+
+        client.pause(&pauser);
+        assert!(client.is_paused());
+        assert!(client.try_act().is_err());
+
+        client.unpause();
+        assert!(!client.is_paused());
+        assert_eq!(client.act(), 1);
+
+        client.revoke_role(&crate::PAUSER, &pauser);
+        assert!(client.try_pause(&pauser).is_err());
+    }
+
+    #[test]
+    fn test_guardian_pause_halts_followers() {
+        let env = Env::default();
+        let (guardian, guardian_admin) = setup(&env);
+        let (follower, _) = setup(&env);
+        follower.set_guardian(&Some(guardian.address.clone()));
+        assert_eq!(follower.get_guardian(), Some(guardian.address.clone()));
+
+        guardian.pause(&guardian_admin);
+        assert!(follower.is_paused());
+        assert!(follower.try_act().is_err());
+
+        guardian.unpause();
+        assert_eq!(follower.act(), 1);
+
+        // A follower that stops following ignores the guardian
+        guardian.pause(&guardian_admin);
+        follower.set_guardian(&None);
+        assert_eq!(follower.act(), 1);
+    }
+
+    #[test]
+    fn test_require_role() {
+        let env = Env::default();
+        let (client, admin) = setup(&env);
+        let keeper = Address::generate(&env);
+        let keeper_role = symbol_short!("KEEPER");
+
+        assert!(client.try_keeper_only(&keeper).is_err());
+        assert!(client.try_keeper_only(&admin).is_err());
+
+        client.grant_role(&keeper_role, &keeper);
+        client.keeper_only(&keeper);
+        assert!(!client.has_role(&crate::PAUSER, &keeper));
+    }
+
+    #[test]
+    fn test_uninitialized_contract_has_no_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = GuardedClient::new(&env, &env.register_contract(None, Guarded));
+        let account = Address::generate(&env);
+
+        assert!(client.try_get_admin().is_err());
+        assert!(client.try_admin_only().is_err());
+        assert!(client.try_grant_role(&crate::PAUSER, &account).is_err());
+    }
+
+    #[test]
+    fn test_nested_guarded_call_panics() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+
+        assert!(client.locked(&false));
+        assert!(client.try_locked(&true).is_err());
+
+        // The failed call rolled its lock back
+        assert!(client.locked(&false));
+        env.as_contract(&client.address, || {
+            assert!(!crate::guard::is_entered(&env));
+        });
+    }
+
+    #[test]
+    fn test_invariants() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+
+        client.backed(&token, &0);
+        assert!(client.try_backed(&token, &-1).is_err());
+        assert!(client.try_backed(&token, &100).is_err());
+
+        soroban_sdk::token::StellarAssetClient::new(&env, &token).mint(&client.address, &100);
+        client.backed(&token, &100);
+        assert!(client.try_backed(&token, &101).is_err());
+    }
+
+    #[test]
+    fn test_pull_goes_through_covering_allowance() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = soroban_sdk::token::Client::new(&env, &token);
+        let user = Address::generate(&env);
+        soroban_sdk::token::StellarAssetClient::new(&env, &token).mint(&user, &300);
+
+        // Without a manager the user transfers directly
+        client.deposit(&user, &token, &100);
+        assert_eq!(token_client.balance(&client.address), 100);
+
+        let manager = MockManagerClient::new(&env, &env.register_contract(None, MockManager));
+        client.set_allowance_manager(&Some(manager.address.clone()));
+        assert_eq!(
+            client.get_allowance_manager(),
+            Some(manager.address.clone())
+        );
+        token_client.approve(&user, &manager.address, &1_000, &1_000);
+        manager.set_allowance(&user, &150);
+
+        client.deposit(&user, &token, &100);
+        assert_eq!(token_client.allowance(&user, &manager.address), 900);
+
+        // Past the allowance the user transfers directly again
+        client.deposit(&user, &token, &100);
+        assert_eq!(token_client.allowance(&user, &manager.address), 900);
+        assert_eq!(token_client.balance(&client.address), 300);
+    }
+
+    #[test]
+    fn test_upgrade_waits_for_delay() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+        let wasm_hash = env
+            .deployer()
+            .upload_contract_wasm(Bytes::from_slice(&env, EMPTY_WASM));
+
+        assert!(client.try_execute_upgrade().is_err());
+        let eta = client.propose_upgrade(&wasm_hash);
+        assert_eq!(eta, env.ledger().timestamp() + DEFAULT_UPGRADE_DELAY);
+        assert_eq!(client.get_pending_upgrade().unwrap().wasm_hash, wasm_hash);
+
+        env.ledger().with_mut(|li| li.timestamp = eta - 1);
+        assert!(client.try_execute_upgrade().is_err());
+
+        client.cancel_upgrade();
+        assert_eq!(client.get_pending_upgrade(), None);
+        let eta = client.propose_upgrade(&wasm_hash);
+
+        env.ledger().with_mut(|li| li.timestamp = eta);
+        client.execute_upgrade();
+
+        // The empty wasm exports nothing, so the old entry points are gone
+        assert!(client.try_get_version().is_err());
+    }
+
+    #[test]
+    fn test_upgrade_delay_only_rises() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+
+        assert_eq!(client.get_upgrade_delay(), DEFAULT_UPGRADE_DELAY);
+        assert!(client.try_set_upgrade_delay(&0).is_err());
+
+        client.set_upgrade_delay(&(2 * DEFAULT_UPGRADE_DELAY));
+        assert_eq!(client.get_upgrade_delay(), 2 * DEFAULT_UPGRADE_DELAY);
+        assert!(client
+            .try_set_upgrade_delay(&DEFAULT_UPGRADE_DELAY)
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_runs_each_step_once() {
+        let env = Env::default();
+        let (client, _) = setup(&env);
+        let steps = || -> u32 {
+            env.as_contract(&client.address, || {
+                env.storage().instance().get(&STEPS).unwrap_or(0)
+            })
+        };
+
+        assert_eq!(client.get_version(), 1);
+        assert_eq!(client.migrate(), 3);
+        assert_eq!(client.get_version(), 3);
+        assert_eq!(steps(), 12);
+
+        // Nothing left to migrate
+        assert_eq!(client.migrate(), 3);
+        assert_eq!(steps(), 12);
+    }
+}
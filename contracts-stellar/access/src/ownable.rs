@@ -0,0 +1,63 @@
+//! Single admin with a two-step handover
+//!
+//! The admin only changes once the proposed address accepts, so a mistyped
+//! address cannot lock the contract.
+
+use soroban_sdk::{symbol_short, Address, Env, Symbol};
+
+// Storage keys
+pub const ADMIN: Symbol = symbol_short!("ADMIN");
+pub const PENDING_ADMIN: Symbol = symbol_short!("PEND_ADMN");
+
+/// Set the first admin, panicking if one is already set
+pub fn init_admin(env: &Env, admin: &Address) {
+    if has_admin(env) {
+        panic!("Already initialized");
+    }
+    env.storage().instance().set(&ADMIN, admin);
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&ADMIN)
+}
+
+pub fn get_admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&ADMIN)
+}
+
+/// Require the admin's authorization and return the admin
+pub fn require_admin(env: &Env) -> Address {
+    let admin = get_admin(env).unwrap_or_else(|| panic!("Not initialized"));
+    admin.require_auth();
+    admin
+}
+
+/// Propose a new admin (only admin)
+pub fn propose_admin(env: &Env, new_admin: &Address) {
+    let admin = require_admin(env);
+    env.storage().instance().set(&PENDING_ADMIN, new_admin);
+
+    env.events().publish(
+        (symbol_short!("ADMIN"), symbol_short!("PROPOSED")),
+        (admin, new_admin.clone()),
+    );
+}
+
+/// Take over as admin (only the proposed admin)
+pub fn accept_admin(env: &Env) {
+    let new_admin = get_pending_admin(env).unwrap_or_else(|| panic!("No admin proposed"));
+    new_admin.require_auth();
+
+    let previous = get_admin(env).unwrap_or_else(|| panic!("Not initialized"));
+    env.storage().instance().set(&ADMIN, &new_admin);
+    env.storage().instance().remove(&PENDING_ADMIN);
+
+    env.events().publish(
+        (symbol_short!("ADMIN"), symbol_short!("ACCEPTED")),
+        (previous, new_admin),
+    );
+}
+
+pub fn get_pending_admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&PENDING_ADMIN)
+}
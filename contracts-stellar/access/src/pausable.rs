@@ -0,0 +1,85 @@
+//! Contract-wide pause switch
+//!
+//! The admin or any holder of the [`PAUSER`] role can pause, but only the
+//! admin can unpause, so a compromised pauser key can halt the contract
+//! without being able to reopen it.
+//!
+//! The admin can also point the contract at a pause guardian, whose
+//! `is_paused` view it reads on every check. One emergency pause on the
+//! guardian then halts every contract following it. A guardian that
+//! can't be read fails closed: the check panics as if paused.
+
+use soroban_sdk::{contractclient, symbol_short, Address, Env, Symbol};
+
+use crate::ownable::{get_admin, require_admin};
+use crate::roles::{has_role, PAUSER};
+
+// Storage keys
+pub const PAUSED: Symbol = symbol_short!("PAUSED");
+pub const GUARDIAN: Symbol = symbol_short!("GUARDIAN");
+
+/// View every pause guardian serves
+#[contractclient(name = "GuardianClient")]
+pub trait GuardianInterface {
+    fn is_paused(env: Env) -> bool;
+}
+
+/// Whether the contract is paused, by its own switch or its guardian
+pub fn is_paused(env: &Env) -> bool {
+    env.storage().instance().get(&PAUSED).unwrap_or(false)
+        || get_guardian(env).is_some_and(|guardian| GuardianClient::new(env, &guardian).is_paused())
+}
+
+/// Panic if the contract is paused
+pub fn require_not_paused(env: &Env) {
+    if is_paused(env) {
+        panic!("Contract is paused");
+    }
+}
+
+/// Pause the contract (admin or pauser)
+pub fn pause(env: &Env, caller: &Address) {
+    caller.require_auth();
+    if get_admin(env).as_ref() != Some(caller) && !has_role(env, &PAUSER, caller) {
+        panic!("Not allowed to pause");
+    }
+    set_paused(env, true);
+}
+
+/// Resume the contract (only admin)
+pub fn unpause(env: &Env) {
+    require_admin(env);
+    set_paused(env, false);
+}
+
+/// Follow a pause guardian, or stop following one (only admin)
+pub fn set_guardian(env: &Env, guardian: Option<Address>) {
+    require_admin(env);
+    match &guardian {
+        Some(guardian) => env.storage().instance().set(&GUARDIAN, guardian),
+        None => env.storage().instance().remove(&GUARDIAN),
+    }
+
+    env.events().publish(
+        (symbol_short!("ACCESS"), symbol_short!("GUARDIAN")),
+        guardian,
+    );
+}
+
+pub fn get_guardian(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&GUARDIAN)
+}
+
+/// Flip the switch without an authorization check, for contracts that
+/// gate pausing through their own rules
+pub fn set_paused(env: &Env, paused: bool) {
+    env.storage().instance().set(&PAUSED, &paused);
+
+    let action = if paused {
+        symbol_short!("PAUSED")
+    } else {
+        symbol_short!("UNPAUSED")
+    };
+    env.events()
+        .publish((symbol_short!("ACCESS"), action), paused);
+}
@@ -0,0 +1,81 @@
+//! Named roles granted to accounts by the admin
+//!
+//! Grants live in persistent storage keyed by role and account, so a
+//! contract can hand out any number of them without growing its instance
+//! entry.
+
+use soroban_sdk::{symbol_short, Address, Env, Symbol};
+
+use crate::ownable::require_admin;
+
+// Storage keys
+pub const ROLE: Symbol = symbol_short!("ROLE");
+
+/// Allowed to pause, but not unpause, a contract
+pub const PAUSER: Symbol = symbol_short!("PAUSER");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+pub fn has_role(env: &Env, role: &Symbol, account: &Address) -> bool {
+    let key = (ROLE, role.clone(), account.clone());
+    if !env.storage().persistent().has(&key) {
+        return false;
+    }
+    env.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+    true
+}
+
+/// Require the account's authorization and that it holds the role
+pub fn require_role(env: &Env, role: &Symbol, account: &Address) {
+    account.require_auth();
+    if !has_role(env, role, account) {
+        panic!("Missing role");
+    }
+}
+
+/// Grant a role to an account (only admin)
+pub fn grant_role(env: &Env, role: &Symbol, account: &Address) {
+    require_admin(env);
+
+    let key = (ROLE, role.clone(), account.clone());
+    env.storage().persistent().set(&key, &true);
+    env.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+
+    env.events().publish(
+        (
+            symbol_short!("ROLE"),
+            symbol_short!("GRANTED"),
+            role.clone(),
+        ),
+        account.clone(),
+    );
+}
+
+/// Revoke a role from an account (only admin)
+pub fn revoke_role(env: &Env, role: &Symbol, account: &Address) {
+    require_admin(env);
+
+    env.storage()
+        .persistent()
+        .remove(&(ROLE, role.clone(), account.clone()));
+
+    env.events().publish(
+        (
+            symbol_short!("ROLE"),
+            symbol_short!("REVOKED"),
+            role.clone(),
+        ),
+        account.clone(),
+    );
+}
@@ -0,0 +1,163 @@
+//! Delayed wasm upgrades with versioned storage migrations
+//!
+//! An upgrade is proposed with the hash of already uploaded wasm and can
+//! only be executed once the upgrade delay has passed, giving users time
+//! to exit before new code runs. The delay can be raised but never
+//! lowered. After the new code is live, `migrate` runs its
+//! [`Upgradeable::migrate_step`] once for every storage version between
+//! the stored one and [`Upgradeable::VERSION`].
+
+use soroban_sdk::{contracttype, symbol_short, BytesN, Env, Symbol, TryFromVal, Val, Vec};
+
+use crate::ownable::require_admin;
+
+// Storage keys
+pub const UPGRADE: Symbol = symbol_short!("UPGRADE");
+pub const UPGRADE_DELAY: Symbol = symbol_short!("UPG_DELAY");
+pub const VERSION: Symbol = symbol_short!("VERSION");
+
+/// Delay before a proposed upgrade can be executed, in seconds
+pub const DEFAULT_UPGRADE_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// Upgrade waiting for its delay to pass
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingUpgrade {
+    pub wasm_hash: BytesN<32>,
+    pub eta: u64,
+}
+
+/// How a contract is upgraded and migrated
+pub trait Upgradeable {
+    /// Storage version the current code expects
+    const VERSION: u32 = 1;
+
+    /// Require the authorization of whoever may upgrade the contract
+    fn require_upgrade_authority(env: &Env) {
+        require_admin(env);
+    }
+
+    /// Move storage from version `from` to `from + 1`
+    fn migrate_step(_env: &Env, _from: u32) {}
+}
+
+pub fn get_upgrade_delay(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&UPGRADE_DELAY)
+        .unwrap_or(DEFAULT_UPGRADE_DELAY)
+}
+
+/// Raise the upgrade delay
+pub fn set_upgrade_delay(env: &Env, delay: u64) {
+    if delay < get_upgrade_delay(env) {
+        panic!("Upgrade delay can only be raised");
+    }
+    env.storage().instance().set(&UPGRADE_DELAY, &delay);
+
+    env.events()
+        .publish((symbol_short!("UPGRADE"), symbol_short!("DELAY")), delay);
+}
+
+pub fn get_pending_upgrade(env: &Env) -> Option<PendingUpgrade> {
+    env.storage().instance().get(&UPGRADE)
+}
+
+/// Schedule an upgrade to uploaded wasm, replacing any pending one
+pub fn propose_upgrade(env: &Env, wasm_hash: &BytesN<32>) -> u64 {
+    let eta = env.ledger().timestamp() + get_upgrade_delay(env);
+    let upgrade = PendingUpgrade {
+        wasm_hash: wasm_hash.clone(),
+        eta,
+    };
+    env.storage().instance().set(&UPGRADE, &upgrade);
+
+    env.events().publish(
+        (symbol_short!("UPGRADE"), symbol_short!("PROPOSED")),
+        (wasm_hash.clone(), eta),
+    );
+    eta
+}
+
+pub fn cancel_upgrade(env: &Env) {
+    let upgrade = get_pending_upgrade(env).unwrap_or_else(|| panic!("No upgrade proposed"));
+    env.storage().instance().remove(&UPGRADE);
+
+    env.events().publish(
+        (symbol_short!("UPGRADE"), symbol_short!("CANCELLED")),
+        upgrade.wasm_hash,
+    );
+}
+
+/// Swap in the proposed wasm once its delay has passed
+///
+/// The new code runs from the next invocation on, which should be
+/// `migrate`.
+pub fn execute_upgrade(env: &Env) {
+    let upgrade = get_pending_upgrade(env).unwrap_or_else(|| panic!("No upgrade proposed"));
+    if env.ledger().timestamp() < upgrade.eta {
+        panic!("Upgrade delay not elapsed");
+    }
+    env.storage().instance().remove(&UPGRADE);
+
+    env.events().publish(
+        (symbol_short!("UPGRADE"), symbol_short!("EXECUTED")),
+        upgrade.wasm_hash.clone(),
+    );
+    env.deployer()
+        .update_current_contract_wasm(upgrade.wasm_hash);
+}
+
+/// Storage version, 1 for contracts that were never migrated
+pub fn get_version(env: &Env) -> u32 {
+    env.storage().instance().get(&VERSION).unwrap_or(1)
+}
+
+/// Bring storage up to the version the current code expects
+pub fn migrate<C: Upgradeable>(env: &Env) -> u32 {
+    let from = get_version(env);
+    if from > C::VERSION {
+        panic!("Storage is newer than the contract");
+    }
+    for version in from..C::VERSION {
+        C::migrate_step(env, version);
+    }
+    env.storage().instance().set(&VERSION, &C::VERSION);
+
+    env.events().publish(
+        (symbol_short!("UPGRADE"), symbol_short!("MIGRATED")),
+        (from, C::VERSION),
+    );
+    C::VERSION
+}
+
+/// Apply an upgrade call a self-governed contract made to itself
+///
+/// Contracts that only act through their own proposals can't call their
+/// upgrade entry points, so they route those calls here instead. Returns
+/// false when `function` is not an upgrade call.
+pub fn apply_own_call<C: Upgradeable>(env: &Env, function: &Symbol, args: &Vec<Val>) -> bool {
+    let arg = |index: u32| {
+        args.get(index)
+            .unwrap_or_else(|| panic!("Missing upgrade argument"))
+    };
+
+    if *function == Symbol::new(env, "propose_upgrade") {
+        let wasm_hash = BytesN::<32>::try_from_val(env, &arg(0))
+            .unwrap_or_else(|_| panic!("Invalid wasm hash"));
+        propose_upgrade(env, &wasm_hash);
+    } else if *function == Symbol::new(env, "cancel_upgrade") {
+        cancel_upgrade(env);
+    } else if *function == Symbol::new(env, "execute_upgrade") {
+        execute_upgrade(env);
+    } else if *function == Symbol::new(env, "migrate") {
+        migrate::<C>(env);
+    } else if *function == Symbol::new(env, "set_upgrade_delay") {
+        let delay =
+            u64::try_from_val(env, &arg(0)).unwrap_or_else(|_| panic!("Invalid upgrade delay"));
+        set_upgrade_delay(env, delay);
+    } else {
+        return false;
+    }
+    true
+}
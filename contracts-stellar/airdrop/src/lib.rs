@@ -0,0 +1,289 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address,
+    Bytes, BytesN, Env, Symbol, Vec,
+};
+
+/// Allocation set the admin has published
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Campaign {
+    pub root: BytesN<32>, // Merkle root of the (address, amount) allocations
+    pub claim_deadline: u64,
+    pub claimed: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    NoCampaign = 3,
+    InvalidDeadline = 4,
+    ClaimWindowClosed = 5,
+    ClaimWindowOpen = 6,
+    AlreadyClaimed = 7,
+    InvalidProof = 8,
+    NothingToSweep = 9,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const TOKEN: Symbol = symbol_short!("TOKEN");
+const CAMPAIGN: Symbol = symbol_short!("CAMPAIGN");
+const CLAIMED: Symbol = symbol_short!("CLAIMED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Pays out allocations committed to by a Merkle root
+///
+/// Leaves are the sha256 of the XDR of `(address, amount)`, and each level
+/// hashes the sorted concatenation of its two children, so proofs carry no
+/// left/right flags.
+#[contract]
+pub struct Airdrop;
+
+#[contractimpl]
+impl Airdrop {
+    /// Initialize the distributor for the token being airdropped
+    pub fn initialize(env: Env, admin: Address, token: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&TOKEN, &token);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Publish the allocations and when claiming closes (only admin)
+    ///
+    /// The contract must hold enough of the token to cover them.
+    pub fn set_merkle_root(env: Env, root: BytesN<32>, claim_deadline: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        if claim_deadline <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+
+        let claimed = Self::get_campaign(env.clone()).map_or(0, |campaign| campaign.claimed);
+        let campaign = Campaign {
+            root: root.clone(),
+            claim_deadline,
+            claimed,
+        };
+        env.storage().instance().set(&CAMPAIGN, &campaign);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("AIRDROP"), symbol_short!("ROOT")),
+            (root, claim_deadline),
+        );
+        Ok(())
+    }
+
+    pub fn get_campaign(env: Env) -> Result<Campaign, Error> {
+        env.storage()
+            .instance()
+            .get(&CAMPAIGN)
+            .ok_or(Error::NoCampaign)
+    }
+
+    /// Claim an allocation with its Merkle proof
+    pub fn claim(
+        env: Env,
+        account: Address,
+        amount: i128,
+        proof: Vec<BytesN<32>>,
+    ) -> Result<(), Error> {
+        account.require_auth();
+
+        let mut campaign = Self::get_campaign(env.clone())?;
+        if env.ledger().timestamp() > campaign.claim_deadline {
+            return Err(Error::ClaimWindowClosed);
+        }
+        if Self::is_claimed(env.clone(), account.clone()) {
+            return Err(Error::AlreadyClaimed);
+        }
+
+        let leaf = Self::leaf_hash(env.clone(), account.clone(), amount);
+        if Self::compute_root(&env, leaf, &proof) != campaign.root {
+            return Err(Error::InvalidProof);
+        }
+
+        let key = (CLAIMED, account.clone());
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        campaign.claimed += amount;
+        env.storage().instance().set(&CAMPAIGN, &campaign);
+
+        let token: Address = env.storage().instance().get(&TOKEN).unwrap();
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &account,
+            &amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("AIRDROP"), symbol_short!("CLAIMED")),
+            (account, amount),
+        );
+        Ok(())
+    }
+
+    pub fn is_claimed(env: Env, account: Address) -> bool {
+        env.storage().persistent().has(&(CLAIMED, account))
+    }
+
+    /// Send what was left unclaimed to `to` once claiming has closed
+    /// (only admin)
+    pub fn sweep(env: Env, to: Address) -> Result<i128, Error> {
+        Self::require_admin(&env)?;
+
+        let campaign = Self::get_campaign(env.clone())?;
+        if env.ledger().timestamp() <= campaign.claim_deadline {
+            return Err(Error::ClaimWindowOpen);
+        }
+
+        let token: Address = env.storage().instance().get(&TOKEN).unwrap();
+        let token_client = token::Client::new(&env, &token);
+        let remaining = token_client.balance(&env.current_contract_address());
+        if remaining <= 0 {
+            return Err(Error::NothingToSweep);
+        }
+        token_client.transfer(&env.current_contract_address(), &to, &remaining);
+
+        env.events().publish(
+            (symbol_short!("AIRDROP"), symbol_short!("SWEPT")),
+            (to, remaining),
+        );
+        Ok(remaining)
+    }
+
+    /// Leaf of an allocation, for building the tree off-chain
+    pub fn leaf_hash(env: Env, account: Address, amount: i128) -> BytesN<32> {
+        env.crypto().sha256(&(account, amount).to_xdr(&env)).into()
+    }
+}
+
+impl stellhydra_access::Upgradeable for Airdrop {}
+
+stellhydra_access::upgradeable!(Airdrop);
+
+impl Airdrop {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn compute_root(env: &Env, leaf: BytesN<32>, proof: &Vec<BytesN<32>>) -> BytesN<32> {
+        let mut node = leaf;
+        for sibling in proof.iter() {
+            node = Self::hash_pair(env, &node, &sibling);
+        }
+        node
+    }
+
+    fn hash_pair(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut data = Bytes::from(first.clone());
+        data.append(&Bytes::from(second.clone()));
+        env.crypto().sha256(&data).into()
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        vec, Env,
+    };
+
+    #[test]
+    fn test_claims_with_proofs_then_sweep() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = token::Client::new(&env, &token);
+        let airdrop_id = env.register_contract(None, Airdrop);
+        let airdrop = AirdropClient::new(&env, &airdrop_id);
+        airdrop.initialize(&Address::generate(&env), &token);
+        StellarAssetClient::new(&env, &token).mint(&airdrop_id, &1_000);
+
+        // Tree of three allocations: ((a, b), c)
+        let accounts = [
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        ];
+        let amounts = [100i128, 200, 300];
+        let leaves: [BytesN<32>; 3] =
+            core::array::from_fn(|i| airdrop.leaf_hash(&accounts[i], &amounts[i]));
+        let ab = env.as_contract(&airdrop_id, || {
+            Airdrop::hash_pair(&env, &leaves[0], &leaves[1])
+        });
+        let root = env.as_contract(&airdrop_id, || Airdrop::hash_pair(&env, &ab, &leaves[2]));
+
+        assert_eq!(
+            airdrop.try_claim(&accounts[0], &100, &vec![&env]),
+            Err(Ok(Error::NoCampaign))
+        );
+        airdrop.set_merkle_root(&root, &1_000);
+
+        let proof_a = vec![&env, leaves[1].clone(), leaves[2].clone()];
+        assert_eq!(
+            airdrop.try_claim(&accounts[0], &999, &proof_a),
+            Err(Ok(Error::InvalidProof))
+        );
+        airdrop.claim(&accounts[0], &100, &proof_a);
+        assert_eq!(token_client.balance(&accounts[0]), 100);
+        assert_eq!(
+            airdrop.try_claim(&accounts[0], &100, &proof_a),
+            Err(Ok(Error::AlreadyClaimed))
+        );
+
+        airdrop.claim(&accounts[2], &300, &vec![&env, ab]);
+        assert_eq!(airdrop.get_campaign().claimed, 400);
+
+        let treasury = Address::generate(&env);
+        assert_eq!(
+            airdrop.try_sweep(&treasury),
+            Err(Ok(Error::ClaimWindowOpen))
+        );
+        env.ledger().with_mut(|li| li.timestamp = 1_001);
+        let proof_b = vec![&env, leaves[0].clone(), leaves[2].clone()];
+        assert_eq!(
+            airdrop.try_claim(&accounts[1], &200, &proof_b),
+            Err(Ok(Error::ClaimWindowClosed))
+        );
+        assert_eq!(airdrop.sweep(&treasury), 600);
+        assert_eq!(token_client.balance(&treasury), 600);
+    }
+}
@@ -0,0 +1,332 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+};
+
+/// What one spender may pull of one owner's token
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Allowance {
+    pub amount: i128,    // left to pull in total
+    pub per_call: i128,  // most pulled by a single call
+    pub expiration: u64, // timestamp after which nothing can be pulled
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    InvalidAllowance = 1,
+    NoAllowance = 2,
+    Expired = 3,
+    InsufficientAllowance = 4,
+    PerCallLimitExceeded = 5,
+    InvalidAmount = 6,
+    AlreadyInitialized = 7,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const ALLOWANCE: Symbol = symbol_short!("ALLOWANCE");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Allowances shared by the contracts pulling user funds
+///
+/// A user approves the manager on a token once, with the token's own
+/// `approve`, then grants each contract (the pool, the router, the bridge)
+/// its own allowance here, scoped by a total amount, a per-call limit and
+/// an expiry. Those contracts pull deposits through `transfer_from`
+/// without the user signing a token transfer each time, and revoking one
+/// contract's allowance leaves the others untouched.
+///
+/// The admin can do nothing but upgrade the manager, and only after the
+/// upgrade delay, which leaves users time to revoke the standing token
+/// approvals they gave it before new code runs.
+#[contract]
+pub struct AllowanceManager;
+
+#[contractimpl]
+impl AllowanceManager {
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Set what `spender` may pull of the owner's `token`, replacing any
+    /// earlier allowance
+    pub fn approve(
+        env: Env,
+        owner: Address,
+        token: Address,
+        spender: Address,
+        allowance: Allowance,
+    ) -> Result<(), Error> {
+        owner.require_auth();
+        if allowance.amount < 0
+            || allowance.per_call <= 0
+            || allowance.expiration <= env.ledger().timestamp()
+        {
+            return Err(Error::InvalidAllowance);
+        }
+
+        let key = (ALLOWANCE, owner.clone(), token.clone(), spender.clone());
+        env.storage().persistent().set(&key, &allowance);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("ALLOW"), symbol_short!("APPROVED"), owner),
+            (token, spender, allowance.amount, allowance.expiration),
+        );
+        Ok(())
+    }
+
+    /// Drop what `spender` may pull of the owner's `token`
+    pub fn revoke(env: Env, owner: Address, token: Address, spender: Address) {
+        owner.require_auth();
+        env.storage().persistent().remove(&(
+            ALLOWANCE,
+            owner.clone(),
+            token.clone(),
+            spender.clone(),
+        ));
+
+        env.events().publish(
+            (symbol_short!("ALLOW"), symbol_short!("REVOKED"), owner),
+            (token, spender),
+        );
+    }
+
+    pub fn allowance(
+        env: Env,
+        owner: Address,
+        token: Address,
+        spender: Address,
+    ) -> Option<Allowance> {
+        env.storage()
+            .persistent()
+            .get(&(ALLOWANCE, owner, token, spender))
+    }
+
+    /// Whether `spender` may pull `amount` of the owner's `token` in one
+    /// call right now
+    ///
+    /// Doesn't check the token approval the manager itself holds.
+    pub fn covers(
+        env: Env,
+        owner: Address,
+        token: Address,
+        spender: Address,
+        amount: i128,
+    ) -> bool {
+        Self::check(&env, &owner, &token, &spender, amount).is_ok()
+    }
+
+    /// Move `amount` of `from`'s `token` to `to` (only `spender`)
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        spender.require_auth();
+        let mut allowance = Self::check(&env, &from, &token, &spender, amount)?;
+
+        allowance.amount -= amount;
+        env.storage().persistent().set(
+            &(ALLOWANCE, from.clone(), token.clone(), spender.clone()),
+            &allowance,
+        );
+        token::Client::new(&env, &token).transfer_from(
+            &env.current_contract_address(),
+            &from,
+            &to,
+            &amount,
+        );
+        Self::extend_instance(&env);
+        Ok(())
+    }
+}
+
+impl stellhydra_access::Upgradeable for AllowanceManager {}
+
+stellhydra_access::upgradeable!(AllowanceManager);
+
+impl AllowanceManager {
+    fn check(
+        env: &Env,
+        owner: &Address,
+        token: &Address,
+        spender: &Address,
+        amount: i128,
+    ) -> Result<Allowance, Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let allowance = Self::allowance(env.clone(), owner.clone(), token.clone(), spender.clone())
+            .ok_or(Error::NoAllowance)?;
+        if env.ledger().timestamp() > allowance.expiration {
+            return Err(Error::Expired);
+        }
+        if amount > allowance.per_call {
+            return Err(Error::PerCallLimitExceeded);
+        }
+        if amount > allowance.amount {
+            return Err(Error::InsufficientAllowance);
+        }
+        Ok(allowance)
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    #[test]
+    fn test_spenders_pull_within_their_allowance() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = token::Client::new(&env, &token);
+        let manager =
+            AllowanceManagerClient::new(&env, &env.register_contract(None, AllowanceManager));
+        let owner = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let other = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&owner, &1_000);
+        token_client.approve(&owner, &manager.address, &1_000, &1_000);
+
+        let expiration = env.ledger().timestamp() + 3_600;
+        assert_eq!(
+            manager.try_approve(
+                &owner,
+                &token,
+                &spender,
+                &Allowance {
+                    amount: 500,
+                    per_call: 0,
+                    expiration,
+                }
+            ),
+            Err(Ok(Error::InvalidAllowance))
+        );
+        manager.approve(
+            &owner,
+            &token,
+            &spender,
+            &Allowance {
+                amount: 500,
+                per_call: 300,
+                expiration,
+            },
+        );
+
+        assert!(manager.covers(&owner, &token, &spender, &300));
+        assert!(!manager.covers(&owner, &token, &other, &300));
+        assert_eq!(
+            manager.try_transfer_from(&other, &token, &owner, &other, &100),
+            Err(Ok(Error::NoAllowance))
+        );
+        assert_eq!(
+            manager.try_transfer_from(&spender, &token, &owner, &spender, &400),
+            Err(Ok(Error::PerCallLimitExceeded))
+        );
+
+        manager.transfer_from(&spender, &token, &owner, &spender, &300);
+        assert_eq!(token_client.balance(&spender), 300);
+        assert_eq!(
+            manager.allowance(&owner, &token, &spender).unwrap().amount,
+            200
+        );
+        assert_eq!(
+            manager.try_transfer_from(&spender, &token, &owner, &spender, &250),
+            Err(Ok(Error::InsufficientAllowance))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = expiration + 1);
+        assert_eq!(
+            manager.try_transfer_from(&spender, &token, &owner, &spender, &100),
+            Err(Ok(Error::Expired))
+        );
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let manager =
+            AllowanceManagerClient::new(&env, &env.register_contract(None, AllowanceManager));
+        let admin = Address::generate(&env);
+        manager.initialize(&admin);
+        assert_eq!(
+            manager.try_initialize(&admin),
+            Err(Ok(Error::AlreadyInitialized))
+        );
+        assert_eq!(manager.get_version(), 1);
+    }
+
+    #[test]
+    fn test_revoking_one_spender_leaves_the_others() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let manager =
+            AllowanceManagerClient::new(&env, &env.register_contract(None, AllowanceManager));
+        let owner = Address::generate(&env);
+        let (pool, bridge) = (Address::generate(&env), Address::generate(&env));
+        StellarAssetClient::new(&env, &token).mint(&owner, &1_000);
+        token::Client::new(&env, &token).approve(&owner, &manager.address, &1_000, &1_000);
+
+        let allowance = Allowance {
+            amount: 1_000,
+            per_call: 1_000,
+            expiration: env.ledger().timestamp() + 3_600,
+        };
+        manager.approve(&owner, &token, &pool, &allowance);
+        manager.approve(&owner, &token, &bridge, &allowance);
+
+        manager.revoke(&owner, &token, &pool);
+        assert_eq!(manager.allowance(&owner, &token, &pool), None);
+        assert_eq!(
+            manager.try_transfer_from(&pool, &token, &owner, &pool, &100),
+            Err(Ok(Error::NoAllowance))
+        );
+        manager.transfer_from(&bridge, &token, &owner, &bridge, &100);
+    }
+}
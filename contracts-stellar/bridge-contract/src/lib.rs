@@ -1,39 +1,333 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, token, Address, Env, Map, Symbol, Vec, BytesN
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    xdr::ToXdr, Address, Bytes, BytesN, Env, FromVal, Map, Symbol, TryFromVal, Val, Vec,
 };
+use stellhydra_access::{allowance, ownable, pausable, upgradeable, Upgradeable};
+use stellhydra_events::bridge::{
+    AccountBlocked, BondPosted, BondWithdrawn, BridgeInitialized, ChainPaused, ComplianceSet,
+    ConfigUpdated, DestinationBlocked, EmergencyWithdrawn, EvmRootAdded, EvmSourceSet,
+    ExposureCapSet, FeeTiersSet, FraudReported, GuardiansSet, InboundClaimable, InboundClaimed,
+    InboundReleased, InsuranceContributed, InsurancePaid, MultiRequestCreated, PriorityFeePaid,
+    RelayerSet, RelayerSlashed, RequestArchived, RequestCreated, RequestFinalized, RequestRetried,
+    RequestStatusChanged, TokenPaused,
+};
+use stellhydra_events::Event;
+use stellhydra_math::{apply_bps, mul_div, sub_bps, BPS};
+
+pub use stellhydra_events::bridge::{BridgeAsset, FeeTier};
+
+mod light_client;
+
+/// Interface of an external compliance contract the bridge can defer to
+#[contractclient(name = "ComplianceClient")]
+pub trait ComplianceInterface {
+    fn is_blocked(env: Env, account: Address) -> bool;
+}
+
+/// Interface of the staking contract consulted for fee discounts
+#[contractclient(name = "StakingClient")]
+pub trait StakingInterface {
+    fn staked_balance(env: Env, user: Address) -> i128;
+}
+
+/// Interface of the referral registry sharing fees with integrators
+#[contractclient(name = "ReferralClient")]
+pub trait ReferralInterface {
+    fn get_share(env: Env, code: Symbol) -> u32;
+    fn get_max_share(env: Env) -> u32;
+    fn accrue(env: Env, source: Address, code: Symbol, token: Address, volume: i128, amount: i128);
+}
+
+/// Interface of the rebate contract paying stakers back part of their fees
+#[contractclient(name = "RebateClient")]
+pub trait RebateInterface {
+    fn report_fee(env: Env, source: Address, user: Address, token: Address, fee: i128);
+}
+
+/// Current layout version of stored bridge requests
+pub const REQUEST_VERSION: u32 = 3;
+
+/// Maximum number of assets locked by a single request
+pub const MAX_REQUEST_ASSETS: u32 = 10;
 
 // Data structures
+/// `from_token`, `amount` and `fee` describe the first locked asset;
+/// `assets` lists every asset, including the first
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BridgeRequest {
+    pub version: u32,
     pub user: Address,
     pub from_token: Address,
     pub to_chain: Symbol,
     pub to_address: BytesN<32>,
     pub amount: i128,
     pub fee: i128,
-    pub status: u32, // 0: Pending, 1: Processing, 2: Completed, 3: Failed
+    pub priority_fee: i128, // paid to the relayer that completes the request
+    pub status: u32,        // 0: Pending, 1: Processing, 2: Completed, 3: Failed
     pub timestamp: u64,
+    pub assets: Vec<BridgeAsset>,
 }
 
+/// Request layout before multi-asset requests (version 2)
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct BridgeConfig {
+pub struct BridgeRequestV2 {
+    pub version: u32,
+    pub user: Address,
+    pub from_token: Address,
+    pub to_chain: Symbol,
+    pub to_address: BytesN<32>,
+    pub amount: i128,
+    pub fee: i128,
+    pub priority_fee: i128,
+    pub status: u32,
+    pub timestamp: u64,
+}
+
+/// Configuration layout of version 1, which also held the admin and the
+/// pause switch
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BridgeConfigV1 {
     pub admin: Address,
     pub fee_recipient: Address,
     pub base_fee: i128,
-    pub fee_percentage: u32, // basis points (e.g., 100 = 1%)
+    pub fee_percentage: u32,
     pub min_amount: i128,
     pub max_amount: i128,
     pub is_paused: bool,
 }
 
+/// Request layout stored before versioning was introduced (version 1)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BridgeRequestV1 {
+    pub user: Address,
+    pub from_token: Address,
+    pub to_chain: Symbol,
+    pub to_address: BytesN<32>,
+    pub amount: i128,
+    pub fee: i128,
+    pub status: u32,
+    pub timestamp: u64,
+}
+
+/// Fee discount granted to users meeting either threshold
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BridgeConfig {
+    pub fee_recipient: Address,
+    pub base_fee: i128,
+    pub fee_percentage: u32, // basis points (e.g., 100 = 1%)
+    pub min_amount: i128,
+    pub max_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InboundTransfer {
+    pub source_chain: Symbol,
+    pub source_tx: BytesN<32>,
+    pub token: Address,
+    pub recipient: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+/// EVM contract whose logs prove inbound transfers from a chain
+///
+/// The log data must be `abi.encode(bytes32 commitment, uint256 amount)`
+/// where `commitment` is the SHA-256 of the XDR of `(recipient, token)`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvmSource {
+    pub emitter: BytesN<20>,
+    pub event_topic: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondConfig {
+    pub token: Address,
+    pub min_bond: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GuardianSet {
+    pub guardians: Vec<Address>,
+    pub threshold: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FraudProof {
+    pub request_id: u64,
+    pub relayer: Address,
+    pub reporter: Address,
+    pub evidence: Bytes,
+    pub approvals: Vec<Address>,
+    pub slashed: i128, // amount paid to the user once the guardians reach the threshold
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsuranceClaim {
+    pub request_id: u64,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BridgeStats {
+    pub total_volume: i128,
+    pub rolling_volume: i128,
+    pub rolling_window_start: u64,
+    pub fees_collected: i128,
+    pub pending_count: u64,
+    pub processing_count: u64,
+    pub completed_count: u64,
+    pub failed_count: u64,
+}
+
+/// Bridge-wide request counts and locked value by status. Failed requests
+/// that were finalized and refunded move to the refunded bucket.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GlobalStats {
+    pub request_count: u64,
+    pub fees_collected: i128,
+    pub pending_count: u64,
+    pub pending_value: i128,
+    pub processing_count: u64,
+    pub processing_value: i128,
+    pub completed_count: u64,
+    pub completed_value: i128,
+    pub failed_count: u64,
+    pub failed_value: i128,
+    pub refunded_count: u64,
+    pub refunded_value: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Paused = 3,
+    ChainPaused = 4,
+    TokenPaused = 5,
+    AmountOutOfRange = 6,
+    InvalidAmount = 7,
+    InvalidConfig = 8,
+    RequestNotFound = 9,
+    InvalidStatusTransition = 10,
+    InboundAlreadyCompleted = 11,
+    RequestNotTerminal = 12,
+    RetentionNotElapsed = 13,
+    NotRelayer = 14,
+    Blocked = 15,
+    InsufficientInsurance = 16,
+    ClaimAlreadyPaid = 17,
+    RequestNotFailed = 18,
+    ExposureCapExceeded = 19,
+    UnknownChain = 20,
+    UnknownBlockRoot = 21,
+    BlockRootExists = 22,
+    InvalidProof = 23,
+    InsufficientBond = 24,
+    NotGuardian = 25,
+    FraudProofExists = 26,
+    FraudProofNotFound = 27,
+    AlreadyApproved = 28,
+    NotRelayedRequest = 29,
+    UnsupportedVersion = 30,
+    NothingToClaim = 31,
+    InvalidAssets = 32,
+    InvalidFeeTier = 33,
+    RetryLimitReached = 34,
+    RequestFinalized = 35,
+    MathOverflow = 36,
+    AssetMismatch = 37,
+    RequestNotSettled = 38,
+    NotAuthorized = 39,
+    InsufficientLiquidity = 40,
+    BondLocked = 41,
+}
+
 // Storage keys
 const CONFIG: Symbol = symbol_short!("CONFIG");
-const REQUESTS: Symbol = symbol_short!("REQUESTS");
+const REQUESTS: Symbol = symbol_short!("REQUESTS"); // version 1 instance map
+const REQUEST: Symbol = symbol_short!("REQUEST");
 const COUNTER: Symbol = symbol_short!("COUNTER");
+const CHAIN_STATS: Symbol = symbol_short!("CH_STATS");
+const TOKEN_STATS: Symbol = symbol_short!("TK_STATS");
+const PAUSED_CHAINS: Symbol = symbol_short!("PAUSED_CH");
+const PAUSED_TOKENS: Symbol = symbol_short!("PAUSED_TK");
+const INBOUND: Symbol = symbol_short!("INBOUND");
+const RETENTION: Symbol = symbol_short!("RETENTION");
+const RELAYERS: Symbol = symbol_short!("RELAYERS");
+const BLOCKED: Symbol = symbol_short!("BLOCKED");
+const BLOCKED_DEST: Symbol = symbol_short!("BLK_DEST");
+const COMPLIANCE: Symbol = symbol_short!("COMPLY");
+const INSURANCE_SHARE: Symbol = symbol_short!("INS_SHARE");
+const INSURANCE: Symbol = symbol_short!("INSURANCE");
+const INSURANCE_CLAIM: Symbol = symbol_short!("INS_CLAIM");
+const EXPOSURE_CAP: Symbol = symbol_short!("EXP_CAP");
+const EXPOSURE: Symbol = symbol_short!("EXPOSURE");
+const EVM_SOURCE: Symbol = symbol_short!("EVM_SRC");
+const EVM_ROOT: Symbol = symbol_short!("EVM_ROOT");
+const BOND_CONFIG: Symbol = symbol_short!("BOND_CFG");
+const BOND: Symbol = symbol_short!("BOND");
+const RELAYED_BY: Symbol = symbol_short!("RELAYEDBY");
+const GUARDIANS: Symbol = symbol_short!("GUARDIANS");
+const FRAUD: Symbol = symbol_short!("FRAUD");
+const FRAUD_DONE: Symbol = symbol_short!("FRAUDDONE");
+const OPEN_FRAUD: Symbol = symbol_short!("OPENFRAUD");
+const LAST_RELAY: Symbol = symbol_short!("LASTRELAY");
+const CLAIM_MODE: Symbol = symbol_short!("CLAIMMODE");
+const CLAIMABLE: Symbol = symbol_short!("CLAIMABLE");
+const FEE_TIERS: Symbol = symbol_short!("FEE_TIERS");
+const STAKING: Symbol = symbol_short!("STAKING");
+const USER_VOLUME: Symbol = symbol_short!("USER_VOL");
+const REFERRALS: Symbol = symbol_short!("REFERRALS");
+const REFERRER: Symbol = symbol_short!("REFERRER");
+const REBATES: Symbol = symbol_short!("REBATES");
+const MAX_RETRIES: Symbol = symbol_short!("MAX_RETRY");
+const RETRIES: Symbol = symbol_short!("RETRIES");
+const FINALIZED: Symbol = symbol_short!("FINALIZED");
+const FINISHED_AT: Symbol = symbol_short!("FINISHED");
+const GLOBAL_STATS: Symbol = symbol_short!("GL_STATS");
+
+// Length of the rolling volume window (24 hours)
+const ROLLING_WINDOW: u64 = 86_400;
+
+// How long a relayer's bond stays locked after it last completed a
+// request, leaving time to file fraud proofs (7 days)
+const BOND_LOCK_PERIOD: u64 = 7 * 86_400;
+
+// Bucket of finalized failures in the global stats
+const REFUNDED_BUCKET: u32 = 4;
+
+// Default number of times a failed request can be retried
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+// Default time a finished request is kept before it can be archived (30 days)
+const DEFAULT_RETENTION_PERIOD: u64 = 30 * 86_400;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
 #[contract]
 pub struct StellarBridge;
@@ -49,27 +343,34 @@ impl StellarBridge {
         fee_percentage: u32,
         min_amount: i128,
         max_amount: i128,
-    ) {
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+
         admin.require_auth();
 
         let config = BridgeConfig {
-            admin: admin.clone(),
             fee_recipient,
             base_fee,
             fee_percentage,
             min_amount,
             max_amount,
-            is_paused: false,
         };
+        Self::validate_config(&config)?;
 
+        ownable::init_admin(&env, &admin);
         env.storage().instance().set(&CONFIG, &config);
         env.storage().instance().set(&COUNTER, &0u64);
+        env.storage()
+            .instance()
+            .set(&upgradeable::VERSION, &<Self as Upgradeable>::VERSION);
+        Self::bump_instance(&env);
 
         // Emit initialization event
-        env.events().publish(
-            (symbol_short!("BRIDGE"), symbol_short!("INIT")),
-            admin,
-        );
+        BridgeInitialized { admin }.publish(&env);
+
+        Ok(())
     }
 
     /// Create a new bridge request
@@ -80,159 +381,3721 @@ impl StellarBridge {
         to_chain: Symbol,
         to_address: BytesN<32>,
         amount: i128,
-    ) -> u64 {
+        priority_fee: Option<i128>,
+    ) -> Result<u64, Error> {
         user.require_auth();
 
-        let config: BridgeConfig = env.storage().instance().get(&CONFIG).unwrap();
-        
-        // Check if bridge is not paused
-        if config.is_paused {
-            panic!("Bridge is paused");
-        }
-
-        // Validate amount
-        if amount < config.min_amount || amount > config.max_amount {
-            panic!("Invalid amount");
+        let priority_fee = priority_fee.unwrap_or(0);
+        if priority_fee < 0 {
+            return Err(Error::InvalidAmount);
         }
 
-        // Calculate fee
-        let fee = config.base_fee + (amount * config.fee_percentage as i128) / 10000;
+        let assets = Vec::from_array(&env, [(from_token, amount)]);
+        let (request_id, request) =
+            Self::open_request(&env, &user, assets, to_chain, to_address, priority_fee)?;
 
-        // Get next request ID
-        let mut counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
-        counter += 1;
-        env.storage().instance().set(&COUNTER, &counter);
+        // Emit bridge initiated event carrying everything a relayer needs,
+        // with the request id and destination chain as topics for filtering
+        RequestCreated {
+            request_id,
+            user,
+            from_token: request.from_token,
+            to_chain: request.to_chain,
+            to_address: request.to_address,
+            amount: request.amount,
+            fee: request.fee,
+            priority_fee: request.priority_fee,
+            timestamp: request.timestamp,
+        }
+        .publish(&env);
 
-        // Create bridge request
-        let request = BridgeRequest {
-            user: user.clone(),
-            from_token: from_token.clone(),
-            to_chain,
-            to_address,
-            amount,
-            fee,
-            status: 0, // Pending
-            timestamp: env.ledger().timestamp(),
-        };
+        Ok(request_id)
+    }
 
-        // Store request
-        let mut requests: Map<u64, BridgeRequest> = env.storage().instance().get(&REQUESTS).unwrap_or(Map::new(&env));
-        requests.set(counter, request.clone());
-        env.storage().instance().set(&REQUESTS, &requests);
+    /// Create a bridge request locking several assets at once. The base fee
+    /// is charged once, on the first asset; the percentage fee applies to
+    /// every asset.
+    pub fn create_bridge_request_multi(
+        env: Env,
+        user: Address,
+        assets: Vec<(Address, i128)>,
+        to_chain: Symbol,
+        to_address: BytesN<32>,
+    ) -> Result<u64, Error> {
+        user.require_auth();
 
-        // Transfer tokens from user (amount + fee)
-        let total_amount = amount + fee;
-        let token_client = token::Client::new(&env, &from_token);
-        token_client.transfer(&user, &env.current_contract_address(), &total_amount);
+        let (request_id, request) =
+            Self::open_request(&env, &user, assets, to_chain, to_address, 0)?;
 
-        // Emit bridge initiated event
-        env.events().publish(
-            (symbol_short!("BRIDGE"), symbol_short!("CREATED")),
-            (counter, user, amount),
-        );
+        MultiRequestCreated {
+            request_id,
+            user,
+            to_chain: request.to_chain,
+            to_address: request.to_address,
+            assets: request.assets,
+            timestamp: request.timestamp,
+        }
+        .publish(&env);
 
-        counter
+        Ok(request_id)
     }
 
     /// Get bridge request by ID
     pub fn get_bridge_request(env: Env, request_id: u64) -> Option<BridgeRequest> {
-        let requests: Map<u64, BridgeRequest> = env.storage().instance().get(&REQUESTS).unwrap_or(Map::new(&env));
-        requests.get(request_id)
+        Self::load_request(&env, request_id).ok()
+    }
+
+    /// Rewrite a stored request in the current layout, returning the
+    /// version it was stored with
+    pub fn migrate_request(env: Env, request_id: u64) -> Result<u32, Error> {
+        let raw: Val = env
+            .storage()
+            .persistent()
+            .get(&(REQUEST, request_id))
+            .ok_or(Error::RequestNotFound)?;
+        let stored_version = Self::stored_version(&env, raw)?;
+        let request = Self::decode_request(&env, raw)?;
+        Self::save_request(&env, request_id, &request);
+
+        Ok(stored_version)
     }
 
     /// Update bridge request status (admin only)
+    ///
+    /// Requests completed this way were relayed by the protocol itself, so
+    /// any priority fee goes to the fee recipient.
     pub fn update_request_status(
         env: Env,
         request_id: u64,
         new_status: u32,
         tx_hash: Option<BytesN<32>>,
-    ) {
-        let config: BridgeConfig = env.storage().instance().get(&CONFIG).unwrap();
-        config.admin.require_auth();
-
-        let mut requests: Map<u64, BridgeRequest> = env.storage().instance().get(&REQUESTS).unwrap_or(Map::new(&env));
-        
-        if let Some(mut request) = requests.get(request_id) {
-            request.status = new_status;
-            requests.set(request_id, request);
-            env.storage().instance().set(&REQUESTS, &requests);
-
-            // Emit status update event
-            env.events().publish(
-                (symbol_short!("BRIDGE"), symbol_short!("STATUS")),
-                (request_id, new_status),
-            );
+    ) -> Result<(), Error> {
+        let config = Self::load_config(&env)?;
+        Self::require_admin(&env)?;
+
+        let request = Self::transition(&env, request_id, new_status, tx_hash)?;
+        if new_status == 2 {
+            Self::pay_priority_fee(&env, request_id, &request, &config.fee_recipient);
         }
-    }
 
-    /// Get bridge configuration
-    pub fn get_config(env: Env) -> BridgeConfig {
-        env.storage().instance().get(&CONFIG).unwrap()
+        Ok(())
     }
 
-    /// Update bridge configuration (admin only)
-    pub fn update_config(
+    /// Mark a request as completed by an approved relayer, paying it the
+    /// request's priority fee
+    pub fn complete_request(
         env: Env,
-        fee_recipient: Option<Address>,
-        base_fee: Option<i128>,
-        fee_percentage: Option<u32>,
-        min_amount: Option<i128>,
-        max_amount: Option<i128>,
-        is_paused: Option<bool>,
-    ) {
-        let mut config: BridgeConfig = env.storage().instance().get(&CONFIG).unwrap();
-        config.admin.require_auth();
+        relayer: Address,
+        request_id: u64,
+        tx_hash: Option<BytesN<32>>,
+    ) -> Result<(), Error> {
+        relayer.require_auth();
 
-        if let Some(recipient) = fee_recipient {
-            config.fee_recipient = recipient;
+        if !Self::is_relayer(env.clone(), relayer.clone()) {
+            return Err(Error::NotRelayer);
         }
-        if let Some(fee) = base_fee {
-            config.base_fee = fee;
+
+        // Relayers must keep the minimum bond posted while relaying
+        if let Some(bond_config) = Self::get_bond_config(env.clone()) {
+            if Self::get_bond(env.clone(), relayer.clone()) < bond_config.min_bond {
+                return Err(Error::InsufficientBond);
+            }
         }
-        if let Some(percentage) = fee_percentage {
-            config.fee_percentage = percentage;
+
+        let request = Self::transition(&env, request_id, 2, tx_hash)?;
+        Self::pay_priority_fee(&env, request_id, &request, &relayer);
+
+        let key = (RELAYED_BY, request_id);
+        env.storage().persistent().set(&key, &relayer);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        let relay_key = (LAST_RELAY, relayer);
+        env.storage()
+            .persistent()
+            .set(&relay_key, &env.ledger().timestamp());
+        env.storage().persistent().extend_ttl(
+            &relay_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        Ok(())
+    }
+
+    /// Move a failed request back to Processing for another delivery
+    /// attempt (admin only). Returns the number of retries so far.
+    pub fn retry_request(env: Env, request_id: u64) -> Result<u32, Error> {
+        Self::require_admin(&env)?;
+
+        let request = Self::load_request(&env, request_id)?;
+        if request.status != 3 {
+            return Err(Error::RequestNotFailed);
         }
-        if let Some(min) = min_amount {
-            config.min_amount = min;
+        if Self::is_request_finalized(env.clone(), request_id) {
+            return Err(Error::RequestFinalized);
         }
-        if let Some(max) = max_amount {
-            config.max_amount = max;
+
+        let retries = Self::get_retry_count(env.clone(), request_id) + 1;
+        if retries > Self::get_max_retries(env.clone()) {
+            return Err(Error::RetryLimitReached);
+        }
+
+        let key = (RETRIES, request_id);
+        env.storage().persistent().set(&key, &retries);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        Self::set_status(&env, request_id, request, 1, None);
+
+        RequestRetried {
+            request_id,
+            retries,
+        }
+        .publish(&env);
+
+        Ok(retries)
+    }
+
+    /// Give up on a failed request and refund the locked assets and
+    /// priority fee to the user (admin only). Bridge fees are kept.
+    pub fn finalize_failure(env: Env, request_id: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let request = Self::load_request(&env, request_id)?;
+        if request.status != 3 {
+            return Err(Error::RequestNotFailed);
         }
-        if let Some(paused) = is_paused {
-            config.is_paused = paused;
+        if Self::is_request_finalized(env.clone(), request_id) {
+            return Err(Error::RequestFinalized);
+        }
+        // Users compensated by the insurance fund are not refunded twice
+        if env
+            .storage()
+            .persistent()
+            .has(&(INSURANCE_CLAIM, request_id))
+        {
+            return Err(Error::ClaimAlreadyPaid);
         }
 
-        env.storage().instance().set(&CONFIG, &config);
+        let key = (FINALIZED, request_id);
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
 
-        // Emit config update event
-        env.events().publish(
-            (symbol_short!("BRIDGE"), symbol_short!("CONFIG")),
-            config.admin.clone(),
+        let mut global_stats = Self::load_global_stats(&env);
+        Self::move_global(
+            &mut global_stats,
+            3,
+            REFUNDED_BUCKET,
+            Self::request_value(&request),
         );
+        env.storage().instance().set(&GLOBAL_STATS, &global_stats);
+
+        for (i, asset) in request.assets.iter().enumerate() {
+            let mut refund = asset.amount;
+            if i == 0 {
+                refund += request.priority_fee;
+            }
+            let token_client = token::Client::new(&env, &asset.token);
+            token_client.transfer(&env.current_contract_address(), &request.user, &refund);
+        }
+
+        RequestFinalized {
+            request_id,
+            user: request.user,
+            assets: request.assets,
+        }
+        .publish(&env);
+
+        Ok(())
     }
 
-    /// Get total number of bridge requests
-    pub fn get_request_count(env: Env) -> u64 {
-        env.storage().instance().get(&COUNTER).unwrap_or(0)
+    /// Set how many times a failed request can be retried (admin only)
+    pub fn set_max_retries(env: Env, max_retries: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&MAX_RETRIES, &max_retries);
+        Self::bump_instance(&env);
+
+        Ok(())
     }
 
-    /// Emergency withdraw (admin only)
-    pub fn emergency_withdraw(
+    pub fn get_max_retries(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&MAX_RETRIES)
+            .unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+
+    pub fn get_retry_count(env: Env, request_id: u64) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&(RETRIES, request_id))
+            .unwrap_or(0)
+    }
+
+    /// Check if a failed request has been finalized and refunded
+    pub fn is_request_finalized(env: Env, request_id: u64) -> bool {
+        env.storage().persistent().has(&(FINALIZED, request_id))
+    }
+
+    /// Get the relayer that completed a request, if it was relayer-completed
+    pub fn get_request_relayer(env: Env, request_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&(RELAYED_BY, request_id))
+    }
+
+    /// Set the token and minimum amount relayers must bond (admin only)
+    pub fn set_bond_config(env: Env, token: Address, min_bond: i128) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if min_bond < 0 {
+            return Err(Error::InvalidConfig);
+        }
+        // Switching the bond token would strand existing bonds
+        if let Some(existing) = Self::get_bond_config(env.clone()) {
+            if existing.token != token {
+                return Err(Error::InvalidConfig);
+            }
+        }
+
+        env.storage()
+            .instance()
+            .set(&BOND_CONFIG, &BondConfig { token, min_bond });
+
+        Ok(())
+    }
+
+    /// Get the relayer bond configuration
+    pub fn get_bond_config(env: Env) -> Option<BondConfig> {
+        env.storage().instance().get(&BOND_CONFIG)
+    }
+
+    /// Post (or top up) a relayer bond
+    pub fn post_bond(env: Env, relayer: Address, amount: i128) -> Result<(), Error> {
+        relayer.require_auth();
+
+        let bond_config = Self::get_bond_config(env.clone()).ok_or(Error::InvalidConfig)?;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let token_client = token::Client::new(&env, &bond_config.token);
+        token_client.transfer(&relayer, &env.current_contract_address(), &amount);
+
+        let bond = Self::get_bond(env.clone(), relayer.clone()) + amount;
+        Self::save_bond(&env, &relayer, bond);
+
+        BondPosted {
+            relayer,
+            amount,
+            bond,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Withdraw bond; an approved relayer must leave at least the minimum
+    ///
+    /// The bond stays locked while a fraud proof against the relayer is
+    /// open, and until `BOND_LOCK_PERIOD` after the last request it
+    /// completed, so it can't be pulled out ahead of a slash.
+    pub fn withdraw_bond(env: Env, relayer: Address, amount: i128) -> Result<(), Error> {
+        relayer.require_auth();
+
+        let bond_config = Self::get_bond_config(env.clone()).ok_or(Error::InvalidConfig)?;
+        let bond = Self::get_bond(env.clone(), relayer.clone());
+        if amount <= 0 || amount > bond {
+            return Err(Error::InvalidAmount);
+        }
+
+        let remaining = bond - amount;
+        if Self::is_relayer(env.clone(), relayer.clone()) && remaining < bond_config.min_bond {
+            return Err(Error::InsufficientBond);
+        }
+        if Self::is_bond_locked(env.clone(), relayer.clone()) {
+            return Err(Error::BondLocked);
+        }
+        Self::save_bond(&env, &relayer, remaining);
+
+        let token_client = token::Client::new(&env, &bond_config.token);
+        token_client.transfer(&env.current_contract_address(), &relayer, &amount);
+
+        BondWithdrawn {
+            relayer,
+            amount,
+            remaining,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get a relayer's posted bond
+    pub fn get_bond(env: Env, relayer: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(BOND, relayer))
+            .unwrap_or(0)
+    }
+
+    /// Whether a relayer's bond can't be withdrawn yet, because of an open
+    /// fraud proof or a recently completed request
+    pub fn is_bond_locked(env: Env, relayer: Address) -> bool {
+        let open: u32 = env
+            .storage()
+            .persistent()
+            .get(&(OPEN_FRAUD, relayer.clone()))
+            .unwrap_or(0);
+        if open > 0 {
+            return true;
+        }
+        let last_relay: Option<u64> = env.storage().persistent().get(&(LAST_RELAY, relayer));
+        last_relay.is_some_and(|at| env.ledger().timestamp() < at.saturating_add(BOND_LOCK_PERIOD))
+    }
+
+    /// Replace the guardian set that validates fraud proofs (admin only)
+    pub fn set_guardians(env: Env, guardians: Vec<Address>, threshold: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if threshold == 0 || threshold > guardians.len() {
+            return Err(Error::InvalidConfig);
+        }
+
+        let set = GuardianSet {
+            guardians,
+            threshold,
+        };
+        env.storage().instance().set(&GUARDIANS, &set);
+
+        GuardiansSet {
+            guardians: set.guardians,
+            threshold: set.threshold,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the guardian set
+    pub fn get_guardians(env: Env) -> Option<GuardianSet> {
+        env.storage().instance().get(&GUARDIANS)
+    }
+
+    /// Report a relayer-completed request as fraudulent
+    pub fn submit_fraud_proof(
         env: Env,
-        token: Address,
-        to: Address,
-        amount: i128,
-    ) {
-        let config: BridgeConfig = env.storage().instance().get(&CONFIG).unwrap();
-        config.admin.require_auth();
+        reporter: Address,
+        request_id: u64,
+        evidence: Bytes,
+    ) -> Result<(), Error> {
+        reporter.require_auth();
 
-        let token_client = token::Client::new(&env, &token);
-        token_client.transfer(&env.current_contract_address(), &to, &amount);
+        let relayer =
+            Self::get_request_relayer(env.clone(), request_id).ok_or(Error::NotRelayedRequest)?;
 
-        // Emit emergency withdraw event
-        env.events().publish(
-            (symbol_short!("BRIDGE"), symbol_short!("EMERGENCY")),
-            (to, amount),
+        let key = (FRAUD, request_id);
+        if env.storage().persistent().has(&key) {
+            return Err(Error::FraudProofExists);
+        }
+
+        let proof = FraudProof {
+            request_id,
+            relayer: relayer.clone(),
+            reporter: reporter.clone(),
+            evidence,
+            approvals: Vec::new(&env),
+            slashed: 0,
+        };
+        env.storage().persistent().set(&key, &proof);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
         );
+        Self::track_open_fraud(&env, &relayer, true);
+
+        FraudReported {
+            request_id,
+            relayer,
+            reporter,
+        }
+        .publish(&env);
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Guardian approval of a fraud proof. Once the threshold is reached the
+    /// relayer loses its approval and its bond is slashed to make the harmed
+    /// user whole for the part of the request locked in the bond token. On
+    /// top, the relayer forfeits the insurance share of that harm to the
+    /// insurance fund. A missing or short bond is taken as far as it goes,
+    /// paying the user first.
+    pub fn approve_fraud_proof(env: Env, guardian: Address, request_id: u64) -> Result<(), Error> {
+        guardian.require_auth();
+
+        let set = Self::get_guardians(env.clone()).ok_or(Error::NotGuardian)?;
+        if !set.guardians.contains(&guardian) {
+            return Err(Error::NotGuardian);
+        }
+
+        let key = (FRAUD, request_id);
+        let mut proof: FraudProof = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(Error::FraudProofNotFound)?;
+        let done_key = (FRAUD_DONE, request_id);
+        if env.storage().persistent().has(&done_key) || proof.approvals.contains(&guardian) {
+            return Err(Error::AlreadyApproved);
+        }
+        proof.approvals.push_back(guardian);
+
+        if proof.approvals.len() >= set.threshold {
+            proof.slashed = Self::slash_relayer(&env, &proof)?;
+            Self::track_open_fraud(&env, &proof.relayer, false);
+            env.storage().persistent().set(&done_key, &true);
+            env.storage().persistent().extend_ttl(
+                &done_key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        }
+        env.storage().persistent().set(&key, &proof);
+
+        Ok(())
+    }
+
+    /// Get the fraud proof filed against a request
+    pub fn get_fraud_proof(env: Env, request_id: u64) -> Option<FraudProof> {
+        env.storage().persistent().get(&(FRAUD, request_id))
+    }
+
+    /// Approve a relayer to complete requests (admin only)
+    pub fn add_relayer(env: Env, relayer: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let mut relayers = Self::load_relayers(&env);
+        relayers.set(relayer.clone(), true);
+        env.storage().instance().set(&RELAYERS, &relayers);
+
+        RelayerSet {
+            relayer,
+            approved: true,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Revoke a relayer (admin only)
+    pub fn remove_relayer(env: Env, relayer: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let mut relayers = Self::load_relayers(&env);
+        relayers.remove(relayer.clone());
+        env.storage().instance().set(&RELAYERS, &relayers);
+
+        RelayerSet {
+            relayer,
+            approved: false,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Check if an address is an approved relayer
+    pub fn is_relayer(env: Env, relayer: Address) -> bool {
+        Self::load_relayers(&env).get(relayer).unwrap_or(false)
+    }
+
+    /// Release tokens for an inbound transfer proven by a receipt proof
+    /// against an attested EVM receipts root. Anyone can submit the proof.
+    ///
+    /// Returns the id the transfer is recorded under, derived from the
+    /// chain, block, transaction and log position.
+    #[allow(clippy::too_many_arguments)]
+    pub fn complete_inbound_with_proof(
+        env: Env,
+        source_chain: Symbol,
+        block_number: u64,
+        tx_index: u32,
+        log_index: u32,
+        proof: Vec<Bytes>,
+        token: Address,
+        recipient: Address,
+    ) -> Result<BytesN<32>, Error> {
+        Self::load_config(&env)?;
+
+        let source =
+            Self::get_evm_source(env.clone(), source_chain.clone()).ok_or(Error::UnknownChain)?;
+        let receipts_root = Self::get_block_root(env.clone(), source_chain.clone(), block_number)
+            .ok_or(Error::UnknownBlockRoot)?;
+
+        let receipt = light_client::verify_receipt_proof(&env, &receipts_root, tx_index, &proof)?;
+        let log = light_client::receipt_log(&env, &receipt, log_index)?;
+
+        if log.address != source.emitter || log.topics.get(0) != Some(source.event_topic) {
+            return Err(Error::InvalidProof);
+        }
+        if log.data.len() != 64 {
+            return Err(Error::InvalidProof);
+        }
+
+        // The log commits to the Stellar recipient and token
+        let commitment: Bytes = env
+            .crypto()
+            .sha256(&(recipient.clone(), token.clone()).to_xdr(&env))
+            .into();
+        if log.data.slice(0..32) != commitment {
+            return Err(Error::InvalidProof);
+        }
+
+        // uint256 amount must fit in an i128
+        let mut amount_bytes = [0u8; 32];
+        log.data.slice(32..64).copy_into_slice(&mut amount_bytes);
+        if amount_bytes[..16].iter().any(|b| *b != 0) {
+            return Err(Error::InvalidAmount);
+        }
+        let mut low = [0u8; 16];
+        low.copy_from_slice(&amount_bytes[16..]);
+        let amount = i128::from_be_bytes(low);
+
+        let source_tx: BytesN<32> = env
+            .crypto()
+            .keccak256(&(source_chain.clone(), block_number, tx_index, log_index).to_xdr(&env))
+            .into();
+
+        Self::release_inbound(
+            &env,
+            source_chain,
+            source_tx.clone(),
+            token,
+            recipient,
+            amount,
+        )?;
+
+        Ok(source_tx)
+    }
+
+    /// Hold inbound transfers in the bridge until the recipient claims them
+    /// instead of pushing funds on completion (admin only)
+    pub fn set_claim_delivery(env: Env, enabled: bool) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&CLAIM_MODE, &enabled);
+        Self::bump_instance(&env);
+
+        Ok(())
+    }
+
+    pub fn is_claim_delivery(env: Env) -> bool {
+        env.storage().instance().get(&CLAIM_MODE).unwrap_or(false)
+    }
+
+    /// Release an inbound transfer held for claiming to its recipient
+    pub fn claim(env: Env, message_id: BytesN<32>) -> Result<InboundTransfer, Error> {
+        let key = (CLAIMABLE, message_id.clone());
+        let transfer: InboundTransfer = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(Error::NothingToClaim)?;
+        transfer.recipient.require_auth();
+
+        Self::check_not_paused(&env, &transfer.source_chain, &transfer.token)?;
+        Self::check_not_blocked(&env, &transfer.recipient)?;
+
+        env.storage().persistent().remove(&key);
+
+        Self::check_reserve(&env, &transfer.token, transfer.amount)?;
+        let token_client = token::Client::new(&env, &transfer.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &transfer.recipient,
+            &transfer.amount,
+        );
+
+        InboundClaimed {
+            source_tx: message_id,
+            recipient: transfer.recipient.clone(),
+            amount: transfer.amount,
+        }
+        .publish(&env);
+
+        Ok(transfer)
+    }
+
+    /// Inbound transfer waiting to be claimed, if any
+    pub fn get_claimable(env: Env, message_id: BytesN<32>) -> Option<InboundTransfer> {
+        env.storage().persistent().get(&(CLAIMABLE, message_id))
+    }
+
+    /// Set the EVM contract and event whose logs prove inbound transfers
+    /// from a chain (admin only)
+    pub fn set_evm_source(
+        env: Env,
+        chain: Symbol,
+        emitter: BytesN<20>,
+        event_topic: BytesN<32>,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let source = EvmSource {
+            emitter,
+            event_topic,
+        };
+        env.storage()
+            .instance()
+            .set(&(EVM_SOURCE, chain.clone()), &source);
+
+        EvmSourceSet {
+            chain,
+            emitter: source.emitter,
+            event_topic: source.event_topic,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the EVM source configured for a chain
+    pub fn get_evm_source(env: Env, chain: Symbol) -> Option<EvmSource> {
+        env.storage().instance().get(&(EVM_SOURCE, chain))
+    }
+
+    /// Record the attested receipts root of an EVM block (admin only)
+    pub fn submit_block_root(
+        env: Env,
+        chain: Symbol,
+        block_number: u64,
+        receipts_root: BytesN<32>,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let key = (EVM_ROOT, chain.clone(), block_number);
+        if env.storage().persistent().has(&key) {
+            return Err(Error::BlockRootExists);
+        }
+        env.storage().persistent().set(&key, &receipts_root);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        EvmRootAdded {
+            chain,
+            block_number,
+            receipts_root,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the attested receipts root of an EVM block
+    pub fn get_block_root(env: Env, chain: Symbol, block_number: u64) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&(EVM_ROOT, chain, block_number))
+    }
+
+    /// Get a completed inbound transfer by its source transaction hash
+    pub fn get_inbound_transfer(env: Env, source_tx: BytesN<32>) -> Option<InboundTransfer> {
+        env.storage().persistent().get(&(INBOUND, source_tx))
+    }
+
+    /// Remove a completed or failed request once the retention period has
+    /// passed, emitting the full record so indexers keep a copy
+    pub fn archive_request(env: Env, request_id: u64) -> Result<(), Error> {
+        let request = Self::load_request(&env, request_id)?;
+
+        if request.status != 2 && request.status != 3 {
+            return Err(Error::RequestNotTerminal);
+        }
+        // Failed requests still hold the user's funds until they are
+        // refunded or compensated by the insurance fund
+        if request.status == 3
+            && !Self::is_request_finalized(env.clone(), request_id)
+            && !env
+                .storage()
+                .persistent()
+                .has(&(INSURANCE_CLAIM, request_id))
+        {
+            return Err(Error::RequestNotSettled);
+        }
+
+        // Requests finished before finish times were recorded fall back to
+        // their creation time
+        let finished_at: u64 = env
+            .storage()
+            .persistent()
+            .get(&(FINISHED_AT, request_id))
+            .unwrap_or(request.timestamp);
+        let retention = Self::get_retention_period(env.clone());
+        if env.ledger().timestamp() < finished_at.saturating_add(retention) {
+            return Err(Error::RetentionNotElapsed);
+        }
+
+        let storage = env.storage().persistent();
+        storage.remove(&(REQUEST, request_id));
+        storage.remove(&(FINISHED_AT, request_id));
+        storage.remove(&(RETRIES, request_id));
+        storage.remove(&(FINALIZED, request_id));
+        storage.remove(&(RELAYED_BY, request_id));
+
+        // Emit archival event carrying the final record
+        RequestArchived {
+            request_id,
+            user: request.user,
+            to_chain: request.to_chain,
+            to_address: request.to_address,
+            assets: request.assets,
+            priority_fee: request.priority_fee,
+            status: request.status,
+            timestamp: request.timestamp,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Set how long finished requests are kept before archival (admin only)
+    pub fn set_retention_period(env: Env, seconds: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&RETENTION, &seconds);
+        Ok(())
+    }
+
+    /// Get how long finished requests are kept before archival
+    pub fn get_retention_period(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&RETENTION)
+            .unwrap_or(DEFAULT_RETENTION_PERIOD)
+    }
+
+    /// Extend the TTL of a stored request
+    pub fn bump_request(env: Env, request_id: u64) -> Result<(), Error> {
+        Self::load_request(&env, request_id)?;
+
+        env.storage().persistent().extend_ttl(
+            &(REQUEST, request_id),
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        Self::bump_instance(&env);
+        Ok(())
+    }
+
+    /// Add or remove a Stellar address from the blocklist (admin only)
+    pub fn set_address_blocked(env: Env, account: Address, blocked: bool) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let key = (BLOCKED, account.clone());
+        if blocked {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        AccountBlocked { account, blocked }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Add or remove a destination-chain address from the blocklist (admin only)
+    pub fn set_destination_blocked(
+        env: Env,
+        to_address: BytesN<32>,
+        blocked: bool,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let key = (BLOCKED_DEST, to_address.clone());
+        if blocked {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        DestinationBlocked {
+            to_address,
+            blocked,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Set or clear the external compliance contract (admin only)
+    pub fn set_compliance_contract(env: Env, compliance: Option<Address>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        match compliance.clone() {
+            Some(address) => env.storage().instance().set(&COMPLIANCE, &address),
+            None => env.storage().instance().remove(&COMPLIANCE),
+        }
+
+        ComplianceSet { compliance }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the external compliance contract, if any
+    pub fn get_compliance_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&COMPLIANCE)
+    }
+
+    /// Check if a Stellar address is blocked locally or by the compliance contract
+    pub fn is_address_blocked(env: Env, account: Address) -> bool {
+        if env.storage().persistent().has(&(BLOCKED, account.clone())) {
+            return true;
+        }
+
+        match Self::get_compliance_contract(env.clone()) {
+            Some(compliance) => ComplianceClient::new(&env, &compliance).is_blocked(&account),
+            None => false,
+        }
+    }
+
+    /// Check if a destination-chain address is blocked
+    pub fn is_destination_blocked(env: Env, to_address: BytesN<32>) -> bool {
+        env.storage().persistent().has(&(BLOCKED_DEST, to_address))
+    }
+
+    /// Replace the fee discount schedule (admin only)
+    pub fn set_fee_tiers(env: Env, tiers: Vec<FeeTier>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        for tier in tiers.iter() {
+            if tier.discount_bps > 10000 || (tier.min_volume.is_none() && tier.min_stake.is_none())
+            {
+                return Err(Error::InvalidFeeTier);
+            }
+        }
+
+        env.storage().instance().set(&FEE_TIERS, &tiers);
+        Self::bump_instance(&env);
+
+        FeeTiersSet { tiers }.publish(&env);
+
+        Ok(())
+    }
+
+    pub fn get_fee_tiers(env: Env) -> Vec<FeeTier> {
+        env.storage()
+            .instance()
+            .get(&FEE_TIERS)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Set or clear the staking contract used for stake-based tiers (admin only)
+    pub fn set_staking_contract(env: Env, staking: Option<Address>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        match staking {
+            Some(address) => env.storage().instance().set(&STAKING, &address),
+            None => env.storage().instance().remove(&STAKING),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_staking_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&STAKING)
+    }
+
+    /// Set or clear the referral registry sharing bridge fees (admin only)
+    ///
+    /// The largest share the registry pays out, together with the insurance
+    /// share, can't exceed the whole fee.
+    pub fn set_referral_registry(env: Env, registry: Option<Address>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        match registry {
+            Some(address) => {
+                let referral_bps = ReferralClient::new(&env, &address).get_max_share();
+                if referral_bps.saturating_add(Self::get_insurance_share(env.clone())) > 10000 {
+                    return Err(Error::InvalidConfig);
+                }
+                env.storage().instance().set(&REFERRALS, &address)
+            }
+            None => env.storage().instance().remove(&REFERRALS),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_referral_registry(env: Env) -> Option<Address> {
+        env.storage().instance().get(&REFERRALS)
+    }
+
+    /// Set or clear the rebate contract bridge fees are reported to (admin only)
+    pub fn set_rebate_contract(env: Env, rebates: Option<Address>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        match rebates {
+            Some(address) => env.storage().instance().set(&REBATES, &address),
+            None => env.storage().instance().remove(&REBATES),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_rebate_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&REBATES)
+    }
+
+    /// Set or clear the referral code credited with a user's requests
+    pub fn set_referral_code(env: Env, user: Address, code: Option<Symbol>) {
+        user.require_auth();
+
+        let key = (REFERRER, user);
+        match code {
+            Some(code) => {
+                env.storage().persistent().set(&key, &code);
+                env.storage().persistent().extend_ttl(
+                    &key,
+                    PERSISTENT_LIFETIME_THRESHOLD,
+                    PERSISTENT_BUMP_AMOUNT,
+                );
+            }
+            None => env.storage().persistent().remove(&key),
+        }
+    }
+
+    pub fn get_referral_code(env: Env, user: Address) -> Option<Symbol> {
+        env.storage().persistent().get(&(REFERRER, user))
+    }
+
+    /// Total amount a user has bridged out
+    pub fn get_user_volume(env: Env, user: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(USER_VOLUME, user))
+            .unwrap_or(0)
+    }
+
+    /// Best discount on the percentage fee the user currently qualifies
+    /// for, in basis points
+    pub fn get_fee_discount(env: Env, user: Address) -> u32 {
+        let tiers = Self::get_fee_tiers(env.clone());
+        if tiers.is_empty() {
+            return 0;
+        }
+
+        let volume = Self::get_user_volume(env.clone(), user.clone());
+        let stake = match Self::get_staking_contract(env.clone()) {
+            Some(staking) => StakingClient::new(&env, &staking).staked_balance(&user),
+            None => 0,
+        };
+
+        tiers
+            .iter()
+            .filter(|tier| {
+                tier.min_volume.is_some_and(|min| volume >= min)
+                    || tier.min_stake.is_some_and(|min| stake >= min)
+            })
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Set the share of bridge fees diverted to the insurance fund, in basis
+    /// points (admin only)
+    ///
+    /// Together with the largest share the referral registry pays out, it
+    /// can't exceed the whole fee.
+    pub fn set_insurance_share(env: Env, share_bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let referral_bps = match Self::get_referral_registry(env.clone()) {
+            Some(registry) => ReferralClient::new(&env, &registry).get_max_share(),
+            None => 0,
+        };
+        if share_bps.saturating_add(referral_bps) > 10000 {
+            return Err(Error::InvalidConfig);
+        }
+        env.storage().instance().set(&INSURANCE_SHARE, &share_bps);
+
+        Ok(())
+    }
+
+    /// Get the share of bridge fees diverted to the insurance fund
+    pub fn get_insurance_share(env: Env) -> u32 {
+        env.storage().instance().get(&INSURANCE_SHARE).unwrap_or(0)
+    }
+
+    /// Get the insurance fund balance held for a token
+    ///
+    /// The balance is reserved out of the bridge's holdings of the token:
+    /// only insurance claims can spend it.
+    pub fn get_insurance_balance(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(INSURANCE, token))
+            .unwrap_or(0)
+    }
+
+    /// Compensate a user for a failed request from the insurance fund held
+    /// for one of the request's assets (admin only)
+    pub fn pay_insurance_claim(
+        env: Env,
+        request_id: u64,
+        recipient: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let request = Self::load_request(&env, request_id)?;
+        if request.status != 3 {
+            return Err(Error::RequestNotFailed);
+        }
+        if Self::is_request_finalized(env.clone(), request_id) {
+            return Err(Error::RequestFinalized);
+        }
+
+        let claim_key = (INSURANCE_CLAIM, request_id);
+        if env.storage().persistent().has(&claim_key) {
+            return Err(Error::ClaimAlreadyPaid);
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Claims are paid from the fund of a token the request actually locked
+        if !request.assets.iter().any(|asset| asset.token == token) {
+            return Err(Error::AssetMismatch);
+        }
+
+        let balance = Self::get_insurance_balance(env.clone(), token.clone());
+        if amount > balance {
+            return Err(Error::InsufficientInsurance);
+        }
+        Self::save_insurance_balance(&env, &token, balance - amount);
+
+        let claim = InsuranceClaim {
+            request_id,
+            recipient: recipient.clone(),
+            token: token.clone(),
+            amount,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&claim_key, &claim);
+        env.storage().persistent().extend_ttl(
+            &claim_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
+        // Emit insurance payout event
+        InsurancePaid {
+            request_id,
+            recipient,
+            token,
+            amount,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the insurance claim paid for a request, if any
+    pub fn get_insurance_claim(env: Env, request_id: u64) -> Option<InsuranceClaim> {
+        env.storage()
+            .persistent()
+            .get(&(INSURANCE_CLAIM, request_id))
+    }
+
+    /// Set or clear the maximum amount of a token a single user can have in
+    /// unfinished requests (admin only)
+    pub fn set_exposure_cap(env: Env, token: Address, cap: Option<i128>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        let key = (EXPOSURE_CAP, token.clone());
+        match cap {
+            Some(cap) if cap <= 0 => return Err(Error::InvalidConfig),
+            Some(cap) => env.storage().instance().set(&key, &cap),
+            None => env.storage().instance().remove(&key),
+        }
+
+        ExposureCapSet { token, cap }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Get the per-user exposure cap for a token, if any
+    pub fn get_exposure_cap(env: Env, token: Address) -> Option<i128> {
+        env.storage().instance().get(&(EXPOSURE_CAP, token))
+    }
+
+    /// Get the amount of a token a user currently has in unfinished requests
+    pub fn get_user_exposure(env: Env, user: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(EXPOSURE, user, token))
+            .unwrap_or(0)
+    }
+
+    /// Pause bridging to and from a single chain (admin only)
+    pub fn pause_chain(env: Env, chain: Symbol) -> Result<(), Error> {
+        Self::set_chain_paused(&env, chain, true)
+    }
+
+    /// Resume bridging to and from a single chain (admin only)
+    pub fn unpause_chain(env: Env, chain: Symbol) -> Result<(), Error> {
+        Self::set_chain_paused(&env, chain, false)
+    }
+
+    /// Pause bridging of a single token (admin only)
+    pub fn pause_token(env: Env, token: Address) -> Result<(), Error> {
+        Self::set_token_paused(&env, token, true)
+    }
+
+    /// Resume bridging of a single token (admin only)
+    pub fn unpause_token(env: Env, token: Address) -> Result<(), Error> {
+        Self::set_token_paused(&env, token, false)
+    }
+
+    /// Check if a chain is paused
+    pub fn is_chain_paused(env: Env, chain: Symbol) -> bool {
+        let paused: Map<Symbol, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_CHAINS)
+            .unwrap_or_else(|| Map::new(&env));
+        paused.get(chain).unwrap_or(false)
+    }
+
+    /// Check if a token is paused
+    pub fn is_token_paused(env: Env, token: Address) -> bool {
+        let paused: Map<Address, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_TOKENS)
+            .unwrap_or_else(|| Map::new(&env));
+        paused.get(token).unwrap_or(false)
+    }
+
+    /// Get bridge configuration
+    pub fn get_config(env: Env) -> Result<BridgeConfig, Error> {
+        Self::load_config(&env)
+    }
+
+    /// Update bridge configuration (admin only)
+    pub fn update_config(
+        env: Env,
+        fee_recipient: Option<Address>,
+        base_fee: Option<i128>,
+        fee_percentage: Option<u32>,
+        min_amount: Option<i128>,
+        max_amount: Option<i128>,
+    ) -> Result<(), Error> {
+        let mut config = Self::load_config(&env)?;
+        let admin = Self::require_admin(&env)?;
+
+        if let Some(recipient) = fee_recipient {
+            config.fee_recipient = recipient;
+        }
+        if let Some(fee) = base_fee {
+            config.base_fee = fee;
+        }
+        if let Some(percentage) = fee_percentage {
+            config.fee_percentage = percentage;
+        }
+        if let Some(min) = min_amount {
+            config.min_amount = min;
+        }
+        if let Some(max) = max_amount {
+            config.max_amount = max;
+        }
+        Self::validate_config(&config)?;
+
+        env.storage().instance().set(&CONFIG, &config);
+
+        // Emit config update event
+        ConfigUpdated { admin }.publish(&env);
+
+        Ok(())
+    }
+
+    /// Get total number of bridge requests
+    pub fn get_request_count(env: Env) -> u64 {
+        env.storage().instance().get(&COUNTER).unwrap_or(0)
+    }
+
+    /// Get bridge-wide counts and locked value by status
+    pub fn get_stats(env: Env) -> GlobalStats {
+        let mut stats = Self::load_global_stats(&env);
+        stats.request_count = Self::get_request_count(env.clone());
+        stats
+    }
+
+    /// Get volume, fee and status statistics for a destination chain
+    pub fn get_bridge_stats(env: Env, chain: Symbol) -> BridgeStats {
+        let mut stats = Self::load_chain_stats(&env, &chain);
+        Self::roll_window(&env, &mut stats);
+        stats
+    }
+
+    /// Get volume, fee and status statistics for a source token
+    pub fn get_token_stats(env: Env, token: Address) -> BridgeStats {
+        let mut stats = Self::load_token_stats(&env, &token);
+        Self::roll_window(&env, &mut stats);
+        stats
+    }
+
+    /// Emergency withdraw (admin only)
+    pub fn emergency_withdraw(
+        env: Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        Self::check_reserve(&env, &token, amount)?;
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &to, &amount);
+
+        // Emit emergency withdraw event
+        EmergencyWithdrawn { token, to, amount }.publish(&env);
+
+        Ok(())
+    }
+
+    fn load_request(env: &Env, request_id: u64) -> Result<BridgeRequest, Error> {
+        let raw: Val = env
+            .storage()
+            .persistent()
+            .get(&(REQUEST, request_id))
+            .ok_or(Error::RequestNotFound)?;
+        Self::decode_request(env, raw)
+    }
+
+    /// Layout version of a stored request; version 1 records have no
+    /// `version` field
+    fn stored_version(env: &Env, raw: Val) -> Result<u32, Error> {
+        let fields =
+            Map::<Symbol, Val>::try_from_val(env, &raw).map_err(|_| Error::UnsupportedVersion)?;
+        match fields.get(symbol_short!("version")) {
+            Some(version) => {
+                u32::try_from_val(env, &version).map_err(|_| Error::UnsupportedVersion)
+            }
+            None => Ok(1),
+        }
+    }
+
+    /// Decode a stored request, upgrading older layouts to the current one
+    fn decode_request(env: &Env, raw: Val) -> Result<BridgeRequest, Error> {
+        match Self::stored_version(env, raw)? {
+            REQUEST_VERSION => {
+                BridgeRequest::try_from_val(env, &raw).map_err(|_| Error::UnsupportedVersion)
+            }
+            1 => {
+                let v1 = BridgeRequestV1::try_from_val(env, &raw)
+                    .map_err(|_| Error::UnsupportedVersion)?;
+                Ok(Self::upgrade_v2(env, Self::upgrade_v1(v1)))
+            }
+            2 => {
+                let v2 = BridgeRequestV2::try_from_val(env, &raw)
+                    .map_err(|_| Error::UnsupportedVersion)?;
+                Ok(Self::upgrade_v2(env, v2))
+            }
+            _ => Err(Error::UnsupportedVersion),
+        }
+    }
+
+    fn upgrade_v1(v1: BridgeRequestV1) -> BridgeRequestV2 {
+        BridgeRequestV2 {
+            version: 2,
+            user: v1.user,
+            from_token: v1.from_token,
+            to_chain: v1.to_chain,
+            to_address: v1.to_address,
+            amount: v1.amount,
+            fee: v1.fee,
+            priority_fee: 0,
+            status: v1.status,
+            timestamp: v1.timestamp,
+        }
+    }
+
+    fn upgrade_v2(env: &Env, v2: BridgeRequestV2) -> BridgeRequest {
+        let asset = BridgeAsset {
+            token: v2.from_token.clone(),
+            amount: v2.amount,
+            fee: v2.fee,
+        };
+        BridgeRequest {
+            version: REQUEST_VERSION,
+            user: v2.user,
+            from_token: v2.from_token,
+            to_chain: v2.to_chain,
+            to_address: v2.to_address,
+            amount: v2.amount,
+            fee: v2.fee,
+            priority_fee: v2.priority_fee,
+            status: v2.status,
+            timestamp: v2.timestamp,
+            assets: Vec::from_array(env, [asset]),
+        }
+    }
+
+    /// Validate, store and fund a new request locking `assets`
+    fn open_request(
+        env: &Env,
+        user: &Address,
+        assets: Vec<(Address, i128)>,
+        to_chain: Symbol,
+        to_address: BytesN<32>,
+        priority_fee: i128,
+    ) -> Result<(u64, BridgeRequest), Error> {
+        let config = Self::load_config(env)?;
+
+        if assets.is_empty() || assets.len() > MAX_REQUEST_ASSETS {
+            return Err(Error::InvalidAssets);
+        }
+
+        // Reject blocked senders and destinations
+        Self::check_not_blocked(env, user)?;
+        if Self::is_destination_blocked(env.clone(), to_address.clone()) {
+            return Err(Error::Blocked);
+        }
+
+        // Stakers and high-volume users pay a reduced percentage fee
+        let discount = Self::get_fee_discount(env.clone(), user.clone());
+        let fee_percentage =
+            sub_bps(env, config.fee_percentage as i128, discount).ok_or(Error::MathOverflow)?;
+
+        let mut locked: Vec<BridgeAsset> = Vec::new(env);
+        for (token, amount) in assets.iter() {
+            // Check if bridge, destination chain and token are not paused
+            Self::check_not_paused(env, &to_chain, &token)?;
+
+            if locked.iter().any(|asset| asset.token == token) {
+                return Err(Error::InvalidAssets);
+            }
+
+            // Validate amount
+            if amount < config.min_amount || amount > config.max_amount {
+                return Err(Error::AmountOutOfRange);
+            }
+
+            // Calculate fee, charging the base fee only once
+            let mut fee = mul_div(env, amount, fee_percentage, BPS).ok_or(Error::MathOverflow)?;
+            if locked.is_empty() {
+                fee += config.base_fee;
+            }
+
+            // Enforce the per-user in-flight cap for this token
+            let exposure =
+                Self::get_user_exposure(env.clone(), user.clone(), token.clone()) + amount;
+            if let Some(cap) = Self::get_exposure_cap(env.clone(), token.clone()) {
+                if exposure > cap {
+                    return Err(Error::ExposureCapExceeded);
+                }
+            }
+            Self::save_user_exposure(env, user, &token, exposure);
+
+            locked.push_back(BridgeAsset { token, amount, fee });
+        }
+
+        // Get next request ID
+        let mut counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
+        counter += 1;
+        env.storage().instance().set(&COUNTER, &counter);
+
+        // Create bridge request
+        let first = locked.get_unchecked(0);
+        let request = BridgeRequest {
+            version: REQUEST_VERSION,
+            user: user.clone(),
+            from_token: first.token.clone(),
+            to_chain,
+            to_address,
+            amount: first.amount,
+            fee: first.fee,
+            priority_fee,
+            status: 0, // Pending
+            timestamp: env.ledger().timestamp(),
+            assets: locked.clone(),
+        };
+
+        // Store request
+        Self::save_request(env, counter, &request);
+        Self::bump_instance(env);
+
+        // Transfer tokens from user (amount + fee, plus the priority fee in
+        // the first token)
+        let mut chain_volume = 0;
+        let mut chain_fees = 0;
+        for (i, asset) in locked.iter().enumerate() {
+            let mut total_amount = asset.amount + asset.fee;
+            if i == 0 {
+                total_amount += priority_fee;
+            }
+            allowance::pull(env, &asset.token, user, total_amount);
+
+            let mut token_stats = Self::load_token_stats(env, &asset.token);
+            Self::record_request(env, &mut token_stats, asset.amount, asset.fee);
+            Self::save_token_stats(env, &asset.token, &token_stats);
+
+            // Set aside the insurance share of the fee
+            let insured = Self::contribute_to_insurance(env, counter, &asset.token, asset.fee);
+
+            // Share what's left of the fee with the user's referrer
+            Self::pay_referral(
+                env,
+                user,
+                &asset.token,
+                asset.amount,
+                asset.fee,
+                asset.fee - insured,
+            );
+
+            // Count the fee towards the user's staker rebate
+            if let Some(rebates) = Self::get_rebate_contract(env.clone()) {
+                RebateClient::new(env, &rebates).report_fee(
+                    &env.current_contract_address(),
+                    user,
+                    &asset.token,
+                    &asset.fee,
+                );
+            }
+
+            chain_volume += asset.amount;
+            chain_fees += asset.fee;
+        }
+
+        let mut chain_stats = Self::load_chain_stats(env, &request.to_chain);
+        Self::record_request(env, &mut chain_stats, chain_volume, chain_fees);
+        Self::save_chain_stats(env, &request.to_chain, &chain_stats);
+
+        let mut global_stats = Self::load_global_stats(env);
+        global_stats.fees_collected += chain_fees;
+        Self::move_global(&mut global_stats, u32::MAX, 0, chain_volume);
+        env.storage().instance().set(&GLOBAL_STATS, &global_stats);
+
+        // Track the user's volume for volume-based fee tiers
+        let volume_key = (USER_VOLUME, user.clone());
+        let volume = Self::get_user_volume(env.clone(), user.clone()) + chain_volume;
+        env.storage().persistent().set(&volume_key, &volume);
+        env.storage().persistent().extend_ttl(
+            &volume_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        Ok((counter, request))
+    }
+
+    fn save_request(env: &Env, request_id: u64, request: &BridgeRequest) {
+        let key = (REQUEST, request_id);
+        env.storage().persistent().set(&key, request);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Validate and apply a status change, keeping the stats in sync
+    fn transition(
+        env: &Env,
+        request_id: u64,
+        new_status: u32,
+        tx_hash: Option<BytesN<32>>,
+    ) -> Result<BridgeRequest, Error> {
+        let request = Self::load_request(env, request_id)?;
+
+        if !Self::is_valid_transition(request.status, new_status) {
+            return Err(Error::InvalidStatusTransition);
+        }
+
+        Ok(Self::set_status(
+            env, request_id, request, new_status, tx_hash,
+        ))
+    }
+
+    /// Apply a status change without validating it, keeping exposure and
+    /// stats in sync
+    fn set_status(
+        env: &Env,
+        request_id: u64,
+        mut request: BridgeRequest,
+        new_status: u32,
+        tx_hash: Option<BytesN<32>>,
+    ) -> BridgeRequest {
+        let old_status = request.status;
+        request.status = new_status;
+        Self::save_request(env, request_id, &request);
+
+        let was_finished = old_status == 2 || old_status == 3;
+        let is_finished = new_status == 2 || new_status == 3;
+
+        // Retention before archival runs from the moment a request finishes
+        let finished_key = (FINISHED_AT, request_id);
+        if is_finished {
+            env.storage()
+                .persistent()
+                .set(&finished_key, &env.ledger().timestamp());
+            env.storage().persistent().extend_ttl(
+                &finished_key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        } else if was_finished {
+            env.storage().persistent().remove(&finished_key);
+        }
+        for asset in request.assets.iter() {
+            // Finished requests no longer count towards the user's exposure;
+            // retried ones count again
+            if was_finished != is_finished {
+                let exposure =
+                    Self::get_user_exposure(env.clone(), request.user.clone(), asset.token.clone());
+                let exposure = if is_finished {
+                    (exposure - asset.amount).max(0)
+                } else {
+                    exposure + asset.amount
+                };
+                Self::save_user_exposure(env, &request.user, &asset.token, exposure);
+            }
+
+            let mut token_stats = Self::load_token_stats(env, &asset.token);
+            Self::move_status(&mut token_stats, old_status, new_status);
+            Self::save_token_stats(env, &asset.token, &token_stats);
+        }
+
+        // Move the request between status buckets
+        let mut chain_stats = Self::load_chain_stats(env, &request.to_chain);
+        Self::move_status(&mut chain_stats, old_status, new_status);
+        Self::save_chain_stats(env, &request.to_chain, &chain_stats);
+
+        let mut global_stats = Self::load_global_stats(env);
+        Self::move_global(
+            &mut global_stats,
+            old_status,
+            new_status,
+            Self::request_value(&request),
+        );
+        env.storage().instance().set(&GLOBAL_STATS, &global_stats);
+
+        // Emit status update event
+        RequestStatusChanged {
+            request_id,
+            status: new_status,
+            tx_hash: tx_hash.map(Into::into),
+        }
+        .publish(env);
+
+        request
+    }
+
+    fn pay_priority_fee(env: &Env, request_id: u64, request: &BridgeRequest, to: &Address) {
+        if request.priority_fee <= 0 {
+            return;
+        }
+
+        let token_client = token::Client::new(env, &request.from_token);
+        token_client.transfer(&env.current_contract_address(), to, &request.priority_fee);
+
+        PriorityFeePaid {
+            request_id,
+            to: to.clone(),
+            amount: request.priority_fee,
+        }
+        .publish(env);
+    }
+
+    fn release_inbound(
+        env: &Env,
+        source_chain: Symbol,
+        source_tx: BytesN<32>,
+        token: Address,
+        recipient: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        Self::check_not_paused(env, &source_chain, &token)?;
+        Self::check_not_blocked(env, &recipient)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Each source transaction can only be released once
+        let key = (INBOUND, source_tx.clone());
+        if env.storage().persistent().has(&key) {
+            return Err(Error::InboundAlreadyCompleted);
+        }
+
+        let transfer = InboundTransfer {
+            source_chain: source_chain.clone(),
+            source_tx: source_tx.clone(),
+            token: token.clone(),
+            recipient: recipient.clone(),
+            amount,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&key, &transfer);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        if Self::is_claim_delivery(env.clone()) {
+            // Funds stay in the bridge until the recipient claims them
+            let claim_key = (CLAIMABLE, source_tx.clone());
+            env.storage().persistent().set(&claim_key, &transfer);
+            env.storage().persistent().extend_ttl(
+                &claim_key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+
+            InboundClaimable {
+                source_chain,
+                source_tx,
+                recipient,
+                amount,
+            }
+            .publish(env);
+            return Ok(());
+        }
+
+        Self::check_reserve(env, &token, amount)?;
+        let token_client = token::Client::new(env, &token);
+        token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
+        // Emit inbound completion event
+        InboundReleased {
+            source_chain,
+            source_tx,
+            recipient,
+            amount,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    fn save_bond(env: &Env, relayer: &Address, bond: i128) {
+        let key = (BOND, relayer.clone());
+        env.storage().persistent().set(&key, &bond);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn slash_relayer(env: &Env, proof: &FraudProof) -> Result<i128, Error> {
+        let request = Self::load_request(env, proof.request_id)?;
+
+        let mut relayers = Self::load_relayers(env);
+        relayers.remove(proof.relayer.clone());
+        env.storage().instance().set(&RELAYERS, &relayers);
+
+        // Bonds are posted in a single token, so they can only make up for
+        // the part of the request locked in that token
+        let mut slashed = 0;
+        let mut insured = 0;
+        if let Some(bond_config) = Self::get_bond_config(env.clone()) {
+            let harm: i128 = request
+                .assets
+                .iter()
+                .filter(|asset| asset.token == bond_config.token)
+                .map(|asset| asset.amount)
+                .sum();
+            let bond = Self::get_bond(env.clone(), proof.relayer.clone());
+            slashed = bond.min(harm).max(0);
+            let share = Self::get_insurance_share(env.clone());
+            insured = apply_bps(env, harm, share)
+                .unwrap_or(0)
+                .min(bond - slashed)
+                .max(0);
+
+            if slashed + insured > 0 {
+                Self::save_bond(env, &proof.relayer, bond - slashed - insured);
+            }
+            if slashed > 0 {
+                let token_client = token::Client::new(env, &bond_config.token);
+                token_client.transfer(&env.current_contract_address(), &request.user, &slashed);
+            }
+            if insured > 0 {
+                // The forfeited bond stays in the bridge, reserved for claims
+                let balance = Self::get_insurance_balance(env.clone(), bond_config.token.clone());
+                Self::save_insurance_balance(env, &bond_config.token, balance + insured);
+            }
+        }
+
+        RelayerSlashed {
+            request_id: proof.request_id,
+            relayer: proof.relayer.clone(),
+            slashed,
+            insured,
+        }
+        .publish(env);
+
+        Ok(slashed)
+    }
+
+    /// Count a fraud proof against `relayer` as opened or settled
+    fn track_open_fraud(env: &Env, relayer: &Address, opened: bool) {
+        let key = (OPEN_FRAUD, relayer.clone());
+        let open: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+        let open = if opened {
+            open + 1
+        } else {
+            open.saturating_sub(1)
+        };
+        if open == 0 {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        env.storage().persistent().set(&key, &open);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Reserve the insurance share of `fee` for claims, returning it
+    fn contribute_to_insurance(env: &Env, request_id: u64, token: &Address, fee: i128) -> i128 {
+        let share = Self::get_insurance_share(env.clone());
+        let contribution = apply_bps(env, fee, share).unwrap_or(0);
+        if contribution <= 0 {
+            return 0;
+        }
+
+        let balance = Self::get_insurance_balance(env.clone(), token.clone());
+        Self::save_insurance_balance(env, token, balance + contribution);
+
+        // Emit insurance contribution event
+        InsuranceContributed {
+            request_id,
+            token: token.clone(),
+            amount: contribution,
+        }
+        .publish(env);
+
+        contribution
+    }
+
+    /// Pay the user's referrer its share of `fee`, at most `available`
+    fn pay_referral(
+        env: &Env,
+        user: &Address,
+        token: &Address,
+        volume: i128,
+        fee: i128,
+        available: i128,
+    ) {
+        let (Some(registry), Some(code)) = (
+            Self::get_referral_registry(env.clone()),
+            Self::get_referral_code(env.clone(), user.clone()),
+        ) else {
+            return;
+        };
+
+        let referral = ReferralClient::new(env, &registry);
+        let share = referral.get_share(&code);
+        if share == 0 {
+            return;
+        }
+
+        let amount = apply_bps(env, fee, share).unwrap_or(0).min(available);
+        if amount > 0 {
+            token::Client::new(env, token).transfer(
+                &env.current_contract_address(),
+                &registry,
+                &amount,
+            );
+        }
+        referral.accrue(
+            &env.current_contract_address(),
+            &code,
+            token,
+            &volume,
+            &amount,
+        );
+    }
+
+    /// Make sure paying out `amount` of `token` leaves the insurance
+    /// reserve for it in place
+    fn check_reserve(env: &Env, token: &Address, amount: i128) -> Result<(), Error> {
+        let held = token::Client::new(env, token).balance(&env.current_contract_address());
+        let reserved = Self::get_insurance_balance(env.clone(), token.clone());
+        if held.saturating_sub(amount) < reserved {
+            return Err(Error::InsufficientLiquidity);
+        }
+        Ok(())
+    }
+
+    fn save_insurance_balance(env: &Env, token: &Address, balance: i128) {
+        let key = (INSURANCE, token.clone());
+        env.storage().persistent().set(&key, &balance);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn save_user_exposure(env: &Env, user: &Address, token: &Address, exposure: i128) {
+        let key = (EXPOSURE, user.clone(), token.clone());
+        if exposure == 0 {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+
+        env.storage().persistent().set(&key, &exposure);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn load_relayers(env: &Env) -> Map<Address, bool> {
+        env.storage()
+            .instance()
+            .get(&RELAYERS)
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    fn bump_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    /// Require the admin's authorization and return the admin
+    ///
+    /// Fails with `NotAuthorized` when no admin is set, so nobody could
+    /// authorize the call.
+    fn require_admin(env: &Env) -> Result<Address, Error> {
+        let admin = ownable::get_admin(env).ok_or(Error::NotAuthorized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    fn load_config(env: &Env) -> Result<BridgeConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Configuration still in the version 1 layout, if any
+    fn legacy_config(env: &Env) -> Option<BridgeConfigV1> {
+        let raw: Val = env.storage().instance().get(&CONFIG)?;
+        let fields = Map::<Symbol, Val>::from_val(env, &raw);
+        if !fields.contains_key(Symbol::new(env, "admin")) {
+            return None;
+        }
+        Some(BridgeConfigV1::from_val(env, &raw))
+    }
+
+    fn validate_config(config: &BridgeConfig) -> Result<(), Error> {
+        if config.base_fee < 0
+            || config.fee_percentage > 10000
+            || config.min_amount <= 0
+            || config.min_amount > config.max_amount
+        {
+            return Err(Error::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    fn check_not_blocked(env: &Env, account: &Address) -> Result<(), Error> {
+        if Self::is_address_blocked(env.clone(), account.clone()) {
+            return Err(Error::Blocked);
+        }
+        Ok(())
+    }
+
+    fn check_not_paused(env: &Env, chain: &Symbol, token: &Address) -> Result<(), Error> {
+        if pausable::is_paused(env) {
+            return Err(Error::Paused);
+        }
+        if Self::is_chain_paused(env.clone(), chain.clone()) {
+            return Err(Error::ChainPaused);
+        }
+        if Self::is_token_paused(env.clone(), token.clone()) {
+            return Err(Error::TokenPaused);
+        }
+        Ok(())
+    }
+
+    /// Pending requests can move to any later status, processing requests
+    /// can only finish, and completed/failed requests are final
+    fn is_valid_transition(old_status: u32, new_status: u32) -> bool {
+        matches!(
+            (old_status, new_status),
+            (0, 1) | (0, 2) | (0, 3) | (1, 2) | (1, 3)
+        )
+    }
+
+    fn set_chain_paused(env: &Env, chain: Symbol, paused: bool) -> Result<(), Error> {
+        Self::require_admin(env)?;
+
+        let mut paused_chains: Map<Symbol, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_CHAINS)
+            .unwrap_or_else(|| Map::new(env));
+
+        if paused {
+            paused_chains.set(chain.clone(), true);
+        } else {
+            paused_chains.remove(chain.clone());
+        }
+        env.storage().instance().set(&PAUSED_CHAINS, &paused_chains);
+
+        ChainPaused { chain, paused }.publish(env);
+
+        Ok(())
+    }
+
+    fn set_token_paused(env: &Env, token: Address, paused: bool) -> Result<(), Error> {
+        Self::require_admin(env)?;
+
+        let mut paused_tokens: Map<Address, bool> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_TOKENS)
+            .unwrap_or_else(|| Map::new(env));
+
+        if paused {
+            paused_tokens.set(token.clone(), true);
+        } else {
+            paused_tokens.remove(token.clone());
+        }
+        env.storage().instance().set(&PAUSED_TOKENS, &paused_tokens);
+
+        TokenPaused { token, paused }.publish(env);
+
+        Ok(())
+    }
+
+    fn load_chain_stats(env: &Env, chain: &Symbol) -> BridgeStats {
+        env.storage()
+            .persistent()
+            .get(&(CHAIN_STATS, chain.clone()))
+            .unwrap_or_default()
+    }
+
+    fn load_token_stats(env: &Env, token: &Address) -> BridgeStats {
+        env.storage()
+            .persistent()
+            .get(&(TOKEN_STATS, token.clone()))
+            .unwrap_or_default()
+    }
+
+    fn save_chain_stats(env: &Env, chain: &Symbol, stats: &BridgeStats) {
+        let key = (CHAIN_STATS, chain.clone());
+        env.storage().persistent().set(&key, stats);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn save_token_stats(env: &Env, token: &Address, stats: &BridgeStats) {
+        let key = (TOKEN_STATS, token.clone());
+        env.storage().persistent().set(&key, stats);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Count a new pending request with the given volume and fees
+    fn record_request(env: &Env, stats: &mut BridgeStats, amount: i128, fee: i128) {
+        Self::roll_window(env, stats);
+        stats.total_volume += amount;
+        stats.rolling_volume += amount;
+        stats.fees_collected += fee;
+        Self::move_status(stats, u32::MAX, 0);
+    }
+
+    /// Reset the rolling volume once the current window has elapsed
+    fn roll_window(env: &Env, stats: &mut BridgeStats) {
+        let now = env.ledger().timestamp();
+        if now >= stats.rolling_window_start + ROLLING_WINDOW {
+            stats.rolling_window_start = now;
+            stats.rolling_volume = 0;
+        }
+    }
+
+    /// Decrement the counter for the old status and increment the new one
+    fn move_status(stats: &mut BridgeStats, old_status: u32, new_status: u32) {
+        if let Some(count) = Self::status_count(stats, old_status) {
+            *count = count.saturating_sub(1);
+        }
+        if let Some(count) = Self::status_count(stats, new_status) {
+            *count += 1;
+        }
+    }
+
+    fn load_global_stats(env: &Env) -> GlobalStats {
+        env.storage()
+            .instance()
+            .get(&GLOBAL_STATS)
+            .unwrap_or_default()
+    }
+
+    /// Total amount locked by a request across its assets
+    fn request_value(request: &BridgeRequest) -> i128 {
+        request.assets.iter().map(|asset| asset.amount).sum()
+    }
+
+    fn move_global(stats: &mut GlobalStats, old_status: u32, new_status: u32, value: i128) {
+        if let Some((count, total)) = Self::global_bucket(stats, old_status) {
+            *count = count.saturating_sub(1);
+            *total -= value;
+        }
+        if let Some((count, total)) = Self::global_bucket(stats, new_status) {
+            *count += 1;
+            *total += value;
+        }
+    }
+
+    fn global_bucket(stats: &mut GlobalStats, status: u32) -> Option<(&mut u64, &mut i128)> {
+        match status {
+            0 => Some((&mut stats.pending_count, &mut stats.pending_value)),
+            1 => Some((&mut stats.processing_count, &mut stats.processing_value)),
+            2 => Some((&mut stats.completed_count, &mut stats.completed_value)),
+            3 => Some((&mut stats.failed_count, &mut stats.failed_value)),
+            REFUNDED_BUCKET => Some((&mut stats.refunded_count, &mut stats.refunded_value)),
+            _ => None,
+        }
+    }
+
+    fn status_count(stats: &mut BridgeStats, status: u32) -> Option<&mut u64> {
+        match status {
+            0 => Some(&mut stats.pending_count),
+            1 => Some(&mut stats.processing_count),
+            2 => Some(&mut stats.completed_count),
+            3 => Some(&mut stats.failed_count),
+            _ => None,
+        }
+    }
+}
+
+stellhydra_access::ownable!(StellarBridge);
+stellhydra_access::pausable!(StellarBridge);
+stellhydra_access::roles!(StellarBridge);
+stellhydra_access::allowance!(StellarBridge);
+
+impl Upgradeable for StellarBridge {
+    const VERSION: u32 = 2;
+
+    /// Version 1 kept the admin in the configuration, so until `migrate`
+    /// moves it out that admin authorizes upgrades
+    fn require_upgrade_authority(env: &Env) {
+        let admin = ownable::get_admin(env)
+            .or_else(|| Self::legacy_config(env).map(|config| config.admin))
+            .unwrap_or_else(|| panic!("Not initialized"));
+        admin.require_auth();
+    }
+
+    fn migrate_step(env: &Env, from: u32) {
+        if from == 1 {
+            // Split the admin and the pause switch out of the configuration
+            if let Some(legacy) = Self::legacy_config(env) {
+                if !ownable::has_admin(env) {
+                    ownable::init_admin(env, &legacy.admin);
+                }
+                if legacy.is_paused {
+                    pausable::set_paused(env, true);
+                }
+                let config = BridgeConfig {
+                    fee_recipient: legacy.fee_recipient,
+                    base_fee: legacy.base_fee,
+                    fee_percentage: legacy.fee_percentage,
+                    min_amount: legacy.min_amount,
+                    max_amount: legacy.max_amount,
+                };
+                env.storage().instance().set(&CONFIG, &config);
+            }
+
+            // Move the requests out of the instance map into their own
+            // entries. Records keep their stored layout and are upgraded
+            // when next read.
+            let requests: Map<u64, Val> = env
+                .storage()
+                .instance()
+                .get(&REQUESTS)
+                .unwrap_or_else(|| Map::new(env));
+            for (request_id, request) in requests.iter() {
+                let key = (REQUEST, request_id);
+                env.storage().persistent().set(&key, &request);
+                env.storage().persistent().extend_ttl(
+                    &key,
+                    PERSISTENT_LIFETIME_THRESHOLD,
+                    PERSISTENT_BUMP_AMOUNT,
+                );
+            }
+            env.storage().instance().remove(&REQUESTS);
+        }
+    }
+}
+
+stellhydra_access::upgradeable!(StellarBridge);
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger},
+        token::StellarAssetClient,
+        vec, Env, IntoVal, String, TryFromVal,
+    };
+    use stellhydra_mock_token::{MockToken, MockTokenClient};
+
+    #[contract]
+    struct MockCompliance;
+
+    #[contractimpl]
+    impl MockCompliance {
+        pub fn set_blocked(env: Env, account: Address) {
+            env.storage().instance().set(&account, &true);
+        }
+
+        pub fn is_blocked(env: Env, account: Address) -> bool {
+            env.storage().instance().get(&account).unwrap_or(false)
+        }
+    }
+
+    #[contract]
+    struct MockStaking;
+
+    #[contractimpl]
+    impl MockStaking {
+        pub fn set_stake(env: Env, user: Address, amount: i128) {
+            env.storage().instance().set(&user, &amount);
+        }
+
+        pub fn staked_balance(env: Env, user: Address) -> i128 {
+            env.storage().instance().get(&user).unwrap_or(0)
+        }
+    }
+
+    #[contract]
+    struct MockReferral;
+
+    #[contractimpl]
+    impl MockReferral {
+        pub fn get_share(_env: Env, code: Symbol) -> u32 {
+            if code == symbol_short!("partner") {
+                2_000
+            } else {
+                0
+            }
+        }
+
+        pub fn get_max_share(_env: Env) -> u32 {
+            2_000
+        }
+
+        pub fn accrue(
+            env: Env,
+            source: Address,
+            code: Symbol,
+            token: Address,
+            volume: i128,
+            amount: i128,
+        ) {
+            source.require_auth();
+            let key = (code, token);
+            let (total_volume, total_amount): (i128, i128) =
+                env.storage().instance().get(&key).unwrap_or((0, 0));
+            env.storage()
+                .instance()
+                .set(&key, &(total_volume + volume, total_amount + amount));
+        }
+
+        pub fn accrued(env: Env, code: Symbol, token: Address) -> (i128, i128) {
+            env.storage()
+                .instance()
+                .get(&(code, token))
+                .unwrap_or((0, 0))
+        }
+    }
+
+    #[contract]
+    struct MockRebate;
+
+    #[contractimpl]
+    impl MockRebate {
+        pub fn report_fee(env: Env, source: Address, user: Address, token: Address, fee: i128) {
+            source.require_auth();
+            let key = (user, token);
+            let fees: i128 = env.storage().instance().get(&key).unwrap_or(0);
+            env.storage().instance().set(&key, &(fees + fee));
+        }
+
+        pub fn fees(env: Env, user: Address, token: Address) -> i128 {
+            env.storage().instance().get(&(user, token)).unwrap_or(0)
+        }
+    }
+
+    fn setup(env: &Env) -> (StellarBridgeClient<'_>, Address, Address) {
+        let contract_id = env.register_contract(None, StellarBridge);
+        let client = StellarBridgeClient::new(env, &contract_id);
+
+        let admin = Address::generate(env);
+        let fee_recipient = Address::generate(env);
+        client.initialize(&admin, &fee_recipient, &10, &100, &100, &1_000_000);
+
+        let token_admin = Address::generate(env);
+        let token = env
+            .register_stellar_asset_contract_v2(token_admin)
+            .address();
+
+        (client, admin, token)
+    }
+
+    fn funded_user(env: &Env, token: &Address, amount: i128) -> Address {
+        let user = Address::generate(env);
+        StellarAssetClient::new(env, token).mint(&user, &amount);
+        user
+    }
+
+    #[test]
+    fn test_stats_track_volume_and_fees() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        client.create_bridge_request(&user, &token, &eth, &to_address, &2_000, &None);
+
+        let chain_stats = client.get_bridge_stats(&eth);
+        assert_eq!(chain_stats.total_volume, 3_000);
+        assert_eq!(chain_stats.rolling_volume, 3_000);
+        // base fee 10 + 1% of each amount
+        assert_eq!(chain_stats.fees_collected, 20 + 10 + 20);
+        assert_eq!(chain_stats.pending_count, 2);
+
+        let token_stats = client.get_token_stats(&token);
+        assert_eq!(token_stats, chain_stats);
+
+        let other = client.get_bridge_stats(&symbol_short!("POLYGON"));
+        assert_eq!(other, BridgeStats::default());
+    }
+
+    #[test]
+    fn test_stats_follow_status_updates() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let first = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        let second = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+
+        client.update_request_status(&first, &1, &None);
+        client.update_request_status(&first, &2, &None);
+        client.update_request_status(&second, &3, &None);
+
+        let stats = client.get_token_stats(&token);
+        assert_eq!(stats.pending_count, 0);
+        assert_eq!(stats.processing_count, 0);
+        assert_eq!(stats.completed_count, 1);
+        assert_eq!(stats.failed_count, 1);
+    }
+
+    #[test]
+    fn test_paused_chain_rejects_requests() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.pause_chain(&eth);
+        assert!(client.is_chain_paused(&eth));
+
+        let result =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(result, Err(Ok(Error::ChainPaused)));
+    }
+
+    #[test]
+    fn test_guardian_pause_halts_every_route() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let guardian = Address::generate(&env);
+        client.grant_role(&stellhydra_access::PAUSER, &guardian);
+        client.pause(&guardian);
+        assert!(client.is_paused());
+
+        let result =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(result, Err(Ok(Error::Paused)));
+
+        client.unpause();
+        client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(client.get_request_count(), 1);
+    }
+
+    #[test]
+    fn test_pause_is_scoped_to_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let other_token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        client.pause_token(&other_token);
+
+        // Unrelated token still bridges
+        client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+
+        client.pause_token(&token);
+        let result =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(result, Err(Ok(Error::TokenPaused)));
+
+        client.unpause_token(&token);
+        client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(client.get_request_count(), 2);
+    }
+
+    #[test]
+    fn test_complete_inbound_releases_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        StellarAssetClient::new(&env, &token).mint(&client.address, &5_000);
+
+        let recipient = Address::generate(&env);
+        let eth = symbol_short!("ETH");
+
+        let proof = prove_inbound(&env, &client, 100, &recipient, &token, 2_000);
+        let source_tx =
+            client.complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token, &recipient);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+        assert_eq!(
+            client.get_inbound_transfer(&source_tx).unwrap().amount,
+            2_000
+        );
+
+        let replay =
+            client.try_complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token, &recipient);
+        assert_eq!(replay, Err(Ok(Error::InboundAlreadyCompleted)));
+
+        let proof = prove_inbound(&env, &client, 101, &recipient, &token, 1_000);
+        client.pause_chain(&eth);
+        let paused =
+            client.try_complete_inbound_with_proof(&eth, &101, &0, &0, &proof, &token, &recipient);
+        assert_eq!(paused, Err(Ok(Error::ChainPaused)));
+        assert_eq!(token_client.balance(&recipient), 2_000);
+
+        client.unpause_chain(&eth);
+        client.pause_token(&token);
+        let paused =
+            client.try_complete_inbound_with_proof(&eth, &101, &0, &0, &proof, &token, &recipient);
+        assert_eq!(paused, Err(Ok(Error::TokenPaused)));
+
+        client.unpause_token(&token);
+        client.complete_inbound_with_proof(&eth, &101, &0, &0, &proof, &token, &recipient);
+        assert_eq!(token_client.balance(&recipient), 3_000);
+    }
+
+    #[test]
+    fn test_token_level_blocks_leave_no_partial_state() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, _) = setup(&env);
+        let token = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+        token.initialize(
+            &String::from_str(&env, "Frozen USD"),
+            &String::from_str(&env, "fUSD"),
+            &6,
+        );
+        let user = Address::generate(&env);
+        token.mint(&user, &10_000);
+        token.mint(&client.address, &5_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        // A sender frozen by the token opens no request
+        token.set_blocked(&user, &true);
+        let frozen = client.try_create_bridge_request(
+            &user,
+            &token.address,
+            &eth,
+            &to_address,
+            &1_000,
+            &None,
+        );
+        assert!(frozen.is_err());
+        assert_eq!(client.get_stats().request_count, 0);
+        assert_eq!(client.get_user_exposure(&user, &token.address), 0);
+
+        // A release to a frozen recipient can be replayed once it is lifted
+        let proof = prove_inbound(&env, &client, 100, &user, &token.address, 2_000);
+        let release = client.try_complete_inbound_with_proof(
+            &eth,
+            &100,
+            &0,
+            &0,
+            &proof,
+            &token.address,
+            &user,
+        );
+        assert!(release.is_err());
+        assert!(client
+            .get_inbound_transfer(&inbound_id(&env, 100))
+            .is_none());
+
+        token.set_blocked(&user, &false);
+        client.complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token.address, &user);
+        assert_eq!(token.balance(&user), 12_000);
+    }
+
+    #[test]
+    fn test_claim_delivery_holds_inbound_until_claimed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        StellarAssetClient::new(&env, &token).mint(&client.address, &5_000);
+        client.set_claim_delivery(&true);
+
+        let recipient = Address::generate(&env);
+        let eth = symbol_short!("ETH");
+
+        let proof = prove_inbound(&env, &client, 100, &recipient, &token, 2_000);
+        let message_id =
+            client.complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token, &recipient);
+        assert_eq!(token_client.balance(&recipient), 0);
+        assert_eq!(client.get_claimable(&message_id).unwrap().amount, 2_000);
+
+        // Completion is still recorded, so the transfer cannot be replayed
+        let replay =
+            client.try_complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token, &recipient);
+        assert_eq!(replay, Err(Ok(Error::InboundAlreadyCompleted)));
+
+        let claimed = client.claim(&message_id);
+        assert_eq!(claimed.recipient, recipient);
+        assert_eq!(token_client.balance(&recipient), 2_000);
+        assert_eq!(client.get_claimable(&message_id), None);
+        assert_eq!(
+            client.try_claim(&message_id),
+            Err(Ok(Error::NothingToClaim))
+        );
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, _) = setup(&env);
+        let result = client.try_initialize(&admin, &admin, &0, &0, &1, &1);
+        assert_eq!(result, Err(Ok(Error::AlreadyInitialized)));
+    }
+
+    #[test]
+    fn test_admin_paths_need_an_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, _) = setup(&env);
+        env.as_contract(&client.address, || {
+            env.storage().instance().remove(&ownable::ADMIN);
+        });
+        let result = client.try_update_config(&None, &Some(0), &None, &None, &None);
+        assert_eq!(result, Err(Ok(Error::NotAuthorized)));
+        assert_eq!(client.try_retry_request(&0), Err(Ok(Error::NotAuthorized)));
+    }
+
+    #[test]
+    fn test_amount_out_of_range() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 10_000_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let too_small =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &99, &None);
+        assert_eq!(too_small, Err(Ok(Error::AmountOutOfRange)));
+
+        let too_large =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000_001, &None);
+        assert_eq!(too_large, Err(Ok(Error::AmountOutOfRange)));
+    }
+
+    #[test]
+    fn test_status_transitions_are_enforced() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        client.update_request_status(&request_id, &2, &None);
+
+        let reopen = client.try_update_request_status(&request_id, &1, &None);
+        assert_eq!(reopen, Err(Ok(Error::InvalidStatusTransition)));
+
+        let missing = client.try_update_request_status(&99, &1, &None);
+        assert_eq!(missing, Err(Ok(Error::RequestNotFound)));
+    }
+
+    #[test]
+    fn test_archive_finished_request_after_retention() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+
+        let active = client.try_archive_request(&request_id);
+        assert_eq!(active, Err(Ok(Error::RequestNotTerminal)));
+
+        // Retention counts from completion, not creation
+        env.ledger()
+            .with_mut(|li| li.timestamp += DEFAULT_RETENTION_PERIOD);
+        client.update_request_status(&request_id, &2, &None);
+        let early = client.try_archive_request(&request_id);
+        assert_eq!(early, Err(Ok(Error::RetentionNotElapsed)));
+
+        env.ledger()
+            .with_mut(|li| li.timestamp += DEFAULT_RETENTION_PERIOD);
+        client.archive_request(&request_id);
+
+        assert_eq!(client.get_bridge_request(&request_id), None);
+        // Archival does not rewrite history in the stats
+        assert_eq!(client.get_bridge_stats(&eth).completed_count, 1);
+    }
+
+    #[test]
+    fn test_archive_requires_failed_request_to_be_settled() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        client.update_request_status(&request_id, &3, &None);
+        client.retry_request(&request_id);
+        client.update_request_status(&request_id, &3, &None);
+        env.ledger()
+            .with_mut(|li| li.timestamp += DEFAULT_RETENTION_PERIOD);
+
+        // The user's funds are still locked, so the record must stay
+        let unsettled = client.try_archive_request(&request_id);
+        assert_eq!(unsettled, Err(Ok(Error::RequestNotSettled)));
+
+        client.finalize_failure(&request_id);
+        client.archive_request(&request_id);
+
+        assert_eq!(client.get_bridge_request(&request_id), None);
+        assert!(!client.is_request_finalized(&request_id));
+        assert_eq!(client.get_retry_count(&request_id), 0);
+    }
+
+    #[test]
+    fn test_priority_fee_paid_to_completing_relayer() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+        let relayer = Address::generate(&env);
+
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &Some(50));
+        // amount + (10 base + 1%) fee + priority fee
+        assert_eq!(token_client.balance(&user), 100_000 - 1_000 - 20 - 50);
+        assert_eq!(
+            client.get_bridge_request(&request_id).unwrap().priority_fee,
+            50
+        );
+
+        let unapproved = client.try_complete_request(&relayer, &request_id, &None);
+        assert_eq!(unapproved, Err(Ok(Error::NotRelayer)));
+
+        client.add_relayer(&relayer);
+        client.complete_request(&relayer, &request_id, &None);
+
+        assert_eq!(token_client.balance(&relayer), 50);
+        assert_eq!(client.get_bridge_request(&request_id).unwrap().status, 2);
+        // Priority fees are relayer pay, not protocol fees
+        assert_eq!(client.get_bridge_stats(&eth).fees_collected, 20);
+    }
+
+    #[test]
+    fn test_negative_priority_fee_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let result =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &Some(-1));
+        assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+    }
+
+    #[test]
+    fn test_blocklist_rejects_users_and_destinations() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.set_address_blocked(&user, &true);
+        let blocked_user =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(blocked_user, Err(Ok(Error::Blocked)));
+
+        client.set_address_blocked(&user, &false);
+        client.set_destination_blocked(&to_address, &true);
+        let blocked_dest =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        assert_eq!(blocked_dest, Err(Ok(Error::Blocked)));
+
+        let clean_address = BytesN::from_array(&env, &[2; 32]);
+        client.create_bridge_request(&user, &token, &eth, &clean_address, &1_000, &None);
+    }
+
+    #[test]
+    fn test_compliance_contract_blocks_inbound_recipient() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        StellarAssetClient::new(&env, &token).mint(&client.address, &5_000);
+
+        let compliance_id = env.register_contract(None, MockCompliance);
+        let compliance = MockComplianceClient::new(&env, &compliance_id);
+        client.set_compliance_contract(&Some(compliance_id.clone()));
+
+        let recipient = Address::generate(&env);
+        compliance.set_blocked(&recipient);
+        assert!(client.is_address_blocked(&recipient));
+
+        let proof = prove_inbound(&env, &client, 100, &recipient, &token, 1_000);
+        let result = client.try_complete_inbound_with_proof(
+            &symbol_short!("ETH"),
+            &100,
+            &0,
+            &0,
+            &proof,
+            &token,
+            &recipient,
+        );
+        assert_eq!(result, Err(Ok(Error::Blocked)));
+
+        client.set_compliance_contract(&None);
+        assert!(!client.is_address_blocked(&recipient));
+    }
+
+    #[test]
+    fn test_insurance_fund_collects_and_pays_claims() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.set_insurance_share(&5000);
+
+        // fee = 10 + 1% of 9_000 = 100, half of which is insured
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(client.get_insurance_balance(&token), 50);
+
+        let pending = client.try_pay_insurance_claim(&request_id, &user, &token, &10);
+        assert_eq!(pending, Err(Ok(Error::RequestNotFailed)));
+
+        client.update_request_status(&request_id, &3, &None);
+
+        let too_much = client.try_pay_insurance_claim(&request_id, &user, &token, &51);
+        assert_eq!(too_much, Err(Ok(Error::InsufficientInsurance)));
+
+        // A token the request never locked cannot be claimed against
+        let other = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let wrong_asset = client.try_pay_insurance_claim(&request_id, &user, &other, &10);
+        assert_eq!(wrong_asset, Err(Ok(Error::AssetMismatch)));
+
+        let balance_before = token_client.balance(&user);
+        client.pay_insurance_claim(&request_id, &user, &token, &40);
+        assert_eq!(token_client.balance(&user), balance_before + 40);
+        assert_eq!(client.get_insurance_balance(&token), 10);
+        assert_eq!(client.get_insurance_claim(&request_id).unwrap().amount, 40);
+
+        let twice = client.try_pay_insurance_claim(&request_id, &user, &token, &5);
+        assert_eq!(twice, Err(Ok(Error::ClaimAlreadyPaid)));
+
+        // What's left of the fund stays reserved for claims
+        let held = token_client.balance(&client.address);
+        let admin = Address::generate(&env);
+        let drain = client.try_emergency_withdraw(&token, &admin, &held);
+        assert_eq!(drain, Err(Ok(Error::InsufficientLiquidity)));
+        client.emergency_withdraw(&token, &admin, &(held - 10));
+        assert_eq!(token_client.balance(&client.address), 10);
+    }
+
+    #[test]
+    fn test_insurance_and_referral_shares_fit_in_the_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, _) = setup(&env);
+        let registry_id = env.register_contract(None, MockReferral);
+        client.set_referral_registry(&Some(registry_id.clone()));
+
+        // Referrers may take up to 20%, leaving at most 80% to insure
+        assert_eq!(
+            client.try_set_insurance_share(&8_001),
+            Err(Ok(Error::InvalidConfig))
+        );
+        client.set_insurance_share(&8_000);
+        assert_eq!(client.get_insurance_share(), 8_000);
+
+        // Nor can a registry come in on top of a larger insurance share
+        client.set_referral_registry(&None);
+        client.set_insurance_share(&9_000);
+        let over = client.try_set_referral_registry(&Some(registry_id));
+        assert_eq!(over, Err(Ok(Error::InvalidConfig)));
+        assert_eq!(client.get_referral_registry(), None);
+    }
+
+    #[test]
+    fn test_exposure_cap_limits_in_flight_value() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.set_exposure_cap(&token, &Some(2_500));
+
+        let first = client.create_bridge_request(&user, &token, &eth, &to_address, &1_500, &None);
+        assert_eq!(client.get_user_exposure(&user, &token), 1_500);
+
+        let over =
+            client.try_create_bridge_request(&user, &token, &eth, &to_address, &1_500, &None);
+        assert_eq!(over, Err(Ok(Error::ExposureCapExceeded)));
+
+        // Other users have their own allowance
+        let other = funded_user(&env, &token, 100_000);
+        client.create_bridge_request(&other, &token, &eth, &to_address, &2_000, &None);
+
+        // Completing a request frees up room
+        client.update_request_status(&first, &2, &None);
+        assert_eq!(client.get_user_exposure(&user, &token), 0);
+        client.create_bridge_request(&user, &token, &eth, &to_address, &2_500, &None);
+    }
+
+    fn rlp_bytes(bytes: &[u8]) -> std::vec::Vec<u8> {
+        let mut out = std::vec::Vec::new();
+        if bytes.len() == 1 && bytes[0] < 0x80 {
+            out.push(bytes[0]);
+        } else if bytes.len() <= 55 {
+            out.push(0x80 + bytes.len() as u8);
+            out.extend_from_slice(bytes);
+        } else {
+            let len = (bytes.len() as u32).to_be_bytes();
+            let skip = len.iter().take_while(|b| **b == 0).count();
+            out.push(0xb7 + (4 - skip) as u8);
+            out.extend_from_slice(&len[skip..]);
+            out.extend_from_slice(bytes);
+        }
+        out
+    }
+
+    fn rlp_list(items: &[std::vec::Vec<u8>]) -> std::vec::Vec<u8> {
+        let payload: std::vec::Vec<u8> = items.concat();
+        let mut out = std::vec::Vec::new();
+        if payload.len() <= 55 {
+            out.push(0xc0 + payload.len() as u8);
+        } else {
+            let len = (payload.len() as u32).to_be_bytes();
+            let skip = len.iter().take_while(|b| **b == 0).count();
+            out.push(0xf7 + (4 - skip) as u8);
+            out.extend_from_slice(&len[skip..]);
+        }
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// EIP-1559 receipt with a single log
+    fn receipt(emitter: &[u8; 20], topic: &[u8; 32], data: &[u8]) -> std::vec::Vec<u8> {
+        let log = rlp_list(&[
+            rlp_bytes(emitter),
+            rlp_list(&[rlp_bytes(topic)]),
+            rlp_bytes(data),
+        ]);
+        let mut out = std::vec![0x02];
+        out.extend(rlp_list(&[
+            rlp_bytes(&[1]),
+            rlp_bytes(&[0x52, 0x08]),
+            rlp_bytes(&[0; 256]),
+            rlp_list(&[log]),
+        ]));
+        out
+    }
+
+    fn keccak(env: &Env, data: &[u8]) -> [u8; 32] {
+        env.crypto()
+            .keccak256(&Bytes::from_slice(env, data))
+            .to_bytes()
+            .to_array()
+    }
+
+    fn inbound_log_data(env: &Env, recipient: &Address, token: &Address, amount: i128) -> [u8; 64] {
+        let commitment = env
+            .crypto()
+            .sha256(&(recipient.clone(), token.clone()).to_xdr(env))
+            .to_bytes()
+            .to_array();
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(&commitment);
+        data[48..].copy_from_slice(&amount.to_be_bytes());
+        data
+    }
+
+    /// Attest an ETH block whose only receipt carries an inbound transfer
+    /// and return the proof for it (transaction 0, log 0)
+    fn prove_inbound(
+        env: &Env,
+        client: &StellarBridgeClient,
+        block_number: u64,
+        recipient: &Address,
+        token: &Address,
+        amount: i128,
+    ) -> Vec<Bytes> {
+        let eth = symbol_short!("ETH");
+        let emitter = [0xaa; 20];
+        let topic = [0xbb; 32];
+        client.set_evm_source(
+            &eth,
+            &BytesN::from_array(env, &emitter),
+            &BytesN::from_array(env, &topic),
+        );
+
+        // Single-leaf trie under key rlp(0) = 0x80
+        let data = inbound_log_data(env, recipient, token, amount);
+        let leaf = rlp_list(&[
+            rlp_bytes(&[0x20, 0x80]),
+            rlp_bytes(&receipt(&emitter, &topic, &data)),
+        ]);
+        client.submit_block_root(
+            &eth,
+            &block_number,
+            &BytesN::from_array(env, &keccak(env, &leaf)),
+        );
+
+        Vec::from_array(env, [Bytes::from_slice(env, &leaf)])
+    }
+
+    /// Id of the transfer proven by [`prove_inbound`] for a block
+    fn inbound_id(env: &Env, block_number: u64) -> BytesN<32> {
+        env.crypto()
+            .keccak256(&(symbol_short!("ETH"), block_number, 0u32, 0u32).to_xdr(env))
+            .into()
+    }
+
+    #[test]
+    fn test_complete_inbound_with_receipt_proof() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        StellarAssetClient::new(&env, &token).mint(&client.address, &10_000);
+
+        let eth = symbol_short!("ETH");
+        let emitter = [0xaa; 20];
+        let topic = [0xbb; 32];
+        client.set_evm_source(
+            &eth,
+            &BytesN::from_array(&env, &emitter),
+            &BytesN::from_array(&env, &topic),
+        );
+
+        let recipient = Address::generate(&env);
+        let other = Address::generate(&env);
+
+        // Receipts trie with transactions 0 and 1: a branch node pointing at
+        // two leaves (key rlp(0) = 0x80, key rlp(1) = 0x01)
+        let receipt_0 = receipt(&emitter, &topic, &inbound_log_data(&env, &other, &token, 1));
+        let receipt_1 = receipt(
+            &emitter,
+            &topic,
+            &inbound_log_data(&env, &recipient, &token, 3_000),
+        );
+        let leaf_0 = rlp_list(&[rlp_bytes(&[0x30]), rlp_bytes(&receipt_0)]);
+        let leaf_1 = rlp_list(&[rlp_bytes(&[0x31]), rlp_bytes(&receipt_1)]);
+
+        let mut children: std::vec::Vec<std::vec::Vec<u8>> =
+            (0..17).map(|_| rlp_bytes(&[])).collect();
+        children[0] = rlp_bytes(&keccak(&env, &leaf_1));
+        children[8] = rlp_bytes(&keccak(&env, &leaf_0));
+        let branch = rlp_list(&children);
+
+        client.submit_block_root(
+            &eth,
+            &100,
+            &BytesN::from_array(&env, &keccak(&env, &branch)),
+        );
+
+        let mut proof = Vec::new(&env);
+        proof.push_back(Bytes::from_slice(&env, &branch));
+        proof.push_back(Bytes::from_slice(&env, &leaf_1));
+
+        // Proving the receipt for a different recipient fails
+        let wrong =
+            client.try_complete_inbound_with_proof(&eth, &100, &1, &0, &proof, &token, &other);
+        assert_eq!(wrong, Err(Ok(Error::InvalidProof)));
+
+        let wrong_index =
+            client.try_complete_inbound_with_proof(&eth, &100, &0, &0, &proof, &token, &recipient);
+        assert_eq!(wrong_index, Err(Ok(Error::InvalidProof)));
+
+        let id = client.complete_inbound_with_proof(&eth, &100, &1, &0, &proof, &token, &recipient);
+        assert_eq!(token_client.balance(&recipient), 3_000);
+        assert_eq!(client.get_inbound_transfer(&id).unwrap().amount, 3_000);
+
+        let replay =
+            client.try_complete_inbound_with_proof(&eth, &100, &1, &0, &proof, &token, &recipient);
+        assert_eq!(replay, Err(Ok(Error::InboundAlreadyCompleted)));
+
+        let unknown_block =
+            client.try_complete_inbound_with_proof(&eth, &101, &1, &0, &proof, &token, &recipient);
+        assert_eq!(unknown_block, Err(Ok(Error::UnknownBlockRoot)));
+    }
+
+    #[test]
+    fn test_single_leaf_receipt_proof() {
+        let env = Env::default();
+
+        let emitter = [0x11; 20];
+        let topic = [0x22; 32];
+        let receipt_0 = receipt(&emitter, &topic, &[0x33; 64]);
+        let leaf = rlp_list(&[rlp_bytes(&[0x20, 0x80]), rlp_bytes(&receipt_0)]);
+        let root = BytesN::from_array(&env, &keccak(&env, &leaf));
+
+        let mut proof = Vec::new(&env);
+        proof.push_back(Bytes::from_slice(&env, &leaf));
+
+        let value = light_client::verify_receipt_proof(&env, &root, 0, &proof).unwrap();
+        assert_eq!(value, Bytes::from_slice(&env, &receipt_0));
+
+        let log = light_client::receipt_log(&env, &value, 0).unwrap();
+        assert_eq!(log.address, BytesN::from_array(&env, &emitter));
+        assert_eq!(log.topics.get(0), Some(BytesN::from_array(&env, &topic)));
+        assert_eq!(log.data, Bytes::from_slice(&env, &[0x33; 64]));
+
+        assert_eq!(
+            light_client::receipt_log(&env, &value, 1),
+            Err(Error::InvalidProof)
+        );
+
+        let bad_root = BytesN::from_array(&env, &[0; 32]);
+        assert_eq!(
+            light_client::verify_receipt_proof(&env, &bad_root, 0, &proof),
+            Err(Error::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_fraud_proof_slashes_relayer_bond() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let relayer = funded_user(&env, &token, 5_000);
+        client.add_relayer(&relayer);
+        client.set_bond_config(&token, &3_000);
+
+        let first = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        let unbonded = client.try_complete_request(&relayer, &first, &None);
+        assert_eq!(unbonded, Err(Ok(Error::InsufficientBond)));
+
+        client.post_bond(&relayer, &5_000);
+        client.complete_request(&relayer, &first, &None);
+        assert_eq!(client.get_request_relayer(&first), Some(relayer.clone()));
+
+        let below_min = client.try_withdraw_bond(&relayer, &2_001);
+        assert_eq!(below_min, Err(Ok(Error::InsufficientBond)));
+
+        // The bond can't leave right after relaying
+        assert!(client.is_bond_locked(&relayer));
+        let early = client.try_withdraw_bond(&relayer, &100);
+        assert_eq!(early, Err(Ok(Error::BondLocked)));
+
+        let g1 = Address::generate(&env);
+        let g2 = Address::generate(&env);
+        let g3 = Address::generate(&env);
+        client.set_guardians(
+            &Vec::from_array(&env, [g1.clone(), g2.clone(), g3.clone()]),
+            &2,
+        );
+
+        let reporter = Address::generate(&env);
+        let evidence = Bytes::from_slice(&env, b"eth tx never sent");
+        client.submit_fraud_proof(&reporter, &first, &evidence);
+        client.set_insurance_share(&1_000);
+
+        // Nor while the proof is open, even once the lock period is over
+        env.ledger().with_mut(|li| li.timestamp += 7 * 86_400);
+        let open = client.try_withdraw_bond(&relayer, &100);
+        assert_eq!(open, Err(Ok(Error::BondLocked)));
+
+        let outsider = client.try_approve_fraud_proof(&reporter, &first);
+        assert_eq!(outsider, Err(Ok(Error::NotGuardian)));
+
+        let user_balance = token_client.balance(&user);
+        client.approve_fraud_proof(&g1, &first);
+        let twice = client.try_approve_fraud_proof(&g1, &first);
+        assert_eq!(twice, Err(Ok(Error::AlreadyApproved)));
+        assert_eq!(client.get_bond(&relayer), 5_000);
+
+        client.approve_fraud_proof(&g2, &first);
+
+        // User is made whole and the insurance share of the harm is forfeited
+        assert_eq!(token_client.balance(&user), user_balance + 1_000);
+        assert_eq!(client.get_insurance_balance(&token), 100);
+        assert_eq!(client.get_bond(&relayer), 3_900);
+        assert!(!client.is_relayer(&relayer));
+        assert_eq!(client.get_fraud_proof(&first).unwrap().slashed, 1_000);
+
+        // Settling the proof releases what's left of the bond
+        assert!(!client.is_bond_locked(&relayer));
+        client.withdraw_bond(&relayer, &3_900);
+        assert_eq!(token_client.balance(&relayer), 3_900);
+
+        let settled = client.try_approve_fraud_proof(&g3, &first);
+        assert_eq!(settled, Err(Ok(Error::AlreadyApproved)));
+    }
+
+    #[test]
+    fn test_fraud_proof_slash_with_mixed_tokens() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let other = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = token::Client::new(&env, &token);
+        let other_client = token::Client::new(&env, &other);
+        let user = funded_user(&env, &token, 100_000);
+        StellarAssetClient::new(&env, &other).mint(&user, &100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let guardian = Address::generate(&env);
+        client.set_guardians(&Vec::from_array(&env, [guardian.clone()]), &1);
+        client.set_bond_config(&token, &0);
+        let reporter = Address::generate(&env);
+        let evidence = Bytes::from_slice(&env, b"eth tx never sent");
+
+        // A short bond pays out what it holds against the bond-token part only
+        let partial = funded_user(&env, &token, 600);
+        client.add_relayer(&partial);
+        client.post_bond(&partial, &600);
+        let assets = vec![&env, (token.clone(), 1_000i128), (other.clone(), 2_000i128)];
+        let mixed = client.create_bridge_request_multi(&user, &assets, &eth, &to_address);
+        client.complete_request(&partial, &mixed, &None);
+
+        let token_before = token_client.balance(&user);
+        let other_before = other_client.balance(&user);
+        client.submit_fraud_proof(&reporter, &mixed, &evidence);
+        client.approve_fraud_proof(&guardian, &mixed);
+        assert_eq!(token_client.balance(&user), token_before + 600);
+        assert_eq!(other_client.balance(&user), other_before);
+        assert_eq!(client.get_bond(&partial), 0);
+        assert_eq!(client.get_fraud_proof(&mixed).unwrap().slashed, 600);
+
+        // Nothing in the bond token means nothing to slash
+        let bonded = funded_user(&env, &token, 5_000);
+        client.add_relayer(&bonded);
+        client.post_bond(&bonded, &5_000);
+        let foreign = client.create_bridge_request(&user, &other, &eth, &to_address, &1_000, &None);
+        client.complete_request(&bonded, &foreign, &None);
+        client.submit_fraud_proof(&reporter, &foreign, &evidence);
+        client.approve_fraud_proof(&guardian, &foreign);
+        assert_eq!(client.get_bond(&bonded), 5_000);
+        assert_eq!(client.get_fraud_proof(&foreign).unwrap().slashed, 0);
+        assert!(!client.is_relayer(&bonded));
+
+        // An unbonded relayer is still removed without reverting the approval
+        let unbonded = Address::generate(&env);
+        client.add_relayer(&unbonded);
+        let single = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        client.complete_request(&unbonded, &single, &None);
+        client.submit_fraud_proof(&reporter, &single, &evidence);
+        client.approve_fraud_proof(&guardian, &single);
+        assert_eq!(client.get_fraud_proof(&single).unwrap().slashed, 0);
+        assert!(!client.is_relayer(&unbonded));
+    }
+
+    #[test]
+    fn test_created_event_carries_full_request() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let request_id =
+            client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &Some(5));
+
+        let (contract, topics, data) = env
+            .events()
+            .all()
+            .iter()
+            .find(|(_, topics, _)| {
+                let name = topics
+                    .get(1)
+                    .and_then(|t| Symbol::try_from_val(&env, &t).ok());
+                name == Some(symbol_short!("CREATED"))
+            })
+            .unwrap();
+        assert_eq!(contract, client.address);
+        assert_eq!(
+            topics,
+            (
+                symbol_short!("BRIDGE"),
+                symbol_short!("CREATED"),
+                1u32,
+                request_id,
+                eth.clone()
+            )
+                .into_val(&env)
+        );
+
+        let event = RequestCreated::try_from_val(&env, &data).unwrap();
+        assert_eq!(
+            event,
+            RequestCreated {
+                request_id,
+                user,
+                from_token: token,
+                to_chain: eth,
+                to_address,
+                amount: 1_000,
+                fee: 20,
+                priority_fee: 5,
+                timestamp: env.ledger().timestamp(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_v1_requests_are_migrated_on_read() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = Address::generate(&env);
+
+        let legacy = BridgeRequestV1 {
+            user: user.clone(),
+            from_token: token.clone(),
+            to_chain: symbol_short!("ETH"),
+            to_address: BytesN::from_array(&env, &[1; 32]),
+            amount: 1_000,
+            fee: 20,
+            status: 0,
+            timestamp: 42,
+        };
+        env.as_contract(&client.address, || {
+            env.storage().persistent().set(&(REQUEST, 7u64), &legacy);
+        });
+
+        let request = client.get_bridge_request(&7).unwrap();
+        assert_eq!(request.version, REQUEST_VERSION);
+        assert_eq!(request.user, user);
+        assert_eq!(request.priority_fee, 0);
+        assert_eq!(request.timestamp, 42);
+
+        // Old records go through the normal state machine
+        client.update_request_status(&7, &1, &None);
+        assert_eq!(client.migrate_request(&7), REQUEST_VERSION);
+        assert_eq!(client.get_bridge_request(&7).unwrap().status, 1);
+    }
+
+    #[test]
+    fn test_migrate_request_reports_stored_version() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let legacy = BridgeRequestV1 {
+            user: Address::generate(&env),
+            from_token: token,
+            to_chain: symbol_short!("ETH"),
+            to_address: BytesN::from_array(&env, &[1; 32]),
+            amount: 1_000,
+            fee: 20,
+            status: 2,
+            timestamp: 0,
+        };
+        env.as_contract(&client.address, || {
+            env.storage().persistent().set(&(REQUEST, 3u64), &legacy);
+        });
+
+        assert_eq!(client.migrate_request(&3), 1);
+        assert_eq!(client.migrate_request(&3), REQUEST_VERSION);
+        assert_eq!(
+            client.try_migrate_request(&4),
+            Err(Ok(Error::RequestNotFound))
+        );
+    }
+
+    #[test]
+    fn test_migrate_moves_requests_out_of_the_map() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        assert_eq!(client.get_version(), 2);
+
+        let legacy = BridgeRequestV1 {
+            user: Address::generate(&env),
+            from_token: token,
+            to_chain: symbol_short!("ETH"),
+            to_address: BytesN::from_array(&env, &[1; 32]),
+            amount: 1_000,
+            fee: 20,
+            status: 0,
+            timestamp: 0,
+        };
+
+        // Put the request back the way version 1 stored it
+        env.as_contract(&client.address, || {
+            let mut map = Map::new(&env);
+            map.set(1u64, legacy.clone());
+            env.storage().instance().set(&REQUESTS, &map);
+            env.storage().instance().set(&upgradeable::VERSION, &1u32);
+        });
+        assert_eq!(client.get_bridge_request(&1), None);
+
+        assert_eq!(client.migrate(), 2);
+        let request = client.get_bridge_request(&1).unwrap();
+        assert_eq!(request.user, legacy.user);
+        assert_eq!(request.amount, 1_000);
+        assert_eq!(client.migrate_request(&1), 1);
+        env.as_contract(&client.address, || {
+            assert!(!env.storage().instance().has(&REQUESTS));
+        });
+    }
+
+    #[test]
+    fn test_migrate_moves_the_admin_out_of_the_config() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, admin, _) = setup(&env);
+        let config = client.get_config();
+
+        // Put the configuration back the way version 1 stored it
+        env.as_contract(&client.address, || {
+            let legacy = BridgeConfigV1 {
+                admin: admin.clone(),
+                fee_recipient: config.fee_recipient.clone(),
+                base_fee: config.base_fee,
+                fee_percentage: config.fee_percentage,
+                min_amount: config.min_amount,
+                max_amount: config.max_amount,
+                is_paused: true,
+            };
+            env.storage().instance().set(&CONFIG, &legacy);
+            env.storage().instance().remove(&ownable::ADMIN);
+            env.storage().instance().set(&upgradeable::VERSION, &1u32);
+        });
+
+        // The admin from the old configuration authorizes the migration
+        assert_eq!(client.migrate(), 2);
+        assert_eq!(env.auths()[0].0, admin);
+        assert_eq!(client.get_admin(), admin);
+        assert_eq!(client.get_config(), config);
+        assert!(client.is_paused());
+    }
+
+    #[test]
+    fn test_multi_asset_request_locks_every_asset() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let other = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let user = funded_user(&env, &token, 10_000);
+        StellarAssetClient::new(&env, &other).mint(&user, &10_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let assets = vec![&env, (token.clone(), 1_000i128), (other.clone(), 2_000i128)];
+        let id = client.create_bridge_request_multi(&user, &assets, &eth, &to_address);
+
+        // Base fee once, 1% of each amount
+        let request = client.get_bridge_request(&id).unwrap();
+        assert_eq!(request.assets.len(), 2);
+        assert_eq!(request.assets.get(0).unwrap().fee, 20);
+        assert_eq!(request.assets.get(1).unwrap().fee, 20);
+        assert_eq!(
+            token::Client::new(&env, &token).balance(&user),
+            10_000 - 1_020
+        );
+        assert_eq!(
+            token::Client::new(&env, &other).balance(&user),
+            10_000 - 2_020
+        );
+
+        assert_eq!(client.get_bridge_stats(&eth).total_volume, 3_000);
+        assert_eq!(client.get_token_stats(&other).pending_count, 1);
+        assert_eq!(client.get_user_exposure(&user, &other), 2_000);
+
+        client.update_request_status(&id, &2, &None);
+        assert_eq!(client.get_token_stats(&other).completed_count, 1);
+        assert_eq!(client.get_user_exposure(&user, &other), 0);
+
+        let duplicate = vec![&env, (token.clone(), 1_000i128), (token.clone(), 1_000i128)];
+        let result = client.try_create_bridge_request_multi(&user, &duplicate, &eth, &to_address);
+        assert_eq!(result, Err(Ok(Error::InvalidAssets)));
+        let empty = Vec::new(&env);
+        let result = client.try_create_bridge_request_multi(&user, &empty, &eth, &to_address);
+        assert_eq!(result, Err(Ok(Error::InvalidAssets)));
+    }
+
+    #[test]
+    fn test_fee_tiers_discount_stakers_and_volume() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let staking_id = env.register_contract(None, MockStaking);
+        let staking = MockStakingClient::new(&env, &staking_id);
+        client.set_staking_contract(&Some(staking_id));
+
+        let tiers = vec![
+            &env,
+            FeeTier {
+                min_volume: Some(5_000),
+                min_stake: None,
+                discount_bps: 2_500,
+            },
+            FeeTier {
+                min_volume: None,
+                min_stake: Some(1_000),
+                discount_bps: 5_000,
+            },
+        ];
+        client.set_fee_tiers(&tiers);
+
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        // No tier yet: base fee 10 + 1%
+        let id = client.create_bridge_request(&user, &token, &eth, &to_address, &10_000, &None);
+        assert_eq!(client.get_bridge_request(&id).unwrap().fee, 110);
+        assert_eq!(client.get_user_volume(&user), 10_000);
+
+        // Volume tier: 25% off the percentage fee
+        let id = client.create_bridge_request(&user, &token, &eth, &to_address, &10_000, &None);
+        assert_eq!(client.get_bridge_request(&id).unwrap().fee, 85);
+
+        // Staking tier wins when it is the better discount
+        staking.set_stake(&user, &1_000);
+        assert_eq!(client.get_fee_discount(&user), 5_000);
+        let id = client.create_bridge_request(&user, &token, &eth, &to_address, &10_000, &None);
+        assert_eq!(client.get_bridge_request(&id).unwrap().fee, 60);
+
+        let invalid = vec![
+            &env,
+            FeeTier {
+                min_volume: None,
+                min_stake: None,
+                discount_bps: 100,
+            },
+        ];
+        assert_eq!(
+            client.try_set_fee_tiers(&invalid),
+            Err(Ok(Error::InvalidFeeTier))
+        );
+    }
+
+    #[test]
+    fn test_referrers_get_a_share_of_fees() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        let registry_id = env.register_contract(None, MockReferral);
+        let registry = MockReferralClient::new(&env, &registry_id);
+        client.set_referral_registry(&Some(registry_id.clone()));
+
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+        let partner = symbol_short!("partner");
+
+        // Without a code nothing is shared
+        client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(token_client.balance(&registry_id), 0);
+
+        // fee = 10 + 1% of 9_000 = 100, 20% of which goes to the referrer
+        client.set_referral_code(&user, &Some(partner.clone()));
+        assert_eq!(client.get_referral_code(&user), Some(partner.clone()));
+        client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(token_client.balance(&registry_id), 20);
+        assert_eq!(registry.accrued(&partner, &token), (9_000, 20));
+
+        client.set_referral_code(&user, &Some(symbol_short!("unknown")));
+        client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(token_client.balance(&registry_id), 20);
+    }
+
+    #[test]
+    fn test_fees_are_reported_for_rebates() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        let rebates = MockRebateClient::new(&env, &env.register_contract(None, MockRebate));
+        client.set_rebate_contract(&Some(rebates.address.clone()));
+        assert_eq!(client.get_rebate_contract(), Some(rebates.address.clone()));
+
+        // fee = 10 + 1% of 9_000 = 100
+        client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(rebates.fees(&user, &token), 100);
+
+        client.set_rebate_contract(&None);
+        client.create_bridge_request(&user, &token, &eth, &to_address, &9_000, &None);
+        assert_eq!(rebates.fees(&user, &token), 100);
+    }
+
+    #[test]
+    fn test_failed_requests_can_be_retried_or_refunded() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let token_client = token::Client::new(&env, &token);
+        let user = funded_user(&env, &token, 10_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+        client.set_max_retries(&1);
+
+        let id = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &Some(50));
+        assert_eq!(
+            client.try_retry_request(&id),
+            Err(Ok(Error::RequestNotFailed))
+        );
+
+        client.update_request_status(&id, &3, &None);
+        assert_eq!(client.get_user_exposure(&user, &token), 0);
+
+        // Retrying puts the request back in flight
+        assert_eq!(client.retry_request(&id), 1);
+        let request = client.get_bridge_request(&id).unwrap();
+        assert_eq!(request.status, 1);
+        assert_eq!(client.get_user_exposure(&user, &token), 1_000);
+        assert_eq!(client.get_bridge_stats(&eth).processing_count, 1);
+
+        client.update_request_status(&id, &3, &None);
+        assert_eq!(
+            client.try_retry_request(&id),
+            Err(Ok(Error::RetryLimitReached))
+        );
+
+        // Finalizing refunds the amount and priority fee, keeping the fee
+        client.finalize_failure(&id);
+        assert!(client.is_request_finalized(&id));
+        assert_eq!(token_client.balance(&user), 10_000 - 20);
+        assert_eq!(
+            client.try_finalize_failure(&id),
+            Err(Ok(Error::RequestFinalized))
+        );
+        assert_eq!(
+            client.try_pay_insurance_claim(&id, &user, &token, &10),
+            Err(Ok(Error::RequestFinalized))
+        );
+    }
+
+    #[test]
+    fn test_global_stats_track_value_by_status() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+
+        let eth = symbol_short!("ETH");
+        let polygon = symbol_short!("POLYGON");
+
+        let first = client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+        let second =
+            client.create_bridge_request(&user, &token, &polygon, &to_address, &2_000, &None);
+        client.create_bridge_request(&user, &token, &eth, &to_address, &3_000, &None);
+
+        client.update_request_status(&first, &2, &None);
+        client.update_request_status(&second, &3, &None);
+
+        let stats = client.get_stats();
+        assert_eq!(stats.request_count, 3);
+        assert_eq!(stats.fees_collected, 20 + 30 + 40);
+        assert_eq!((stats.pending_count, stats.pending_value), (1, 3_000));
+        assert_eq!((stats.completed_count, stats.completed_value), (1, 1_000));
+        assert_eq!((stats.failed_count, stats.failed_value), (1, 2_000));
+
+        client.finalize_failure(&second);
+        let stats = client.get_stats();
+        assert_eq!((stats.failed_count, stats.failed_value), (0, 0));
+        assert_eq!((stats.refunded_count, stats.refunded_value), (1, 2_000));
+    }
+
+    #[test]
+    fn test_rolling_volume_resets_after_window() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let (client, _, token) = setup(&env);
+        let user = funded_user(&env, &token, 100_000);
+        let to_address = BytesN::from_array(&env, &[1; 32]);
+        let eth = symbol_short!("ETH");
+
+        client.create_bridge_request(&user, &token, &eth, &to_address, &1_000, &None);
+
+        env.ledger().with_mut(|li| li.timestamp += ROLLING_WINDOW);
+        assert_eq!(client.get_bridge_stats(&eth).rolling_volume, 0);
+
+        client.create_bridge_request(&user, &token, &eth, &to_address, &500, &None);
+        let stats = client.get_bridge_stats(&eth);
+        assert_eq!(stats.rolling_volume, 500);
+        assert_eq!(stats.total_volume, 1_500);
+    }
+
+    #[test]
+    fn test_scenario_opens_and_completes_requests() {
+        let env = Env::default();
+        let scenario = testutils::BridgeScenario::new(&env)
+            .with_request(5_000)
+            .with_completed_request(1_000);
+        let bridge = &scenario.bridge;
+
+        let pending = scenario.request_ids.get(0).unwrap();
+        let completed = scenario.request_ids.get(1).unwrap();
+        assert_eq!(bridge.get_bridge_request(&pending).unwrap().status, 0);
+        assert_eq!(bridge.get_bridge_request(&completed).unwrap().status, 2);
+        assert_eq!(
+            bridge.get_bridge_request(&completed).unwrap().fee,
+            testutils::BridgeScenario::fee(1_000)
+        );
+        assert_eq!(
+            token::Client::new(&env, &scenario.token).balance(&scenario.user),
+            0
+        );
+    }
+}
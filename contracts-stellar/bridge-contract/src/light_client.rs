@@ -0,0 +1,297 @@
+//! Minimal EVM light client used to prove inbound transfers.
+//!
+//! Receipts roots of EVM blocks are attested on-chain; an inbound transfer is
+//! then proven by a Merkle-Patricia proof of the transaction receipt against
+//! that root, followed by decoding the log the EVM bridge contract emitted.
+//! Only hashed trie nodes are supported; inline (<32 byte) child nodes never
+//! occur in receipts tries in practice and are rejected.
+
+use soroban_sdk::{Bytes, BytesN, Env, Vec};
+
+use crate::Error;
+
+/// A decoded EVM log entry
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvmLog {
+    pub address: BytesN<20>,
+    pub topics: Vec<BytesN<32>>,
+    pub data: Bytes,
+}
+
+/// Location of an RLP item's payload inside a buffer
+#[derive(Clone, Copy, Debug)]
+struct Item {
+    start: u32,
+    len: u32,
+    is_list: bool,
+}
+
+impl Item {
+    fn end(&self) -> u32 {
+        self.start + self.len
+    }
+}
+
+/// Verify `proof` against `receipts_root` and return the receipt stored
+/// under transaction index `tx_index`
+pub fn verify_receipt_proof(
+    env: &Env,
+    receipts_root: &BytesN<32>,
+    tx_index: u32,
+    proof: &Vec<Bytes>,
+) -> Result<Bytes, Error> {
+    let (key, key_len) = index_key_nibbles(tx_index);
+    let mut key_pos = 0usize;
+    let mut expected: Bytes = receipts_root.clone().into();
+
+    let last = proof.len().saturating_sub(1);
+    for (depth, node) in proof.iter().enumerate() {
+        let node_hash: Bytes = env.crypto().keccak256(&node).into();
+        if node_hash != expected {
+            return Err(Error::InvalidProof);
+        }
+
+        let (root, next) = decode(&node, 0, node.len())?;
+        if !root.is_list || next != node.len() {
+            return Err(Error::InvalidProof);
+        }
+
+        match count(&node, &root)? {
+            17 => {
+                if key_pos == key_len {
+                    // Value stored directly in the branch
+                    let value = nth(&node, &root, 16)?;
+                    if depth as u32 != last || value.is_list || value.len == 0 {
+                        return Err(Error::InvalidProof);
+                    }
+                    return Ok(node.slice(value.start..value.end()));
+                }
+
+                let child = nth(&node, &root, key[key_pos] as u32)?;
+                key_pos += 1;
+                expected = hash_reference(&node, &child)?;
+            }
+            2 => {
+                let path = nth(&node, &root, 0)?;
+                if path.is_list || path.len == 0 {
+                    return Err(Error::InvalidProof);
+                }
+
+                // Hex-prefix encoding: high nibble of the first byte holds the
+                // leaf flag (2) and odd-length flag (1)
+                let prefix = node.get(path.start).ok_or(Error::InvalidProof)?;
+                let flag = prefix >> 4;
+                let is_leaf = flag & 2 != 0;
+                if flag > 3 {
+                    return Err(Error::InvalidProof);
+                }
+
+                if flag & 1 != 0 {
+                    if key_pos >= key_len || key[key_pos] != prefix & 0x0f {
+                        return Err(Error::InvalidProof);
+                    }
+                    key_pos += 1;
+                }
+                for i in 1..path.len {
+                    let byte = node.get(path.start + i).ok_or(Error::InvalidProof)?;
+                    if key_pos + 2 > key_len
+                        || key[key_pos] != byte >> 4
+                        || key[key_pos + 1] != byte & 0x0f
+                    {
+                        return Err(Error::InvalidProof);
+                    }
+                    key_pos += 2;
+                }
+
+                let value = nth(&node, &root, 1)?;
+                if is_leaf {
+                    if key_pos != key_len || depth as u32 != last || value.is_list {
+                        return Err(Error::InvalidProof);
+                    }
+                    return Ok(node.slice(value.start..value.end()));
+                }
+                expected = hash_reference(&node, &value)?;
+            }
+            _ => return Err(Error::InvalidProof),
+        }
+    }
+
+    Err(Error::InvalidProof)
+}
+
+/// Decode the `log_index`-th log of a successful receipt
+pub fn receipt_log(env: &Env, receipt: &Bytes, log_index: u32) -> Result<EvmLog, Error> {
+    // Typed receipts (EIP-2718) start with a single type byte
+    let first = receipt.get(0).ok_or(Error::InvalidProof)?;
+    let start = if first < 0x80 { 1 } else { 0 };
+
+    let (body, next) = decode(receipt, start, receipt.len())?;
+    if !body.is_list || next != receipt.len() {
+        return Err(Error::InvalidProof);
+    }
+
+    // Post-Byzantium status: 0x01 is success
+    let status = nth(receipt, &body, 0)?;
+    if status.is_list || status.len != 1 || receipt.get(status.start) != Some(1) {
+        return Err(Error::InvalidProof);
+    }
+
+    let logs = nth(receipt, &body, 3)?;
+    if !logs.is_list {
+        return Err(Error::InvalidProof);
+    }
+    let log = nth(receipt, &logs, log_index)?;
+    if !log.is_list || count(receipt, &log)? != 3 {
+        return Err(Error::InvalidProof);
+    }
+
+    let address = nth(receipt, &log, 0)?;
+    if address.is_list || address.len != 20 {
+        return Err(Error::InvalidProof);
+    }
+    let mut address_bytes = [0u8; 20];
+    receipt
+        .slice(address.start..address.end())
+        .copy_into_slice(&mut address_bytes);
+
+    let topic_list = nth(receipt, &log, 1)?;
+    if !topic_list.is_list {
+        return Err(Error::InvalidProof);
+    }
+    let mut topics = Vec::new(env);
+    let mut pos = topic_list.start;
+    while pos < topic_list.end() {
+        let (topic, next) = decode(receipt, pos, topic_list.end())?;
+        if topic.is_list || topic.len != 32 {
+            return Err(Error::InvalidProof);
+        }
+        let mut topic_bytes = [0u8; 32];
+        receipt
+            .slice(topic.start..topic.end())
+            .copy_into_slice(&mut topic_bytes);
+        topics.push_back(BytesN::from_array(env, &topic_bytes));
+        pos = next;
+    }
+
+    let data = nth(receipt, &log, 2)?;
+    if data.is_list {
+        return Err(Error::InvalidProof);
+    }
+
+    Ok(EvmLog {
+        address: BytesN::from_array(env, &address_bytes),
+        topics,
+        data: receipt.slice(data.start..data.end()),
+    })
+}
+
+/// Nibbles of the trie key for a transaction index, i.e. of `rlp(tx_index)`
+fn index_key_nibbles(tx_index: u32) -> ([u8; 10], usize) {
+    let mut encoded = [0u8; 5];
+    let encoded_len = if tx_index == 0 {
+        encoded[0] = 0x80;
+        1
+    } else if tx_index < 0x80 {
+        encoded[0] = tx_index as u8;
+        1
+    } else {
+        let bytes = tx_index.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        let len = 4 - skip;
+        encoded[0] = 0x80 + len as u8;
+        encoded[1..=len].copy_from_slice(&bytes[skip..]);
+        len + 1
+    };
+
+    let mut nibbles = [0u8; 10];
+    for (i, byte) in encoded[..encoded_len].iter().enumerate() {
+        nibbles[2 * i] = byte >> 4;
+        nibbles[2 * i + 1] = byte & 0x0f;
+    }
+    (nibbles, encoded_len * 2)
+}
+
+/// Resolve a child reference to the hash of the next node in the proof
+fn hash_reference(node: &Bytes, child: &Item) -> Result<Bytes, Error> {
+    if child.is_list || child.len != 32 {
+        return Err(Error::InvalidProof);
+    }
+    Ok(node.slice(child.start..child.end()))
+}
+
+/// Decode the RLP item starting at `pos`, returning it and the position of
+/// the following item
+fn decode(data: &Bytes, pos: u32, limit: u32) -> Result<(Item, u32), Error> {
+    let prefix = data.get(pos).ok_or(Error::InvalidProof)?;
+
+    let (start, len, is_list) = match prefix {
+        0x00..=0x7f => (pos, 1, false),
+        0x80..=0xb7 => (pos + 1, (prefix - 0x80) as u32, false),
+        0xb8..=0xbf => {
+            let len_len = (prefix - 0xb7) as u32;
+            (
+                pos + 1 + len_len,
+                read_length(data, pos + 1, len_len)?,
+                false,
+            )
+        }
+        0xc0..=0xf7 => (pos + 1, (prefix - 0xc0) as u32, true),
+        0xf8..=0xff => {
+            let len_len = (prefix - 0xf7) as u32;
+            (
+                pos + 1 + len_len,
+                read_length(data, pos + 1, len_len)?,
+                true,
+            )
+        }
+    };
+
+    let end = start.checked_add(len).ok_or(Error::InvalidProof)?;
+    if end > limit {
+        return Err(Error::InvalidProof);
+    }
+    Ok((
+        Item {
+            start,
+            len,
+            is_list,
+        },
+        end,
+    ))
+}
+
+fn read_length(data: &Bytes, pos: u32, len_len: u32) -> Result<u32, Error> {
+    if len_len > 4 {
+        return Err(Error::InvalidProof);
+    }
+    let mut len = 0u32;
+    for i in 0..len_len {
+        len = (len << 8) | data.get(pos + i).ok_or(Error::InvalidProof)? as u32;
+    }
+    Ok(len)
+}
+
+fn count(data: &Bytes, list: &Item) -> Result<u32, Error> {
+    let mut n = 0;
+    let mut pos = list.start;
+    while pos < list.end() {
+        let (_, next) = decode(data, pos, list.end())?;
+        pos = next;
+        n += 1;
+    }
+    Ok(n)
+}
+
+fn nth(data: &Bytes, list: &Item, index: u32) -> Result<Item, Error> {
+    let mut pos = list.start;
+    let mut i = 0;
+    while pos < list.end() {
+        let (item, next) = decode(data, pos, list.end())?;
+        if i == index {
+            return Ok(item);
+        }
+        pos = next;
+        i += 1;
+    }
+    Err(Error::InvalidProof)
+}
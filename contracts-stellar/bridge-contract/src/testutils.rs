@@ -0,0 +1,108 @@
+//! Scenario builders for tests against the bridge
+//!
+//! Deploys the bridge with a Stellar asset, a user and an approved
+//! relayer, then opens requests on top:
+//!
+//! ```ignore
+//! let scenario = BridgeScenario::new(&env).with_request(5_000).with_completed_request(1_000);
+//! let pending = scenario.request_ids.get(0).unwrap();
+//! ```
+//!
+//! All authorizations are mocked.
+
+use soroban_sdk::{
+    symbol_short, testutils::Address as _, token::StellarAssetClient, Address, BytesN, Env, Symbol,
+    Vec,
+};
+
+use crate::{StellarBridge, StellarBridgeClient};
+
+/// Flat fee charged on every request
+pub const BASE_FEE: i128 = 10;
+/// Fee on the amount, in basis points
+pub const FEE_BPS: u32 = 100;
+pub const MIN_AMOUNT: i128 = 100;
+pub const MAX_AMOUNT: i128 = 1_000_000_000;
+/// Chain the scenario's requests are sent to
+pub const TO_CHAIN: Symbol = symbol_short!("ETH");
+
+/// The bridge deployed with the accounts using it
+pub struct BridgeScenario<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub fee_recipient: Address,
+    pub token: Address,
+    pub user: Address,
+    pub relayer: Address,
+    /// Recipient of the scenario's requests on [`TO_CHAIN`]
+    pub to_address: BytesN<32>,
+    pub bridge: StellarBridgeClient<'a>,
+    /// Requests opened so far, in order
+    pub request_ids: Vec<u64>,
+}
+
+impl BridgeScenario<'_> {
+    pub fn new(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let admin = Address::generate(env);
+        let fee_recipient = Address::generate(env);
+        let bridge = StellarBridgeClient::new(env, &env.register_contract(None, StellarBridge));
+        bridge.initialize(
+            &admin,
+            &fee_recipient,
+            &BASE_FEE,
+            &FEE_BPS,
+            &MIN_AMOUNT,
+            &MAX_AMOUNT,
+        );
+        let relayer = Address::generate(env);
+        bridge.add_relayer(&relayer);
+
+        BridgeScenario {
+            env: env.clone(),
+            admin,
+            fee_recipient,
+            token: env
+                .register_stellar_asset_contract_v2(Address::generate(env))
+                .address(),
+            user: Address::generate(env),
+            relayer,
+            to_address: BytesN::from_array(env, &[7; 32]),
+            bridge,
+            request_ids: Vec::new(env),
+        }
+    }
+
+    /// Fee the bridge charges on a request for `amount`
+    pub fn fee(amount: i128) -> i128 {
+        BASE_FEE + amount * FEE_BPS as i128 / 10_000
+    }
+
+    /// Open a request from the user for `amount`, minting them the amount
+    /// and its fee first
+    pub fn with_request(mut self, amount: i128) -> Self {
+        StellarAssetClient::new(&self.env, &self.token)
+            .mint(&self.user, &(amount + Self::fee(amount)));
+        let id = self.bridge.create_bridge_request(
+            &self.user,
+            &self.token,
+            &TO_CHAIN,
+            &self.to_address,
+            &amount,
+            &None,
+        );
+        self.request_ids.push_back(id);
+        self
+    }
+
+    /// Open a request and have the relayer complete it
+    pub fn with_completed_request(self, amount: i128) -> Self {
+        let scenario = self.with_request(amount);
+        let id = scenario.request_ids.last().unwrap();
+        scenario
+            .bridge
+            .complete_request(&scenario.relayer, &id, &None);
+        scenario
+    }
+}
@@ -0,0 +1,252 @@
+//! Admin operations on a deployed suite
+//!
+//! Each subcommand maps to one contract call made by the signing account,
+//! which must be the contract's admin, or a guardian committee member for
+//! an emergency pause. Contracts are found by their name in the deployment
+//! manifest.
+
+use anyhow::Result;
+use clap::{Subcommand, ValueEnum};
+use soroban_sdk::xdr::{ScAddress, ScSymbol, ScVal};
+
+use crate::deploy::{address, string, symbol};
+use crate::manifest::Manifest;
+
+#[derive(Debug, Subcommand)]
+pub enum Admin {
+    /// Register a DEX with the router
+    RegisterDex {
+        /// Id the router quotes the DEX under
+        name: String,
+        /// DEX or adapter contract
+        address: String,
+        #[arg(long, default_value = "liquidity_pool")]
+        dex_type: String,
+        #[arg(long)]
+        fee_bps: u32,
+    },
+    /// Change the bridge fees and limits; options left out are unchanged
+    SetFees {
+        #[arg(long)]
+        fee_recipient: Option<String>,
+        #[arg(long)]
+        base_fee: Option<i128>,
+        /// Percentage fee, in basis points
+        #[arg(long)]
+        fee_percentage: Option<u32>,
+        #[arg(long)]
+        min_amount: Option<i128>,
+        #[arg(long)]
+        max_amount: Option<i128>,
+    },
+    /// Pause a contract
+    Pause { contract: Pausable },
+    /// Resume a paused contract
+    Unpause { contract: Pausable },
+    /// Halt every contract following the guardian
+    EmergencyPause {
+        /// Short reason recorded with the pause, up to 32 characters
+        reason: String,
+    },
+    /// Lift the guardian's emergency pause
+    LiftEmergency,
+    /// Allow an account to submit prices to the oracle
+    AddOracle { address: String },
+    /// Record a new deployment of a contract in the registry
+    Register {
+        /// Name the contract is resolved by, e.g. `bridge`
+        name: String,
+        address: String,
+    },
+}
+
+/// Contracts with a pause switch, by manifest name
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Pausable {
+    Bridge,
+    Escrow,
+    Router,
+    PriceOracle,
+    LiquidityPool,
+    EscrowFactory,
+}
+
+impl Pausable {
+    fn name(self) -> &'static str {
+        match self {
+            Pausable::Bridge => "bridge",
+            Pausable::Escrow => "escrow",
+            Pausable::Router => "router",
+            Pausable::PriceOracle => "price_oracle",
+            Pausable::LiquidityPool => "liquidity_pool",
+            Pausable::EscrowFactory => "escrow_factory",
+        }
+    }
+}
+
+/// A contract call: contract id, function and arguments
+#[derive(Debug, PartialEq)]
+pub struct Call {
+    pub contract: String,
+    pub function: &'static str,
+    pub args: Vec<ScVal>,
+}
+
+impl Admin {
+    pub fn call(&self, manifest: &Manifest, caller: &ScAddress) -> Result<Call> {
+        let call = |name: &str, function, args| -> Result<Call> {
+            Ok(Call {
+                contract: manifest.contract(name)?.into(),
+                function,
+                args,
+            })
+        };
+
+        match self {
+            Admin::RegisterDex {
+                name,
+                address: dex,
+                dex_type,
+                fee_bps,
+            } => call(
+                "router",
+                "register_dex",
+                vec![
+                    string(name)?,
+                    address(dex)?,
+                    string(dex_type)?,
+                    (*fee_bps).into(),
+                ],
+            ),
+            Admin::SetFees {
+                fee_recipient,
+                base_fee,
+                fee_percentage,
+                min_amount,
+                max_amount,
+            } => call(
+                "bridge",
+                "update_config",
+                vec![
+                    fee_recipient
+                        .as_deref()
+                        .map(address)
+                        .transpose()?
+                        .unwrap_or(ScVal::Void),
+                    (*base_fee).into(),
+                    (*fee_percentage).into(),
+                    (*min_amount).into(),
+                    (*max_amount).into(),
+                ],
+            ),
+            Admin::Pause { contract } => call(
+                contract.name(),
+                "pause",
+                vec![ScVal::Address(caller.clone())],
+            ),
+            Admin::Unpause { contract } => call(contract.name(), "unpause", vec![]),
+            Admin::EmergencyPause { reason } => call(
+                "guardian",
+                "emergency_pause",
+                vec![
+                    ScVal::Address(caller.clone()),
+                    ScVal::Symbol(ScSymbol(reason.as_str().try_into()?)),
+                ],
+            ),
+            Admin::LiftEmergency => call("guardian", "unpause", vec![]),
+            Admin::AddOracle { address: oracle } => {
+                call("price_oracle", "add_oracle", vec![address(oracle)?])
+            }
+            Admin::Register {
+                name,
+                address: contract,
+            } => call(
+                "registry",
+                "register",
+                vec![symbol(name)?, address(contract)?],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stellhydra_rpc::parse_address;
+
+    const ADMIN: &str = "GBXPKLRTMHH3NWEE32YSLZMRSBBQ6ITJCME7FK3P5SB7XEKRNJN2F7IS";
+    const BRIDGE: &str = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC";
+
+    #[test]
+    fn test_maps_commands_to_calls() {
+        let mut manifest = Manifest::default();
+        manifest.contracts.insert("bridge".into(), BRIDGE.into());
+        let admin = parse_address(ADMIN).unwrap();
+
+        let set_fees = Admin::SetFees {
+            fee_recipient: None,
+            base_fee: Some(50),
+            fee_percentage: None,
+            min_amount: None,
+            max_amount: Some(1_000),
+        };
+        assert_eq!(
+            set_fees.call(&manifest, &admin).unwrap(),
+            Call {
+                contract: BRIDGE.into(),
+                function: "update_config",
+                args: vec![
+                    ScVal::Void,
+                    50i128.into(),
+                    ScVal::Void,
+                    ScVal::Void,
+                    1_000i128.into()
+                ],
+            }
+        );
+
+        let pause = Admin::Pause {
+            contract: Pausable::Bridge,
+        };
+        assert_eq!(
+            pause.call(&manifest, &admin).unwrap().args,
+            vec![ScVal::Address(admin.clone())]
+        );
+
+        manifest.contracts.insert("guardian".into(), BRIDGE.into());
+        let emergency = Admin::EmergencyPause {
+            reason: "exploit".into(),
+        };
+        assert_eq!(
+            emergency.call(&manifest, &admin).unwrap().args,
+            vec![
+                ScVal::Address(admin.clone()),
+                ScVal::Symbol(ScSymbol("exploit".try_into().unwrap()))
+            ]
+        );
+        let too_long = Admin::EmergencyPause {
+            reason: "x".repeat(33),
+        };
+        assert!(too_long.call(&manifest, &admin).is_err());
+
+        manifest.contracts.insert("registry".into(), BRIDGE.into());
+        let register = Admin::Register {
+            name: "bridge".into(),
+            address: BRIDGE.into(),
+        };
+        assert_eq!(
+            register.call(&manifest, &admin).unwrap().args,
+            vec![
+                ScVal::Symbol(ScSymbol("bridge".try_into().unwrap())),
+                address(BRIDGE).unwrap()
+            ]
+        );
+
+        // Nothing to call without the contract in the manifest
+        let pause = Admin::Pause {
+            contract: Pausable::Router,
+        };
+        assert!(pause.call(&manifest, &admin).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! What the CLI needs from the network, so deployments can be checked
+//! against a recording stand-in
+
+use anyhow::Result;
+use soroban_sdk::xdr::{ScAddress, ScVal};
+use stellhydra_rpc::Client;
+
+pub trait Chain {
+    /// The signing account, admin of everything it deploys
+    fn account(&self) -> Result<ScAddress>;
+
+    async fn upload_wasm(&self, wasm: &[u8]) -> Result<[u8; 32]>;
+
+    async fn deploy(&self, wasm_hash: &[u8; 32], salt: &[u8; 32]) -> Result<ScAddress>;
+
+    /// Invoke a contract function, returning the transaction hash
+    async fn invoke(&self, contract: &[u8; 32], function: &str, args: Vec<ScVal>)
+        -> Result<String>;
+}
+
+impl Chain for Client {
+    fn account(&self) -> Result<ScAddress> {
+        Client::account(self)
+    }
+
+    async fn upload_wasm(&self, wasm: &[u8]) -> Result<[u8; 32]> {
+        Client::upload_wasm(self, wasm).await
+    }
+
+    async fn deploy(&self, wasm_hash: &[u8; 32], salt: &[u8; 32]) -> Result<ScAddress> {
+        Client::deploy(self, wasm_hash, salt).await
+    }
+
+    async fn invoke(
+        &self,
+        contract: &[u8; 32],
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<String> {
+        Ok(Client::invoke(self, contract, function, args).await?.hash)
+    }
+}
@@ -0,0 +1,587 @@
+//! Deployment of the contract suite
+//!
+//! Contracts go out in dependency order, each initialized right after it
+//! is deployed and wired to the ones before it: the router reads the
+//! oracle, the factories deploy uploaded wasm, the token factory mints
+//! for the bridge and the pool is registered with the router. With a
+//! guardian committee configured, the guardian goes out next and every
+//! pausable contract follows it. The registry goes out last, with every
+//! contract registered under its manifest name. Every step is recorded in
+//! the manifest and skipped when it is already there.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{ScAddress, ScBytes, ScString, ScSymbol, ScVal, ScVec};
+use stellhydra_rpc::{address_string, parse_address, parse_contract};
+
+use crate::chain::Chain;
+use crate::manifest::Manifest;
+
+/// Parameters of a deployment, read from a TOML file
+#[derive(Debug, Deserialize)]
+pub struct DeployConfig {
+    pub bridge: BridgeConfig,
+    #[serde(default)]
+    pub oracle: OracleConfig,
+    /// Pool to deploy and register with the router, if any
+    pub pool: Option<PoolConfig>,
+    /// Emergency pause committee, if any
+    pub guardian: Option<GuardianConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BridgeConfig {
+    /// Receives the bridge fees; the deployer by default
+    pub fee_recipient: Option<String>,
+    pub base_fee: i64,
+    /// Percentage fee, in basis points
+    pub fee_percentage: u32,
+    pub min_amount: i64,
+    pub max_amount: i64,
+    /// Accounts allowed to complete requests
+    #[serde(default)]
+    pub relayers: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OracleConfig {
+    /// Accounts allowed to submit prices
+    #[serde(default)]
+    pub feeders: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PoolConfig {
+    pub token_a: String,
+    pub token_b: String,
+    pub fee_bps: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GuardianConfig {
+    /// Accounts allowed to pause the whole suite
+    pub members: Vec<String>,
+}
+
+/// Contracts following the guardian, when deployed
+const GUARDED: [&str; 6] = [
+    "price_oracle",
+    "router",
+    "escrow",
+    "bridge",
+    "escrow_factory",
+    "liquidity_pool",
+];
+
+/// Deploy the suite, resuming from whatever the manifest already holds
+pub async fn deploy<C: Chain>(
+    chain: &C,
+    config: &DeployConfig,
+    wasm_dir: &Path,
+    manifest: &mut Manifest,
+    manifest_path: &Path,
+) -> Result<()> {
+    let mut deployer = Deployer {
+        chain,
+        wasm_dir,
+        manifest,
+        manifest_path,
+    };
+    let admin = ScVal::Address(chain.account()?);
+
+    let oracle = deployer
+        .contract("price_oracle", "stellhydra_price_oracle")
+        .await?;
+    deployer
+        .call(
+            "price_oracle.initialize",
+            &oracle,
+            "initialize",
+            vec![admin.clone()],
+        )
+        .await?;
+    for feeder in &config.oracle.feeders {
+        deployer
+            .call(
+                &format!("price_oracle.add_oracle({feeder})"),
+                &oracle,
+                "add_oracle",
+                vec![address(feeder)?],
+            )
+            .await?;
+    }
+
+    let router = deployer.contract("router", "stellhydra_router").await?;
+    deployer
+        .call(
+            "router.initialize",
+            &router,
+            "initialize",
+            vec![admin.clone(), ScVal::Address(oracle)],
+        )
+        .await?;
+
+    let escrow = deployer.contract("escrow", "stellar_eth_escrow").await?;
+    deployer
+        .call(
+            "escrow.initialize",
+            &escrow,
+            "initialize",
+            vec![admin.clone()],
+        )
+        .await?;
+
+    let bridge = deployer.contract("bridge", "stellhydra_bridge").await?;
+    let fee_recipient = match &config.bridge.fee_recipient {
+        Some(recipient) => address(recipient)?,
+        None => admin.clone(),
+    };
+    deployer
+        .call(
+            "bridge.initialize",
+            &bridge,
+            "initialize",
+            vec![
+                admin.clone(),
+                fee_recipient,
+                i128::from(config.bridge.base_fee).into(),
+                config.bridge.fee_percentage.into(),
+                i128::from(config.bridge.min_amount).into(),
+                i128::from(config.bridge.max_amount).into(),
+            ],
+        )
+        .await?;
+    for relayer in &config.bridge.relayers {
+        deployer
+            .call(
+                &format!("bridge.add_relayer({relayer})"),
+                &bridge,
+                "add_relayer",
+                vec![address(relayer)?],
+            )
+            .await?;
+    }
+
+    // Per-swap escrows are deployed by the factory, never directly
+    let instance = deployer
+        .upload("escrow_instance", "stellhydra_escrow_instance")
+        .await?;
+    let escrow_factory = deployer
+        .contract("escrow_factory", "stellhydra_escrow_factory")
+        .await?;
+    deployer
+        .call(
+            "escrow_factory.initialize",
+            &escrow_factory,
+            "initialize",
+            vec![admin.clone(), bytes(&instance)?],
+        )
+        .await?;
+
+    let wrapped_token = deployer
+        .upload("wrapped_token", "stellhydra_wrapped_token")
+        .await?;
+    let token_factory = deployer
+        .contract("token_factory", "stellhydra_token_factory")
+        .await?;
+    deployer
+        .call(
+            "token_factory.initialize",
+            &token_factory,
+            "initialize",
+            vec![
+                admin.clone(),
+                ScVal::Address(bridge),
+                bytes(&wrapped_token)?,
+            ],
+        )
+        .await?;
+
+    if let Some(pool_config) = &config.pool {
+        let pool = deployer
+            .contract("liquidity_pool", "stellhydra_liquidity_pool")
+            .await?;
+        deployer
+            .call(
+                "liquidity_pool.initialize",
+                &pool,
+                "initialize",
+                vec![
+                    admin.clone(),
+                    address(&pool_config.token_a)?,
+                    address(&pool_config.token_b)?,
+                    pool_config.fee_bps.into(),
+                ],
+            )
+            .await?;
+        deployer
+            .call(
+                "router.register_dex(liquidity_pool)",
+                &router,
+                "register_dex",
+                vec![
+                    string("liquidity_pool")?,
+                    ScVal::Address(pool),
+                    string("liquidity_pool")?,
+                    pool_config.fee_bps.into(),
+                ],
+            )
+            .await?;
+    }
+
+    if let Some(guardian_config) = &config.guardian {
+        let guardian = deployer.contract("guardian", "stellhydra_guardian").await?;
+        let members = guardian_config
+            .members
+            .iter()
+            .map(|member| address(member))
+            .collect::<Result<Vec<_>>>()?;
+        deployer
+            .call(
+                "guardian.initialize",
+                &guardian,
+                "initialize",
+                vec![admin.clone(), ScVal::Vec(Some(ScVec(members.try_into()?)))],
+            )
+            .await?;
+        for name in GUARDED {
+            let Some(id) = deployer.manifest.contracts.get(name) else {
+                continue;
+            };
+            let contract = parse_address(id)?;
+            deployer
+                .call(
+                    &format!("{name}.set_guardian"),
+                    &contract,
+                    "set_guardian",
+                    vec![ScVal::Address(guardian.clone())],
+                )
+                .await?;
+        }
+    }
+
+    // Contracts and off-chain tools look the others up here
+    let registry = deployer.contract("registry", "stellhydra_registry").await?;
+    deployer
+        .call(
+            "registry.initialize",
+            &registry,
+            "initialize",
+            vec![admin.clone(), symbol(&deployer.manifest.network)?],
+        )
+        .await?;
+    let contracts: Vec<(String, String)> = deployer
+        .manifest
+        .contracts
+        .iter()
+        .map(|(name, id)| (name.clone(), id.clone()))
+        .collect();
+    for (name, id) in contracts {
+        deployer
+            .call(
+                &format!("registry.register({name})"),
+                &registry,
+                "register",
+                vec![symbol(&name)?, address(&id)?],
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+struct Deployer<'a, C> {
+    chain: &'a C,
+    wasm_dir: &'a Path,
+    manifest: &'a mut Manifest,
+    manifest_path: &'a Path,
+}
+
+impl<C: Chain> Deployer<'_, C> {
+    /// Upload a contract's wasm, returning its hash
+    async fn upload(&mut self, name: &str, wasm: &str) -> Result<[u8; 32]> {
+        if let Some(hash) = self.manifest.wasm.get(name) {
+            return hex::decode(hash)?
+                .try_into()
+                .map_err(|_| anyhow!("Invalid wasm hash for {name}"));
+        }
+
+        let path = self.wasm_dir.join(format!("{wasm}.wasm"));
+        let code =
+            std::fs::read(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+        let hash = self.chain.upload_wasm(&code).await?;
+        println!("Uploaded {name}: {}", hex::encode(hash));
+
+        self.manifest.wasm.insert(name.into(), hex::encode(hash));
+        self.manifest.save(self.manifest_path)?;
+        Ok(hash)
+    }
+
+    /// Upload and deploy a contract, returning its address
+    async fn contract(&mut self, name: &str, wasm: &str) -> Result<ScAddress> {
+        if let Some(id) = self.manifest.contracts.get(name) {
+            return parse_address(id);
+        }
+
+        let hash = self.upload(name, wasm).await?;
+        let salt: [u8; 32] = Sha256::digest(format!("{name}:{}", self.manifest.started_at)).into();
+        let address = self.chain.deploy(&hash, &salt).await?;
+        println!("Deployed {name}: {}", address_string(&address));
+
+        self.manifest
+            .contracts
+            .insert(name.into(), address_string(&address));
+        self.manifest.save(self.manifest_path)?;
+        Ok(address)
+    }
+
+    /// Make a setup call, once; `step` names it in the manifest
+    async fn call(
+        &mut self,
+        step: &str,
+        contract: &ScAddress,
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<()> {
+        if self.manifest.done.iter().any(|done| done == step) {
+            return Ok(());
+        }
+
+        let contract = parse_contract(&address_string(contract))?;
+        let hash = self.chain.invoke(&contract, function, args).await?;
+        println!("{step}: {hash}");
+
+        self.manifest.done.push(step.into());
+        self.manifest.save(self.manifest_path)
+    }
+}
+
+pub fn address(address: &str) -> Result<ScVal> {
+    Ok(ScVal::Address(parse_address(address)?))
+}
+
+pub fn string(value: &str) -> Result<ScVal> {
+    Ok(ScVal::String(ScString(value.try_into()?)))
+}
+
+pub fn symbol(value: &str) -> Result<ScVal> {
+    Ok(ScVal::Symbol(ScSymbol(value.try_into()?)))
+}
+
+fn bytes(value: &[u8]) -> Result<ScVal> {
+    Ok(ScVal::Bytes(ScBytes(value.try_into()?)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+
+    use soroban_sdk::xdr::Hash;
+
+    use super::*;
+
+    const DEPLOYER: &str = "GBXPKLRTMHH3NWEE32YSLZMRSBBQ6ITJCME7FK3P5SB7XEKRNJN2F7IS";
+    const TOKEN_A: &str = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC";
+
+    /// Records what a deployment does; contract ids and wasm hashes are
+    /// hashes of their inputs
+    #[derive(Default)]
+    struct Recorder {
+        deployed: RefCell<usize>,
+        calls: RefCell<Vec<(String, String, Vec<ScVal>)>>,
+    }
+
+    impl Chain for Recorder {
+        fn account(&self) -> Result<ScAddress> {
+            parse_address(DEPLOYER)
+        }
+
+        async fn upload_wasm(&self, wasm: &[u8]) -> Result<[u8; 32]> {
+            Ok(Sha256::digest(wasm).into())
+        }
+
+        async fn deploy(&self, _wasm_hash: &[u8; 32], salt: &[u8; 32]) -> Result<ScAddress> {
+            *self.deployed.borrow_mut() += 1;
+            Ok(ScAddress::Contract(Hash(Sha256::digest(salt).into())))
+        }
+
+        async fn invoke(
+            &self,
+            contract: &[u8; 32],
+            function: &str,
+            args: Vec<ScVal>,
+        ) -> Result<String> {
+            let contract = address_string(&ScAddress::Contract(Hash(*contract)));
+            self.calls
+                .borrow_mut()
+                .push((contract, function.into(), args));
+            Ok("00".repeat(32))
+        }
+    }
+
+    fn workspace(name: &str) -> (PathBuf, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("stellhydra-cli-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for wasm in [
+            "stellhydra_price_oracle",
+            "stellhydra_router",
+            "stellar_eth_escrow",
+            "stellhydra_bridge",
+            "stellhydra_escrow_instance",
+            "stellhydra_escrow_factory",
+            "stellhydra_wrapped_token",
+            "stellhydra_token_factory",
+            "stellhydra_liquidity_pool",
+            "stellhydra_guardian",
+            "stellhydra_registry",
+        ] {
+            std::fs::write(dir.join(format!("{wasm}.wasm")), wasm).unwrap();
+        }
+        let manifest = dir.join("manifest.json");
+        (dir, manifest)
+    }
+
+    fn config() -> DeployConfig {
+        toml::from_str(&format!(
+            r#"
+            [bridge]
+            base_fee = 100
+            fee_percentage = 30
+            min_amount = 1000
+            max_amount = 1000000000
+            relayers = ["{DEPLOYER}"]
+
+            [pool]
+            token_a = "{TOKEN_A}"
+            token_b = "{TOKEN_A}"
+            fee_bps = 30
+
+            [guardian]
+            members = ["{DEPLOYER}"]
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_deploys_and_wires_the_suite() {
+        let (dir, path) = workspace("wiring");
+        let chain = Recorder::default();
+        let mut manifest = Manifest::default();
+
+        deploy(&chain, &config(), &dir, &mut manifest, &path)
+            .await
+            .unwrap();
+
+        let contract = |name: &str| manifest.contracts[name].clone();
+        let calls = chain.calls.borrow();
+        let call = |contract: String, function: &str| {
+            calls
+                .iter()
+                .find(|(id, name, _)| *id == contract && name == function)
+                .map(|(_, _, args)| args.clone())
+                .unwrap()
+        };
+
+        assert_eq!(manifest.contracts.len(), 9);
+        assert_eq!(
+            call(contract("router"), "initialize")[1],
+            address(&contract("price_oracle")).unwrap()
+        );
+        assert_eq!(
+            call(contract("escrow_factory"), "initialize")[1],
+            ScVal::try_from(hex::decode(&manifest.wasm["escrow_instance"]).unwrap()).unwrap()
+        );
+        assert_eq!(
+            call(contract("token_factory"), "initialize")[1],
+            address(&contract("bridge")).unwrap()
+        );
+        assert_eq!(
+            call(contract("router"), "register_dex")[1],
+            address(&contract("liquidity_pool")).unwrap()
+        );
+        assert_eq!(
+            call(contract("bridge"), "add_relayer"),
+            vec![address(DEPLOYER).unwrap()]
+        );
+
+        assert_eq!(
+            call(contract("guardian"), "initialize")[1],
+            ScVal::Vec(Some(ScVec(
+                vec![address(DEPLOYER).unwrap()].try_into().unwrap()
+            )))
+        );
+        for name in GUARDED {
+            assert_eq!(
+                call(contract(name), "set_guardian"),
+                vec![address(&contract("guardian")).unwrap()]
+            );
+        }
+
+        // Every contract, the registry included, is registered by name
+        let registered: Vec<_> = calls
+            .iter()
+            .filter(|(id, function, _)| *id == contract("registry") && function == "register")
+            .map(|(_, _, args)| args.clone())
+            .collect();
+        assert_eq!(registered.len(), manifest.contracts.len());
+        assert!(registered.contains(&vec![
+            symbol("bridge").unwrap(),
+            address(&contract("bridge")).unwrap()
+        ]));
+
+        // The router can only be wired once the oracle is up
+        let position = |contract: String| calls.iter().position(|(id, _, _)| *id == contract);
+        assert!(position(contract("price_oracle")) < position(contract("router")));
+
+        // What was written is what a resumed deployment reads
+        assert_eq!(Manifest::load(&path).unwrap(), Some(manifest.clone()));
+    }
+
+    #[tokio::test]
+    async fn test_resumes_from_the_manifest() {
+        let (dir, path) = workspace("resume");
+        let chain = Recorder::default();
+        let mut manifest = Manifest::default();
+        deploy(&chain, &config(), &dir, &mut manifest, &path)
+            .await
+            .unwrap();
+
+        // Interrupted after the bridge was deployed, before it was set up
+        let mut interrupted = manifest.clone();
+        for contract in [
+            "escrow_factory",
+            "token_factory",
+            "liquidity_pool",
+            "guardian",
+        ] {
+            interrupted.contracts.remove(contract);
+        }
+        let bridge_setup = interrupted
+            .done
+            .iter()
+            .position(|step| step == "bridge.initialize")
+            .unwrap();
+        interrupted.done.truncate(bridge_setup);
+
+        let resumed = Recorder::default();
+        deploy(&resumed, &config(), &dir, &mut interrupted, &path)
+            .await
+            .unwrap();
+
+        assert_eq!(*resumed.deployed.borrow(), 4);
+        assert_eq!(interrupted, manifest);
+        assert_eq!(
+            resumed.calls.borrow()[0].1,
+            "initialize",
+            "picks up at the bridge's setup"
+        );
+        assert_eq!(resumed.calls.borrow()[0].0, manifest.contracts["bridge"]);
+    }
+}
@@ -0,0 +1,142 @@
+//! StellHydra deployment and admin CLI
+//!
+//! `stellhydra deploy` puts the contract suite on a network and writes a
+//! manifest of what went where; the other subcommands run admin
+//! operations against the contracts the manifest names. Network settings
+//! come from `config/<network>.toml`, and the signing key from the
+//! environment.
+//!
+//! ```text
+//! stellhydra --network testnet deploy --config deploy.toml
+//! stellhydra --network testnet pause bridge
+//! ```
+
+mod admin;
+mod chain;
+mod deploy;
+mod manifest;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use stellhydra_rpc::{address_string, parse_contract, Client};
+
+use crate::admin::Admin;
+use crate::deploy::DeployConfig;
+use crate::manifest::Manifest;
+
+#[derive(Debug, Parser)]
+#[command(name = "stellhydra", version, about)]
+struct Cli {
+    /// Network to use, read from `<config-dir>/<network>.toml`
+    #[arg(long, default_value = "testnet", global = true)]
+    network: String,
+    #[arg(long, default_value = "config", global = true)]
+    config_dir: PathBuf,
+    /// Deployment manifest; `deployments/<network>.json` by default
+    #[arg(long, global = true)]
+    manifest: Option<PathBuf>,
+    /// Secret key (S...) of the deployer and admin
+    #[arg(long, env = "STELLAR_SECRET_KEY", hide_env_values = true)]
+    secret_key: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Deploy and initialize the contract suite, resuming an unfinished
+    /// deployment
+    Deploy {
+        /// Deployment parameters
+        #[arg(long, default_value = "deploy.toml")]
+        config: PathBuf,
+        /// Directory holding the built contracts
+        #[arg(long, default_value = "target/wasm32-unknown-unknown/release")]
+        wasm_dir: PathBuf,
+    },
+    #[command(flatten)]
+    Admin(Admin),
+}
+
+/// The parts of a network config the CLI uses
+#[derive(Debug, Deserialize)]
+struct Network {
+    rpc_url: String,
+    network_passphrase: String,
+    /// Inclusion fee per transaction, in stroops
+    fee_rate: u32,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let path = cli.config_dir.join(format!("{}.toml", cli.network));
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let network: Network =
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))?;
+    let client = Client::new(
+        &network.rpc_url,
+        &network.network_passphrase,
+        network.fee_rate,
+    )
+    .with_secret_key(&cli.secret_key)?;
+
+    let manifest_path = cli
+        .manifest
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("deployments/{}.json", cli.network)));
+    let account = address_string(&client.account()?);
+
+    match &cli.command {
+        Command::Deploy { config, wasm_dir } => {
+            let text = std::fs::read_to_string(config)
+                .with_context(|| format!("Cannot read {}", config.display()))?;
+            let config: DeployConfig = toml::from_str(&text)
+                .with_context(|| format!("Invalid config {}", config.display()))?;
+
+            let mut manifest = match Manifest::load(&manifest_path)? {
+                Some(manifest) if manifest.network != cli.network => {
+                    bail!(
+                        "{} is a {} manifest",
+                        manifest_path.display(),
+                        manifest.network
+                    )
+                }
+                Some(manifest) if manifest.deployer != account => {
+                    bail!(
+                        "{} was deployed by {}",
+                        manifest_path.display(),
+                        manifest.deployer
+                    )
+                }
+                Some(manifest) => manifest,
+                None => Manifest {
+                    network: cli.network.clone(),
+                    deployer: account,
+                    started_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                    ..Manifest::default()
+                },
+            };
+
+            deploy::deploy(&client, &config, wasm_dir, &mut manifest, &manifest_path).await?;
+            println!("Manifest written to {}", manifest_path.display());
+        }
+        Command::Admin(admin) => {
+            let manifest = Manifest::load(&manifest_path)?
+                .with_context(|| format!("No manifest at {}", manifest_path.display()))?;
+            let call = admin.call(&manifest, &client.account()?)?;
+
+            let submitted = client
+                .invoke(&parse_contract(&call.contract)?, call.function, call.args)
+                .await?;
+            println!("{}: {}", call.function, submitted.hash);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Deployment manifest: what was deployed where, in JSON for other tools
+//!
+//! The manifest is written after every step of a deployment, so a
+//! deployment that stops halfway resumes from it instead of starting over.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub network: String,
+    /// Account that deployed the contracts, and their admin
+    pub deployer: String,
+    /// Unix time the deployment started; salts the contract ids
+    pub started_at: u64,
+    /// Uploaded wasm hashes (hex) by contract name
+    pub wasm: BTreeMap<String, String>,
+    /// Contract ids by name
+    pub contracts: BTreeMap<String, String>,
+    /// Setup calls made, in order
+    pub done: Vec<String>,
+}
+
+impl Manifest {
+    /// Load a manifest, or `None` if there is none yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Invalid manifest {}", path.display()))
+                .map(Some),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(path, json).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    pub fn contract(&self, name: &str) -> Result<&str> {
+        self.contracts
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("No {name} contract in the manifest"))
+    }
+}
@@ -0,0 +1,480 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, BytesN, Env, Symbol,
+};
+
+/// Registry of the escrow factory, telling whether an order's escrow exists
+#[contractclient(name = "EscrowFactoryClient")]
+pub trait EscrowFactoryInterface {
+    fn get_escrow(env: Env, order_hash: BytesN<32>) -> Option<Address>;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuctionConfig {
+    pub escrow_factory: Address,
+    pub bond_token: Address,
+    pub bond_amount: i128,
+    pub exclusivity_period: u64, // seconds the winner has to create the escrow
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuctionStatus {
+    Open,
+    Accepted,
+    Filled,
+    Cancelled,
+}
+
+/// Cross-chain order whose price decays from `start_amount` to
+/// `end_amount` over the auction
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Auction {
+    pub id: u64,
+    pub maker: Address,
+    pub order_hash: BytesN<32>,
+    pub dest_chain: Symbol,
+    pub start_amount: i128, // amount the resolver delivers on the destination
+    pub end_amount: i128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub status: AuctionStatus,
+    pub resolver: Option<Address>,
+    pub accepted_amount: i128,
+    pub bond: i128,
+    pub deadline: u64, // for the escrow, once accepted
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidConfig = 3,
+    AuctionNotFound = 4,
+    InvalidStatus = 5,
+    AuctionNotActive = 6,
+    EscrowMissing = 7,
+    DeadlineNotReached = 8,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const CONFIG: Symbol = symbol_short!("CONFIG");
+const COUNT: Symbol = symbol_short!("COUNT");
+const AUCTION: Symbol = symbol_short!("AUCTION");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Price discovery for cross-chain orders
+///
+/// Makers post orders with a decaying price; the first resolver to
+/// accept the current price posts a bond and gets exclusivity to create
+/// the order's escrow. The bond comes back once the escrow exists, and
+/// goes to the maker if the resolver lets the deadline pass.
+#[contract]
+pub struct DutchAuction;
+
+#[contractimpl]
+impl DutchAuction {
+    pub fn initialize(env: Env, admin: Address, config: AuctionConfig) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        if config.bond_amount < 0 {
+            return Err(Error::InvalidConfig);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&CONFIG, &config);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Update the auction parameters (only admin)
+    ///
+    /// Bonds already posted are returned or slashed at the amount taken;
+    /// the bond token must stay the same while any are outstanding.
+    pub fn set_config(env: Env, config: AuctionConfig) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        if config.bond_amount < 0 {
+            return Err(Error::InvalidConfig);
+        }
+
+        env.storage().instance().set(&CONFIG, &config);
+        Ok(())
+    }
+
+    pub fn get_config(env: Env) -> Result<AuctionConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Post an order for auction, starting now
+    pub fn post_order(
+        env: Env,
+        maker: Address,
+        order_hash: BytesN<32>,
+        dest_chain: Symbol,
+        start_amount: i128,
+        end_amount: i128,
+        duration: u64,
+    ) -> Result<u64, Error> {
+        maker.require_auth();
+        if end_amount <= 0 || start_amount < end_amount || duration == 0 {
+            return Err(Error::InvalidConfig);
+        }
+
+        let id: u64 = env.storage().instance().get(&COUNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&COUNT, &id);
+        Self::extend_instance(&env);
+
+        let now = env.ledger().timestamp();
+        let auction = Auction {
+            id,
+            maker: maker.clone(),
+            order_hash: order_hash.clone(),
+            dest_chain,
+            start_amount,
+            end_amount,
+            start_time: now,
+            end_time: now + duration,
+            status: AuctionStatus::Open,
+            resolver: None,
+            accepted_amount: 0,
+            bond: 0,
+            deadline: 0,
+        };
+        Self::save_auction(&env, &auction);
+
+        env.events().publish(
+            (symbol_short!("AUCTION"), symbol_short!("POSTED"), id),
+            (maker, order_hash, start_amount, end_amount),
+        );
+        Ok(id)
+    }
+
+    /// Price of an auction at the current time
+    pub fn current_amount(env: Env, id: u64) -> Result<i128, Error> {
+        let auction = Self::get_auction(env.clone(), id)?;
+        Ok(Self::amount_at(&auction, env.ledger().timestamp()))
+    }
+
+    /// Accept an open auction at the current price, posting the bond
+    pub fn accept(env: Env, resolver: Address, id: u64) -> Result<i128, Error> {
+        resolver.require_auth();
+
+        let mut auction = Self::get_auction(env.clone(), id)?;
+        if auction.status != AuctionStatus::Open {
+            return Err(Error::InvalidStatus);
+        }
+        let now = env.ledger().timestamp();
+        if now > auction.end_time {
+            return Err(Error::AuctionNotActive);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        if config.bond_amount > 0 {
+            token::Client::new(&env, &config.bond_token).transfer(
+                &resolver,
+                &env.current_contract_address(),
+                &config.bond_amount,
+            );
+        }
+
+        let amount = Self::amount_at(&auction, now);
+        auction.status = AuctionStatus::Accepted;
+        auction.resolver = Some(resolver.clone());
+        auction.accepted_amount = amount;
+        auction.bond = config.bond_amount;
+        auction.deadline = now + config.exclusivity_period;
+        Self::save_auction(&env, &auction);
+
+        env.events().publish(
+            (symbol_short!("AUCTION"), symbol_short!("ACCEPTED"), id),
+            (resolver, amount, auction.deadline),
+        );
+        Ok(amount)
+    }
+
+    /// Return the bond once the order's escrow has been created
+    pub fn settle(env: Env, id: u64) -> Result<(), Error> {
+        let mut auction = Self::get_auction(env.clone(), id)?;
+        if auction.status != AuctionStatus::Accepted {
+            return Err(Error::InvalidStatus);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        if EscrowFactoryClient::new(&env, &config.escrow_factory)
+            .get_escrow(&auction.order_hash)
+            .is_none()
+        {
+            return Err(Error::EscrowMissing);
+        }
+
+        let resolver = auction.resolver.clone().unwrap();
+        auction.status = AuctionStatus::Filled;
+        Self::save_auction(&env, &auction);
+        if auction.bond > 0 {
+            token::Client::new(&env, &config.bond_token).transfer(
+                &env.current_contract_address(),
+                &resolver,
+                &auction.bond,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("AUCTION"), symbol_short!("FILLED"), id),
+            (resolver, auction.accepted_amount),
+        );
+        Ok(())
+    }
+
+    /// Pay the bond to the maker when the winner missed the deadline,
+    /// and reopen the auction
+    pub fn slash(env: Env, id: u64) -> Result<(), Error> {
+        let mut auction = Self::get_auction(env.clone(), id)?;
+        if auction.status != AuctionStatus::Accepted {
+            return Err(Error::InvalidStatus);
+        }
+        if env.ledger().timestamp() <= auction.deadline {
+            return Err(Error::DeadlineNotReached);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        let resolver = auction.resolver.clone().unwrap();
+        let bond = auction.bond;
+        auction.status = AuctionStatus::Open;
+        auction.resolver = None;
+        auction.accepted_amount = 0;
+        auction.bond = 0;
+        auction.deadline = 0;
+        Self::save_auction(&env, &auction);
+        if bond > 0 {
+            token::Client::new(&env, &config.bond_token).transfer(
+                &env.current_contract_address(),
+                &auction.maker,
+                &bond,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("AUCTION"), symbol_short!("SLASHED"), id),
+            resolver,
+        );
+        Ok(())
+    }
+
+    /// Withdraw an order nobody has accepted (only maker)
+    pub fn cancel_order(env: Env, id: u64) -> Result<(), Error> {
+        let mut auction = Self::get_auction(env.clone(), id)?;
+        auction.maker.require_auth();
+        if auction.status != AuctionStatus::Open {
+            return Err(Error::InvalidStatus);
+        }
+
+        auction.status = AuctionStatus::Cancelled;
+        Self::save_auction(&env, &auction);
+
+        env.events().publish(
+            (symbol_short!("AUCTION"), symbol_short!("CANCELLED"), id),
+            (),
+        );
+        Ok(())
+    }
+
+    pub fn get_auction(env: Env, id: u64) -> Result<Auction, Error> {
+        env.storage()
+            .persistent()
+            .get(&(AUCTION, id))
+            .ok_or(Error::AuctionNotFound)
+    }
+
+    pub fn get_auction_count(env: Env) -> u64 {
+        env.storage().instance().get(&COUNT).unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for DutchAuction {}
+
+stellhydra_access::upgradeable!(DutchAuction);
+
+impl DutchAuction {
+    /// Linear decay from the start to the end amount
+    fn amount_at(auction: &Auction, now: u64) -> i128 {
+        if now <= auction.start_time {
+            return auction.start_amount;
+        }
+        if now >= auction.end_time {
+            return auction.end_amount;
+        }
+
+        let elapsed = (now - auction.start_time) as i128;
+        let duration = (auction.end_time - auction.start_time) as i128;
+        auction.start_amount - (auction.start_amount - auction.end_amount) * elapsed / duration
+    }
+
+    fn save_auction(env: &Env, auction: &Auction) {
+        let key = (AUCTION, auction.id);
+        env.storage().persistent().set(&key, auction);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    /// Escrow factory whose registry the test fills in by hand
+    #[contract]
+    struct MockFactory;
+
+    #[contractimpl]
+    impl MockFactory {
+        pub fn set_escrow(env: Env, order_hash: BytesN<32>, escrow: Address) {
+            env.storage().persistent().set(&order_hash, &escrow);
+        }
+
+        pub fn get_escrow(env: Env, order_hash: BytesN<32>) -> Option<Address> {
+            env.storage().persistent().get(&order_hash)
+        }
+    }
+
+    struct Setup<'a> {
+        auction: DutchAuctionClient<'a>,
+        factory: MockFactoryClient<'a>,
+        bond: token::Client<'a>,
+        maker: Address,
+        resolver: Address,
+        id: u64,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let bond = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let resolver = Address::generate(env);
+        StellarAssetClient::new(env, &bond).mint(&resolver, &500);
+
+        let factory = MockFactoryClient::new(env, &env.register_contract(None, MockFactory));
+        let auction = DutchAuctionClient::new(env, &env.register_contract(None, DutchAuction));
+        auction.initialize(
+            &Address::generate(env),
+            &AuctionConfig {
+                escrow_factory: factory.address.clone(),
+                bond_token: bond.clone(),
+                bond_amount: 100,
+                exclusivity_period: 300,
+            },
+        );
+
+        let maker = Address::generate(env);
+        let id = auction.post_order(
+            &maker,
+            &BytesN::from_array(env, &[1; 32]),
+            &Symbol::new(env, "ethereum"),
+            &2_000,
+            &1_000,
+            &1_000,
+        );
+
+        Setup {
+            auction,
+            factory,
+            bond: token::Client::new(env, &bond),
+            maker,
+            resolver,
+            id,
+        }
+    }
+
+    #[test]
+    fn test_price_decays_until_accepted() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        assert_eq!(s.auction.current_amount(&s.id), 2_000);
+        env.ledger().with_mut(|li| li.timestamp = 250);
+        assert_eq!(s.auction.current_amount(&s.id), 1_750);
+
+        assert_eq!(s.auction.accept(&s.resolver, &s.id), 1_750);
+        assert_eq!(s.bond.balance(&s.resolver), 400);
+        assert_eq!(
+            s.auction.try_accept(&Address::generate(&env), &s.id),
+            Err(Ok(Error::InvalidStatus))
+        );
+        assert_eq!(s.auction.try_settle(&s.id), Err(Ok(Error::EscrowMissing)));
+
+        s.factory.set_escrow(
+            &BytesN::from_array(&env, &[1; 32]),
+            &Address::generate(&env),
+        );
+        s.auction.settle(&s.id);
+        assert_eq!(s.bond.balance(&s.resolver), 500);
+        let auction = s.auction.get_auction(&s.id);
+        assert_eq!(auction.status, AuctionStatus::Filled);
+        assert_eq!(auction.accepted_amount, 1_750);
+    }
+
+    #[test]
+    fn test_missed_deadline_slashes_bond_and_reopens() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        s.auction.accept(&s.resolver, &s.id);
+        assert_eq!(
+            s.auction.try_slash(&s.id),
+            Err(Ok(Error::DeadlineNotReached))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 301);
+        s.auction.slash(&s.id);
+        assert_eq!(s.bond.balance(&s.maker), 100);
+        assert_eq!(s.auction.get_auction(&s.id).status, AuctionStatus::Open);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_001);
+        assert_eq!(
+            s.auction.try_accept(&s.resolver, &s.id),
+            Err(Ok(Error::AuctionNotActive))
+        );
+        s.auction.cancel_order(&s.id);
+        assert_eq!(
+            s.auction.get_auction(&s.id).status,
+            AuctionStatus::Cancelled
+        );
+    }
+}
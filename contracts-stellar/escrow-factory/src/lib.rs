@@ -0,0 +1,236 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    xdr::ToXdr, Address, BytesN, Env, Symbol,
+};
+use stellhydra_access::pausable;
+use stellhydra_events::escrow::EscrowDeployed;
+use stellhydra_events::Event;
+
+/// Terms of a swap, as taken by the escrow contract's initializer
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowParams {
+    pub order_hash: BytesN<32>,
+    pub maker: Address,
+    pub taker: Address,
+    pub asset: Address,
+    pub amount: i128,
+    pub hash_lock: BytesN<32>,
+    pub time_lock: u64,
+}
+
+/// Initializer of the escrow contract the factory deploys
+#[contractclient(name = "EscrowInstanceClient")]
+pub trait EscrowInstanceInterface {
+    fn initialize(env: Env, params: EscrowParams);
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    EscrowExists = 4,
+    Paused = 5,
+    OrderHashMismatch = 6,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const WASM_HASH: Symbol = symbol_short!("WASM_HASH");
+const ESCROW: Symbol = symbol_short!("ESCROW");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Deploys one escrow contract per swap and keeps the registry from
+/// order hash to escrow
+///
+/// The order hash is the deploy salt, so a resolver can tell where an
+/// order's escrow lives before it is created, and a bug or storage
+/// blowup in one escrow stays confined to it. The factory recomputes the
+/// hash from the terms, so nobody can take another order's slot with
+/// terms of their own.
+#[contract]
+pub struct EscrowFactory;
+
+#[contractimpl]
+impl EscrowFactory {
+    /// Initialize the factory with the uploaded escrow wasm
+    pub fn initialize(env: Env, admin: Address, wasm_hash: BytesN<32>) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&WASM_HASH, &wasm_hash);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Use another escrow wasm for future deployments (only admin)
+    pub fn set_wasm_hash(env: Env, wasm_hash: BytesN<32>) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        env.storage().instance().set(&WASM_HASH, &wasm_hash);
+        Ok(())
+    }
+
+    pub fn get_wasm_hash(env: Env) -> Result<BytesN<32>, Error> {
+        env.storage()
+            .instance()
+            .get(&WASM_HASH)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Deploy the escrow of an order and fund it from the maker
+    ///
+    /// `params.order_hash` must be the `order_hash` of the other terms.
+    pub fn create_escrow(env: Env, params: EscrowParams) -> Result<Address, Error> {
+        params.maker.require_auth();
+        if pausable::is_paused(&env) {
+            return Err(Error::Paused);
+        }
+        if params.amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if Self::order_hash(env.clone(), params.clone()) != params.order_hash {
+            return Err(Error::OrderHashMismatch);
+        }
+        if Self::get_escrow(env.clone(), params.order_hash.clone()).is_some() {
+            return Err(Error::EscrowExists);
+        }
+
+        let wasm_hash = Self::get_wasm_hash(env.clone())?;
+        let escrow = env
+            .deployer()
+            .with_current_contract(params.order_hash.clone())
+            .deploy(wasm_hash);
+        EscrowInstanceClient::new(&env, &escrow).initialize(&params);
+        token::Client::new(&env, &params.asset).transfer(&params.maker, &escrow, &params.amount);
+
+        let key = (ESCROW, params.order_hash.clone());
+        env.storage().persistent().set(&key, &escrow);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        Self::extend_instance(&env);
+
+        EscrowDeployed {
+            order_hash: params.order_hash,
+            escrow: escrow.clone(),
+            maker: params.maker,
+            amount: params.amount,
+        }
+        .publish(&env);
+        Ok(escrow)
+    }
+
+    /// Hash of an order's terms: sha256 of the params, as XDR, with
+    /// `order_hash` zeroed
+    pub fn order_hash(env: Env, params: EscrowParams) -> BytesN<32> {
+        let terms = EscrowParams {
+            order_hash: BytesN::from_array(&env, &[0; 32]),
+            ..params
+        };
+        env.crypto().sha256(&terms.to_xdr(&env)).into()
+    }
+
+    /// Escrow deployed for an order
+    pub fn get_escrow(env: Env, order_hash: BytesN<32>) -> Option<Address> {
+        env.storage().persistent().get(&(ESCROW, order_hash))
+    }
+
+    /// Address the escrow of an order is deployed at
+    pub fn predict_address(env: Env, order_hash: BytesN<32>) -> Address {
+        env.deployer()
+            .with_current_contract(order_hash)
+            .deployed_address()
+    }
+}
+
+stellhydra_access::pausable!(EscrowFactory);
+
+impl stellhydra_access::Upgradeable for EscrowFactory {}
+
+stellhydra_access::upgradeable!(EscrowFactory);
+
+impl EscrowFactory {
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    #[test]
+    fn test_escrow_addresses_follow_order_hash() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let factory_id = env.register_contract(None, EscrowFactory);
+        let factory = EscrowFactoryClient::new(&env, &factory_id);
+        factory.initialize(
+            &Address::generate(&env),
+            &BytesN::from_array(&env, &[0; 32]),
+        );
+
+        let order = BytesN::from_array(&env, &[1; 32]);
+        assert_eq!(
+            factory.predict_address(&order),
+            factory.predict_address(&order)
+        );
+        assert_ne!(
+            factory.predict_address(&order),
+            factory.predict_address(&BytesN::from_array(&env, &[2; 32]))
+        );
+        assert_eq!(factory.get_escrow(&order), None);
+
+        let mut params = EscrowParams {
+            order_hash: order,
+            maker: Address::generate(&env),
+            taker: Address::generate(&env),
+            asset: Address::generate(&env),
+            amount: 0,
+            hash_lock: BytesN::from_array(&env, &[0; 32]),
+            time_lock: 0,
+        };
+        assert_eq!(
+            factory.try_create_escrow(&params),
+            Err(Ok(Error::InvalidAmount))
+        );
+
+        // The slot of an order can't be claimed with other terms
+        params.amount = 1_000;
+        assert_eq!(
+            factory.try_create_escrow(&params),
+            Err(Ok(Error::OrderHashMismatch))
+        );
+
+        // The hash covers every term but itself
+        let hash = factory.order_hash(&params);
+        params.order_hash = hash.clone();
+        assert_eq!(factory.order_hash(&params), hash);
+        params.maker = Address::generate(&env);
+        assert_ne!(factory.order_hash(&params), hash);
+    }
+}
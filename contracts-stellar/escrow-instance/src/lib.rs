@@ -0,0 +1,220 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Bytes,
+    BytesN, Env, Symbol,
+};
+use stellhydra_events::escrow::{EscrowRefunded, EscrowWithdrawn};
+use stellhydra_events::Event;
+
+/// Terms of the swap, fixed when the escrow is deployed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowParams {
+    pub order_hash: BytesN<32>,
+    pub maker: Address,
+    pub taker: Address, // resolver receiving the funds against the secret
+    pub asset: Address,
+    pub amount: i128,
+    pub hash_lock: BytesN<32>, // sha256 of the secret
+    pub time_lock: u64,        // withdrawals until, refunds after
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscrowStatus {
+    Active,
+    Withdrawn,
+    Refunded,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    InvalidStatus = 2,
+    InvalidSecret = 3,
+    TimelockExpired = 4,
+    TimelockNotExpired = 5,
+}
+
+// Storage keys
+const PARAMS: Symbol = symbol_short!("PARAMS");
+const STATUS: Symbol = symbol_short!("STATUS");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Hash time locked escrow holding the funds of a single swap
+///
+/// Deployed and funded by the escrow factory, so each swap lives in its
+/// own contract and storage. Unlike the rest of the protocol it can't be
+/// upgraded: nobody can change the code holding funds once a swap is
+/// locked, and fixes reach new swaps through the factory's wasm hash.
+#[contract]
+pub struct EscrowInstance;
+
+#[contractimpl]
+impl EscrowInstance {
+    /// Initialize the escrow; called by the factory right after deploying it
+    pub fn initialize(env: Env, params: EscrowParams) {
+        if env.storage().instance().has(&PARAMS) {
+            panic!("Already initialized");
+        }
+
+        env.storage().instance().set(&PARAMS, &params);
+        env.storage().instance().set(&STATUS, &EscrowStatus::Active);
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    pub fn get_params(env: Env) -> Result<EscrowParams, Error> {
+        env.storage()
+            .instance()
+            .get(&PARAMS)
+            .ok_or(Error::NotInitialized)
+    }
+
+    pub fn get_status(env: Env) -> Result<EscrowStatus, Error> {
+        env.storage()
+            .instance()
+            .get(&STATUS)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Pay the taker against the secret, before the time lock
+    pub fn withdraw(env: Env, secret: Bytes) -> Result<(), Error> {
+        let params = Self::get_params(env.clone())?;
+        params.taker.require_auth();
+        if Self::get_status(env.clone())? != EscrowStatus::Active {
+            return Err(Error::InvalidStatus);
+        }
+        if BytesN::from(env.crypto().sha256(&secret)) != params.hash_lock {
+            return Err(Error::InvalidSecret);
+        }
+        if env.ledger().timestamp() > params.time_lock {
+            return Err(Error::TimelockExpired);
+        }
+
+        env.storage()
+            .instance()
+            .set(&STATUS, &EscrowStatus::Withdrawn);
+        token::Client::new(&env, &params.asset).transfer(
+            &env.current_contract_address(),
+            &params.taker,
+            &params.amount,
+        );
+
+        EscrowWithdrawn {
+            order_hash: params.order_hash,
+            secret,
+        }
+        .publish(&env);
+        Ok(())
+    }
+
+    /// Return the funds to the maker once the time lock has passed
+    pub fn refund(env: Env) -> Result<(), Error> {
+        let params = Self::get_params(env.clone())?;
+        params.maker.require_auth();
+        if Self::get_status(env.clone())? != EscrowStatus::Active {
+            return Err(Error::InvalidStatus);
+        }
+        if env.ledger().timestamp() <= params.time_lock {
+            return Err(Error::TimelockNotExpired);
+        }
+
+        env.storage()
+            .instance()
+            .set(&STATUS, &EscrowStatus::Refunded);
+        token::Client::new(&env, &params.asset).transfer(
+            &env.current_contract_address(),
+            &params.maker,
+            &params.amount,
+        );
+
+        EscrowRefunded {
+            order_hash: params.order_hash,
+            amount: params.amount,
+        }
+        .publish(&env);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    fn setup<'a>(env: &Env, secret: &Bytes) -> (EscrowInstanceClient<'a>, EscrowParams) {
+        env.mock_all_auths();
+
+        let asset = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let params = EscrowParams {
+            order_hash: BytesN::from_array(env, &[7; 32]),
+            maker: Address::generate(env),
+            taker: Address::generate(env),
+            asset: asset.clone(),
+            amount: 1_000,
+            hash_lock: env.crypto().sha256(secret).into(),
+            time_lock: 3_600,
+        };
+
+        let escrow_id = env.register_contract(None, EscrowInstance);
+        let escrow = EscrowInstanceClient::new(env, &escrow_id);
+        escrow.initialize(&params);
+        StellarAssetClient::new(env, &asset).mint(&escrow_id, &params.amount);
+
+        (escrow, params)
+    }
+
+    #[test]
+    fn test_taker_withdraws_with_secret() {
+        let env = Env::default();
+        let secret = Bytes::from_slice(&env, b"my_secret_32_bytes_exactly_here!");
+        let (escrow, params) = setup(&env, &secret);
+        let token = token::Client::new(&env, &params.asset);
+
+        assert_eq!(
+            escrow.try_withdraw(&Bytes::from_slice(&env, b"wrong")),
+            Err(Ok(Error::InvalidSecret))
+        );
+        assert_eq!(escrow.try_refund(), Err(Ok(Error::TimelockNotExpired)));
+
+        escrow.withdraw(&secret);
+        assert_eq!(token.balance(&params.taker), 1_000);
+        assert_eq!(escrow.get_status(), EscrowStatus::Withdrawn);
+        assert_eq!(escrow.try_withdraw(&secret), Err(Ok(Error::InvalidStatus)));
+    }
+
+    #[test]
+    fn test_maker_refunds_after_time_lock() {
+        let env = Env::default();
+        let secret = Bytes::from_slice(&env, b"my_secret_32_bytes_exactly_here!");
+        let (escrow, params) = setup(&env, &secret);
+
+        env.ledger().with_mut(|li| li.timestamp = 3_601);
+        assert_eq!(
+            escrow.try_withdraw(&secret),
+            Err(Ok(Error::TimelockExpired))
+        );
+
+        escrow.refund();
+        assert_eq!(
+            token::Client::new(&env, &params.asset).balance(&params.maker),
+            1_000
+        );
+        assert_eq!(escrow.get_status(), EscrowStatus::Refunded);
+    }
+}
@@ -0,0 +1,57 @@
+//! On-chain limit order book
+
+use soroban_sdk::{contracttype, Address};
+
+use crate::event;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Limit order placed, with what was left after matching
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderPlaced {
+    pub id: u64,
+    pub owner: Address,
+    pub side: Side,
+    pub price: i128,
+    pub amount: i128,
+    pub remaining: i128,
+}
+event!(OrderPlaced, "BOOK", "PLACED", 1, id);
+
+/// Open order withdrawn by its owner
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderCancelled {
+    pub id: u64,
+    pub remaining: i128,
+}
+event!(OrderCancelled, "BOOK", "CANCELLED", 1, id);
+
+/// Resting order filled, fully or in part, by a taker
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderFilled {
+    pub id: u64,
+    pub taker: Address,
+    pub amount: i128,
+    pub price: i128,
+}
+event!(OrderFilled, "BOOK", "FILL", 1, id);
+
+/// Market swap filled against the book
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BookSwap {
+    pub taker: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: i128,
+    pub amount_out: i128,
+}
+event!(BookSwap, "BOOK", "SWAP", 1, taker);
@@ -0,0 +1,358 @@
+//! Bridge requests out of Stellar and transfers released into it
+
+use soroban_sdk::{contracttype, Address, Bytes, BytesN, Symbol, Vec};
+
+use crate::event;
+
+/// A single asset locked by a bridge request
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BridgeAsset {
+    pub token: Address,
+    pub amount: i128,
+    pub fee: i128,
+}
+
+/// Fee discount for users above a bridged volume or staked amount
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: Option<i128>,
+    pub min_stake: Option<i128>,
+    pub discount_bps: u32,
+}
+
+/// Single-asset request opened, carrying everything a relayer needs
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestCreated {
+    pub request_id: u64,
+    pub user: Address,
+    pub from_token: Address,
+    pub to_chain: Symbol,
+    pub to_address: BytesN<32>,
+    pub amount: i128,
+    pub fee: i128,
+    pub priority_fee: i128,
+    pub timestamp: u64,
+}
+event!(RequestCreated, "BRIDGE", "CREATED", 1, request_id, to_chain);
+
+/// Request locking several assets opened
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MultiRequestCreated {
+    pub request_id: u64,
+    pub user: Address,
+    pub to_chain: Symbol,
+    pub to_address: BytesN<32>,
+    pub assets: Vec<BridgeAsset>,
+    pub timestamp: u64,
+}
+event!(
+    MultiRequestCreated,
+    "BRIDGE",
+    "CREATED_M",
+    1,
+    request_id,
+    to_chain
+);
+
+/// Request moved to a new status (0: pending, 1: processing,
+/// 2: completed, 3: failed)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestStatusChanged {
+    pub request_id: u64,
+    pub status: u32,
+    /// Hash of the transaction on the destination chain, 32 bytes when set
+    pub tx_hash: Option<Bytes>,
+}
+event!(RequestStatusChanged, "BRIDGE", "STATUS", 1, request_id);
+
+/// Failed request moved back to processing for another attempt
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestRetried {
+    pub request_id: u64,
+    pub retries: u32,
+}
+event!(RequestRetried, "BRIDGE", "RETRY", 1, request_id);
+
+/// Failed request given up on and its assets refunded
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestFinalized {
+    pub request_id: u64,
+    pub user: Address,
+    pub assets: Vec<BridgeAsset>,
+}
+event!(RequestFinalized, "BRIDGE", "FINALIZED", 1, request_id);
+
+/// Priority fee paid to whoever completed a request
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriorityFeePaid {
+    pub request_id: u64,
+    pub to: Address,
+    pub amount: i128,
+}
+event!(PriorityFeePaid, "BRIDGE", "PRIO_FEE", 1, request_id);
+
+/// Transfer from another chain paid out to its recipient
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InboundReleased {
+    pub source_chain: Symbol,
+    pub source_tx: BytesN<32>,
+    pub recipient: Address,
+    pub amount: i128,
+}
+event!(
+    InboundReleased,
+    "BRIDGE",
+    "INBOUND",
+    1,
+    source_chain,
+    source_tx
+);
+
+/// Transfer from another chain held until its recipient claims it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InboundClaimable {
+    pub source_chain: Symbol,
+    pub source_tx: BytesN<32>,
+    pub recipient: Address,
+    pub amount: i128,
+}
+event!(
+    InboundClaimable,
+    "BRIDGE",
+    "CLAIMABLE",
+    1,
+    source_chain,
+    source_tx
+);
+
+/// Held inbound transfer claimed by its recipient
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InboundClaimed {
+    pub source_tx: BytesN<32>,
+    pub recipient: Address,
+    pub amount: i128,
+}
+event!(InboundClaimed, "BRIDGE", "CLAIMED", 1, source_tx);
+
+/// Bridge set up with its admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BridgeInitialized {
+    pub admin: Address,
+}
+event!(BridgeInitialized, "BRIDGE", "INIT", 1);
+
+/// Bridge configuration changed by the admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigUpdated {
+    pub admin: Address,
+}
+event!(ConfigUpdated, "BRIDGE", "CONFIG", 1);
+
+/// Finished request removed from storage once its retention elapsed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestArchived {
+    pub request_id: u64,
+    pub user: Address,
+    pub to_chain: Symbol,
+    pub to_address: BytesN<32>,
+    pub assets: Vec<BridgeAsset>,
+    pub priority_fee: i128,
+    pub status: u32,
+    pub timestamp: u64,
+}
+event!(RequestArchived, "BRIDGE", "ARCHIVED", 1, request_id);
+
+/// Relayer added to or removed from the approved set
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelayerSet {
+    pub relayer: Address,
+    pub approved: bool,
+}
+event!(RelayerSet, "BRIDGE", "RELAYER", 1, relayer);
+
+/// Relayer bond topped up
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondPosted {
+    pub relayer: Address,
+    pub amount: i128,
+    /// Bond held after the deposit
+    pub bond: i128,
+}
+event!(BondPosted, "BRIDGE", "BOND", 1, relayer);
+
+/// Part of a relayer bond withdrawn
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondWithdrawn {
+    pub relayer: Address,
+    pub amount: i128,
+    pub remaining: i128,
+}
+event!(BondWithdrawn, "BRIDGE", "UNBOND", 1, relayer);
+
+/// Guardians allowed to sign inbound attestations replaced
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GuardiansSet {
+    pub guardians: Vec<Address>,
+    pub threshold: u32,
+}
+event!(GuardiansSet, "BRIDGE", "GUARDIANS", 1);
+
+/// Fraud proof opened against the relayer of a completed request
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FraudReported {
+    pub request_id: u64,
+    pub relayer: Address,
+    pub reporter: Address,
+}
+event!(FraudReported, "BRIDGE", "FRAUD", 1, request_id, relayer);
+
+/// Relayer bond slashed after an upheld fraud proof
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelayerSlashed {
+    pub request_id: u64,
+    pub relayer: Address,
+    /// Paid to the harmed user
+    pub slashed: i128,
+    /// Forfeited to the insurance reserve
+    pub insured: i128,
+}
+event!(RelayerSlashed, "BRIDGE", "SLASHED", 1, request_id, relayer);
+
+/// Contract and event emitting inbound transfers on an EVM chain set
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvmSourceSet {
+    pub chain: Symbol,
+    pub emitter: BytesN<20>,
+    pub event_topic: BytesN<32>,
+}
+event!(EvmSourceSet, "BRIDGE", "EVM_SRC", 1, chain);
+
+/// Attested receipts root of an EVM block recorded
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvmRootAdded {
+    pub chain: Symbol,
+    pub block_number: u64,
+    pub receipts_root: BytesN<32>,
+}
+event!(EvmRootAdded, "BRIDGE", "EVM_ROOT", 1, chain);
+
+/// Stellar account blocked from or allowed back into the bridge
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccountBlocked {
+    pub account: Address,
+    pub blocked: bool,
+}
+event!(AccountBlocked, "BRIDGE", "BLOCKED", 1, account);
+
+/// Destination address blocked from or allowed back into the bridge
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DestinationBlocked {
+    pub to_address: BytesN<32>,
+    pub blocked: bool,
+}
+event!(DestinationBlocked, "BRIDGE", "BLK_DEST", 1, to_address);
+
+/// External compliance contract set or cleared
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComplianceSet {
+    pub compliance: Option<Address>,
+}
+event!(ComplianceSet, "BRIDGE", "COMPLY", 1);
+
+/// Fee discount tiers replaced
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeTiersSet {
+    pub tiers: Vec<FeeTier>,
+}
+event!(FeeTiersSet, "BRIDGE", "FEE_TIERS", 1);
+
+/// Cap on the amount of a token locked in the bridge set or cleared
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExposureCapSet {
+    pub token: Address,
+    pub cap: Option<i128>,
+}
+event!(ExposureCapSet, "BRIDGE", "EXP_CAP", 1, token);
+
+/// Share of a request fee set aside in the insurance reserve
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsuranceContributed {
+    pub request_id: u64,
+    pub token: Address,
+    pub amount: i128,
+}
+event!(
+    InsuranceContributed,
+    "BRIDGE",
+    "INS_IN",
+    1,
+    request_id,
+    token
+);
+
+/// Insurance reserve paid out to cover a loss
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InsurancePaid {
+    pub request_id: u64,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: i128,
+}
+event!(InsurancePaid, "BRIDGE", "INS_OUT", 1, request_id, token);
+
+/// Requests to a chain paused or resumed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChainPaused {
+    pub chain: Symbol,
+    pub paused: bool,
+}
+event!(ChainPaused, "BRIDGE", "PAUSE_CH", 1, chain);
+
+/// Requests bridging a token paused or resumed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenPaused {
+    pub token: Address,
+    pub paused: bool,
+}
+event!(TokenPaused, "BRIDGE", "PAUSE_TK", 1, token);
+
+/// Tokens moved out of the bridge by the admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyWithdrawn {
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
+}
+event!(EmergencyWithdrawn, "BRIDGE", "EMERGENCY", 1, token);
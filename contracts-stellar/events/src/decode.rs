@@ -0,0 +1,51 @@
+//! Decoding published events back into their structs
+//!
+//! Indexers and relayers read events as XDR, either as [`ScVal`]s or as the
+//! base64 strings RPC returns. These helpers check the topics of such an
+//! event against an [`Event`] type and decode its data into that type,
+//! returning `None` for any other event or schema version.
+
+use soroban_sdk::xdr::{Limits, ReadXdr, ScVal};
+use soroban_sdk::{Env, Symbol, TryFromVal, Val};
+
+use crate::Event;
+
+/// Whether the topics are those of `E`, whatever its schema version
+pub fn is_event<E: Event>(env: &Env, topics: &[ScVal]) -> bool {
+    topics.len() >= 3
+        && symbol(env, &topics[0]) == Some(E::CONTRACT)
+        && symbol(env, &topics[1]) == Some(E::NAME)
+}
+
+/// Schema version an event was published with
+pub fn version(env: &Env, topics: &[ScVal]) -> Option<u32> {
+    let topic = topics.get(2)?;
+    let val = Val::try_from_val(env, topic).ok()?;
+    u32::try_from_val(env, &val).ok()
+}
+
+/// Decode an event published as `E`, in the version this crate knows
+pub fn decode<E: Event>(env: &Env, topics: &[ScVal], data: &ScVal) -> Option<E> {
+    if !is_event::<E>(env, topics) || version(env, topics) != Some(E::VERSION) {
+        return None;
+    }
+
+    let val = Val::try_from_val(env, data).ok()?;
+    E::try_from_val(env, &val).ok()
+}
+
+/// Decode an event given as base64 XDR topics and data, as RPC returns it
+pub fn decode_base64<E: Event>(env: &Env, topics: &[&str], data: &str) -> Option<E> {
+    let topics = topics
+        .iter()
+        .map(|topic| ScVal::from_xdr_base64(topic, Limits::none()).ok())
+        .collect::<Option<std::vec::Vec<_>>>()?;
+    let data = ScVal::from_xdr_base64(data, Limits::none()).ok()?;
+
+    decode(env, &topics, &data)
+}
+
+fn symbol(env: &Env, topic: &ScVal) -> Option<Symbol> {
+    let val = Val::try_from_val(env, topic).ok()?;
+    Symbol::try_from_val(env, &val).ok()
+}
@@ -0,0 +1,101 @@
+//! Hash time locked escrows: the single-contract HTLC and the per-order
+//! escrows deployed by the factory
+
+use soroban_sdk::{contracttype, Address, Bytes, BytesN};
+
+use crate::event;
+
+/// Funds locked in the HTLC under a hash lock
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcCreated {
+    pub escrow_id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+}
+event!(HtlcCreated, "HTLC", "CREATED", 1, escrow_id);
+
+/// A resolver committed to an HTLC escrow
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcLocked {
+    pub escrow_id: Bytes,
+    pub resolver: Address,
+}
+event!(HtlcLocked, "HTLC", "LOCKED", 1, escrow_id);
+
+/// HTLC escrow released with its secret, now public
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcCompleted {
+    pub escrow_id: Bytes,
+    pub resolver: Address,
+    pub secret: Bytes,
+}
+event!(HtlcCompleted, "HTLC", "COMPLETED", 1, escrow_id);
+
+/// HTLC escrow released by a registered resolver other than the one that
+/// locked it, after the exclusive withdrawal, earning them the safety
+/// deposit
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcPublicWithdrawn {
+    pub escrow_id: Bytes,
+    pub resolver: Address,
+    pub secret: Bytes,
+    pub safety_deposit: i128,
+}
+event!(HtlcPublicWithdrawn, "HTLC", "PUBLIC", 1, escrow_id);
+
+/// Slice of a partially fillable HTLC escrow released with one of its
+/// secrets, now public
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcPartiallyFilled {
+    pub escrow_id: Bytes,
+    pub resolver: Address,
+    pub index: u32,
+    pub secret: Bytes,
+    pub fill_amount: i128,
+    pub filled: i128,
+}
+event!(HtlcPartiallyFilled, "HTLC", "FILLED", 1, escrow_id);
+
+/// HTLC escrow returned to its maker after the time lock
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HtlcRefunded {
+    pub escrow_id: Bytes,
+    pub maker: Address,
+}
+event!(HtlcRefunded, "HTLC", "REFUNDED", 1, escrow_id);
+
+/// Escrow contract deployed and funded for an order
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowDeployed {
+    pub order_hash: BytesN<32>,
+    pub escrow: Address,
+    pub maker: Address,
+    pub amount: i128,
+}
+event!(EscrowDeployed, "FACTORY", "ESCROW", 1, order_hash);
+
+/// Order escrow paid out to the taker with its secret
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowWithdrawn {
+    pub order_hash: BytesN<32>,
+    pub secret: Bytes,
+}
+event!(EscrowWithdrawn, "ESCROW", "WITHDRAWN", 1, order_hash);
+
+/// Order escrow returned to its maker
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowRefunded {
+    pub order_hash: BytesN<32>,
+    pub amount: i128,
+}
+event!(EscrowRefunded, "ESCROW", "REFUNDED", 1, order_hash);
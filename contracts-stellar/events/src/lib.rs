@@ -0,0 +1,196 @@
+#![no_std]
+
+//! Events published by the StellHydra contracts
+//!
+//! Covers every event of the escrow, bridge, pool, router, order book and
+//! price oracle contracts. The periphery contracts publish plain tuples.
+//!
+//! Every event is a `#[contracttype]` struct implementing [`Event`], and is
+//! published with the topics `(contract, name, version, keys...)`: the
+//! contract family and event name as short symbols, the schema version as
+//! a `u32`, then the fields indexers filter on. The struct itself is the
+//! data, so it reaches consumers as a map keyed by field name.
+//!
+//! A schema's version is bumped whenever its fields change. Consumers
+//! check it before decoding, which the helpers in [`decode`] (behind the
+//! `std` feature) do for them.
+
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+use soroban_sdk::{Env, IntoVal, Symbol, TryFromVal, Val, Vec};
+
+pub mod book;
+pub mod bridge;
+pub mod escrow;
+pub mod oracle;
+pub mod pool;
+pub mod router;
+
+#[cfg(any(test, feature = "std"))]
+pub mod decode;
+
+/// Event with a fixed topic layout and schema version
+pub trait Event: Clone + IntoVal<Env, Val> + TryFromVal<Env, Val> {
+    /// Contract family publishing the event, the first topic
+    const CONTRACT: Symbol;
+    /// Event name, the second topic
+    const NAME: Symbol;
+    /// Schema version, the third topic
+    const VERSION: u32;
+
+    /// Fields published as topics after the version, for filtering
+    fn keys(&self, env: &Env) -> Vec<Val>;
+
+    fn topics(&self, env: &Env) -> Vec<Val> {
+        let mut topics = soroban_sdk::vec![
+            env,
+            Self::CONTRACT.into_val(env),
+            Self::NAME.into_val(env),
+            Self::VERSION.into_val(env),
+        ];
+        topics.append(&self.keys(env));
+        topics
+    }
+
+    fn publish(&self, env: &Env) {
+        env.events().publish(self.topics(env), self.clone());
+    }
+}
+
+/// Implement [`Event`] for a struct, naming the fields published as keys
+macro_rules! event {
+    ($event:ident, $contract:literal, $name:literal, $version:literal $(, $key:ident)*) => {
+        impl $crate::Event for $event {
+            const CONTRACT: soroban_sdk::Symbol = soroban_sdk::symbol_short!($contract);
+            const NAME: soroban_sdk::Symbol = soroban_sdk::symbol_short!($name);
+            const VERSION: u32 = $version;
+
+            fn keys(&self, _env: &soroban_sdk::Env) -> soroban_sdk::Vec<soroban_sdk::Val> {
+                #[allow(unused_imports)]
+                use soroban_sdk::IntoVal;
+                soroban_sdk::vec![_env $(, self.$key.clone().into_val(_env))*]
+            }
+        }
+    };
+}
+pub(crate) use event;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Events};
+    use soroban_sdk::xdr::{ScVal, WriteXdr};
+    use soroban_sdk::{contract, symbol_short, Address, BytesN};
+
+    use crate::bridge::{BridgeAsset, MultiRequestCreated, RequestStatusChanged};
+    use crate::escrow::EscrowRefunded;
+
+    #[contract]
+    struct Publisher;
+
+    /// Publish an event from a contract and read back its XDR topics and
+    /// data, the way an indexer sees them
+    fn published<E: Event>(env: &Env, event: &E) -> (std::vec::Vec<ScVal>, ScVal) {
+        let contract_id = env.register_contract(None, Publisher);
+        env.as_contract(&contract_id, || event.publish(env));
+
+        let (_, topics, data) = env.events().all().last().unwrap();
+        let topics = topics
+            .iter()
+            .map(|topic| ScVal::try_from_val(env, &topic).unwrap())
+            .collect();
+        (topics, ScVal::try_from_val(env, &data).unwrap())
+    }
+
+    #[test]
+    fn test_topics_follow_the_convention() {
+        let env = Env::default();
+        let event = RequestStatusChanged {
+            request_id: 7,
+            status: 2,
+            tx_hash: Some(BytesN::from_array(&env, &[1; 32]).into()),
+        };
+
+        let contract_id = env.register_contract(None, Publisher);
+        env.as_contract(&contract_id, || event.publish(&env));
+
+        let (_, topics, _) = env.events().all().last().unwrap();
+        let expected: Vec<Val> = soroban_sdk::vec![
+            &env,
+            symbol_short!("BRIDGE").into_val(&env),
+            symbol_short!("STATUS").into_val(&env),
+            1u32.into_val(&env),
+            7u64.into_val(&env),
+        ];
+        assert_eq!(topics, expected);
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let env = Env::default();
+        let assets = soroban_sdk::vec![
+            &env,
+            BridgeAsset {
+                token: Address::generate(&env),
+                amount: 1_000,
+                fee: 3,
+            },
+        ];
+        let event = MultiRequestCreated {
+            request_id: 1,
+            user: Address::generate(&env),
+            to_chain: symbol_short!("ethereum"),
+            to_address: BytesN::from_array(&env, &[2; 32]),
+            assets,
+            timestamp: 12345,
+        };
+
+        let (topics, data) = published(&env, &event);
+        assert!(decode::is_event::<MultiRequestCreated>(&env, &topics));
+        assert_eq!(decode::version(&env, &topics), Some(1));
+        assert_eq!(
+            decode::decode::<MultiRequestCreated>(&env, &topics, &data),
+            Some(event.clone())
+        );
+
+        let topics_base64: std::vec::Vec<_> = topics
+            .iter()
+            .map(|topic| {
+                topic
+                    .to_xdr_base64(soroban_sdk::xdr::Limits::none())
+                    .unwrap()
+            })
+            .collect();
+        let topics_base64: std::vec::Vec<&str> =
+            topics_base64.iter().map(|topic| topic.as_str()).collect();
+        let data_base64 = data
+            .to_xdr_base64(soroban_sdk::xdr::Limits::none())
+            .unwrap();
+        assert_eq!(
+            decode::decode_base64::<MultiRequestCreated>(&env, &topics_base64, &data_base64),
+            Some(event)
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_other_events_and_versions() {
+        let env = Env::default();
+        let event = EscrowRefunded {
+            order_hash: BytesN::from_array(&env, &[3; 32]),
+            amount: 500,
+        };
+
+        let (mut topics, data) = published(&env, &event);
+        assert!(!decode::is_event::<RequestStatusChanged>(&env, &topics));
+        assert_eq!(
+            decode::decode::<RequestStatusChanged>(&env, &topics, &data),
+            None
+        );
+
+        // A newer schema this build does not know about is left alone
+        topics[2] = ScVal::U32(2);
+        assert!(decode::is_event::<EscrowRefunded>(&env, &topics));
+        assert_eq!(decode::decode::<EscrowRefunded>(&env, &topics, &data), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Price oracle
+
+use soroban_sdk::{contracttype, Address, String};
+
+use crate::event;
+
+/// Price accepted for a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceUpdated {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+    pub decimals: u32,
+    pub timestamp: u64,
+    pub source: String,
+    pub confidence: i128,
+}
+event!(PriceUpdated, "PRICE", "UPDATE", 1, base, quote);
+
+/// Price moving past the deviation guard, held until oracles confirm it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceHeld {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+}
+event!(PriceHeld, "PRICE", "HELD", 1, base, quote);
+
+/// Pair listed on its first accepted price
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairListed {
+    pub base: String,
+    pub quote: String,
+}
+event!(PairListed, "PRICE", "LISTED", 1, base, quote);
+
+/// Pair removed from the listed pairs
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairDelisted {
+    pub base: String,
+    pub quote: String,
+}
+event!(PairDelisted, "PRICE", "DELISTED", 1, base, quote);
+
+/// Deprecated pair pointed at the pair replacing it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairAliased {
+    pub base: String,
+    pub quote: String,
+    pub successor_base: String,
+    pub successor_quote: String,
+}
+event!(PairAliased, "PRICE", "ALIASED", 1, base, quote);
+
+/// Stellar asset registered under the symbol SEP-40 readers ask for
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetRegistered {
+    pub symbol: String,
+    pub token: Address,
+}
+event!(AssetRegistered, "PRICE", "ASSET", 1, symbol);
+
+/// Pair frozen by the admin or an oracle vote
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairFrozen {
+    pub base: String,
+    pub quote: String,
+}
+event!(PairFrozen, "PRICE", "FROZEN", 1, base, quote);
+
+/// Frozen pair serving prices again
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairUnfrozen {
+    pub base: String,
+    pub quote: String,
+}
+event!(PairUnfrozen, "PRICE", "UNFROZEN", 1, base, quote);
+
+/// Pair suspended by the circuit breaker after a price jump
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairSuspended {
+    pub base: String,
+    pub quote: String,
+    pub previous_price: i128,
+    pub price: i128,
+}
+event!(PairSuspended, "PRICE", "SUSPENDED", 1, base, quote);
+
+/// Suspended pair resumed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairResumed {
+    pub base: String,
+    pub quote: String,
+}
+event!(PairResumed, "PRICE", "RESUMED", 1, base, quote);
+
+/// Price proposed optimistically, final unless disputed by `active_at`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceProposed {
+    pub base: String,
+    pub quote: String,
+    pub proposer: Address,
+    pub price: i128,
+    pub active_at: u64,
+}
+event!(PriceProposed, "PRICE", "PROPOSED", 1, base, quote);
+
+/// Optimistic price disputed and sent back to a normal round
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceDisputed {
+    pub base: String,
+    pub quote: String,
+    pub proposer: Address,
+    pub disputer: Address,
+}
+event!(PriceDisputed, "PRICE", "DISPUTED", 1, base, quote);
+
+/// Emergency price proposed by the admin, usable from `active_at`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverrideProposed {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+    pub decimals: u32,
+    pub active_at: u64,
+}
+event!(OverrideProposed, "PRICE", "OVR_PROP", 1, base, quote);
+
+/// Emergency price written as the pair's latest round
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverrideExecuted {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+}
+event!(OverrideExecuted, "PRICE", "OVR_EXEC", 1, base, quote);
+
+/// Pending emergency price dropped
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverrideCancelled {
+    pub base: String,
+    pub quote: String,
+}
+event!(OverrideCancelled, "PRICE", "OVR_CANC", 1, base, quote);
+
+/// Oracle bond slashed and paid to whoever proved the misbehaviour
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleSlashed {
+    pub oracle: Address,
+    pub amount: i128,
+    pub recipient: Address,
+}
+event!(OracleSlashed, "PRICE", "SLASHED", 1, oracle);
+
+/// Consumer rejected a pushed price update
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PushFailed {
+    pub base: String,
+    pub quote: String,
+    pub consumer: Address,
+}
+event!(PushFailed, "PRICE", "PUSH_FAIL", 1, base, quote, consumer);
+
+/// Premium subscription extended
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Subscribed {
+    pub consumer: Address,
+    pub until: u64,
+}
+event!(Subscribed, "PRICE", "SUBSCRIBE", 1, consumer);
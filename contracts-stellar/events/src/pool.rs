@@ -0,0 +1,78 @@
+//! Constant product liquidity pool
+
+use soroban_sdk::{contracttype, Address};
+
+use crate::event;
+
+/// Pool initialized for a token pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolCreated {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub fee_rate: u32,
+}
+event!(PoolCreated, "POOL", "CREATE", 1);
+
+/// Liquidity deposited for pool shares
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LiquidityAdded {
+    pub user: Address,
+    pub amount_a: i128,
+    pub amount_b: i128,
+    pub shares: i128,
+}
+event!(LiquidityAdded, "POOL", "ADD_LIQ", 1, user);
+
+/// Pool shares redeemed for liquidity
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LiquidityRemoved {
+    pub user: Address,
+    pub amount_a: i128,
+    pub amount_b: i128,
+    pub shares: i128,
+}
+event!(LiquidityRemoved, "POOL", "REM_LIQ", 1, user);
+
+/// Pool shares moved between accounts
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SharesTransferred {
+    pub from: Address,
+    pub to: Address,
+    pub shares: i128,
+}
+event!(SharesTransferred, "POOL", "XFER_LP", 1, from, to);
+
+/// Tokens swapped against the pool
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolSwap {
+    pub user: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: i128,
+    pub amount_out: i128,
+}
+event!(PoolSwap, "POOL", "SWAP", 1, user);
+
+/// Pool's share of an epoch's gauge emissions collected for its
+/// liquidity providers
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardsHarvested {
+    pub epoch: u64,
+    pub amount: i128,
+}
+event!(RewardsHarvested, "POOL", "HARVEST", 1, epoch);
+
+/// Liquidity mining rewards paid to a provider
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardsClaimed {
+    pub user: Address,
+    pub amount: i128,
+}
+event!(RewardsClaimed, "POOL", "CLAIM_RW", 1, user);
@@ -0,0 +1,17 @@
+//! Router splitting swaps across registered venues
+
+use soroban_sdk::{contracttype, Address};
+
+use crate::event;
+
+/// Swap executed along the best route found
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RouterSwap {
+    pub user: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: i128,
+    pub amount_out: i128,
+}
+event!(RouterSwap, "ROUTER", "SWAP", 1, user);
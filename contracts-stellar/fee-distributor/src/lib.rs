@@ -0,0 +1,325 @@
+#![no_std]
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Env, IntoVal, Symbol, Val, Vec,
+};
+
+/// Interface of a recipient that pulls its share and accounts for it
+/// itself, such as the staking contract
+#[contractclient(name = "RewardsClient")]
+pub trait RewardsInterface {
+    /// Pull `amount` of the reward token from `funder`
+    fn notify_reward(env: Env, funder: Address, amount: i128);
+}
+
+/// One destination of the fees
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Recipient {
+    pub destination: Address,
+    pub weight: u32,
+    pub notify: bool, // pulls its share through `notify_reward` instead of a transfer
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidRecipients = 3,
+    NothingToDistribute = 4,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const RECIPIENTS: Symbol = symbol_short!("RECIPNTS");
+const RECEIVED: Symbol = symbol_short!("RECEIVED");
+const TOTAL: Symbol = symbol_short!("TOTAL");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Collects the fees of the pool, router and bridge, which send them to
+/// this contract's address, and splits them among the recipients
+#[contract]
+pub struct FeeDistributor;
+
+#[contractimpl]
+impl FeeDistributor {
+    /// Initialize the distributor
+    pub fn initialize(env: Env, admin: Address, recipients: Vec<Recipient>) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        Self::check_recipients(&recipients)?;
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&RECIPIENTS, &recipients);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Replace the recipients and their weights (only admin)
+    pub fn set_recipients(env: Env, recipients: Vec<Recipient>) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Self::check_recipients(&recipients)?;
+
+        env.storage().instance().set(&RECIPIENTS, &recipients);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("FEES"), symbol_short!("RECIPNTS")),
+            recipients,
+        );
+        Ok(())
+    }
+
+    pub fn get_recipients(env: Env) -> Result<Vec<Recipient>, Error> {
+        env.storage()
+            .instance()
+            .get(&RECIPIENTS)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Fees of `token` held and not yet distributed
+    pub fn get_pending(env: Env, token: Address) -> i128 {
+        token::Client::new(&env, &token).balance(&env.current_contract_address())
+    }
+
+    /// Split the held balance of `token` among the recipients by weight
+    ///
+    /// Anyone may call this. Rounding dust goes to the last recipient so
+    /// nothing is left behind.
+    pub fn distribute(env: Env, token: Address) -> Result<i128, Error> {
+        let recipients = Self::get_recipients(env.clone())?;
+        let amount = Self::get_pending(env.clone(), token.clone());
+        if amount <= 0 {
+            return Err(Error::NothingToDistribute);
+        }
+
+        let total_weight: i128 = recipients.iter().map(|r| r.weight as i128).sum();
+        let token_client = token::Client::new(&env, &token);
+        let mut remaining = amount;
+        for (index, recipient) in recipients.iter().enumerate() {
+            let share = if index as u32 == recipients.len() - 1 {
+                remaining
+            } else {
+                amount * recipient.weight as i128 / total_weight
+            };
+            if share == 0 {
+                continue;
+            }
+            remaining -= share;
+
+            if recipient.notify {
+                // The recipient pulls the share, one call below this one
+                Self::authorize_transfer(&env, &token, &recipient.destination, share);
+                RewardsClient::new(&env, &recipient.destination)
+                    .notify_reward(&env.current_contract_address(), &share);
+            } else {
+                token_client.transfer(
+                    &env.current_contract_address(),
+                    &recipient.destination,
+                    &share,
+                );
+            }
+            Self::record(&env, &recipient.destination, &token, share);
+        }
+
+        let total_key = (TOTAL, token.clone());
+        let total: i128 = env.storage().persistent().get(&total_key).unwrap_or(0);
+        Self::save(&env, &total_key, total + amount);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("FEES"), symbol_short!("DISTRIB"), token),
+            amount,
+        );
+        Ok(amount)
+    }
+
+    /// Total of `token` a recipient has received
+    pub fn get_received(env: Env, recipient: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(RECEIVED, recipient, token))
+            .unwrap_or(0)
+    }
+
+    /// Total of `token` distributed so far
+    pub fn get_total_distributed(env: Env, token: Address) -> i128 {
+        env.storage().persistent().get(&(TOTAL, token)).unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for FeeDistributor {}
+
+stellhydra_access::upgradeable!(FeeDistributor);
+
+impl FeeDistributor {
+    fn check_recipients(recipients: &Vec<Recipient>) -> Result<(), Error> {
+        if recipients.is_empty() || recipients.iter().any(|r| r.weight == 0) {
+            return Err(Error::InvalidRecipients);
+        }
+        Ok(())
+    }
+
+    fn authorize_transfer(env: &Env, token: &Address, to: &Address, amount: i128) {
+        let transfer = SubContractInvocation {
+            context: ContractContext {
+                contract: token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+                args: (env.current_contract_address(), to.clone(), amount).into_val(env),
+            },
+            sub_invocations: Vec::new(env),
+        };
+        env.authorize_as_current_contract(Vec::from_array(
+            env,
+            [InvokerContractAuthEntry::Contract(transfer)],
+        ));
+    }
+
+    fn record(env: &Env, recipient: &Address, token: &Address, share: i128) {
+        let key = (RECEIVED, recipient.clone(), token.clone());
+        let received: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        Self::save(env, &key, received + share);
+
+        env.events().publish(
+            (
+                symbol_short!("FEES"),
+                symbol_short!("PAID"),
+                recipient.clone(),
+            ),
+            (token.clone(), share),
+        );
+    }
+
+    fn save(env: &Env, key: &impl IntoVal<Env, Val>, amount: i128) {
+        env.storage().persistent().set(key, &amount);
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, vec, Env};
+
+    /// Staking contract that pulls its rewards
+    #[contract]
+    struct MockStaking;
+
+    #[contractimpl]
+    impl MockStaking {
+        pub fn init(env: Env, reward_token: Address) {
+            env.storage().instance().set(&ADMIN, &reward_token);
+        }
+
+        pub fn notify_reward(env: Env, funder: Address, amount: i128) {
+            funder.require_auth();
+            let reward_token: Address = env.storage().instance().get(&ADMIN).unwrap();
+            token::Client::new(&env, &reward_token).transfer(
+                &funder,
+                &env.current_contract_address(),
+                &amount,
+            );
+        }
+    }
+
+    fn recipient(destination: &Address, weight: u32, notify: bool) -> Recipient {
+        Recipient {
+            destination: destination.clone(),
+            weight,
+            notify,
+        }
+    }
+
+    #[test]
+    fn test_fees_split_by_weight() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = token::Client::new(&env, &token);
+
+        let staking_id = env.register_contract(None, MockStaking);
+        MockStakingClient::new(&env, &staking_id).init(&token);
+        let treasury = Address::generate(&env);
+        let insurance = Address::generate(&env);
+
+        let distributor_id = env.register_contract(None, FeeDistributor);
+        let distributor = FeeDistributorClient::new(&env, &distributor_id);
+        distributor.initialize(
+            &Address::generate(&env),
+            &vec![
+                &env,
+                recipient(&treasury, 5, false),
+                recipient(&staking_id, 3, true),
+                recipient(&insurance, 2, false),
+            ],
+        );
+        assert_eq!(
+            distributor.try_distribute(&token),
+            Err(Ok(Error::NothingToDistribute))
+        );
+
+        // Fees arrive as plain transfers from the pool, router or bridge
+        StellarAssetClient::new(&env, &token).mint(&distributor_id, &1_001);
+        assert_eq!(distributor.get_pending(&token), 1_001);
+        assert_eq!(distributor.distribute(&token), 1_001);
+
+        assert_eq!(token_client.balance(&treasury), 500);
+        assert_eq!(token_client.balance(&staking_id), 300);
+        assert_eq!(token_client.balance(&insurance), 201);
+        assert_eq!(token_client.balance(&distributor_id), 0);
+        assert_eq!(distributor.get_received(&staking_id, &token), 300);
+        assert_eq!(distributor.get_total_distributed(&token), 1_001);
+    }
+
+    #[test]
+    fn test_recipients_need_weight() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let treasury = Address::generate(&env);
+        let distributor_id = env.register_contract(None, FeeDistributor);
+        let distributor = FeeDistributorClient::new(&env, &distributor_id);
+        assert_eq!(
+            distributor.try_initialize(&Address::generate(&env), &vec![&env]),
+            Err(Ok(Error::InvalidRecipients))
+        );
+
+        distributor.initialize(
+            &Address::generate(&env),
+            &vec![&env, recipient(&treasury, 1, false)],
+        );
+        assert_eq!(
+            distributor.try_set_recipients(&vec![&env, recipient(&treasury, 0, false)]),
+            Err(Ok(Error::InvalidRecipients))
+        );
+    }
+}
@@ -0,0 +1,531 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Env, Symbol,
+};
+use stellhydra_access::{ownable, require_admin};
+use stellhydra_math::{apply_bps, mul_div};
+
+/// Stake source, such as the staking contract
+#[contractclient(name = "StakingClient")]
+pub trait StakingInterface {
+    /// Stake of `account` as of `timestamp`
+    fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebateConfig {
+    pub staking: Address, // staking contract stakes are read from
+    pub rebate_bps: u32,  // share of reported fees credited back
+    pub min_stake: i128,  // stake needed at the epoch start to claim
+}
+
+/// Limits on what a token's rebates pay out per epoch
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenCaps {
+    pub user_cap: i128,  // most one account is credited
+    pub epoch_cap: i128, // most paid out across all accounts
+}
+
+/// Fees an account paid in one token during an epoch
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EpochRebate {
+    pub fees: i128,
+    pub rebate: i128, // credited, after the user cap
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidConfig = 3,
+    NotSource = 4,
+    InvalidAmount = 5,
+    EpochNotFinalized = 6,
+    AlreadyClaimed = 7,
+    NotStaker = 8,
+    NothingToClaim = 9,
+    InsufficientFunds = 10,
+}
+
+// Storage keys
+const CONFIG: Symbol = symbol_short!("CONFIG");
+const SOURCE: Symbol = symbol_short!("SOURCE");
+const CAPS: Symbol = symbol_short!("CAPS");
+const FUNDS: Symbol = symbol_short!("FUNDS");
+const REBATE: Symbol = symbol_short!("REBATE");
+const TOTAL: Symbol = symbol_short!("TOTAL");
+const CLAIMED: Symbol = symbol_short!("CLAIMED");
+
+/// Length of a rebate epoch, in seconds
+pub const EPOCH_LENGTH: u64 = 7 * 24 * 60 * 60;
+
+const BPS_DENOMINATOR: u32 = 10_000;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Fee rebates for stakers of the protocol token
+///
+/// Sources the admin has allowed, such as the bridge and the router,
+/// report the fees each user pays. Every report credits the user a share
+/// of the fee for the current weekly epoch, up to a per-token user cap, and
+/// only tokens the admin has set caps for earn rebates. Once the epoch
+/// ends, users who held at least the minimum stake at its start claim what
+/// they were credited from the funds the treasury put in. When an epoch's
+/// credits exceed the token's epoch cap, every claim is scaled down alike.
+#[contract]
+pub struct FeeRebate;
+
+#[contractimpl]
+impl FeeRebate {
+    /// Set the admin managing sources and caps, and the rebate terms
+    pub fn initialize(env: Env, admin: Address, config: RebateConfig) -> Result<(), Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+        Self::save_config(&env, &config)
+    }
+
+    pub fn get_config(env: Env) -> Result<RebateConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Change the rebate terms (only admin)
+    ///
+    /// A new rebate share applies to fees reported from then on; a new
+    /// minimum stake applies to every claim from then on.
+    pub fn set_config(env: Env, config: RebateConfig) -> Result<(), Error> {
+        require_admin(&env);
+        Self::get_config(env.clone())?;
+        Self::save_config(&env, &config)
+    }
+
+    /// Allow or disallow a contract to report fees (only admin)
+    pub fn set_source(env: Env, source: Address, allowed: bool) {
+        require_admin(&env);
+        let key = (SOURCE, source.clone());
+        if allowed {
+            Self::save(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events().publish(
+            (symbol_short!("REBATE"), symbol_short!("SOURCE")),
+            (source, allowed),
+        );
+    }
+
+    pub fn is_source(env: Env, source: Address) -> bool {
+        env.storage().persistent().has(&(SOURCE, source))
+    }
+
+    /// Set or clear a token's caps (only admin)
+    ///
+    /// Fees in a token without caps earn no rebates.
+    pub fn set_caps(env: Env, token: Address, caps: Option<TokenCaps>) -> Result<(), Error> {
+        require_admin(&env);
+        let key = (CAPS, token.clone());
+        match caps {
+            Some(caps) => {
+                if caps.user_cap < 0 || caps.epoch_cap < 0 {
+                    return Err(Error::InvalidConfig);
+                }
+                Self::save(&env, &key, &caps);
+            }
+            None => env.storage().persistent().remove(&key),
+        }
+
+        env.events()
+            .publish((symbol_short!("REBATE"), symbol_short!("CAPS")), token);
+        Ok(())
+    }
+
+    pub fn get_caps(env: Env, token: Address) -> Option<TokenCaps> {
+        env.storage().persistent().get(&(CAPS, token))
+    }
+
+    /// Add to the funds rebates in `token` are paid from
+    pub fn fund(env: Env, funder: Address, token: Address, amount: i128) -> Result<(), Error> {
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        token::Client::new(&env, &token).transfer(
+            &funder,
+            &env.current_contract_address(),
+            &amount,
+        );
+        let funds = Self::get_funds(env.clone(), token.clone());
+        Self::save(&env, &(FUNDS, token.clone()), &(funds + amount));
+
+        env.events().publish(
+            (symbol_short!("REBATE"), symbol_short!("FUNDED")),
+            (funder, token, amount),
+        );
+        Ok(())
+    }
+
+    /// Take unused funds back (only admin)
+    pub fn withdraw(env: Env, token: Address, to: Address, amount: i128) -> Result<(), Error> {
+        require_admin(&env);
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        Self::take_funds(&env, &token, amount)?;
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &to, &amount);
+        Ok(())
+    }
+
+    pub fn get_funds(env: Env, token: Address) -> i128 {
+        env.storage().persistent().get(&(FUNDS, token)).unwrap_or(0)
+    }
+
+    pub fn current_epoch(env: Env) -> u64 {
+        env.ledger().timestamp() / EPOCH_LENGTH
+    }
+
+    /// Record a fee `user` paid in `token`, from an allowed source
+    pub fn report_fee(
+        env: Env,
+        source: Address,
+        user: Address,
+        token: Address,
+        fee: i128,
+    ) -> Result<(), Error> {
+        source.require_auth();
+        if !Self::is_source(env.clone(), source.clone()) {
+            return Err(Error::NotSource);
+        }
+        if fee < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let config = Self::get_config(env.clone())?;
+        let Some(caps) = Self::get_caps(env.clone(), token.clone()) else {
+            return Ok(());
+        };
+
+        let epoch = Self::current_epoch(env.clone());
+        let mut account = Self::get_rebate(env.clone(), user.clone(), token.clone(), epoch);
+        let credit = apply_bps(&env, fee, config.rebate_bps).expect("Math overflow");
+        let rebate = (account.rebate + credit).min(caps.user_cap.max(account.rebate));
+        let total = Self::get_epoch_total(env.clone(), token.clone(), epoch);
+        Self::save(
+            &env,
+            &(TOTAL, token.clone(), epoch),
+            &(total + rebate - account.rebate),
+        );
+
+        account.fees += fee;
+        account.rebate = rebate;
+        Self::save(
+            &env,
+            &(REBATE, user.clone(), token.clone(), epoch),
+            &account,
+        );
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("REBATE"), symbol_short!("REPORTED"), epoch),
+            (source, user, token, fee),
+        );
+        Ok(())
+    }
+
+    /// What `user` was credited in `token` during `epoch`
+    pub fn get_rebate(env: Env, user: Address, token: Address, epoch: u64) -> EpochRebate {
+        env.storage()
+            .persistent()
+            .get(&(REBATE, user, token, epoch))
+            .unwrap_or_default()
+    }
+
+    /// Credits of every account in `token` during `epoch`
+    pub fn get_epoch_total(env: Env, token: Address, epoch: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(TOTAL, token, epoch))
+            .unwrap_or(0)
+    }
+
+    /// What `user` would receive claiming their `epoch` rebate in `token`
+    pub fn get_claimable(env: Env, user: Address, token: Address, epoch: u64) -> i128 {
+        Self::claimable(&env, &user, &token, epoch).unwrap_or(0)
+    }
+
+    /// Pay `user` their rebate in `token` for an ended `epoch`
+    pub fn claim(env: Env, user: Address, token: Address, epoch: u64) -> Result<i128, Error> {
+        user.require_auth();
+        let amount = Self::claimable(&env, &user, &token, epoch)?;
+        Self::take_funds(&env, &token, amount)?;
+        Self::save(&env, &(CLAIMED, user.clone(), token.clone(), epoch), &true);
+
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &user, &amount);
+
+        env.events().publish(
+            (symbol_short!("REBATE"), symbol_short!("CLAIMED"), epoch),
+            (user, token, amount),
+        );
+        Ok(amount)
+    }
+
+    pub fn is_claimed(env: Env, user: Address, token: Address, epoch: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(CLAIMED, user, token, epoch))
+    }
+}
+
+impl FeeRebate {
+    fn claimable(env: &Env, user: &Address, token: &Address, epoch: u64) -> Result<i128, Error> {
+        if epoch >= Self::current_epoch(env.clone()) {
+            return Err(Error::EpochNotFinalized);
+        }
+        if Self::is_claimed(env.clone(), user.clone(), token.clone(), epoch) {
+            return Err(Error::AlreadyClaimed);
+        }
+
+        // Stake only counts if it was in place before the fees were paid,
+        // so accounts can't stake just to claim
+        let config = Self::get_config(env.clone())?;
+        let stake =
+            StakingClient::new(env, &config.staking).get_past_votes(user, &(epoch * EPOCH_LENGTH));
+        if stake <= 0 || stake < config.min_stake {
+            return Err(Error::NotStaker);
+        }
+
+        let rebate = Self::get_rebate(env.clone(), user.clone(), token.clone(), epoch).rebate;
+        let total = Self::get_epoch_total(env.clone(), token.clone(), epoch);
+        let epoch_cap = Self::get_caps(env.clone(), token.clone())
+            .map(|caps| caps.epoch_cap)
+            .unwrap_or(0);
+        let amount = if total > epoch_cap {
+            mul_div(env, rebate, epoch_cap, total).expect("Math overflow")
+        } else {
+            rebate
+        };
+        if amount <= 0 {
+            return Err(Error::NothingToClaim);
+        }
+        Ok(amount)
+    }
+
+    fn take_funds(env: &Env, token: &Address, amount: i128) -> Result<(), Error> {
+        let funds = Self::get_funds(env.clone(), token.clone());
+        if amount > funds {
+            return Err(Error::InsufficientFunds);
+        }
+        Self::save(env, &(FUNDS, token.clone()), &(funds - amount));
+        Ok(())
+    }
+
+    fn save_config(env: &Env, config: &RebateConfig) -> Result<(), Error> {
+        if config.rebate_bps > BPS_DENOMINATOR || config.min_stake < 0 {
+            return Err(Error::InvalidConfig);
+        }
+        env.storage().instance().set(&CONFIG, config);
+        Self::extend_instance(env);
+        Ok(())
+    }
+
+    fn save<K, V>(env: &Env, key: &K, value: &V)
+    where
+        K: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+        V: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+    {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+impl stellhydra_access::Upgradeable for FeeRebate {}
+
+stellhydra_access::ownable!(FeeRebate);
+stellhydra_access::upgradeable!(FeeRebate);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    #[contract]
+    struct MockStaking;
+
+    #[contractimpl]
+    impl MockStaking {
+        pub fn set_stake(env: Env, account: Address, stake: i128) {
+            env.storage().instance().set(&account, &stake);
+        }
+
+        pub fn get_past_votes(env: Env, account: Address, _timestamp: u64) -> i128 {
+            env.storage().instance().get(&account).unwrap_or(0)
+        }
+    }
+
+    struct Setup<'a> {
+        env: Env,
+        rebates: FeeRebateClient<'a>,
+        staking: MockStakingClient<'a>,
+        source: Address,
+        token: Address,
+    }
+
+    impl Setup<'_> {
+        fn new() -> Self {
+            let env = Env::default();
+            env.mock_all_auths();
+            env.ledger().with_mut(|li| li.timestamp = 10 * EPOCH_LENGTH);
+
+            let staking = MockStakingClient::new(&env, &env.register_contract(None, MockStaking));
+            let rebates = FeeRebateClient::new(&env, &env.register_contract(None, FeeRebate));
+            rebates.initialize(
+                &Address::generate(&env),
+                &RebateConfig {
+                    staking: staking.address.clone(),
+                    rebate_bps: 2_000,
+                    min_stake: 100,
+                },
+            );
+            let source = Address::generate(&env);
+            rebates.set_source(&source, &true);
+
+            let token = env
+                .register_stellar_asset_contract_v2(Address::generate(&env))
+                .address();
+            rebates.set_caps(
+                &token,
+                &Some(TokenCaps {
+                    user_cap: 50,
+                    epoch_cap: 1_000,
+                }),
+            );
+            let treasury = Address::generate(&env);
+            StellarAssetClient::new(&env, &token).mint(&treasury, &1_000);
+            rebates.fund(&treasury, &token, &1_000);
+
+            Setup {
+                env,
+                rebates,
+                staking,
+                source,
+                token,
+            }
+        }
+
+        fn staker(&self, stake: i128) -> Address {
+            let user = Address::generate(&self.env);
+            self.staking.set_stake(&user, &stake);
+            user
+        }
+
+        fn end_epoch(&self) {
+            self.env
+                .ledger()
+                .with_mut(|li| li.timestamp += EPOCH_LENGTH);
+        }
+    }
+
+    #[test]
+    fn test_stakers_claim_share_of_reported_fees() {
+        let s = Setup::new();
+        let user = s.staker(100);
+        let epoch = s.rebates.current_epoch();
+
+        assert_eq!(
+            s.rebates
+                .try_report_fee(&Address::generate(&s.env), &user, &s.token, &100),
+            Err(Ok(Error::NotSource))
+        );
+        s.rebates.report_fee(&s.source, &user, &s.token, &100);
+        s.rebates.report_fee(&s.source, &user, &s.token, &50);
+        assert_eq!(
+            s.rebates.get_rebate(&user, &s.token, &epoch),
+            EpochRebate {
+                fees: 150,
+                rebate: 30
+            }
+        );
+
+        // Fees in tokens without caps earn nothing
+        let other = Address::generate(&s.env);
+        s.rebates.report_fee(&s.source, &user, &other, &100);
+        assert_eq!(s.rebates.get_rebate(&user, &other, &epoch).rebate, 0);
+
+        assert_eq!(
+            s.rebates.try_claim(&user, &s.token, &epoch),
+            Err(Ok(Error::EpochNotFinalized))
+        );
+        s.end_epoch();
+        assert_eq!(s.rebates.get_claimable(&user, &s.token, &epoch), 30);
+        assert_eq!(s.rebates.claim(&user, &s.token, &epoch), 30);
+        assert_eq!(token::Client::new(&s.env, &s.token).balance(&user), 30);
+        assert_eq!(s.rebates.get_funds(&s.token), 970);
+        assert_eq!(
+            s.rebates.try_claim(&user, &s.token, &epoch),
+            Err(Ok(Error::AlreadyClaimed))
+        );
+    }
+
+    #[test]
+    fn test_caps_and_minimum_stake_limit_rebates() {
+        let s = Setup::new();
+        let whale = s.staker(1_000);
+        let dust = s.staker(99);
+        let epoch = s.rebates.current_epoch();
+
+        // Each account is credited at most the user cap
+        s.rebates.report_fee(&s.source, &whale, &s.token, &10_000);
+        s.rebates.report_fee(&s.source, &dust, &s.token, &100);
+        assert_eq!(s.rebates.get_rebate(&whale, &s.token, &epoch).rebate, 50);
+        assert_eq!(s.rebates.get_epoch_total(&s.token, &epoch), 70);
+        s.end_epoch();
+
+        // Accounts below the minimum stake can't claim
+        assert_eq!(
+            s.rebates.try_claim(&dust, &s.token, &epoch),
+            Err(Ok(Error::NotStaker))
+        );
+
+        // Past the epoch cap every claim is scaled down alike
+        s.rebates.set_caps(
+            &s.token,
+            &Some(TokenCaps {
+                user_cap: 50,
+                epoch_cap: 35,
+            }),
+        );
+        assert_eq!(s.rebates.claim(&whale, &s.token, &epoch), 25);
+    }
+}
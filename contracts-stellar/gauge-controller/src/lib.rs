@@ -0,0 +1,620 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Env, Symbol, Vec,
+};
+use stellhydra_access::{ownable, require_admin};
+use stellhydra_math::mul_div;
+
+/// Voting power source, such as the staking contract
+#[contractclient(name = "VotesClient")]
+pub trait VotesInterface {
+    /// Voting power of `account` as of `timestamp`
+    fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GaugeConfig {
+    pub votes: Address,        // staking contract voting power is read from
+    pub reward_token: Address, // token emissions are paid in
+}
+
+/// Share of a voter's power given to one gauge
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GaugeVote {
+    pub gauge: Address,
+    pub weight_bps: u32,
+}
+
+/// Votes an account cast in an epoch
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ballot {
+    pub power: i128, // voting power as of the epoch start
+    pub votes: Vec<GaugeVote>,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    UnknownGauge = 3,
+    GaugeExists = 4,
+    TooManyGauges = 5,
+    InvalidWeights = 6,
+    NoVotingPower = 7,
+    EpochNotFinalized = 8,
+    EpochFinalized = 9,
+    AlreadyClaimed = 10,
+    InvalidAmount = 11,
+    EpochHasVotes = 12,
+}
+
+// Storage keys
+const CONFIG: Symbol = symbol_short!("CONFIG");
+const GAUGES: Symbol = symbol_short!("GAUGES");
+const BALLOT: Symbol = symbol_short!("BALLOT");
+const WEIGHT: Symbol = symbol_short!("WEIGHT");
+const TOTAL: Symbol = symbol_short!("TOTAL");
+const EMISSION: Symbol = symbol_short!("EMISSION");
+const CLAIMED: Symbol = symbol_short!("CLAIMED");
+
+/// Length of a voting epoch, in seconds
+pub const EPOCH_LENGTH: u64 = 7 * 24 * 60 * 60;
+/// Most gauges the controller splits emissions across
+pub const MAX_GAUGES: u32 = 50;
+/// Fixed-point scale of relative gauge weights
+pub const WEIGHT_SCALE: i128 = 1_000_000_000;
+
+const BPS_DENOMINATOR: u32 = 10_000;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Gauge controller splitting liquidity mining emissions by staker vote
+///
+/// Time runs in weekly epochs. During an epoch stakers split their voting
+/// power, as of the epoch start, across the registered gauges (pools and
+/// vaults), and anyone funds the epoch's emissions. Once the epoch ends its
+/// weights are final and each gauge claims its share of the emissions.
+#[contract]
+pub struct GaugeController;
+
+#[contractimpl]
+impl GaugeController {
+    /// Set the admin managing the gauge list, the voting power source and
+    /// the reward token
+    pub fn initialize(env: Env, admin: Address, config: GaugeConfig) -> Result<(), Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+        env.storage().instance().set(&CONFIG, &config);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_config(env: Env) -> Result<GaugeConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Let stakers vote emissions to a gauge (only admin)
+    pub fn add_gauge(env: Env, gauge: Address) -> Result<(), Error> {
+        require_admin(&env);
+
+        let mut gauges = Self::get_gauges(env.clone());
+        if gauges.contains(&gauge) {
+            return Err(Error::GaugeExists);
+        }
+        if gauges.len() >= MAX_GAUGES {
+            return Err(Error::TooManyGauges);
+        }
+        gauges.push_back(gauge.clone());
+        env.storage().instance().set(&GAUGES, &gauges);
+        Self::extend_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("GAUGE"), symbol_short!("ADDED")), gauge);
+        Ok(())
+    }
+
+    /// Stop taking votes for a gauge (only admin)
+    ///
+    /// Votes already cast for it still count, so it keeps its share of
+    /// the current epoch.
+    pub fn remove_gauge(env: Env, gauge: Address) -> Result<(), Error> {
+        require_admin(&env);
+
+        let mut gauges = Self::get_gauges(env.clone());
+        let index = gauges.first_index_of(&gauge).ok_or(Error::UnknownGauge)?;
+        gauges.remove(index);
+        env.storage().instance().set(&GAUGES, &gauges);
+
+        env.events()
+            .publish((symbol_short!("GAUGE"), symbol_short!("REMOVED")), gauge);
+        Ok(())
+    }
+
+    pub fn get_gauges(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&GAUGES)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn current_epoch(env: Env) -> u64 {
+        env.ledger().timestamp() / EPOCH_LENGTH
+    }
+
+    /// Split the voter's power across gauges for the current epoch
+    ///
+    /// Weights are in basis points and may add up to less than 100%. A
+    /// second vote in the same epoch replaces the first.
+    pub fn vote(env: Env, voter: Address, votes: Vec<GaugeVote>) -> Result<(), Error> {
+        voter.require_auth();
+        let config = Self::get_config(env.clone())?;
+        let epoch = Self::current_epoch(env.clone());
+
+        let gauges = Self::get_gauges(env.clone());
+        let mut voted = Vec::new(&env);
+        let mut total_bps = 0u32;
+        for vote in votes.iter() {
+            if !gauges.contains(&vote.gauge) {
+                return Err(Error::UnknownGauge);
+            }
+            // One entry per gauge
+            if voted.contains(&vote.gauge) {
+                return Err(Error::InvalidWeights);
+            }
+            voted.push_back(vote.gauge);
+            total_bps = total_bps.saturating_add(vote.weight_bps);
+        }
+        if total_bps > BPS_DENOMINATOR {
+            return Err(Error::InvalidWeights);
+        }
+
+        // Power is fixed at the epoch start, so tokens moved mid-epoch
+        // can't vote twice
+        let power =
+            VotesClient::new(&env, &config.votes).get_past_votes(&voter, &(epoch * EPOCH_LENGTH));
+        if power <= 0 {
+            return Err(Error::NoVotingPower);
+        }
+
+        let key = (BALLOT, voter.clone(), epoch);
+        if let Some(previous) = env.storage().persistent().get::<_, Ballot>(&key) {
+            Self::tally(&env, epoch, &previous, -1);
+        }
+        let ballot = Ballot { power, votes };
+        Self::tally(&env, epoch, &ballot, 1);
+        Self::save(&env, &key, &ballot);
+
+        env.events().publish(
+            (symbol_short!("GAUGE"), symbol_short!("VOTED"), epoch),
+            (voter, power),
+        );
+        Ok(())
+    }
+
+    pub fn get_ballot(env: Env, voter: Address, epoch: u64) -> Option<Ballot> {
+        env.storage().persistent().get(&(BALLOT, voter, epoch))
+    }
+
+    /// Voting power given to a gauge in an epoch
+    pub fn get_gauge_weight(env: Env, gauge: Address, epoch: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(WEIGHT, gauge, epoch))
+            .unwrap_or(0)
+    }
+
+    /// Voting power given to every gauge in an epoch
+    pub fn get_total_weight(env: Env, epoch: u64) -> i128 {
+        env.storage().persistent().get(&(TOTAL, epoch)).unwrap_or(0)
+    }
+
+    /// Gauge's final share of an ended epoch, scaled by `WEIGHT_SCALE`
+    pub fn get_relative_weight(env: Env, gauge: Address, epoch: u64) -> Result<i128, Error> {
+        Self::require_final(&env, epoch)?;
+        let total = Self::get_total_weight(env.clone(), epoch);
+        if total == 0 {
+            return Ok(0);
+        }
+        let weight = Self::get_gauge_weight(env.clone(), gauge, epoch);
+        Ok(mul_div(&env, weight, WEIGHT_SCALE, total).expect("Math overflow"))
+    }
+
+    /// Add reward tokens to the emissions of the current or a later epoch
+    pub fn fund(env: Env, funder: Address, epoch: u64, amount: i128) -> Result<(), Error> {
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if epoch < Self::current_epoch(env.clone()) {
+            return Err(Error::EpochFinalized);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        token::Client::new(&env, &config.reward_token).transfer(
+            &funder,
+            &env.current_contract_address(),
+            &amount,
+        );
+        let key = (EMISSION, epoch);
+        let emission = Self::get_emission(env.clone(), epoch);
+        Self::save(&env, &key, &(emission + amount));
+
+        env.events().publish(
+            (symbol_short!("GAUGE"), symbol_short!("FUNDED"), epoch),
+            (funder, amount),
+        );
+        Ok(())
+    }
+
+    /// Emissions funded for an epoch
+    pub fn get_emission(env: Env, epoch: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(EMISSION, epoch))
+            .unwrap_or(0)
+    }
+
+    /// Pay a gauge its share of an ended epoch's emissions
+    ///
+    /// The gauge authorizes the claim, so a pool collecting through its
+    /// own entry point can account for what it receives.
+    pub fn claim(env: Env, gauge: Address, epoch: u64) -> Result<i128, Error> {
+        gauge.require_auth();
+        Self::require_final(&env, epoch)?;
+
+        let key = (CLAIMED, gauge.clone(), epoch);
+        if env.storage().persistent().has(&key) {
+            return Err(Error::AlreadyClaimed);
+        }
+        let total = Self::get_total_weight(env.clone(), epoch);
+        let amount = if total == 0 {
+            0
+        } else {
+            let weight = Self::get_gauge_weight(env.clone(), gauge.clone(), epoch);
+            mul_div(&env, Self::get_emission(env.clone(), epoch), weight, total)
+                .expect("Math overflow")
+        };
+        Self::save(&env, &key, &true);
+
+        if amount > 0 {
+            let config = Self::get_config(env.clone())?;
+            token::Client::new(&env, &config.reward_token).transfer(
+                &env.current_contract_address(),
+                &gauge,
+                &amount,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("GAUGE"), symbol_short!("CLAIMED"), epoch),
+            (gauge, amount),
+        );
+        Ok(amount)
+    }
+
+    pub fn is_claimed(env: Env, gauge: Address, epoch: u64) -> bool {
+        env.storage().persistent().has(&(CLAIMED, gauge, epoch))
+    }
+
+    /// Move the emissions of an ended epoch nobody voted in to the current
+    /// one, returning the amount moved
+    pub fn roll_over(env: Env, epoch: u64) -> Result<i128, Error> {
+        Self::require_final(&env, epoch)?;
+        if Self::get_total_weight(env.clone(), epoch) != 0 {
+            return Err(Error::EpochHasVotes);
+        }
+
+        let amount = Self::get_emission(env.clone(), epoch);
+        if amount > 0 {
+            let current = Self::current_epoch(env.clone());
+            env.storage().persistent().remove(&(EMISSION, epoch));
+            let emission = Self::get_emission(env.clone(), current);
+            Self::save(&env, &(EMISSION, current), &(emission + amount));
+        }
+        Ok(amount)
+    }
+}
+
+impl GaugeController {
+    fn require_final(env: &Env, epoch: u64) -> Result<(), Error> {
+        if epoch >= Self::current_epoch(env.clone()) {
+            return Err(Error::EpochNotFinalized);
+        }
+        Ok(())
+    }
+
+    /// Add (`sign` 1) or remove (`sign` -1) a ballot from the weights
+    fn tally(env: &Env, epoch: u64, ballot: &Ballot, sign: i128) {
+        let mut total = Self::get_total_weight(env.clone(), epoch);
+        for vote in ballot.votes.iter() {
+            let power = mul_div(
+                env,
+                ballot.power,
+                vote.weight_bps as i128,
+                BPS_DENOMINATOR as i128,
+            )
+            .expect("Math overflow");
+            let key = (WEIGHT, vote.gauge.clone(), epoch);
+            let weight = Self::get_gauge_weight(env.clone(), vote.gauge, epoch);
+            Self::save(env, &key, &(weight + sign * power));
+            total += sign * power;
+        }
+        Self::save(env, &(TOTAL, epoch), &total);
+    }
+
+    fn save<K, V>(env: &Env, key: &K, value: &V)
+    where
+        K: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+        V: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+    {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+impl stellhydra_access::Upgradeable for GaugeController {}
+
+stellhydra_access::ownable!(GaugeController);
+stellhydra_access::upgradeable!(GaugeController);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        vec, Env,
+    };
+
+    #[contract]
+    struct MockVotes;
+
+    #[contractimpl]
+    impl MockVotes {
+        pub fn set_votes(env: Env, account: Address, votes: i128) {
+            env.storage().instance().set(&account, &votes);
+        }
+
+        pub fn get_past_votes(env: Env, account: Address, _timestamp: u64) -> i128 {
+            env.storage().instance().get(&account).unwrap_or(0)
+        }
+    }
+
+    struct Setup<'a> {
+        env: Env,
+        controller: GaugeControllerClient<'a>,
+        votes: MockVotesClient<'a>,
+        reward_token: Address,
+        gauges: [Address; 2],
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+        env.ledger().with_mut(|li| li.timestamp = 10 * EPOCH_LENGTH);
+
+        let votes = MockVotesClient::new(env, &env.register_contract(None, MockVotes));
+        let reward_token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let controller =
+            GaugeControllerClient::new(env, &env.register_contract(None, GaugeController));
+        controller.initialize(
+            &Address::generate(env),
+            &GaugeConfig {
+                votes: votes.address.clone(),
+                reward_token: reward_token.clone(),
+            },
+        );
+
+        let gauges = [Address::generate(env), Address::generate(env)];
+        for gauge in &gauges {
+            controller.add_gauge(gauge);
+        }
+        Setup {
+            env: env.clone(),
+            controller,
+            votes,
+            reward_token,
+            gauges,
+        }
+    }
+
+    impl Setup<'_> {
+        fn voter(&self, power: i128) -> Address {
+            let voter = Address::generate(&self.env);
+            self.votes.set_votes(&voter, &power);
+            voter
+        }
+
+        fn ballot(&self, weights: [u32; 2]) -> Vec<GaugeVote> {
+            let mut votes = Vec::new(&self.env);
+            for (gauge, weight_bps) in self.gauges.iter().zip(weights) {
+                if weight_bps > 0 {
+                    votes.push_back(GaugeVote {
+                        gauge: gauge.clone(),
+                        weight_bps,
+                    });
+                }
+            }
+            votes
+        }
+
+        fn fund(&self, amount: i128) {
+            let funder = Address::generate(&self.env);
+            StellarAssetClient::new(&self.env, &self.reward_token).mint(&funder, &amount);
+            let epoch = self.controller.current_epoch();
+            self.controller.fund(&funder, &epoch, &amount);
+        }
+
+        fn next_epoch(&self) {
+            self.env
+                .ledger()
+                .with_mut(|li| li.timestamp += EPOCH_LENGTH);
+        }
+
+        fn balance(&self, account: &Address) -> i128 {
+            token::Client::new(&self.env, &self.reward_token).balance(account)
+        }
+    }
+
+    #[test]
+    fn test_emissions_follow_final_weights() {
+        let env = Env::default();
+        let s = setup(&env);
+        let [pool, vault] = s.gauges.clone();
+        let epoch = s.controller.current_epoch();
+
+        s.controller.vote(&s.voter(300), &s.ballot([10_000, 0]));
+        s.controller.vote(&s.voter(100), &s.ballot([5_000, 5_000]));
+        s.fund(1_000);
+        assert_eq!(s.controller.get_gauge_weight(&pool, &epoch), 350);
+        assert_eq!(s.controller.get_total_weight(&epoch), 400);
+
+        // Weights are only final once the epoch is over
+        assert_eq!(
+            s.controller.try_claim(&pool, &epoch),
+            Err(Ok(Error::EpochNotFinalized))
+        );
+        assert_eq!(
+            s.controller.try_get_relative_weight(&pool, &epoch),
+            Err(Ok(Error::EpochNotFinalized))
+        );
+
+        s.next_epoch();
+        assert_eq!(s.controller.get_relative_weight(&pool, &epoch), 875_000_000);
+        assert_eq!(s.controller.claim(&pool, &epoch), 875);
+        assert_eq!(s.controller.claim(&vault, &epoch), 125);
+        assert_eq!((s.balance(&pool), s.balance(&vault)), (875, 125));
+        assert_eq!(
+            s.controller.try_claim(&pool, &epoch),
+            Err(Ok(Error::AlreadyClaimed))
+        );
+
+        // The ended epoch takes no more funding
+        let funder = Address::generate(&env);
+        assert_eq!(
+            s.controller.try_fund(&funder, &epoch, &1),
+            Err(Ok(Error::EpochFinalized))
+        );
+    }
+
+    #[test]
+    fn test_vote_again_replaces_ballot() {
+        let env = Env::default();
+        let s = setup(&env);
+        let [pool, vault] = s.gauges.clone();
+        let epoch = s.controller.current_epoch();
+        let voter = s.voter(1_000);
+
+        s.controller.vote(&voter, &s.ballot([10_000, 0]));
+        s.controller.vote(&voter, &s.ballot([2_500, 2_500]));
+        assert_eq!(s.controller.get_gauge_weight(&pool, &epoch), 250);
+        assert_eq!(s.controller.get_gauge_weight(&vault, &epoch), 250);
+        assert_eq!(s.controller.get_total_weight(&epoch), 500);
+        assert_eq!(
+            s.controller.get_ballot(&voter, &epoch).unwrap().power,
+            1_000
+        );
+
+        // Power is spent per epoch; the next one starts from nothing
+        s.next_epoch();
+        assert_eq!(s.controller.get_total_weight(&(epoch + 1)), 0);
+    }
+
+    #[test]
+    fn test_invalid_ballots() {
+        let env = Env::default();
+        let s = setup(&env);
+        let voter = s.voter(1_000);
+        let unknown = vec![
+            &env,
+            GaugeVote {
+                gauge: Address::generate(&env),
+                weight_bps: 100,
+            },
+        ];
+        let mut twice = s.ballot([4_000, 0]);
+        twice.append(&s.ballot([4_000, 0]));
+
+        assert_eq!(
+            s.controller.try_vote(&voter, &unknown),
+            Err(Ok(Error::UnknownGauge))
+        );
+        assert_eq!(
+            s.controller.try_vote(&voter, &s.ballot([6_000, 5_000])),
+            Err(Ok(Error::InvalidWeights))
+        );
+        assert_eq!(
+            s.controller.try_vote(&voter, &twice),
+            Err(Ok(Error::InvalidWeights))
+        );
+        assert_eq!(
+            s.controller
+                .try_vote(&Address::generate(&env), &s.ballot([10_000, 0])),
+            Err(Ok(Error::NoVotingPower))
+        );
+
+        // A removed gauge takes no new votes
+        s.controller.remove_gauge(&s.gauges[1]);
+        assert_eq!(
+            s.controller.try_vote(&voter, &s.ballot([0, 10_000])),
+            Err(Ok(Error::UnknownGauge))
+        );
+    }
+
+    #[test]
+    fn test_unvoted_emissions_roll_over() {
+        let env = Env::default();
+        let s = setup(&env);
+        let epoch = s.controller.current_epoch();
+        s.fund(1_000);
+
+        assert_eq!(
+            s.controller.try_roll_over(&epoch),
+            Err(Ok(Error::EpochNotFinalized))
+        );
+        s.next_epoch();
+        assert_eq!(s.controller.claim(&s.gauges[0], &epoch), 0);
+        assert_eq!(s.controller.roll_over(&epoch), 1_000);
+        assert_eq!(s.controller.get_emission(&(epoch + 1)), 1_000);
+        assert_eq!(s.controller.get_emission(&epoch), 0);
+
+        // Voted epochs are paid to their gauges instead
+        s.controller.vote(&s.voter(10), &s.ballot([10_000, 0]));
+        s.next_epoch();
+        assert_eq!(
+            s.controller.try_roll_over(&(epoch + 1)),
+            Err(Ok(Error::EpochHasVotes))
+        );
+        assert_eq!(s.controller.claim(&s.gauges[0], &(epoch + 1)), 1_000);
+    }
+}
@@ -0,0 +1,751 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, Address,
+    Env, String, Symbol, TryFromVal, Val, Vec,
+};
+use stellhydra_access::upgradeable;
+
+/// Interface of a contract governance counts votes from, such as the
+/// staking contract or a snapshot of liquidity pool shares
+#[contractclient(name = "VotesClient")]
+pub trait VotesInterface {
+    /// Voting power of `account` as of `timestamp`
+    fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128;
+    /// Voting power of every account as of `timestamp`
+    fn get_past_total_supply(env: Env, timestamp: u64) -> i128;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GovernanceConfig {
+    pub voting_delay: u64,   // seconds between proposal and the start of voting
+    pub voting_period: u64,  // seconds voting stays open
+    pub timelock_delay: u64, // seconds a passed proposal waits once queued
+    pub grace_period: u64,   // seconds a queued proposal stays executable
+    pub quorum_bps: u32,     // share of the total supply that must vote for or abstain
+    pub threshold_bps: u32,  // share of for and against votes that must be for
+    pub proposal_threshold: i128, // voting power needed to propose
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub description: String,
+    pub snapshot: u64, // voting power is counted as of this time
+    pub vote_start: u64,
+    pub vote_end: u64,
+    pub for_votes: i128,
+    pub against_votes: i128,
+    pub abstain_votes: i128,
+    pub eta: u64, // 0 until queued
+    pub executed: bool,
+    pub canceled: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VoteType {
+    Against,
+    For,
+    Abstain,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProposalState {
+    Pending,
+    Active,
+    Defeated,
+    Succeeded,
+    Queued,
+    Expired,
+    Executed,
+    Canceled,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VoteReceipt {
+    pub support: VoteType,
+    pub weight: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidConfig = 3,
+    BelowProposalThreshold = 4,
+    ProposalNotFound = 5,
+    InvalidState = 6,
+    AlreadyVoted = 7,
+    NoVotingPower = 8,
+    TimelockNotElapsed = 9,
+    NotProposer = 10,
+    UnsupportedCall = 11,
+}
+
+// Storage keys
+const CONFIG: Symbol = symbol_short!("CONFIG");
+const SOURCES: Symbol = symbol_short!("SOURCES");
+const COUNTER: Symbol = symbol_short!("COUNTER");
+const PROPOSAL: Symbol = symbol_short!("PROPOSAL");
+const RECEIPT: Symbol = symbol_short!("RECEIPT");
+
+const MAX_BPS: u32 = 10_000;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contract]
+pub struct Governance;
+
+#[contractimpl]
+impl Governance {
+    /// Initialize governance with its config and the contracts votes are
+    /// counted from
+    ///
+    /// Both can only change afterwards through proposals targeting this
+    /// contract's `update_config` and `set_voting_sources`.
+    pub fn initialize(
+        env: Env,
+        config: GovernanceConfig,
+        voting_sources: Vec<Address>,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+        Self::check_config(&config)?;
+        if voting_sources.is_empty() {
+            return Err(Error::InvalidConfig);
+        }
+
+        env.storage().instance().set(&CONFIG, &config);
+        env.storage().instance().set(&SOURCES, &voting_sources);
+        env.storage().instance().set(&COUNTER, &0u64);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_config(env: Env) -> Result<GovernanceConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    pub fn get_voting_sources(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&SOURCES)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Voting power of an account as of `timestamp`, summed over every
+    /// voting source
+    pub fn get_voting_power(env: Env, account: Address, timestamp: u64) -> i128 {
+        Self::get_voting_sources(env.clone())
+            .iter()
+            .map(|source| VotesClient::new(&env, &source).get_past_votes(&account, &timestamp))
+            .sum()
+    }
+
+    /// Propose calling `function` on `target` with `args`
+    ///
+    /// Voting power is snapshotted just before the proposal, as of the end
+    /// of the previous second, so power gained in the proposing ledger
+    /// counts neither towards the threshold nor for votes.
+    pub fn propose(
+        env: Env,
+        proposer: Address,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+        description: String,
+    ) -> Result<u64, Error> {
+        proposer.require_auth();
+        let config = Self::get_config(env.clone())?;
+
+        let now = env.ledger().timestamp();
+        let snapshot = now.saturating_sub(1);
+        if Self::get_voting_power(env.clone(), proposer.clone(), snapshot)
+            < config.proposal_threshold
+        {
+            return Err(Error::BelowProposalThreshold);
+        }
+
+        let id: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0) + 1;
+        env.storage().instance().set(&COUNTER, &id);
+
+        let vote_start = now + config.voting_delay;
+        let proposal = Proposal {
+            id,
+            proposer: proposer.clone(),
+            target,
+            function,
+            args,
+            description,
+            snapshot,
+            vote_start,
+            vote_end: vote_start + config.voting_period,
+            for_votes: 0,
+            against_votes: 0,
+            abstain_votes: 0,
+            eta: 0,
+            executed: false,
+            canceled: false,
+        };
+        Self::save_proposal(&env, &proposal);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("GOV"), symbol_short!("PROPOSED"), id),
+            (proposer, proposal.target, proposal.function),
+        );
+        Ok(id)
+    }
+
+    /// Vote on an active proposal with the voter's power at its snapshot
+    pub fn cast_vote(
+        env: Env,
+        voter: Address,
+        proposal_id: u64,
+        support: VoteType,
+    ) -> Result<i128, Error> {
+        voter.require_auth();
+
+        let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+        if Self::state(env.clone(), proposal_id)? != ProposalState::Active {
+            return Err(Error::InvalidState);
+        }
+
+        let receipt_key = (RECEIPT, proposal_id, voter.clone());
+        if env.storage().persistent().has(&receipt_key) {
+            return Err(Error::AlreadyVoted);
+        }
+
+        let weight = Self::get_voting_power(env.clone(), voter.clone(), proposal.snapshot);
+        if weight <= 0 {
+            return Err(Error::NoVotingPower);
+        }
+
+        match support {
+            VoteType::For => proposal.for_votes += weight,
+            VoteType::Against => proposal.against_votes += weight,
+            VoteType::Abstain => proposal.abstain_votes += weight,
+        }
+        Self::save_proposal(&env, &proposal);
+
+        env.storage()
+            .persistent()
+            .set(&receipt_key, &VoteReceipt { support, weight });
+        env.storage().persistent().extend_ttl(
+            &receipt_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        env.events().publish(
+            (symbol_short!("GOV"), symbol_short!("VOTED"), proposal_id),
+            (voter, support, weight),
+        );
+        Ok(weight)
+    }
+
+    /// Start the timelock of a proposal that passed
+    pub fn queue(env: Env, proposal_id: u64) -> Result<u64, Error> {
+        let config = Self::get_config(env.clone())?;
+        let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+        if Self::state(env.clone(), proposal_id)? != ProposalState::Succeeded {
+            return Err(Error::InvalidState);
+        }
+
+        proposal.eta = env.ledger().timestamp() + config.timelock_delay;
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("GOV"), symbol_short!("QUEUED"), proposal_id),
+            proposal.eta,
+        );
+        Ok(proposal.eta)
+    }
+
+    /// Make the proposed call once the timelock has elapsed
+    ///
+    /// Calls target this contract as the invoker, so contracts whose admin
+    /// is this contract accept them.
+    pub fn execute(env: Env, proposal_id: u64) -> Result<(), Error> {
+        let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+        if Self::state(env.clone(), proposal_id)? != ProposalState::Queued {
+            return Err(Error::InvalidState);
+        }
+        if env.ledger().timestamp() < proposal.eta {
+            return Err(Error::TimelockNotElapsed);
+        }
+
+        proposal.executed = true;
+        Self::save_proposal(&env, &proposal);
+
+        // A contract can't call itself, so its own settings are applied
+        // directly
+        if proposal.target == env.current_contract_address() {
+            Self::apply_own_call(&env, &proposal.function, &proposal.args)?;
+        } else {
+            env.invoke_contract::<Val>(&proposal.target, &proposal.function, proposal.args);
+        }
+
+        env.events().publish(
+            (symbol_short!("GOV"), symbol_short!("EXECUTED"), proposal_id),
+            (),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a proposal that hasn't been executed (only its proposer)
+    pub fn cancel(env: Env, proposer: Address, proposal_id: u64) -> Result<(), Error> {
+        proposer.require_auth();
+
+        let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+        if proposal.proposer != proposer {
+            return Err(Error::NotProposer);
+        }
+        match Self::state(env.clone(), proposal_id)? {
+            ProposalState::Executed | ProposalState::Canceled | ProposalState::Expired => {
+                return Err(Error::InvalidState)
+            }
+            _ => {}
+        }
+
+        proposal.canceled = true;
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("GOV"), symbol_short!("CANCELED"), proposal_id),
+            (),
+        );
+        Ok(())
+    }
+
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Result<Proposal, Error> {
+        env.storage()
+            .persistent()
+            .get(&(PROPOSAL, proposal_id))
+            .ok_or(Error::ProposalNotFound)
+    }
+
+    pub fn get_proposal_count(env: Env) -> u64 {
+        env.storage().instance().get(&COUNTER).unwrap_or(0)
+    }
+
+    pub fn get_receipt(env: Env, proposal_id: u64, voter: Address) -> Option<VoteReceipt> {
+        env.storage()
+            .persistent()
+            .get(&(RECEIPT, proposal_id, voter))
+    }
+
+    /// Current state of a proposal
+    pub fn state(env: Env, proposal_id: u64) -> Result<ProposalState, Error> {
+        let proposal = Self::get_proposal(env.clone(), proposal_id)?;
+        let config = Self::get_config(env.clone())?;
+        let now = env.ledger().timestamp();
+
+        if proposal.canceled {
+            return Ok(ProposalState::Canceled);
+        }
+        if proposal.executed {
+            return Ok(ProposalState::Executed);
+        }
+        if now < proposal.vote_start {
+            return Ok(ProposalState::Pending);
+        }
+        if now <= proposal.vote_end {
+            return Ok(ProposalState::Active);
+        }
+        if !Self::passed(&env, &config, &proposal) {
+            return Ok(ProposalState::Defeated);
+        }
+        if proposal.eta == 0 {
+            return Ok(ProposalState::Succeeded);
+        }
+        if now > proposal.eta + config.grace_period {
+            return Ok(ProposalState::Expired);
+        }
+        Ok(ProposalState::Queued)
+    }
+}
+
+impl stellhydra_access::Upgradeable for Governance {
+    /// Upgrades only pass as proposals targeting this contract, which
+    /// apply them directly
+    fn require_upgrade_authority(env: &Env) {
+        env.current_contract_address().require_auth();
+    }
+}
+
+stellhydra_access::upgradeable!(Governance);
+
+impl Governance {
+    fn check_config(config: &GovernanceConfig) -> Result<(), Error> {
+        if config.voting_period == 0
+            || config.quorum_bps > MAX_BPS
+            || config.threshold_bps > MAX_BPS
+            || config.proposal_threshold < 0
+        {
+            return Err(Error::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    /// Whether a closed proposal reached quorum and the approval threshold
+    fn passed(env: &Env, config: &GovernanceConfig, proposal: &Proposal) -> bool {
+        let total_supply: i128 = Self::get_voting_sources(env.clone())
+            .iter()
+            .map(|source| VotesClient::new(env, &source).get_past_total_supply(&proposal.snapshot))
+            .sum();
+
+        let participating = proposal.for_votes + proposal.abstain_votes;
+        if participating * (MAX_BPS as i128) < total_supply * config.quorum_bps as i128 {
+            return false;
+        }
+
+        let decisive = proposal.for_votes + proposal.against_votes;
+        decisive > 0
+            && proposal.for_votes * MAX_BPS as i128 > decisive * config.threshold_bps as i128
+    }
+
+    /// Apply a proposal targeting governance itself
+    fn apply_own_call(env: &Env, function: &Symbol, args: &Vec<Val>) -> Result<(), Error> {
+        if upgradeable::apply_own_call::<Self>(env, function, args) {
+            return Ok(());
+        }
+
+        let arg = args.get(0).ok_or(Error::UnsupportedCall)?;
+
+        if *function == Symbol::new(env, "update_config") {
+            let config =
+                GovernanceConfig::try_from_val(env, &arg).map_err(|_| Error::UnsupportedCall)?;
+            Self::check_config(&config)?;
+            env.storage().instance().set(&CONFIG, &config);
+        } else if *function == Symbol::new(env, "set_voting_sources") {
+            let sources =
+                Vec::<Address>::try_from_val(env, &arg).map_err(|_| Error::UnsupportedCall)?;
+            if sources.is_empty() {
+                return Err(Error::InvalidConfig);
+            }
+            env.storage().instance().set(&SOURCES, &sources);
+        } else {
+            return Err(Error::UnsupportedCall);
+        }
+        Ok(())
+    }
+
+    fn save_proposal(env: &Env, proposal: &Proposal) {
+        let key = (PROPOSAL, proposal.id);
+        env.storage().persistent().set(&key, proposal);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        vec, BytesN, Env, IntoVal,
+    };
+
+    #[contract]
+    struct MockVotes;
+
+    #[contractimpl]
+    impl MockVotes {
+        pub fn set_votes(env: Env, account: Address, votes: i128) {
+            let total: i128 = env.storage().instance().get(&COUNTER).unwrap_or(0);
+            let previous: i128 = env.storage().instance().get(&account).unwrap_or(0);
+            env.storage().instance().set(&account, &votes);
+            env.storage()
+                .instance()
+                .set(&COUNTER, &(total - previous + votes));
+        }
+
+        pub fn get_past_votes(env: Env, account: Address, _timestamp: u64) -> i128 {
+            env.storage().instance().get(&account).unwrap_or(0)
+        }
+
+        pub fn get_past_total_supply(env: Env, _timestamp: u64) -> i128 {
+            env.storage().instance().get(&COUNTER).unwrap_or(0)
+        }
+    }
+
+    /// Contract administered by governance
+    #[contract]
+    struct MockTarget;
+
+    #[contractimpl]
+    impl MockTarget {
+        pub fn init(env: Env, admin: Address) {
+            env.storage().instance().set(&CONFIG, &admin);
+        }
+
+        pub fn set_value(env: Env, value: u32) {
+            let admin: Address = env.storage().instance().get(&CONFIG).unwrap();
+            admin.require_auth();
+            env.storage().instance().set(&COUNTER, &value);
+        }
+
+        pub fn value(env: Env) -> u32 {
+            env.storage().instance().get(&COUNTER).unwrap_or(0)
+        }
+    }
+
+    fn config() -> GovernanceConfig {
+        GovernanceConfig {
+            voting_delay: 60,
+            voting_period: 600,
+            timelock_delay: 3_600,
+            grace_period: 86_400,
+            quorum_bps: 2_000,
+            threshold_bps: 5_000,
+            proposal_threshold: 100,
+        }
+    }
+
+    fn setup(env: &Env) -> (GovernanceClient<'_>, MockVotesClient<'_>) {
+        env.mock_all_auths();
+
+        let votes_id = env.register_contract(None, MockVotes);
+        let votes = MockVotesClient::new(env, &votes_id);
+        let governance_id = env.register_contract(None, Governance);
+        let governance = GovernanceClient::new(env, &governance_id);
+        governance.initialize(&config(), &vec![env, votes_id]);
+
+        (governance, votes)
+    }
+
+    fn advance(env: &Env, seconds: u64) {
+        env.ledger().with_mut(|li| li.timestamp += seconds);
+    }
+
+    #[test]
+    fn test_passed_proposal_is_executed_after_timelock() {
+        let env = Env::default();
+        let (governance, votes) = setup(&env);
+
+        let target_id = env.register_contract(None, MockTarget);
+        let target = MockTargetClient::new(&env, &target_id);
+        target.init(&governance.address);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        votes.set_votes(&alice, &600);
+        votes.set_votes(&bob, &300);
+        votes.set_votes(&carol, &100);
+
+        let id = governance.propose(
+            &alice,
+            &target_id,
+            &Symbol::new(&env, "set_value"),
+            &vec![&env, 7u32.into_val(&env)],
+            &String::from_str(&env, "Set the value to 7"),
+        );
+        assert_eq!(governance.state(&id), ProposalState::Pending);
+        assert_eq!(
+            governance.try_cast_vote(&bob, &id, &VoteType::For),
+            Err(Ok(Error::InvalidState))
+        );
+
+        advance(&env, 60);
+        assert_eq!(governance.cast_vote(&alice, &id, &VoteType::For), 600);
+        assert_eq!(governance.cast_vote(&bob, &id, &VoteType::Against), 300);
+        assert_eq!(
+            governance.try_cast_vote(&bob, &id, &VoteType::For),
+            Err(Ok(Error::AlreadyVoted))
+        );
+        assert_eq!(
+            governance.get_receipt(&id, &bob),
+            Some(VoteReceipt {
+                support: VoteType::Against,
+                weight: 300
+            })
+        );
+        assert_eq!(governance.try_queue(&id), Err(Ok(Error::InvalidState)));
+
+        advance(&env, 601);
+        assert_eq!(governance.state(&id), ProposalState::Succeeded);
+        let eta = governance.queue(&id);
+        assert_eq!(eta, env.ledger().timestamp() + 3_600);
+        assert_eq!(
+            governance.try_execute(&id),
+            Err(Ok(Error::TimelockNotElapsed))
+        );
+
+        advance(&env, 3_600);
+        governance.execute(&id);
+        assert_eq!(target.value(), 7);
+        assert_eq!(governance.state(&id), ProposalState::Executed);
+        assert_eq!(governance.try_execute(&id), Err(Ok(Error::InvalidState)));
+    }
+
+    #[test]
+    fn test_proposals_need_quorum_and_majority() {
+        let env = Env::default();
+        let (governance, votes) = setup(&env);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let whale = Address::generate(&env);
+        votes.set_votes(&alice, &100);
+        votes.set_votes(&bob, &150);
+        votes.set_votes(&whale, &1_000);
+
+        let target = Address::generate(&env);
+        let function = Symbol::new(&env, "set_value");
+        let description = String::from_str(&env, "");
+        assert_eq!(
+            governance.try_propose(
+                &Address::generate(&env),
+                &target,
+                &function,
+                &vec![&env],
+                &description
+            ),
+            Err(Ok(Error::BelowProposalThreshold))
+        );
+
+        // 100 of 1250 votes is short of the 20% quorum
+        let low_turnout = governance.propose(&alice, &target, &function, &vec![&env], &description);
+        advance(&env, 60);
+        governance.cast_vote(&alice, &low_turnout, &VoteType::For);
+
+        // Quorum is met, but the majority is against
+        let rejected = governance.propose(&alice, &target, &function, &vec![&env], &description);
+        advance(&env, 60);
+        governance.cast_vote(&alice, &rejected, &VoteType::For);
+        governance.cast_vote(&bob, &rejected, &VoteType::Against);
+        governance.cast_vote(&whale, &rejected, &VoteType::Abstain);
+
+        advance(&env, 601);
+        assert_eq!(governance.state(&low_turnout), ProposalState::Defeated);
+        assert_eq!(governance.state(&rejected), ProposalState::Defeated);
+        assert_eq!(
+            governance.try_queue(&rejected),
+            Err(Ok(Error::InvalidState))
+        );
+    }
+
+    #[test]
+    fn test_proposals_can_be_cancelled_or_expire() {
+        let env = Env::default();
+        let (governance, votes) = setup(&env);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        votes.set_votes(&alice, &500);
+        votes.set_votes(&bob, &500);
+
+        let target = Address::generate(&env);
+        let function = Symbol::new(&env, "set_value");
+        let description = String::from_str(&env, "");
+
+        let cancelled = governance.propose(&alice, &target, &function, &vec![&env], &description);
+        assert_eq!(
+            governance.try_cancel(&bob, &cancelled),
+            Err(Ok(Error::NotProposer))
+        );
+        governance.cancel(&alice, &cancelled);
+        assert_eq!(governance.state(&cancelled), ProposalState::Canceled);
+
+        let expiring = governance.propose(&bob, &target, &function, &vec![&env], &description);
+        advance(&env, 60);
+        governance.cast_vote(&alice, &expiring, &VoteType::For);
+        advance(&env, 601);
+        governance.queue(&expiring);
+        advance(&env, 3_600 + 86_401);
+        assert_eq!(governance.state(&expiring), ProposalState::Expired);
+        assert_eq!(
+            governance.try_execute(&expiring),
+            Err(Ok(Error::InvalidState))
+        );
+    }
+
+    #[test]
+    fn test_governance_changes_its_own_config() {
+        let env = Env::default();
+        let (governance, votes) = setup(&env);
+
+        let alice = Address::generate(&env);
+        votes.set_votes(&alice, &1_000);
+
+        let mut new_config = config();
+        new_config.quorum_bps = 4_000;
+        let id = governance.propose(
+            &alice,
+            &governance.address,
+            &Symbol::new(&env, "update_config"),
+            &vec![&env, new_config.clone().into_val(&env)],
+            &String::from_str(&env, "Raise quorum"),
+        );
+        advance(&env, 60);
+        governance.cast_vote(&alice, &id, &VoteType::For);
+        advance(&env, 601);
+        governance.queue(&id);
+        advance(&env, 3_600);
+        governance.execute(&id);
+
+        assert_eq!(governance.get_config(), new_config);
+    }
+
+    #[test]
+    fn test_governance_schedules_its_own_upgrade() {
+        let env = Env::default();
+        let (governance, votes) = setup(&env);
+
+        let alice = Address::generate(&env);
+        votes.set_votes(&alice, &1_000);
+
+        let wasm_hash = BytesN::from_array(&env, &[7; 32]);
+        let id = governance.propose(
+            &alice,
+            &governance.address,
+            &Symbol::new(&env, "propose_upgrade"),
+            &vec![&env, wasm_hash.clone().into_val(&env)],
+            &String::from_str(&env, "Ship v2"),
+        );
+        advance(&env, 60);
+        governance.cast_vote(&alice, &id, &VoteType::For);
+        advance(&env, 601);
+        governance.queue(&id);
+        advance(&env, 3_600);
+        governance.execute(&id);
+
+        let upgrade = governance.get_pending_upgrade().unwrap();
+        assert_eq!(upgrade.wasm_hash, wasm_hash);
+        assert_eq!(
+            upgrade.eta,
+            env.ledger().timestamp() + governance.get_upgrade_delay()
+        );
+    }
+}
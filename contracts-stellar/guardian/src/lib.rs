@@ -0,0 +1,210 @@
+#![no_std]
+
+use soroban_sdk::{contract, contracterror, contractimpl, symbol_short, Address, Env, Symbol, Vec};
+use stellhydra_access::{ownable, pausable, require_admin};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotMember = 1,
+    AlreadyMember = 2,
+    TooManyMembers = 3,
+}
+
+// Storage keys
+const MEMBERS: Symbol = symbol_short!("MEMBERS");
+
+/// Largest committee the guardian keeps
+pub const MAX_MEMBERS: u32 = 10;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Protocol-wide emergency pause held by a small committee
+///
+/// Contracts follow the guardian through their `set_guardian` entry point
+/// and read `is_paused` here on every pause check, so any one committee
+/// member can halt new escrows, swaps and bridge requests everywhere with
+/// a single call. Only the admin can lift the pause, so a leaked member key
+/// can stop the protocol but not reopen it.
+#[contract]
+pub struct Guardian;
+
+#[contractimpl]
+impl Guardian {
+    /// Set the admin and the first committee
+    pub fn initialize(env: Env, admin: Address, members: Vec<Address>) -> Result<(), Error> {
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+
+        let mut committee = Vec::new(&env);
+        for member in members.iter() {
+            if committee.contains(&member) {
+                return Err(Error::AlreadyMember);
+            }
+            committee.push_back(member);
+        }
+        Self::save_members(&env, &committee)
+    }
+
+    /// Add a committee member (only admin)
+    pub fn add_member(env: Env, member: Address) -> Result<(), Error> {
+        require_admin(&env);
+
+        let mut members = Self::get_members(env.clone());
+        if members.contains(&member) {
+            return Err(Error::AlreadyMember);
+        }
+        members.push_back(member.clone());
+        Self::save_members(&env, &members)?;
+
+        env.events()
+            .publish((symbol_short!("GUARDIAN"), symbol_short!("ADDED")), member);
+        Ok(())
+    }
+
+    /// Remove a committee member (only admin)
+    pub fn remove_member(env: Env, member: Address) -> Result<(), Error> {
+        require_admin(&env);
+
+        let mut members = Self::get_members(env.clone());
+        let index = members.first_index_of(&member).ok_or(Error::NotMember)?;
+        members.remove(index);
+        Self::save_members(&env, &members)?;
+
+        env.events().publish(
+            (symbol_short!("GUARDIAN"), symbol_short!("REMOVED")),
+            member,
+        );
+        Ok(())
+    }
+
+    pub fn get_members(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&MEMBERS)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Halt every contract following the guardian (committee member)
+    pub fn emergency_pause(env: Env, member: Address, reason: Symbol) -> Result<(), Error> {
+        member.require_auth();
+        if !Self::get_members(env.clone()).contains(&member) {
+            return Err(Error::NotMember);
+        }
+
+        pausable::set_paused(&env, true);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("GUARDIAN"), symbol_short!("EMERGENCY")),
+            (member, reason),
+        );
+        Ok(())
+    }
+
+    /// Lift the emergency pause (only admin)
+    pub fn unpause(env: Env) {
+        pausable::unpause(&env);
+        Self::extend_instance(&env);
+    }
+
+    /// Whether the protocol is halted, read by every following contract
+    pub fn is_paused(env: Env) -> bool {
+        // Followers fail closed if the guardian can't be read, so every
+        // check keeps it alive
+        Self::extend_instance(&env);
+        pausable::is_paused(&env)
+    }
+}
+
+impl Guardian {
+    fn save_members(env: &Env, members: &Vec<Address>) -> Result<(), Error> {
+        if members.len() > MAX_MEMBERS {
+            return Err(Error::TooManyMembers);
+        }
+        env.storage().instance().set(&MEMBERS, members);
+        Self::extend_instance(env);
+        Ok(())
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+stellhydra_access::ownable!(Guardian);
+
+impl stellhydra_access::Upgradeable for Guardian {}
+
+stellhydra_access::upgradeable!(Guardian);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, vec, Env};
+
+    fn setup(env: &Env) -> (GuardianClient<'_>, Address, Address) {
+        env.mock_all_auths();
+        let admin = Address::generate(env);
+        let member = Address::generate(env);
+        let guardian = GuardianClient::new(env, &env.register_contract(None, Guardian));
+        guardian.initialize(&admin, &vec![env, member.clone()]);
+        (guardian, admin, member)
+    }
+
+    #[test]
+    fn test_any_member_pauses_only_admin_unpauses() {
+        let env = Env::default();
+        let (guardian, _, member) = setup(&env);
+        let stranger = Address::generate(&env);
+        let reason = symbol_short!("exploit");
+
+        assert_eq!(
+            guardian.try_emergency_pause(&stranger, &reason),
+            Err(Ok(Error::NotMember))
+        );
+        assert!(!guardian.is_paused());
+
+        guardian.emergency_pause(&member, &reason);
+        assert!(guardian.is_paused());
+
+        guardian.unpause();
+        assert!(!guardian.is_paused());
+
+        // A removed member can no longer pull the switch
+        guardian.remove_member(&member);
+        assert_eq!(
+            guardian.try_emergency_pause(&member, &reason),
+            Err(Ok(Error::NotMember))
+        );
+    }
+
+    #[test]
+    fn test_committee_stays_small() {
+        let env = Env::default();
+        let (guardian, _, member) = setup(&env);
+
+        assert_eq!(
+            guardian.try_add_member(&member),
+            Err(Ok(Error::AlreadyMember))
+        );
+        for _ in 1..MAX_MEMBERS {
+            guardian.add_member(&Address::generate(&env));
+        }
+        assert_eq!(guardian.get_members().len(), MAX_MEMBERS);
+        assert_eq!(
+            guardian.try_add_member(&Address::generate(&env)),
+            Err(Ok(Error::TooManyMembers))
+        );
+        assert_eq!(
+            guardian.try_remove_member(&Address::generate(&env)),
+            Err(Ok(Error::NotMember))
+        );
+    }
+}
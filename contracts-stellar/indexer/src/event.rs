@@ -0,0 +1,130 @@
+//! Every event the StellHydra contracts publish, as one typed enum
+
+use soroban_sdk::xdr::ScVal;
+use soroban_sdk::Env;
+use stellhydra_events::book::{BookSwap, OrderCancelled, OrderFilled, OrderPlaced};
+use stellhydra_events::bridge::{
+    AccountBlocked, BondPosted, BondWithdrawn, BridgeInitialized, ChainPaused, ComplianceSet,
+    ConfigUpdated, DestinationBlocked, EmergencyWithdrawn, EvmRootAdded, EvmSourceSet,
+    ExposureCapSet, FeeTiersSet, FraudReported, GuardiansSet, InboundClaimable, InboundClaimed,
+    InboundReleased, InsuranceContributed, InsurancePaid, MultiRequestCreated, PriorityFeePaid,
+    RelayerSet, RelayerSlashed, RequestArchived, RequestCreated, RequestFinalized, RequestRetried,
+    RequestStatusChanged, TokenPaused,
+};
+use stellhydra_events::decode::decode;
+use stellhydra_events::escrow::{
+    EscrowDeployed, EscrowRefunded, EscrowWithdrawn, HtlcCompleted, HtlcCreated, HtlcLocked,
+    HtlcPartiallyFilled, HtlcPublicWithdrawn, HtlcRefunded,
+};
+use stellhydra_events::oracle::{
+    AssetRegistered, OracleSlashed, OverrideCancelled, OverrideExecuted, OverrideProposed,
+    PairAliased, PairDelisted, PairFrozen, PairListed, PairResumed, PairSuspended, PairUnfrozen,
+    PriceDisputed, PriceHeld, PriceProposed, PriceUpdated, PushFailed, Subscribed,
+};
+use stellhydra_events::pool::{
+    LiquidityAdded, LiquidityRemoved, PoolCreated, PoolSwap, RewardsClaimed, RewardsHarvested,
+    SharesTransferred,
+};
+use stellhydra_events::router::RouterSwap;
+
+/// Declare the enum and its decoder from one list, so a new event type
+/// can't be added to one and not the other
+macro_rules! events {
+    ($($event:ident),* $(,)?) => {
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub enum StellHydraEvent {
+            $($event($event),)*
+        }
+
+        impl StellHydraEvent {
+            /// Decode an event in a schema version this build knows; `None`
+            /// for any other event
+            pub fn decode(env: &Env, topics: &[ScVal], data: &ScVal) -> Option<Self> {
+                $(
+                    if let Some(event) = decode::<$event>(env, topics, data) {
+                        return Some(StellHydraEvent::$event(event));
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+events!(
+    // Bridge
+    RequestCreated,
+    MultiRequestCreated,
+    RequestStatusChanged,
+    RequestRetried,
+    RequestFinalized,
+    PriorityFeePaid,
+    InboundReleased,
+    InboundClaimable,
+    InboundClaimed,
+    RequestArchived,
+    // Bridge relayers, insurance and admin
+    BridgeInitialized,
+    ConfigUpdated,
+    RelayerSet,
+    BondPosted,
+    BondWithdrawn,
+    GuardiansSet,
+    FraudReported,
+    RelayerSlashed,
+    EvmSourceSet,
+    EvmRootAdded,
+    AccountBlocked,
+    DestinationBlocked,
+    ComplianceSet,
+    FeeTiersSet,
+    ExposureCapSet,
+    InsuranceContributed,
+    InsurancePaid,
+    ChainPaused,
+    TokenPaused,
+    EmergencyWithdrawn,
+    // Escrows
+    HtlcCreated,
+    HtlcLocked,
+    HtlcCompleted,
+    HtlcPartiallyFilled,
+    HtlcPublicWithdrawn,
+    HtlcRefunded,
+    EscrowDeployed,
+    EscrowWithdrawn,
+    EscrowRefunded,
+    // Price oracle
+    PriceUpdated,
+    PriceHeld,
+    PriceProposed,
+    PriceDisputed,
+    PairListed,
+    PairDelisted,
+    PairAliased,
+    PairFrozen,
+    PairUnfrozen,
+    PairSuspended,
+    PairResumed,
+    OverrideProposed,
+    OverrideExecuted,
+    OverrideCancelled,
+    AssetRegistered,
+    OracleSlashed,
+    PushFailed,
+    Subscribed,
+    // Liquidity pool
+    PoolCreated,
+    LiquidityAdded,
+    LiquidityRemoved,
+    SharesTransferred,
+    PoolSwap,
+    RewardsHarvested,
+    RewardsClaimed,
+    // Router and order book
+    RouterSwap,
+    OrderPlaced,
+    OrderCancelled,
+    OrderFilled,
+    BookSwap,
+);
@@ -0,0 +1,61 @@
+//! Event data as JSON, for sinks storing it schema-free
+//!
+//! Integers wider than 64 bits become decimal strings, since JSON readers
+//! commonly parse numbers as doubles. Bytes are hex, addresses strkeys,
+//! and maps keyed by symbols or strings become objects.
+
+use serde_json::{Map, Value};
+use soroban_sdk::xdr::{Limits, ScVal, UInt128Parts, WriteXdr};
+use stellhydra_rpc::address_string;
+
+pub fn to_json(value: &ScVal) -> Value {
+    match value {
+        ScVal::Void => Value::Null,
+        ScVal::Bool(value) => Value::Bool(*value),
+        ScVal::U32(value) => Value::from(*value),
+        ScVal::I32(value) => Value::from(*value),
+        ScVal::U64(value) => Value::from(*value),
+        ScVal::I64(value) => Value::from(*value),
+        ScVal::Timepoint(value) => Value::from(value.0),
+        ScVal::Duration(value) => Value::from(value.0),
+        ScVal::U128(UInt128Parts { hi, lo }) => {
+            Value::String(((u128::from(*hi) << 64) | u128::from(*lo)).to_string())
+        }
+        ScVal::I128(parts) => Value::String(i128::from(parts).to_string()),
+        ScVal::Bytes(bytes) => Value::String(hex::encode(bytes.as_slice())),
+        ScVal::String(text) => Value::String(text.to_utf8_string_lossy()),
+        ScVal::Symbol(symbol) => Value::String(symbol.to_utf8_string_lossy()),
+        ScVal::Address(address) => Value::String(address_string(address)),
+        ScVal::Vec(Some(items)) => Value::Array(items.iter().map(to_json).collect()),
+        ScVal::Vec(None) | ScVal::Map(None) => Value::Null,
+        ScVal::Map(Some(entries)) => {
+            let keys: Option<Vec<String>> = entries
+                .iter()
+                .map(|entry| match &entry.key {
+                    ScVal::Symbol(symbol) => Some(symbol.to_utf8_string_lossy()),
+                    ScVal::String(text) => Some(text.to_utf8_string_lossy()),
+                    _ => None,
+                })
+                .collect();
+            match keys {
+                Some(keys) => Value::Object(
+                    keys.into_iter()
+                        .zip(entries.iter())
+                        .map(|(key, entry)| (key, to_json(&entry.val)))
+                        .collect::<Map<_, _>>(),
+                ),
+                None => Value::Array(
+                    entries
+                        .iter()
+                        .map(|entry| Value::Array(vec![to_json(&entry.key), to_json(&entry.val)]))
+                        .collect(),
+                ),
+            }
+        }
+        // Nothing StellHydra publishes; kept as XDR rather than dropped
+        other => other
+            .to_xdr_base64(Limits::none())
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+    }
+}
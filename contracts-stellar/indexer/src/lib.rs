@@ -0,0 +1,324 @@
+//! StellHydra event indexer
+//!
+//! Pages through the events of the StellHydra contracts with Soroban RPC,
+//! decodes each into a typed [`StellHydraEvent`] and stores the page in a
+//! [`Sink`]: a JSON lines file, SQLite or PostgreSQL. Dashboards read the
+//! sink; services acting on events, like the relayer, use [`Decoder`] on
+//! the events they page through themselves, so both decode alike.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use stellhydra_indexer::{sink::JsonSink, Indexer};
+//! use stellhydra_rpc::Client;
+//!
+//! let client = Client::new(
+//!     "https://soroban-testnet.stellar.org",
+//!     "Test SDF Network ; September 2015",
+//!     100,
+//! );
+//! let bridge = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC".to_string();
+//! let mut indexer = Indexer::new(client, vec![bridge], JsonSink::open("events.jsonl")?);
+//! for decoded in indexer.poll().await? {
+//!     println!("{:?}", decoded.event);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events are read by contract id, at most 25 at a time. Per-order
+//! escrows publish from their own addresses, which the factory's
+//! `EscrowDeployed` events name.
+
+mod event;
+pub mod json;
+pub mod sink;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use soroban_sdk::testutils::EnvTestConfig;
+use soroban_sdk::xdr::{Limits, ReadXdr, ScVal};
+use soroban_sdk::Env;
+use stellhydra_rpc::{Client, Event, EventCursor};
+
+pub use event::StellHydraEvent;
+pub use sink::Sink;
+
+/// An event as sinks store it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexedEvent {
+    /// RPC paging token, unique per event
+    pub id: String,
+    pub ledger: u32,
+    /// Contract that published the event (C...)
+    pub contract_id: String,
+    /// Contract family, the first topic
+    pub contract: String,
+    /// Event name, the second topic
+    pub name: String,
+    pub version: u32,
+    /// The event's fields
+    pub data: serde_json::Value,
+}
+
+/// An event decoded both ways
+#[derive(Clone, Debug)]
+pub struct Decoded {
+    pub record: IndexedEvent,
+    pub event: StellHydraEvent,
+}
+
+/// Decodes RPC events
+///
+/// Typed events hold host values, so they live as long as the decoder's
+/// environment.
+pub struct Decoder {
+    env: Env,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        // The host only decodes here, so there's nothing worth a snapshot
+        Decoder {
+            env: Env::new_with_config(EnvTestConfig {
+                capture_snapshot_at_drop: false,
+            }),
+        }
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Decode a StellHydra event in a schema version this build knows;
+    /// `None` for any other event
+    pub fn decode(&self, event: &Event) -> Option<Decoded> {
+        let topics = event
+            .topic
+            .iter()
+            .map(|topic| ScVal::from_xdr_base64(topic, Limits::none()).ok())
+            .collect::<Option<Vec<_>>>()?;
+        let data = ScVal::from_xdr_base64(&event.value, Limits::none()).ok()?;
+        let typed = StellHydraEvent::decode(&self.env, &topics, &data)?;
+
+        // Decoding checked the topics, so the first three are known
+        let (ScVal::Symbol(contract), ScVal::Symbol(name), ScVal::U32(version)) =
+            (&topics[0], &topics[1], &topics[2])
+        else {
+            return None;
+        };
+        Some(Decoded {
+            record: IndexedEvent {
+                id: event.id.clone(),
+                ledger: event.ledger,
+                contract_id: event.contract_id.clone(),
+                contract: contract.to_utf8_string_lossy(),
+                name: name.to_utf8_string_lossy(),
+                version: *version,
+                data: json::to_json(&data),
+            },
+            event: typed,
+        })
+    }
+}
+
+/// Reads the events of a set of contracts into a sink
+pub struct Indexer<S> {
+    client: Client,
+    contracts: Vec<String>,
+    decoder: Decoder,
+    sink: S,
+    /// Ledger to start from while the sink has no cursor
+    start_ledger: Option<u32>,
+}
+
+impl<S: Sink> Indexer<S> {
+    pub fn new(client: Client, contracts: Vec<String>, sink: S) -> Self {
+        Indexer {
+            client,
+            contracts,
+            decoder: Decoder::new(),
+            sink,
+            start_ledger: None,
+        }
+    }
+
+    /// Ledger to start from on first run; the latest by default
+    pub fn start_ledger(mut self, ledger: u32) -> Self {
+        self.start_ledger = Some(ledger);
+        self
+    }
+
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Read the next page of events into the sink, returning the
+    /// StellHydra events among them
+    pub async fn poll(&mut self) -> Result<Vec<Decoded>> {
+        let cursor = self.sink.cursor().await?;
+        let from = match &cursor {
+            Some(id) => EventCursor::After(id),
+            None => {
+                let ledger = match self.start_ledger {
+                    Some(ledger) => ledger,
+                    None => self.client.latest_ledger().await?,
+                };
+                self.start_ledger = Some(ledger);
+                EventCursor::Ledger(ledger)
+            }
+        };
+        let contracts: Vec<&str> = self.contracts.iter().map(String::as_str).collect();
+        let page = self.client.events(&contracts, from).await?;
+
+        let mut decoded = vec![];
+        for event in &page.events {
+            match self.decoder.decode(event) {
+                Some(event) => decoded.push(event),
+                None => tracing::debug!("Skipped event {} of {}", event.id, event.contract_id),
+            }
+        }
+        if let Some(last) = page.events.last() {
+            let records: Vec<IndexedEvent> =
+                decoded.iter().map(|event| event.record.clone()).collect();
+            self.sink.write(&records, &last.id).await?;
+        }
+        Ok(decoded)
+    }
+
+    /// Poll until the task is dropped, pausing `interval` once caught up
+    /// and after errors, which are logged and retried
+    pub async fn run(&mut self, interval: Duration) {
+        loop {
+            match self.poll().await {
+                Ok(events) if !events.is_empty() => continue,
+                Ok(_) => {}
+                Err(error) => tracing::warn!("Indexer: {error:#}"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::xdr::{ScAddress, WriteXdr};
+    use soroban_sdk::{Address, BytesN, Symbol, TryFromVal, Val};
+    use stellhydra_events::bridge::RequestCreated;
+    use stellhydra_events::escrow::EscrowRefunded;
+    use stellhydra_rpc::address_string;
+
+    /// An event as RPC would return it
+    fn rpc_event<E: stellhydra_events::Event>(
+        env: &Env,
+        id: &str,
+        contract: &Address,
+        event: E,
+    ) -> Event {
+        let base64 = |val: Val| {
+            ScVal::try_from_val(env, &val)
+                .unwrap()
+                .to_xdr_base64(Limits::none())
+                .unwrap()
+        };
+
+        Event {
+            id: id.into(),
+            ledger: 1,
+            contract_id: address_string(&ScAddress::from(contract)),
+            topic: event.topics(env).iter().map(base64).collect(),
+            value: base64(event.into_val(env)),
+        }
+    }
+
+    #[test]
+    fn test_decodes_typed_events_and_records() {
+        let decoder = Decoder::new();
+        let env = decoder.env();
+        let (bridge, user, token) = (
+            Address::generate(env),
+            Address::generate(env),
+            Address::generate(env),
+        );
+        let created = RequestCreated {
+            request_id: 7,
+            user: user.clone(),
+            from_token: token.clone(),
+            to_chain: Symbol::new(env, "ETH"),
+            to_address: BytesN::from_array(env, &[0xab; 32]),
+            amount: 1_000_000,
+            fee: 10_000,
+            priority_fee: 0,
+            timestamp: 42,
+        };
+
+        let decoded = decoder
+            .decode(&rpc_event(
+                env,
+                "0000000001-0000000001",
+                &bridge,
+                created.clone(),
+            ))
+            .unwrap();
+        assert_eq!(decoded.event, StellHydraEvent::RequestCreated(created));
+        assert_eq!(
+            decoded.record,
+            IndexedEvent {
+                id: "0000000001-0000000001".into(),
+                ledger: 1,
+                contract_id: address_string(&ScAddress::from(&bridge)),
+                contract: "BRIDGE".into(),
+                name: "CREATED".into(),
+                version: 1,
+                data: serde_json::json!({
+                    "amount": "1000000",
+                    "fee": "10000",
+                    "from_token": address_string(&ScAddress::from(&token)),
+                    "priority_fee": "0",
+                    "request_id": 7,
+                    "timestamp": 42,
+                    "to_address": "ab".repeat(32),
+                    "to_chain": "ETH",
+                    "user": address_string(&ScAddress::from(&user)),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_skips_other_events() {
+        let decoder = Decoder::new();
+        let env = decoder.env();
+        let contract = Address::generate(env);
+        let mut event = rpc_event(
+            env,
+            "0000000001-0000000001",
+            &contract,
+            EscrowRefunded {
+                order_hash: BytesN::from_array(env, &[3; 32]),
+                amount: 500,
+            },
+        );
+        assert!(decoder.decode(&event).is_some());
+
+        // A schema version this build doesn't know
+        event.topic[2] = ScVal::U32(2).to_xdr_base64(Limits::none()).unwrap();
+        assert!(decoder.decode(&event).is_none());
+
+        event.topic = vec!["not xdr".into()];
+        assert!(decoder.decode(&event).is_none());
+    }
+}
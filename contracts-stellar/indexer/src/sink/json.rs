@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::IndexedEvent;
+
+use super::Sink;
+
+/// Appends events to a JSON lines file, with the cursor kept next to it
+pub struct JsonSink {
+    path: PathBuf,
+    cursor_path: PathBuf,
+    /// Ids of the events in the file
+    seen: HashSet<String>,
+}
+
+impl JsonSink {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let cursor_path = path.with_extension("cursor");
+
+        let mut seen = HashSet::new();
+        match std::fs::File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let event: IndexedEvent = serde_json::from_str(&line?)
+                        .with_context(|| format!("Corrupt event file {}", path.display()))?;
+                    seen.insert(event.id);
+                }
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        Ok(JsonSink {
+            path,
+            cursor_path,
+            seen,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl Sink for JsonSink {
+    async fn cursor(&mut self) -> Result<Option<String>> {
+        match std::fs::read_to_string(&self.cursor_path) {
+            Ok(cursor) => Ok(Some(cursor.trim().to_string())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn write(&mut self, events: &[IndexedEvent], cursor: &str) -> Result<()> {
+        let mut lines = vec![];
+        for event in events {
+            if self.seen.insert(event.id.clone()) {
+                serde_json::to_writer(&mut lines, event)?;
+                lines.push(b'\n');
+            }
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&lines)?;
+        file.sync_data()?;
+
+        let tmp = self.cursor_path.with_extension("cursor.tmp");
+        std::fs::write(&tmp, cursor)?;
+        std::fs::rename(&tmp, &self.cursor_path)?;
+        Ok(())
+    }
+}
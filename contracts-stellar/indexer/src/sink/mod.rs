@@ -0,0 +1,109 @@
+//! Where indexed events go
+//!
+//! A sink stores each page of events together with the cursor after it,
+//! so ingestion resumes from the sink itself. Delivery is at least once:
+//! a crash between the events and the cursor replays the page, and every
+//! sink ignores an event id it already holds.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::IndexedEvent;
+
+mod json;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use json::JsonSink;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresSink;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+
+#[async_trait]
+pub trait Sink: Send {
+    /// RPC paging token of the last event stored, if any
+    async fn cursor(&mut self) -> Result<Option<String>>;
+
+    /// Store a page of events and the cursor to resume after it
+    async fn write(&mut self, events: &[IndexedEvent], cursor: &str) -> Result<()>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn event(id: &str) -> IndexedEvent {
+        IndexedEvent {
+            id: id.into(),
+            ledger: 1,
+            contract_id: "C...".into(),
+            contract: "ESCROW".into(),
+            name: "REFUNDED".into(),
+            version: 1,
+            data: serde_json::json!({ "amount": "500" }),
+        }
+    }
+
+    /// A page written twice, as after a crash before the cursor, is stored
+    /// once
+    async fn check_sink(sink: &mut impl Sink) -> Vec<IndexedEvent> {
+        assert_eq!(sink.cursor().await.unwrap(), None);
+        sink.write(&[event("1"), event("2")], "2").await.unwrap();
+        sink.write(&[event("2"), event("3")], "4").await.unwrap();
+        assert_eq!(sink.cursor().await.unwrap().as_deref(), Some("4"));
+        vec![event("1"), event("2"), event("3")]
+    }
+
+    #[tokio::test]
+    async fn test_json_sink() {
+        let path = std::env::temp_dir().join(format!("indexer-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("cursor"));
+
+        let expected = check_sink(&mut JsonSink::open(&path).unwrap()).await;
+        // Reopened, it still knows the events it holds
+        let mut sink = JsonSink::open(&path).unwrap();
+        sink.write(&[event("3")], "4").await.unwrap();
+
+        let stored: Vec<IndexedEvent> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(stored, expected);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("cursor")).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_sink() {
+        let mut sink = SqliteSink::new(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+        let expected = check_sink(&mut sink).await;
+
+        let mut statement = sink
+            .connection()
+            .prepare("SELECT id, ledger, contract_id, contract, name, version, data FROM events ORDER BY id")
+            .unwrap();
+        let stored: Vec<IndexedEvent> = statement
+            .query_map([], |row| {
+                Ok(IndexedEvent {
+                    id: row.get(0)?,
+                    ledger: row.get(1)?,
+                    contract_id: row.get(2)?,
+                    contract: row.get(3)?,
+                    name: row.get(4)?,
+                    version: row.get(5)?,
+                    data: serde_json::from_str(&row.get::<_, String>(6)?).unwrap(),
+                })
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(stored, expected);
+    }
+}
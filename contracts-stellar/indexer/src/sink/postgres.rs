@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio_postgres::{Client, NoTls};
+
+use crate::IndexedEvent;
+
+use super::Sink;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    ledger BIGINT NOT NULL,
+    contract_id TEXT NOT NULL,
+    contract TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_kind ON events (contract, name, ledger);
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    event TEXT NOT NULL
+);
+";
+
+/// Stores events in PostgreSQL, data as JSONB
+pub struct PostgresSink {
+    client: Client,
+}
+
+impl PostgresSink {
+    /// Connect without TLS, for a database on the same host or network;
+    /// use [`PostgresSink::new`] with a client of your own otherwise
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls)
+            .await
+            .context("Cannot connect to PostgreSQL")?;
+        tokio::spawn(async move {
+            if let Err(error) = connection.await {
+                tracing::error!("PostgreSQL connection: {error}");
+            }
+        });
+        Self::new(client).await
+    }
+
+    pub async fn new(client: Client) -> Result<Self> {
+        client.batch_execute(SCHEMA).await?;
+        Ok(PostgresSink { client })
+    }
+}
+
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn cursor(&mut self) -> Result<Option<String>> {
+        let row = self
+            .client
+            .query_opt("SELECT event FROM cursor WHERE id = 0", &[])
+            .await?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn write(&mut self, events: &[IndexedEvent], cursor: &str) -> Result<()> {
+        let tx = self.client.transaction().await?;
+        let insert = tx
+            .prepare(
+                "INSERT INTO events (id, ledger, contract_id, contract, name, version, data)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (id) DO NOTHING",
+            )
+            .await?;
+        for event in events {
+            tx.execute(
+                &insert,
+                &[
+                    &event.id,
+                    &i64::from(event.ledger),
+                    &event.contract_id,
+                    &event.contract,
+                    &event.name,
+                    &i32::try_from(event.version)?,
+                    &event.data,
+                ],
+            )
+            .await?;
+        }
+        tx.execute(
+            "INSERT INTO cursor (id, event) VALUES (0, $1)
+             ON CONFLICT (id) DO UPDATE SET event = excluded.event",
+            &[&cursor],
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::IndexedEvent;
+
+use super::Sink;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    ledger INTEGER NOT NULL,
+    contract_id TEXT NOT NULL,
+    contract TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_kind ON events (contract, name, ledger);
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    event TEXT NOT NULL
+);
+";
+
+/// Stores events in an SQLite database, data as JSON text
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(Connection::open(path)?)
+    }
+
+    pub fn new(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteSink { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+#[async_trait]
+impl Sink for SqliteSink {
+    async fn cursor(&mut self) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row("SELECT event FROM cursor WHERE id = 0", [], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    async fn write(&mut self, events: &[IndexedEvent], cursor: &str) -> Result<()> {
+        let tx = self.connection.transaction()?;
+        for event in events {
+            tx.execute(
+                "INSERT OR IGNORE INTO events (id, ledger, contract_id, contract, name, version, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    event.id,
+                    event.ledger,
+                    event.contract_id,
+                    event.contract,
+                    event.name,
+                    event.version,
+                    event.data.to_string(),
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO cursor (id, event) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET event = excluded.event",
+            params![cursor],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
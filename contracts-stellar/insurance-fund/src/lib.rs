@@ -0,0 +1,484 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Bytes, Env,
+    Symbol, Vec,
+};
+
+/// Product the fund covers, such as the bridge or the escrows
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Product {
+    pub name: Symbol,
+    pub exposure: i128, // value at risk the fund is meant to cover
+    pub paid_out: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClaimStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Paid,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Claim {
+    pub id: u64,
+    pub claimant: Address,
+    pub product: Symbol,
+    pub reference: Bytes, // bridge request or escrow id the loss happened on
+    pub amount: i128,     // requested, then approved
+    pub status: ClaimStatus,
+    pub submitted_at: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    ProductNotFound = 4,
+    ClaimNotFound = 5,
+    ClaimExists = 6,
+    InvalidStatus = 7,
+    InsufficientFunds = 8,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const TOKEN: Symbol = symbol_short!("TOKEN");
+const PRODUCTS: Symbol = symbol_short!("PRODUCTS");
+const RESERVED: Symbol = symbol_short!("RESERVED");
+const FEES: Symbol = symbol_short!("FEES");
+const CLAIM_CNT: Symbol = symbol_short!("CLAIM_CNT");
+const CLAIM: Symbol = symbol_short!("CLAIM");
+const CLAIMED: Symbol = symbol_short!("CLAIMED");
+const DEPOSITED: Symbol = symbol_short!("DEPOSITED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// Coverage ratios are expressed in basis points
+const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Insurance fund backing users of the protocol's products
+///
+/// Fed by fee streams (as a fee-distributor recipient) and voluntary
+/// deposits. Losses are claimed against a product and a bridge request
+/// or escrow id; the admin, typically governance, approves and sets the
+/// payout, which is then reserved until paid.
+#[contract]
+pub struct InsuranceFund;
+
+#[contractimpl]
+impl InsuranceFund {
+    pub fn initialize(env: Env, admin: Address, token: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&TOKEN, &token);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Add a product or update its exposure (only admin)
+    pub fn set_product(env: Env, name: Symbol, exposure: i128) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        if exposure < 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let mut products = Self::get_products(env.clone());
+        let product = match products.iter().position(|p| p.name == name) {
+            Some(index) => {
+                let mut product = products.get(index as u32).unwrap();
+                product.exposure = exposure;
+                products.set(index as u32, product.clone());
+                product
+            }
+            None => {
+                let product = Product {
+                    name: name.clone(),
+                    exposure,
+                    paid_out: 0,
+                };
+                products.push_back(product.clone());
+                product
+            }
+        };
+        env.storage().instance().set(&PRODUCTS, &products);
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("PRODUCT"), name),
+            product.exposure,
+        );
+        Ok(())
+    }
+
+    pub fn get_products(env: Env) -> Vec<Product> {
+        env.storage()
+            .instance()
+            .get(&PRODUCTS)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Voluntary contribution to the fund
+    pub fn deposit(env: Env, from: Address, amount: i128) -> Result<(), Error> {
+        from.require_auth();
+        Self::pull(&env, &from, amount)?;
+
+        let key = (DEPOSITED, from.clone());
+        let deposited: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(deposited + amount));
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("DEPOSIT")),
+            (from, amount),
+        );
+        Ok(())
+    }
+
+    /// Pull a fee stream's share from `funder`, as a fee-distributor
+    /// recipient
+    pub fn notify_reward(env: Env, funder: Address, amount: i128) -> Result<(), Error> {
+        Self::pull(&env, &funder, amount)?;
+        let fees: i128 = env.storage().instance().get(&FEES).unwrap_or(0);
+        env.storage().instance().set(&FEES, &(fees + amount));
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("FEES")),
+            (funder, amount),
+        );
+        Ok(())
+    }
+
+    /// File a claim for a loss on a product
+    pub fn submit_claim(
+        env: Env,
+        claimant: Address,
+        product: Symbol,
+        reference: Bytes,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        claimant.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if !Self::get_products(env.clone())
+            .iter()
+            .any(|p| p.name == product)
+        {
+            return Err(Error::ProductNotFound);
+        }
+        // One live claim per loss; a rejected one can be filed again
+        let claimed_key = (CLAIMED, product.clone(), reference.clone());
+        if env.storage().persistent().has(&claimed_key) {
+            return Err(Error::ClaimExists);
+        }
+
+        let id: u64 = env.storage().instance().get(&CLAIM_CNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&CLAIM_CNT, &id);
+        Self::extend_instance(&env);
+
+        let claim = Claim {
+            id,
+            claimant: claimant.clone(),
+            product: product.clone(),
+            reference: reference.clone(),
+            amount,
+            status: ClaimStatus::Pending,
+            submitted_at: env.ledger().timestamp(),
+        };
+        Self::save_claim(&env, &claim);
+        env.storage().persistent().set(&claimed_key, &id);
+        env.storage().persistent().extend_ttl(
+            &claimed_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("CLAIM"), id),
+            (claimant, product, reference, amount),
+        );
+        Ok(id)
+    }
+
+    /// Approve a pending claim for `payout`, at most the amount
+    /// requested, and reserve it (only admin)
+    pub fn approve_claim(env: Env, id: u64, payout: i128) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let mut claim = Self::get_claim(env.clone(), id)?;
+        if claim.status != ClaimStatus::Pending {
+            return Err(Error::InvalidStatus);
+        }
+        if payout <= 0 || payout > claim.amount {
+            return Err(Error::InvalidAmount);
+        }
+        if payout > Self::get_available(env.clone())? {
+            return Err(Error::InsufficientFunds);
+        }
+
+        let reserved: i128 = env.storage().instance().get(&RESERVED).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&RESERVED, &(reserved + payout));
+        claim.amount = payout;
+        claim.status = ClaimStatus::Approved;
+        Self::save_claim(&env, &claim);
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("APPROVED"), id),
+            payout,
+        );
+        Ok(())
+    }
+
+    /// Reject a pending claim (only admin)
+    pub fn reject_claim(env: Env, id: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let mut claim = Self::get_claim(env.clone(), id)?;
+        if claim.status != ClaimStatus::Pending {
+            return Err(Error::InvalidStatus);
+        }
+
+        claim.status = ClaimStatus::Rejected;
+        Self::save_claim(&env, &claim);
+        env.storage()
+            .persistent()
+            .remove(&(CLAIMED, claim.product, claim.reference));
+
+        env.events()
+            .publish((symbol_short!("INSURE"), symbol_short!("REJECTED"), id), ());
+        Ok(())
+    }
+
+    /// Pay an approved claim to its claimant
+    pub fn pay_claim(env: Env, id: u64) -> Result<(), Error> {
+        let mut claim = Self::get_claim(env.clone(), id)?;
+        if claim.status != ClaimStatus::Approved {
+            return Err(Error::InvalidStatus);
+        }
+
+        claim.status = ClaimStatus::Paid;
+        Self::save_claim(&env, &claim);
+        let reserved: i128 = env.storage().instance().get(&RESERVED).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&RESERVED, &(reserved - claim.amount));
+
+        let mut products = Self::get_products(env.clone());
+        if let Some(index) = products.iter().position(|p| p.name == claim.product) {
+            let mut product = products.get(index as u32).unwrap();
+            product.paid_out += claim.amount;
+            products.set(index as u32, product);
+            env.storage().instance().set(&PRODUCTS, &products);
+        }
+
+        let token: Address = env.storage().instance().get(&TOKEN).unwrap();
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &claim.claimant,
+            &claim.amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("INSURE"), symbol_short!("PAID"), id),
+            (claim.claimant, claim.amount),
+        );
+        Ok(())
+    }
+
+    pub fn get_claim(env: Env, id: u64) -> Result<Claim, Error> {
+        env.storage()
+            .persistent()
+            .get(&(CLAIM, id))
+            .ok_or(Error::ClaimNotFound)
+    }
+
+    pub fn get_claim_count(env: Env) -> u64 {
+        env.storage().instance().get(&CLAIM_CNT).unwrap_or(0)
+    }
+
+    /// Funds held, including those reserved for approved claims
+    pub fn get_balance(env: Env) -> Result<i128, Error> {
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&TOKEN)
+            .ok_or(Error::NotInitialized)?;
+        Ok(token::Client::new(&env, &token).balance(&env.current_contract_address()))
+    }
+
+    /// Funds not reserved for approved claims
+    pub fn get_available(env: Env) -> Result<i128, Error> {
+        let reserved: i128 = env.storage().instance().get(&RESERVED).unwrap_or(0);
+        Ok(Self::get_balance(env)? - reserved)
+    }
+
+    /// Available funds over a product's exposure, in basis points
+    pub fn get_coverage_ratio(env: Env, product: Symbol) -> Result<i128, Error> {
+        let product = Self::get_products(env.clone())
+            .iter()
+            .find(|p| p.name == product)
+            .ok_or(Error::ProductNotFound)?;
+        if product.exposure == 0 {
+            return Ok(0);
+        }
+        Ok(Self::get_available(env)? * BPS_DENOMINATOR / product.exposure)
+    }
+
+    /// Total received from fee streams
+    pub fn get_fee_income(env: Env) -> i128 {
+        env.storage().instance().get(&FEES).unwrap_or(0)
+    }
+
+    /// Total deposited voluntarily by an account
+    pub fn get_deposited(env: Env, account: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(DEPOSITED, account))
+            .unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for InsuranceFund {}
+
+stellhydra_access::upgradeable!(InsuranceFund);
+
+impl InsuranceFund {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn pull(env: &Env, from: &Address, amount: i128) -> Result<(), Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&TOKEN)
+            .ok_or(Error::NotInitialized)?;
+        token::Client::new(env, &token).transfer(from, &env.current_contract_address(), &amount);
+        Ok(())
+    }
+
+    fn save_claim(env: &Env, claim: &Claim) {
+        let key = (CLAIM, claim.id);
+        env.storage().persistent().set(&key, claim);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Env};
+
+    fn setup(env: &Env) -> (InsuranceFundClient<'_>, Address) {
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let fund = InsuranceFundClient::new(env, &env.register_contract(None, InsuranceFund));
+        fund.initialize(&Address::generate(env), &token);
+        fund.set_product(&symbol_short!("bridge"), &100_000);
+        fund.set_product(&symbol_short!("escrow"), &20_000);
+
+        (fund, token)
+    }
+
+    #[test]
+    fn test_fund_grows_from_fees_and_deposits() {
+        let env = Env::default();
+        let (fund, token) = setup(&env);
+        // The distributor authorizes the pull as a sub-invocation
+        env.mock_all_auths_allowing_non_root_auth();
+        let distributor = Address::generate(&env);
+        let backer = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&distributor, &6_000);
+        StellarAssetClient::new(&env, &token).mint(&backer, &4_000);
+
+        fund.notify_reward(&distributor, &6_000);
+        fund.deposit(&backer, &4_000);
+        assert_eq!(fund.get_balance(), 10_000);
+        assert_eq!(fund.get_fee_income(), 6_000);
+        assert_eq!(fund.get_deposited(&backer), 4_000);
+
+        assert_eq!(fund.get_coverage_ratio(&symbol_short!("bridge")), 1_000);
+        assert_eq!(fund.get_coverage_ratio(&symbol_short!("escrow")), 5_000);
+        assert_eq!(
+            fund.try_get_coverage_ratio(&symbol_short!("pool")),
+            Err(Ok(Error::ProductNotFound))
+        );
+    }
+
+    #[test]
+    fn test_claims_are_approved_reserved_and_paid() {
+        let env = Env::default();
+        let (fund, token) = setup(&env);
+        StellarAssetClient::new(&env, &token).mint(&fund.address, &10_000);
+        let claimant = Address::generate(&env);
+        let reference = Bytes::from_array(&env, &[9; 32]);
+
+        let id = fund.submit_claim(&claimant, &symbol_short!("bridge"), &reference, &3_000);
+        assert_eq!(
+            fund.try_submit_claim(&claimant, &symbol_short!("bridge"), &reference, &3_000),
+            Err(Ok(Error::ClaimExists))
+        );
+        assert_eq!(
+            fund.try_approve_claim(&id, &4_000),
+            Err(Ok(Error::InvalidAmount))
+        );
+
+        fund.approve_claim(&id, &2_500);
+        assert_eq!(fund.get_available(), 7_500);
+        fund.pay_claim(&id);
+        assert_eq!(token::Client::new(&env, &token).balance(&claimant), 2_500);
+        assert_eq!(fund.get_claim(&id).status, ClaimStatus::Paid);
+        assert_eq!(fund.get_products().get(0).unwrap().paid_out, 2_500);
+        assert_eq!(fund.try_pay_claim(&id), Err(Ok(Error::InvalidStatus)));
+
+        // A rejected claim frees its reference for a new one
+        let other = Bytes::from_array(&env, &[8; 32]);
+        let rejected = fund.submit_claim(&claimant, &symbol_short!("escrow"), &other, &100);
+        fund.reject_claim(&rejected);
+        fund.submit_claim(&claimant, &symbol_short!("escrow"), &other, &100);
+        assert_eq!(fund.get_claim_count(), 3);
+    }
+}
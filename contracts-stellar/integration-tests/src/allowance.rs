@@ -0,0 +1,94 @@
+use soroban_sdk::{symbol_short, token, Address, BytesN, Env};
+use stellhydra_allowance_manager::{Allowance, AllowanceManager, AllowanceManagerClient};
+
+use crate::setup::Protocol;
+
+/// Whether `user` signed for a token transfer nested in the last call,
+/// rather than just the call itself
+fn signed_transfer(env: &Env, user: &Address) -> bool {
+    env.auths()
+        .iter()
+        .any(|(address, invocation)| address == user && !invocation.sub_invocations.is_empty())
+}
+
+#[test]
+fn test_one_approval_serves_pool_router_and_bridge() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+
+    let manager = AllowanceManagerClient::new(&env, &env.register_contract(None, AllowanceManager));
+    manager.initialize(&protocol.admin);
+    let follow = Some(manager.address.clone());
+    protocol.pool.set_allowance_manager(&follow);
+    protocol.router.set_allowance_manager(&follow);
+    protocol.bridge.set_allowance_manager(&follow);
+
+    let provider = protocol.funded_user(token_a, 1_000_000);
+    protocol.mint(token_b, &provider, 1_000_000);
+    protocol
+        .pool
+        .add_liquidity(&provider, &1_000_000, &1_000_000, &0, &0);
+
+    // One standing approval of the manager, then a scoped allowance per
+    // contract
+    let user = protocol.funded_user(token_a, 100_000);
+    token::Client::new(&env, token_a).approve(&user, &manager.address, &100_000, &100_000);
+    let allowance = Allowance {
+        amount: 20_000,
+        per_call: 10_000,
+        expiration: env.ledger().timestamp() + 3_600,
+    };
+    for spender in [
+        &protocol.pool.address,
+        &protocol.router.address,
+        &protocol.bridge.address,
+    ] {
+        manager.approve(&user, token_a, spender, &allowance);
+    }
+
+    let amount_out = protocol.pool.swap(&user, token_a, &10_000, &0);
+    assert!(!signed_transfer(&env, &user));
+    assert_eq!(protocol.balance(token_b, &user), amount_out);
+    let left = |spender: &Address| manager.allowance(&user, token_a, spender).unwrap().amount;
+    assert_eq!(left(&protocol.pool.address), 10_000);
+
+    let deadline = env.ledger().timestamp() + 60;
+    let quote = protocol.router.get_amounts_out(&10_000, token_a, token_b);
+    let routed = protocol
+        .router
+        .swap_exact_tokens_for_tokens(&user, &10_000, &quote, token_a, token_b, &deadline);
+    assert!(!signed_transfer(&env, &user));
+    assert_eq!(routed, quote);
+    assert_eq!(protocol.balance(token_b, &user), amount_out + routed);
+    assert_eq!(left(&protocol.router.address), 10_000);
+    assert_eq!(protocol.balance(token_a, &protocol.router.address), 0);
+    assert_eq!(protocol.balance(token_b, &protocol.router.address), 0);
+
+    // amount + 10 + 1% fee
+    let to_address = BytesN::from_array(&env, &[7; 32]);
+    protocol.bridge.create_bridge_request(
+        &user,
+        token_a,
+        &symbol_short!("ETH"),
+        &to_address,
+        &9_000,
+        &None,
+    );
+    assert!(!signed_transfer(&env, &user));
+    assert_eq!(left(&protocol.bridge.address), 20_000 - 9_100);
+
+    // Past the per-call limit the user signs the transfer as before
+    protocol.pool.swap(&user, token_a, &15_000, &0);
+    assert!(signed_transfer(&env, &user));
+    assert_eq!(left(&protocol.pool.address), 10_000);
+
+    // Revoking one contract leaves the others pulling
+    manager.revoke(&user, token_a, &protocol.pool.address);
+    protocol.pool.swap(&user, token_a, &1_000, &0);
+    assert!(signed_transfer(&env, &user));
+    protocol
+        .router
+        .swap_exact_tokens_for_tokens(&user, &1_000, &0, token_a, token_b, &deadline);
+    assert!(!signed_transfer(&env, &user));
+}
@@ -0,0 +1,96 @@
+use soroban_sdk::{symbol_short, testutils::Address as _, Address, BytesN, Env};
+
+use crate::setup::{Protocol, BASE_FEE, FEE_BPS};
+
+fn fee(amount: i128) -> i128 {
+    BASE_FEE + amount * FEE_BPS as i128 / 10_000
+}
+
+#[test]
+fn test_bridge_out_accounts_for_fees_and_relayer_pay() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let token = &protocol.token_a;
+    let user = protocol.funded_user(token, 100_000);
+    let relayer = Address::generate(&env);
+    let to_address = BytesN::from_array(&env, &[7; 32]);
+    let eth = symbol_short!("ETH");
+    protocol.bridge.add_relayer(&relayer);
+
+    let first =
+        protocol
+            .bridge
+            .create_bridge_request(&user, token, &eth, &to_address, &10_000, &Some(25));
+    let second =
+        protocol
+            .bridge
+            .create_bridge_request(&user, token, &eth, &to_address, &4_000, &None);
+
+    // The bridge holds the amounts, the fees and the priority fee
+    let locked = 10_000 + fee(10_000) + 25 + 4_000 + fee(4_000);
+    assert_eq!(protocol.balance(token, &user), 100_000 - locked);
+    assert_eq!(protocol.balance(token, &protocol.bridge.address), locked);
+
+    protocol.bridge.complete_request(&relayer, &first, &None);
+    assert_eq!(protocol.balance(token, &relayer), 25);
+
+    // A failed request refunds its amount but keeps the fee
+    protocol.bridge.update_request_status(&second, &3, &None);
+    protocol.bridge.finalize_failure(&second);
+    assert_eq!(protocol.balance(token, &user), 100_000 - locked + 4_000);
+    assert_eq!(
+        protocol.balance(token, &protocol.bridge.address),
+        10_000 + fee(10_000) + fee(4_000)
+    );
+
+    let stats = protocol.bridge.get_stats();
+    assert_eq!(stats.request_count, 2);
+    assert_eq!(stats.fees_collected, fee(10_000) + fee(4_000));
+    assert_eq!(stats.completed_count, 1);
+    assert_eq!(stats.completed_value, 10_000);
+    assert_eq!(stats.refunded_count, 1);
+    assert_eq!(stats.refunded_value, 4_000);
+
+    let chain_stats = protocol.bridge.get_bridge_stats(&eth);
+    assert_eq!(chain_stats.total_volume, 14_000);
+    assert_eq!(chain_stats.fees_collected, fee(10_000) + fee(4_000));
+}
+
+#[test]
+fn test_swap_proceeds_can_be_bridged_out() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+    let provider = protocol.funded_user(token_a, 1_000_000);
+    protocol.mint(token_b, &provider, 1_000_000);
+    protocol
+        .pool
+        .add_liquidity(&provider, &1_000_000, &1_000_000, &0, &0);
+
+    let user = protocol.funded_user(token_a, 50_000);
+    let deadline = env.ledger().timestamp() + 60;
+    let received = protocol
+        .router
+        .swap_exact_tokens_for_tokens(&user, &50_000, &0, token_a, token_b, &deadline);
+
+    // Bridge out everything the swap paid, net of the bridge fee
+    let amount = (received - BASE_FEE) * 10_000 / (10_000 + FEE_BPS as i128);
+    let to_address = BytesN::from_array(&env, &[9; 32]);
+    let request_id = protocol.bridge.create_bridge_request(
+        &user,
+        token_b,
+        &symbol_short!("ETH"),
+        &to_address,
+        &amount,
+        &None,
+    );
+
+    let request = protocol.bridge.get_bridge_request(&request_id).unwrap();
+    assert_eq!(request.from_token, *token_b);
+    assert_eq!(request.fee, fee(amount));
+    assert_eq!(
+        protocol.balance(token_b, &user),
+        received - amount - fee(amount)
+    );
+    assert!(protocol.balance(token_b, &user) < 2);
+}
@@ -0,0 +1,327 @@
+//! Resource costs of the public entrypoints at realistic state sizes
+//!
+//! Each entrypoint is called once with the budget reset around it, and the
+//! CPU instructions and memory it was charged are checked against a limit
+//! recorded here. A change that makes a call more expensive fails the test
+//! until its limit is raised on purpose. Run with `--nocapture` to see the
+//! measured costs.
+//!
+//! Contracts run natively in tests, so the figures cover host work
+//! (storage, crypto, cross-contract calls, (de)serialization) and
+//! underestimate what the same call costs as wasm on the network.
+
+use soroban_sdk::{
+    symbol_short,
+    testutils::{Address as _, Ledger},
+    Address, Bytes, BytesN, Env, String,
+};
+use stellar_eth_escrow::{HashAlgo, TimeLocks};
+use stellhydra_mock_dex::{Curve, MockDex, MockDexClient};
+
+use crate::setup::{env_without_snapshot, Protocol};
+
+/// Escrows open before the measured calls
+const ESCROWS: u32 = 1_000;
+/// DEXes registered on the router before the measured calls
+const DEXES: u32 = 50;
+/// Bridge requests open before the measured calls
+const REQUESTS: u32 = 1_000;
+
+/// Most a single call may be charged
+struct Limit {
+    cpu: u64,
+    mem: u64,
+}
+
+const fn limit(cpu: u64, mem: u64) -> Limit {
+    Limit { cpu, mem }
+}
+
+/// Run `call` on a fresh budget and check what it was charged
+fn measure<T>(env: &Env, name: &str, limit: Limit, call: impl FnOnce() -> T) -> T {
+    env.budget().reset_unlimited();
+    let result = call();
+    let cpu = env.budget().cpu_instruction_cost();
+    let mem = env.budget().memory_bytes_cost();
+    std::println!("{name:<36} cpu {cpu:>12} mem {mem:>12}");
+
+    assert!(
+        cpu <= limit.cpu,
+        "{name} used {cpu} cpu, limit {}",
+        limit.cpu
+    );
+    assert!(
+        mem <= limit.mem,
+        "{name} used {mem} bytes, limit {}",
+        limit.mem
+    );
+    result
+}
+
+fn secret(env: &Env, i: u32) -> Bytes {
+    let mut bytes = [0u8; 32];
+    bytes[..4].copy_from_slice(&i.to_be_bytes());
+    Bytes::from_array(env, &bytes)
+}
+
+// Each escrow has its own persistent entry and the maker index is paged,
+// so these costs stay flat however many escrows were created before
+#[test]
+fn test_escrow_entrypoint_costs() {
+    let env = env_without_snapshot();
+    let protocol = Protocol::deploy(&env);
+    let escrow = &protocol.escrow;
+    let token = &protocol.token_a;
+    let maker = protocol.funded_user(token, 1_000_000_000);
+    let resolver = Address::generate(&env);
+    let locks = TimeLocks {
+        withdrawal: 0,
+        public_withdrawal: 1_800,
+        cancellation: 3_600,
+        public_cancellation: 7_200,
+    };
+
+    env.budget().reset_unlimited();
+    let mut ids = std::vec::Vec::new();
+    for i in 0..ESCROWS {
+        let hash_lock = env.crypto().sha256(&secret(&env, i)).into();
+        ids.push(escrow.create_escrow(
+            &maker,
+            &None,
+            &1_000,
+            token,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &locks,
+            &(i as u64),
+        ));
+    }
+
+    let hash_lock = env.crypto().sha256(&secret(&env, ESCROWS)).into();
+    measure(
+        &env,
+        "escrow.create_escrow",
+        limit(18_000_000, 6_500_000),
+        || {
+            escrow.create_escrow(
+                &maker,
+                &None,
+                &1_000,
+                token,
+                &hash_lock,
+                &HashAlgo::Sha256,
+                &locks,
+                &(ESCROWS as u64),
+            )
+        },
+    );
+    measure(
+        &env,
+        "escrow.lock_escrow",
+        limit(5_000_000, 2_000_000),
+        || escrow.lock_escrow(&ids[0], &resolver),
+    );
+    measure(
+        &env,
+        "escrow.complete_escrow",
+        limit(10_000_000, 3_500_000),
+        || escrow.complete_escrow(&ids[0], &secret(&env, 0), &resolver),
+    );
+    measure(&env, "escrow.get_escrow", limit(500_000, 500_000), || {
+        escrow.get_escrow(&ids[1])
+    });
+    measure(
+        &env,
+        "escrow.get_escrows_by_maker",
+        limit(5_000_000, 1_000_000),
+        || escrow.get_escrows_by_maker(&maker, &0, &50),
+    );
+    measure(&env, "escrow.get_stats", limit(500_000, 500_000), || {
+        escrow.get_stats()
+    });
+
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+    measure(
+        &env,
+        "escrow.refund_escrow",
+        limit(7_000_000, 3_000_000),
+        || escrow.refund_escrow(&ids[1]),
+    );
+}
+
+#[test]
+fn test_router_entrypoint_costs() {
+    let env = env_without_snapshot();
+    let protocol = Protocol::deploy(&env);
+    let router = &protocol.router;
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+
+    // The pool from the deployment plus adapters quoting slightly better
+    // than it, and than each other the later they were registered, so the
+    // last one wins
+    env.budget().reset_unlimited();
+    let provider = protocol.funded_user(token_a, 1_000_000_000);
+    protocol.mint(token_b, &provider, 1_000_000_000);
+    protocol
+        .pool
+        .add_liquidity(&provider, &1_000_000_000, &1_000_000_000, &0, &0);
+    for i in 1..DEXES {
+        let dex = MockDexClient::new(&env, &env.register_contract(None, MockDex));
+        dex.set_curve(token_a, token_b, &Curve::Linear(10_000 + i as i128));
+        protocol.mint(token_b, &dex.address, 1_000_000_000);
+        router.register_dex(
+            &String::from_str(&env, &std::format!("dex-{i}")),
+            &dex.address,
+            &String::from_str(&env, "adapter"),
+            &30,
+        );
+    }
+
+    let user = protocol.funded_user(token_a, 1_000_000);
+    let extra = MockDexClient::new(&env, &env.register_contract(None, MockDex));
+    let extra_id = String::from_str(&env, "extra");
+    let adapter = String::from_str(&env, "adapter");
+    measure(
+        &env,
+        "router.register_dex",
+        limit(1_500_000, 360_000),
+        || router.register_dex(&extra_id, &extra.address, &adapter, &30),
+    );
+    measure(
+        &env,
+        "router.set_dex_status",
+        limit(1_500_000, 360_000),
+        || router.set_dex_status(&extra_id, &false),
+    );
+    measure(&env, "router.get_dexes", limit(800_000, 170_000), || {
+        router.get_dexes()
+    });
+    measure(
+        &env,
+        "router.find_best_route",
+        limit(3_100_000, 1_500_000),
+        || router.find_best_route(token_a, token_b, &10_000),
+    );
+    measure(
+        &env,
+        "router.get_amounts_out",
+        limit(3_100_000, 1_500_000),
+        || router.get_amounts_out(&10_000, token_a, token_b),
+    );
+    let received = measure(
+        &env,
+        "router.swap_exact_tokens_for_tokens",
+        limit(4_400_000, 2_000_000),
+        || router.swap_exact_tokens_for_tokens(&user, &10_000, &0, token_a, token_b, &60),
+    );
+    assert_eq!(received, 10_000 + DEXES as i128 - 1);
+}
+
+// Calls that change liquidity shares also write their voting checkpoints
+#[test]
+fn test_pool_entrypoint_costs() {
+    let env = env_without_snapshot();
+    let protocol = Protocol::deploy(&env);
+    let pool = &protocol.pool;
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+    let provider = protocol.funded_user(token_a, 2_000_000);
+    protocol.mint(token_b, &provider, 2_000_000);
+    let other = Address::generate(&env);
+
+    measure(
+        &env,
+        "pool.add_liquidity (first)",
+        limit(760_000, 150_000),
+        || pool.add_liquidity(&provider, &1_000_000, &1_000_000, &0, &0),
+    );
+    measure(&env, "pool.add_liquidity", limit(750_000, 125_000), || {
+        pool.add_liquidity(&provider, &500_000, &500_000, &0, &0)
+    });
+    measure(&env, "pool.get_amount_out", limit(40_000, 8_000), || {
+        pool.get_amount_out(token_a, token_b, &10_000)
+    });
+    measure(&env, "pool.swap", limit(530_000, 90_000), || {
+        pool.swap(&provider, token_a, &10_000, &0)
+    });
+    measure(&env, "pool.transfer_shares", limit(300_000, 68_000), || {
+        pool.transfer_shares(&provider, &other, &1_000)
+    });
+    measure(
+        &env,
+        "pool.remove_liquidity",
+        limit(790_000, 133_000),
+        || pool.remove_liquidity(&provider, &100_000, &0, &0),
+    );
+    measure(&env, "pool.get_pool_info", limit(40_000, 9_000), || {
+        pool.get_pool_info()
+    });
+    measure(&env, "pool.get_position", limit(55_000, 9_500), || {
+        pool.get_position(&provider)
+    });
+}
+
+#[test]
+fn test_bridge_request_entrypoint_costs() {
+    let env = env_without_snapshot();
+    let protocol = Protocol::deploy(&env);
+    let bridge = &protocol.bridge;
+    let token = &protocol.token_a;
+    let user = protocol.funded_user(token, 1_000_000_000);
+    let relayer = Address::generate(&env);
+    let to_address = BytesN::from_array(&env, &[7; 32]);
+    let eth = symbol_short!("ETH");
+    bridge.add_relayer(&relayer);
+
+    env.budget().reset_unlimited();
+    let mut ids = std::vec::Vec::new();
+    for _ in 0..REQUESTS {
+        ids.push(bridge.create_bridge_request(&user, token, &eth, &to_address, &1_000, &None));
+    }
+
+    measure(
+        &env,
+        "bridge.create_bridge_request",
+        limit(6_400_000, 2_800_000),
+        || bridge.create_bridge_request(&user, token, &eth, &to_address, &1_000, &Some(5)),
+    );
+    measure(
+        &env,
+        "bridge.get_bridge_request",
+        limit(140_000, 190_000),
+        || bridge.get_bridge_request(&ids[0]),
+    );
+    measure(
+        &env,
+        "bridge.complete_request",
+        limit(7_600_000, 3_000_000),
+        || bridge.complete_request(&relayer, &ids[0], &None),
+    );
+    measure(
+        &env,
+        "bridge.update_request_status",
+        limit(5_000_000, 2_100_000),
+        || bridge.update_request_status(&ids[1], &3, &None),
+    );
+    measure(
+        &env,
+        "bridge.retry_request",
+        limit(6_100_000, 2_500_000),
+        || bridge.retry_request(&ids[1]),
+    );
+    bridge.update_request_status(&ids[1], &3, &None);
+    measure(
+        &env,
+        "bridge.finalize_failure",
+        limit(4_700_000, 2_100_000),
+        || bridge.finalize_failure(&ids[1]),
+    );
+    measure(&env, "bridge.get_stats", limit(150_000, 190_000), || {
+        bridge.get_stats()
+    });
+    measure(
+        &env,
+        "bridge.get_bridge_stats",
+        limit(150_000, 190_000),
+        || bridge.get_bridge_stats(&eth),
+    );
+}
@@ -0,0 +1,112 @@
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Bytes, Env,
+};
+use stellar_eth_escrow::{Error, HashAlgo, TimeLocks};
+
+use crate::setup::Protocol;
+
+fn time_locks(env: &Env) -> TimeLocks {
+    let now = env.ledger().timestamp();
+    TimeLocks {
+        withdrawal: now,
+        public_withdrawal: now + 1_800,
+        cancellation: now + 3_600,
+        public_cancellation: now + 7_200,
+    }
+}
+
+#[test]
+fn test_escrow_create_lock_complete_moves_funds_to_resolver() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let token = &protocol.token_a;
+    let maker = protocol.funded_user(token, 5_000);
+    let resolver = Address::generate(&env);
+
+    let secret = Bytes::from_slice(&env, b"my_secret_32_bytes_exactly_here!");
+    let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+
+    let escrow_id = protocol.escrow.create_escrow(
+        &maker,
+        &None,
+        &2_000,
+        token,
+        &hash_lock,
+        &HashAlgo::Sha256,
+        &time_locks(&env),
+        &0,
+    );
+    assert_eq!(protocol.balance(token, &maker), 3_000);
+    assert_eq!(protocol.balance(token, &protocol.escrow.address), 2_000);
+
+    protocol.escrow.lock_escrow(&escrow_id, &resolver);
+
+    // A wrong secret leaves the funds where they are
+    let wrong = Bytes::from_slice(&env, b"not_the_secret");
+    assert_eq!(
+        protocol
+            .escrow
+            .try_complete_escrow(&escrow_id, &wrong, &resolver),
+        Err(Ok(Error::InvalidSecret))
+    );
+    assert_eq!(protocol.balance(token, &protocol.escrow.address), 2_000);
+
+    protocol
+        .escrow
+        .complete_escrow(&escrow_id, &secret, &resolver);
+    assert_eq!(protocol.balance(token, &resolver), 2_000);
+    assert_eq!(protocol.balance(token, &protocol.escrow.address), 0);
+
+    let escrow = protocol.escrow.get_escrow(&escrow_id).unwrap();
+    assert_eq!(escrow.status, 2);
+    assert_eq!(escrow.secret, Some(secret.clone()));
+
+    // A completed escrow cannot be settled a second time
+    assert_eq!(
+        protocol
+            .escrow
+            .try_complete_escrow(&escrow_id, &secret, &resolver),
+        Err(Ok(Error::InvalidStatus))
+    );
+    assert_eq!(
+        protocol.escrow.try_refund_escrow(&escrow_id),
+        Err(Ok(Error::InvalidStatus))
+    );
+}
+
+#[test]
+fn test_unclaimed_escrow_refunds_maker_after_time_lock() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let token = &protocol.token_b;
+    let maker = protocol.funded_user(token, 1_000);
+    let hash_lock: Bytes = env
+        .crypto()
+        .sha256(&Bytes::from_slice(&env, b"secret"))
+        .into();
+
+    let locks = time_locks(&env);
+    let escrow_id = protocol.escrow.create_escrow(
+        &maker,
+        &None,
+        &1_000,
+        token,
+        &hash_lock,
+        &HashAlgo::Sha256,
+        &locks,
+        &0,
+    );
+    assert_eq!(
+        protocol.escrow.try_refund_escrow(&escrow_id),
+        Err(Ok(Error::TimelockNotExpired))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = locks.cancellation);
+    protocol.escrow.refund_escrow(&escrow_id);
+
+    assert_eq!(protocol.balance(token, &maker), 1_000);
+    assert_eq!(protocol.balance(token, &protocol.escrow.address), 0);
+    assert_eq!(protocol.escrow.get_escrow(&escrow_id).unwrap().status, 3);
+}
@@ -0,0 +1,90 @@
+use soroban_sdk::{symbol_short, testutils::Address as _, vec, Address, Bytes, BytesN, Env};
+use stellar_eth_escrow::{Error, HashAlgo, TimeLocks};
+use stellhydra_guardian::{Guardian, GuardianClient};
+
+use crate::setup::Protocol;
+
+#[test]
+fn test_one_emergency_pause_halts_the_protocol() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+
+    let member = Address::generate(&env);
+    let guardian = GuardianClient::new(&env, &env.register_contract(None, Guardian));
+    guardian.initialize(&protocol.admin, &vec![&env, member.clone()]);
+    let follow = Some(guardian.address.clone());
+    protocol.escrow.set_guardian(&follow);
+    protocol.bridge.set_guardian(&follow);
+    protocol.pool.set_guardian(&follow);
+    protocol.router.set_guardian(&follow);
+
+    let provider = protocol.funded_user(token_a, 1_000_000);
+    protocol.mint(token_b, &provider, 1_000_000);
+    protocol
+        .pool
+        .add_liquidity(&provider, &1_000_000, &1_000_000, &0, &0);
+    let user = protocol.funded_user(token_a, 100_000);
+    let hash_lock: Bytes = env
+        .crypto()
+        .sha256(&Bytes::from_slice(&env, b"secret"))
+        .into();
+    let now = env.ledger().timestamp();
+    let time_locks = TimeLocks {
+        withdrawal: now,
+        public_withdrawal: now + 1_800,
+        cancellation: now + 3_600,
+        public_cancellation: now + 7_200,
+    };
+    let to_address = BytesN::from_array(&env, &[7; 32]);
+    let deadline = env.ledger().timestamp() + 60;
+
+    guardian.emergency_pause(&member, &symbol_short!("incident"));
+
+    assert_eq!(
+        protocol.escrow.try_create_escrow(
+            &user,
+            &None,
+            &1_000,
+            token_a,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0
+        ),
+        Err(Ok(Error::Paused))
+    );
+    assert!(protocol
+        .bridge
+        .try_create_bridge_request(
+            &user,
+            token_a,
+            &symbol_short!("ETH"),
+            &to_address,
+            &1_000,
+            &None
+        )
+        .is_err());
+    assert!(protocol.pool.try_swap(&user, token_a, &1_000, &0).is_err());
+    assert!(protocol
+        .router
+        .try_swap_exact_tokens_for_tokens(&user, &1_000, &0, token_a, token_b, &deadline)
+        .is_err());
+    assert_eq!(protocol.balance(token_a, &user), 100_000);
+
+    // Liquidity providers can still leave while the protocol is halted
+    protocol.pool.remove_liquidity(&provider, &100_000, &0, &0);
+
+    guardian.unpause();
+    protocol.escrow.create_escrow(
+        &user,
+        &None,
+        &1_000,
+        token_a,
+        &hash_lock,
+        &HashAlgo::Sha256,
+        &time_locks,
+        &0,
+    );
+    assert!(protocol.pool.swap(&user, token_a, &1_000, &0) > 0);
+}
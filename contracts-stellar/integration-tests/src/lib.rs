@@ -0,0 +1,29 @@
+//! End-to-end flows across the StellHydra contracts
+//!
+//! Each contract's unit tests stand in mocks for the contracts it calls.
+//! These tests register the real escrow, bridge, pool, router and oracle
+//! side by side in one `Env`, funded with Stellar asset contracts, so a
+//! change to one contract's interface or accounting shows up in the flows
+//! that depend on it.
+//!
+//! [`guardian`] checks that one emergency pause halts all of them, and
+//! [`allowance`] that one token approval serves all of them.
+//!
+//! [`budget`] holds the resource cost of each entrypoint, measured with
+//! realistic amounts of state, to a recorded limit.
+//!
+//! With the `proptest` feature, [`properties`] also runs random sequences
+//! of actions against the pool and the escrow and checks their invariants
+//! after every step.
+
+#![cfg(test)]
+
+mod allowance;
+mod bridge;
+mod budget;
+mod escrow;
+mod guardian;
+#[cfg(feature = "proptest")]
+mod properties;
+mod setup;
+mod swap;
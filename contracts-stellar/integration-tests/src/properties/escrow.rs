@@ -0,0 +1,213 @@
+use proptest::prelude::*;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Bytes,
+};
+use stellar_eth_escrow::{Error, HashAlgo, TimeLocks};
+
+use super::config;
+use crate::setup::{env_without_snapshot, Protocol};
+
+const MAKERS: usize = 3;
+const FUNDING: i128 = 1_000_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Create {
+        maker: usize,
+        amount: i128,
+        duration: u64,
+    },
+    Lock {
+        escrow: usize,
+    },
+    Complete {
+        escrow: usize,
+        right_secret: bool,
+    },
+    Refund {
+        escrow: usize,
+    },
+    Wait {
+        seconds: u64,
+    },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        (0..MAKERS, 1..100_000i128, 1..1_000u64).prop_map(|(maker, amount, duration)| {
+            Action::Create {
+                maker,
+                amount,
+                duration,
+            }
+        }),
+        (0..8usize).prop_map(|escrow| Action::Lock { escrow }),
+        (0..8usize, any::<bool>()).prop_map(|(escrow, right_secret)| Action::Complete {
+            escrow,
+            right_secret
+        }),
+        (0..8usize).prop_map(|escrow| Action::Refund { escrow }),
+        (0..600u64).prop_map(|seconds| Action::Wait { seconds }),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Status {
+    Pending,
+    Locked,
+    Completed,
+    Refunded,
+}
+
+/// What the escrow should look like, kept alongside the contract
+struct Model {
+    id: Bytes,
+    maker: usize,
+    amount: i128,
+    /// When locking and withdrawals close and refunds open
+    cancellation: u64,
+    secret: Bytes,
+    status: Status,
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn escrow_state_machine_never_double_spends(
+        actions in prop::collection::vec(action(), 1..40)
+    ) {
+        let env = env_without_snapshot();
+        let protocol = Protocol::deploy(&env);
+        let escrow = &protocol.escrow;
+        let token = &protocol.token_a;
+        let makers: Vec<Address> = (0..MAKERS)
+            .map(|_| protocol.funded_user(token, FUNDING))
+            .collect();
+        let resolver = Address::generate(&env);
+        let mut models: Vec<Model> = Vec::new();
+
+        for action in &actions {
+            let now = env.ledger().timestamp();
+            match *action {
+                Action::Create { maker, amount, duration } => {
+                    let secret = Bytes::from_array(&env, &[models.len() as u8; 32]);
+                    let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+                    let locks = TimeLocks {
+                        withdrawal: now,
+                        public_withdrawal: now + duration,
+                        cancellation: now + duration,
+                        public_cancellation: now + 2 * duration,
+                    };
+                    let result = escrow.try_create_escrow(
+                        &makers[maker], &None, &amount, token, &hash_lock, &HashAlgo::Sha256, &locks,
+                        &(models.len() as u64),
+                    );
+                    let spent: i128 = models
+                        .iter()
+                        .filter(|model| model.maker == maker)
+                        .filter(|model| model.status != Status::Refunded)
+                        .map(|model| model.amount)
+                        .sum();
+                    if spent + amount > FUNDING {
+                        prop_assert!(result.is_err());
+                        continue;
+                    }
+                    let id = result.unwrap().unwrap();
+                    prop_assert!(models.iter().all(|model| model.id != id));
+                    models.push(Model {
+                        id,
+                        maker,
+                        amount,
+                        cancellation: now + duration,
+                        secret,
+                        status: Status::Pending,
+                    });
+                }
+                Action::Lock { escrow: index } => {
+                    let Some(model) = models.get_mut(index) else { continue };
+                    let result = escrow.try_lock_escrow(&model.id, &resolver);
+                    if model.status != Status::Pending {
+                        prop_assert_eq!(result, Err(Ok(Error::InvalidStatus)));
+                    } else if now >= model.cancellation {
+                        prop_assert_eq!(result, Err(Ok(Error::LockWindowClosed)));
+                    } else {
+                        prop_assert_eq!(result, Ok(Ok(())));
+                        model.status = Status::Locked;
+                    }
+                }
+                Action::Complete { escrow: index, right_secret } => {
+                    let Some(model) = models.get_mut(index) else { continue };
+                    let secret = if right_secret {
+                        model.secret.clone()
+                    } else {
+                        Bytes::from_slice(&env, b"wrong")
+                    };
+                    let result = escrow.try_complete_escrow(&model.id, &secret, &resolver);
+                    let expected = if model.status != Status::Locked {
+                        Err(Ok(Error::InvalidStatus))
+                    } else if !right_secret {
+                        Err(Ok(Error::InvalidSecret))
+                    } else if now >= model.cancellation {
+                        Err(Ok(Error::TimelockExpired))
+                    } else {
+                        model.status = Status::Completed;
+                        Ok(Ok(()))
+                    };
+                    prop_assert_eq!(result, expected);
+                }
+                Action::Refund { escrow: index } => {
+                    let Some(model) = models.get_mut(index) else { continue };
+                    let result = escrow.try_refund_escrow(&model.id);
+                    let expected = if !matches!(model.status, Status::Pending | Status::Locked) {
+                        Err(Ok(Error::InvalidStatus))
+                    } else if now < model.cancellation {
+                        Err(Ok(Error::TimelockNotExpired))
+                    } else {
+                        model.status = Status::Refunded;
+                        Ok(Ok(()))
+                    };
+                    prop_assert_eq!(result, expected);
+                }
+                Action::Wait { seconds } => {
+                    env.ledger().with_mut(|li| li.timestamp += seconds);
+                }
+            }
+
+            // Funds are held once and paid out once: the contract holds
+            // exactly the open escrows, the resolver exactly the completed
+            // ones, and every maker the rest of their funding
+            let held = |statuses: &[Status]| -> i128 {
+                models
+                    .iter()
+                    .filter(|model| statuses.contains(&model.status))
+                    .map(|model| model.amount)
+                    .sum()
+            };
+            prop_assert_eq!(
+                protocol.balance(token, &escrow.address),
+                held(&[Status::Pending, Status::Locked])
+            );
+            prop_assert_eq!(protocol.balance(token, &resolver), held(&[Status::Completed]));
+            for (index, maker) in makers.iter().enumerate() {
+                let committed: i128 = models
+                    .iter()
+                    .filter(|model| model.maker == index && model.status != Status::Refunded)
+                    .map(|model| model.amount)
+                    .sum();
+                prop_assert_eq!(protocol.balance(token, maker), FUNDING - committed);
+            }
+            for model in &models {
+                let stored = escrow.get_escrow(&model.id).unwrap();
+                let status = match model.status {
+                    Status::Pending => 0,
+                    Status::Locked => 1,
+                    Status::Completed => 2,
+                    Status::Refunded => 3,
+                };
+                prop_assert_eq!(stored.status, status);
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Property-based suites
+//!
+//! Each case replays a random sequence of actions from several accounts.
+//! Actions the contract rejects are part of the sequence too: a rejected
+//! call must leave state untouched, so the invariants are checked after
+//! every action whether it succeeded or not.
+
+mod escrow;
+mod pool;
+
+use proptest::test_runner::Config;
+
+/// Cases per property; every case deploys the contracts afresh, so keep
+/// the default run short unless `PROPTEST_CASES` asks for more
+pub fn config() -> Config {
+    if std::env::var("PROPTEST_CASES").is_ok() {
+        return Config::default();
+    }
+    Config {
+        cases: 64,
+        ..Config::default()
+    }
+}
@@ -0,0 +1,166 @@
+use proptest::prelude::*;
+use soroban_sdk::{testutils::Address as _, Address};
+
+use super::config;
+use crate::setup::{env_without_snapshot, Protocol};
+
+const USERS: usize = 3;
+const FUNDING: i128 = 1_000_000_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Add {
+        user: usize,
+        amount_a: i128,
+        amount_b: i128,
+    },
+    Remove {
+        user: usize,
+        percent: i128,
+    },
+    Swap {
+        user: usize,
+        a_to_b: bool,
+        amount: i128,
+    },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        (0..USERS, 1..1_000_000i128, 1..1_000_000i128).prop_map(|(user, amount_a, amount_b)| {
+            Action::Add {
+                user,
+                amount_a,
+                amount_b,
+            }
+        }),
+        (0..USERS, 1..=100i128).prop_map(|(user, percent)| Action::Remove { user, percent }),
+        (0..USERS, any::<bool>(), 1..1_000_000i128).prop_map(|(user, a_to_b, amount)| {
+            Action::Swap {
+                user,
+                a_to_b,
+                amount,
+            }
+        }),
+    ]
+}
+
+/// Reserves product and total shares after an action
+struct Snapshot {
+    k: i128,
+    shares: i128,
+}
+
+fn apply(protocol: &Protocol, users: &[Address], action: &Action) {
+    let pool = &protocol.pool;
+    // Rejected actions are expected (empty pool, dust amounts) and ignored
+    match *action {
+        Action::Add {
+            user,
+            amount_a,
+            amount_b,
+        } => {
+            let _ = pool.try_add_liquidity(&users[user], &amount_a, &amount_b, &0, &0);
+        }
+        Action::Remove { user, percent } => {
+            let held = pool
+                .get_position(&users[user])
+                .map(|position| position.shares)
+                .unwrap_or(0);
+            let shares = held * percent / 100;
+            if shares > 0 {
+                let _ = pool.try_remove_liquidity(&users[user], &shares, &0, &0);
+            }
+        }
+        Action::Swap {
+            user,
+            a_to_b,
+            amount,
+        } => {
+            let token_in = if a_to_b {
+                &protocol.token_a
+            } else {
+                &protocol.token_b
+            };
+            let _ = pool.try_swap(&users[user], token_in, &amount, &0);
+        }
+    }
+}
+
+fn check_invariants(protocol: &Protocol, users: &[Address], previous: &Snapshot) -> Snapshot {
+    let info = protocol.pool.get_pool_info();
+
+    // The pool's books match what it holds
+    assert_eq!(
+        info.reserve_a,
+        protocol.balance(&protocol.token_a, &protocol.pool.address)
+    );
+    assert_eq!(
+        info.reserve_b,
+        protocol.balance(&protocol.token_b, &protocol.pool.address)
+    );
+
+    // Shares are neither created nor lost outside of positions
+    let held: i128 = users
+        .iter()
+        .map(|user| {
+            protocol
+                .pool
+                .get_position(user)
+                .map(|position| position.shares)
+                .unwrap_or(0)
+        })
+        .sum();
+    assert_eq!(held, info.total_shares);
+
+    // No token is created or destroyed
+    for token in [&protocol.token_a, &protocol.token_b] {
+        let total: i128 = users
+            .iter()
+            .map(|user| protocol.balance(token, user))
+            .sum::<i128>()
+            + protocol.balance(token, &protocol.pool.address);
+        assert_eq!(total, FUNDING * USERS as i128);
+    }
+
+    // The product of the reserves per share squared never falls: swaps
+    // add fees, and rounding on deposits and withdrawals favours the pool
+    let current = Snapshot {
+        k: info.reserve_a * info.reserve_b,
+        shares: info.total_shares,
+    };
+    if previous.shares > 0 && current.shares > 0 {
+        assert!(
+            current.k * previous.shares * previous.shares
+                >= previous.k * current.shares * current.shares,
+            "k per share fell"
+        );
+    }
+    current
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn pool_invariants_hold_over_random_actions(
+        actions in prop::collection::vec(action(), 1..40)
+    ) {
+        let env = env_without_snapshot();
+        let protocol = Protocol::deploy(&env);
+        let users: Vec<Address> = (0..USERS)
+            .map(|_| {
+                let user = Address::generate(&env);
+                protocol.mint(&protocol.token_a, &user, FUNDING);
+                protocol.mint(&protocol.token_b, &user, FUNDING);
+                user
+            })
+            .collect();
+
+        let mut snapshot = Snapshot { k: 0, shares: 0 };
+        for action in &actions {
+            apply(&protocol, &users, action);
+            snapshot = check_invariants(&protocol, &users, &snapshot);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use soroban_sdk::{
+    testutils::{Address as _, EnvTestConfig},
+    token::StellarAssetClient,
+    Address, Env, String,
+};
+use stellar_eth_escrow::{StellarEthEscrow, StellarEthEscrowClient};
+use stellhydra_bridge::{StellarBridge, StellarBridgeClient};
+use stellhydra_liquidity_pool::{testutils::PoolScenario, LiquidityPoolClient};
+use stellhydra_price_oracle::{PriceOracle, PriceOracleClient};
+use stellhydra_router::{Router, RouterClient};
+
+/// Bridge fees: a flat 10 plus 1% of the amount
+pub const BASE_FEE: i128 = 10;
+pub const FEE_BPS: u32 = 100;
+
+/// Pool fee, in basis points
+pub const POOL_FEE_BPS: u32 = stellhydra_liquidity_pool::testutils::FEE_BPS;
+
+/// Test environment that doesn't write a snapshot when dropped, for tests
+/// generating many cases or too much state to keep a snapshot of
+pub fn env_without_snapshot() -> Env {
+    Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    })
+}
+
+/// The whole suite deployed and wired together, with two tokens to trade
+pub struct Protocol<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub token_a: Address,
+    pub token_b: Address,
+    pub escrow: StellarEthEscrowClient<'a>,
+    pub bridge: StellarBridgeClient<'a>,
+    pub pool: LiquidityPoolClient<'a>,
+    pub oracle: PriceOracleClient<'a>,
+    pub router: RouterClient<'a>,
+}
+
+impl Protocol<'_> {
+    pub fn deploy(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let fee_recipient = Address::generate(env);
+        // The pool's scenario brings the admin and the pair the other
+        // contracts share
+        let PoolScenario {
+            admin,
+            token_a,
+            token_b,
+            pool,
+            ..
+        } = PoolScenario::new(env);
+
+        let escrow =
+            StellarEthEscrowClient::new(env, &env.register_contract(None, StellarEthEscrow));
+        escrow.initialize(&admin);
+
+        let bridge = StellarBridgeClient::new(env, &env.register_contract(None, StellarBridge));
+        bridge.initialize(
+            &admin,
+            &fee_recipient,
+            &BASE_FEE,
+            &FEE_BPS,
+            &100,
+            &1_000_000_000,
+        );
+
+        let oracle = PriceOracleClient::new(env, &env.register_contract(None, PriceOracle));
+        oracle.initialize(&admin);
+
+        let router = RouterClient::new(env, &env.register_contract(None, Router));
+        router.initialize(&admin, &oracle.address);
+        router.register_dex(
+            &String::from_str(env, "pool"),
+            &pool.address,
+            &String::from_str(env, "liquidity_pool"),
+            &POOL_FEE_BPS,
+        );
+
+        Protocol {
+            env: env.clone(),
+            admin,
+            token_a,
+            token_b,
+            escrow,
+            bridge,
+            pool,
+            oracle,
+            router,
+        }
+    }
+
+    /// A fresh account holding `amount` of `token`
+    pub fn funded_user(&self, token: &Address, amount: i128) -> Address {
+        let user = Address::generate(&self.env);
+        self.mint(token, &user, amount);
+        user
+    }
+
+    pub fn mint(&self, token: &Address, to: &Address, amount: i128) {
+        StellarAssetClient::new(&self.env, token).mint(to, &amount);
+    }
+
+    pub fn balance(&self, token: &Address, account: &Address) -> i128 {
+        soroban_sdk::token::Client::new(&self.env, token).balance(account)
+    }
+}
@@ -0,0 +1,111 @@
+use soroban_sdk::{testutils::Address as _, Address, Env, String};
+
+use crate::setup::Protocol;
+
+/// Seed the pool with `amount` of each token from a liquidity provider
+fn seed_pool(protocol: &Protocol, amount: i128) -> Address {
+    let provider = protocol.funded_user(&protocol.token_a, amount);
+    protocol.mint(&protocol.token_b, &provider, amount);
+    protocol
+        .pool
+        .add_liquidity(&provider, &amount, &amount, &0, &0);
+    provider
+}
+
+#[test]
+fn test_router_swap_settles_through_the_pool() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    seed_pool(&protocol, 1_000_000);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+    let trader = protocol.funded_user(token_a, 10_000);
+
+    let quote = protocol.pool.get_amount_out(token_a, token_b, &10_000);
+    let route = protocol.router.find_best_route(token_a, token_b, &10_000);
+    assert_eq!(route.expected_output, quote);
+    assert_eq!(route.steps.len(), 1);
+    assert_eq!(route.steps.get(0).unwrap().dex, protocol.pool.address);
+
+    let deadline = env.ledger().timestamp() + 60;
+    let amount_out = protocol
+        .router
+        .swap_exact_tokens_for_tokens(&trader, &10_000, &quote, token_a, token_b, &deadline);
+    assert_eq!(amount_out, quote);
+    assert_eq!(protocol.balance(token_a, &trader), 0);
+    assert_eq!(protocol.balance(token_b, &trader), quote);
+
+    // The router holds nothing; the pool's reserves match its balances
+    assert_eq!(protocol.balance(token_a, &protocol.router.address), 0);
+    assert_eq!(protocol.balance(token_b, &protocol.router.address), 0);
+    let info = protocol.pool.get_pool_info();
+    assert_eq!(info.reserve_a, 1_010_000);
+    assert_eq!(info.reserve_b, 1_000_000 - quote);
+    assert_eq!(
+        info.reserve_a,
+        protocol.balance(token_a, &protocol.pool.address)
+    );
+    assert_eq!(
+        info.reserve_b,
+        protocol.balance(token_b, &protocol.pool.address)
+    );
+}
+
+#[test]
+fn test_pool_price_tracks_oracle_price_after_swaps() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    seed_pool(&protocol, 1_000_000);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+
+    // The oracle prices A in B at par, with 7 decimals
+    let feeder = Address::generate(&env);
+    protocol.oracle.add_oracle(&feeder);
+    protocol
+        .oracle
+        .register_asset(&String::from_str(&env, "AAA"), token_a);
+    protocol
+        .oracle
+        .register_asset(&String::from_str(&env, "BBB"), token_b);
+    protocol.oracle.update_price_by_address(
+        &feeder,
+        token_a,
+        token_b,
+        &10_000_000,
+        &7,
+        &String::from_str(&env, "pool"),
+    );
+    let oracle_price = protocol
+        .oracle
+        .get_price_by_address(token_a, token_b)
+        .unwrap();
+
+    // A small trade fills within the pool fee plus price impact of the
+    // oracle price
+    let trader = protocol.funded_user(token_a, 1_000);
+    let deadline = env.ledger().timestamp() + 60;
+    let amount_out = protocol
+        .router
+        .swap_exact_tokens_for_tokens(&trader, &1_000, &0, token_a, token_b, &deadline);
+    let oracle_out = 1_000 * oracle_price.price / 10_000_000;
+    assert!(amount_out <= oracle_out);
+    assert!(amount_out * 10_000 >= oracle_out * 9_960);
+}
+
+#[test]
+fn test_paused_pool_fails_routed_swap_without_moving_funds() {
+    let env = Env::default();
+    let protocol = Protocol::deploy(&env);
+    seed_pool(&protocol, 1_000_000);
+    let (token_a, token_b) = (&protocol.token_a, &protocol.token_b);
+    let trader = protocol.funded_user(token_a, 10_000);
+
+    protocol.pool.pause(&protocol.admin);
+
+    let deadline = env.ledger().timestamp() + 60;
+    let result = protocol
+        .router
+        .try_swap_exact_tokens_for_tokens(&trader, &10_000, &0, token_a, token_b, &deadline);
+    assert!(result.is_err());
+    assert_eq!(protocol.balance(token_a, &trader), 10_000);
+    assert_eq!(protocol.pool.get_pool_info().reserve_a, 1_000_000);
+}
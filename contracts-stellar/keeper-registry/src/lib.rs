@@ -0,0 +1,365 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+    Val, Vec,
+};
+use stellhydra_access::ownable;
+
+/// Call keepers run on behalf of a protocol, and what they earn for it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Job {
+    pub id: u64,
+    pub owner: Address,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub condition: Option<Symbol>, // view on the target that must return true first
+    pub interval: u64,             // minimum seconds between two runs
+    pub bounty_token: Address,
+    pub bounty: i128, // paid to the keeper per run
+    pub balance: i128,
+    pub last_performed: u64,
+    pub active: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    JobNotFound = 1,
+    JobInactive = 2,
+    InvalidAmount = 3,
+    InsufficientFunds = 4,
+    TooSoon = 5,
+    ConditionNotMet = 6,
+}
+
+// Storage keys
+const JOB_COUNT: Symbol = symbol_short!("JOB_COUNT");
+const JOB: Symbol = symbol_short!("JOB");
+const EARNED: Symbol = symbol_short!("EARNED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Registry of automation jobs such as escrow refunds, bridge expiries
+/// and TWAP order chunks
+///
+/// Anyone may run a funded job once it is due; the run is paid from the
+/// job's bounty balance.
+#[contract]
+pub struct KeeperRegistry;
+
+#[contractimpl]
+impl KeeperRegistry {
+    /// Set the admin allowed to upgrade the registry
+    ///
+    /// Jobs and bounties stay under their owners' control; the admin can
+    /// only ship new code.
+    pub fn initialize(env: Env, admin: Address) {
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+    }
+
+    /// Register a job and fund it with `funding` of the bounty token
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_job(
+        env: Env,
+        owner: Address,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+        condition: Option<Symbol>,
+        interval: u64,
+        bounty_token: Address,
+        bounty: i128,
+        funding: i128,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+        if bounty <= 0 || funding < 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        if funding > 0 {
+            token::Client::new(&env, &bounty_token).transfer(
+                &owner,
+                &env.current_contract_address(),
+                &funding,
+            );
+        }
+
+        let id: u64 = env.storage().instance().get(&JOB_COUNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&JOB_COUNT, &id);
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let job = Job {
+            id,
+            owner: owner.clone(),
+            target: target.clone(),
+            function: function.clone(),
+            args,
+            condition,
+            interval,
+            bounty_token,
+            bounty,
+            balance: funding,
+            last_performed: 0,
+            active: true,
+        };
+        Self::save_job(&env, &job);
+
+        env.events().publish(
+            (symbol_short!("KEEPER"), symbol_short!("JOB"), id),
+            (owner, target, function),
+        );
+        Ok(id)
+    }
+
+    /// Top up a job's bounty balance
+    pub fn fund_job(env: Env, funder: Address, id: u64, amount: i128) -> Result<(), Error> {
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let mut job = Self::get_job(env.clone(), id)?;
+        if !job.active {
+            return Err(Error::JobInactive);
+        }
+        token::Client::new(&env, &job.bounty_token).transfer(
+            &funder,
+            &env.current_contract_address(),
+            &amount,
+        );
+        job.balance += amount;
+        Self::save_job(&env, &job);
+
+        env.events().publish(
+            (symbol_short!("KEEPER"), symbol_short!("FUNDED"), id),
+            (funder, amount),
+        );
+        Ok(())
+    }
+
+    /// Stop a job and refund what is left of its balance (only its owner)
+    pub fn cancel_job(env: Env, id: u64) -> Result<i128, Error> {
+        let mut job = Self::get_job(env.clone(), id)?;
+        job.owner.require_auth();
+        if !job.active {
+            return Err(Error::JobInactive);
+        }
+
+        let refund = job.balance;
+        job.balance = 0;
+        job.active = false;
+        Self::save_job(&env, &job);
+        if refund > 0 {
+            token::Client::new(&env, &job.bounty_token).transfer(
+                &env.current_contract_address(),
+                &job.owner,
+                &refund,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("KEEPER"), symbol_short!("CANCELLED"), id),
+            refund,
+        );
+        Ok(refund)
+    }
+
+    /// Run a due job and pay its bounty to the keeper
+    pub fn perform(env: Env, keeper: Address, id: u64) -> Result<Val, Error> {
+        keeper.require_auth();
+
+        let mut job = Self::get_job(env.clone(), id)?;
+        Self::check_due(&env, &job)?;
+
+        job.balance -= job.bounty;
+        job.last_performed = env.ledger().timestamp();
+        Self::save_job(&env, &job);
+
+        let result = env.invoke_contract::<Val>(&job.target, &job.function, job.args.clone());
+        token::Client::new(&env, &job.bounty_token).transfer(
+            &env.current_contract_address(),
+            &keeper,
+            &job.bounty,
+        );
+
+        let earned_key = (EARNED, keeper.clone(), job.bounty_token.clone());
+        let earned: i128 = env.storage().persistent().get(&earned_key).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&earned_key, &(earned + job.bounty));
+        env.storage().persistent().extend_ttl(
+            &earned_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        env.events().publish(
+            (symbol_short!("KEEPER"), symbol_short!("PERFORMED"), id),
+            (keeper, job.bounty),
+        );
+        Ok(result)
+    }
+
+    /// Whether `perform` would currently run the job
+    pub fn is_performable(env: Env, id: u64) -> bool {
+        match Self::get_job(env.clone(), id) {
+            Ok(job) => Self::check_due(&env, &job).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn get_job(env: Env, id: u64) -> Result<Job, Error> {
+        env.storage()
+            .persistent()
+            .get(&(JOB, id))
+            .ok_or(Error::JobNotFound)
+    }
+
+    pub fn get_job_count(env: Env) -> u64 {
+        env.storage().instance().get(&JOB_COUNT).unwrap_or(0)
+    }
+
+    /// Total of `token` a keeper has earned
+    pub fn get_earned(env: Env, keeper: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(EARNED, keeper, token))
+            .unwrap_or(0)
+    }
+}
+
+stellhydra_access::ownable!(KeeperRegistry);
+
+impl stellhydra_access::Upgradeable for KeeperRegistry {}
+
+stellhydra_access::upgradeable!(KeeperRegistry);
+
+impl KeeperRegistry {
+    fn check_due(env: &Env, job: &Job) -> Result<(), Error> {
+        if !job.active {
+            return Err(Error::JobInactive);
+        }
+        if job.balance < job.bounty {
+            return Err(Error::InsufficientFunds);
+        }
+        if job.last_performed > 0 && env.ledger().timestamp() < job.last_performed + job.interval {
+            return Err(Error::TooSoon);
+        }
+        if let Some(condition) = &job.condition {
+            let ready: bool = env.invoke_contract(&job.target, condition, Vec::new(env));
+            if !ready {
+                return Err(Error::ConditionNotMet);
+            }
+        }
+        Ok(())
+    }
+
+    fn save_job(env: &Env, job: &Job) {
+        let key = (JOB, job.id);
+        env.storage().persistent().set(&key, job);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        vec, Env, IntoVal,
+    };
+
+    /// Target with work that only becomes due after a while
+    #[contract]
+    struct MockTarget;
+
+    #[contractimpl]
+    impl MockTarget {
+        pub fn has_work(env: Env) -> bool {
+            env.ledger().timestamp() >= 100
+        }
+
+        pub fn work(env: Env, amount: u32) -> u32 {
+            let done: u32 = env.storage().instance().get(&JOB).unwrap_or(0) + amount;
+            env.storage().instance().set(&JOB, &done);
+            done
+        }
+    }
+
+    #[test]
+    fn test_keepers_run_due_jobs_for_bounty() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_client = token::Client::new(&env, &token);
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&owner, &100);
+
+        let target = env.register_contract(None, MockTarget);
+        let registry_id = env.register_contract(None, KeeperRegistry);
+        let registry = KeeperRegistryClient::new(&env, &registry_id);
+        let id = registry.register_job(
+            &owner,
+            &target,
+            &Symbol::new(&env, "work"),
+            &vec![&env, 2u32.into_val(&env)],
+            &Some(Symbol::new(&env, "has_work")),
+            &60,
+            &token,
+            &30,
+            &70,
+        );
+
+        assert!(!registry.is_performable(&id));
+        assert_eq!(
+            registry.try_perform(&keeper, &id).err(),
+            Some(Ok(Error::ConditionNotMet))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 100);
+        registry.perform(&keeper, &id);
+        assert_eq!(token_client.balance(&keeper), 30);
+        assert_eq!(
+            registry.try_perform(&keeper, &id).err(),
+            Some(Ok(Error::TooSoon))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 160);
+        registry.perform(&keeper, &id);
+        assert_eq!(registry.get_earned(&keeper, &token), 60);
+
+        // Ten left can't cover another bounty
+        env.ledger().with_mut(|li| li.timestamp = 220);
+        assert_eq!(
+            registry.try_perform(&keeper, &id).err(),
+            Some(Ok(Error::InsufficientFunds))
+        );
+        registry.fund_job(&owner, &id, &30);
+        registry.perform(&keeper, &id);
+
+        assert_eq!(registry.cancel_job(&id), 10);
+        assert_eq!(token_client.balance(&owner), 10);
+        assert!(!registry.get_job(&id).active);
+    }
+}
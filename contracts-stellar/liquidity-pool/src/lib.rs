@@ -1,9 +1,22 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, String, Symbol,
-    token::{self, TokenClient},
+    contract, contractimpl, contracttype, symbol_short, token::TokenClient, Address, Env, Symbol,
 };
+use stellhydra_access::{
+    allowance, guard, invariant_covered, ownable, require_admin, require_not_paused,
+};
+use stellhydra_events::pool::{
+    LiquidityAdded, LiquidityRemoved, PoolCreated, PoolSwap, RewardsClaimed, RewardsHarvested,
+    SharesTransferred,
+};
+use stellhydra_events::Event;
+use stellhydra_math::{mul_div, sqrt_mul, sub_bps};
+
+pub mod rewards;
+pub mod votes;
+
+use rewards::{GaugeControllerClient, RewardsConfig};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -27,8 +40,6 @@ pub struct LiquidityPosition {
 
 // Storage keys
 const POOL_INFO: Symbol = symbol_short!("POOL");
-const ADMIN: Symbol = symbol_short!("ADMIN");
-const POSITIONS: Symbol = symbol_short!("POS");
 
 #[contract]
 pub struct LiquidityPool;
@@ -36,14 +47,8 @@ pub struct LiquidityPool;
 #[contractimpl]
 impl LiquidityPool {
     /// Initialize liquidity pool
-    pub fn initialize(
-        env: Env,
-        admin: Address,
-        token_a: Address,
-        token_b: Address,
-        fee_rate: u32,
-    ) {
-        if env.storage().instance().has(&ADMIN) {
+    pub fn initialize(env: Env, admin: Address, token_a: Address, token_b: Address, fee_rate: u32) {
+        if ownable::has_admin(&env) {
             panic!("Pool already initialized");
         }
 
@@ -58,14 +63,16 @@ impl LiquidityPool {
             fee_rate,
         };
 
-        env.storage().instance().set(&ADMIN, &admin);
+        ownable::init_admin(&env, &admin);
         env.storage().instance().set(&POOL_INFO, &pool_info);
 
         // Emit pool creation event
-        env.events().publish(
-            (symbol_short!("POOL"), symbol_short!("CREATE")),
-            (token_a, token_b, fee_rate),
-        );
+        PoolCreated {
+            token_a,
+            token_b,
+            fee_rate,
+        }
+        .publish(&env);
     }
 
     /// Add liquidity to the pool
@@ -78,20 +85,34 @@ impl LiquidityPool {
         amount_b_min: i128,
     ) -> (i128, i128, i128) {
         user.require_auth();
+        require_not_paused(&env);
+        guard::enter(&env);
 
         let mut pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
 
         let (amount_a, amount_b, liquidity_shares) = if pool_info.total_shares == 0 {
             // First liquidity provision
-            let shares = (amount_a_desired * amount_b_desired).sqrt();
+            let shares = sqrt_mul(&env, amount_a_desired, amount_b_desired).expect("Math overflow");
             (amount_a_desired, amount_b_desired, shares)
         } else {
             // Calculate optimal amounts
-            let amount_b_optimal = (amount_a_desired * pool_info.reserve_b) / pool_info.reserve_a;
+            let amount_b_optimal = mul_div(
+                &env,
+                amount_a_desired,
+                pool_info.reserve_b,
+                pool_info.reserve_a,
+            )
+            .expect("Math overflow");
             let (amount_a, amount_b) = if amount_b_optimal <= amount_b_desired {
                 (amount_a_desired, amount_b_optimal)
             } else {
-                let amount_a_optimal = (amount_b_desired * pool_info.reserve_a) / pool_info.reserve_b;
+                let amount_a_optimal = mul_div(
+                    &env,
+                    amount_b_desired,
+                    pool_info.reserve_a,
+                    pool_info.reserve_b,
+                )
+                .expect("Math overflow");
                 (amount_a_optimal, amount_b_desired)
             };
 
@@ -101,25 +122,25 @@ impl LiquidityPool {
             }
 
             // Calculate liquidity shares
-            let shares_a = (amount_a * pool_info.total_shares) / pool_info.reserve_a;
-            let shares_b = (amount_b * pool_info.total_shares) / pool_info.reserve_b;
+            let shares_a = mul_div(&env, amount_a, pool_info.total_shares, pool_info.reserve_a)
+                .expect("Math overflow");
+            let shares_b = mul_div(&env, amount_b, pool_info.total_shares, pool_info.reserve_b)
+                .expect("Math overflow");
             let shares = shares_a.min(shares_b);
 
             (amount_a, amount_b, shares)
         };
 
         // Transfer tokens to pool
-        let token_a_client = TokenClient::new(&env, &pool_info.token_a);
-        let token_b_client = TokenClient::new(&env, &pool_info.token_b);
-
-        token_a_client.transfer(&user, &env.current_contract_address(), &amount_a);
-        token_b_client.transfer(&user, &env.current_contract_address(), &amount_b);
+        allowance::pull(&env, &pool_info.token_a, &user, amount_a);
+        allowance::pull(&env, &pool_info.token_b, &user, amount_b);
 
         // Update pool state
         pool_info.reserve_a += amount_a;
         pool_info.reserve_b += amount_b;
         pool_info.total_shares += liquidity_shares;
 
+        Self::check_reserves(&env, &pool_info);
         env.storage().instance().set(&POOL_INFO, &pool_info);
 
         // Update user position
@@ -135,17 +156,24 @@ impl LiquidityPool {
                 token_b_deposited: 0,
             });
 
+        rewards::settle(&env, &user, position.shares);
         position.shares += liquidity_shares;
         position.token_a_deposited += amount_a;
         position.token_b_deposited += amount_b;
 
         env.storage().persistent().set(&position_key, &position);
+        votes::push(&env, &Some(user.clone()), position.shares);
+        votes::push(&env, &None, pool_info.total_shares);
+        guard::exit(&env);
 
         // Emit liquidity added event
-        env.events().publish(
-            (symbol_short!("POOL"), symbol_short!("ADD_LIQ")),
-            (user, amount_a, amount_b, liquidity_shares),
-        );
+        LiquidityAdded {
+            user,
+            amount_a,
+            amount_b,
+            shares: liquidity_shares,
+        }
+        .publish(&env);
 
         (amount_a, amount_b, liquidity_shares)
     }
@@ -159,22 +187,32 @@ impl LiquidityPool {
         amount_b_min: i128,
     ) -> (i128, i128) {
         user.require_auth();
+        guard::enter(&env);
 
         let mut pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
         let position_key = (user.clone(), symbol_short!("LP"));
-        let mut position: LiquidityPosition = env
-            .storage()
-            .persistent()
-            .get(&position_key)
-            .unwrap();
+        let mut position: LiquidityPosition =
+            env.storage().persistent().get(&position_key).unwrap();
 
         if position.shares < liquidity_shares {
             panic!("Insufficient liquidity shares");
         }
 
         // Calculate withdrawal amounts
-        let amount_a = (liquidity_shares * pool_info.reserve_a) / pool_info.total_shares;
-        let amount_b = (liquidity_shares * pool_info.reserve_b) / pool_info.total_shares;
+        let amount_a = mul_div(
+            &env,
+            liquidity_shares,
+            pool_info.reserve_a,
+            pool_info.total_shares,
+        )
+        .expect("Math overflow");
+        let amount_b = mul_div(
+            &env,
+            liquidity_shares,
+            pool_info.reserve_b,
+            pool_info.total_shares,
+        )
+        .expect("Math overflow");
 
         // Check slippage protection
         if amount_a < amount_a_min || amount_b < amount_b_min {
@@ -193,24 +231,111 @@ impl LiquidityPool {
         pool_info.reserve_b -= amount_b;
         pool_info.total_shares -= liquidity_shares;
 
+        Self::check_reserves(&env, &pool_info);
         env.storage().instance().set(&POOL_INFO, &pool_info);
 
         // Update user position
+        rewards::settle(&env, &user, position.shares);
         position.shares -= liquidity_shares;
-        position.token_a_deposited = (position.token_a_deposited * position.shares) / (position.shares + liquidity_shares);
-        position.token_b_deposited = (position.token_b_deposited * position.shares) / (position.shares + liquidity_shares);
+        position.token_a_deposited = mul_div(
+            &env,
+            position.token_a_deposited,
+            position.shares,
+            position.shares + liquidity_shares,
+        )
+        .unwrap_or(0);
+        position.token_b_deposited = mul_div(
+            &env,
+            position.token_b_deposited,
+            position.shares,
+            position.shares + liquidity_shares,
+        )
+        .unwrap_or(0);
 
         env.storage().persistent().set(&position_key, &position);
+        votes::push(&env, &Some(user.clone()), position.shares);
+        votes::push(&env, &None, pool_info.total_shares);
+        guard::exit(&env);
 
         // Emit liquidity removed event
-        env.events().publish(
-            (symbol_short!("POOL"), symbol_short!("REM_LIQ")),
-            (user, amount_a, amount_b, liquidity_shares),
-        );
+        LiquidityRemoved {
+            user,
+            amount_a,
+            amount_b,
+            shares: liquidity_shares,
+        }
+        .publish(&env);
 
         (amount_a, amount_b)
     }
 
+    /// Move liquidity shares to another owner, e.g. into a vault
+    pub fn transfer_shares(env: Env, from: Address, to: Address, liquidity_shares: i128) {
+        from.require_auth();
+
+        if liquidity_shares <= 0 {
+            panic!("Invalid share amount");
+        }
+
+        let from_key = (from.clone(), symbol_short!("LP"));
+        let mut from_position: LiquidityPosition =
+            env.storage().persistent().get(&from_key).unwrap();
+
+        if from_position.shares < liquidity_shares {
+            panic!("Insufficient liquidity shares");
+        }
+
+        // Deposited amounts follow the shares pro rata
+        let moved_a = mul_div(
+            &env,
+            from_position.token_a_deposited,
+            liquidity_shares,
+            from_position.shares,
+        )
+        .expect("Math overflow");
+        let moved_b = mul_div(
+            &env,
+            from_position.token_b_deposited,
+            liquidity_shares,
+            from_position.shares,
+        )
+        .expect("Math overflow");
+
+        rewards::settle(&env, &from, from_position.shares);
+        from_position.shares -= liquidity_shares;
+        from_position.token_a_deposited -= moved_a;
+        from_position.token_b_deposited -= moved_b;
+        env.storage().persistent().set(&from_key, &from_position);
+        votes::push(&env, &Some(from.clone()), from_position.shares);
+
+        let to_key = (to.clone(), symbol_short!("LP"));
+        let mut to_position: LiquidityPosition =
+            env.storage()
+                .persistent()
+                .get(&to_key)
+                .unwrap_or(LiquidityPosition {
+                    owner: to.clone(),
+                    shares: 0,
+                    token_a_deposited: 0,
+                    token_b_deposited: 0,
+                });
+
+        rewards::settle(&env, &to, to_position.shares);
+        to_position.shares += liquidity_shares;
+        to_position.token_a_deposited += moved_a;
+        to_position.token_b_deposited += moved_b;
+        env.storage().persistent().set(&to_key, &to_position);
+        votes::push(&env, &Some(to.clone()), to_position.shares);
+
+        // Emit share transfer event
+        SharesTransferred {
+            from,
+            to,
+            shares: liquidity_shares,
+        }
+        .publish(&env);
+    }
+
     /// Swap tokens
     pub fn swap(
         env: Env,
@@ -220,31 +345,23 @@ impl LiquidityPool {
         amount_out_min: i128,
     ) -> i128 {
         user.require_auth();
+        require_not_paused(&env);
+        guard::enter(&env);
 
         let mut pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
-
-        let (reserve_in, reserve_out, token_out) = if token_in == pool_info.token_a {
-            (pool_info.reserve_a, pool_info.reserve_b, pool_info.token_b.clone())
-        } else if token_in == pool_info.token_b {
-            (pool_info.reserve_b, pool_info.reserve_a, pool_info.token_a.clone())
-        } else {
-            panic!("Invalid token");
-        };
-
-        // Calculate output amount with fee
-        let amount_in_with_fee = amount_in * (10000 - pool_info.fee_rate as i128) / 10000;
-        let amount_out = (amount_in_with_fee * reserve_out) / (reserve_in + amount_in_with_fee);
+        let (token_out, amount_out) = Self::quote(&env, &pool_info, &token_in, amount_in);
 
         if amount_out < amount_out_min {
             panic!("Insufficient output amount");
         }
 
         // Transfer tokens
-        let token_in_client = TokenClient::new(&env, &token_in);
-        let token_out_client = TokenClient::new(&env, &token_out);
-
-        token_in_client.transfer(&user, &env.current_contract_address(), &amount_in);
-        token_out_client.transfer(&env.current_contract_address(), &user, &amount_out);
+        allowance::pull(&env, &token_in, &user, amount_in);
+        TokenClient::new(&env, &token_out).transfer(
+            &env.current_contract_address(),
+            &user,
+            &amount_out,
+        );
 
         // Update reserves
         if token_in == pool_info.token_a {
@@ -255,13 +372,36 @@ impl LiquidityPool {
             pool_info.reserve_a -= amount_out;
         }
 
+        Self::check_reserves(&env, &pool_info);
         env.storage().instance().set(&POOL_INFO, &pool_info);
+        guard::exit(&env);
 
         // Emit swap event
-        env.events().publish(
-            (symbol_short!("POOL"), symbol_short!("SWAP")),
-            (user, token_in, token_out, amount_in, amount_out),
-        );
+        PoolSwap {
+            user,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+        }
+        .publish(&env);
+
+        amount_out
+    }
+
+    /// Output a swap of `amount_in` of `token_in` into `token_out` would
+    /// receive at the current reserves, net of the pool fee
+    pub fn get_amount_out(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+    ) -> i128 {
+        let pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
+        let (pool_token_out, amount_out) = Self::quote(&env, &pool_info, &token_in, amount_in);
+        if pool_token_out != token_out {
+            panic!("Invalid token");
+        }
 
         amount_out
     }
@@ -276,4 +416,407 @@ impl LiquidityPool {
         let position_key = (user, symbol_short!("LP"));
         env.storage().persistent().get(&position_key)
     }
-}
\ No newline at end of file
+
+    /// Collect liquidity mining emissions from a gauge controller (only
+    /// admin)
+    pub fn set_rewards(env: Env, controller: Address, reward_token: Address) {
+        require_admin(&env);
+
+        // Rewards held for providers must not count towards the reserves
+        let pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
+        if reward_token == pool_info.token_a || reward_token == pool_info.token_b {
+            panic!("Invalid token");
+        }
+        if let Some(config) = rewards::get_config(&env) {
+            if config.reward_token != reward_token {
+                panic!("Reward token already set");
+            }
+        }
+
+        rewards::set_config(
+            &env,
+            &RewardsConfig {
+                controller,
+                reward_token,
+            },
+        );
+    }
+
+    pub fn get_rewards_config(env: Env) -> Option<RewardsConfig> {
+        rewards::get_config(&env)
+    }
+
+    /// Collect the pool's share of an ended epoch's emissions for the
+    /// current liquidity providers
+    pub fn harvest(env: Env, epoch: u64) -> i128 {
+        let config = rewards::get_config(&env).unwrap_or_else(|| panic!("Rewards not set"));
+        guard::enter(&env);
+
+        let amount = GaugeControllerClient::new(&env, &config.controller)
+            .claim(&env.current_contract_address(), &epoch);
+        let pool_info: PoolInfo = env.storage().instance().get(&POOL_INFO).unwrap();
+        rewards::distribute(&env, amount, pool_info.total_shares);
+        guard::exit(&env);
+
+        RewardsHarvested { epoch, amount }.publish(&env);
+        amount
+    }
+
+    /// Pay out the provider's accrued liquidity mining rewards
+    pub fn claim_rewards(env: Env, user: Address) -> i128 {
+        user.require_auth();
+        let config = rewards::get_config(&env).unwrap_or_else(|| panic!("Rewards not set"));
+
+        let shares = Self::get_position(env.clone(), user.clone()).map_or(0, |p| p.shares);
+        let mut info = rewards::settled(&env, &user, shares);
+        let amount = info.rewards;
+        info.rewards = 0;
+        rewards::save(&env, &user, &info);
+
+        if amount > 0 {
+            TokenClient::new(&env, &config.reward_token).transfer(
+                &env.current_contract_address(),
+                &user,
+                &amount,
+            );
+            RewardsClaimed { user, amount }.publish(&env);
+        }
+        amount
+    }
+
+    /// Rewards the provider could claim now
+    pub fn get_pending_rewards(env: Env, user: Address) -> i128 {
+        let shares = Self::get_position(env.clone(), user.clone()).map_or(0, |p| p.shares);
+        rewards::settled(&env, &user, shares).rewards
+    }
+
+    /// Liquidity shares of `account` as of `timestamp`, read by governance
+    pub fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128 {
+        votes::shares_at(&env, &Some(account), timestamp)
+    }
+
+    /// Liquidity shares outstanding as of `timestamp`, read by governance
+    pub fn get_past_total_supply(env: Env, timestamp: u64) -> i128 {
+        votes::shares_at(&env, &None, timestamp)
+    }
+}
+
+impl LiquidityPool {
+    /// Panic unless the pool holds the reserves it books, e.g. after a token
+    /// taking a fee on transfer delivered less than it was sent
+    fn check_reserves(env: &Env, pool_info: &PoolInfo) {
+        invariant_covered!(env, pool_info.token_a, pool_info.reserve_a);
+        invariant_covered!(env, pool_info.token_b, pool_info.reserve_b);
+    }
+
+    /// Token a swap of `token_in` pays out, and how much of it
+    fn quote(
+        env: &Env,
+        pool_info: &PoolInfo,
+        token_in: &Address,
+        amount_in: i128,
+    ) -> (Address, i128) {
+        let (reserve_in, reserve_out, token_out) = if *token_in == pool_info.token_a {
+            (
+                pool_info.reserve_a,
+                pool_info.reserve_b,
+                pool_info.token_b.clone(),
+            )
+        } else if *token_in == pool_info.token_b {
+            (
+                pool_info.reserve_b,
+                pool_info.reserve_a,
+                pool_info.token_a.clone(),
+            )
+        } else {
+            panic!("Invalid token");
+        };
+
+        // Calculate output amount with fee
+        let amount_in_with_fee =
+            sub_bps(env, amount_in, pool_info.fee_rate).expect("Math overflow");
+        let amount_out = mul_div(
+            env,
+            amount_in_with_fee,
+            reserve_out,
+            reserve_in + amount_in_with_fee,
+        )
+        .expect("Math overflow");
+
+        (token_out, amount_out)
+    }
+}
+
+stellhydra_access::ownable!(LiquidityPool);
+stellhydra_access::pausable!(LiquidityPool);
+stellhydra_access::roles!(LiquidityPool);
+stellhydra_access::allowance!(LiquidityPool);
+
+impl stellhydra_access::Upgradeable for LiquidityPool {}
+
+stellhydra_access::upgradeable!(LiquidityPool);
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        contract,
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env, String,
+    };
+    use stellhydra_mock_token::{MockToken, MockTokenClient};
+
+    /// Gauge controller paying whatever it holds to the claiming gauge
+    #[contract]
+    struct MockController;
+
+    #[contractimpl]
+    impl MockController {
+        pub fn init(env: Env, reward_token: Address) {
+            env.storage().instance().set(&POOL_INFO, &reward_token);
+        }
+
+        pub fn claim(env: Env, gauge: Address, _epoch: u64) -> i128 {
+            gauge.require_auth();
+            let reward_token: Address = env.storage().instance().get(&POOL_INFO).unwrap();
+            let token = TokenClient::new(&env, &reward_token);
+            let amount = token.balance(&env.current_contract_address());
+            token.transfer(&env.current_contract_address(), &gauge, &amount);
+            amount
+        }
+    }
+
+    fn mock_token<'a>(env: &Env, symbol: &str, decimals: u32) -> MockTokenClient<'a> {
+        let token = MockTokenClient::new(env, &env.register_contract(None, MockToken));
+        token.initialize(
+            &String::from_str(env, symbol),
+            &String::from_str(env, symbol),
+            &decimals,
+        );
+        token
+    }
+
+    #[test]
+    fn test_large_reserves_do_not_overflow() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_a = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_b = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let pool = LiquidityPoolClient::new(&env, &env.register_contract(None, LiquidityPool));
+        pool.initialize(&Address::generate(&env), &token_a, &token_b, &30);
+
+        // The product of the first deposit is well past i128
+        let amount = 100_000_000_000_000_000_000i128;
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_a).mint(&user, &(2 * amount));
+        StellarAssetClient::new(&env, &token_b).mint(&user, &amount);
+        let (_, _, shares) = pool.add_liquidity(&user, &amount, &amount, &0, &0);
+        assert_eq!(shares, amount);
+
+        let amount_out = pool.swap(&user, &token_a, &(amount / 100), &0);
+        assert_eq!(amount_out, 987_158_034_397_061_298);
+        let (amount_a, amount_b) = pool.remove_liquidity(&user, &shares, &0, &0);
+        assert_eq!(
+            (amount_a, amount_b),
+            (amount + amount / 100, amount - amount_out)
+        );
+    }
+
+    #[test]
+    fn test_paused_pool_still_allows_withdrawals() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_a = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_b = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let pool = LiquidityPoolClient::new(&env, &env.register_contract(None, LiquidityPool));
+        let admin = Address::generate(&env);
+        pool.initialize(&admin, &token_a, &token_b, &30);
+
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_a).mint(&user, &2_000_000);
+        StellarAssetClient::new(&env, &token_b).mint(&user, &2_000_000);
+        let (_, _, shares) = pool.add_liquidity(&user, &1_000_000, &1_000_000, &0, &0);
+
+        pool.pause(&admin);
+        assert!(pool.try_swap(&user, &token_a, &1_000, &0).is_err());
+        assert!(pool
+            .try_add_liquidity(&user, &1_000, &1_000, &0, &0)
+            .is_err());
+        assert_eq!(
+            pool.remove_liquidity(&user, &shares, &0, &0),
+            (1_000_000, 1_000_000)
+        );
+
+        pool.unpause();
+        pool.add_liquidity(&user, &1_000_000, &1_000_000, &0, &0);
+        assert!(pool.swap(&user, &token_a, &1_000, &0) > 0);
+    }
+
+    #[test]
+    fn test_pool_reserves_stay_in_sync_with_unusual_tokens() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        // Six decimals on one side, eighteen and a 1% transfer fee on the other
+        let usdc = mock_token(&env, "USDC", 6);
+        let weth = mock_token(&env, "WETH", 18);
+        let pool = LiquidityPoolClient::new(&env, &env.register_contract(None, LiquidityPool));
+        pool.initialize(&Address::generate(&env), &usdc.address, &weth.address, &30);
+
+        let provider = Address::generate(&env);
+        usdc.mint(&provider, &3_000_000_000);
+        weth.mint(&provider, &1_000_000_000_000_000_000);
+        pool.add_liquidity(
+            &provider,
+            &3_000_000_000,
+            &1_000_000_000_000_000_000,
+            &0,
+            &0,
+        );
+        weth.set_transfer_fee(&100);
+
+        // The fee comes out of what the trader receives, not the pool
+        let trader = Address::generate(&env);
+        usdc.mint(&trader, &30_000_000);
+        let amount_out = pool.swap(&trader, &usdc.address, &30_000_000, &0);
+        assert_eq!(weth.balance(&trader), amount_out - amount_out / 100);
+
+        let info = pool.get_pool_info();
+        assert_eq!(info.reserve_a, usdc.balance(&pool.address));
+        assert_eq!(info.reserve_b, weth.balance(&pool.address));
+
+        // A trader the token blocks cannot swap, and nothing moves
+        usdc.mint(&trader, &1_000_000);
+        weth.set_blocked(&trader, &true);
+        assert!(pool
+            .try_swap(&trader, &usdc.address, &1_000_000, &0)
+            .is_err());
+        assert_eq!(pool.get_pool_info(), info);
+        assert_eq!(usdc.balance(&trader), 1_000_000);
+
+        // Paying in the fee token would book more than the pool received
+        let seller = Address::generate(&env);
+        weth.mint(&seller, &1_000_000_000_000_000);
+        assert!(pool
+            .try_swap(&seller, &weth.address, &1_000_000_000_000_000, &0)
+            .is_err());
+        assert_eq!(pool.get_pool_info(), info);
+    }
+
+    #[test]
+    fn test_harvested_emissions_follow_shares() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let scenario = testutils::PoolScenario::new(&env);
+        let pool = &scenario.pool;
+        let reward_token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let controller =
+            MockControllerClient::new(&env, &env.register_contract(None, MockController));
+        controller.init(&reward_token);
+        assert!(pool
+            .try_set_rewards(&controller.address, &scenario.token_a)
+            .is_err());
+        pool.set_rewards(&controller.address, &reward_token);
+        let emit = |amount: i128| {
+            StellarAssetClient::new(&env, &reward_token).mint(&controller.address, &amount);
+        };
+
+        // Emissions before anyone provides liquidity wait for the first one
+        emit(100);
+        assert_eq!(pool.harvest(&1), 100);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        for user in [&alice, &bob] {
+            StellarAssetClient::new(&env, &scenario.token_a).mint(user, &1_000_000);
+            StellarAssetClient::new(&env, &scenario.token_b).mint(user, &1_000_000);
+        }
+        pool.add_liquidity(&alice, &300_000, &300_000, &0, &0);
+        pool.add_liquidity(&bob, &100_000, &100_000, &0, &0);
+        emit(300);
+        pool.harvest(&2);
+        assert_eq!(pool.get_pending_rewards(&alice), 300);
+        assert_eq!(pool.get_pending_rewards(&bob), 100);
+
+        // Shares moved after a harvest take no rewards already earned with them
+        pool.transfer_shares(&alice, &bob, &200_000);
+        emit(400);
+        pool.harvest(&3);
+        assert_eq!(pool.get_pending_rewards(&alice), 400);
+        assert_eq!(pool.get_pending_rewards(&bob), 400);
+
+        assert_eq!(pool.claim_rewards(&alice), 400);
+        assert_eq!(pool.claim_rewards(&alice), 0);
+        assert_eq!(TokenClient::new(&env, &reward_token).balance(&alice), 400);
+
+        // Rewards held by the pool stay out of its reserves
+        let info = pool.get_pool_info();
+        assert_eq!(
+            info.reserve_a,
+            TokenClient::new(&env, &scenario.token_a).balance(&pool.address)
+        );
+    }
+
+    #[test]
+    fn test_past_votes_follow_share_changes() {
+        let env = Env::default();
+        env.ledger().set_timestamp(1_000);
+        let scenario = testutils::PoolScenario::new(&env).with_liquidity(1_000_000, 1_000_000);
+        let pool = &scenario.pool;
+        let provider = &scenario.provider;
+        let holder = Address::generate(&env);
+
+        env.ledger().set_timestamp(2_000);
+        pool.transfer_shares(provider, &holder, &400_000);
+        env.ledger().set_timestamp(3_000);
+        pool.remove_liquidity(provider, &100_000, &0, &0);
+
+        assert_eq!(pool.get_past_votes(provider, &999), 0);
+        assert_eq!(pool.get_past_votes(provider, &1_999), 1_000_000);
+        assert_eq!(pool.get_past_votes(provider, &2_000), 600_000);
+        assert_eq!(pool.get_past_votes(&holder, &2_500), 400_000);
+        assert_eq!(pool.get_past_votes(provider, &3_000), 500_000);
+        assert_eq!(pool.get_past_total_supply(&2_999), 1_000_000);
+        assert_eq!(pool.get_past_total_supply(&3_000), 900_000);
+    }
+
+    #[test]
+    fn test_scenario_seeds_the_pool() {
+        let env = Env::default();
+        let scenario = testutils::PoolScenario::new(&env).with_liquidity(1_000_000, 2_000_000);
+        let info = scenario.pool.get_pool_info();
+        assert_eq!((info.reserve_a, info.reserve_b), (1_000_000, 2_000_000));
+        assert_eq!(
+            scenario
+                .pool
+                .get_position(&scenario.provider)
+                .unwrap()
+                .shares,
+            info.total_shares
+        );
+
+        let trader = scenario.funded_user(10_000, 0);
+        let out = scenario.pool.swap(&trader, &scenario.token_a, &10_000, &0);
+        assert_eq!(
+            soroban_sdk::token::Client::new(&env, &scenario.token_b).balance(&trader),
+            out
+        );
+    }
+}
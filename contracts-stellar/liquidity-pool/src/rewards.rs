@@ -0,0 +1,104 @@
+//! Liquidity mining rewards for the pool's liquidity providers
+//!
+//! The pool is a gauge of the gauge controller. Once an epoch's weights
+//! are final, `harvest` collects the pool's share of that epoch's
+//! emissions and spreads it over the liquidity shares outstanding at the
+//! time, through a reward-per-share accumulator. Each provider's rewards
+//! are settled against the accumulator before their shares change.
+
+use soroban_sdk::{contractclient, contracttype, symbol_short, Address, Env, Symbol};
+use stellhydra_math::mul_div;
+
+/// Side of the gauge controller the pool collects from
+#[contractclient(name = "GaugeControllerClient")]
+pub trait GaugeControllerInterface {
+    fn claim(env: Env, gauge: Address, epoch: u64) -> i128;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardsConfig {
+    pub controller: Address,
+    pub reward_token: Address,
+}
+
+/// Rewards of one liquidity provider
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardInfo {
+    pub reward_per_share_paid: i128, // accumulator value rewards were last settled at
+    pub rewards: i128,               // settled but unclaimed rewards
+}
+
+// Storage keys
+const REWARDS: Symbol = symbol_short!("REWARDS");
+const RPS: Symbol = symbol_short!("RPS");
+const QUEUED: Symbol = symbol_short!("QUEUED");
+const REWARD: Symbol = symbol_short!("REWARD");
+
+// Fixed-point scale of the reward-per-share accumulator
+const PRECISION: i128 = 1_000_000_000_000;
+
+pub fn get_config(env: &Env) -> Option<RewardsConfig> {
+    env.storage().instance().get(&REWARDS)
+}
+
+pub fn set_config(env: &Env, config: &RewardsConfig) {
+    env.storage().instance().set(&REWARDS, config);
+}
+
+fn reward_per_share(env: &Env) -> i128 {
+    env.storage().instance().get(&RPS).unwrap_or(0)
+}
+
+/// Spread `amount` over `total_shares`, holding it while there are none
+pub fn distribute(env: &Env, amount: i128, total_shares: i128) {
+    let queued: i128 = env.storage().instance().get(&QUEUED).unwrap_or(0);
+    if total_shares == 0 {
+        env.storage().instance().set(&QUEUED, &(queued + amount));
+        return;
+    }
+
+    let added = mul_div(env, queued + amount, PRECISION, total_shares).expect("Math overflow");
+    env.storage()
+        .instance()
+        .set(&RPS, &(reward_per_share(env) + added));
+    env.storage().instance().remove(&QUEUED);
+}
+
+/// Rewards of `owner`, holding `shares`, with what accrued since their
+/// last settlement added
+pub fn settled(env: &Env, owner: &Address, shares: i128) -> RewardInfo {
+    let mut info: RewardInfo = env
+        .storage()
+        .persistent()
+        .get(&(REWARD, owner.clone()))
+        .unwrap_or(RewardInfo {
+            reward_per_share_paid: 0,
+            rewards: 0,
+        });
+    let reward_per_share = reward_per_share(env);
+    info.rewards += mul_div(
+        env,
+        shares,
+        reward_per_share - info.reward_per_share_paid,
+        PRECISION,
+    )
+    .expect("Math overflow");
+    info.reward_per_share_paid = reward_per_share;
+    info
+}
+
+/// Settle `owner`'s rewards before their `shares` change
+pub fn settle(env: &Env, owner: &Address, shares: i128) {
+    if get_config(env).is_none() {
+        return;
+    }
+    save(env, owner, &settled(env, owner, shares));
+}
+
+pub fn save(env: &Env, owner: &Address, info: &RewardInfo) {
+    env.storage()
+        .persistent()
+        .set(&(REWARD, owner.clone()), info);
+}
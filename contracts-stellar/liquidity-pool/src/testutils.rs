@@ -0,0 +1,78 @@
+//! Scenario builders for tests against the pool
+//!
+//! Deploys a pool between two Stellar assets and seeds it:
+//!
+//! ```ignore
+//! let scenario = PoolScenario::new(&env).with_liquidity(1_000_000, 2_000_000);
+//! let trader = scenario.funded_user(10_000, 0);
+//! scenario.pool.swap(&trader, &scenario.token_a, &10_000, &0);
+//! ```
+//!
+//! All authorizations are mocked.
+
+use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Address, Env};
+
+use crate::{LiquidityPool, LiquidityPoolClient};
+
+/// Swap fee of the deployed pool, in basis points
+pub const FEE_BPS: u32 = 30;
+
+/// A pool deployed with its two tokens
+pub struct PoolScenario<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub token_a: Address,
+    pub token_b: Address,
+    /// Account providing the liquidity added by the scenario
+    pub provider: Address,
+    pub pool: LiquidityPoolClient<'a>,
+}
+
+impl PoolScenario<'_> {
+    pub fn new(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let admin = Address::generate(env);
+        let token_a = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let token_b = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let pool = LiquidityPoolClient::new(env, &env.register_contract(None, LiquidityPool));
+        pool.initialize(&admin, &token_a, &token_b, &FEE_BPS);
+
+        PoolScenario {
+            env: env.clone(),
+            admin,
+            token_a,
+            token_b,
+            provider: Address::generate(env),
+            pool,
+        }
+    }
+
+    /// Add liquidity from the provider, minting them the tokens first.
+    /// Amounts off the pool's ratio are added at the ratio, as usual.
+    pub fn with_liquidity(self, amount_a: i128, amount_b: i128) -> Self {
+        self.mint(&self.provider, amount_a, amount_b);
+        self.pool
+            .add_liquidity(&self.provider, &amount_a, &amount_b, &0, &0);
+        self
+    }
+
+    /// A fresh account holding the given amounts of each token
+    pub fn funded_user(&self, amount_a: i128, amount_b: i128) -> Address {
+        let user = Address::generate(&self.env);
+        self.mint(&user, amount_a, amount_b);
+        user
+    }
+
+    fn mint(&self, to: &Address, amount_a: i128, amount_b: i128) {
+        for (token, amount) in [(&self.token_a, amount_a), (&self.token_b, amount_b)] {
+            if amount > 0 {
+                StellarAssetClient::new(&self.env, token).mint(to, &amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Liquidity share history for governance
+//!
+//! Governance counts the pool's liquidity shares as voting power as of a
+//! proposal's snapshot, so every change to a provider's shares or to the
+//! total is checkpointed with its timestamp. Each checkpoint is its own
+//! entry and reads binary-search them, so neither grows in cost with the
+//! history.
+
+use soroban_sdk::{contracttype, symbol_short, Address, Env, IntoVal, Symbol, Val};
+
+/// Liquidity shares from `timestamp` on
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub timestamp: u64,
+    pub shares: i128,
+}
+
+// Storage keys
+const CP_COUNT: Symbol = symbol_short!("CP_COUNT");
+const CHECKPT: Symbol = symbol_short!("CHECKPT");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Record `shares` from now on, overwriting a checkpoint from the same
+/// timestamp
+///
+/// `holder` is the provider, or none for the total.
+pub fn push(env: &Env, holder: &Option<Address>, shares: i128) {
+    let now = env.ledger().timestamp();
+    let mut count = count(env, holder);
+    if count > 0 {
+        if let Some(last) = checkpoint(env, holder, count - 1) {
+            if last.timestamp == now {
+                count -= 1;
+            }
+        }
+    }
+
+    let key = (CHECKPT, holder.clone(), count);
+    env.storage().persistent().set(
+        &key,
+        &Checkpoint {
+            timestamp: now,
+            shares,
+        },
+    );
+    extend(env, &key);
+
+    let key = (CP_COUNT, holder.clone());
+    env.storage().persistent().set(&key, &(count + 1));
+    extend(env, &key);
+}
+
+/// Shares of the latest checkpoint at or before `timestamp`
+pub fn shares_at(env: &Env, holder: &Option<Address>, timestamp: u64) -> i128 {
+    let (mut low, mut high) = (0, count(env, holder));
+    while low < high {
+        let mid = (low + high) / 2;
+        match checkpoint(env, holder, mid) {
+            Some(checkpoint) if checkpoint.timestamp > timestamp => high = mid,
+            _ => low = mid + 1,
+        }
+    }
+    if low == 0 {
+        return 0;
+    }
+    checkpoint(env, holder, low - 1)
+        .map(|checkpoint| checkpoint.shares)
+        .unwrap_or(0)
+}
+
+fn count(env: &Env, holder: &Option<Address>) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&(CP_COUNT, holder.clone()))
+        .unwrap_or(0)
+}
+
+fn checkpoint(env: &Env, holder: &Option<Address>, index: u32) -> Option<Checkpoint> {
+    env.storage()
+        .persistent()
+        .get(&(CHECKPT, holder.clone(), index))
+}
+
+fn extend<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    env.storage().persistent().extend_ttl(
+        key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
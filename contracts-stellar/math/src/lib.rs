@@ -0,0 +1,356 @@
+#![no_std]
+
+//! Checked integer and fixed-point math shared by the StellHydra contracts
+//!
+//! Products that do not fit in an `i128` are carried in 256 bits, so
+//! `a * b / c` only fails when the result itself does not fit. Every
+//! function returns `None` on overflow, division by zero or a negative
+//! input it cannot take, and leaves it to the caller to turn that into
+//! its own error.
+
+use soroban_sdk::{Env, I256};
+
+/// One in basis points
+pub const BPS: i128 = 10_000;
+
+/// One in 18-decimal fixed point
+pub const WAD: i128 = 1_000_000_000_000_000_000;
+
+/// One in 27-decimal fixed point
+pub const RAY: i128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// `a * b / denominator`, rounded toward zero
+pub fn mul_div(env: &Env, a: i128, b: i128, denominator: i128) -> Option<i128> {
+    mul_div_rounded(env, a, b, denominator, false)
+}
+
+/// `a * b / denominator`, rounded away from zero
+pub fn mul_div_up(env: &Env, a: i128, b: i128, denominator: i128) -> Option<i128> {
+    mul_div_rounded(env, a, b, denominator, true)
+}
+
+fn mul_div_rounded(env: &Env, a: i128, b: i128, denominator: i128, up: bool) -> Option<i128> {
+    if denominator == 0 {
+        return None;
+    }
+    let away = if (a < 0) ^ (b < 0) ^ (denominator < 0) {
+        -1
+    } else {
+        1
+    };
+
+    if let Some(product) = a.checked_mul(b) {
+        let quotient = product.checked_div(denominator)?;
+        if up && product % denominator != 0 {
+            return quotient.checked_add(away);
+        }
+        return Some(quotient);
+    }
+
+    let product = I256::from_i128(env, a).mul(&I256::from_i128(env, b));
+    let denominator = I256::from_i128(env, denominator);
+    let quotient = product.div(&denominator);
+    let exact = quotient.mul(&denominator) == product;
+    let quotient = quotient.to_i128()?;
+    if up && !exact {
+        return quotient.checked_add(away);
+    }
+    Some(quotient)
+}
+
+/// Square root, rounded down
+pub fn sqrt(value: i128) -> Option<i128> {
+    (value >= 0).then(|| value.isqrt())
+}
+
+/// Square root of `a * b`, rounded down, for products beyond `i128`
+pub fn sqrt_mul(env: &Env, a: i128, b: i128) -> Option<i128> {
+    if a < 0 || b < 0 {
+        return None;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product.isqrt());
+    }
+
+    // Newton's iteration from above, starting close to the root
+    let mut x = (a.isqrt() + 1)
+        .checked_mul(b.isqrt() + 1)
+        .map_or(a.max(b), |start| start.min(a.max(b)));
+    loop {
+        let quotient = mul_div(env, a, b, x)?;
+        let next = x / 2 + quotient / 2 + (x % 2 + quotient % 2) / 2;
+        if next >= x {
+            return Some(x);
+        }
+        x = next;
+    }
+}
+
+/// `10^decimals`
+pub fn pow10(decimals: u32) -> Option<i128> {
+    10i128.checked_pow(decimals)
+}
+
+/// Convert a value between decimal precisions, rounding toward zero
+pub fn rescale(env: &Env, value: i128, from_decimals: u32, to_decimals: u32) -> Option<i128> {
+    if to_decimals >= from_decimals {
+        value.checked_mul(pow10(to_decimals - from_decimals)?)
+    } else {
+        mul_div(env, value, 1, pow10(from_decimals - to_decimals)?)
+    }
+}
+
+/// Product of two WAD values
+pub fn wad_mul(env: &Env, a: i128, b: i128) -> Option<i128> {
+    mul_div(env, a, b, WAD)
+}
+
+/// Quotient of two WAD values
+pub fn wad_div(env: &Env, a: i128, b: i128) -> Option<i128> {
+    mul_div(env, a, WAD, b)
+}
+
+/// Product of two RAY values
+pub fn ray_mul(env: &Env, a: i128, b: i128) -> Option<i128> {
+    mul_div(env, a, b, RAY)
+}
+
+/// Quotient of two RAY values
+pub fn ray_div(env: &Env, a: i128, b: i128) -> Option<i128> {
+    mul_div(env, a, RAY, b)
+}
+
+/// `bps` basis points of `amount`, rounded toward zero
+pub fn apply_bps(env: &Env, amount: i128, bps: u32) -> Option<i128> {
+    mul_div(env, amount, bps as i128, BPS)
+}
+
+/// `bps` basis points of `amount`, rounded away from zero
+pub fn apply_bps_up(env: &Env, amount: i128, bps: u32) -> Option<i128> {
+    mul_div_up(env, amount, bps as i128, BPS)
+}
+
+/// What is left of `amount` after taking `bps` basis points of it
+pub fn sub_bps(env: &Env, amount: i128, bps: u32) -> Option<i128> {
+    amount.checked_sub(apply_bps(env, amount, bps)?)
+}
+
+/// `part` as basis points of `whole`, rounded toward zero
+pub fn bps_of(env: &Env, part: i128, whole: i128) -> Option<i128> {
+    mul_div(env, part, BPS, whole)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const CASES: usize = 2_000;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0x5e11_4d7a)
+    }
+
+    /// Environment for thousands of 256-bit host operations
+    fn unmetered_env() -> Env {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+        env
+    }
+
+    /// Magnitudes spread over the whole range, not just around i128::MAX
+    fn any_i128(rng: &mut StdRng) -> i128 {
+        let bits = rng.gen_range(0..127);
+        let magnitude = rng.gen::<i128>().rem_euclid(1i128 << bits);
+        if rng.gen() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    #[test]
+    fn test_mul_div_rounds_toward_and_away_from_zero() {
+        let env = Env::default();
+        assert_eq!(mul_div(&env, 7, 3, 2), Some(10));
+        assert_eq!(mul_div_up(&env, 7, 3, 2), Some(11));
+        assert_eq!(mul_div(&env, -7, 3, 2), Some(-10));
+        assert_eq!(mul_div_up(&env, -7, 3, 2), Some(-11));
+        assert_eq!(mul_div_up(&env, 7, -3, -2), Some(11));
+        assert_eq!(mul_div_up(&env, 6, 3, 2), Some(9));
+        assert_eq!(mul_div(&env, 0, i128::MAX, 1), Some(0));
+    }
+
+    #[test]
+    fn test_mul_div_edge_cases() {
+        let env = Env::default();
+        assert_eq!(mul_div(&env, 1, 1, 0), None);
+        assert_eq!(mul_div(&env, i128::MAX, 2, 1), None);
+        assert_eq!(mul_div(&env, i128::MIN, 1, -1), None);
+        assert_eq!(mul_div(&env, i128::MIN, -1, 1), None);
+        assert_eq!(mul_div(&env, i128::MIN, 1, 1), Some(i128::MIN));
+
+        // Intermediate products past i128 are fine
+        assert_eq!(
+            mul_div(&env, i128::MAX, i128::MAX, i128::MAX),
+            Some(i128::MAX)
+        );
+        assert_eq!(
+            mul_div(&env, i128::MIN, i128::MAX, i128::MAX),
+            Some(i128::MIN)
+        );
+        assert_eq!(mul_div(&env, i128::MAX, 3, 6), Some(i128::MAX / 2));
+        assert_eq!(mul_div_up(&env, i128::MAX, 3, 6), Some(i128::MAX / 2 + 1));
+        assert_eq!(mul_div_up(&env, i128::MAX, 4, 4), Some(i128::MAX));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let env = Env::default();
+        assert_eq!(sqrt(-1), None);
+        assert_eq!(sqrt(0), Some(0));
+        assert_eq!(sqrt(15), Some(3));
+        assert_eq!(sqrt(16), Some(4));
+        assert_eq!(sqrt(i128::MAX), Some(13_043_817_825_332_782_212));
+
+        assert_eq!(sqrt_mul(&env, -1, 4), None);
+        assert_eq!(sqrt_mul(&env, 0, i128::MAX), Some(0));
+        assert_eq!(sqrt_mul(&env, i128::MAX, i128::MAX), Some(i128::MAX));
+        assert_eq!(
+            sqrt_mul(&env, i128::MAX, 1),
+            Some(13_043_817_825_332_782_212)
+        );
+        assert_eq!(sqrt_mul(&env, 1 << 100, 1 << 60), Some(1 << 80));
+    }
+
+    #[test]
+    fn test_fixed_point_and_bps() {
+        let env = Env::default();
+        assert_eq!(wad_mul(&env, 3 * WAD / 2, 2 * WAD), Some(3 * WAD));
+        assert_eq!(wad_div(&env, 3 * WAD, 2 * WAD), Some(3 * WAD / 2));
+        assert_eq!(ray_mul(&env, RAY / 4, 4 * RAY), Some(RAY));
+        assert_eq!(ray_div(&env, RAY, 3 * RAY), Some(RAY / 3));
+        assert_eq!(wad_div(&env, WAD, 0), None);
+
+        assert_eq!(
+            pow10(38),
+            Some(100_000_000_000_000_000_000_000_000_000_000_000_000)
+        );
+        assert_eq!(pow10(39), None);
+        assert_eq!(rescale(&env, 123_456_789, 8, 6), Some(1_234_567));
+        assert_eq!(rescale(&env, 1_234_567, 6, 8), Some(123_456_700));
+        assert_eq!(rescale(&env, 1, 0, 39), None);
+
+        assert_eq!(apply_bps(&env, 1_999, 30), Some(5));
+        assert_eq!(apply_bps_up(&env, 1_999, 30), Some(6));
+        assert_eq!(sub_bps(&env, 1_000, 250), Some(975));
+        assert_eq!(bps_of(&env, 25, 1_000), Some(250));
+        assert_eq!(bps_of(&env, 1, 0), None);
+    }
+
+    // Property tests over seeded random inputs
+
+    #[test]
+    fn prop_mul_div_matches_i128_when_product_fits() {
+        let env = unmetered_env();
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = any_i128(&mut rng) >> 64;
+            let b = any_i128(&mut rng) >> 64;
+            let c = any_i128(&mut rng);
+            if c == 0 {
+                continue;
+            }
+            let down = mul_div(&env, a, b, c).unwrap();
+            assert_eq!(down, a * b / c);
+
+            let up = mul_div_up(&env, a, b, c).unwrap();
+            let exact = (a * b) % c == 0;
+            assert_eq!(
+                up - down,
+                if exact {
+                    0
+                } else {
+                    (a * b).signum() * c.signum()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn prop_mul_div_is_exact_past_i128() {
+        let env = unmetered_env();
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = any_i128(&mut rng);
+            let b = any_i128(&mut rng);
+            if b == 0 {
+                continue;
+            }
+
+            // Cancelling factors come back unchanged however large
+            assert_eq!(mul_div(&env, a, b, b), Some(a));
+            assert_eq!(mul_div_up(&env, a, b, b), Some(a));
+
+            // Splitting the denominator agrees with dividing in one go
+            let c = rng.gen_range(1..=i64::MAX as i128);
+            let d = rng.gen_range(1..=i64::MAX as i128);
+            if let Some(whole) = mul_div(&env, a, b, c * d) {
+                let step = mul_div(&env, a, b, c).map(|q| q / d);
+                if let Some(step) = step {
+                    assert_eq!(whole, step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prop_mul_div_up_bounds_mul_div() {
+        let env = unmetered_env();
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = any_i128(&mut rng).abs();
+            let b = any_i128(&mut rng).abs();
+            let c = any_i128(&mut rng).abs().max(1);
+            match (mul_div(&env, a, b, c), mul_div_up(&env, a, b, c)) {
+                (Some(down), Some(up)) => assert!(up == down || up == down + 1),
+                (Some(down), None) => assert_eq!(down, i128::MAX),
+                (None, up) => assert_eq!(up, None),
+            }
+        }
+    }
+
+    #[test]
+    fn prop_sqrt_mul_is_floor_root() {
+        let env = unmetered_env();
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = any_i128(&mut rng).abs();
+            let b = any_i128(&mut rng).abs();
+            let root = sqrt_mul(&env, a, b).unwrap();
+
+            // root^2 <= a * b < (root + 1)^2, in 256 bits
+            let wide = |x: i128| I256::from_i128(&env, x);
+            let product = wide(a).mul(&wide(b));
+            assert!(wide(root).mul(&wide(root)) <= product);
+            let next = wide(root).add(&wide(1));
+            assert!(next.mul(&next) > product);
+        }
+    }
+
+    #[test]
+    fn prop_bps_splits_add_up() {
+        let env = unmetered_env();
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let amount = rng.gen_range(0..i128::MAX / BPS);
+            let bps = rng.gen_range(0..=10_000u32);
+            let fee = apply_bps(&env, amount, bps).unwrap();
+            assert!((0..=amount).contains(&fee));
+            assert_eq!(sub_bps(&env, amount, bps).unwrap() + fee, amount);
+            assert!(apply_bps_up(&env, amount, bps).unwrap() - fee <= 1);
+        }
+    }
+}
@@ -0,0 +1,473 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, xdr::ToXdr,
+    Address, Bytes, BytesN, Env, Symbol, Vec,
+};
+
+/// Interface of an application receiving messages
+#[contractclient(name = "HandlerClient")]
+pub trait MessageHandler {
+    fn handle_message(env: Env, source_chain: Symbol, sender: Bytes, payload: Bytes);
+}
+
+/// Message from another chain addressed to a handler on this one
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    pub source_chain: Symbol,
+    pub sender: Bytes, // sending application on the source chain
+    pub sequence: u64, // per source chain, assigned by its messaging contract
+    pub dest_chain: Symbol,
+    pub recipient: Address,
+    pub payload: Bytes,
+}
+
+/// Signature of one guardian over a message
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attestation {
+    pub guardian: u32, // index in the guardian set
+    pub signature: BytesN<64>,
+}
+
+/// Keys attesting messages, and how many must agree
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GuardianSet {
+    pub keys: Vec<BytesN<32>>,
+    pub threshold: u32,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidConfig = 3,
+    WrongChain = 4,
+    UnknownHandler = 5,
+    AlreadyDelivered = 6,
+    InvalidAttestations = 7,
+    NotEnoughAttestations = 8,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const CHAIN: Symbol = symbol_short!("CHAIN");
+const GUARDIANS: Symbol = symbol_short!("GUARDIANS");
+const SEQUENCE: Symbol = symbol_short!("SEQUENCE");
+const HANDLER: Symbol = symbol_short!("HANDLER");
+const DELIVERED: Symbol = symbol_short!("DELIVERED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Message passing between chains, secured by a guardian set
+///
+/// Outbound messages are only events; guardians watch them and sign the
+/// matching `Message` on the destination. Inbound messages are delivered
+/// by anyone holding enough guardian signatures over the XDR of this
+/// contract's address followed by the message, and are handed to the
+/// registered handler they name.
+#[contract]
+pub struct Messaging;
+
+#[contractimpl]
+impl Messaging {
+    /// Initialize with this chain's identifier and the guardian set
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        chain: Symbol,
+        guardians: GuardianSet,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        Self::check_guardians(&guardians)?;
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&CHAIN, &chain);
+        env.storage().instance().set(&GUARDIANS, &guardians);
+        env.storage().instance().set(&SEQUENCE, &0u64);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Replace the guardian set (only admin)
+    pub fn set_guardians(env: Env, guardians: GuardianSet) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::check_guardians(&guardians)?;
+        env.storage().instance().set(&GUARDIANS, &guardians);
+
+        env.events().publish(
+            (symbol_short!("MSG"), symbol_short!("GUARDIANS")),
+            guardians,
+        );
+        Ok(())
+    }
+
+    pub fn get_guardians(env: Env) -> Result<GuardianSet, Error> {
+        env.storage()
+            .instance()
+            .get(&GUARDIANS)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Allow or disallow a contract to receive messages (only admin)
+    pub fn set_handler(env: Env, handler: Address, allowed: bool) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let key = (HANDLER, handler.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+            env.storage().persistent().extend_ttl(
+                &key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events().publish(
+            (symbol_short!("MSG"), symbol_short!("HANDLER")),
+            (handler, allowed),
+        );
+        Ok(())
+    }
+
+    pub fn is_handler(env: Env, handler: Address) -> bool {
+        env.storage().persistent().has(&(HANDLER, handler))
+    }
+
+    /// Send a message to an application on another chain
+    ///
+    /// The canonical event carries everything guardians need to rebuild
+    /// the message on the destination.
+    pub fn send_message(
+        env: Env,
+        sender: Address,
+        dest_chain: Symbol,
+        dest_address: Bytes,
+        payload: Bytes,
+    ) -> Result<u64, Error> {
+        sender.require_auth();
+
+        let chain: Symbol = env
+            .storage()
+            .instance()
+            .get(&CHAIN)
+            .ok_or(Error::NotInitialized)?;
+        let sequence: u64 = env.storage().instance().get(&SEQUENCE).unwrap_or(0) + 1;
+        env.storage().instance().set(&SEQUENCE, &sequence);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("MSG"), symbol_short!("SENT"), dest_chain),
+            (chain, sender, sequence, dest_address, payload),
+        );
+        Ok(sequence)
+    }
+
+    /// Sequence of the last message sent
+    pub fn get_sequence(env: Env) -> u64 {
+        env.storage().instance().get(&SEQUENCE).unwrap_or(0)
+    }
+
+    /// Deliver an attested message to its handler
+    ///
+    /// Attestations must be sorted by guardian index, without repeats.
+    pub fn deliver_message(
+        env: Env,
+        message: Message,
+        attestations: Vec<Attestation>,
+    ) -> Result<(), Error> {
+        let chain: Symbol = env
+            .storage()
+            .instance()
+            .get(&CHAIN)
+            .ok_or(Error::NotInitialized)?;
+        if message.dest_chain != chain {
+            return Err(Error::WrongChain);
+        }
+        if !Self::is_handler(env.clone(), message.recipient.clone()) {
+            return Err(Error::UnknownHandler);
+        }
+        let key = (DELIVERED, message.source_chain.clone(), message.sequence);
+        if env.storage().persistent().has(&key) {
+            return Err(Error::AlreadyDelivered);
+        }
+
+        Self::verify_attestations(&env, &message, &attestations)?;
+
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        HandlerClient::new(&env, &message.recipient).handle_message(
+            &message.source_chain,
+            &message.sender,
+            &message.payload,
+        );
+
+        env.events().publish(
+            (
+                symbol_short!("MSG"),
+                symbol_short!("DELIVERED"),
+                message.source_chain,
+            ),
+            (message.sequence, message.recipient),
+        );
+        Ok(())
+    }
+
+    pub fn is_delivered(env: Env, source_chain: Symbol, sequence: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(DELIVERED, source_chain, sequence))
+    }
+}
+
+impl stellhydra_access::Upgradeable for Messaging {}
+
+stellhydra_access::upgradeable!(Messaging);
+
+impl Messaging {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn check_guardians(guardians: &GuardianSet) -> Result<(), Error> {
+        if guardians.threshold == 0 || guardians.threshold > guardians.keys.len() {
+            return Err(Error::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    fn verify_attestations(
+        env: &Env,
+        message: &Message,
+        attestations: &Vec<Attestation>,
+    ) -> Result<(), Error> {
+        let guardians = Self::get_guardians(env.clone())?;
+        if attestations.len() < guardians.threshold {
+            return Err(Error::NotEnoughAttestations);
+        }
+
+        let signed = (env.current_contract_address(), message.clone()).to_xdr(env);
+        let mut next_index = 0;
+        for attestation in attestations.iter() {
+            if attestation.guardian < next_index {
+                return Err(Error::InvalidAttestations);
+            }
+            let key = guardians
+                .keys
+                .get(attestation.guardian)
+                .ok_or(Error::InvalidAttestations)?;
+            env.crypto()
+                .ed25519_verify(&key, &signed, &attestation.signature);
+            next_index = attestation.guardian + 1;
+        }
+        Ok(())
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    /// Application recording what it received
+    #[contract]
+    struct MockHandler;
+
+    #[contractimpl]
+    impl MockHandler {
+        pub fn handle_message(env: Env, source_chain: Symbol, sender: Bytes, payload: Bytes) {
+            env.storage()
+                .instance()
+                .set(&HANDLER, &(source_chain, sender, payload));
+        }
+
+        pub fn received(env: Env) -> Option<(Symbol, Bytes, Bytes)> {
+            env.storage().instance().get(&HANDLER)
+        }
+    }
+
+    struct Setup<'a> {
+        messaging: MessagingClient<'a>,
+        handler: MockHandlerClient<'a>,
+        keys: [SigningKey; 3],
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let keys = [
+            SigningKey::from_bytes(&[1; 32]),
+            SigningKey::from_bytes(&[2; 32]),
+            SigningKey::from_bytes(&[3; 32]),
+        ];
+        let mut public_keys = Vec::new(env);
+        for key in keys.iter() {
+            public_keys.push_back(BytesN::from_array(env, &key.verifying_key().to_bytes()));
+        }
+
+        let messaging_id = env.register_contract(None, Messaging);
+        let messaging = MessagingClient::new(env, &messaging_id);
+        messaging.initialize(
+            &Address::generate(env),
+            &Symbol::new(env, "stellar"),
+            &GuardianSet {
+                keys: public_keys,
+                threshold: 2,
+            },
+        );
+
+        let handler_id = env.register_contract(None, MockHandler);
+        messaging.set_handler(&handler_id, &true);
+
+        Setup {
+            messaging,
+            handler: MockHandlerClient::new(env, &handler_id),
+            keys,
+        }
+    }
+
+    fn message(env: &Env, recipient: &Address, sequence: u64) -> Message {
+        Message {
+            source_chain: Symbol::new(env, "ethereum"),
+            sender: Bytes::from_array(env, &[0xaa; 20]),
+            sequence,
+            dest_chain: Symbol::new(env, "stellar"),
+            recipient: recipient.clone(),
+            payload: Bytes::from_array(env, b"hello"),
+        }
+    }
+
+    fn attest(env: &Env, s: &Setup, message: &Message, guardians: &[u32]) -> Vec<Attestation> {
+        let signed = (s.messaging.address.clone(), message.clone()).to_xdr(env);
+        let mut bytes = [0u8; 1024];
+        let bytes = &mut bytes[..signed.len() as usize];
+        signed.copy_into_slice(bytes);
+
+        let mut attestations = Vec::new(env);
+        for guardian in guardians {
+            let signature = s.keys[*guardian as usize].sign(bytes).to_bytes();
+            attestations.push_back(Attestation {
+                guardian: *guardian,
+                signature: BytesN::from_array(env, &signature),
+            });
+        }
+        attestations
+    }
+
+    #[test]
+    fn test_attested_messages_reach_handler_once() {
+        let env = Env::default();
+        let s = setup(&env);
+        let message = message(&env, &s.handler.address, 1);
+
+        assert_eq!(
+            s.messaging
+                .try_deliver_message(&message, &attest(&env, &s, &message, &[1])),
+            Err(Ok(Error::NotEnoughAttestations))
+        );
+        assert_eq!(
+            s.messaging
+                .try_deliver_message(&message, &attest(&env, &s, &message, &[1, 1])),
+            Err(Ok(Error::InvalidAttestations))
+        );
+
+        let attestations = attest(&env, &s, &message, &[0, 2]);
+        s.messaging.deliver_message(&message, &attestations);
+        assert_eq!(
+            s.handler.received(),
+            Some((
+                message.source_chain.clone(),
+                message.sender.clone(),
+                message.payload.clone()
+            ))
+        );
+        assert!(s.messaging.is_delivered(&message.source_chain, &1));
+        assert_eq!(
+            s.messaging.try_deliver_message(&message, &attestations),
+            Err(Ok(Error::AlreadyDelivered))
+        );
+
+        // Signatures don't carry over to a different message
+        let mut forged = message.clone();
+        forged.sequence = 2;
+        assert!(s
+            .messaging
+            .try_deliver_message(&forged, &attestations)
+            .is_err());
+    }
+
+    #[test]
+    fn test_messages_need_a_registered_handler_here() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        let stranger = message(&env, &Address::generate(&env), 1);
+        assert_eq!(
+            s.messaging
+                .try_deliver_message(&stranger, &attest(&env, &s, &stranger, &[0, 1])),
+            Err(Ok(Error::UnknownHandler))
+        );
+
+        let mut elsewhere = message(&env, &s.handler.address, 1);
+        elsewhere.dest_chain = Symbol::new(&env, "polygon");
+        assert_eq!(
+            s.messaging
+                .try_deliver_message(&elsewhere, &attest(&env, &s, &elsewhere, &[0, 1])),
+            Err(Ok(Error::WrongChain))
+        );
+    }
+
+    #[test]
+    fn test_sent_messages_are_sequenced() {
+        let env = Env::default();
+        let s = setup(&env);
+        let sender = Address::generate(&env);
+        let dest = Symbol::new(&env, "ethereum");
+        let payload = Bytes::from_array(&env, b"ping");
+
+        let first = s.messaging.send_message(
+            &sender,
+            &dest,
+            &Bytes::from_array(&env, &[0xbb; 20]),
+            &payload,
+        );
+        let second = s.messaging.send_message(
+            &sender,
+            &dest,
+            &Bytes::from_array(&env, &[0xbb; 20]),
+            &payload,
+        );
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(s.messaging.get_sequence(), 2);
+    }
+}
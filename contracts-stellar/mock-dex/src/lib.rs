@@ -0,0 +1,252 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token::TokenClient, Address, Env, Symbol,
+};
+
+/// How the venue prices a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Curve {
+    /// Fixed rate in basis points of the input, whatever the size
+    Linear(i128),
+    /// Constant product over virtual reserves `(reserve_in, reserve_out)`,
+    /// which move with every swap so larger trades get worse prices
+    ConstantProduct(i128, i128),
+}
+
+/// Failure injected into the venue's calls
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Failure {
+    None,
+    /// Quotes panic, as a venue with no liquidity would
+    Quote,
+    /// Quotes work but swaps panic
+    Swap,
+}
+
+// Storage keys
+const CURVE: Symbol = symbol_short!("CURVE");
+const FAILURE: Symbol = symbol_short!("FAILURE");
+const SLIPPAGE: Symbol = symbol_short!("SLIPPAGE");
+const SWAPS: Symbol = symbol_short!("SWAPS");
+
+const BPS: i128 = 10_000;
+
+/// DEX adapter whose prices and failures are set by the test
+///
+/// It serves the router's adapter interface (`get_quote` and `swap`),
+/// pricing each pair along the curve set for it and paying out of its own
+/// balance, so tests mint it the output tokens it should have. Slippage
+/// makes swaps pay less than they quoted. Setters need no authorization;
+/// it only belongs in tests.
+#[contract]
+pub struct MockDex;
+
+#[contractimpl]
+impl MockDex {
+    /// Price swaps of `token_in` into `token_out` along `curve`
+    pub fn set_curve(env: Env, token_in: Address, token_out: Address, curve: Curve) {
+        env.storage()
+            .persistent()
+            .set(&(CURVE, token_in, token_out), &curve);
+    }
+
+    pub fn get_curve(env: Env, token_in: Address, token_out: Address) -> Option<Curve> {
+        env.storage()
+            .persistent()
+            .get(&(CURVE, token_in, token_out))
+    }
+
+    pub fn set_failure(env: Env, failure: Failure) {
+        env.storage().instance().set(&FAILURE, &failure);
+    }
+
+    pub fn get_failure(env: Env) -> Failure {
+        env.storage()
+            .instance()
+            .get(&FAILURE)
+            .unwrap_or(Failure::None)
+    }
+
+    /// Pay `slippage_bps` less than quoted on every swap
+    pub fn set_slippage(env: Env, slippage_bps: u32) {
+        if slippage_bps as i128 > BPS {
+            panic!("Slippage too high");
+        }
+
+        env.storage().instance().set(&SLIPPAGE, &slippage_bps);
+    }
+
+    pub fn get_slippage(env: Env) -> u32 {
+        env.storage().instance().get(&SLIPPAGE).unwrap_or(0)
+    }
+
+    /// Number of swaps executed
+    pub fn get_swap_count(env: Env) -> u32 {
+        env.storage().instance().get(&SWAPS).unwrap_or(0)
+    }
+
+    pub fn get_quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128 {
+        if Self::get_failure(env.clone()) == Failure::Quote {
+            panic!("Quote failed");
+        }
+
+        let curve =
+            Self::get_curve(env, token_in, token_out).unwrap_or_else(|| panic!("No liquidity"));
+        Self::price(&curve, amount_in)
+    }
+
+    pub fn swap(
+        env: Env,
+        taker: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+    ) -> i128 {
+        taker.require_auth();
+        if Self::get_failure(env.clone()) == Failure::Swap {
+            panic!("Swap failed");
+        }
+        if amount_in <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let curve = Self::get_curve(env.clone(), token_in.clone(), token_out.clone())
+            .unwrap_or_else(|| panic!("No liquidity"));
+        let quote = Self::price(&curve, amount_in);
+        let amount_out = quote - quote * Self::get_slippage(env.clone()) as i128 / BPS;
+        if amount_out < min_out {
+            panic!("Slippage exceeded");
+        }
+
+        TokenClient::new(&env, &token_in).transfer(
+            &taker,
+            &env.current_contract_address(),
+            &amount_in,
+        );
+        TokenClient::new(&env, &token_out).transfer(
+            &env.current_contract_address(),
+            &taker,
+            &amount_out,
+        );
+
+        if let Curve::ConstantProduct(reserve_in, reserve_out) = curve {
+            Self::set_curve(
+                env.clone(),
+                token_in,
+                token_out,
+                Curve::ConstantProduct(reserve_in + amount_in, reserve_out - amount_out),
+            );
+        }
+        let swaps = Self::get_swap_count(env.clone());
+        env.storage().instance().set(&SWAPS, &(swaps + 1));
+
+        amount_out
+    }
+}
+
+impl MockDex {
+    fn price(curve: &Curve, amount_in: i128) -> i128 {
+        match *curve {
+            Curve::Linear(rate_bps) => amount_in * rate_bps / BPS,
+            Curve::ConstantProduct(reserve_in, reserve_out) => {
+                amount_in * reserve_out / (reserve_in + amount_in)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env, String};
+    use stellhydra_mock_token::{MockToken, MockTokenClient};
+
+    fn token<'a>(env: &Env) -> MockTokenClient<'a> {
+        let token = MockTokenClient::new(env, &env.register_contract(None, MockToken));
+        token.initialize(
+            &String::from_str(env, "Mock"),
+            &String::from_str(env, "MOCK"),
+            &7,
+        );
+        token
+    }
+
+    #[test]
+    fn test_constant_product_curve_moves_with_swaps() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (token_in, token_out) = (token(&env), token(&env));
+        let dex = MockDexClient::new(&env, &env.register_contract(None, MockDex));
+        token_out.mint(&dex.address, &1_000_000);
+
+        dex.set_curve(
+            &token_in.address,
+            &token_out.address,
+            &Curve::ConstantProduct(100_000, 100_000),
+        );
+        let first = dex.get_quote(&token_in.address, &token_out.address, &10_000);
+        assert_eq!(first, 9_090);
+
+        let taker = Address::generate(&env);
+        token_in.mint(&taker, &20_000);
+        assert_eq!(
+            dex.swap(
+                &taker,
+                &token_in.address,
+                &token_out.address,
+                &10_000,
+                &first
+            ),
+            first
+        );
+        assert!(dex.get_quote(&token_in.address, &token_out.address, &10_000) < first);
+        assert_eq!(dex.get_swap_count(), 1);
+        assert_eq!(token_out.balance(&taker), first);
+    }
+
+    #[test]
+    fn test_injected_failures_and_slippage() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (token_in, token_out) = (token(&env), token(&env));
+        let dex = MockDexClient::new(&env, &env.register_contract(None, MockDex));
+        token_out.mint(&dex.address, &1_000_000);
+        let taker = Address::generate(&env);
+        token_in.mint(&taker, &10_000);
+
+        assert!(dex
+            .try_get_quote(&token_in.address, &token_out.address, &1_000)
+            .is_err());
+        dex.set_curve(&token_in.address, &token_out.address, &Curve::Linear(9_000));
+
+        dex.set_failure(&Failure::Quote);
+        assert!(dex
+            .try_get_quote(&token_in.address, &token_out.address, &1_000)
+            .is_err());
+
+        dex.set_failure(&Failure::Swap);
+        assert_eq!(
+            dex.get_quote(&token_in.address, &token_out.address, &1_000),
+            900
+        );
+        assert!(dex
+            .try_swap(&taker, &token_in.address, &token_out.address, &1_000, &0)
+            .is_err());
+
+        // 2% slippage pays 882 on a 900 quote
+        dex.set_failure(&Failure::None);
+        dex.set_slippage(&200);
+        assert!(dex
+            .try_swap(&taker, &token_in.address, &token_out.address, &1_000, &900)
+            .is_err());
+        assert_eq!(
+            dex.swap(&taker, &token_in.address, &token_out.address, &1_000, &0),
+            882
+        );
+        assert_eq!(token_in.balance(&taker), 9_000);
+    }
+}
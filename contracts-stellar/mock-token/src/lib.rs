@@ -0,0 +1,329 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token::TokenInterface, Address, Env,
+    String, Symbol,
+};
+
+/// Metadata set when the token is initialized
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Allowance of a spender, valid up to a ledger
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Allowance {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+// Storage keys
+const METADATA: Symbol = symbol_short!("METADATA");
+const FEE: Symbol = symbol_short!("FEE");
+const BALANCE: Symbol = symbol_short!("BALANCE");
+const ALLOWANCE: Symbol = symbol_short!("ALLOWANCE");
+const BLOCKED: Symbol = symbol_short!("BLOCKED");
+
+const BPS: i128 = 10_000;
+
+/// Token implementing the standard interface, for tests only
+///
+/// Anyone can mint, and the token can be switched into the behaviours
+/// real tokens have that a Stellar asset contract does not: a fee taken
+/// from every transfer, and accounts that can neither send nor receive.
+/// None of the setters need authorization, so never deploy it anywhere
+/// real value is at stake.
+#[contract]
+pub struct MockToken;
+
+#[contractimpl]
+impl MockToken {
+    pub fn initialize(env: Env, name: String, symbol: String, decimals: u32) {
+        if env.storage().instance().has(&METADATA) {
+            panic!("Already initialized");
+        }
+
+        env.storage().instance().set(
+            &METADATA,
+            &TokenMetadata {
+                name,
+                symbol,
+                decimals,
+            },
+        );
+    }
+
+    /// Mint tokens to any account, without authorization
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        Self::check_amount(amount);
+
+        Self::add_balance(&env, &to, amount);
+        env.events().publish((symbol_short!("mint"), to), amount);
+    }
+
+    /// Take `fee_bps` of every transfer from what the recipient receives;
+    /// the fee is burned
+    pub fn set_transfer_fee(env: Env, fee_bps: u32) {
+        if fee_bps as i128 > BPS {
+            panic!("Fee too high");
+        }
+
+        env.storage().instance().set(&FEE, &fee_bps);
+    }
+
+    pub fn get_transfer_fee(env: Env) -> u32 {
+        env.storage().instance().get(&FEE).unwrap_or(0)
+    }
+
+    /// Block or unblock an account from sending and receiving the token
+    pub fn set_blocked(env: Env, account: Address, blocked: bool) {
+        let key = (BLOCKED, account);
+        if blocked {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    pub fn is_blocked(env: Env, account: Address) -> bool {
+        env.storage().persistent().has(&(BLOCKED, account))
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for MockToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        Self::read_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        if amount < 0 {
+            panic!("Negative amount");
+        }
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic!("Expiration ledger is in the past");
+        }
+
+        env.storage().temporary().set(
+            &(ALLOWANCE, from.clone(), spender.clone()),
+            &Allowance {
+                amount,
+                expiration_ledger,
+            },
+        );
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&(BALANCE, id)).unwrap_or(0)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::check_amount(amount);
+        Self::check_not_blocked(&env, &from);
+
+        Self::spend_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_amount(amount);
+        Self::check_not_blocked(&env, &from);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::spend_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        Self::metadata(&env).decimals
+    }
+
+    fn name(env: Env) -> String {
+        Self::metadata(&env).name
+    }
+
+    fn symbol(env: Env) -> String {
+        Self::metadata(&env).symbol
+    }
+}
+
+impl MockToken {
+    fn metadata(env: &Env) -> TokenMetadata {
+        env.storage().instance().get(&METADATA).unwrap()
+    }
+
+    fn check_amount(amount: i128) {
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+    }
+
+    fn check_not_blocked(env: &Env, account: &Address) {
+        if Self::is_blocked(env.clone(), account.clone()) {
+            panic!("Account is blocked");
+        }
+    }
+
+    /// Debit `amount` from the sender and credit it, less the transfer
+    /// fee, to the recipient
+    fn move_balance(env: &Env, from: &Address, to: &Address, amount: i128) {
+        Self::check_amount(amount);
+        Self::check_not_blocked(env, from);
+        Self::check_not_blocked(env, to);
+
+        let fee = amount * Self::get_transfer_fee(env.clone()) as i128 / BPS;
+        Self::spend_balance(env, from, amount);
+        Self::add_balance(env, to, amount - fee);
+        env.events().publish(
+            (symbol_short!("transfer"), from.clone(), to.clone()),
+            amount,
+        );
+    }
+
+    fn read_allowance(env: &Env, from: &Address, spender: &Address) -> Allowance {
+        let allowance: Option<Allowance> =
+            env.storage()
+                .temporary()
+                .get(&(ALLOWANCE, from.clone(), spender.clone()));
+        match allowance {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+            _ => Allowance {
+                amount: 0,
+                expiration_ledger: 0,
+            },
+        }
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let mut allowance = Self::read_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic!("Insufficient allowance");
+        }
+        allowance.amount -= amount;
+        env.storage()
+            .temporary()
+            .set(&(ALLOWANCE, from.clone(), spender.clone()), &allowance);
+    }
+
+    fn add_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        env.storage()
+            .persistent()
+            .set(&(BALANCE, id.clone()), &(balance + amount));
+    }
+
+    fn spend_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        if balance < amount {
+            panic!("Insufficient balance");
+        }
+        env.storage()
+            .persistent()
+            .set(&(BALANCE, id.clone()), &(balance - amount));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    fn setup(env: &Env, decimals: u32) -> MockTokenClient<'_> {
+        let token = MockTokenClient::new(env, &env.register_contract(None, MockToken));
+        token.initialize(
+            &String::from_str(env, "Mock USD"),
+            &String::from_str(env, "mUSD"),
+            &decimals,
+        );
+        token
+    }
+
+    #[test]
+    fn test_anyone_mints_and_decimals_are_configurable() {
+        let env = Env::default();
+        let token = setup(&env, 6);
+        let user = Address::generate(&env);
+
+        token.mint(&user, &1_000_000);
+        assert!(env.auths().is_empty());
+        assert_eq!(token.balance(&user), 1_000_000);
+        assert_eq!(token.decimals(), 6);
+        assert_eq!(token.symbol(), String::from_str(&env, "mUSD"));
+    }
+
+    #[test]
+    fn test_transfer_fee_is_taken_from_the_recipient() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let token = setup(&env, 7);
+        let (user, other, spender) = (
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        );
+        token.mint(&user, &10_000);
+        token.set_transfer_fee(&100);
+
+        token.transfer(&user, &other, &5_000);
+        assert_eq!(token.balance(&user), 5_000);
+        assert_eq!(token.balance(&other), 4_950);
+
+        token.approve(&user, &spender, &2_000, &100);
+        token.transfer_from(&spender, &user, &other, &2_000);
+        assert_eq!(token.balance(&user), 3_000);
+        assert_eq!(token.balance(&other), 6_930);
+        assert_eq!(token.allowance(&user, &spender), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blocked")]
+    fn test_blocked_account_cannot_receive() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let token = setup(&env, 7);
+        let (user, blocked) = (Address::generate(&env), Address::generate(&env));
+        token.mint(&user, &1_000);
+
+        token.set_blocked(&blocked, &true);
+        assert!(token.is_blocked(&blocked));
+        token.transfer(&user, &blocked, &100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blocked")]
+    fn test_blocked_account_cannot_send() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let token = setup(&env, 7);
+        let (user, other) = (Address::generate(&env), Address::generate(&env));
+        token.mint(&user, &1_000);
+
+        token.set_blocked(&user, &true);
+        token.transfer(&user, &other, &100);
+    }
+}
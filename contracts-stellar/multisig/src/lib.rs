@@ -0,0 +1,501 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Symbol,
+    TryFromVal, Val, Vec,
+};
+use stellhydra_access::upgradeable;
+
+/// Call waiting for enough owner confirmations
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transaction {
+    pub id: u64,
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub submitter: Address,
+    pub confirmations: u32,
+    pub executed: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidThreshold = 3,
+    NotOwner = 4,
+    AlreadyOwner = 5,
+    TransactionNotFound = 6,
+    AlreadyExecuted = 7,
+    AlreadyConfirmed = 8,
+    NotConfirmed = 9,
+    NotEnoughConfirmations = 10,
+    UnsupportedCall = 11,
+}
+
+// Storage keys
+const OWNERS: Symbol = symbol_short!("OWNERS");
+const THRESHOLD: Symbol = symbol_short!("THRESHOLD");
+const TX_COUNT: Symbol = symbol_short!("TX_COUNT");
+const TX: Symbol = symbol_short!("TX");
+const CONFIRMED: Symbol = symbol_short!("CONFIRMED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contract]
+pub struct Multisig;
+
+#[contractimpl]
+impl Multisig {
+    /// Initialize the multisig with its owners and the number of
+    /// confirmations a transaction needs
+    pub fn initialize(env: Env, owners: Vec<Address>, threshold: u32) -> Result<(), Error> {
+        if env.storage().instance().has(&THRESHOLD) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        let mut unique = Vec::new(&env);
+        for owner in owners.iter() {
+            if unique.contains(&owner) {
+                return Err(Error::AlreadyOwner);
+            }
+            unique.push_back(owner);
+        }
+        Self::check_threshold(unique.len(), threshold)?;
+
+        env.storage().instance().set(&OWNERS, &unique);
+        env.storage().instance().set(&THRESHOLD, &threshold);
+        env.storage().instance().set(&TX_COUNT, &0u64);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_owners(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&OWNERS)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_threshold(env: Env) -> Result<u32, Error> {
+        env.storage()
+            .instance()
+            .get(&THRESHOLD)
+            .ok_or(Error::NotInitialized)
+    }
+
+    pub fn is_owner(env: Env, account: Address) -> bool {
+        Self::get_owners(env).contains(&account)
+    }
+
+    /// Submit a call for the owners to confirm; the submitter's own
+    /// confirmation is counted (only owners)
+    pub fn submit(
+        env: Env,
+        owner: Address,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+        Self::check_owner(&env, &owner)?;
+
+        let id: u64 = env.storage().instance().get(&TX_COUNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&TX_COUNT, &id);
+
+        let mut transaction = Transaction {
+            id,
+            target,
+            function,
+            args,
+            submitter: owner.clone(),
+            confirmations: 0,
+            executed: false,
+        };
+        Self::add_confirmation(&env, &mut transaction, &owner);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("MULTISIG"), symbol_short!("SUBMITTED"), id),
+            (owner, transaction.target, transaction.function),
+        );
+        Ok(id)
+    }
+
+    /// Confirm a pending transaction (only owners)
+    pub fn confirm(env: Env, owner: Address, id: u64) -> Result<(), Error> {
+        owner.require_auth();
+        Self::check_owner(&env, &owner)?;
+
+        let mut transaction = Self::pending_transaction(&env, id)?;
+        if Self::is_confirmed(env.clone(), id, owner.clone()) {
+            return Err(Error::AlreadyConfirmed);
+        }
+        Self::add_confirmation(&env, &mut transaction, &owner);
+        Ok(())
+    }
+
+    /// Withdraw a confirmation before the transaction runs (only owners)
+    pub fn revoke(env: Env, owner: Address, id: u64) -> Result<(), Error> {
+        owner.require_auth();
+        Self::check_owner(&env, &owner)?;
+
+        let mut transaction = Self::pending_transaction(&env, id)?;
+        if !Self::is_confirmed(env.clone(), id, owner.clone()) {
+            return Err(Error::NotConfirmed);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&(CONFIRMED, id, owner.clone()));
+        transaction.confirmations -= 1;
+        Self::save_transaction(&env, &transaction);
+
+        env.events().publish(
+            (symbol_short!("MULTISIG"), symbol_short!("REVOKED"), id),
+            owner,
+        );
+        Ok(())
+    }
+
+    /// Run a transaction confirmed by enough current owners (only owners)
+    ///
+    /// The target sees the multisig as the invoker, so contracts whose
+    /// admin is the multisig accept it.
+    pub fn execute(env: Env, owner: Address, id: u64) -> Result<Val, Error> {
+        owner.require_auth();
+        Self::check_owner(&env, &owner)?;
+
+        let mut transaction = Self::pending_transaction(&env, id)?;
+        if Self::count_confirmations(&env, id) < Self::get_threshold(env.clone())? {
+            return Err(Error::NotEnoughConfirmations);
+        }
+
+        transaction.executed = true;
+        Self::save_transaction(&env, &transaction);
+
+        // A contract can't call itself, so changes to the owner set are
+        // applied directly
+        let result = if transaction.target == env.current_contract_address() {
+            Self::apply_own_call(&env, &transaction.function, &transaction.args)?;
+            Val::VOID.into()
+        } else {
+            env.invoke_contract::<Val>(&transaction.target, &transaction.function, transaction.args)
+        };
+
+        env.events().publish(
+            (symbol_short!("MULTISIG"), symbol_short!("EXECUTED"), id),
+            owner,
+        );
+        Ok(result)
+    }
+
+    pub fn get_transaction(env: Env, id: u64) -> Result<Transaction, Error> {
+        env.storage()
+            .persistent()
+            .get(&(TX, id))
+            .ok_or(Error::TransactionNotFound)
+    }
+
+    pub fn get_transaction_count(env: Env) -> u64 {
+        env.storage().instance().get(&TX_COUNT).unwrap_or(0)
+    }
+
+    pub fn is_confirmed(env: Env, id: u64, owner: Address) -> bool {
+        env.storage().persistent().has(&(CONFIRMED, id, owner))
+    }
+}
+
+impl stellhydra_access::Upgradeable for Multisig {
+    /// Upgrades need the owners' threshold, as transactions targeting the
+    /// multisig itself
+    fn require_upgrade_authority(env: &Env) {
+        env.current_contract_address().require_auth();
+    }
+}
+
+stellhydra_access::upgradeable!(Multisig);
+
+impl Multisig {
+    fn check_owner(env: &Env, account: &Address) -> Result<(), Error> {
+        if !Self::is_owner(env.clone(), account.clone()) {
+            return Err(Error::NotOwner);
+        }
+        Ok(())
+    }
+
+    fn check_threshold(owners: u32, threshold: u32) -> Result<(), Error> {
+        if threshold == 0 || threshold > owners {
+            return Err(Error::InvalidThreshold);
+        }
+        Ok(())
+    }
+
+    fn pending_transaction(env: &Env, id: u64) -> Result<Transaction, Error> {
+        let transaction = Self::get_transaction(env.clone(), id)?;
+        if transaction.executed {
+            return Err(Error::AlreadyExecuted);
+        }
+        Ok(transaction)
+    }
+
+    fn add_confirmation(env: &Env, transaction: &mut Transaction, owner: &Address) {
+        let key = (CONFIRMED, transaction.id, owner.clone());
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        transaction.confirmations += 1;
+        Self::save_transaction(env, transaction);
+
+        env.events().publish(
+            (
+                symbol_short!("MULTISIG"),
+                symbol_short!("CONFIRMED"),
+                transaction.id,
+            ),
+            owner.clone(),
+        );
+    }
+
+    /// Confirmations from addresses that are still owners
+    fn count_confirmations(env: &Env, id: u64) -> u32 {
+        Self::get_owners(env.clone())
+            .iter()
+            .filter(|owner| Self::is_confirmed(env.clone(), id, owner.clone()))
+            .count() as u32
+    }
+
+    /// Apply a transaction targeting the multisig itself
+    fn apply_own_call(env: &Env, function: &Symbol, args: &Vec<Val>) -> Result<(), Error> {
+        if upgradeable::apply_own_call::<Self>(env, function, args) {
+            return Ok(());
+        }
+
+        let mut owners = Self::get_owners(env.clone());
+        let mut threshold = Self::get_threshold(env.clone())?;
+
+        if *function == Symbol::new(env, "add_owner") {
+            let owner = Self::arg::<Address>(env, args, 0)?;
+            if owners.contains(&owner) {
+                return Err(Error::AlreadyOwner);
+            }
+            owners.push_back(owner);
+        } else if *function == Symbol::new(env, "remove_owner") {
+            let owner = Self::arg::<Address>(env, args, 0)?;
+            let index = owners.first_index_of(&owner).ok_or(Error::NotOwner)?;
+            owners.remove(index);
+        } else if *function == Symbol::new(env, "change_threshold") {
+            threshold = Self::arg::<u32>(env, args, 0)?;
+        } else {
+            return Err(Error::UnsupportedCall);
+        }
+
+        Self::check_threshold(owners.len(), threshold)?;
+        env.storage().instance().set(&OWNERS, &owners);
+        env.storage().instance().set(&THRESHOLD, &threshold);
+
+        env.events().publish(
+            (symbol_short!("MULTISIG"), symbol_short!("OWNERS")),
+            (owners, threshold),
+        );
+        Ok(())
+    }
+
+    fn arg<T: TryFromVal<Env, Val>>(env: &Env, args: &Vec<Val>, index: u32) -> Result<T, Error> {
+        args.get(index)
+            .and_then(|arg| T::try_from_val(env, &arg).ok())
+            .ok_or(Error::UnsupportedCall)
+    }
+
+    fn save_transaction(env: &Env, transaction: &Transaction) {
+        let key = (TX, transaction.id);
+        env.storage().persistent().set(&key, transaction);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, vec, Env, IntoVal};
+
+    /// Contract administered by the multisig
+    #[contract]
+    struct MockTarget;
+
+    #[contractimpl]
+    impl MockTarget {
+        pub fn init(env: Env, admin: Address) {
+            env.storage().instance().set(&OWNERS, &admin);
+        }
+
+        pub fn set_value(env: Env, value: u32) -> u32 {
+            let admin: Address = env.storage().instance().get(&OWNERS).unwrap();
+            admin.require_auth();
+            env.storage().instance().set(&THRESHOLD, &value);
+            value
+        }
+
+        pub fn value(env: Env) -> u32 {
+            env.storage().instance().get(&THRESHOLD).unwrap_or(0)
+        }
+    }
+
+    fn setup(env: &Env) -> (MultisigClient<'_>, Address, Address, Address) {
+        env.mock_all_auths();
+
+        let alice = Address::generate(env);
+        let bob = Address::generate(env);
+        let carol = Address::generate(env);
+        let multisig_id = env.register_contract(None, Multisig);
+        let multisig = MultisigClient::new(env, &multisig_id);
+        multisig.initialize(&vec![env, alice.clone(), bob.clone(), carol.clone()], &2);
+
+        (multisig, alice, bob, carol)
+    }
+
+    #[test]
+    fn test_initialize_rejects_bad_threshold() {
+        let env = Env::default();
+        let owner = Address::generate(&env);
+        let multisig_id = env.register_contract(None, Multisig);
+        let multisig = MultisigClient::new(&env, &multisig_id);
+
+        assert_eq!(
+            multisig.try_initialize(&vec![&env, owner.clone()], &2),
+            Err(Ok(Error::InvalidThreshold))
+        );
+        assert_eq!(
+            multisig.try_initialize(&vec![&env, owner.clone(), owner.clone()], &1),
+            Err(Ok(Error::AlreadyOwner))
+        );
+        multisig.initialize(&vec![&env, owner.clone()], &1);
+        assert_eq!(
+            multisig.try_initialize(&vec![&env, owner], &1),
+            Err(Ok(Error::AlreadyInitialized))
+        );
+    }
+
+    #[test]
+    fn test_call_runs_once_threshold_confirms() {
+        let env = Env::default();
+        let (multisig, alice, bob, carol) = setup(&env);
+
+        let target_id = env.register_contract(None, MockTarget);
+        let target = MockTargetClient::new(&env, &target_id);
+        target.init(&multisig.address);
+
+        let outsider = Address::generate(&env);
+        let function = Symbol::new(&env, "set_value");
+        let args = vec![&env, 7u32.into_val(&env)];
+        assert_eq!(
+            multisig.try_submit(&outsider, &target_id, &function, &args),
+            Err(Ok(Error::NotOwner))
+        );
+
+        let id = multisig.submit(&alice, &target_id, &function, &args);
+        assert!(multisig.is_confirmed(&id, &alice));
+        assert_eq!(
+            multisig.try_confirm(&alice, &id),
+            Err(Ok(Error::AlreadyConfirmed))
+        );
+        assert_eq!(
+            multisig.try_execute(&alice, &id).err(),
+            Some(Ok(Error::NotEnoughConfirmations))
+        );
+
+        multisig.confirm(&bob, &id);
+        assert_eq!(multisig.get_transaction(&id).confirmations, 2);
+        let result = multisig.execute(&carol, &id);
+        assert_eq!(u32::try_from_val(&env, &result).unwrap(), 7);
+        assert_eq!(target.value(), 7);
+        assert_eq!(
+            multisig.try_execute(&alice, &id).err(),
+            Some(Ok(Error::AlreadyExecuted))
+        );
+    }
+
+    #[test]
+    fn test_revoked_confirmation_no_longer_counts() {
+        let env = Env::default();
+        let (multisig, alice, bob, _) = setup(&env);
+
+        let target = Address::generate(&env);
+        let id = multisig.submit(&alice, &target, &Symbol::new(&env, "noop"), &vec![&env]);
+        multisig.confirm(&bob, &id);
+        multisig.revoke(&bob, &id);
+
+        assert!(!multisig.is_confirmed(&id, &bob));
+        assert_eq!(multisig.get_transaction(&id).confirmations, 1);
+        assert_eq!(multisig.try_revoke(&bob, &id), Err(Ok(Error::NotConfirmed)));
+        assert_eq!(
+            multisig.try_execute(&alice, &id).err(),
+            Some(Ok(Error::NotEnoughConfirmations))
+        );
+    }
+
+    #[test]
+    fn test_owner_set_changes_through_the_multisig() {
+        let env = Env::default();
+        let (multisig, alice, bob, carol) = setup(&env);
+
+        let own = multisig.address.clone();
+        let dave = Address::generate(&env);
+        let add = multisig.submit(
+            &alice,
+            &own,
+            &Symbol::new(&env, "add_owner"),
+            &vec![&env, dave.into_val(&env)],
+        );
+        multisig.confirm(&bob, &add);
+        multisig.execute(&alice, &add);
+        assert!(multisig.is_owner(&dave));
+
+        let remove = multisig.submit(
+            &alice,
+            &own,
+            &Symbol::new(&env, "remove_owner"),
+            &vec![&env, carol.into_val(&env)],
+        );
+        let raise = multisig.submit(
+            &alice,
+            &own,
+            &Symbol::new(&env, "change_threshold"),
+            &vec![&env, 4u32.into_val(&env)],
+        );
+        multisig.confirm(&carol, &remove);
+        multisig.confirm(&carol, &raise);
+        multisig.execute(&alice, &remove);
+        assert!(!multisig.is_owner(&carol));
+
+        // Carol's confirmation left with her, and three owners can't meet
+        // a threshold of four
+        multisig.confirm(&dave, &raise);
+        assert_eq!(
+            multisig.try_execute(&alice, &raise).err(),
+            Some(Ok(Error::InvalidThreshold))
+        );
+        assert_eq!(multisig.get_threshold(), 2);
+    }
+}
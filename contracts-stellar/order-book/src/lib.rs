@@ -0,0 +1,821 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+    Vec,
+};
+use stellhydra_access::pausable;
+use stellhydra_events::book::{BookSwap, OrderCancelled, OrderFilled, OrderPlaced};
+use stellhydra_events::Event;
+
+pub use stellhydra_events::book::Side;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub recipient: Address,
+    pub maker_fee_bps: u32,
+    pub taker_fee_bps: u32,
+}
+
+/// Bounds keeping every side of the book small enough to walk in one
+/// transaction
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BookLimits {
+    pub min_amount: i128, // smallest order, in base units
+    pub max_orders: u32,  // most orders resting on one side of a market
+}
+
+/// Limit order on the `base`/`quote` market
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Order {
+    pub id: u64,
+    pub owner: Address,
+    pub base: Address,
+    pub quote: Address,
+    pub side: Side,
+    pub price: i128, // quote units per base unit, scaled by PRICE_SCALE
+    pub amount: i128,
+    pub remaining: i128, // base amount still to fill
+    pub locked: i128,    // escrow left: base for sells, quote for buys
+    pub open: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    InvalidPrice = 4,
+    InvalidFee = 5,
+    OrderNotFound = 6,
+    OrderClosed = 7,
+    NoLiquidity = 8,
+    SlippageExceeded = 9,
+    Paused = 10,
+    OrderTooSmall = 11,
+    BookFull = 12,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const FEES: Symbol = symbol_short!("FEES");
+const COUNT: Symbol = symbol_short!("COUNT");
+const ORDER: Symbol = symbol_short!("ORDER");
+const BOOK: Symbol = symbol_short!("BOOK");
+const LIMITS: Symbol = symbol_short!("LIMITS");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Fixed-point scale of prices (7 decimals, as Stellar amounts)
+pub const PRICE_SCALE: i128 = 10_000_000;
+
+const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Orders resting on one side of a market unless configured otherwise
+pub const DEFAULT_MAX_ORDERS: u32 = 50;
+
+/// Central limit order book
+///
+/// Orders escrow what they sell and match against the opposite side at
+/// the resting order's price, best price first and oldest first at equal
+/// prices; what does not match rests on the book, as long as its side has
+/// room under the book limits. Makers and takers pay their fee out of
+/// what they receive. `swap` takes liquidity as a market order, which is
+/// how the router uses the book.
+#[contract]
+pub struct OrderBook;
+
+#[contractimpl]
+impl OrderBook {
+    pub fn initialize(env: Env, admin: Address, fees: FeeConfig) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        Self::check_fees(&fees)?;
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&FEES, &fees);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Update the fee schedule (only admin)
+    pub fn set_fees(env: Env, fees: FeeConfig) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Self::check_fees(&fees)?;
+
+        env.storage().instance().set(&FEES, &fees);
+        Ok(())
+    }
+
+    pub fn get_fees(env: Env) -> Result<FeeConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&FEES)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Update the minimum order size and the cap on resting orders per
+    /// side (only admin)
+    ///
+    /// Lowering the cap leaves fuller sides as they are; they only take
+    /// new orders once they drop below it.
+    pub fn set_limits(env: Env, limits: BookLimits) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        if limits.min_amount <= 0 || limits.max_orders == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        env.storage().instance().set(&LIMITS, &limits);
+        Ok(())
+    }
+
+    pub fn get_limits(env: Env) -> BookLimits {
+        env.storage().instance().get(&LIMITS).unwrap_or(BookLimits {
+            min_amount: 1,
+            max_orders: DEFAULT_MAX_ORDERS,
+        })
+    }
+
+    /// Place a limit order for `amount` of base, matching what it can
+    /// right away and resting the rest
+    pub fn place_order(
+        env: Env,
+        owner: Address,
+        base: Address,
+        quote: Address,
+        side: Side,
+        price: i128,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        owner.require_auth();
+        if pausable::is_paused(&env) {
+            return Err(Error::Paused);
+        }
+        if amount <= 0 || base == quote {
+            return Err(Error::InvalidAmount);
+        }
+        if amount < Self::get_limits(env.clone()).min_amount {
+            return Err(Error::OrderTooSmall);
+        }
+        if price <= 0 {
+            return Err(Error::InvalidPrice);
+        }
+
+        let (locked, escrow_token) = match side {
+            Side::Sell => (amount, base.clone()),
+            Side::Buy => (Self::ceil_value(amount, price), quote.clone()),
+        };
+        token::Client::new(&env, &escrow_token).transfer(
+            &owner,
+            &env.current_contract_address(),
+            &locked,
+        );
+
+        let id: u64 = env.storage().instance().get(&COUNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&COUNT, &id);
+        Self::extend_instance(&env);
+
+        let mut order = Order {
+            id,
+            owner: owner.clone(),
+            base,
+            quote,
+            side,
+            price,
+            amount,
+            remaining: amount,
+            locked,
+            open: true,
+        };
+        let received = Self::take(&env, &mut order, Some(price), true)?;
+        Self::pay_taker(&env, &order, received);
+
+        if order.remaining == 0 {
+            order.open = false;
+            Self::release(&env, &mut order);
+        } else {
+            Self::insert(&env, &order)?;
+        }
+        Self::save_order(&env, &order);
+
+        OrderPlaced {
+            id,
+            owner,
+            side,
+            price,
+            amount,
+            remaining: order.remaining,
+        }
+        .publish(&env);
+        Ok(id)
+    }
+
+    /// Cancel an open order and return its escrow (only its owner)
+    pub fn cancel_order(env: Env, id: u64) -> Result<(), Error> {
+        let mut order = Self::get_order(env.clone(), id)?;
+        order.owner.require_auth();
+        if !order.open {
+            return Err(Error::OrderClosed);
+        }
+
+        let key = (BOOK, order.base.clone(), order.quote.clone(), order.side);
+        let mut ids = Self::book_ids(&env, &order.base, &order.quote, order.side);
+        if let Some(index) = ids.first_index_of(id) {
+            ids.remove(index);
+        }
+        env.storage().persistent().set(&key, &ids);
+
+        order.open = false;
+        Self::release(&env, &mut order);
+        Self::save_order(&env, &order);
+
+        OrderCancelled {
+            id,
+            remaining: order.remaining,
+        }
+        .publish(&env);
+        Ok(())
+    }
+
+    /// Sell `amount_in` of `token_in` for `token_out` against resting
+    /// orders, requiring at least `min_out`
+    ///
+    /// Whatever the book cannot absorb stays with the taker.
+    pub fn swap(
+        env: Env,
+        taker: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+    ) -> Result<i128, Error> {
+        taker.require_auth();
+        if pausable::is_paused(&env) {
+            return Err(Error::Paused);
+        }
+        let mut order = Self::market_order(&env, &taker, &token_in, &token_out, amount_in)?;
+
+        token::Client::new(&env, &token_in).transfer(
+            &taker,
+            &env.current_contract_address(),
+            &amount_in,
+        );
+        let received = Self::take(&env, &mut order, None, true)?;
+        if received < min_out {
+            return Err(Error::SlippageExceeded);
+        }
+        Self::pay_taker(&env, &order, received);
+        Self::release(&env, &mut order);
+
+        BookSwap {
+            taker,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out: received,
+        }
+        .publish(&env);
+        Ok(received)
+    }
+
+    /// What `swap` would currently return, after the taker fee
+    pub fn get_quote(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+    ) -> Result<i128, Error> {
+        let caller = env.current_contract_address();
+        let mut order = Self::market_order(&env, &caller, &token_in, &token_out, amount_in)?;
+        Self::take(&env, &mut order, None, false)
+    }
+
+    pub fn get_order(env: Env, id: u64) -> Result<Order, Error> {
+        env.storage()
+            .persistent()
+            .get(&(ORDER, id))
+            .ok_or(Error::OrderNotFound)
+    }
+
+    /// Open orders on one side of a market, best first
+    pub fn get_book(
+        env: Env,
+        base: Address,
+        quote: Address,
+        side: Side,
+    ) -> Result<Vec<Order>, Error> {
+        let mut orders = Vec::new(&env);
+        for id in Self::book_ids(&env, &base, &quote, side).iter() {
+            orders.push_back(Self::get_order(env.clone(), id)?);
+        }
+        Ok(orders)
+    }
+}
+
+stellhydra_access::pausable!(OrderBook);
+
+impl stellhydra_access::Upgradeable for OrderBook {}
+
+stellhydra_access::upgradeable!(OrderBook);
+
+impl OrderBook {
+    fn check_fees(fees: &FeeConfig) -> Result<(), Error> {
+        if fees.maker_fee_bps > 10000 || fees.taker_fee_bps > 10000 {
+            return Err(Error::InvalidFee);
+        }
+        Ok(())
+    }
+
+    /// Transient order taking liquidity from whichever market trades
+    /// `token_in` for `token_out`
+    fn market_order(
+        env: &Env,
+        taker: &Address,
+        token_in: &Address,
+        token_out: &Address,
+        amount_in: i128,
+    ) -> Result<Order, Error> {
+        if amount_in <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let (base, quote, side, remaining) =
+            if !Self::book_ids(env, token_in, token_out, Side::Buy).is_empty() {
+                (token_in, token_out, Side::Sell, amount_in)
+            } else if !Self::book_ids(env, token_out, token_in, Side::Sell).is_empty() {
+                (token_out, token_in, Side::Buy, i128::MAX)
+            } else {
+                return Err(Error::NoLiquidity);
+            };
+
+        Ok(Order {
+            id: 0,
+            owner: taker.clone(),
+            base: base.clone(),
+            quote: quote.clone(),
+            side,
+            price: 0,
+            amount: amount_in,
+            remaining,
+            locked: amount_in,
+            open: true,
+        })
+    }
+
+    /// Match `taker` against the opposite side of its market, up to its
+    /// limit price, and return what the taker receives after fees
+    ///
+    /// Without `execute` nothing is stored or paid, for quoting.
+    fn take(
+        env: &Env,
+        taker: &mut Order,
+        limit: Option<i128>,
+        execute: bool,
+    ) -> Result<i128, Error> {
+        let fees = Self::get_fees(env.clone())?;
+        let maker_side = match taker.side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let ids = Self::book_ids(env, &taker.base, &taker.quote, maker_side);
+
+        let mut received = 0;
+        let mut taker_fees = 0;
+        let mut consumed = 0;
+        for id in ids.iter() {
+            let mut maker = Self::get_order(env.clone(), id)?;
+            let crosses = match (taker.side, limit) {
+                (_, None) => true,
+                (Side::Buy, Some(limit)) => maker.price <= limit,
+                (Side::Sell, Some(limit)) => maker.price >= limit,
+            };
+            if !crosses {
+                break;
+            }
+
+            // The buyer pays rounded up, so makers are never short-changed
+            let (fill, value) = match taker.side {
+                Side::Buy => {
+                    let affordable = taker.locked * PRICE_SCALE / maker.price;
+                    let fill = maker.remaining.min(taker.remaining).min(affordable);
+                    (fill, Self::ceil_value(fill, maker.price))
+                }
+                Side::Sell => {
+                    let fill = maker.remaining.min(taker.remaining);
+                    (fill, fill * maker.price / PRICE_SCALE)
+                }
+            };
+            if fill == 0 {
+                break;
+            }
+
+            // The maker gets the other side of the fill, less its fee
+            let (maker_gets, maker_token, taker_gets) = match taker.side {
+                Side::Buy => (value, taker.quote.clone(), fill),
+                Side::Sell => (fill, taker.base.clone(), value),
+            };
+            let maker_fee = maker_gets * fees.maker_fee_bps as i128 / BPS_DENOMINATOR;
+            let taker_fee = taker_gets * fees.taker_fee_bps as i128 / BPS_DENOMINATOR;
+            received += taker_gets - taker_fee;
+            taker_fees += taker_fee;
+
+            taker.remaining -= fill;
+            maker.remaining -= fill;
+            match taker.side {
+                Side::Buy => {
+                    taker.locked -= value;
+                    maker.locked -= fill;
+                }
+                Side::Sell => {
+                    taker.locked -= fill;
+                    maker.locked -= value;
+                }
+            }
+
+            if execute {
+                let token = token::Client::new(env, &maker_token);
+                token.transfer(
+                    &env.current_contract_address(),
+                    &maker.owner,
+                    &(maker_gets - maker_fee),
+                );
+                if maker_fee > 0 {
+                    token.transfer(&env.current_contract_address(), &fees.recipient, &maker_fee);
+                }
+                if maker.remaining == 0 {
+                    maker.open = false;
+                    Self::release(env, &mut maker);
+                }
+                Self::save_order(env, &maker);
+
+                OrderFilled {
+                    id: maker.id,
+                    taker: taker.owner.clone(),
+                    amount: fill,
+                    price: maker.price,
+                }
+                .publish(env);
+            }
+
+            if maker.remaining == 0 {
+                consumed += 1;
+            }
+            if taker.remaining == 0 || maker.remaining > 0 {
+                break;
+            }
+        }
+
+        if execute {
+            if consumed > 0 {
+                let key = (BOOK, taker.base.clone(), taker.quote.clone(), maker_side);
+                env.storage().persistent().set(&key, &ids.slice(consumed..));
+            }
+            if taker_fees > 0 {
+                let received_token = match taker.side {
+                    Side::Buy => &taker.base,
+                    Side::Sell => &taker.quote,
+                };
+                token::Client::new(env, received_token).transfer(
+                    &env.current_contract_address(),
+                    &fees.recipient,
+                    &taker_fees,
+                );
+            }
+        }
+        Ok(received)
+    }
+
+    fn pay_taker(env: &Env, taker: &Order, received: i128) {
+        if received <= 0 {
+            return;
+        }
+        let received_token = match taker.side {
+            Side::Buy => &taker.base,
+            Side::Sell => &taker.quote,
+        };
+        token::Client::new(env, received_token).transfer(
+            &env.current_contract_address(),
+            &taker.owner,
+            &received,
+        );
+    }
+
+    /// Return what is left of an order's escrow to its owner
+    fn release(env: &Env, order: &mut Order) {
+        if order.locked <= 0 {
+            return;
+        }
+        let escrow_token = match order.side {
+            Side::Sell => &order.base,
+            Side::Buy => &order.quote,
+        };
+        token::Client::new(env, escrow_token).transfer(
+            &env.current_contract_address(),
+            &order.owner,
+            &order.locked,
+        );
+        order.locked = 0;
+    }
+
+    /// Rest an order on its side of the book, behind orders at the same
+    /// or a better price
+    fn insert(env: &Env, order: &Order) -> Result<(), Error> {
+        let mut ids = Self::book_ids(env, &order.base, &order.quote, order.side);
+        if ids.len() >= Self::get_limits(env.clone()).max_orders {
+            return Err(Error::BookFull);
+        }
+
+        // Binary search for the first order this one beats
+        let (mut low, mut high) = (0, ids.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let other = Self::get_order(env.clone(), ids.get_unchecked(mid))?;
+            let better = match order.side {
+                Side::Buy => order.price > other.price,
+                Side::Sell => order.price < other.price,
+            };
+            if better {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        ids.insert(low, order.id);
+
+        let key = (BOOK, order.base.clone(), order.quote.clone(), order.side);
+        env.storage().persistent().set(&key, &ids);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        Ok(())
+    }
+
+    fn book_ids(env: &Env, base: &Address, quote: &Address, side: Side) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&(BOOK, base.clone(), quote.clone(), side))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Quote value of a base amount at a price, rounded up
+    fn ceil_value(amount: i128, price: i128) -> i128 {
+        (amount * price + PRICE_SCALE - 1) / PRICE_SCALE
+    }
+
+    fn save_order(env: &Env, order: &Order) {
+        let key = (ORDER, order.id);
+        env.storage().persistent().set(&key, order);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Env};
+
+    struct Setup<'a> {
+        book: OrderBookClient<'a>,
+        base: token::Client<'a>,
+        quote: token::Client<'a>,
+        fee_recipient: Address,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let base = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let quote = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let fee_recipient = Address::generate(env);
+
+        let book = OrderBookClient::new(env, &env.register_contract(None, OrderBook));
+        book.initialize(
+            &Address::generate(env),
+            &FeeConfig {
+                recipient: fee_recipient.clone(),
+                maker_fee_bps: 10,
+                taker_fee_bps: 30,
+            },
+        );
+
+        Setup {
+            book,
+            base: token::Client::new(env, &base),
+            quote: token::Client::new(env, &quote),
+            fee_recipient,
+        }
+    }
+
+    fn trader(env: &Env, token: &token::Client, amount: i128) -> Address {
+        let trader = Address::generate(env);
+        StellarAssetClient::new(env, &token.address).mint(&trader, &amount);
+        trader
+    }
+
+    #[test]
+    fn test_crossing_orders_match_at_resting_price() {
+        let env = Env::default();
+        let s = setup(&env);
+        let price = |p: i128| p * PRICE_SCALE;
+
+        let seller = trader(&env, &s.base, 10_000);
+        let cheap = s.book.place_order(
+            &seller,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Sell,
+            &price(2),
+            &1_000,
+        );
+        s.book.place_order(
+            &seller,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Sell,
+            &price(3),
+            &1_000,
+        );
+        assert_eq!(s.base.balance(&seller), 8_000);
+
+        // Buys 1_000 at 2 and 500 at 3, within its limit of 3
+        let buyer = trader(&env, &s.quote, 10_000);
+        let bid = s.book.place_order(
+            &buyer,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Buy,
+            &price(3),
+            &1_500,
+        );
+        assert_eq!(s.base.balance(&buyer), 1_500 - 4);
+        assert_eq!(s.quote.balance(&buyer), 10_000 - 3_500);
+        assert_eq!(s.quote.balance(&seller), 3_500 - 3);
+        assert_eq!(s.base.balance(&s.fee_recipient), 4);
+        assert_eq!(s.quote.balance(&s.fee_recipient), 3);
+
+        assert!(!s.book.get_order(&cheap).open);
+        assert!(!s.book.get_order(&bid).open);
+        let asks = s
+            .book
+            .get_book(&s.base.address, &s.quote.address, &Side::Sell);
+        assert_eq!(asks.len(), 1);
+        assert_eq!(asks.get(0).unwrap().remaining, 500);
+    }
+
+    #[test]
+    fn test_resting_orders_rank_by_price_and_cancel() {
+        let env = Env::default();
+        let s = setup(&env);
+        let buyer = trader(&env, &s.quote, 10_000);
+
+        let low = s.book.place_order(
+            &buyer,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Buy,
+            &PRICE_SCALE,
+            &1_000,
+        );
+        let high = s.book.place_order(
+            &buyer,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Buy,
+            &(2 * PRICE_SCALE),
+            &1_000,
+        );
+        let bids = s
+            .book
+            .get_book(&s.base.address, &s.quote.address, &Side::Buy);
+        assert_eq!(bids.get(0).unwrap().id, high);
+        assert_eq!(bids.get(1).unwrap().id, low);
+
+        s.book.cancel_order(&high);
+        assert_eq!(s.quote.balance(&buyer), 9_000);
+        assert_eq!(s.book.try_cancel_order(&high), Err(Ok(Error::OrderClosed)));
+        assert_eq!(
+            s.book
+                .get_book(&s.base.address, &s.quote.address, &Side::Buy)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_swap_takes_liquidity_as_market_order() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        let buyer = trader(&env, &s.quote, 10_000);
+        s.book.place_order(
+            &buyer,
+            &s.base.address,
+            &s.quote.address,
+            &Side::Buy,
+            &(2 * PRICE_SCALE),
+            &1_000,
+        );
+
+        // Selling 1_500 base: 1_000 fill at 2, the rest comes back
+        let taker = trader(&env, &s.base, 1_500);
+        let quote = s.book.get_quote(&s.base.address, &s.quote.address, &1_500);
+        assert_eq!(quote, 2_000 - 6);
+        assert_eq!(
+            s.book
+                .try_swap(&taker, &s.base.address, &s.quote.address, &1_500, &2_000),
+            Err(Ok(Error::SlippageExceeded))
+        );
+        assert_eq!(
+            s.book
+                .swap(&taker, &s.base.address, &s.quote.address, &1_500, &quote),
+            quote
+        );
+        assert_eq!(s.quote.balance(&taker), quote);
+        assert_eq!(s.base.balance(&taker), 500);
+        assert_eq!(s.base.balance(&buyer), 1_000 - 1);
+
+        assert_eq!(
+            s.book.try_get_quote(&s.base.address, &s.quote.address, &1),
+            Err(Ok(Error::NoLiquidity))
+        );
+    }
+
+    #[test]
+    fn test_limits_bound_each_side() {
+        let env = Env::default();
+        let s = setup(&env);
+        s.book.set_limits(&BookLimits {
+            min_amount: 100,
+            max_orders: 3,
+        });
+
+        let seller = trader(&env, &s.base, 10_000);
+        let place = |price: i128, amount: i128| {
+            s.book.try_place_order(
+                &seller,
+                &s.base.address,
+                &s.quote.address,
+                &Side::Sell,
+                &(price * PRICE_SCALE),
+                &amount,
+            )
+        };
+        assert_eq!(place(2, 99), Err(Ok(Error::OrderTooSmall)));
+
+        // Equal prices queue in time order, better ones jump ahead
+        let first = place(3, 100).unwrap().unwrap();
+        let second = place(3, 100).unwrap().unwrap();
+        let best = place(2, 100).unwrap().unwrap();
+        assert_eq!(place(1, 100), Err(Ok(Error::BookFull)));
+
+        let asks = s
+            .book
+            .get_book(&s.base.address, &s.quote.address, &Side::Sell);
+        let ids: Vec<u64> = Vec::from_array(&env, [best, first, second]);
+        let mut listed = Vec::new(&env);
+        for order in asks.iter() {
+            listed.push_back(order.id);
+        }
+        assert_eq!(listed, ids);
+
+        s.book.cancel_order(&first);
+        place(1, 100).unwrap().unwrap();
+    }
+}
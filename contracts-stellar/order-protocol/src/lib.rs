@@ -0,0 +1,383 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address,
+    BytesN, Env, IntoVal, Symbol, Val,
+};
+use stellhydra_access::ownable;
+
+/// Order a maker signed off-chain
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Order {
+    pub maker: Address,
+    pub maker_asset: Address,
+    pub maker_amount: i128,
+    pub taker_asset: Address,
+    pub taker_amount: i128,
+    pub expiry: u64,
+    pub nonce: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    InvalidOrder = 1,
+    UnknownKey = 2,
+    KeyAlreadyRegistered = 3,
+    OrderExpired = 4,
+    OrderCancelled = 5,
+    OrderFilled = 6,
+    InvalidAmount = 7,
+    NonceTooLow = 8,
+}
+
+// Storage keys
+const MAKER_KEY: Symbol = symbol_short!("MAKER_KEY");
+const KEY_MAKER: Symbol = symbol_short!("KEY_MAKER");
+const MIN_NONCE: Symbol = symbol_short!("MIN_NONCE");
+const FILLED: Symbol = symbol_short!("FILLED");
+const CANCELLED: Symbol = symbol_short!("CANCELLED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Fills limit orders signed off-chain
+///
+/// A maker registers an ed25519 key, approves this contract to spend the
+/// asset it sells, and signs the XDR of this contract's address followed
+/// by the order. Takers fill any part of the remainder until expiry. The
+/// maker cancels single orders, or every order below a nonce at once.
+#[contract]
+pub struct OrderProtocol;
+
+#[contractimpl]
+impl OrderProtocol {
+    /// Set the admin allowed to upgrade the protocol
+    ///
+    /// Orders are settled between makers and takers alone, so the admin
+    /// has no say over fills or cancellations.
+    pub fn initialize(env: Env, admin: Address) {
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+    }
+
+    /// Register the key a maker signs orders with (only the maker)
+    pub fn set_maker_key(env: Env, maker: Address, pubkey: BytesN<32>) -> Result<(), Error> {
+        maker.require_auth();
+
+        if let Some(owner) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&(KEY_MAKER, pubkey.clone()))
+        {
+            if owner != maker {
+                return Err(Error::KeyAlreadyRegistered);
+            }
+        }
+        if let Some(previous) = Self::get_maker_key(env.clone(), maker.clone()) {
+            env.storage().persistent().remove(&(KEY_MAKER, previous));
+        }
+
+        Self::save(&env, &(MAKER_KEY, maker.clone()), &pubkey);
+        Self::save(&env, &(KEY_MAKER, pubkey), &maker);
+        Ok(())
+    }
+
+    pub fn get_maker_key(env: Env, maker: Address) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&(MAKER_KEY, maker))
+    }
+
+    /// Hash identifying an order
+    pub fn hash_order(env: Env, order: Order) -> BytesN<32> {
+        env.crypto()
+            .sha256(&(env.current_contract_address(), order).to_xdr(&env))
+            .into()
+    }
+
+    /// Fill up to `maker_amount` of an order, paying its price pro rata
+    ///
+    /// The taker's side is rounded up so partial fills never underpay the
+    /// maker. Returns the maker amount actually filled, which is capped at
+    /// what is left of the order.
+    pub fn fill(
+        env: Env,
+        taker: Address,
+        order: Order,
+        signature: BytesN<64>,
+        maker_amount: i128,
+    ) -> Result<i128, Error> {
+        taker.require_auth();
+        if order.maker_amount <= 0 || order.taker_amount <= 0 {
+            return Err(Error::InvalidOrder);
+        }
+        if maker_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let pubkey =
+            Self::get_maker_key(env.clone(), order.maker.clone()).ok_or(Error::UnknownKey)?;
+        let message = (env.current_contract_address(), order.clone()).to_xdr(&env);
+        env.crypto().ed25519_verify(&pubkey, &message, &signature);
+
+        if env.ledger().timestamp() > order.expiry {
+            return Err(Error::OrderExpired);
+        }
+        if order.nonce < Self::get_min_nonce(env.clone(), order.maker.clone()) {
+            return Err(Error::NonceTooLow);
+        }
+        let hash = Self::hash_order(env.clone(), order.clone());
+        if env.storage().persistent().has(&(CANCELLED, hash.clone())) {
+            return Err(Error::OrderCancelled);
+        }
+
+        let filled = Self::get_filled(env.clone(), hash.clone());
+        let remaining = order.maker_amount - filled;
+        if remaining <= 0 {
+            return Err(Error::OrderFilled);
+        }
+        let maker_amount = maker_amount.min(remaining);
+        let taker_amount =
+            (maker_amount * order.taker_amount + order.maker_amount - 1) / order.maker_amount;
+
+        Self::save(&env, &(FILLED, hash.clone()), &(filled + maker_amount));
+
+        token::Client::new(&env, &order.taker_asset).transfer(&taker, &order.maker, &taker_amount);
+        token::Client::new(&env, &order.maker_asset).transfer_from(
+            &env.current_contract_address(),
+            &order.maker,
+            &taker,
+            &maker_amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("ORDER"), symbol_short!("FILLED"), hash),
+            (taker, maker_amount, taker_amount),
+        );
+        Ok(maker_amount)
+    }
+
+    /// Cancel one order (only its maker)
+    pub fn cancel_order(env: Env, order: Order) -> Result<(), Error> {
+        order.maker.require_auth();
+
+        let hash = Self::hash_order(env.clone(), order.clone());
+        Self::save(&env, &(CANCELLED, hash.clone()), &true);
+
+        env.events().publish(
+            (symbol_short!("ORDER"), symbol_short!("CANCELLED"), hash),
+            order.maker,
+        );
+        Ok(())
+    }
+
+    /// Cancel every order of the maker with a nonce below `min_nonce`
+    pub fn cancel_up_to(env: Env, maker: Address, min_nonce: u64) -> Result<(), Error> {
+        maker.require_auth();
+        if min_nonce <= Self::get_min_nonce(env.clone(), maker.clone()) {
+            return Err(Error::NonceTooLow);
+        }
+
+        Self::save(&env, &(MIN_NONCE, maker.clone()), &min_nonce);
+
+        env.events().publish(
+            (symbol_short!("ORDER"), symbol_short!("NONCE")),
+            (maker, min_nonce),
+        );
+        Ok(())
+    }
+
+    /// Lowest nonce the maker's orders may still carry
+    pub fn get_min_nonce(env: Env, maker: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&(MIN_NONCE, maker))
+            .unwrap_or(0)
+    }
+
+    /// Maker amount of an order filled so far
+    pub fn get_filled(env: Env, hash: BytesN<32>) -> i128 {
+        env.storage().persistent().get(&(FILLED, hash)).unwrap_or(0)
+    }
+
+    /// Maker amount still fillable, or zero if the order can't be filled
+    pub fn get_remaining(env: Env, order: Order) -> i128 {
+        let hash = Self::hash_order(env.clone(), order.clone());
+        if env.ledger().timestamp() > order.expiry
+            || order.nonce < Self::get_min_nonce(env.clone(), order.maker.clone())
+            || env.storage().persistent().has(&(CANCELLED, hash.clone()))
+        {
+            return 0;
+        }
+        (order.maker_amount - Self::get_filled(env, hash)).max(0)
+    }
+}
+
+stellhydra_access::ownable!(OrderProtocol);
+
+impl stellhydra_access::Upgradeable for OrderProtocol {}
+
+stellhydra_access::upgradeable!(OrderProtocol);
+
+impl OrderProtocol {
+    fn save(env: &Env, key: &impl IntoVal<Env, Val>, value: &impl IntoVal<Env, Val>) {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    struct Setup<'a> {
+        protocol: OrderProtocolClient<'a>,
+        sell: token::Client<'a>,
+        buy: token::Client<'a>,
+        maker: Address,
+        taker: Address,
+        signing_key: SigningKey,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let protocol_id = env.register_contract(None, OrderProtocol);
+        let protocol = OrderProtocolClient::new(env, &protocol_id);
+        let sell = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let buy = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+
+        let maker = Address::generate(env);
+        let taker = Address::generate(env);
+        StellarAssetClient::new(env, &sell).mint(&maker, &1_000);
+        StellarAssetClient::new(env, &buy).mint(&taker, &10_000);
+        token::Client::new(env, &sell).approve(&maker, &protocol_id, &1_000, &1_000);
+
+        let signing_key = SigningKey::from_bytes(&[3; 32]);
+        protocol.set_maker_key(
+            &maker,
+            &BytesN::from_array(env, &signing_key.verifying_key().to_bytes()),
+        );
+
+        Setup {
+            protocol,
+            sell: token::Client::new(env, &sell),
+            buy: token::Client::new(env, &buy),
+            maker,
+            taker,
+            signing_key,
+        }
+    }
+
+    fn order(s: &Setup, nonce: u64) -> Order {
+        Order {
+            maker: s.maker.clone(),
+            maker_asset: s.sell.address.clone(),
+            maker_amount: 1_000,
+            taker_asset: s.buy.address.clone(),
+            taker_amount: 3_000,
+            expiry: 500,
+            nonce,
+        }
+    }
+
+    fn sign(env: &Env, s: &Setup, order: &Order) -> BytesN<64> {
+        let message = (s.protocol.address.clone(), order.clone()).to_xdr(env);
+        let mut bytes = [0u8; 1024];
+        let bytes = &mut bytes[..message.len() as usize];
+        message.copy_into_slice(bytes);
+        BytesN::from_array(env, &s.signing_key.sign(bytes).to_bytes())
+    }
+
+    #[test]
+    fn test_orders_fill_in_parts() {
+        let env = Env::default();
+        let s = setup(&env);
+        let order = order(&s, 1);
+        let signature = sign(&env, &s, &order);
+
+        assert_eq!(s.protocol.fill(&s.taker, &order, &signature, &400), 400);
+        assert_eq!(s.sell.balance(&s.taker), 400);
+        assert_eq!(s.buy.balance(&s.maker), 1_200);
+        assert_eq!(s.protocol.get_remaining(&order), 600);
+
+        // Asking for more than is left fills the remainder
+        assert_eq!(s.protocol.fill(&s.taker, &order, &signature, &5_000), 600);
+        assert_eq!(s.sell.balance(&s.taker), 1_000);
+        assert_eq!(s.buy.balance(&s.maker), 3_000);
+        assert_eq!(
+            s.protocol.try_fill(&s.taker, &order, &signature, &1),
+            Err(Ok(Error::OrderFilled))
+        );
+    }
+
+    #[test]
+    fn test_tampered_orders_are_rejected() {
+        let env = Env::default();
+        let s = setup(&env);
+        let order = order(&s, 1);
+        let signature = sign(&env, &s, &order);
+
+        let mut cheaper = order.clone();
+        cheaper.taker_amount = 1;
+        assert!(s
+            .protocol
+            .try_fill(&s.taker, &cheaper, &signature, &1_000)
+            .is_err());
+
+        env.ledger().with_mut(|li| li.timestamp = 501);
+        assert_eq!(
+            s.protocol.try_fill(&s.taker, &order, &signature, &1_000),
+            Err(Ok(Error::OrderExpired))
+        );
+    }
+
+    #[test]
+    fn test_makers_cancel_orders() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        let first = order(&s, 1);
+        let first_signature = sign(&env, &s, &first);
+        s.protocol.cancel_order(&first);
+        assert_eq!(
+            s.protocol
+                .try_fill(&s.taker, &first, &first_signature, &1_000),
+            Err(Ok(Error::OrderCancelled))
+        );
+
+        let second = order(&s, 2);
+        let second_signature = sign(&env, &s, &second);
+        s.protocol.cancel_up_to(&s.maker, &3);
+        assert_eq!(s.protocol.get_remaining(&second), 0);
+        assert_eq!(
+            s.protocol
+                .try_fill(&s.taker, &second, &second_signature, &1_000),
+            Err(Ok(Error::NonceTooLow))
+        );
+
+        let third = order(&s, 3);
+        let third_signature = sign(&env, &s, &third);
+        assert_eq!(
+            s.protocol.fill(&s.taker, &third, &third_signature, &1_000),
+            1_000
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Weighted aggregation of round answers.
+//!
+//! Each oracle's answer for a pair counts with the weight the admin gave
+//! it (1 by default), so a high-quality source can dominate best-effort
+//! feeders without excluding them. A round publishes the weighted median
+//! of its answers, or their weighted mean if the pair is set up so.
+
+use soroban_sdk::{
+    contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol, Vec,
+};
+use stellhydra_access::require_admin;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const WEIGHTS: Symbol = symbol_short!("WEIGHTS");
+const AGGREGATE: Symbol = symbol_short!("AGGREGATE");
+
+// Weight of an oracle without one configured
+const DEFAULT_WEIGHT: u32 = 1;
+
+/// How a round's answers are combined
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Aggregation {
+    Median,
+    Mean,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Set the weights of oracles answering for a pair; unlisted oracles
+    /// keep the default weight (only admin)
+    pub fn set_pair_weights(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        weights: Map<Address, u32>,
+    ) {
+        require_admin(&env);
+
+        if weights.values().iter().any(|weight| weight == 0) {
+            panic!("Weight must be positive");
+        }
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        env.storage()
+            .persistent()
+            .set(&(WEIGHTS, token_pair), &weights);
+    }
+
+    pub fn get_pair_weights(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Map<Address, u32> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::weights_of(&env, &token_pair)
+    }
+
+    /// Choose how a pair's round answers are combined (only admin)
+    pub fn set_aggregation(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        aggregation: Aggregation,
+    ) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        env.storage()
+            .persistent()
+            .set(&(AGGREGATE, token_pair), &aggregation);
+    }
+
+    pub fn get_aggregation(env: Env, base_token: String, quote_token: String) -> Aggregation {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage()
+            .persistent()
+            .get(&(AGGREGATE, token_pair))
+            .unwrap_or(Aggregation::Median)
+    }
+}
+
+impl PriceOracle {
+    /// Combine a round's answers into the price it publishes
+    pub(crate) fn aggregate(
+        env: &Env,
+        token_pair: &TokenPair,
+        answers: &Map<Address, i128>,
+    ) -> i128 {
+        let weights = Self::weights_of(env, token_pair);
+
+        // Answers sorted by price, with their weights
+        let mut sorted: Vec<(i128, u32)> = Vec::new(env);
+        let mut total_weight: i128 = 0;
+        for (oracle, answer) in answers.iter() {
+            let weight = weights.get(oracle).unwrap_or(DEFAULT_WEIGHT);
+            total_weight += weight as i128;
+
+            let mut index = 0;
+            while index < sorted.len() && sorted.get_unchecked(index).0 < answer {
+                index += 1;
+            }
+            sorted.insert(index, (answer, weight));
+        }
+
+        let aggregation = Self::get_aggregation(
+            env.clone(),
+            token_pair.base.clone(),
+            token_pair.quote.clone(),
+        );
+        match aggregation {
+            Aggregation::Median => Self::weighted_median(&sorted, total_weight),
+            Aggregation::Mean => {
+                let mut sum: i128 = 0;
+                for (answer, weight) in sorted.iter() {
+                    sum = answer
+                        .checked_mul(weight as i128)
+                        .and_then(|value| sum.checked_add(value))
+                        .unwrap_or_else(|| panic!("Arithmetic overflow"));
+                }
+                sum / total_weight
+            }
+        }
+    }
+
+    /// First answer reaching half the total weight; the mean of it and the
+    /// next answer when it reaches exactly half
+    fn weighted_median(sorted: &Vec<(i128, u32)>, total_weight: i128) -> i128 {
+        let mut cumulative: i128 = 0;
+        for index in 0..sorted.len() {
+            let (answer, weight) = sorted.get_unchecked(index);
+            cumulative += weight as i128;
+
+            if cumulative * 2 == total_weight && index + 1 < sorted.len() {
+                return (answer + sorted.get_unchecked(index + 1).0) / 2;
+            }
+            if cumulative * 2 >= total_weight {
+                return answer;
+            }
+        }
+        panic!("No answers");
+    }
+
+    fn weights_of(env: &Env, token_pair: &TokenPair) -> Map<Address, u32> {
+        env.storage()
+            .persistent()
+            .get(&(WEIGHTS, token_pair.clone()))
+            .unwrap_or_else(|| Map::new(env))
+    }
+}
@@ -0,0 +1,116 @@
+//! Aliases of deprecated pairs.
+//!
+//! When a token migrates or a symbol is renamed, the old pair can be
+//! aliased to its successor. Reads against the old pair then resolve to
+//! the successor's feed and report it as deprecated, while new answers
+//! have to be submitted for the successor.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, Env, String, Symbol};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::PairAliased;
+use stellhydra_events::Event;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const ALIAS: Symbol = symbol_short!("ALIAS");
+
+/// Successor of a deprecated pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairAlias {
+    pub successor: TokenPair,
+    pub deprecated_at: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Deprecate a pair in favour of another one (only admin)
+    pub fn alias_pair(
+        env: Env,
+        old_base: String,
+        old_quote: String,
+        new_base: String,
+        new_quote: String,
+    ) {
+        require_admin(&env);
+
+        let old_pair = TokenPair {
+            base: old_base,
+            quote: old_quote,
+        };
+        let new_pair = TokenPair {
+            base: new_base,
+            quote: new_quote,
+        };
+        let mut current = Some(new_pair.clone());
+        while let Some(pair) = current {
+            if pair == old_pair {
+                panic!("Alias cycle");
+            }
+            current = Self::get_pair_alias(env.clone(), pair.base, pair.quote)
+                .map(|alias| alias.successor);
+        }
+
+        let alias = PairAlias {
+            successor: new_pair,
+            deprecated_at: env.ledger().timestamp(),
+        };
+        env.storage()
+            .persistent()
+            .set(&(ALIAS, old_pair.clone()), &alias);
+
+        PairAliased {
+            base: old_pair.base,
+            quote: old_pair.quote,
+            successor_base: alias.successor.base,
+            successor_quote: alias.successor.quote,
+        }
+        .publish(&env);
+    }
+
+    /// Make a deprecated pair resolve to itself again (only admin)
+    pub fn unalias_pair(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let key = (ALIAS, token_pair);
+        if !env.storage().persistent().has(&key) {
+            panic!("Pair is not aliased");
+        }
+        env.storage().persistent().remove(&key);
+    }
+
+    pub fn get_pair_alias(env: Env, base_token: String, quote_token: String) -> Option<PairAlias> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().get(&(ALIAS, token_pair))
+    }
+}
+
+impl PriceOracle {
+    /// Pair a read of `token_pair` resolves to, following aliases
+    pub(crate) fn successor(env: &Env, token_pair: &TokenPair) -> TokenPair {
+        let mut current = token_pair.clone();
+        while let Some(alias) = env
+            .storage()
+            .persistent()
+            .get::<_, PairAlias>(&(ALIAS, current.clone()))
+        {
+            current = alias.successor;
+        }
+        current
+    }
+
+    /// Panic if the pair has been deprecated
+    pub(crate) fn check_not_aliased(env: &Env, token_pair: &TokenPair) {
+        if env.storage().persistent().has(&(ALIAS, token_pair.clone())) {
+            panic!("Pair is deprecated");
+        }
+    }
+}
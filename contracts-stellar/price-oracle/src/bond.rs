@@ -0,0 +1,209 @@
+//! Oracle bonds.
+//!
+//! Once a bond is configured, every oracle posts `min_bond` of the bond
+//! token when it is added and can only submit while its bond covers that
+//! minimum. The admin can slash a bond for a bad submission. A removed
+//! oracle gets its bond back after the unbonding cooldown.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, token, Address, Env, Symbol};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::OracleSlashed;
+use stellhydra_events::Event;
+
+use crate::{PriceOracle, PriceOracleClient};
+
+const BOND_CFG: Symbol = symbol_short!("BOND_CFG");
+const BOND: Symbol = symbol_short!("BOND");
+
+/// Bond every oracle has to post
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BondConfig {
+    pub token: Address,
+    pub min_bond: i128,
+    pub cooldown: u64,
+}
+
+/// Bond held for an oracle
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleBond {
+    pub amount: i128,
+    /// Set once the oracle is removed; the bond unlocks `cooldown` later
+    pub removed_at: Option<u64>,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Require oracles to post a bond (only admin)
+    ///
+    /// Oracles added before the bond was configured must top up before
+    /// their next submission.
+    pub fn set_bond_config(env: Env, config: BondConfig) {
+        require_admin(&env);
+
+        if config.min_bond < 0 {
+            panic!("Bond must not be negative");
+        }
+        if let Some(current) = Self::get_bond_config(env.clone()) {
+            if current.token != config.token {
+                panic!("Bond token cannot change");
+            }
+        }
+
+        env.storage().instance().set(&BOND_CFG, &config);
+    }
+
+    pub fn get_bond_config(env: Env) -> Option<BondConfig> {
+        env.storage().instance().get(&BOND_CFG)
+    }
+
+    pub fn get_oracle_bond(env: Env, oracle: Address) -> Option<OracleBond> {
+        env.storage().persistent().get(&(BOND, oracle))
+    }
+
+    /// Add to an oracle's bond
+    pub fn top_up_bond(env: Env, oracle: Address, amount: i128) {
+        oracle.require_auth();
+
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+        let config = Self::get_bond_config(env.clone()).unwrap_or_else(|| panic!("No bond"));
+
+        token::Client::new(&env, &config.token).transfer(
+            &oracle,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let mut bond = Self::get_oracle_bond(env.clone(), oracle.clone()).unwrap_or(OracleBond {
+            amount: 0,
+            removed_at: None,
+        });
+        bond.amount += amount;
+        env.storage().persistent().set(&(BOND, oracle), &bond);
+    }
+
+    /// Withdraw a removed oracle's bond once the cooldown has passed
+    pub fn withdraw_bond(env: Env, oracle: Address) -> i128 {
+        oracle.require_auth();
+
+        let config = Self::get_bond_config(env.clone()).unwrap_or_else(|| panic!("No bond"));
+        let bond =
+            Self::get_oracle_bond(env.clone(), oracle.clone()).unwrap_or_else(|| panic!("No bond"));
+
+        let removed_at = match bond.removed_at {
+            Some(removed_at) if !Self::is_oracle_authorized(env.clone(), oracle.clone()) => {
+                removed_at
+            }
+            _ => panic!("Oracle still registered"),
+        };
+        if env.ledger().timestamp() < removed_at + config.cooldown {
+            panic!("Bond still locked");
+        }
+
+        env.storage().persistent().remove(&(BOND, oracle.clone()));
+        if bond.amount > 0 {
+            token::Client::new(&env, &config.token).transfer(
+                &env.current_contract_address(),
+                &oracle,
+                &bond.amount,
+            );
+        }
+
+        bond.amount
+    }
+
+    /// Confiscate part of an oracle's bond for a bad submission (only admin)
+    pub fn slash_oracle(env: Env, oracle: Address, amount: i128, recipient: Address) {
+        require_admin(&env);
+
+        Self::slash(&env, &oracle, amount, &recipient);
+    }
+}
+
+impl PriceOracle {
+    /// Take the bond an oracle must post when it is added
+    pub(crate) fn post_bond(env: &Env, oracle: &Address) {
+        let config = match Self::get_bond_config(env.clone()) {
+            Some(config) => config,
+            None => return,
+        };
+
+        let mut bond = Self::get_oracle_bond(env.clone(), oracle.clone()).unwrap_or(OracleBond {
+            amount: 0,
+            removed_at: None,
+        });
+
+        // A re-added oracle only tops up what is missing
+        let missing = config.min_bond - bond.amount;
+        if missing > 0 {
+            oracle.require_auth();
+            token::Client::new(env, &config.token).transfer(
+                oracle,
+                &env.current_contract_address(),
+                &missing,
+            );
+            bond.amount += missing;
+        }
+        bond.removed_at = None;
+
+        env.storage()
+            .persistent()
+            .set(&(BOND, oracle.clone()), &bond);
+    }
+
+    /// Start the unbonding cooldown of a removed oracle
+    pub(crate) fn start_unbonding(env: &Env, oracle: &Address) {
+        if let Some(mut bond) = Self::get_oracle_bond(env.clone(), oracle.clone()) {
+            bond.removed_at = Some(env.ledger().timestamp());
+            env.storage()
+                .persistent()
+                .set(&(BOND, oracle.clone()), &bond);
+        }
+    }
+
+    /// Panic unless the oracle's bond covers the configured minimum
+    pub(crate) fn check_bond(env: &Env, oracle: &Address) {
+        let config = match Self::get_bond_config(env.clone()) {
+            Some(config) => config,
+            None => return,
+        };
+
+        let bonded = Self::get_oracle_bond(env.clone(), oracle.clone())
+            .map(|bond| bond.amount)
+            .unwrap_or(0);
+        if bonded < config.min_bond {
+            panic!("Insufficient bond");
+        }
+    }
+
+    pub(crate) fn slash(env: &Env, oracle: &Address, amount: i128, recipient: &Address) {
+        let config = Self::get_bond_config(env.clone()).unwrap_or_else(|| panic!("No bond"));
+        let mut bond =
+            Self::get_oracle_bond(env.clone(), oracle.clone()).unwrap_or_else(|| panic!("No bond"));
+
+        if amount <= 0 || amount > bond.amount {
+            panic!("Invalid slash amount");
+        }
+
+        bond.amount -= amount;
+        env.storage()
+            .persistent()
+            .set(&(BOND, oracle.clone()), &bond);
+
+        token::Client::new(env, &config.token).transfer(
+            &env.current_contract_address(),
+            recipient,
+            &amount,
+        );
+
+        OracleSlashed {
+            oracle: oracle.clone(),
+            amount,
+            recipient: recipient.clone(),
+        }
+        .publish(env);
+    }
+}
@@ -0,0 +1,160 @@
+//! Circuit breaker on extreme moves.
+//!
+//! When an accepted price moves further than the configured limit from
+//! the previous one within a heartbeat of the pair, the pair is suspended.
+//! A suspended pair is served like a frozen one until the admin, or the
+//! round quorum of oracles, resumes it after review. Pairs without a
+//! configured heartbeat compare every pair of consecutive prices.
+
+use soroban_sdk::{contractimpl, symbol_short, Address, Env, Map, String, Symbol};
+use stellhydra_access::{ownable, require_admin};
+use stellhydra_events::oracle::{PairResumed, PairSuspended};
+use stellhydra_events::Event;
+use stellhydra_math::bps_of;
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, TokenPair};
+
+const BREAKER: Symbol = symbol_short!("BREAKER");
+const SUSPENDED: Symbol = symbol_short!("SUSPENDED");
+const RSM_VOTES: Symbol = symbol_short!("RSM_VOTES");
+
+#[contractimpl]
+impl PriceOracle {
+    /// Set the largest move, in basis points, a price may make within a
+    /// heartbeat; zero disables the breaker (only admin)
+    pub fn set_circuit_breaker(env: Env, max_move_bps: u32) {
+        require_admin(&env);
+
+        env.storage().instance().set(&BREAKER, &max_move_bps);
+    }
+
+    pub fn get_circuit_breaker(env: Env) -> u32 {
+        env.storage().instance().get(&BREAKER).unwrap_or(0)
+    }
+
+    /// Resume a suspended pair (the admin, or authorized oracles voting)
+    ///
+    /// The admin resumes it at once; oracles do it by reaching the round
+    /// quorum in votes.
+    pub fn resume_pair(env: Env, caller: Address, base_token: String, quote_token: String) {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if !Self::suspended(&env, &token_pair) {
+            panic!("Pair is not suspended");
+        }
+
+        if ownable::get_admin(&env) == Some(caller.clone()) {
+            caller.require_auth();
+            Self::resume(&env, &token_pair);
+            return;
+        }
+
+        Self::check_oracle(&env, &caller);
+        let votes_key = (RSM_VOTES, token_pair.clone());
+        let mut votes: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&votes_key)
+            .unwrap_or_else(|| Map::new(&env));
+        votes.set(caller, true);
+
+        // Votes of removed oracles no longer count
+        let agreeing = votes
+            .keys()
+            .iter()
+            .filter(|voter| Self::is_oracle_authorized(env.clone(), voter.clone()))
+            .count() as u32;
+
+        if agreeing >= Self::get_quorum(env.clone()) {
+            Self::resume(&env, &token_pair);
+        } else {
+            env.storage().persistent().set(&votes_key, &votes);
+        }
+    }
+
+    pub fn is_pair_suspended(env: Env, base_token: String, quote_token: String) -> bool {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::suspended(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    pub(crate) fn suspended(env: &Env, token_pair: &TokenPair) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(SUSPENDED, token_pair.clone()))
+    }
+
+    /// Suspend the pair if its new price moved too far from the previous
+    /// one
+    pub(crate) fn check_breaker(
+        env: &Env,
+        token_pair: &TokenPair,
+        previous: Option<PriceData>,
+        price_data: &PriceData,
+    ) {
+        let max_move_bps = Self::get_circuit_breaker(env.clone());
+        let previous = match previous {
+            Some(previous) if max_move_bps > 0 => previous,
+            _ => return,
+        };
+
+        let config = Self::get_pair_config(
+            env.clone(),
+            token_pair.base.clone(),
+            token_pair.quote.clone(),
+        );
+        if let Some(config) = config {
+            if price_data.timestamp.saturating_sub(previous.timestamp) > config.heartbeat {
+                return;
+            }
+        }
+
+        let previous_price =
+            Self::rescale(env, previous.price, previous.decimals, price_data.decimals);
+        if previous_price == 0 {
+            return;
+        }
+        let change = bps_of(
+            env,
+            (price_data.price - previous_price).abs(),
+            previous_price.abs(),
+        )
+        .unwrap_or(i128::MAX);
+        if change <= max_move_bps as i128 {
+            return;
+        }
+
+        env.storage()
+            .persistent()
+            .set(&(SUSPENDED, token_pair.clone()), &true);
+        PairSuspended {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+            previous_price: previous.price,
+            price: price_data.price,
+        }
+        .publish(env);
+    }
+
+    fn resume(env: &Env, token_pair: &TokenPair) {
+        env.storage()
+            .persistent()
+            .remove(&(SUSPENDED, token_pair.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(RSM_VOTES, token_pair.clone()));
+
+        PairResumed {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+        }
+        .publish(env);
+    }
+}
@@ -0,0 +1,79 @@
+//! Committee-written pairs.
+//!
+//! A pair can be handed to a single committee address, such as a multisig
+//! or an aggregator contract, instead of the registered oracles. The
+//! committee's answer stands for the whole round, so the committee can
+//! change how it reaches agreement without touching this contract. Only
+//! the committee may then write the pair, under its own authorization.
+
+use soroban_sdk::{contractimpl, symbol_short, Address, Env, String, Symbol};
+use stellhydra_access::require_admin;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair, PENDING};
+
+const COMMITTEE: Symbol = symbol_short!("COMMITTEE");
+
+#[contractimpl]
+impl PriceOracle {
+    /// Hand a pair to a committee, or back to the oracles (only admin)
+    pub fn set_pair_committee(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        committee: Option<Address>,
+    ) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        // Answers collected under the previous writers don't carry over
+        env.storage()
+            .persistent()
+            .remove(&(PENDING, token_pair.clone()));
+
+        match committee {
+            Some(committee) => env
+                .storage()
+                .persistent()
+                .set(&(COMMITTEE, token_pair), &committee),
+            None => env.storage().persistent().remove(&(COMMITTEE, token_pair)),
+        }
+    }
+
+    pub fn get_pair_committee(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<Address> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::committee(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    pub(crate) fn committee(env: &Env, token_pair: &TokenPair) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&(COMMITTEE, token_pair.clone()))
+    }
+
+    /// Panic unless the submitter may write the pair: its committee if it
+    /// has one, otherwise an authorized, bonded oracle assigned to it
+    pub(crate) fn check_writer(env: &Env, submitter: &Address, token_pair: &TokenPair) {
+        match Self::committee(env, token_pair) {
+            Some(committee) if committee == *submitter => {}
+            Some(_) => panic!("Pair is written by its committee"),
+            None => {
+                Self::check_registered(env, submitter);
+                Self::check_feed(env, submitter, token_pair);
+            }
+        }
+    }
+}
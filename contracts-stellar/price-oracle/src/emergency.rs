@@ -0,0 +1,190 @@
+//! Timelocked admin price overrides.
+//!
+//! When every oracle of a pair is offline the admin can propose a price.
+//! It only goes live once the override delay has passed and the admin
+//! executes it, and any fresh submission from an authorized oracle in the
+//! meantime cancels it.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, Env, String, Symbol};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::{OverrideCancelled, OverrideExecuted, OverrideProposed};
+use stellhydra_events::Event;
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, RoundData, TokenPair, PENDING};
+
+const OVERRIDE: Symbol = symbol_short!("OVERRIDE");
+const OVR_DELAY: Symbol = symbol_short!("OVR_DELAY");
+
+// Time an override waits before it can go live unless configured otherwise
+const DEFAULT_OVERRIDE_DELAY: u64 = 3_600;
+
+/// Admin price waiting for its delay to pass
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceOverride {
+    pub price: i128,
+    pub decimals: u32,
+    pub proposed_at: u64,
+    pub active_at: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Set how long an override waits before it can go live (only admin)
+    pub fn set_override_delay(env: Env, delay: u64) {
+        require_admin(&env);
+
+        env.storage().instance().set(&OVR_DELAY, &delay);
+    }
+
+    pub fn get_override_delay(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&OVR_DELAY)
+            .unwrap_or(DEFAULT_OVERRIDE_DELAY)
+    }
+
+    /// Propose an emergency price for a pair (only admin)
+    ///
+    /// The price keeps the decimals of the pair's latest price.
+    pub fn propose_override(env: Env, base_token: String, quote_token: String, price: i128) {
+        require_admin(&env);
+
+        if price <= 0 {
+            panic!("Price must be positive");
+        }
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let latest: PriceData = env
+            .storage()
+            .persistent()
+            .get(&token_pair)
+            .unwrap_or_else(|| panic!("Pair has no price"));
+
+        let now = env.ledger().timestamp();
+        let proposal = PriceOverride {
+            price,
+            decimals: latest.decimals,
+            proposed_at: now,
+            active_at: now + Self::get_override_delay(env.clone()),
+        };
+        env.storage()
+            .persistent()
+            .set(&(OVERRIDE, token_pair.clone()), &proposal);
+
+        OverrideProposed {
+            base: token_pair.base,
+            quote: token_pair.quote,
+            price: proposal.price,
+            decimals: proposal.decimals,
+            active_at: proposal.active_at,
+        }
+        .publish(&env);
+    }
+
+    /// Make a proposed override the pair's price once its delay has passed
+    /// (only admin)
+    ///
+    /// The override skips every check a submission goes through, so only
+    /// the admin decides when it lands.
+    pub fn execute_override(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let proposal =
+            Self::pending_override(&env, &token_pair).unwrap_or_else(|| panic!("No override"));
+
+        let now = env.ledger().timestamp();
+        if now < proposal.active_at {
+            panic!("Override still timelocked");
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&(OVERRIDE, token_pair.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(PENDING, token_pair.clone()));
+
+        let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+        let round_data = RoundData {
+            round_id,
+            price: proposal.price,
+            decimals: proposal.decimals,
+            started_at: proposal.proposed_at,
+            updated_at: now,
+            submissions: 0,
+            confidence: 0,
+            answered_in_round: round_id,
+        };
+
+        OverrideExecuted {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+            price: proposal.price,
+        }
+        .publish(&env);
+        Self::finalize_round(
+            &env,
+            token_pair,
+            round_data,
+            String::from_str(&env, "override"),
+        );
+    }
+
+    /// Withdraw a proposed override (only admin)
+    pub fn cancel_override(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if !Self::drop_override(&env, &token_pair) {
+            panic!("No override");
+        }
+    }
+
+    pub fn get_override(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<PriceOverride> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::pending_override(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    /// Cancel a pair's override, returning whether there was one
+    pub(crate) fn drop_override(env: &Env, token_pair: &TokenPair) -> bool {
+        let key = (OVERRIDE, token_pair.clone());
+        if !env.storage().persistent().has(&key) {
+            return false;
+        }
+        env.storage().persistent().remove(&key);
+
+        OverrideCancelled {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+        }
+        .publish(env);
+        true
+    }
+
+    fn pending_override(env: &Env, token_pair: &TokenPair) -> Option<PriceOverride> {
+        env.storage()
+            .persistent()
+            .get(&(OVERRIDE, token_pair.clone()))
+    }
+}
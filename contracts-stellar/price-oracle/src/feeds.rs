@@ -0,0 +1,144 @@
+//! Per-oracle feed assignment.
+//!
+//! The admin assigns each oracle the pairs it is expected to feed. Once
+//! feed restriction is switched on, submissions to unassigned pairs are
+//! rejected. Both directions of the assignment are kept for monitoring.
+
+use soroban_sdk::{contractimpl, symbol_short, Address, Env, String, Symbol, Vec};
+use stellhydra_access::require_admin;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const FEEDS: Symbol = symbol_short!("FEEDS");
+const FEEDERS: Symbol = symbol_short!("FEEDERS");
+const FEED_ONLY: Symbol = symbol_short!("FEED_ONLY");
+
+#[contractimpl]
+impl PriceOracle {
+    /// Only accept submissions to assigned pairs (only admin)
+    pub fn set_feed_restriction(env: Env, enabled: bool) {
+        require_admin(&env);
+
+        env.storage().instance().set(&FEED_ONLY, &enabled);
+    }
+
+    pub fn is_feed_restricted(env: Env) -> bool {
+        env.storage().instance().get(&FEED_ONLY).unwrap_or(false)
+    }
+
+    /// Assign a pair to an oracle (only admin)
+    pub fn assign_pair(env: Env, oracle: Address, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        if !Self::is_oracle_authorized(env.clone(), oracle.clone()) {
+            panic!("Unauthorized oracle");
+        }
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        let mut feeds = Self::feeds_of(&env, &oracle);
+        if feeds.contains(&token_pair) {
+            return;
+        }
+        feeds.push_back(token_pair.clone());
+        env.storage()
+            .persistent()
+            .set(&(FEEDS, oracle.clone()), &feeds);
+
+        let mut feeders = Self::feeders_of(&env, &token_pair);
+        feeders.push_back(oracle);
+        env.storage()
+            .persistent()
+            .set(&(FEEDERS, token_pair), &feeders);
+    }
+
+    /// Take a pair away from an oracle (only admin)
+    pub fn unassign_pair(env: Env, oracle: Address, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::unassign(&env, &oracle, &token_pair);
+    }
+
+    /// Pairs an oracle is assigned to feed
+    pub fn get_oracle_feeds(env: Env, oracle: Address) -> Vec<TokenPair> {
+        Self::feeds_of(&env, &oracle)
+    }
+
+    /// Oracles assigned to feed a pair
+    pub fn get_pair_feeders(env: Env, base_token: String, quote_token: String) -> Vec<Address> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::feeders_of(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    /// Panic if feeds are restricted and the pair isn't assigned to the oracle
+    pub(crate) fn check_feed(env: &Env, oracle: &Address, token_pair: &TokenPair) {
+        if Self::is_feed_restricted(env.clone())
+            && !Self::feeds_of(env, oracle).contains(token_pair)
+        {
+            panic!("Pair not assigned");
+        }
+    }
+
+    /// Drop every assignment of a removed oracle
+    pub(crate) fn clear_feeds(env: &Env, oracle: &Address) {
+        for token_pair in Self::feeds_of(env, oracle).iter() {
+            Self::unassign(env, oracle, &token_pair);
+        }
+    }
+
+    fn unassign(env: &Env, oracle: &Address, token_pair: &TokenPair) {
+        let mut feeds = Self::feeds_of(env, oracle);
+        if let Some(index) = feeds.first_index_of(token_pair) {
+            feeds.remove(index);
+        }
+        let mut feeders = Self::feeders_of(env, token_pair);
+        if let Some(index) = feeders.first_index_of(oracle) {
+            feeders.remove(index);
+        }
+
+        if feeds.is_empty() {
+            env.storage().persistent().remove(&(FEEDS, oracle.clone()));
+        } else {
+            env.storage()
+                .persistent()
+                .set(&(FEEDS, oracle.clone()), &feeds);
+        }
+        if feeders.is_empty() {
+            env.storage()
+                .persistent()
+                .remove(&(FEEDERS, token_pair.clone()));
+        } else {
+            env.storage()
+                .persistent()
+                .set(&(FEEDERS, token_pair.clone()), &feeders);
+        }
+    }
+
+    fn feeds_of(env: &Env, oracle: &Address) -> Vec<TokenPair> {
+        env.storage()
+            .persistent()
+            .get(&(FEEDS, oracle.clone()))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn feeders_of(env: &Env, token_pair: &TokenPair) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&(FEEDERS, token_pair.clone()))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+}
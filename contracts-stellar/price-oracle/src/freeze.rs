@@ -0,0 +1,125 @@
+//! Per-pair freezes.
+//!
+//! A frozen pair serves no price, nor do the inverse and cross pairs
+//! computed from it, so consumers halt on that market alone.
+//! `get_price_status` still reports the last price and flags it frozen.
+//! The admin freezes and unfreezes directly; oracles do it by reaching the
+//! round quorum in votes.
+
+use soroban_sdk::{contractimpl, symbol_short, Address, Env, Map, String, Symbol};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::{PairFrozen, PairUnfrozen};
+use stellhydra_events::Event;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const FROZEN: Symbol = symbol_short!("FROZEN");
+const FRZ_VOTES: Symbol = symbol_short!("FRZ_VOTES");
+
+#[contractimpl]
+impl PriceOracle {
+    /// Stop serving a pair's price (only admin)
+    pub fn freeze_pair(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        Self::set_frozen(&env, &token_pair, true);
+    }
+
+    /// Serve a frozen pair's price again (only admin)
+    pub fn unfreeze_pair(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        Self::set_frozen(&env, &token_pair, false);
+    }
+
+    /// Vote to freeze or unfreeze a pair (only authorized oracles)
+    ///
+    /// The pair changes state once the round quorum votes the same way.
+    pub fn vote_freeze(
+        env: Env,
+        oracle: Address,
+        base_token: String,
+        quote_token: String,
+        frozen: bool,
+    ) {
+        Self::check_oracle(&env, &oracle);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if Self::frozen(&env, &token_pair) == frozen {
+            return;
+        }
+
+        let votes_key = (FRZ_VOTES, token_pair.clone());
+        let mut votes: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&votes_key)
+            .unwrap_or_else(|| Map::new(&env));
+        votes.set(oracle, frozen);
+
+        // Votes of removed oracles no longer count
+        let agreeing = votes
+            .iter()
+            .filter(|(voter, vote)| {
+                *vote == frozen && Self::is_oracle_authorized(env.clone(), voter.clone())
+            })
+            .count() as u32;
+
+        if agreeing >= Self::get_quorum(env.clone()) {
+            Self::set_frozen(&env, &token_pair, frozen);
+        } else {
+            env.storage().persistent().set(&votes_key, &votes);
+        }
+    }
+
+    pub fn is_pair_frozen(env: Env, base_token: String, quote_token: String) -> bool {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::frozen(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    pub(crate) fn frozen(env: &Env, token_pair: &TokenPair) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(FROZEN, token_pair.clone()))
+    }
+
+    fn set_frozen(env: &Env, token_pair: &TokenPair, frozen: bool) {
+        env.storage()
+            .persistent()
+            .remove(&(FRZ_VOTES, token_pair.clone()));
+        if frozen {
+            env.storage()
+                .persistent()
+                .set(&(FROZEN, token_pair.clone()), &true);
+        } else {
+            env.storage()
+                .persistent()
+                .remove(&(FROZEN, token_pair.clone()));
+        }
+
+        let base = token_pair.base.clone();
+        let quote = token_pair.quote.clone();
+        if frozen {
+            PairFrozen { base, quote }.publish(env);
+        } else {
+            PairUnfrozen { base, quote }.publish(env);
+        }
+    }
+}
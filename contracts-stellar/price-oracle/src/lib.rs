@@ -1,8 +1,13 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol,
+    contract, contractimpl, contracttype, symbol_short, Address, Env, IntoVal, Map, String, Symbol,
+    Val, Vec,
 };
+use stellhydra_access::{ownable, require_admin, require_not_paused};
+use stellhydra_events::oracle::{AssetRegistered, PriceHeld, PriceUpdated};
+use stellhydra_events::Event;
+use stellhydra_math::{bps_of, mul_div, pow10};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -11,6 +16,8 @@ pub struct PriceData {
     pub decimals: u32,
     pub timestamp: u64,
     pub source: String,
+    /// Half-width of the price's confidence interval, in the price's decimals
+    pub confidence: i128,
 }
 
 #[contracttype]
@@ -20,10 +27,165 @@ pub struct TokenPair {
     pub quote: String,
 }
 
+/// Finalized answer for a round
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundData {
+    pub round_id: u64,
+    pub price: i128,
+    pub decimals: u32,
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub submissions: u32,
+    pub confidence: i128,
+    /// Round in which the answer was computed; rounds are never carried
+    /// over, so this always equals `round_id`
+    pub answered_in_round: u64,
+}
+
+/// Round collecting answers until quorum is reached
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingRound {
+    pub round_id: u64,
+    pub decimals: u32,
+    pub started_at: u64,
+    pub answers: Map<Address, i128>,
+    /// Widest confidence reported so far
+    pub confidence: i128,
+}
+
+mod aggregation;
+mod alias;
+mod bond;
+mod breaker;
+mod committee;
+mod emergency;
+mod feeds;
+mod freeze;
+mod liveness;
+mod lp;
+mod optimistic;
+mod push;
+mod reflector;
+mod registry;
+mod sep40;
+mod signed;
+mod subscription;
+mod ttl;
+mod units;
+mod volatility;
+
+pub use aggregation::Aggregation;
+pub use alias::PairAlias;
+pub use bond::{BondConfig, OracleBond};
+pub use emergency::PriceOverride;
+pub use liveness::{MissedHeartbeat, OracleLiveness, PairLiveness};
+pub use lp::{LiquidityPoolClient, LiquidityPoolInterface, LpFeed, PoolInfo};
+pub use optimistic::OptimisticPrice;
+pub use push::{PriceConsumer, PriceConsumerClient};
+pub use reflector::{CachedPrice, ReflectorClient, ReflectorFeed, ReflectorInterface};
+pub use registry::PairInfo;
+pub use sep40::{Asset, Sep40Config, Sep40PriceData};
+pub use signed::SignedPrice;
+pub use subscription::ReadFees;
+
+/// One entry of a batch price update
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceUpdate {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+    pub confidence: i128,
+    pub decimals: u32,
+    pub source: String,
+}
+
+/// Update cadence expected for a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairConfig {
+    pub max_staleness: u64,
+    pub heartbeat: u64,
+}
+
+/// Latest price with its age and staleness under the pair's config
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceStatus {
+    pub price_data: PriceData,
+    pub age: u64,
+    pub is_stale: bool,
+    /// Frozen, or suspended by the circuit breaker
+    pub is_frozen: bool,
+    /// The pair is an alias and the price is its successor's
+    pub is_deprecated: bool,
+}
+
+/// Latest price with its age, origin and flags
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceDetails {
+    pub price: i128,
+    pub decimals: u32,
+    pub timestamp: u64,
+    pub age: u64,
+    pub confidence: i128,
+    pub source: String,
+    /// Oracles that contributed to the round; 0 for external and computed
+    /// prices
+    pub submissions: u32,
+    pub is_stale: bool,
+    pub is_frozen: bool,
+    pub is_deprecated: bool,
+}
+
+/// Limit on how far a single oracle can move a price without a second
+/// oracle confirming it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviationGuard {
+    pub max_deviation_bps: u32,
+    pub confirm_window: u64,
+}
+
+/// Round answer held back by the deviation guard
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HeldPrice {
+    pub round: RoundData,
+    pub oracle: Address,
+    pub source: String,
+    pub held_at: u64,
+}
+
 // Storage keys
-const ADMIN: Symbol = symbol_short!("ADMIN");
 const ORACLES: Symbol = symbol_short!("ORACLES");
-const PRICES: Symbol = symbol_short!("PRICES");
+const QUORUM: Symbol = symbol_short!("QUORUM");
+const PENDING: Symbol = symbol_short!("PENDING");
+const ROUND: Symbol = symbol_short!("ROUND");
+const LATEST: Symbol = symbol_short!("LATEST");
+const HISTORY: Symbol = symbol_short!("HISTORY");
+const HIST_LEN: Symbol = symbol_short!("HIST_LEN");
+const HIST_FROM: Symbol = symbol_short!("HIST_FROM");
+const HIST_SIZE: Symbol = symbol_short!("HIST_SIZE");
+const DEVIATION: Symbol = symbol_short!("DEVIATION");
+const HELD: Symbol = symbol_short!("HELD");
+const PAIR_CFG: Symbol = symbol_short!("PAIR_CFG");
+const PIVOT: Symbol = symbol_short!("PIVOT");
+const ASSET_ADDR: Symbol = symbol_short!("ASSET_ADR");
+const ASSET_SYM: Symbol = symbol_short!("ASSET_SYM");
+
+// Number of price points kept per pair unless configured otherwise
+const DEFAULT_HISTORY_SIZE: u32 = 48;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
 #[contract]
 pub struct PriceOracle;
@@ -32,12 +194,12 @@ pub struct PriceOracle;
 impl PriceOracle {
     /// Initialize the price oracle
     pub fn initialize(env: Env, admin: Address) {
-        if env.storage().instance().has(&ADMIN) {
+        if ownable::has_admin(&env) {
             panic!("Contract already initialized");
         }
 
         admin.require_auth();
-        env.storage().instance().set(&ADMIN, &admin);
+        ownable::init_admin(&env, &admin);
 
         // Initialize empty oracle list
         let empty_oracles: Map<Address, bool> = Map::new(&env);
@@ -45,9 +207,10 @@ impl PriceOracle {
     }
 
     /// Add oracle address (only admin)
+    ///
+    /// If a bond is configured, the oracle posts it here.
     pub fn add_oracle(env: Env, oracle: Address) {
-        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
+        require_admin(&env);
 
         let mut oracles: Map<Address, bool> = env
             .storage()
@@ -55,14 +218,14 @@ impl PriceOracle {
             .get(&ORACLES)
             .unwrap_or_else(|| Map::new(&env));
 
+        Self::post_bond(&env, &oracle);
         oracles.set(oracle, true);
         env.storage().instance().set(&ORACLES, &oracles);
     }
 
     /// Remove oracle address (only admin)
     pub fn remove_oracle(env: Env, oracle: Address) {
-        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
+        require_admin(&env);
 
         let mut oracles: Map<Address, bool> = env
             .storage()
@@ -70,11 +233,32 @@ impl PriceOracle {
             .get(&ORACLES)
             .unwrap_or_else(|| Map::new(&env));
 
+        if oracles.contains_key(oracle.clone()) {
+            Self::start_unbonding(&env, &oracle);
+            Self::clear_feeds(&env, &oracle);
+        }
         oracles.remove(oracle);
         env.storage().instance().set(&ORACLES, &oracles);
     }
 
-    /// Update price (only authorized oracles)
+    /// Set how many oracle answers finalize a round (only admin)
+    pub fn set_quorum(env: Env, quorum: u32) {
+        require_admin(&env);
+
+        if quorum == 0 {
+            panic!("Quorum must be positive");
+        }
+
+        env.storage().instance().set(&QUORUM, &quorum);
+    }
+
+    pub fn get_quorum(env: Env) -> u32 {
+        env.storage().instance().get(&QUORUM).unwrap_or(1)
+    }
+
+    /// Update price (only authorized oracles, or the pair's committee)
+    ///
+    /// Submits an answer to the pair's current round.
     pub fn update_price(
         env: Env,
         oracle: Address,
@@ -84,134 +268,2949 @@ impl PriceOracle {
         decimals: u32,
         source: String,
     ) {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+
+        oracle.require_auth();
+        Self::submit(
+            &env, oracle, token_pair, round_id, price, 0, decimals, source,
+        );
+    }
+
+    /// Update price together with its confidence interval (only authorized
+    /// oracles)
+    ///
+    /// A round's confidence is the widest one reported by its submitters.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_price_with_confidence(
+        env: Env,
+        oracle: Address,
+        base_token: String,
+        quote_token: String,
+        price: i128,
+        confidence: i128,
+        decimals: u32,
+        source: String,
+    ) {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+
         oracle.require_auth();
+        Self::submit(
+            &env, oracle, token_pair, round_id, price, confidence, decimals, source,
+        );
+    }
 
-        // Check if oracle is authorized
-        let oracles: Map<Address, bool> = env
-            .storage()
-            .instance()
-            .get(&ORACLES)
-            .unwrap_or_else(|| Map::new(&env));
+    /// Update prices of several pairs under a single authorization (only
+    /// authorized oracles)
+    ///
+    /// Either every entry is applied or none is.
+    pub fn update_prices(env: Env, oracle: Address, updates: Vec<PriceUpdate>) {
+        oracle.require_auth();
 
-        if !oracles.get(oracle).unwrap_or(false) {
-            panic!("Unauthorized oracle");
+        for update in updates.iter() {
+            let token_pair = TokenPair {
+                base: update.base,
+                quote: update.quote,
+            };
+            let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+
+            Self::submit(
+                &env,
+                oracle.clone(),
+                token_pair,
+                round_id,
+                update.price,
+                update.confidence,
+                update.decimals,
+                update.source,
+            );
         }
+    }
+
+    /// Update price for a pair of registered token contracts (only
+    /// authorized oracles)
+    pub fn update_price_by_address(
+        env: Env,
+        oracle: Address,
+        base_token: Address,
+        quote_token: Address,
+        price: i128,
+        decimals: u32,
+        source: String,
+    ) {
+        let token_pair = Self::address_pair(&env, base_token, quote_token)
+            .unwrap_or_else(|| panic!("Unknown asset"));
+        let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+
+        oracle.require_auth();
+        Self::submit(
+            &env, oracle, token_pair, round_id, price, 0, decimals, source,
+        );
+    }
 
+    /// Submit an answer for a specific round (only authorized oracles)
+    ///
+    /// The round finalizes with the pair's weighted aggregate of the
+    /// answers once quorum is reached.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_round(
+        env: Env,
+        oracle: Address,
+        base_token: String,
+        quote_token: String,
+        round_id: u64,
+        price: i128,
+        decimals: u32,
+        source: String,
+    ) {
         let token_pair = TokenPair {
             base: base_token,
             quote: quote_token,
         };
 
-        let price_data = PriceData {
-            price,
-            decimals,
-            timestamp: env.ledger().timestamp(),
-            source,
+        oracle.require_auth();
+        Self::submit(
+            &env, oracle, token_pair, round_id, price, 0, decimals, source,
+        );
+    }
+
+    /// Get a finalized round
+    ///
+    /// Like every free read, this leaves premium pairs out.
+    pub fn get_round_data(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        round_id: u64,
+    ) -> Option<RoundData> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
         };
+        if Self::premium(&env, &token_pair) {
+            return None;
+        }
 
-        env.storage().persistent().set(&token_pair, &price_data);
+        env.storage()
+            .persistent()
+            .get(&(ROUND, token_pair, round_id))
+    }
 
-        // Emit price update event
-        env.events().publish(
-            (symbol_short!("PRICE"), symbol_short!("UPDATE")),
-            (token_pair, price_data),
-        );
+    /// Get the id of the latest finalized round (0 if none)
+    pub fn get_latest_round(env: Env, base_token: String, quote_token: String) -> u64 {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::latest_round_id(&env, &token_pair)
     }
 
-    /// Get latest price for token pair
-    pub fn get_price(env: Env, base_token: String, quote_token: String) -> Option<PriceData> {
+    /// Get the latest finalized round, so consumers can record exactly
+    /// which print they used
+    pub fn latest_round(env: Env, base_token: String, quote_token: String) -> Option<RoundData> {
         let token_pair = TokenPair {
             base: base_token,
             quote: quote_token,
         };
+        if Self::premium(&env, &token_pair) {
+            return None;
+        }
+        let round_id = Self::latest_round_id(&env, &token_pair);
 
-        env.storage().persistent().get(&token_pair)
+        env.storage()
+            .persistent()
+            .get(&(ROUND, token_pair, round_id))
     }
 
-    /// Get price with staleness check
-    pub fn get_price_with_staleness_check(
+    /// Get the round still collecting answers, if any
+    pub fn get_pending_round(
         env: Env,
         base_token: String,
         quote_token: String,
-        max_staleness: u64,
-    ) -> Option<PriceData> {
-        let price_data = Self::get_price(env.clone(), base_token, quote_token)?;
-        let current_time = env.ledger().timestamp();
+    ) -> Option<PendingRound> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
 
-        if current_time - price_data.timestamp > max_staleness {
-            None // Price is too stale
-        } else {
-            Some(price_data)
-        }
+        env.storage().persistent().get(&(PENDING, token_pair))
     }
 
-    /// Check if oracle is authorized
-    pub fn is_oracle_authorized(env: Env, oracle: Address) -> bool {
-        let oracles: Map<Address, bool> = env
+    /// Configure the deviation guard; a zero limit disables it (only admin)
+    pub fn set_deviation_guard(env: Env, max_deviation_bps: u32, confirm_window: u64) {
+        require_admin(&env);
+
+        let guard = DeviationGuard {
+            max_deviation_bps,
+            confirm_window,
+        };
+        env.storage().instance().set(&DEVIATION, &guard);
+    }
+
+    pub fn get_deviation_guard(env: Env) -> Option<DeviationGuard> {
+        env.storage().instance().get(&DEVIATION)
+    }
+
+    /// Confirm a price held by the deviation guard (only oracles allowed to
+    /// write the pair, other than the one that submitted it)
+    ///
+    /// Confirming writes the price, so it is refused whenever a submission
+    /// would be: while paused, or for a frozen or deprecated pair.
+    pub fn confirm_price(env: Env, oracle: Address, base_token: String, quote_token: String) {
+        oracle.require_auth();
+        require_not_paused(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        Self::check_not_aliased(&env, &token_pair);
+        Self::check_writer(&env, &oracle, &token_pair);
+        if Self::frozen(&env, &token_pair) {
+            panic!("Pair is frozen");
+        }
+
+        let held: HeldPrice = env
             .storage()
-            .instance()
-            .get(&ORACLES)
-            .unwrap_or_else(|| Map::new(&env));
+            .persistent()
+            .get(&(HELD, token_pair.clone()))
+            .unwrap_or_else(|| panic!("No held price"));
 
-        oracles.get(oracle).unwrap_or(false)
+        if held.oracle == oracle {
+            panic!("Confirmation requires a second oracle");
+        }
+        let guard = Self::get_deviation_guard(env.clone()).unwrap();
+        if env.ledger().timestamp() > held.held_at + guard.confirm_window {
+            panic!("Confirmation window expired");
+        }
+
+        Self::finalize_round(&env, token_pair, held.round, held.source);
     }
 
-    /// Get all authorized oracles
-    pub fn get_oracles(env: Env) -> Map<Address, bool> {
-        env.storage()
-            .instance()
-            .get(&ORACLES)
-            .unwrap_or_else(|| Map::new(&env))
+    /// Get the price held back by the deviation guard, if any
+    pub fn get_held_price(env: Env, base_token: String, quote_token: String) -> Option<HeldPrice> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().get(&(HELD, token_pair))
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{testutils::Address as _, Address, Env, String};
+    /// Set how many past prices are kept per pair (only admin)
+    pub fn set_history_size(env: Env, size: u32) {
+        require_admin(&env);
 
-    #[test]
-    fn test_initialize_and_add_oracle() {
-        let env = Env::default();
-        env.mock_all_auths();
+        if size == 0 {
+            panic!("History size must be positive");
+        }
 
-        let contract_id = env.register_contract(None, PriceOracle);
-        let client = PriceOracleClient::new(&env, &contract_id);
+        env.storage().instance().set(&HIST_SIZE, &size);
+    }
 
-        let admin = Address::generate(&env);
-        let oracle = Address::generate(&env);
+    pub fn get_history_size(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&HIST_SIZE)
+            .unwrap_or(DEFAULT_HISTORY_SIZE)
+    }
 
-        client.initialize(&admin);
-        client.add_oracle(&oracle);
+    /// Get up to `count` recent prices for a pair, newest first
+    ///
+    /// Empty for premium pairs.
+    pub fn get_price_history(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        count: u32,
+    ) -> Vec<PriceData> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let mut history = Vec::new(&env);
+        if Self::premium(&env, &token_pair) {
+            return history;
+        }
 
-        assert!(client.is_oracle_authorized(&oracle));
+        let (oldest, total) = Self::history_range(&env, &token_pair);
+        let mut seq = total;
+        while seq > oldest && history.len() < count {
+            seq -= 1;
+            match Self::history_entry(&env, &token_pair, seq) {
+                Some(entry) => history.push_back(entry),
+                None => break,
+            }
+        }
+        history
     }
 
-    #[test]
-    fn test_update_and_get_price() {
-        let env = Env::default();
-        env.mock_all_auths();
+    /// Get the price that was current at `timestamp`, if it is still in
+    /// the pair's history and the pair is not premium
+    pub fn get_price_at(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        timestamp: u64,
+    ) -> Option<PriceData> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if Self::premium(&env, &token_pair) {
+            return None;
+        }
 
-        let contract_id = env.register_contract(None, PriceOracle);
-        let client = PriceOracleClient::new(&env, &contract_id);
+        // Binary search for the last entry at or before `timestamp`. Entries
+        // pruned before the history size grew count as too old.
+        let (mut low, mut high) = Self::history_range(&env, &token_pair);
+        let mut found = None;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match Self::history_entry(&env, &token_pair, mid) {
+                Some(entry) if entry.timestamp > timestamp => high = mid,
+                Some(entry) => {
+                    found = Some(entry);
+                    low = mid + 1;
+                }
+                None => low = mid + 1,
+            }
+        }
+        found
+    }
 
-        let admin = Address::generate(&env);
-        let oracle = Address::generate(&env);
+    /// Get the time-weighted average price over the last `window_secs`
+    ///
+    /// Returns None unless the retained history covers the whole window,
+    /// and for premium pairs.
+    pub fn get_twap(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        window_secs: u64,
+    ) -> Option<i128> {
+        if window_secs == 0 {
+            panic!("Window must be positive");
+        }
 
-        client.initialize(&admin);
-        client.add_oracle(&oracle);
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if Self::premium(&env, &token_pair) {
+            return None;
+        }
 
-        let base = String::from_str(&env, "XLM");
-        let quote = String::from_str(&env, "USD");
-        let price = 1000000i128; // $0.10 with 8 decimals
-        let decimals = 8u32;
-        let source = String::from_str(&env, "StellarX");
+        let now = env.ledger().timestamp();
+        let window_start = now.saturating_sub(window_secs);
+        let (oldest, total) = Self::history_range(&env, &token_pair);
 
-        client.update_price(&oracle, &base, &quote, &price, &decimals, &source);
+        // Each price holds from its timestamp until the next update
+        let mut weighted: i128 = 0;
+        let mut end = now;
+        let mut decimals = None;
+        let mut seq = total;
+        while seq > oldest {
+            seq -= 1;
+            let entry = Self::history_entry(&env, &token_pair, seq)?;
+            if *decimals.get_or_insert(entry.decimals) != entry.decimals {
+                panic!("Decimals changed within window");
+            }
 
-        let price_data = client.get_price(&base, &quote).unwrap();
-        assert_eq!(price_data.price, price);
-        assert_eq!(price_data.decimals, decimals);
-        assert_eq!(price_data.source, source);
+            let start = entry.timestamp.max(window_start);
+            if end > start {
+                weighted += entry.price * (end - start) as i128;
+            }
+            if entry.timestamp <= window_start {
+                return Some(weighted / window_secs as i128);
+            }
+            end = entry.timestamp;
+        }
+
+        None
     }
-}
\ No newline at end of file
+
+    /// Get latest price for token pair
+    ///
+    /// Deprecated pairs resolve to their successor. Pairs our oracles don't
+    /// feed fall back to their Reflector feed, if
+    /// any, then to the inverse of the reverse pair and finally to a price
+    /// derived through the pivot token. Computed prices are marked by their
+    /// source ("inverse" or "derived"). Premium pairs are only served by
+    /// `get_premium_price`.
+    pub fn get_price(env: Env, base_token: String, quote_token: String) -> Option<PriceData> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::lookup(&env, &token_pair, false)
+    }
+
+    /// Get latest price for a pair of registered token contracts
+    pub fn get_price_by_address(
+        env: Env,
+        base_token: Address,
+        quote_token: Address,
+    ) -> Option<PriceData> {
+        let token_pair = Self::address_pair(&env, base_token, quote_token)?;
+        Self::get_price(env, token_pair.base, token_pair.quote)
+    }
+
+    /// Register the token contract a symbol refers to (only admin)
+    ///
+    /// Symbols and addresses map one to one, so a second token claiming an
+    /// existing symbol has to be registered under a different one.
+    pub fn register_asset(env: Env, symbol: String, token: Address) {
+        require_admin(&env);
+
+        let addr_key = (ASSET_ADDR, symbol.clone());
+        let sym_key = (ASSET_SYM, token.clone());
+        if env.storage().persistent().has(&addr_key) || env.storage().persistent().has(&sym_key) {
+            panic!("Asset already registered");
+        }
+
+        env.storage().persistent().set(&addr_key, &token);
+        env.storage().persistent().set(&sym_key, &symbol);
+
+        AssetRegistered { symbol, token }.publish(&env);
+    }
+
+    /// Remove a symbol from the registry (only admin)
+    pub fn unregister_asset(env: Env, symbol: String) {
+        require_admin(&env);
+
+        let addr_key = (ASSET_ADDR, symbol);
+        let token: Address = env
+            .storage()
+            .persistent()
+            .get(&addr_key)
+            .unwrap_or_else(|| panic!("Unknown asset"));
+
+        env.storage().persistent().remove(&addr_key);
+        env.storage().persistent().remove(&(ASSET_SYM, token));
+    }
+
+    pub fn get_asset_address(env: Env, symbol: String) -> Option<Address> {
+        env.storage().persistent().get(&(ASSET_ADDR, symbol))
+    }
+
+    pub fn get_asset_symbol(env: Env, token: Address) -> Option<String> {
+        env.storage().persistent().get(&(ASSET_SYM, token))
+    }
+
+    /// Price of `base` in `quote` composed from the `base`/`via` and
+    /// `quote`/`via` prices
+    pub fn get_derived_price(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        via: String,
+    ) -> Option<PriceData> {
+        Self::derive(&env, base_token, quote_token, via, false)
+    }
+
+    /// Set or clear the token used to derive prices of pairs without a
+    /// direct feed (only admin)
+    pub fn set_pivot(env: Env, pivot: Option<String>) {
+        require_admin(&env);
+
+        match pivot {
+            Some(pivot) => env.storage().instance().set(&PIVOT, &pivot),
+            None => env.storage().instance().remove(&PIVOT),
+        }
+    }
+
+    pub fn get_pivot(env: Env) -> Option<String> {
+        env.storage().instance().get(&PIVOT)
+    }
+
+    /// Get price only if its confidence interval is no wider than
+    /// `max_confidence`
+    pub fn get_price_with_confidence(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        max_confidence: i128,
+    ) -> Option<PriceData> {
+        let price_data = Self::get_price(env, base_token, quote_token)?;
+
+        if price_data.confidence > max_confidence {
+            None // Market too wide
+        } else {
+            Some(price_data)
+        }
+    }
+
+    /// Set the staleness limit and expected heartbeat of a pair (only admin)
+    pub fn set_pair_config(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        max_staleness: u64,
+        heartbeat: u64,
+    ) {
+        require_admin(&env);
+
+        if heartbeat > max_staleness {
+            panic!("Heartbeat exceeds max staleness");
+        }
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let config = PairConfig {
+            max_staleness,
+            heartbeat,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&(PAIR_CFG, token_pair), &config);
+    }
+
+    pub fn get_pair_config(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<PairConfig> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().get(&(PAIR_CFG, token_pair))
+    }
+
+    /// Check if a pair has no price, or one older than its max staleness
+    pub fn is_stale(env: Env, base_token: String, quote_token: String) -> bool {
+        match Self::get_price_status(env, base_token, quote_token) {
+            Some(status) => status.is_stale,
+            None => true,
+        }
+    }
+
+    /// Get latest price with its age and staleness flag
+    pub fn get_price_status(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<PriceStatus> {
+        let requested = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let token_pair = Self::successor(&env, &requested);
+        let is_deprecated = token_pair != requested;
+        let config = Self::get_pair_config(
+            env.clone(),
+            token_pair.base.clone(),
+            token_pair.quote.clone(),
+        );
+
+        // A frozen or suspended pair still reports its last price
+        let is_frozen = Self::frozen(&env, &token_pair) || Self::suspended(&env, &token_pair);
+        let price_data = if is_frozen {
+            if Self::premium(&env, &token_pair) {
+                return None;
+            }
+            env.storage().persistent().get(&token_pair)?
+        } else {
+            Self::lookup(&env, &token_pair, false)?
+        };
+
+        let age = env
+            .ledger()
+            .timestamp()
+            .saturating_sub(price_data.timestamp);
+        let is_stale = match config {
+            Some(config) => age > config.max_staleness,
+            None => false,
+        };
+
+        Some(PriceStatus {
+            price_data,
+            age,
+            is_stale,
+            is_frozen,
+            is_deprecated,
+        })
+    }
+
+    /// Get latest price with everything needed to judge it in one read
+    pub fn get_price_detailed(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<PriceDetails> {
+        let token_pair = Self::successor(
+            &env,
+            &TokenPair {
+                base: base_token.clone(),
+                quote: quote_token.clone(),
+            },
+        );
+        let status = Self::get_price_status(env.clone(), base_token, quote_token)?;
+        let price_data = status.price_data;
+
+        // Only prices aggregated by our own rounds have submitters to count
+        let stored: Option<PriceData> = env.storage().persistent().get(&token_pair);
+        let submissions = match stored {
+            Some(stored) if stored == price_data => {
+                let round_id = Self::latest_round_id(&env, &token_pair);
+                env.storage()
+                    .persistent()
+                    .get::<_, RoundData>(&(ROUND, token_pair, round_id))
+                    .map(|round| round.submissions)
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        };
+
+        Some(PriceDetails {
+            price: price_data.price,
+            decimals: price_data.decimals,
+            timestamp: price_data.timestamp,
+            age: status.age,
+            confidence: price_data.confidence,
+            source: price_data.source,
+            submissions,
+            is_stale: status.is_stale,
+            is_frozen: status.is_frozen,
+            is_deprecated: status.is_deprecated,
+        })
+    }
+
+    /// Check if oracle is authorized
+    pub fn is_oracle_authorized(env: Env, oracle: Address) -> bool {
+        let oracles: Map<Address, bool> = env
+            .storage()
+            .instance()
+            .get(&ORACLES)
+            .unwrap_or_else(|| Map::new(&env));
+
+        oracles.get(oracle).unwrap_or(false)
+    }
+
+    /// Get all authorized oracles
+    pub fn get_oracles(env: Env) -> Map<Address, bool> {
+        env.storage()
+            .instance()
+            .get(&ORACLES)
+            .unwrap_or_else(|| Map::new(&env))
+    }
+
+    fn check_oracle(env: &Env, oracle: &Address) {
+        oracle.require_auth();
+        Self::check_registered(env, oracle);
+    }
+
+    /// Panic unless the oracle is authorized and bonded
+    fn check_registered(env: &Env, oracle: &Address) {
+        // Check if oracle is authorized
+        if !Self::is_oracle_authorized(env.clone(), oracle.clone()) {
+            panic!("Unauthorized oracle");
+        }
+        Self::check_bond(env, oracle);
+    }
+
+    /// Record an authorized oracle's or the committee's answer for a pair's
+    /// round
+    #[allow(clippy::too_many_arguments)]
+    fn submit(
+        env: &Env,
+        oracle: Address,
+        token_pair: TokenPair,
+        round_id: u64,
+        price: i128,
+        confidence: i128,
+        decimals: u32,
+        source: String,
+    ) {
+        require_not_paused(env);
+        if confidence < 0 {
+            panic!("Confidence must not be negative");
+        }
+        Self::check_not_aliased(env, &token_pair);
+        Self::check_writer(env, &oracle, &token_pair);
+
+        // A live oracle makes an emergency override unnecessary
+        Self::drop_override(env, &token_pair);
+        Self::check_no_proposal(env, &token_pair);
+
+        // Only the round after the latest finalized one is open
+        if round_id != Self::latest_round_id(env, &token_pair) + 1 {
+            panic!("Round not open");
+        }
+
+        let pending_key = (PENDING, token_pair.clone());
+        let mut round = match env
+            .storage()
+            .persistent()
+            .get::<_, PendingRound>(&pending_key)
+        {
+            Some(round) if round.round_id == round_id => round,
+            _ => PendingRound {
+                round_id,
+                decimals,
+                started_at: env.ledger().timestamp(),
+                answers: Map::new(env),
+                confidence: 0,
+            },
+        };
+
+        if round.decimals != decimals {
+            panic!("Decimals mismatch");
+        }
+        if round.answers.contains_key(oracle.clone()) {
+            panic!("Already submitted");
+        }
+        round.answers.set(oracle.clone(), price);
+        Self::record_submission(env, &oracle, &token_pair);
+        round.confidence = round.confidence.max(confidence);
+
+        // A committee's answer is a whole round on its own
+        let by_committee = Self::committee(env, &token_pair).is_some();
+        let below_quorum = !by_committee && round.answers.len() < Self::get_quorum(env.clone());
+        if below_quorum && !Self::is_optimistic(env, &token_pair) {
+            env.storage().persistent().set(&pending_key, &round);
+            return;
+        }
+        env.storage().persistent().remove(&pending_key);
+
+        let now = env.ledger().timestamp();
+        let round_data = RoundData {
+            round_id,
+            price: Self::aggregate(env, &token_pair, &round.answers),
+            decimals,
+            started_at: round.started_at,
+            updated_at: now,
+            submissions: round.answers.len(),
+            confidence: round.confidence,
+            answered_in_round: round_id,
+        };
+
+        // Short of quorum, the answer waits out the dispute window instead
+        if below_quorum {
+            Self::propose(env, &token_pair, oracle, round_data, source);
+            return;
+        }
+
+        // A lone oracle moving the price too far needs a second oracle to
+        // confirm it
+        if !by_committee
+            && round_data.submissions < 2
+            && Self::exceeds_deviation(env, &token_pair, &round_data)
+        {
+            let held = HeldPrice {
+                round: round_data,
+                oracle,
+                source,
+                held_at: now,
+            };
+            env.storage()
+                .persistent()
+                .set(&(HELD, token_pair.clone()), &held);
+
+            PriceHeld {
+                base: token_pair.base,
+                quote: token_pair.quote,
+                price: held.round.price,
+            }
+            .publish(env);
+            return;
+        }
+
+        Self::finalize_round(env, token_pair, round_data, source);
+    }
+
+    fn finalize_round(env: &Env, token_pair: TokenPair, round_data: RoundData, source: String) {
+        let round_id = round_data.round_id;
+        let now = env.ledger().timestamp();
+        env.storage()
+            .persistent()
+            .remove(&(HELD, token_pair.clone()));
+        Self::settle_proposal(env, &token_pair);
+        env.storage()
+            .persistent()
+            .set(&(ROUND, token_pair.clone(), round_id), &round_data);
+        env.storage()
+            .persistent()
+            .set(&(LATEST, token_pair.clone()), &round_id);
+
+        let price_data = PriceData {
+            price: round_data.price,
+            decimals: round_data.decimals,
+            timestamp: now,
+            source,
+            confidence: round_data.confidence,
+        };
+
+        let previous: Option<PriceData> = env.storage().persistent().get(&token_pair);
+        env.storage().persistent().set(&token_pair, &price_data);
+        Self::check_breaker(env, &token_pair, previous, &price_data);
+        Self::record_history(env, &token_pair, &price_data);
+        Self::list_pair(env, &token_pair, price_data.decimals, &price_data.source);
+
+        for key in [
+            token_pair.clone().into_val(env),
+            (LATEST, token_pair.clone()).into_val(env),
+            (ROUND, token_pair.clone(), round_id).into_val(env),
+        ] {
+            env.storage().persistent().extend_ttl::<Val>(
+                &key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        }
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        // Emit price update event
+        PriceUpdated {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+            price: price_data.price,
+            decimals: price_data.decimals,
+            timestamp: price_data.timestamp,
+            source: price_data.source.clone(),
+            confidence: price_data.confidence,
+        }
+        .publish(env);
+        Self::notify_consumers(env, &token_pair, &price_data);
+    }
+
+    /// Pair of registered symbols for two token contracts
+    fn address_pair(env: &Env, base: Address, quote: Address) -> Option<TokenPair> {
+        Some(TokenPair {
+            base: Self::get_asset_symbol(env.clone(), base)?,
+            quote: Self::get_asset_symbol(env.clone(), quote)?,
+        })
+    }
+
+    /// Resolve a pair's price, using premium pairs only if `paid`
+    fn lookup(env: &Env, token_pair: &TokenPair, paid: bool) -> Option<PriceData> {
+        if let Some(price_data) = Self::resolve_price(env, token_pair, paid) {
+            return Some(price_data);
+        }
+
+        let pivot = Self::get_pivot(env.clone())?;
+        if token_pair.base == pivot || token_pair.quote == pivot {
+            return None;
+        }
+        Self::derive(
+            env,
+            token_pair.base.clone(),
+            token_pair.quote.clone(),
+            pivot,
+            paid,
+        )
+    }
+
+    /// Price fed by our oracles or, failing that, by the pair's Reflector
+    /// feed or liquidity pool
+    fn direct_price(env: &Env, token_pair: &TokenPair, paid: bool) -> Option<PriceData> {
+        let token_pair = &Self::successor(env, token_pair);
+        if Self::frozen(env, token_pair)
+            || Self::suspended(env, token_pair)
+            || (!paid && Self::premium(env, token_pair))
+        {
+            return None;
+        }
+
+        env.storage()
+            .persistent()
+            .get(token_pair)
+            .or_else(|| Self::reflector_price(env, token_pair))
+            .or_else(|| Self::lp_price(env, token_pair, paid))
+    }
+
+    /// Direct price of a pair or the inverse of its reverse pair
+    fn resolve_price(env: &Env, token_pair: &TokenPair, paid: bool) -> Option<PriceData> {
+        if let Some(price_data) = Self::direct_price(env, token_pair, paid) {
+            return Some(price_data);
+        }
+
+        let reverse = Self::direct_price(
+            env,
+            &TokenPair {
+                base: token_pair.quote.clone(),
+                quote: token_pair.base.clone(),
+            },
+            paid,
+        )?;
+        if reverse.price <= 0 {
+            return None;
+        }
+
+        // 1 / price at the same precision
+        let one = pow10(reverse.decimals)?;
+        let price = mul_div(env, one, one, reverse.price)?;
+
+        // The relative width of the interval carries over to the inverse
+        Some(PriceData {
+            price,
+            decimals: reverse.decimals,
+            timestamp: reverse.timestamp,
+            source: String::from_str(env, "inverse"),
+            confidence: mul_div(env, price, reverse.confidence, reverse.price)?,
+        })
+    }
+
+    /// Compose `base`/`via` and `quote`/`via` into `base`/`quote`, keeping
+    /// the decimals of the `base`/`via` leg
+    fn derive(
+        env: &Env,
+        base: String,
+        quote: String,
+        via: String,
+        paid: bool,
+    ) -> Option<PriceData> {
+        let base_leg = Self::resolve_price(
+            env,
+            &TokenPair {
+                base,
+                quote: via.clone(),
+            },
+            paid,
+        )?;
+        let quote_leg = Self::resolve_price(
+            env,
+            &TokenPair {
+                base: quote,
+                quote: via,
+            },
+            paid,
+        )?;
+        if quote_leg.price <= 0 {
+            return None;
+        }
+
+        let price = mul_div(
+            env,
+            base_leg.price,
+            pow10(quote_leg.decimals)?,
+            quote_leg.price,
+        )?;
+
+        // Relative widths of the legs add up
+        let confidence = Self::relative_width(env, price, &base_leg)?
+            .checked_add(Self::relative_width(env, price, &quote_leg)?)?;
+
+        Some(PriceData {
+            price,
+            decimals: base_leg.decimals,
+            timestamp: base_leg.timestamp.min(quote_leg.timestamp),
+            source: String::from_str(env, "derived"),
+            confidence,
+        })
+    }
+
+    /// `leg`'s confidence as a share of `price`
+    fn relative_width(env: &Env, price: i128, leg: &PriceData) -> Option<i128> {
+        if leg.price <= 0 {
+            return None;
+        }
+        mul_div(env, price, leg.confidence, leg.price)
+    }
+
+    fn exceeds_deviation(env: &Env, token_pair: &TokenPair, round_data: &RoundData) -> bool {
+        let guard = match Self::get_deviation_guard(env.clone()) {
+            Some(guard) if guard.max_deviation_bps > 0 => guard,
+            _ => return false,
+        };
+        let previous: PriceData = match env.storage().persistent().get(token_pair) {
+            Some(previous) => previous,
+            None => return false,
+        };
+
+        let previous_price =
+            Self::rescale(env, previous.price, previous.decimals, round_data.decimals);
+        if previous_price == 0 {
+            return false;
+        }
+        let deviation = bps_of(
+            env,
+            (round_data.price - previous_price).abs(),
+            previous_price.abs(),
+        )
+        .unwrap_or(i128::MAX);
+        deviation > guard.max_deviation_bps as i128
+    }
+
+    /// Append a price to the pair's history, dropping every entry beyond
+    /// the history size, including those left over from a larger size
+    fn record_history(env: &Env, token_pair: &TokenPair, price_data: &PriceData) {
+        let len_key = (HIST_LEN, token_pair.clone());
+        let total: u64 = env.storage().persistent().get(&len_key).unwrap_or(0);
+
+        env.storage()
+            .persistent()
+            .set(&(HISTORY, token_pair.clone(), total), price_data);
+        env.storage().persistent().set(&len_key, &(total + 1));
+        env.storage().persistent().extend_ttl(
+            &(HISTORY, token_pair.clone(), total),
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        env.storage().persistent().extend_ttl(
+            &len_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        // Histories written before the oldest entry was tracked were kept
+        // at the current size
+        let size = Self::get_history_size(env.clone()) as u64;
+        let from_key = (HIST_FROM, token_pair.clone());
+        let from: u64 = env
+            .storage()
+            .persistent()
+            .get(&from_key)
+            .unwrap_or(total.saturating_sub(size));
+        let keep_from = (total + 1).saturating_sub(size);
+        for seq in from..keep_from {
+            env.storage()
+                .persistent()
+                .remove(&(HISTORY, token_pair.clone(), seq));
+        }
+        env.storage()
+            .persistent()
+            .set(&from_key, &from.max(keep_from));
+        env.storage().persistent().extend_ttl(
+            &from_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Sequence numbers `[oldest, total)` of the retained history
+    fn history_range(env: &Env, token_pair: &TokenPair) -> (u64, u64) {
+        let total: u64 = env
+            .storage()
+            .persistent()
+            .get(&(HIST_LEN, token_pair.clone()))
+            .unwrap_or(0);
+        let from: u64 = env
+            .storage()
+            .persistent()
+            .get(&(HIST_FROM, token_pair.clone()))
+            .unwrap_or(0);
+        let size = Self::get_history_size(env.clone()) as u64;
+        (total.saturating_sub(size).max(from), total)
+    }
+
+    fn history_entry(env: &Env, token_pair: &TokenPair, seq: u64) -> Option<PriceData> {
+        env.storage()
+            .persistent()
+            .get(&(HISTORY, token_pair.clone(), seq))
+    }
+
+    fn latest_round_id(env: &Env, token_pair: &TokenPair) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&(LATEST, token_pair.clone()))
+            .unwrap_or(0)
+    }
+}
+
+stellhydra_access::ownable!(PriceOracle);
+stellhydra_access::pausable!(PriceOracle);
+stellhydra_access::roles!(PriceOracle);
+
+impl stellhydra_access::Upgradeable for PriceOracle {}
+
+stellhydra_access::upgradeable!(PriceOracle);
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger},
+        token, vec, Address, Env, IntoVal, String, Val,
+    };
+
+    #[test]
+    fn test_initialize_and_add_oracle() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        assert!(client.is_oracle_authorized(&oracle));
+    }
+
+    #[test]
+    fn test_admin_transfer_needs_acceptance() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let multisig = Address::generate(&env);
+        client.initialize(&admin);
+
+        assert!(client.try_accept_admin().is_err());
+
+        client.propose_admin(&multisig);
+        assert_eq!(client.get_admin(), admin);
+        assert_eq!(client.get_pending_admin(), Some(multisig.clone()));
+
+        client.accept_admin();
+        assert_eq!(env.auths()[0].0, multisig);
+        assert_eq!(client.get_admin(), multisig);
+        assert_eq!(client.get_pending_admin(), None);
+    }
+
+    #[test]
+    fn test_update_and_get_price() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let price = 1000000i128; // $0.10 with 8 decimals
+        let decimals = 8u32;
+        let source = String::from_str(&env, "StellarX");
+
+        client.update_price(&oracle, &base, &quote, &price, &decimals, &source);
+
+        let price_data = client.get_price(&base, &quote).unwrap();
+        assert_eq!(price_data.price, price);
+        assert_eq!(price_data.decimals, decimals);
+        assert_eq!(price_data.source, source);
+    }
+
+    #[test]
+    fn test_paused_oracle_rejects_submissions_but_serves_reads() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        let pauser = Address::generate(&env);
+
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+        client.grant_role(&stellhydra_access::PAUSER, &pauser);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &base, &quote, &1000000, &8, &source);
+
+        client.pause(&pauser);
+        assert!(client.is_paused());
+        assert!(client
+            .try_update_price(&oracle, &base, &quote, &1100000, &8, &source)
+            .is_err());
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 1000000);
+
+        client.unpause();
+        client.update_price(&oracle, &base, &quote, &1100000, &8, &source);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 1100000);
+    }
+
+    #[test]
+    fn test_round_finalizes_at_quorum_with_median() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        client.initialize(&admin);
+        client.set_quorum(&3);
+
+        let oracles = [
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        ];
+        for oracle in oracles.iter() {
+            client.add_oracle(oracle);
+        }
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.submit_round(&oracles[0], &base, &quote, &1, &1_000, &8, &source);
+        client.submit_round(&oracles[1], &base, &quote, &1, &1_200, &8, &source);
+        assert_eq!(client.get_price(&base, &quote), None);
+        assert_eq!(
+            client
+                .get_pending_round(&base, &quote)
+                .unwrap()
+                .answers
+                .len(),
+            2
+        );
+
+        client.submit_round(&oracles[2], &base, &quote, &1, &1_100, &8, &source);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 1_100);
+        assert_eq!(client.get_latest_round(&base, &quote), 1);
+
+        let round = client.get_round_data(&base, &quote, &1).unwrap();
+        assert_eq!(round.price, 1_100);
+        assert_eq!(round.submissions, 3);
+        assert_eq!(round.answered_in_round, 1);
+        assert_eq!(client.latest_round(&base, &quote), Some(round));
+        assert_eq!(client.get_pending_round(&base, &quote), None);
+
+        // Finalized rounds are closed; the next one opens
+        assert!(client
+            .try_submit_round(&oracles[0], &base, &quote, &1, &900, &8, &source)
+            .is_err());
+        client.update_price(&oracles[0], &base, &quote, &900, &8, &source);
+        assert_eq!(client.get_pending_round(&base, &quote).unwrap().round_id, 2);
+        assert_eq!(client.latest_round(&base, &quote).unwrap().round_id, 1);
+        assert_eq!(client.latest_round(&quote, &base), None);
+    }
+
+    #[test]
+    fn test_price_history_keeps_last_entries() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+        client.set_history_size(&3);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        for i in 1..=5 {
+            env.ledger().with_mut(|li| li.timestamp = i * 100);
+            client.update_price(&oracle, &base, &quote, &(i as i128 * 10), &8, &source);
+        }
+
+        let history = client.get_price_history(&base, &quote, &10);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(0).unwrap().price, 50);
+        assert_eq!(history.get(2).unwrap().price, 30);
+        assert_eq!(client.get_price_history(&base, &quote, &1).len(), 1);
+
+        assert_eq!(client.get_price_at(&base, &quote, &450).unwrap().price, 40);
+        assert_eq!(client.get_price_at(&base, &quote, &300).unwrap().price, 30);
+        assert_eq!(
+            client.get_price_at(&base, &quote, &1_000).unwrap().price,
+            50
+        );
+        // Older than the retained history
+        assert_eq!(client.get_price_at(&base, &quote, &250), None);
+
+        // Growing the history does not resurrect pruned entries
+        client.set_history_size(&10);
+        assert_eq!(client.get_price_history(&base, &quote, &10).len(), 3);
+        assert_eq!(client.get_price_at(&base, &quote, &250), None);
+
+        // Shrinking it hides the tail at once and drops it on the next write
+        client.set_history_size(&1);
+        assert_eq!(client.get_price_history(&base, &quote, &10).len(), 1);
+        env.ledger().with_mut(|li| li.timestamp = 600);
+        client.update_price(&oracle, &base, &quote, &60, &8, &source);
+        let token_pair = TokenPair {
+            base: base.clone(),
+            quote: quote.clone(),
+        };
+        env.as_contract(&contract_id, || {
+            for seq in 0..5u64 {
+                assert!(!env
+                    .storage()
+                    .persistent()
+                    .has(&(HISTORY, token_pair.clone(), seq)));
+            }
+        });
+
+        client.set_history_size(&10);
+        let history = client.get_price_history(&base, &quote, &10);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.get(0).unwrap().price, 60);
+        assert_eq!(client.get_price_at(&base, &quote, &450), None);
+    }
+
+    #[test]
+    fn test_twap_weights_prices_by_duration() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.update_price(&oracle, &base, &quote, &100, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp = 1_100);
+        client.update_price(&oracle, &base, &quote, &200, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp = 1_400);
+
+        // 1_000..1_100 at 100, 1_100..1_400 at 200
+        assert_eq!(
+            client.get_twap(&base, &quote, &400),
+            Some((100 * 100 + 200 * 300) / 400)
+        );
+        assert_eq!(client.get_twap(&base, &quote, &200), Some(200));
+
+        // Window reaches past the first recorded price
+        assert_eq!(client.get_twap(&base, &quote, &500), None);
+    }
+
+    #[test]
+    fn test_deviation_guard_holds_large_moves() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        let second = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+        client.add_oracle(&second);
+        client.set_deviation_guard(&1_000, &60);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.update_price(&oracle, &base, &quote, &1_000, &8, &source);
+        // Within 10%
+        client.update_price(&oracle, &base, &quote, &1_090, &8, &source);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 1_090);
+
+        // A 10x move is held
+        client.update_price(&oracle, &base, &quote, &10_900, &8, &source);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 1_090);
+        assert_eq!(
+            client.get_held_price(&base, &quote).unwrap().round.price,
+            10_900
+        );
+        assert!(client.try_confirm_price(&oracle, &base, &quote).is_err());
+
+        client.confirm_price(&second, &base, &quote);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 10_900);
+        assert_eq!(client.get_held_price(&base, &quote), None);
+        assert_eq!(client.get_latest_round(&base, &quote), 3);
+
+        // Held prices expire unconfirmed
+        client.update_price(&oracle, &base, &quote, &1_000, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp += 61);
+        assert!(client.try_confirm_price(&second, &base, &quote).is_err());
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 10_900);
+    }
+
+    #[test]
+    fn test_confirm_price_respects_write_guards() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        let second = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+        client.add_oracle(&second);
+        client.set_deviation_guard(&1_000, &60);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.update_price(&oracle, &base, &quote, &1_000, &8, &source);
+        client.update_price(&oracle, &base, &quote, &10_000, &8, &source);
+        assert!(client.get_held_price(&base, &quote).is_some());
+
+        client.grant_role(&stellhydra_access::PAUSER, &admin);
+        client.pause(&admin);
+        assert!(client.try_confirm_price(&second, &base, &quote).is_err());
+        client.unpause();
+
+        client.freeze_pair(&base, &quote);
+        assert!(client.try_confirm_price(&second, &base, &quote).is_err());
+        client.unfreeze_pair(&base, &quote);
+
+        let successor = String::from_str(&env, "USDC");
+        client.alias_pair(&base, &quote, &base, &successor);
+        assert!(client.try_confirm_price(&second, &base, &quote).is_err());
+
+        // Nothing got through
+        assert_eq!(client.get_latest_round(&base, &quote), 1);
+        assert!(client.get_held_price(&base, &quote).is_some());
+    }
+
+    #[test]
+    fn test_pair_config_drives_staleness() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        assert!(client.is_stale(&base, &quote));
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.update_price(&oracle, &base, &quote, &1_000, &8, &source);
+        client.set_pair_config(&base, &quote, &600, &300);
+        assert!(!client.is_stale(&base, &quote));
+
+        env.ledger().with_mut(|li| li.timestamp = 1_601);
+        let status = client.get_price_status(&base, &quote).unwrap();
+        assert_eq!(status.age, 601);
+        assert!(status.is_stale);
+        assert!(client.is_stale(&base, &quote));
+
+        assert!(client
+            .try_set_pair_config(&base, &quote, &60, &300)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cross_pairs_are_derived_through_pivot() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let eth = String::from_str(&env, "ETH");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        // XLM/USD 0.10 with 8 decimals, ETH/USD 2500 with 6 decimals
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp = 1_100);
+        client.update_price(&oracle, &eth, &usd, &2_500_000_000, &6, &source);
+
+        let derived = client.get_derived_price(&xlm, &eth, &usd).unwrap();
+        // 0.10 / 2500 = 0.00004
+        assert_eq!(derived.price, 4_000);
+        assert_eq!(derived.decimals, 8);
+        assert_eq!(derived.timestamp, 1_000);
+
+        assert_eq!(client.get_price(&xlm, &eth), None);
+        client.set_pivot(&Some(usd.clone()));
+        assert_eq!(client.get_price(&xlm, &eth).unwrap().price, 4_000);
+        // Pairs against the pivot itself resolve as inverses
+        let inverse = client.get_price(&usd, &eth).unwrap();
+        assert_eq!(inverse.source, String::from_str(&env, "inverse"));
+    }
+
+    #[test]
+    fn test_inverse_pairs_are_resolved() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        // XLM/USD 0.10 with 8 decimals
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+
+        let inverse = client.get_price(&usd, &xlm).unwrap();
+        assert_eq!(inverse.price, 1_000_000_000);
+        assert_eq!(inverse.decimals, 8);
+        assert_eq!(inverse.source, String::from_str(&env, "inverse"));
+
+        // A direct feed wins over the inverse
+        client.update_price(&oracle, &usd, &xlm, &999_000_000, &8, &source);
+        assert_eq!(client.get_price(&usd, &xlm).unwrap().source, source);
+    }
+
+    #[test]
+    fn test_address_keyed_pairs_use_registry() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let usdc = Address::generate(&env);
+        let bridged_usdc = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.register_asset(&String::from_str(&env, "USDC"), &usdc);
+        client.register_asset(&String::from_str(&env, "XLM"), &xlm);
+
+        // The symbol is taken; the bridged token needs its own
+        assert!(client
+            .try_register_asset(&String::from_str(&env, "USDC"), &bridged_usdc)
+            .is_err());
+        client.register_asset(&String::from_str(&env, "USDC.e"), &bridged_usdc);
+
+        let source = String::from_str(&env, "StellarX");
+        client.update_price_by_address(&oracle, &xlm, &usdc, &10_000_000, &8, &source);
+
+        assert_eq!(
+            client.get_price_by_address(&xlm, &usdc).unwrap().price,
+            10_000_000
+        );
+        assert_eq!(client.get_price_by_address(&xlm, &bridged_usdc), None);
+
+        // Symbol reads see the same feed
+        let price = client
+            .get_price(
+                &String::from_str(&env, "XLM"),
+                &String::from_str(&env, "USDC"),
+            )
+            .unwrap();
+        assert_eq!(price.price, 10_000_000);
+
+        client.unregister_asset(&String::from_str(&env, "USDC"));
+        assert_eq!(client.get_asset_symbol(&usdc), None);
+        assert_eq!(client.get_price_by_address(&xlm, &usdc), None);
+    }
+
+    #[test]
+    fn test_batch_update_applies_every_pair() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        let update = |base: &str, price: i128| PriceUpdate {
+            base: String::from_str(&env, base),
+            quote: usd.clone(),
+            price,
+            confidence: 0,
+            decimals: 8,
+            source: source.clone(),
+        };
+
+        let updates = vec![
+            &env,
+            update("XLM", 10_000_000),
+            update("BTC", 6_500_000_000_000),
+        ];
+        client.update_prices(&oracle, &updates);
+
+        let xlm = client
+            .get_price(&String::from_str(&env, "XLM"), &usd)
+            .unwrap();
+        let btc = client
+            .get_price(&String::from_str(&env, "BTC"), &usd)
+            .unwrap();
+        assert_eq!(xlm.price, 10_000_000);
+        assert_eq!(btc.price, 6_500_000_000_000);
+
+        let update_topics: Vec<Val> =
+            (symbol_short!("PRICE"), symbol_short!("UPDATE")).into_val(&env);
+        let price_events = env
+            .events()
+            .all()
+            .iter()
+            .filter(|(contract, topics, _)| {
+                *contract == contract_id && topics.slice(0..2) == update_topics
+            })
+            .count();
+        assert_eq!(price_events, 2);
+
+        // Unauthorized oracles cannot batch either
+        let stranger = Address::generate(&env);
+        assert!(client.try_update_prices(&stranger, &updates).is_err());
+    }
+
+    #[test]
+    fn test_confidence_is_widest_submitted_and_filters_reads() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+        client.set_quorum(&2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.update_price_with_confidence(
+            &oracle1,
+            &xlm,
+            &usd,
+            &10_000_000,
+            &50_000,
+            &8,
+            &source,
+        );
+        client.update_price_with_confidence(
+            &oracle2,
+            &xlm,
+            &usd,
+            &10_200_000,
+            &120_000,
+            &8,
+            &source,
+        );
+
+        let price_data = client.get_price(&xlm, &usd).unwrap();
+        assert_eq!(price_data.confidence, 120_000);
+        assert_eq!(
+            client.get_round_data(&xlm, &usd, &1).unwrap().confidence,
+            120_000
+        );
+
+        assert_eq!(client.get_price_with_confidence(&xlm, &usd, &100_000), None);
+        assert_eq!(
+            client.get_price_with_confidence(&xlm, &usd, &150_000),
+            Some(price_data)
+        );
+
+        // The inverse keeps the same relative width
+        let inverse = client.get_price(&usd, &xlm).unwrap();
+        assert_eq!(inverse.price, 990_099_009);
+        assert_eq!(inverse.confidence, 990_099_009 * 120_000 / 10_100_000);
+
+        assert!(client
+            .try_update_price_with_confidence(&oracle1, &xlm, &usd, &10_000_000, &-1, &8, &source)
+            .is_err());
+    }
+
+    #[test]
+    fn test_oracle_bonds_are_slashed_and_withdrawn() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        let victim = Address::generate(&env);
+        client.initialize(&admin);
+
+        let bond_token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let balance = token::Client::new(&env, &bond_token);
+        token::StellarAssetClient::new(&env, &bond_token).mint(&oracle, &1_000);
+        client.set_bond_config(&BondConfig {
+            token: bond_token.clone(),
+            min_bond: 500,
+            cooldown: 3_600,
+        });
+
+        client.add_oracle(&oracle);
+        assert_eq!(balance.balance(&oracle), 500);
+        assert_eq!(client.get_oracle_bond(&oracle).unwrap().amount, 500);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+
+        // A slashed oracle cannot submit until it tops its bond back up
+        client.slash_oracle(&oracle, &200, &victim);
+        assert_eq!(balance.balance(&victim), 200);
+        assert!(client
+            .try_update_price(&oracle, &xlm, &usd, &10_100_000, &8, &source)
+            .is_err());
+        assert!(client.try_slash_oracle(&oracle, &400, &victim).is_err());
+
+        client.top_up_bond(&oracle, &200);
+        client.update_price(&oracle, &xlm, &usd, &10_100_000, &8, &source);
+
+        // The bond only unlocks a cooldown after deregistration
+        assert!(client.try_withdraw_bond(&oracle).is_err());
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.remove_oracle(&oracle);
+        assert!(client.try_withdraw_bond(&oracle).is_err());
+
+        env.ledger().with_mut(|li| li.timestamp = 4_600);
+        assert_eq!(client.withdraw_bond(&oracle), 500);
+        assert_eq!(balance.balance(&oracle), 800);
+        assert_eq!(client.get_oracle_bond(&oracle), None);
+    }
+
+    #[test]
+    fn test_feed_assignment_restricts_submissions() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let btc = String::from_str(&env, "BTC");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.assign_pair(&oracle1, &xlm, &usd);
+        client.assign_pair(&oracle2, &xlm, &usd);
+        client.assign_pair(&oracle2, &btc, &usd);
+
+        // Assignments are only enforced once restriction is switched on
+        client.update_price(&oracle1, &btc, &usd, &6_500_000_000_000, &8, &source);
+        client.set_feed_restriction(&true);
+        assert!(client
+            .try_update_price(&oracle1, &btc, &usd, &6_600_000_000_000, &8, &source)
+            .is_err());
+        client.update_price(&oracle2, &btc, &usd, &6_600_000_000_000, &8, &source);
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+
+        assert_eq!(
+            client.get_oracle_feeds(&oracle2),
+            vec![
+                &env,
+                TokenPair {
+                    base: xlm.clone(),
+                    quote: usd.clone()
+                },
+                TokenPair {
+                    base: btc.clone(),
+                    quote: usd.clone()
+                },
+            ]
+        );
+        assert_eq!(
+            client.get_pair_feeders(&xlm, &usd),
+            vec![&env, oracle1.clone(), oracle2.clone()]
+        );
+
+        client.unassign_pair(&oracle1, &xlm, &usd);
+        assert_eq!(
+            client.get_pair_feeders(&xlm, &usd),
+            vec![&env, oracle2.clone()]
+        );
+        assert!(client.get_oracle_feeds(&oracle1).is_empty());
+
+        // Removing an oracle drops its assignments
+        client.remove_oracle(&oracle2);
+        assert!(client.get_pair_feeders(&btc, &usd).is_empty());
+    }
+
+    #[test]
+    fn test_fed_pairs_are_listed_in_registry() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let btc = String::from_str(&env, "BTC");
+        let eth = String::from_str(&env, "ETH");
+        let usd = String::from_str(&env, "USD");
+        let stellarx = String::from_str(&env, "StellarX");
+        let soroswap = String::from_str(&env, "Soroswap");
+
+        env.ledger().with_mut(|li| li.timestamp = 100);
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &stellarx);
+        client.update_price(&oracle, &btc, &usd, &6_500_000_000_000, &8, &stellarx);
+        client.update_price(&oracle, &xlm, &usd, &10_100_000, &8, &soroswap);
+        client.set_pair_config(&xlm, &usd, &600, &60);
+        assert_eq!(client.get_pair_count(), 2);
+
+        let page = client.get_all_pairs(&0, &10);
+        assert_eq!(page.len(), 2);
+        let xlm_info = page.get_unchecked(0);
+        assert_eq!(
+            xlm_info.pair,
+            TokenPair {
+                base: xlm.clone(),
+                quote: usd.clone()
+            }
+        );
+        assert_eq!(xlm_info.decimals, 8);
+        assert_eq!(
+            xlm_info.sources,
+            vec![&env, stellarx.clone(), soroswap.clone()]
+        );
+        assert_eq!(xlm_info.max_staleness, Some(600));
+        assert_eq!(xlm_info.heartbeat, Some(60));
+        assert_eq!(xlm_info.listed_at, 100);
+        assert_eq!(page.get_unchecked(1).pair.base, btc);
+
+        // A Reflector-backed pair is listed with the feed and delisted with it
+        let feed = ReflectorFeed {
+            feed: Address::generate(&env),
+            asset: Asset::Other(symbol_short!("ETH")),
+            decimals: 8,
+            cache_secs: 60,
+        };
+        client.set_reflector_feed(&eth, &usd, &Some(feed));
+        assert_eq!(
+            client.get_all_pairs(&2, &10).get_unchecked(0).pair.base,
+            eth
+        );
+        client.set_reflector_feed(&eth, &usd, &None);
+        assert_eq!(client.get_pair_info(&eth, &usd), None);
+
+        client.delist_pair(&xlm, &usd);
+        assert_eq!(client.get_price(&xlm, &usd), None);
+        assert_eq!(client.get_all_pairs(&0, &10).len(), 1);
+        assert_eq!(client.get_all_pairs(&1, &10).len(), 0);
+    }
+
+    #[test]
+    fn test_override_is_timelocked_and_cancelled_by_oracles() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let usdc = String::from_str(&env, "USDC");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &usdc, &usd, &100_000_000, &8, &source);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.propose_override(&usdc, &usd, &80_000_000);
+        let proposal = client.get_override(&usdc, &usd).unwrap();
+        assert_eq!(proposal.decimals, 8);
+        assert_eq!(proposal.active_at, 4_600);
+
+        assert!(client.try_execute_override(&usdc, &usd).is_err());
+        env.ledger().with_mut(|li| li.timestamp = 4_600);
+        env.mock_auths(&[]);
+        assert!(client.try_execute_override(&usdc, &usd).is_err());
+        env.mock_all_auths();
+        client.execute_override(&usdc, &usd);
+
+        let price_data = client.get_price(&usdc, &usd).unwrap();
+        assert_eq!(price_data.price, 80_000_000);
+        assert_eq!(price_data.source, String::from_str(&env, "override"));
+        assert_eq!(client.get_latest_round(&usdc, &usd), 2);
+        assert_eq!(client.get_override(&usdc, &usd), None);
+
+        // A fresh oracle update cancels a pending override
+        client.propose_override(&usdc, &usd, &70_000_000);
+        client.update_price(&oracle, &usdc, &usd, &95_000_000, &8, &source);
+        assert_eq!(client.get_override(&usdc, &usd), None);
+        env.ledger().with_mut(|li| li.timestamp = 10_000);
+        assert!(client.try_execute_override(&usdc, &usd).is_err());
+        assert_eq!(client.get_price(&usdc, &usd).unwrap().price, 95_000_000);
+    }
+
+    #[test]
+    fn test_signed_prices_are_relayed_once() {
+        use ed25519_dalek::{Signer, SigningKey};
+        use soroban_sdk::{xdr::ToXdr, BytesN};
+
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let pubkey = BytesN::from_array(&env, &signing_key.verifying_key().to_bytes());
+        client.set_oracle_key(&oracle, &pubkey);
+
+        let sign = |payload: &SignedPrice| {
+            let message = (contract_id.clone(), payload.clone()).to_xdr(&env);
+            let mut bytes = [0u8; 1024];
+            let bytes = &mut bytes[..message.len() as usize];
+            message.copy_into_slice(bytes);
+            BytesN::from_array(&env, &signing_key.sign(bytes).to_bytes())
+        };
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        let mut payload = SignedPrice {
+            base: String::from_str(&env, "XLM"),
+            quote: String::from_str(&env, "USD"),
+            price: 10_000_000,
+            confidence: 0,
+            decimals: 8,
+            source: String::from_str(&env, "StellarX"),
+            timestamp: 990,
+            nonce: 1,
+        };
+        let signature = sign(&payload);
+        client.submit_signed_price(&payload, &pubkey, &signature);
+
+        let price_data = client.get_price(&payload.base, &payload.quote).unwrap();
+        assert_eq!(price_data.price, 10_000_000);
+        assert_eq!(client.get_oracle_nonce(&oracle), 1);
+
+        // Replays and tampered payloads are rejected
+        assert!(client
+            .try_submit_signed_price(&payload, &pubkey, &signature)
+            .is_err());
+        payload.nonce = 2;
+        assert!(client
+            .try_submit_signed_price(&payload, &pubkey, &signature)
+            .is_err());
+
+        // So are payloads older than the max age
+        payload.timestamp = 600;
+        let signature = sign(&payload);
+        assert!(client
+            .try_submit_signed_price(&payload, &pubkey, &signature)
+            .is_err());
+
+        payload.timestamp = 1_000;
+        payload.price = 10_100_000;
+        let signature = sign(&payload);
+        client.submit_signed_price(&payload, &pubkey, &signature);
+        assert_eq!(
+            client
+                .get_price(&payload.base, &payload.quote)
+                .unwrap()
+                .price,
+            10_100_000
+        );
+
+        // Keys of removed oracles no longer count
+        client.remove_oracle(&oracle);
+        payload.nonce = 3;
+        let signature = sign(&payload);
+        assert!(client
+            .try_submit_signed_price(&payload, &pubkey, &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_frozen_pairs_serve_no_price() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+
+        client.freeze_pair(&xlm, &usd);
+        assert!(client.is_pair_frozen(&xlm, &usd));
+        assert_eq!(client.get_price(&xlm, &usd), None);
+        assert_eq!(client.get_price(&usd, &xlm), None);
+        let status = client.get_price_status(&xlm, &usd).unwrap();
+        assert!(status.is_frozen);
+        assert_eq!(status.price_data.price, 10_000_000);
+
+        client.unfreeze_pair(&xlm, &usd);
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 10_000_000);
+        assert!(!client.get_price_status(&xlm, &usd).unwrap().is_frozen);
+
+        // Oracles freeze a pair once the quorum agrees
+        client.set_quorum(&2);
+        client.vote_freeze(&oracle1, &xlm, &usd, &true);
+        assert!(!client.is_pair_frozen(&xlm, &usd));
+        client.vote_freeze(&oracle2, &xlm, &usd, &true);
+        assert!(client.is_pair_frozen(&xlm, &usd));
+
+        client.vote_freeze(&oracle1, &xlm, &usd, &false);
+        client.vote_freeze(&oracle2, &xlm, &usd, &false);
+        assert!(!client.is_pair_frozen(&xlm, &usd));
+
+        let stranger = Address::generate(&env);
+        assert!(client
+            .try_vote_freeze(&stranger, &xlm, &usd, &true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_premium_pairs_are_paid_reads() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        let consumer = Address::generate(&env);
+        let distributor = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let fee_token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let balance = token::Client::new(&env, &fee_token);
+        token::StellarAssetClient::new(&env, &fee_token).mint(&consumer, &1_000);
+        client.set_read_fees(&ReadFees {
+            token: fee_token.clone(),
+            subscription_fee: 300,
+            subscription_period: 86_400,
+            read_fee: 10,
+        });
+
+        let btc = String::from_str(&env, "BTC");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &btc, &usd, &6_500_000_000_000, &8, &source);
+        client.set_premium_pair(&btc, &usd, &true);
+
+        // Neither the pair nor its inverse is served for free
+        assert_eq!(client.get_price(&btc, &usd), None);
+        assert_eq!(client.get_price(&usd, &btc), None);
+
+        // Nor are its rounds and history
+        assert_eq!(client.latest_round(&btc, &usd), None);
+        assert_eq!(client.get_round_data(&btc, &usd, &1), None);
+        assert!(client.get_price_history(&btc, &usd, &5).is_empty());
+        assert_eq!(client.get_price_at(&btc, &usd, &0), None);
+        assert_eq!(client.get_twap(&btc, &usd, &60), None);
+
+        let price_data = client.get_premium_price(&consumer, &btc, &usd).unwrap();
+        assert_eq!(price_data.price, 6_500_000_000_000);
+        assert_eq!(balance.balance(&consumer), 990);
+
+        // Subscribers read without paying per read
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        assert_eq!(client.subscribe(&consumer, &2), 173_800);
+        client.get_premium_price(&consumer, &usd, &btc).unwrap();
+        assert_eq!(balance.balance(&consumer), 390);
+
+        env.ledger().with_mut(|li| li.timestamp = 173_800);
+        client.get_premium_price(&consumer, &btc, &usd).unwrap();
+        assert_eq!(balance.balance(&consumer), 380);
+
+        assert_eq!(client.withdraw_fees(&distributor), 620);
+        assert_eq!(balance.balance(&distributor), 620);
+        assert_eq!(client.get_accrued_fees(), 0);
+    }
+
+    #[test]
+    fn test_liveness_flags_missed_heartbeats() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let btc = String::from_str(&env, "BTC");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        let xlm_usd = TokenPair {
+            base: xlm.clone(),
+            quote: usd.clone(),
+        };
+        let btc_usd = TokenPair {
+            base: btc.clone(),
+            quote: usd.clone(),
+        };
+
+        client.set_pair_config(&xlm, &usd, &600, &60);
+        client.assign_pair(&oracle2, &btc, &usd);
+
+        env.ledger().with_mut(|li| li.timestamp = 100);
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp = 130);
+        client.update_price(&oracle1, &btc, &usd, &6_500_000_000_000, &8, &source);
+
+        let liveness = client.get_oracle_liveness(&oracle1);
+        assert_eq!(liveness.last_submission, 130);
+        assert_eq!(liveness.pairs.len(), 2);
+        assert!(liveness.pairs.contains(&PairLiveness {
+            pair: xlm_usd.clone(),
+            last_submission: 100,
+        }));
+
+        // Only pairs with a heartbeat are checked
+        env.ledger().with_mut(|li| li.timestamp = 161);
+        assert_eq!(
+            client.get_missed_heartbeats(),
+            vec![
+                &env,
+                MissedHeartbeat {
+                    oracle: oracle1.clone(),
+                    pair: xlm_usd.clone(),
+                    last_submission: 100,
+                },
+            ]
+        );
+
+        // Assigned pairs count even before the first submission
+        client.set_pair_config(&btc, &usd, &600, &20);
+        let missed = client.get_missed_heartbeats();
+        assert_eq!(missed.len(), 3);
+        assert!(missed.contains(&MissedHeartbeat {
+            oracle: oracle2.clone(),
+            pair: btc_usd,
+            last_submission: 0,
+        }));
+
+        client.update_price(&oracle1, &xlm, &usd, &10_100_000, &8, &source);
+        assert_eq!(client.get_missed_heartbeats().len(), 2);
+    }
+
+    #[test]
+    fn test_prices_and_amounts_are_rescaled() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        // 1 XLM = 0.12345678 USD
+        client.update_price(&oracle, &xlm, &usd, &12_345_678, &8, &source);
+
+        let scaled = client.get_price_scaled(&xlm, &usd, &18).unwrap();
+        assert_eq!(scaled.price, 123_456_780_000_000_000);
+        assert_eq!(scaled.decimals, 18);
+        assert_eq!(
+            client.get_price_scaled(&xlm, &usd, &4).unwrap().price,
+            1_234
+        );
+
+        // 250 XLM (7 decimals) in USD with 6 decimals
+        assert_eq!(
+            client.convert_amount(&xlm, &usd, &2_500_000_000, &7, &6),
+            Some(30_864_195)
+        );
+        assert!(client
+            .try_convert_amount(&xlm, &usd, &2_500_000_000, &7, &60)
+            .is_err());
+
+        let btc = String::from_str(&env, "BTC");
+        assert_eq!(client.convert_amount(&btc, &usd, &1, &7, &6), None);
+    }
+
+    #[test]
+    fn test_volatility_is_deviation_of_returns() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        // Returns of +1%, -1%, +1%, -1%
+        for price in [10_000_000, 10_100_000, 9_999_000, 10_098_990, 9_998_000] {
+            client.update_price(&oracle, &xlm, &usd, &price, &8, &source);
+        }
+
+        assert_eq!(client.get_volatility(&xlm, &usd, &5), None);
+        assert_eq!(client.get_volatility(&xlm, &usd, &4), Some(100));
+        assert_eq!(client.get_volatility(&xlm, &usd, &2), Some(100));
+
+        // A steady price has no volatility
+        let btc = String::from_str(&env, "BTC");
+        for _ in 0..3 {
+            client.update_price(&oracle, &btc, &usd, &6_500_000_000_000, &8, &source);
+        }
+        assert_eq!(client.get_volatility(&btc, &usd, &2), Some(0));
+    }
+
+    #[contract]
+    struct MockPool;
+
+    #[contractimpl]
+    impl MockPool {
+        pub fn set_reserves(env: Env, reserve_a: i128, reserve_b: i128, total_shares: i128) {
+            let info = PoolInfo {
+                token_a: env.current_contract_address(),
+                token_b: env.current_contract_address(),
+                reserve_a,
+                reserve_b,
+                total_shares,
+                fee_rate: 30,
+            };
+            env.storage().instance().set(&symbol_short!("POOL"), &info);
+        }
+
+        pub fn get_pool_info(env: Env) -> PoolInfo {
+            env.storage()
+                .instance()
+                .get(&symbol_short!("POOL"))
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_lp_shares_are_priced_at_fair_value() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usdc = String::from_str(&env, "USDC");
+        let usd = String::from_str(&env, "USD");
+        let lp = String::from_str(&env, "XLM-USDC-LP");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &xlm, &usd, &12_000_000, &8, &source);
+        client.update_price(&oracle, &usdc, &usd, &100_000_000, &8, &source);
+
+        // 1000 XLM and 120 USDC: 240 USD over sqrt(1000 * 120) shares
+        let pool_id = env.register_contract(None, MockPool);
+        let pool = MockPoolClient::new(&env, &pool_id);
+        pool.set_reserves(&10_000_000_000, &1_200_000_000, &3_464_101_615);
+        client.set_lp_feed(
+            &lp,
+            &usd,
+            &Some(LpFeed {
+                pool: pool_id.clone(),
+                asset_a: xlm.clone(),
+                asset_b: usdc.clone(),
+            }),
+        );
+
+        let price_data = client.get_price(&lp, &usd).unwrap();
+        assert_eq!(price_data.price, 69_282_032);
+        assert_eq!(price_data.decimals, 8);
+        assert_eq!(price_data.source, String::from_str(&env, "lp-fair"));
+
+        // Skewing the reserves at constant product doesn't move the price
+        pool.set_reserves(&20_000_000_000, &600_000_000, &3_464_101_615);
+        assert_eq!(client.get_price(&lp, &usd).unwrap().price, 69_282_032);
+
+        client.set_lp_feed(&lp, &usd, &None);
+        assert_eq!(client.get_price(&lp, &usd), None);
+    }
+
+    #[test]
+    fn test_detailed_price_reports_age_and_submitters() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+        client.set_quorum(&2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+        client.update_price(&oracle2, &xlm, &usd, &10_200_000, &8, &source);
+        client.set_pair_config(&xlm, &usd, &300, &60);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_200);
+        let details = client.get_price_detailed(&xlm, &usd).unwrap();
+        assert_eq!(details.price, 10_100_000);
+        assert_eq!(details.timestamp, 1_000);
+        assert_eq!(details.age, 200);
+        assert_eq!(details.submissions, 2);
+        assert!(!details.is_stale);
+        assert!(!details.is_frozen);
+
+        // Computed prices have no submitters of their own
+        let inverse = client.get_price_detailed(&usd, &xlm).unwrap();
+        assert_eq!(inverse.source, String::from_str(&env, "inverse"));
+        assert_eq!(inverse.submissions, 0);
+
+        client.freeze_pair(&xlm, &usd);
+        env.ledger().with_mut(|li| li.timestamp = 1_400);
+        let details = client.get_price_detailed(&xlm, &usd).unwrap();
+        assert!(details.is_stale);
+        assert!(details.is_frozen);
+
+        // A price stamped ahead of the ledger clock has age 0, not an underflow
+        env.ledger().with_mut(|li| li.timestamp = 900);
+        assert_eq!(client.get_price_detailed(&xlm, &usd).unwrap().age, 0);
+    }
+
+    #[test]
+    fn test_pair_entries_are_kept_alive() {
+        use soroban_sdk::testutils::storage::Persistent;
+
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        let token_pair = TokenPair {
+            base: xlm.clone(),
+            quote: usd.clone(),
+        };
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+        client.update_price(&oracle, &xlm, &usd, &10_100_000, &8, &source);
+
+        let ttl =
+            |key: &Val| env.as_contract(&contract_id, || env.storage().persistent().get_ttl(key));
+        let price_key: Val = token_pair.clone().into_val(&env);
+        let first_entry: Val = (HISTORY, token_pair.clone(), 0u64).into_val(&env);
+        assert_eq!(ttl(&price_key), PERSISTENT_BUMP_AMOUNT);
+        assert_eq!(ttl(&first_entry), PERSISTENT_BUMP_AMOUNT);
+
+        client.bump_pair(&xlm, &usd, &(90 * DAY_IN_LEDGERS));
+        assert_eq!(ttl(&price_key), 90 * DAY_IN_LEDGERS);
+        assert_eq!(ttl(&first_entry), 90 * DAY_IN_LEDGERS);
+
+        // Bumps are capped at the network's maximum TTL
+        client.bump_pair(&xlm, &usd, &u32::MAX);
+        assert_eq!(
+            ttl(&price_key),
+            env.as_contract(&contract_id, || env.storage().max_ttl())
+        );
+
+        let btc = String::from_str(&env, "BTC");
+        assert!(client.try_bump_pair(&btc, &usd, &DAY_IN_LEDGERS).is_err());
+    }
+
+    #[test]
+    fn test_weighted_sources_dominate_rounds() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let exchange = Address::generate(&env);
+        let feeder1 = Address::generate(&env);
+        let feeder2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&exchange);
+        client.add_oracle(&feeder1);
+        client.add_oracle(&feeder2);
+        client.set_quorum(&3);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        let mut weights = Map::new(&env);
+        weights.set(exchange.clone(), 3);
+        client.set_pair_weights(&xlm, &usd, &weights);
+
+        // The exchange outweighs both feeders together
+        client.update_price(&exchange, &xlm, &usd, &10_000_000, &8, &source);
+        client.update_price(&feeder1, &xlm, &usd, &10_400_000, &8, &source);
+        client.update_price(&feeder2, &xlm, &usd, &10_500_000, &8, &source);
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 10_000_000);
+
+        // (3 * 10.0 + 10.4 + 10.6) / 5
+        client.set_aggregation(&xlm, &usd, &Aggregation::Mean);
+        client.update_price(&exchange, &xlm, &usd, &10_000_000, &8, &source);
+        client.update_price(&feeder1, &xlm, &usd, &10_400_000, &8, &source);
+        client.update_price(&feeder2, &xlm, &usd, &10_600_000, &8, &source);
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 10_200_000);
+
+        weights.set(feeder1.clone(), 0);
+        assert!(client.try_set_pair_weights(&xlm, &usd, &weights).is_err());
+    }
+
+    #[test]
+    fn test_optimistic_prices_wait_out_disputes() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        let oracle3 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+        client.add_oracle(&oracle3);
+        client.set_quorum(&3);
+        client.set_dispute_window(&600);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        // A single answer is proposed and goes live after the window
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+        assert!(client.get_price(&xlm, &usd).is_none());
+        assert_eq!(
+            client.get_proposed_price(&xlm, &usd).unwrap().active_at,
+            env.ledger().timestamp() + 600
+        );
+        assert!(client
+            .try_update_price(&oracle2, &xlm, &usd, &10_100_000, &8, &source)
+            .is_err());
+        assert!(client.try_activate_price(&xlm, &usd).is_err());
+
+        env.ledger().with_mut(|li| li.timestamp += 600);
+        assert!(client.try_challenge_price(&oracle2, &xlm, &usd).is_err());
+        client.activate_price(&xlm, &usd);
+        let price_data = client.get_price(&xlm, &usd).unwrap();
+        assert_eq!(price_data.price, 10_000_000);
+        assert!(client.get_proposed_price(&xlm, &usd).is_none());
+
+        // A challenged proposal needs quorum
+        client.update_price(&oracle1, &xlm, &usd, &20_000_000, &8, &source);
+        assert!(client.try_challenge_price(&oracle1, &xlm, &usd).is_err());
+        client.challenge_price(&oracle2, &xlm, &usd);
+        assert!(client.is_pair_disputed(&xlm, &usd));
+        assert!(client.get_proposed_price(&xlm, &usd).is_none());
+
+        client.update_price(&oracle2, &xlm, &usd, &10_100_000, &8, &source);
+        assert_eq!(client.get_latest_round(&xlm, &usd), 1);
+        client.update_price(&oracle3, &xlm, &usd, &10_200_000, &8, &source);
+        assert_eq!(client.get_latest_round(&xlm, &usd), 2);
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 10_200_000);
+        assert!(!client.is_pair_disputed(&xlm, &usd));
+    }
+
+    #[test]
+    fn test_activation_is_refused_like_a_submission() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+        client.set_quorum(&2);
+        client.set_dispute_window(&600);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp += 600);
+
+        client.pause(&admin);
+        assert!(client.try_activate_price(&xlm, &usd).is_err());
+        client.unpause();
+
+        client.freeze_pair(&xlm, &usd);
+        assert!(client.try_activate_price(&xlm, &usd).is_err());
+        client.unfreeze_pair(&xlm, &usd);
+
+        let new = String::from_str(&env, "XLM2");
+        client.alias_pair(&xlm, &usd, &new, &usd);
+        assert!(client.try_activate_price(&xlm, &usd).is_err());
+        assert_eq!(client.get_latest_round(&xlm, &usd), 0);
+    }
+
+    #[test]
+    fn test_aliased_pairs_resolve_to_successor() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let old = String::from_str(&env, "yXLM");
+        let new = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &old, &usd, &9_000_000, &8, &source);
+        client.update_price(&oracle, &new, &usd, &10_000_000, &8, &source);
+
+        client.alias_pair(&old, &usd, &new, &usd);
+        assert_eq!(client.get_price(&old, &usd).unwrap().price, 10_000_000);
+        let details = client.get_price_detailed(&old, &usd).unwrap();
+        assert_eq!(details.price, 10_000_000);
+        assert_eq!(details.submissions, 1);
+        assert!(details.is_deprecated);
+        assert!(!client.get_price_detailed(&new, &usd).unwrap().is_deprecated);
+        assert_eq!(
+            client.get_pair_alias(&old, &usd).unwrap().successor,
+            TokenPair {
+                base: new.clone(),
+                quote: usd.clone()
+            }
+        );
+
+        // The inverse of a deprecated pair follows the successor too
+        assert_eq!(client.get_price(&usd, &old).unwrap().price, 1_000_000_000);
+
+        assert!(client
+            .try_update_price(&oracle, &old, &usd, &9_000_000, &8, &source)
+            .is_err());
+        assert!(client.try_alias_pair(&new, &usd, &old, &usd).is_err());
+
+        client.unalias_pair(&old, &usd);
+        assert_eq!(client.get_price(&old, &usd).unwrap().price, 9_000_000);
+    }
+
+    #[test]
+    fn test_circuit_breaker_suspends_extreme_moves() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+        client.set_circuit_breaker(&2_000);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        client.set_pair_config(&xlm, &usd, &600, &60);
+        client.update_price(&oracle1, &xlm, &usd, &10_000_000, &8, &source);
+
+        // A large move after more than a heartbeat is not a flash crash
+        env.ledger().with_mut(|li| li.timestamp += 120);
+        client.update_price(&oracle1, &xlm, &usd, &7_000_000, &8, &source);
+        assert!(!client.is_pair_suspended(&xlm, &usd));
+
+        // Within a heartbeat it suspends the pair
+        env.ledger().with_mut(|li| li.timestamp += 30);
+        client.update_price(&oracle1, &xlm, &usd, &4_000_000, &8, &source);
+        assert!(client.is_pair_suspended(&xlm, &usd));
+        assert!(client.get_price(&xlm, &usd).is_none());
+        let status = client.get_price_status(&xlm, &usd).unwrap();
+        assert!(status.is_frozen);
+        assert_eq!(status.price_data.price, 4_000_000);
+
+        // Oracles resume it at quorum
+        client.set_quorum(&2);
+        client.resume_pair(&oracle1, &xlm, &usd);
+        assert!(client.is_pair_suspended(&xlm, &usd));
+        client.resume_pair(&oracle2, &xlm, &usd);
+        assert!(!client.is_pair_suspended(&xlm, &usd));
+        assert!(client.try_resume_pair(&admin, &xlm, &usd).is_err());
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 4_000_000);
+    }
+
+    #[test]
+    fn test_committee_writes_its_pairs_alone() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle1 = Address::generate(&env);
+        let oracle2 = Address::generate(&env);
+        let committee = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle1);
+        client.add_oracle(&oracle2);
+        client.set_quorum(&2);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "multisig");
+        client.update_price(&oracle1, &xlm, &usd, &9_000_000, &8, &source);
+        client.set_pair_committee(&xlm, &usd, &Some(committee.clone()));
+        assert_eq!(
+            client.get_pair_committee(&xlm, &usd),
+            Some(committee.clone())
+        );
+        assert_eq!(client.get_pending_round(&xlm, &usd), None);
+
+        // Oracles can no longer write the pair
+        assert!(client
+            .try_update_price(&oracle2, &xlm, &usd, &10_000_000, &8, &source)
+            .is_err());
+
+        // The committee's answer finalizes the round on its own
+        client.update_price(&committee, &xlm, &usd, &10_000_000, &8, &source);
+        assert_eq!(env.auths()[0].0, committee);
+        let round = client.latest_round(&xlm, &usd).unwrap();
+        assert_eq!(round.price, 10_000_000);
+        assert_eq!(round.submissions, 1);
+
+        // Other pairs stay with the oracles
+        let eth = String::from_str(&env, "ETH");
+        assert!(client
+            .try_update_price(&committee, &eth, &usd, &1, &8, &source)
+            .is_err());
+
+        client.set_pair_committee(&xlm, &usd, &None);
+        assert!(client
+            .try_update_price(&committee, &xlm, &usd, &10_000_000, &8, &source)
+            .is_err());
+    }
+
+    #[contract]
+    struct MockConsumer;
+
+    #[contractimpl]
+    impl MockConsumer {
+        pub fn set_failing(env: Env, failing: bool) {
+            env.storage()
+                .instance()
+                .set(&symbol_short!("FAILING"), &failing);
+        }
+
+        pub fn on_price_update(env: Env, base: String, quote: String, price_data: PriceData) {
+            if env.storage().instance().has(&symbol_short!("FAILING")) {
+                panic!("consumer failure");
+            }
+            env.storage()
+                .instance()
+                .set(&TokenPair { base, quote }, &price_data);
+        }
+
+        pub fn last(env: Env, base: String, quote: String) -> Option<PriceData> {
+            env.storage().instance().get(&TokenPair { base, quote })
+        }
+    }
+
+    #[test]
+    fn test_updates_are_pushed_to_consumers() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let xlm = String::from_str(&env, "XLM");
+        let usd = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+
+        let failing = env.register_contract(None, MockConsumer);
+        MockConsumerClient::new(&env, &failing).set_failing(&true);
+        let consumer_id = env.register_contract(None, MockConsumer);
+        let consumer = MockConsumerClient::new(&env, &consumer_id);
+        client.register_consumer(&xlm, &usd, &failing);
+        client.register_consumer(&xlm, &usd, &consumer_id);
+        assert_eq!(client.get_consumers(&xlm, &usd).len(), 2);
+
+        // The failing consumer neither blocks the update nor the others
+        client.update_price(&oracle, &xlm, &usd, &10_000_000, &8, &source);
+        assert_eq!(client.get_price(&xlm, &usd).unwrap().price, 10_000_000);
+        assert_eq!(consumer.last(&xlm, &usd).unwrap().price, 10_000_000);
+
+        client.unregister_consumer(&xlm, &usd, &consumer_id);
+        client.update_price(&oracle, &xlm, &usd, &10_100_000, &8, &source);
+        assert_eq!(consumer.last(&xlm, &usd).unwrap().price, 10_000_000);
+
+        for _ in 0..4 {
+            client.register_consumer(&xlm, &usd, &Address::generate(&env));
+        }
+        assert!(client
+            .try_register_consumer(&xlm, &usd, &Address::generate(&env))
+            .is_err());
+    }
+
+    #[contract]
+    struct MockReflector;
+
+    #[contractimpl]
+    impl MockReflector {
+        pub fn set_price(env: Env, price: i128, timestamp: u64) {
+            let data = Sep40PriceData { price, timestamp };
+            env.storage().instance().set(&symbol_short!("PRICE"), &data);
+        }
+
+        pub fn decimals(_env: Env) -> u32 {
+            14
+        }
+
+        pub fn lastprice(env: Env, _asset: Asset) -> Option<Sep40PriceData> {
+            env.storage().instance().get(&symbol_short!("PRICE"))
+        }
+    }
+
+    #[test]
+    fn test_reflector_feed_backs_unfed_pairs() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+        let reflector_id = env.register_contract(None, MockReflector);
+        let reflector = MockReflectorClient::new(&env, &reflector_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let base = String::from_str(&env, "BTC");
+        let quote = String::from_str(&env, "USD");
+        client.set_reflector_feed(
+            &base,
+            &quote,
+            &Some(ReflectorFeed {
+                feed: reflector_id,
+                asset: Asset::Other(Symbol::new(&env, "BTC")),
+                decimals: 8,
+                cache_secs: 60,
+            }),
+        );
+        assert_eq!(client.get_price(&base, &quote), None);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        reflector.set_price(&(65_000 * 10i128.pow(14)), &990);
+        let price = client.get_price(&base, &quote).unwrap();
+        assert_eq!(price.price, 65_000 * 10i128.pow(8));
+        assert_eq!(price.decimals, 8);
+        assert_eq!(price.timestamp, 990);
+
+        // Cached until the cache period passes
+        reflector.set_price(&(66_000 * 10i128.pow(14)), &1_020);
+        assert_eq!(client.get_price(&base, &quote).unwrap().timestamp, 990);
+        env.ledger().with_mut(|li| li.timestamp = 1_060);
+        assert_eq!(
+            client.get_price(&base, &quote).unwrap().price,
+            66_000 * 10i128.pow(8)
+        );
+
+        // Our own oracles take precedence once they feed the pair
+        let source = String::from_str(&env, "StellarX");
+        client.update_price(&oracle, &base, &quote, &42, &8, &source);
+        assert_eq!(client.get_price(&base, &quote).unwrap().price, 42);
+    }
+
+    #[test]
+    fn test_sep40_reads_map_assets_to_pairs() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        client.initialize(&admin);
+        client.add_oracle(&oracle);
+
+        let usd = Asset::Other(Symbol::new(&env, "USD"));
+        let xlm = Asset::Other(Symbol::new(&env, "XLM"));
+        client.set_sep40_config(&Sep40Config {
+            base: usd.clone(),
+            quote_token: String::from_str(&env, "USD"),
+            decimals: 14,
+            resolution: 300,
+        });
+        client.set_sep40_asset(&xlm, &String::from_str(&env, "XLM"));
+
+        assert_eq!(client.base(), usd);
+        assert_eq!(client.assets().len(), 1);
+        assert_eq!(client.decimals(), 14);
+        assert_eq!(client.resolution(), 300);
+        assert_eq!(client.lastprice(&xlm), None);
+
+        let base = String::from_str(&env, "XLM");
+        let quote = String::from_str(&env, "USD");
+        let source = String::from_str(&env, "StellarX");
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.update_price(&oracle, &base, &quote, &10_000_000, &8, &source);
+        env.ledger().with_mut(|li| li.timestamp = 1_300);
+        client.update_price(&oracle, &base, &quote, &12_000_000, &8, &source);
+
+        // Rescaled from 8 to 14 decimals
+        let last = client.lastprice(&xlm).unwrap();
+        assert_eq!(last.price, 12_000_000 * 1_000_000);
+        assert_eq!(last.timestamp, 1_300);
+        assert_eq!(
+            client.price(&xlm, &1_100).unwrap().price,
+            10_000_000 * 1_000_000
+        );
+        assert_eq!(client.prices(&xlm, &5).unwrap().len(), 2);
+
+        let unknown = Asset::Other(Symbol::new(&env, "BTC"));
+        assert_eq!(client.prices(&unknown, &5), None);
+
+        // History reads hold back the same prices `lastprice` does
+        client.freeze_pair(&base, &quote);
+        assert_eq!(client.lastprice(&xlm), None);
+        assert_eq!(client.price(&xlm, &1_100), None);
+        assert_eq!(client.prices(&xlm, &5), None);
+        client.unfreeze_pair(&base, &quote);
+
+        client.set_premium_pair(&base, &quote, &true);
+        assert_eq!(client.price(&xlm, &1_100), None);
+        assert_eq!(client.prices(&xlm, &5), None);
+        client.set_premium_pair(&base, &quote, &false);
+
+        // A deprecated pair reads its successor's history
+        let successor = String::from_str(&env, "XLM2");
+        client.update_price(&oracle, &successor, &quote, &20_000_000, &8, &source);
+        client.alias_pair(&base, &quote, &successor, &quote);
+        assert_eq!(
+            client.price(&xlm, &1_300).unwrap().price,
+            20_000_000 * 1_000_000
+        );
+        assert_eq!(client.prices(&xlm, &5).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_scenario_publishes_prices() {
+        let env = Env::default();
+        let scenario = testutils::OracleScenario::new(&env).with_price("XLM", "USDC", 1_200_000);
+
+        let price = scenario
+            .oracle
+            .get_price(
+                &String::from_str(&env, "XLM"),
+                &String::from_str(&env, "USDC"),
+            )
+            .unwrap();
+        assert_eq!(price.price, 1_200_000);
+        assert_eq!(price.decimals, testutils::DECIMALS);
+    }
+}
@@ -0,0 +1,120 @@
+//! Oracle liveness.
+//!
+//! Every accepted submission records when the oracle last fed the pair.
+//! An oracle misses a pair's heartbeat when it hasn't fed a pair it is
+//! assigned to, or has fed before, within the pair's configured heartbeat.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, Address, Env, Map, Symbol, Vec};
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const LIVENESS: Symbol = symbol_short!("LIVENESS");
+
+/// When an oracle last fed a pair (0 if never)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairLiveness {
+    pub pair: TokenPair,
+    pub last_submission: u64,
+}
+
+/// Submission record of an oracle
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleLiveness {
+    pub oracle: Address,
+    pub last_submission: u64,
+    pub pairs: Vec<PairLiveness>,
+}
+
+/// Pair an oracle failed to feed within its heartbeat
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissedHeartbeat {
+    pub oracle: Address,
+    pub pair: TokenPair,
+    pub last_submission: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    pub fn get_oracle_liveness(env: Env, oracle: Address) -> OracleLiveness {
+        let submissions = Self::submissions_of(&env, &oracle);
+
+        let mut pairs = Vec::new(&env);
+        let mut last_submission = 0;
+        for (pair, at) in Self::tracked_pairs(&env, &oracle, &submissions).iter() {
+            last_submission = last_submission.max(at);
+            pairs.push_back(PairLiveness {
+                pair,
+                last_submission: at,
+            });
+        }
+
+        OracleLiveness {
+            oracle,
+            last_submission,
+            pairs,
+        }
+    }
+
+    /// Authorized oracles behind on a pair with a configured heartbeat
+    pub fn get_missed_heartbeats(env: Env) -> Vec<MissedHeartbeat> {
+        let now = env.ledger().timestamp();
+
+        let mut missed = Vec::new(&env);
+        for (oracle, _) in Self::get_oracles(env.clone()).iter() {
+            let submissions = Self::submissions_of(&env, &oracle);
+            for (pair, at) in Self::tracked_pairs(&env, &oracle, &submissions).iter() {
+                let config =
+                    match Self::get_pair_config(env.clone(), pair.base.clone(), pair.quote.clone())
+                    {
+                        Some(config) if config.heartbeat > 0 => config,
+                        _ => continue,
+                    };
+
+                if now.saturating_sub(at) > config.heartbeat {
+                    missed.push_back(MissedHeartbeat {
+                        oracle: oracle.clone(),
+                        pair,
+                        last_submission: at,
+                    });
+                }
+            }
+        }
+        missed
+    }
+}
+
+impl PriceOracle {
+    /// Record an accepted submission
+    pub(crate) fn record_submission(env: &Env, oracle: &Address, token_pair: &TokenPair) {
+        let mut submissions = Self::submissions_of(env, oracle);
+        submissions.set(token_pair.clone(), env.ledger().timestamp());
+        env.storage()
+            .persistent()
+            .set(&(LIVENESS, oracle.clone()), &submissions);
+    }
+
+    fn submissions_of(env: &Env, oracle: &Address) -> Map<TokenPair, u64> {
+        env.storage()
+            .persistent()
+            .get(&(LIVENESS, oracle.clone()))
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    /// Assigned and previously fed pairs with their last submission
+    fn tracked_pairs(
+        env: &Env,
+        oracle: &Address,
+        submissions: &Map<TokenPair, u64>,
+    ) -> Map<TokenPair, u64> {
+        let mut pairs = submissions.clone();
+        for pair in Self::get_oracle_feeds(env.clone(), oracle.clone()).iter() {
+            if !pairs.contains_key(pair.clone()) {
+                pairs.set(pair, 0);
+            }
+        }
+        pairs
+    }
+}
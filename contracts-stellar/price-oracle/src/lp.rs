@@ -0,0 +1,132 @@
+//! Fair-value prices of liquidity pool shares.
+//!
+//! A pair can be backed by a constant product pool: its base is the pool's
+//! share token and the price of one share is derived from the reserves and
+//! the feeds of both pool assets. Using the fair-value formula
+//! `2 * sqrt(reserve_a * reserve_b) * sqrt(price_a * price_b) / shares`
+//! rather than the raw reserve values means skewing the reserves inside a
+//! transaction cannot move the share price. Pool assets and shares are
+//! assumed to use the same number of decimals.
+
+use soroban_sdk::{
+    contractclient, contractimpl, contracttype, symbol_short, Address, Env, String, Symbol,
+};
+use stellhydra_access::require_admin;
+use stellhydra_math::{mul_div, sqrt_mul};
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, TokenPair};
+
+const LP_FEED: Symbol = symbol_short!("LP_FEED");
+
+/// Pool state as returned by the liquidity pool contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolInfo {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub reserve_a: i128,
+    pub reserve_b: i128,
+    pub total_shares: i128,
+    pub fee_rate: u32,
+}
+
+/// Reads used from a liquidity pool
+#[contractclient(name = "LiquidityPoolClient")]
+pub trait LiquidityPoolInterface {
+    fn get_pool_info(env: Env) -> PoolInfo;
+}
+
+/// Pool backing an LP share pair, with the symbols of its two assets
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LpFeed {
+    pub pool: Address,
+    pub asset_a: String,
+    pub asset_b: String,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Price a pair's base as a share of a liquidity pool, or clear it
+    /// (only admin)
+    pub fn set_lp_feed(env: Env, base_token: String, quote_token: String, feed: Option<LpFeed>) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        match feed {
+            Some(feed) => env
+                .storage()
+                .persistent()
+                .set(&(LP_FEED, token_pair), &feed),
+            None => env.storage().persistent().remove(&(LP_FEED, token_pair)),
+        }
+    }
+
+    pub fn get_lp_feed(env: Env, base_token: String, quote_token: String) -> Option<LpFeed> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().get(&(LP_FEED, token_pair))
+    }
+}
+
+impl PriceOracle {
+    /// Fair value of one pool share, in the decimals of the first asset's
+    /// price
+    pub(crate) fn lp_price(env: &Env, token_pair: &TokenPair, paid: bool) -> Option<PriceData> {
+        let feed: LpFeed = env
+            .storage()
+            .persistent()
+            .get(&(LP_FEED, token_pair.clone()))?;
+
+        let price_a = Self::lookup(
+            env,
+            &TokenPair {
+                base: feed.asset_a,
+                quote: token_pair.quote.clone(),
+            },
+            paid,
+        )?;
+        let price_b = Self::lookup(
+            env,
+            &TokenPair {
+                base: feed.asset_b,
+                quote: token_pair.quote.clone(),
+            },
+            paid,
+        )?;
+        let decimals = price_a.decimals;
+        let price_b_scaled = Self::rescale(env, price_b.price, price_b.decimals, decimals);
+
+        let pool = LiquidityPoolClient::new(env, &feed.pool).get_pool_info();
+        if pool.total_shares <= 0 || pool.reserve_a < 0 || pool.reserve_b < 0 {
+            return None;
+        }
+        if price_a.price <= 0 || price_b_scaled <= 0 {
+            return None;
+        }
+
+        let reserves_root = sqrt_mul(env, pool.reserve_a, pool.reserve_b)?;
+        let prices_root = sqrt_mul(env, price_a.price, price_b_scaled)?;
+        let price = mul_div(
+            env,
+            reserves_root,
+            prices_root.checked_mul(2)?,
+            pool.total_shares,
+        )?;
+
+        Some(PriceData {
+            price,
+            decimals,
+            timestamp: price_a.timestamp.min(price_b.timestamp),
+            source: String::from_str(env, "lp-fair"),
+            confidence: 0,
+        })
+    }
+}
@@ -0,0 +1,216 @@
+//! Optimistic updates with a dispute window.
+//!
+//! With a dispute window configured, the first answer of a round that
+//! would otherwise wait for quorum is proposed on its own. It goes live once
+//! the window has passed and anybody activates it. Until then any other
+//! authorized oracle can challenge it, which turns the proposal back into
+//! a regular round that needs quorum to finalize.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol};
+use stellhydra_access::{require_admin, require_not_paused};
+use stellhydra_events::oracle::{PriceDisputed, PriceProposed};
+use stellhydra_events::Event;
+
+use crate::{PendingRound, PriceOracle, PriceOracleClient, RoundData, TokenPair, PENDING};
+
+const OPT_WINDOW: Symbol = symbol_short!("OPT_WIN");
+const OPTIMIST: Symbol = symbol_short!("OPTIMIST");
+const DISPUTED: Symbol = symbol_short!("DISPUTED");
+
+/// Answer proposed by a single oracle, waiting out the dispute window
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptimisticPrice {
+    pub round: RoundData,
+    pub oracle: Address,
+    pub source: String,
+    pub active_at: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Set the dispute window of optimistic updates; zero disables them
+    /// (only admin)
+    pub fn set_dispute_window(env: Env, window: u64) {
+        require_admin(&env);
+
+        env.storage().instance().set(&OPT_WINDOW, &window);
+    }
+
+    pub fn get_dispute_window(env: Env) -> u64 {
+        env.storage().instance().get(&OPT_WINDOW).unwrap_or(0)
+    }
+
+    /// Make an unchallenged proposal the pair's price once its dispute
+    /// window has passed
+    ///
+    /// Activating writes the price, so it is refused whenever a submission
+    /// would be: while paused, or for a frozen, suspended or deprecated
+    /// pair.
+    pub fn activate_price(env: Env, base_token: String, quote_token: String) {
+        require_not_paused(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        Self::check_not_aliased(&env, &token_pair);
+        if Self::frozen(&env, &token_pair) {
+            panic!("Pair is frozen");
+        }
+        if Self::suspended(&env, &token_pair) {
+            panic!("Pair is suspended");
+        }
+        let proposal =
+            Self::proposal(&env, &token_pair).unwrap_or_else(|| panic!("No proposed price"));
+
+        if env.ledger().timestamp() < proposal.active_at {
+            panic!("Dispute window still open");
+        }
+
+        let mut round = proposal.round;
+        round.updated_at = env.ledger().timestamp();
+        Self::finalize_round(&env, token_pair, round, proposal.source);
+    }
+
+    /// Challenge a proposed price (only authorized oracles other than the
+    /// proposer)
+    ///
+    /// The proposal becomes the first answer of a round that only
+    /// finalizes at quorum; the pair stays out of optimistic mode until
+    /// then.
+    pub fn challenge_price(env: Env, oracle: Address, base_token: String, quote_token: String) {
+        Self::check_oracle(&env, &oracle);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        let proposal =
+            Self::proposal(&env, &token_pair).unwrap_or_else(|| panic!("No proposed price"));
+
+        if proposal.oracle == oracle {
+            panic!("Challenge requires a second oracle");
+        }
+        if env.ledger().timestamp() >= proposal.active_at {
+            panic!("Dispute window closed");
+        }
+
+        let mut answers = Map::new(&env);
+        answers.set(proposal.oracle.clone(), proposal.round.price);
+        let round = PendingRound {
+            round_id: proposal.round.round_id,
+            decimals: proposal.round.decimals,
+            started_at: proposal.round.started_at,
+            answers,
+            confidence: proposal.round.confidence,
+        };
+
+        env.storage()
+            .persistent()
+            .remove(&(OPTIMIST, token_pair.clone()));
+        env.storage()
+            .persistent()
+            .set(&(PENDING, token_pair.clone()), &round);
+        env.storage()
+            .persistent()
+            .set(&(DISPUTED, token_pair.clone()), &true);
+
+        PriceDisputed {
+            base: token_pair.base,
+            quote: token_pair.quote,
+            proposer: proposal.oracle,
+            disputer: oracle,
+        }
+        .publish(&env);
+    }
+
+    pub fn get_proposed_price(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<OptimisticPrice> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::proposal(&env, &token_pair)
+    }
+
+    /// Whether a challenged round of the pair is waiting for quorum
+    pub fn is_pair_disputed(env: Env, base_token: String, quote_token: String) -> bool {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().has(&(DISPUTED, token_pair))
+    }
+}
+
+impl PriceOracle {
+    /// Whether answers of the pair are proposed rather than collected
+    pub(crate) fn is_optimistic(env: &Env, token_pair: &TokenPair) -> bool {
+        Self::get_dispute_window(env.clone()) > 0
+            && !env
+                .storage()
+                .persistent()
+                .has(&(DISPUTED, token_pair.clone()))
+    }
+
+    /// Propose a round's answer to go live after the dispute window
+    pub(crate) fn propose(
+        env: &Env,
+        token_pair: &TokenPair,
+        oracle: Address,
+        round: RoundData,
+        source: String,
+    ) {
+        let proposal = OptimisticPrice {
+            round,
+            oracle,
+            source,
+            active_at: env.ledger().timestamp() + Self::get_dispute_window(env.clone()),
+        };
+        env.storage()
+            .persistent()
+            .set(&(OPTIMIST, token_pair.clone()), &proposal);
+
+        PriceProposed {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+            proposer: proposal.oracle,
+            price: proposal.round.price,
+            active_at: proposal.active_at,
+        }
+        .publish(env);
+    }
+
+    /// Panic while a proposal of the pair waits out its window
+    pub(crate) fn check_no_proposal(env: &Env, token_pair: &TokenPair) {
+        if env
+            .storage()
+            .persistent()
+            .has(&(OPTIMIST, token_pair.clone()))
+        {
+            panic!("Proposed price pending");
+        }
+    }
+
+    /// Forget a pair's proposal and dispute once a round finalizes
+    pub(crate) fn settle_proposal(env: &Env, token_pair: &TokenPair) {
+        env.storage()
+            .persistent()
+            .remove(&(OPTIMIST, token_pair.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(DISPUTED, token_pair.clone()));
+    }
+
+    fn proposal(env: &Env, token_pair: &TokenPair) -> Option<OptimisticPrice> {
+        env.storage()
+            .persistent()
+            .get(&(OPTIMIST, token_pair.clone()))
+    }
+}
@@ -0,0 +1,109 @@
+//! Price pushes to consumer contracts.
+//!
+//! Consumers registered for a pair get `on_price_update` called every time
+//! a new price for the pair is published. A failing consumer neither blocks
+//! the update nor the other consumers.
+
+use soroban_sdk::{contractclient, contractimpl, symbol_short, Address, Env, String, Symbol, Vec};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::PushFailed;
+use stellhydra_events::Event;
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, TokenPair};
+
+const CONSUMERS: Symbol = symbol_short!("CONSUMERS");
+
+// Consumers called per update, bounding the cost of an update
+const MAX_CONSUMERS: u32 = 5;
+
+/// Callback implemented by consumer contracts
+#[contractclient(name = "PriceConsumerClient")]
+pub trait PriceConsumer {
+    fn on_price_update(env: Env, base: String, quote: String, price_data: PriceData);
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Push a pair's updates to a consumer contract (only admin)
+    pub fn register_consumer(env: Env, base_token: String, quote_token: String, consumer: Address) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        let mut consumers = Self::consumers_of(&env, &token_pair);
+        if consumers.contains(&consumer) {
+            return;
+        }
+        if consumers.len() >= MAX_CONSUMERS {
+            panic!("Too many consumers");
+        }
+        consumers.push_back(consumer);
+        env.storage()
+            .persistent()
+            .set(&(CONSUMERS, token_pair), &consumers);
+    }
+
+    /// Stop pushing a pair's updates to a consumer (only admin)
+    pub fn unregister_consumer(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        consumer: Address,
+    ) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        let mut consumers = Self::consumers_of(&env, &token_pair);
+        if let Some(index) = consumers.first_index_of(&consumer) {
+            consumers.remove(index);
+        }
+        env.storage()
+            .persistent()
+            .set(&(CONSUMERS, token_pair), &consumers);
+    }
+
+    pub fn get_consumers(env: Env, base_token: String, quote_token: String) -> Vec<Address> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::consumers_of(&env, &token_pair)
+    }
+}
+
+impl PriceOracle {
+    /// Call every consumer of a pair with its new price
+    pub(crate) fn notify_consumers(env: &Env, token_pair: &TokenPair, price_data: &PriceData) {
+        for consumer in Self::consumers_of(env, token_pair).iter() {
+            let result = PriceConsumerClient::new(env, &consumer).try_on_price_update(
+                &token_pair.base,
+                &token_pair.quote,
+                price_data,
+            );
+
+            if result.is_err() {
+                PushFailed {
+                    base: token_pair.base.clone(),
+                    quote: token_pair.quote.clone(),
+                    consumer,
+                }
+                .publish(env);
+            }
+        }
+    }
+
+    fn consumers_of(env: &Env, token_pair: &TokenPair) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&(CONSUMERS, token_pair.clone()))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+}
@@ -0,0 +1,140 @@
+//! Reflector passthrough feeds.
+//!
+//! A pair without prices from our own oracles can be backed by a Reflector
+//! (SEP-40) feed. Reads cross-call the feed, rescale the answer to the
+//! pair's decimals and cache it for a short period.
+
+use soroban_sdk::{
+    contractclient, contractimpl, contracttype, symbol_short, Address, Env, String, Symbol,
+};
+use stellhydra_access::require_admin;
+
+use crate::{Asset, PriceData, PriceOracle, PriceOracleClient, Sep40PriceData, TokenPair};
+
+const REFLECTOR: Symbol = symbol_short!("REFLECTOR");
+const RFL_CACHE: Symbol = symbol_short!("RFL_CACHE");
+
+/// Reads used from a Reflector feed
+#[contractclient(name = "ReflectorClient")]
+pub trait ReflectorInterface {
+    fn decimals(env: Env) -> u32;
+    fn lastprice(env: Env, asset: Asset) -> Option<Sep40PriceData>;
+}
+
+/// External feed backing a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReflectorFeed {
+    pub feed: Address,
+    pub asset: Asset,
+    pub decimals: u32,
+    pub cache_secs: u64,
+}
+
+/// Cached answer from a Reflector feed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CachedPrice {
+    pub price_data: PriceData,
+    pub fetched_at: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Back a pair with a Reflector feed, or clear it (only admin)
+    pub fn set_reflector_feed(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        feed: Option<ReflectorFeed>,
+    ) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        match feed {
+            Some(feed) => {
+                Self::list_pair(
+                    &env,
+                    &token_pair,
+                    feed.decimals,
+                    &String::from_str(&env, "reflector"),
+                );
+                env.storage()
+                    .persistent()
+                    .set(&(REFLECTOR, token_pair), &feed);
+            }
+            None => {
+                Self::clear_reflector_feed(&env, &token_pair);
+
+                // A pair our oracles never fed has no source left
+                if !env.storage().persistent().has(&token_pair) {
+                    Self::unlist_pair(&env, &token_pair);
+                }
+            }
+        }
+    }
+
+    pub fn get_reflector_feed(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<ReflectorFeed> {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().get(&(REFLECTOR, token_pair))
+    }
+}
+
+impl PriceOracle {
+    pub(crate) fn clear_reflector_feed(env: &Env, token_pair: &TokenPair) {
+        env.storage()
+            .persistent()
+            .remove(&(REFLECTOR, token_pair.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(RFL_CACHE, token_pair.clone()));
+    }
+
+    /// Price of a pair from its Reflector feed, if one is configured
+    pub(crate) fn reflector_price(env: &Env, token_pair: &TokenPair) -> Option<PriceData> {
+        let feed: ReflectorFeed = env
+            .storage()
+            .persistent()
+            .get(&(REFLECTOR, token_pair.clone()))?;
+
+        let now = env.ledger().timestamp();
+        let cache_key = (RFL_CACHE, token_pair.clone());
+        if let Some(cached) = env.storage().persistent().get::<_, CachedPrice>(&cache_key) {
+            if now < cached.fetched_at + feed.cache_secs {
+                return Some(cached.price_data);
+            }
+        }
+
+        let client = ReflectorClient::new(env, &feed.feed);
+        let answer = client.lastprice(&feed.asset)?;
+        let price_data = PriceData {
+            price: Self::rescale(env, answer.price, client.decimals(), feed.decimals),
+            decimals: feed.decimals,
+            timestamp: answer.timestamp,
+            source: String::from_str(env, "reflector"),
+            confidence: 0,
+        };
+
+        env.storage().persistent().set(
+            &cache_key,
+            &CachedPrice {
+                price_data: price_data.clone(),
+                fetched_at: now,
+            },
+        );
+
+        Some(price_data)
+    }
+}
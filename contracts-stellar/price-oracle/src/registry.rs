@@ -0,0 +1,170 @@
+//! Registry of the pairs the oracle covers.
+//!
+//! A pair is listed when its first price is published, by our oracles or
+//! by a Reflector feed, and delisted once it has no source left.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, Env, String, Symbol, Vec};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::{PairDelisted, PairListed};
+use stellhydra_events::Event;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const PAIRS: Symbol = symbol_short!("PAIRS");
+const PAIR_META: Symbol = symbol_short!("PAIR_META");
+
+// Distinct sources remembered per pair
+const MAX_PAIR_SOURCES: u32 = 8;
+
+/// Listed pair with its metadata
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairInfo {
+    pub pair: TokenPair,
+    pub decimals: u32,
+    pub sources: Vec<String>,
+    /// Staleness config, if the pair has one
+    pub max_staleness: Option<u64>,
+    pub heartbeat: Option<u64>,
+    pub listed_at: u64,
+}
+
+/// Stored part of a pair's listing
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PairMeta {
+    decimals: u32,
+    sources: Vec<String>,
+    listed_at: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Page through the listed pairs in listing order
+    pub fn get_all_pairs(env: Env, start: u32, limit: u32) -> Vec<PairInfo> {
+        let pairs = Self::listed_pairs(&env);
+        let end = start.saturating_add(limit).min(pairs.len());
+
+        let mut page = Vec::new(&env);
+        for index in start..end {
+            let pair = pairs.get_unchecked(index);
+            if let Some(info) = Self::pair_info(&env, pair) {
+                page.push_back(info);
+            }
+        }
+        page
+    }
+
+    /// Number of listed pairs
+    pub fn get_pair_count(env: Env) -> u32 {
+        Self::listed_pairs(&env).len()
+    }
+
+    pub fn get_pair_info(env: Env, base_token: String, quote_token: String) -> Option<PairInfo> {
+        Self::pair_info(
+            &env,
+            TokenPair {
+                base: base_token,
+                quote: quote_token,
+            },
+        )
+    }
+
+    /// Retire a pair: drop its latest price and Reflector backing and
+    /// delist it (only admin)
+    pub fn delist_pair(env: Env, base_token: String, quote_token: String) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        env.storage().persistent().remove(&token_pair);
+        Self::clear_reflector_feed(&env, &token_pair);
+        Self::unlist_pair(&env, &token_pair);
+    }
+}
+
+impl PriceOracle {
+    /// List a pair on its first price and keep its metadata current
+    pub(crate) fn list_pair(env: &Env, token_pair: &TokenPair, decimals: u32, source: &String) {
+        let key = (PAIR_META, token_pair.clone());
+        let mut meta = match env.storage().persistent().get::<_, PairMeta>(&key) {
+            Some(meta) => meta,
+            None => {
+                let mut pairs = Self::listed_pairs(env);
+                pairs.push_back(token_pair.clone());
+                env.storage().persistent().set(&PAIRS, &pairs);
+
+                PairListed {
+                    base: token_pair.base.clone(),
+                    quote: token_pair.quote.clone(),
+                }
+                .publish(env);
+
+                PairMeta {
+                    decimals,
+                    sources: Vec::new(env),
+                    listed_at: env.ledger().timestamp(),
+                }
+            }
+        };
+
+        meta.decimals = decimals;
+        if !meta.sources.contains(source) && meta.sources.len() < MAX_PAIR_SOURCES {
+            meta.sources.push_back(source.clone());
+        }
+        env.storage().persistent().set(&key, &meta);
+    }
+
+    /// Delist a pair left without a price or feed
+    pub(crate) fn unlist_pair(env: &Env, token_pair: &TokenPair) {
+        let key = (PAIR_META, token_pair.clone());
+        if !env.storage().persistent().has(&key) {
+            return;
+        }
+        env.storage().persistent().remove(&key);
+
+        let mut pairs = Self::listed_pairs(env);
+        if let Some(index) = pairs.first_index_of(token_pair) {
+            pairs.remove(index);
+        }
+        env.storage().persistent().set(&PAIRS, &pairs);
+
+        PairDelisted {
+            base: token_pair.base.clone(),
+            quote: token_pair.quote.clone(),
+        }
+        .publish(env);
+    }
+
+    fn listed_pairs(env: &Env) -> Vec<TokenPair> {
+        env.storage()
+            .persistent()
+            .get(&PAIRS)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn pair_info(env: &Env, token_pair: TokenPair) -> Option<PairInfo> {
+        let meta: PairMeta = env
+            .storage()
+            .persistent()
+            .get(&(PAIR_META, token_pair.clone()))?;
+
+        let config = Self::get_pair_config(
+            env.clone(),
+            token_pair.base.clone(),
+            token_pair.quote.clone(),
+        );
+
+        Some(PairInfo {
+            pair: token_pair,
+            max_staleness: config.as_ref().map(|config| config.max_staleness),
+            heartbeat: config.map(|config| config.heartbeat),
+            decimals: meta.decimals,
+            sources: meta.sources,
+            listed_at: meta.listed_at,
+        })
+    }
+}
@@ -0,0 +1,175 @@
+//! SEP-40 oracle interface.
+//!
+//! Exposes the standard Soroban oracle reads on top of the pair-based API so
+//! protocols integrating SEP-40 can consume StellHydra prices directly. Each
+//! SEP-40 asset maps to a base token string, quoted against the configured
+//! quote token.
+
+use soroban_sdk::{
+    contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol, Vec,
+};
+use stellhydra_access::require_admin;
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, TokenPair};
+
+const SEP40_CONFIG: Symbol = symbol_short!("SEP40_CFG");
+const SEP40_ASSETS: Symbol = symbol_short!("SEP40_AST");
+
+/// Asset identifier as defined by SEP-40
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// Price record as defined by SEP-40
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sep40PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sep40Config {
+    pub base: Asset,
+    pub quote_token: String,
+    pub decimals: u32,
+    pub resolution: u32,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Configure the SEP-40 base asset, the quote token it maps to, and the
+    /// decimals and resolution reported to consumers (only admin)
+    pub fn set_sep40_config(env: Env, config: Sep40Config) {
+        require_admin(&env);
+
+        if config.resolution == 0 {
+            panic!("Resolution must be positive");
+        }
+
+        env.storage().instance().set(&SEP40_CONFIG, &config);
+    }
+
+    /// Map a SEP-40 asset to the base token string it is priced under
+    /// (only admin)
+    pub fn set_sep40_asset(env: Env, asset: Asset, base_token: String) {
+        require_admin(&env);
+
+        let mut assets = Self::sep40_assets(&env);
+        assets.set(asset, base_token);
+        env.storage().instance().set(&SEP40_ASSETS, &assets);
+    }
+
+    /// Base asset prices are quoted in
+    pub fn base(env: Env) -> Asset {
+        Self::sep40_config(&env).base
+    }
+
+    /// Assets with a price feed
+    pub fn assets(env: Env) -> Vec<Asset> {
+        Self::sep40_assets(&env).keys()
+    }
+
+    /// Decimals of the reported prices
+    pub fn decimals(env: Env) -> u32 {
+        Self::sep40_config(&env).decimals
+    }
+
+    /// Expected interval between price updates, in seconds
+    pub fn resolution(env: Env) -> u32 {
+        Self::sep40_config(&env).resolution
+    }
+
+    /// Price of `asset` at `timestamp`
+    ///
+    /// Like `lastprice`, none for a frozen, suspended or premium pair, and
+    /// a deprecated pair reads its successor's history.
+    pub fn price(env: Env, asset: Asset, timestamp: u64) -> Option<Sep40PriceData> {
+        let config = Self::sep40_config(&env);
+        let token_pair = Self::sep40_live_pair(&env, &config, asset)?;
+
+        let price_data =
+            Self::get_price_at(env.clone(), token_pair.base, token_pair.quote, timestamp)?;
+        Some(Self::to_sep40(&env, &config, price_data))
+    }
+
+    /// Up to `records` most recent prices of `asset`, newest first
+    ///
+    /// Subject to the same checks as `price`.
+    pub fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<Sep40PriceData>> {
+        let config = Self::sep40_config(&env);
+        let token_pair = Self::sep40_live_pair(&env, &config, asset)?;
+
+        let history =
+            Self::get_price_history(env.clone(), token_pair.base, token_pair.quote, records);
+        if history.is_empty() {
+            return None;
+        }
+
+        let mut prices = Vec::new(&env);
+        for price_data in history.iter() {
+            prices.push_back(Self::to_sep40(&env, &config, price_data));
+        }
+        Some(prices)
+    }
+
+    /// Most recent price of `asset`
+    pub fn lastprice(env: Env, asset: Asset) -> Option<Sep40PriceData> {
+        let config = Self::sep40_config(&env);
+        let token_pair = Self::sep40_pair(&env, &config, asset)?;
+
+        let price_data = Self::get_price(env.clone(), token_pair.base, token_pair.quote)?;
+        Some(Self::to_sep40(&env, &config, price_data))
+    }
+}
+
+impl PriceOracle {
+    fn sep40_config(env: &Env) -> Sep40Config {
+        env.storage()
+            .instance()
+            .get(&SEP40_CONFIG)
+            .unwrap_or_else(|| panic!("SEP-40 not configured"))
+    }
+
+    fn sep40_assets(env: &Env) -> Map<Asset, String> {
+        env.storage()
+            .instance()
+            .get(&SEP40_ASSETS)
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    fn sep40_pair(env: &Env, config: &Sep40Config, asset: Asset) -> Option<TokenPair> {
+        let base = Self::sep40_assets(env).get(asset)?;
+        Some(TokenPair {
+            base,
+            quote: config.quote_token.clone(),
+        })
+    }
+
+    /// Pair whose history is served for `asset`, the way `get_price`
+    /// serves the latest price
+    fn sep40_live_pair(env: &Env, config: &Sep40Config, asset: Asset) -> Option<TokenPair> {
+        let token_pair = Self::successor(env, &Self::sep40_pair(env, config, asset)?);
+        if Self::frozen(env, &token_pair) || Self::suspended(env, &token_pair) {
+            return None;
+        }
+        Some(token_pair)
+    }
+
+    fn to_sep40(env: &Env, config: &Sep40Config, price_data: PriceData) -> Sep40PriceData {
+        Sep40PriceData {
+            price: Self::rescale(env, price_data.price, price_data.decimals, config.decimals),
+            timestamp: price_data.timestamp,
+        }
+    }
+
+    /// Convert a price between decimal precisions
+    pub(crate) fn rescale(env: &Env, price: i128, from: u32, to: u32) -> i128 {
+        stellhydra_math::rescale(env, price, from, to)
+            .unwrap_or_else(|| panic!("Arithmetic overflow"))
+    }
+}
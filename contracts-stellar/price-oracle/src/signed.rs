@@ -0,0 +1,139 @@
+//! Signed off-chain price submission.
+//!
+//! An oracle registers an ed25519 key and signs price payloads off-chain;
+//! anyone can relay them. The signed message is the XDR of this contract's
+//! address followed by the payload, so a signature cannot be replayed on
+//! another deployment. Payloads carry a strictly increasing per-oracle
+//! nonce and expire after the signed payload max age.
+
+use soroban_sdk::{
+    contractimpl, contracttype, symbol_short, xdr::ToXdr, Address, BytesN, Env, String, Symbol,
+};
+use stellhydra_access::require_admin;
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+const ORC_KEY: Symbol = symbol_short!("ORC_KEY");
+const KEY_ORC: Symbol = symbol_short!("KEY_ORC");
+const NONCE: Symbol = symbol_short!("NONCE");
+const SIG_AGE: Symbol = symbol_short!("SIG_AGE");
+
+// Oldest signed payload accepted unless configured otherwise
+const DEFAULT_SIGNED_MAX_AGE: u64 = 300;
+
+/// Price an oracle signed off-chain
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedPrice {
+    pub base: String,
+    pub quote: String,
+    pub price: i128,
+    pub confidence: i128,
+    pub decimals: u32,
+    pub source: String,
+    pub timestamp: u64,
+    pub nonce: u64,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Register the key an oracle signs payloads with (only the oracle)
+    pub fn set_oracle_key(env: Env, oracle: Address, pubkey: BytesN<32>) {
+        oracle.require_auth();
+
+        if !Self::is_oracle_authorized(env.clone(), oracle.clone()) {
+            panic!("Unauthorized oracle");
+        }
+        if let Some(owner) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&(KEY_ORC, pubkey.clone()))
+        {
+            if owner != oracle {
+                panic!("Key already registered");
+            }
+        }
+
+        if let Some(previous) = Self::get_oracle_key(env.clone(), oracle.clone()) {
+            env.storage().persistent().remove(&(KEY_ORC, previous));
+        }
+        env.storage()
+            .persistent()
+            .set(&(ORC_KEY, oracle.clone()), &pubkey);
+        env.storage().persistent().set(&(KEY_ORC, pubkey), &oracle);
+    }
+
+    pub fn get_oracle_key(env: Env, oracle: Address) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&(ORC_KEY, oracle))
+    }
+
+    /// Set the oldest signed payload still accepted (only admin)
+    pub fn set_signed_max_age(env: Env, max_age: u64) {
+        require_admin(&env);
+
+        env.storage().instance().set(&SIG_AGE, &max_age);
+    }
+
+    pub fn get_signed_max_age(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&SIG_AGE)
+            .unwrap_or(DEFAULT_SIGNED_MAX_AGE)
+    }
+
+    /// Last nonce used by an oracle's signed payloads
+    pub fn get_oracle_nonce(env: Env, oracle: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&(NONCE, oracle))
+            .unwrap_or(0)
+    }
+
+    /// Relay a price signed by an authorized oracle
+    pub fn submit_signed_price(
+        env: Env,
+        payload: SignedPrice,
+        pubkey: BytesN<32>,
+        signature: BytesN<64>,
+    ) {
+        let oracle: Address = env
+            .storage()
+            .persistent()
+            .get(&(KEY_ORC, pubkey.clone()))
+            .unwrap_or_else(|| panic!("Unknown key"));
+
+        let message = (env.current_contract_address(), payload.clone()).to_xdr(&env);
+        env.crypto().ed25519_verify(&pubkey, &message, &signature);
+
+        if payload.nonce <= Self::get_oracle_nonce(env.clone(), oracle.clone()) {
+            panic!("Nonce already used");
+        }
+        let now = env.ledger().timestamp();
+        if payload.timestamp > now {
+            panic!("Payload from the future");
+        }
+        if now - payload.timestamp > Self::get_signed_max_age(env.clone()) {
+            panic!("Payload expired");
+        }
+        env.storage()
+            .persistent()
+            .set(&(NONCE, oracle.clone()), &payload.nonce);
+
+        let token_pair = TokenPair {
+            base: payload.base,
+            quote: payload.quote,
+        };
+        let round_id = Self::latest_round_id(&env, &token_pair) + 1;
+
+        Self::submit(
+            &env,
+            oracle,
+            token_pair,
+            round_id,
+            payload.price,
+            payload.confidence,
+            payload.decimals,
+            payload.source,
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Paid reads of premium pairs.
+//!
+//! Premium pairs, and the inverse and cross prices computed from them, are
+//! left out of the free reads, rounds, history and TWAP included. Consumers read them through
+//! `get_premium_price`, either under a subscription or by paying the
+//! per-read fee. Fees accrue in the contract until the admin withdraws
+//! them, e.g. to a fee distributor.
+
+use soroban_sdk::{contractimpl, contracttype, symbol_short, token, Address, Env, String, Symbol};
+use stellhydra_access::require_admin;
+use stellhydra_events::oracle::Subscribed;
+use stellhydra_events::Event;
+
+use crate::{PriceData, PriceOracle, PriceOracleClient, TokenPair};
+
+const READ_FEES: Symbol = symbol_short!("READ_FEES");
+const PREMIUM: Symbol = symbol_short!("PREMIUM");
+const SUB_UNTIL: Symbol = symbol_short!("SUB_UNTIL");
+const ACCRUED: Symbol = symbol_short!("ACCRUED");
+
+/// Prices of premium reads
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReadFees {
+    pub token: Address,
+    pub subscription_fee: i128,
+    pub subscription_period: u64,
+    /// Fee of a single read without subscription; 0 requires a subscription
+    pub read_fee: i128,
+}
+
+#[contractimpl]
+impl PriceOracle {
+    /// Set the fees of premium reads (only admin)
+    pub fn set_read_fees(env: Env, fees: ReadFees) {
+        require_admin(&env);
+
+        if fees.subscription_fee < 0 || fees.read_fee < 0 || fees.subscription_period == 0 {
+            panic!("Invalid read fees");
+        }
+        if let Some(current) = Self::get_read_fees(env.clone()) {
+            if current.token != fees.token && Self::get_accrued_fees(env.clone()) > 0 {
+                panic!("Withdraw fees before changing token");
+            }
+        }
+
+        env.storage().instance().set(&READ_FEES, &fees);
+    }
+
+    pub fn get_read_fees(env: Env) -> Option<ReadFees> {
+        env.storage().instance().get(&READ_FEES)
+    }
+
+    /// Mark a pair as premium, or make it free again (only admin)
+    pub fn set_premium_pair(env: Env, base_token: String, quote_token: String, premium: bool) {
+        require_admin(&env);
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        if premium {
+            env.storage()
+                .persistent()
+                .set(&(PREMIUM, token_pair), &true);
+        } else {
+            env.storage().persistent().remove(&(PREMIUM, token_pair));
+        }
+    }
+
+    pub fn is_premium_pair(env: Env, base_token: String, quote_token: String) -> bool {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        Self::premium(&env, &token_pair)
+    }
+
+    /// Pay for `periods` subscription periods, extending any running one
+    pub fn subscribe(env: Env, consumer: Address, periods: u32) -> u64 {
+        consumer.require_auth();
+
+        if periods == 0 {
+            panic!("Periods must be positive");
+        }
+        let fees = Self::get_read_fees(env.clone()).unwrap_or_else(|| panic!("No read fees"));
+
+        Self::charge(
+            &env,
+            &fees,
+            &consumer,
+            fees.subscription_fee * periods as i128,
+        );
+
+        let now = env.ledger().timestamp();
+        let start = Self::get_subscription(env.clone(), consumer.clone()).max(now);
+        let until = start + fees.subscription_period * periods as u64;
+        env.storage()
+            .persistent()
+            .set(&(SUB_UNTIL, consumer.clone()), &until);
+
+        Subscribed { consumer, until }.publish(&env);
+        until
+    }
+
+    /// End of a consumer's subscription (0 if it never subscribed)
+    pub fn get_subscription(env: Env, consumer: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&(SUB_UNTIL, consumer))
+            .unwrap_or(0)
+    }
+
+    /// Get latest price, including premium pairs
+    ///
+    /// Consumers without a running subscription pay the read fee when the
+    /// price needs a premium pair.
+    pub fn get_premium_price(
+        env: Env,
+        consumer: Address,
+        base_token: String,
+        quote_token: String,
+    ) -> Option<PriceData> {
+        consumer.require_auth();
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if let Some(price_data) = Self::lookup(&env, &token_pair, false) {
+            return Some(price_data);
+        }
+        let price_data = Self::lookup(&env, &token_pair, true)?;
+
+        if env.ledger().timestamp() >= Self::get_subscription(env.clone(), consumer.clone()) {
+            let fees = Self::get_read_fees(env.clone()).unwrap_or_else(|| panic!("No read fees"));
+            if fees.read_fee == 0 {
+                panic!("Subscription required");
+            }
+            Self::charge(&env, &fees, &consumer, fees.read_fee);
+        }
+
+        Some(price_data)
+    }
+
+    pub fn get_accrued_fees(env: Env) -> i128 {
+        env.storage().instance().get(&ACCRUED).unwrap_or(0)
+    }
+
+    /// Pay out the accrued read fees (only admin)
+    pub fn withdraw_fees(env: Env, recipient: Address) -> i128 {
+        require_admin(&env);
+
+        let accrued = Self::get_accrued_fees(env.clone());
+        if accrued > 0 {
+            let fees = Self::get_read_fees(env.clone()).unwrap();
+            env.storage().instance().set(&ACCRUED, &0i128);
+            token::Client::new(&env, &fees.token).transfer(
+                &env.current_contract_address(),
+                &recipient,
+                &accrued,
+            );
+        }
+
+        accrued
+    }
+}
+
+impl PriceOracle {
+    pub(crate) fn premium(env: &Env, token_pair: &TokenPair) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(PREMIUM, token_pair.clone()))
+    }
+
+    fn charge(env: &Env, fees: &ReadFees, consumer: &Address, amount: i128) {
+        if amount == 0 {
+            return;
+        }
+
+        token::Client::new(env, &fees.token).transfer(
+            consumer,
+            &env.current_contract_address(),
+            &amount,
+        );
+        let accrued = Self::get_accrued_fees(env.clone()) + amount;
+        env.storage().instance().set(&ACCRUED, &accrued);
+    }
+}
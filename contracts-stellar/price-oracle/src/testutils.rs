@@ -0,0 +1,57 @@
+//! Scenario builders for tests against the oracle
+//!
+//! Deploys the oracle with one authorized feeder and publishes prices
+//! through it:
+//!
+//! ```ignore
+//! let scenario = OracleScenario::new(&env).with_price("XLM", "USDC", 1_200_000);
+//! ```
+//!
+//! All authorizations are mocked.
+
+use soroban_sdk::{testutils::Address as _, Address, Env, String};
+
+use crate::{PriceOracle, PriceOracleClient};
+
+/// Decimals of the prices the scenario publishes
+pub const DECIMALS: u32 = 7;
+
+/// The oracle deployed with the feeder publishing to it
+pub struct OracleScenario<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub feeder: Address,
+    pub oracle: PriceOracleClient<'a>,
+}
+
+impl OracleScenario<'_> {
+    pub fn new(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let admin = Address::generate(env);
+        let feeder = Address::generate(env);
+        let oracle = PriceOracleClient::new(env, &env.register_contract(None, PriceOracle));
+        oracle.initialize(&admin);
+        oracle.add_oracle(&feeder);
+
+        OracleScenario {
+            env: env.clone(),
+            admin,
+            feeder,
+            oracle,
+        }
+    }
+
+    /// Publish a price for the pair, with [`DECIMALS`] decimals
+    pub fn with_price(self, base: &str, quote: &str, price: i128) -> Self {
+        self.oracle.update_price(
+            &self.feeder,
+            &String::from_str(&self.env, base),
+            &String::from_str(&self.env, quote),
+            &price,
+            &DECIMALS,
+            &String::from_str(&self.env, "scenario"),
+        );
+        self
+    }
+}
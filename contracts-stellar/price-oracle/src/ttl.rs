@@ -0,0 +1,55 @@
+//! TTL maintenance of pair entries.
+//!
+//! Updates extend the entries they write. `bump_pair` lets anyone keep a
+//! rarely updated pair, including its whole retained history, from being
+//! archived.
+
+use soroban_sdk::{contractimpl, Env, IntoVal, String, Val};
+
+use crate::{
+    PriceOracle, PriceOracleClient, TokenPair, HISTORY, HIST_FROM, HIST_LEN, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD, LATEST, PAIR_CFG, ROUND,
+};
+
+#[contractimpl]
+impl PriceOracle {
+    /// Extend the TTL of a pair's entries to at least `ledgers` ledgers
+    pub fn bump_pair(env: Env, base_token: String, quote_token: String, ledgers: u32) {
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+        if !env.storage().persistent().has(&token_pair) {
+            panic!("Pair has no price");
+        }
+
+        let extend_to = ledgers.min(env.storage().max_ttl());
+        let round_id = Self::latest_round_id(&env, &token_pair);
+        Self::extend(&env, &token_pair, extend_to);
+        Self::extend(&env, &(LATEST, token_pair.clone()), extend_to);
+        Self::extend(&env, &(ROUND, token_pair.clone(), round_id), extend_to);
+        Self::extend(&env, &(PAIR_CFG, token_pair.clone()), extend_to);
+        Self::extend(&env, &(HIST_LEN, token_pair.clone()), extend_to);
+        Self::extend(&env, &(HIST_FROM, token_pair.clone()), extend_to);
+
+        let (oldest, total) = Self::history_range(&env, &token_pair);
+        for seq in oldest..total {
+            Self::extend(&env, &(HISTORY, token_pair.clone(), seq), extend_to);
+        }
+
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+impl PriceOracle {
+    /// Extend a persistent entry, if it exists, to `extend_to` ledgers
+    pub(crate) fn extend<K: IntoVal<Env, Val>>(env: &Env, key: &K, extend_to: u32) {
+        if env.storage().persistent().has(key) {
+            env.storage()
+                .persistent()
+                .extend_ttl(key, extend_to, extend_to);
+        }
+    }
+}
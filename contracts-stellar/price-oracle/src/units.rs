@@ -0,0 +1,66 @@
+//! Decimal normalization helpers.
+//!
+//! Prices come with their own `decimals`; these reads do the exponent math
+//! for consumers, with checked arithmetic throughout.
+
+use soroban_sdk::{contractimpl, Env, String};
+
+use stellhydra_math::{mul_div, pow10};
+
+use crate::{PriceData, PriceOracle, PriceOracleClient};
+
+#[contractimpl]
+impl PriceOracle {
+    /// Get latest price expressed with `target_decimals` decimals
+    pub fn get_price_scaled(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        target_decimals: u32,
+    ) -> Option<PriceData> {
+        let mut price_data = Self::get_price(env.clone(), base_token, quote_token)?;
+
+        let exponent = target_decimals as i64 - price_data.decimals as i64;
+        price_data.price = Self::scale(&env, price_data.price, 1, exponent);
+        price_data.confidence = Self::scale(&env, price_data.confidence, 1, exponent);
+        price_data.decimals = target_decimals;
+        Some(price_data)
+    }
+
+    /// Value of `amount` base tokens (with `amount_decimals` decimals) in
+    /// quote tokens with `target_decimals` decimals, rounded down
+    pub fn convert_amount(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        amount: i128,
+        amount_decimals: u32,
+        target_decimals: u32,
+    ) -> Option<i128> {
+        let price_data = Self::get_price(env.clone(), base_token, quote_token)?;
+
+        let exponent = target_decimals as i64 - amount_decimals as i64 - price_data.decimals as i64;
+        Some(Self::scale(&env, amount, price_data.price, exponent))
+    }
+}
+
+impl PriceOracle {
+    /// `a * b * 10^exponent`, rounded toward zero
+    ///
+    /// When scaling down, the product may exceed i128 as long as the
+    /// result does not.
+    fn scale(env: &Env, a: i128, b: i128, exponent: i64) -> i128 {
+        let factor = u32::try_from(exponent.unsigned_abs()).ok().and_then(pow10);
+
+        let scaled = match factor {
+            Some(factor) if exponent >= 0 => {
+                a.checked_mul(b).and_then(|value| value.checked_mul(factor))
+            }
+            Some(factor) => mul_div(env, a, b, factor),
+            // Shrinking by more than i128 can hold leaves nothing
+            None if exponent < 0 => a.checked_mul(b).map(|_| 0),
+            None => None,
+        };
+        scaled.unwrap_or_else(|| panic!("Arithmetic overflow"))
+    }
+}
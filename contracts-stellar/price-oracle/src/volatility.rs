@@ -0,0 +1,68 @@
+//! Rolling volatility of a pair.
+//!
+//! Volatility is the standard deviation of the returns between consecutive
+//! prices of the retained history, in basis points per update.
+
+use soroban_sdk::{contractimpl, Env, String, Vec};
+
+use stellhydra_math::{mul_div, sqrt, BPS};
+
+use crate::{PriceOracle, PriceOracleClient, TokenPair};
+
+// Returns are kept in millionths so the deviation survives rounding
+const RETURN_SCALE: i128 = 1_000_000;
+
+#[contractimpl]
+impl PriceOracle {
+    /// Standard deviation of the last `window` returns, in basis points
+    ///
+    /// None until the history holds `window + 1` prices.
+    pub fn get_volatility(
+        env: Env,
+        base_token: String,
+        quote_token: String,
+        window: u32,
+    ) -> Option<i128> {
+        if window < 2 {
+            panic!("Window must cover at least two returns");
+        }
+
+        let token_pair = TokenPair {
+            base: base_token,
+            quote: quote_token,
+        };
+
+        let (oldest, total) = Self::history_range(&env, &token_pair);
+        if total - oldest <= window as u64 {
+            return None;
+        }
+
+        let mut returns: Vec<i128> = Vec::new(&env);
+        let mut later = Self::history_entry(&env, &token_pair, total - 1)?;
+        for seq in (total - 1 - window as u64..total - 1).rev() {
+            let earlier = Self::history_entry(&env, &token_pair, seq)?;
+            let previous = Self::rescale(&env, earlier.price, earlier.decimals, later.decimals);
+            if previous <= 0 {
+                return None;
+            }
+
+            returns.push_back(mul_div(
+                &env,
+                later.price - previous,
+                RETURN_SCALE,
+                previous,
+            )?);
+            later = earlier;
+        }
+
+        let count = returns.len() as i128;
+        let mean = returns.iter().sum::<i128>() / count;
+        let mut variance: i128 = 0;
+        for value in returns.iter() {
+            let deviation = value - mean;
+            variance = variance.checked_add(deviation.checked_mul(deviation)? / count)?;
+        }
+
+        mul_div(&env, sqrt(variance)?, BPS, RETURN_SCALE)
+    }
+}
@@ -0,0 +1,381 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token::TokenInterface, Address, Env,
+    String, Symbol,
+};
+
+/// Metadata fixed when the token is deployed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Allowance of a spender, valid up to a ledger
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Allowance {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const METADATA: Symbol = symbol_short!("METADATA");
+const CAP: Symbol = symbol_short!("CAP");
+const SUPPLY: Symbol = symbol_short!("SUPPLY");
+const MINTER: Symbol = symbol_short!("MINTER");
+const BALANCE: Symbol = symbol_short!("BALANCE");
+const ALLOWANCE: Symbol = symbol_short!("ALLOWANCE");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// StellHydra protocol token
+///
+/// Supply can never exceed the cap set at deployment. Only minters the
+/// admin has granted, such as emissions or vesting contracts, can mint;
+/// burned tokens free room under the cap.
+#[contract]
+pub struct ProtocolToken;
+
+#[contractimpl]
+impl ProtocolToken {
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        name: String,
+        symbol: String,
+        decimals: u32,
+        cap: i128,
+    ) {
+        if env.storage().instance().has(&ADMIN) {
+            panic!("Already initialized");
+        }
+        if cap <= 0 {
+            panic!("Cap must be positive");
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(
+            &METADATA,
+            &TokenMetadata {
+                name,
+                symbol,
+                decimals,
+            },
+        );
+        env.storage().instance().set(&CAP, &cap);
+        env.storage().instance().set(&SUPPLY, &0i128);
+        Self::extend_instance(&env);
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&ADMIN).unwrap()
+    }
+
+    /// Hand the admin role over (only admin)
+    pub fn set_admin(env: Env, new_admin: Address) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        env.storage().instance().set(&ADMIN, &new_admin);
+
+        env.events()
+            .publish((symbol_short!("set_admin"), admin), new_admin);
+    }
+
+    /// Grant or revoke the minter role (only admin)
+    pub fn set_minter(env: Env, minter: Address, allowed: bool) {
+        Self::admin(env.clone()).require_auth();
+        let key = (MINTER, minter.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+            env.storage().persistent().extend_ttl(
+                &key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events()
+            .publish((symbol_short!("minter"), minter), allowed);
+    }
+
+    pub fn is_minter(env: Env, minter: Address) -> bool {
+        env.storage().persistent().has(&(MINTER, minter))
+    }
+
+    /// Mint tokens up to the cap (only minters)
+    pub fn mint(env: Env, minter: Address, to: Address, amount: i128) {
+        minter.require_auth();
+        if !Self::is_minter(env.clone(), minter.clone()) {
+            panic!("Not a minter");
+        }
+        Self::check_amount(amount);
+
+        let supply = Self::total_supply(env.clone()) + amount;
+        if supply > Self::cap(env.clone()) {
+            panic!("Cap exceeded");
+        }
+        env.storage().instance().set(&SUPPLY, &supply);
+        Self::add_balance(&env, &to, amount);
+        Self::extend_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("mint"), minter, to), amount);
+    }
+
+    pub fn cap(env: Env) -> i128 {
+        env.storage().instance().get(&CAP).unwrap()
+    }
+
+    pub fn total_supply(env: Env) -> i128 {
+        env.storage().instance().get(&SUPPLY).unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for ProtocolToken {}
+
+stellhydra_access::upgradeable!(ProtocolToken);
+
+#[contractimpl]
+impl TokenInterface for ProtocolToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        Self::read_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        if amount < 0 {
+            panic!("Negative amount");
+        }
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic!("Expiration ledger is in the past");
+        }
+
+        let key = (ALLOWANCE, from.clone(), spender.clone());
+        env.storage().temporary().set(
+            &key,
+            &Allowance {
+                amount,
+                expiration_ledger,
+            },
+        );
+        if amount > 0 {
+            let live_for = expiration_ledger - env.ledger().sequence();
+            env.storage()
+                .temporary()
+                .extend_ttl(&key, live_for, live_for);
+        }
+
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&(BALANCE, id)).unwrap_or(0)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_balance(&env, &from, amount);
+        Self::add_balance(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::spend_balance(&env, &from, amount);
+        Self::add_balance(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_balance(&env, &from, amount);
+        Self::reduce_supply(&env, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::spend_balance(&env, &from, amount);
+        Self::reduce_supply(&env, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        Self::metadata(&env).decimals
+    }
+
+    fn name(env: Env) -> String {
+        Self::metadata(&env).name
+    }
+
+    fn symbol(env: Env) -> String {
+        Self::metadata(&env).symbol
+    }
+}
+
+impl ProtocolToken {
+    fn metadata(env: &Env) -> TokenMetadata {
+        env.storage().instance().get(&METADATA).unwrap()
+    }
+
+    fn check_amount(amount: i128) {
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+    }
+
+    fn reduce_supply(env: &Env, amount: i128) {
+        let supply = Self::total_supply(env.clone());
+        env.storage().instance().set(&SUPPLY, &(supply - amount));
+    }
+
+    fn read_allowance(env: &Env, from: &Address, spender: &Address) -> Allowance {
+        let allowance: Option<Allowance> =
+            env.storage()
+                .temporary()
+                .get(&(ALLOWANCE, from.clone(), spender.clone()));
+        match allowance {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+            _ => Allowance {
+                amount: 0,
+                expiration_ledger: 0,
+            },
+        }
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let mut allowance = Self::read_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic!("Insufficient allowance");
+        }
+        allowance.amount -= amount;
+        env.storage()
+            .temporary()
+            .set(&(ALLOWANCE, from.clone(), spender.clone()), &allowance);
+    }
+
+    fn add_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        Self::write_balance(env, id, balance + amount);
+    }
+
+    fn spend_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        if balance < amount {
+            panic!("Insufficient balance");
+        }
+        Self::write_balance(env, id, balance - amount);
+    }
+
+    fn write_balance(env: &Env, id: &Address, balance: i128) {
+        let key = (BALANCE, id.clone());
+        env.storage().persistent().set(&key, &balance);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    fn setup(env: &Env) -> (ProtocolTokenClient<'_>, Address) {
+        env.mock_all_auths();
+
+        let token = ProtocolTokenClient::new(env, &env.register_contract(None, ProtocolToken));
+        token.initialize(
+            &Address::generate(env),
+            &String::from_str(env, "StellHydra"),
+            &String::from_str(env, "HYDRA"),
+            &7,
+            &1_000_000,
+        );
+        let minter = Address::generate(env);
+        token.set_minter(&minter, &true);
+
+        (token, minter)
+    }
+
+    #[test]
+    fn test_minting_stays_under_cap() {
+        let env = Env::default();
+        let (token, minter) = setup(&env);
+        let user = Address::generate(&env);
+
+        token.mint(&minter, &user, &600_000);
+        assert_eq!(token.total_supply(), 600_000);
+        assert!(token.try_mint(&minter, &user, &400_001).is_err());
+
+        // Burning frees room under the cap
+        token.burn(&user, &100_000);
+        token.mint(&minter, &user, &500_000);
+        assert_eq!(token.total_supply(), 1_000_000);
+        assert_eq!(token.balance(&user), 1_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not a minter")]
+    fn test_revoked_minter_cannot_mint() {
+        let env = Env::default();
+        let (token, minter) = setup(&env);
+
+        token.set_minter(&minter, &false);
+        token.mint(&minter, &Address::generate(&env), &1);
+    }
+
+    #[test]
+    fn test_transfers_follow_token_interface() {
+        let env = Env::default();
+        let (token, minter) = setup(&env);
+        let user = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let other = Address::generate(&env);
+
+        token.mint(&minter, &user, &1_000);
+        token.approve(&user, &spender, &600, &100);
+        token.transfer_from(&spender, &user, &other, &400);
+        token.burn_from(&spender, &user, &200);
+        assert_eq!(token.balance(&user), 400);
+        assert_eq!(token.balance(&other), 400);
+        assert_eq!(token.allowance(&user, &spender), 0);
+        assert_eq!(token.total_supply(), 800);
+        assert_eq!(token.symbol(), String::from_str(&env, "HYDRA"));
+    }
+}
@@ -0,0 +1,361 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+};
+
+/// Referral code registered by an integrator
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralCode {
+    pub code: Symbol,
+    pub owner: Address,
+    pub payout: Address,
+    pub share_bps: u32, // share of the protocol fee paid to the code
+}
+
+/// What a code brought in, per token
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CodeStats {
+    pub referrals: u64,
+    pub volume: i128,
+    pub earned: i128,
+    pub claimed: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidShare = 3,
+    CodeTaken = 4,
+    CodeNotFound = 5,
+    NotSource = 6,
+    InvalidAmount = 7,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const MAX_SHARE: Symbol = symbol_short!("MAX_SHARE");
+const SOURCE: Symbol = symbol_short!("SOURCE");
+const CODE: Symbol = symbol_short!("CODE");
+const STATS: Symbol = symbol_short!("STATS");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Referral registry shared by the protocol's fee-charging contracts
+///
+/// Integrators register a code once. Sources the admin has allowed,
+/// such as the bridge, send each code its share of the fees they charge
+/// and record it here; the code's owner claims the accrued amounts to
+/// its payout address.
+#[contract]
+pub struct Referral;
+
+#[contractimpl]
+impl Referral {
+    pub fn initialize(env: Env, admin: Address, max_share_bps: u32) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        if max_share_bps > 10000 {
+            return Err(Error::InvalidShare);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&MAX_SHARE, &max_share_bps);
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        Ok(())
+    }
+
+    /// Set the highest share a code may take (only admin)
+    pub fn set_max_share(env: Env, max_share_bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        if max_share_bps > 10000 {
+            return Err(Error::InvalidShare);
+        }
+        env.storage().instance().set(&MAX_SHARE, &max_share_bps);
+        Ok(())
+    }
+
+    pub fn get_max_share(env: Env) -> u32 {
+        env.storage().instance().get(&MAX_SHARE).unwrap_or(0)
+    }
+
+    /// Allow or disallow a contract to accrue referral fees (only admin)
+    pub fn set_source(env: Env, source: Address, allowed: bool) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let key = (SOURCE, source.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+            env.storage().persistent().extend_ttl(
+                &key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events().publish(
+            (symbol_short!("REFERRAL"), symbol_short!("SOURCE")),
+            (source, allowed),
+        );
+        Ok(())
+    }
+
+    pub fn is_source(env: Env, source: Address) -> bool {
+        env.storage().persistent().has(&(SOURCE, source))
+    }
+
+    /// Register a new code
+    pub fn register_code(
+        env: Env,
+        owner: Address,
+        code: Symbol,
+        payout: Address,
+        share_bps: u32,
+    ) -> Result<(), Error> {
+        owner.require_auth();
+        if Self::get_code(env.clone(), code.clone()).is_some() {
+            return Err(Error::CodeTaken);
+        }
+        Self::check_share(&env, share_bps)?;
+
+        Self::save_code(
+            &env,
+            &ReferralCode {
+                code: code.clone(),
+                owner: owner.clone(),
+                payout,
+                share_bps,
+            },
+        );
+
+        env.events().publish(
+            (symbol_short!("REFERRAL"), symbol_short!("CODE"), code),
+            (owner, share_bps),
+        );
+        Ok(())
+    }
+
+    /// Change a code's payout address and share (only its owner)
+    pub fn update_code(
+        env: Env,
+        code: Symbol,
+        payout: Address,
+        share_bps: u32,
+    ) -> Result<(), Error> {
+        let mut referral = Self::get_code(env.clone(), code.clone()).ok_or(Error::CodeNotFound)?;
+        referral.owner.require_auth();
+        Self::check_share(&env, share_bps)?;
+
+        referral.payout = payout;
+        referral.share_bps = share_bps;
+        Self::save_code(&env, &referral);
+
+        env.events().publish(
+            (symbol_short!("REFERRAL"), symbol_short!("UPDATED"), code),
+            share_bps,
+        );
+        Ok(())
+    }
+
+    pub fn get_code(env: Env, code: Symbol) -> Option<ReferralCode> {
+        env.storage().persistent().get(&(CODE, code))
+    }
+
+    /// Share of the fee owed to a code, zero for unknown codes
+    ///
+    /// Capped by the current maximum, so lowering it applies to existing
+    /// codes too.
+    pub fn get_share(env: Env, code: Symbol) -> u32 {
+        match Self::get_code(env.clone(), code) {
+            Some(referral) => referral.share_bps.min(Self::get_max_share(env)),
+            None => 0,
+        }
+    }
+
+    /// Record a referral from an allowed source
+    ///
+    /// The source transfers `amount` of `token` to the registry before
+    /// calling; `volume` is the size of the referred operation.
+    pub fn accrue(
+        env: Env,
+        source: Address,
+        code: Symbol,
+        token: Address,
+        volume: i128,
+        amount: i128,
+    ) -> Result<(), Error> {
+        source.require_auth();
+        if !Self::is_source(env.clone(), source.clone()) {
+            return Err(Error::NotSource);
+        }
+        if volume < 0 || amount < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if Self::get_code(env.clone(), code.clone()).is_none() {
+            return Err(Error::CodeNotFound);
+        }
+
+        let mut stats = Self::get_stats(env.clone(), code.clone(), token.clone());
+        stats.referrals += 1;
+        stats.volume += volume;
+        stats.earned += amount;
+        Self::save_stats(&env, &code, &token, &stats);
+
+        env.events().publish(
+            (symbol_short!("REFERRAL"), symbol_short!("ACCRUED"), code),
+            (source, token, volume, amount),
+        );
+        Ok(())
+    }
+
+    /// Pay what a code has accrued in `token` to its payout address
+    /// (only its owner)
+    pub fn claim(env: Env, code: Symbol, token: Address) -> Result<i128, Error> {
+        let referral = Self::get_code(env.clone(), code.clone()).ok_or(Error::CodeNotFound)?;
+        referral.owner.require_auth();
+
+        let mut stats = Self::get_stats(env.clone(), code.clone(), token.clone());
+        let amount = stats.earned - stats.claimed;
+        if amount > 0 {
+            stats.claimed = stats.earned;
+            Self::save_stats(&env, &code, &token, &stats);
+            token::Client::new(&env, &token).transfer(
+                &env.current_contract_address(),
+                &referral.payout,
+                &amount,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("REFERRAL"), symbol_short!("CLAIMED"), code),
+            (token, amount),
+        );
+        Ok(amount)
+    }
+
+    /// Accrued and not yet claimed by a code, in `token`
+    pub fn get_claimable(env: Env, code: Symbol, token: Address) -> i128 {
+        let stats = Self::get_stats(env, code, token);
+        stats.earned - stats.claimed
+    }
+
+    pub fn get_stats(env: Env, code: Symbol, token: Address) -> CodeStats {
+        env.storage()
+            .persistent()
+            .get(&(STATS, code, token))
+            .unwrap_or_default()
+    }
+}
+
+impl stellhydra_access::Upgradeable for Referral {}
+
+stellhydra_access::upgradeable!(Referral);
+
+impl Referral {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn check_share(env: &Env, share_bps: u32) -> Result<(), Error> {
+        if share_bps > Self::get_max_share(env.clone()) {
+            return Err(Error::InvalidShare);
+        }
+        Ok(())
+    }
+
+    fn save_code(env: &Env, referral: &ReferralCode) {
+        let key = (CODE, referral.code.clone());
+        env.storage().persistent().set(&key, referral);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn save_stats(env: &Env, code: &Symbol, token: &Address, stats: &CodeStats) {
+        let key = (STATS, code.clone(), token.clone());
+        env.storage().persistent().set(&key, stats);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Env};
+
+    #[test]
+    fn test_codes_accrue_and_claim() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let registry = ReferralClient::new(&env, &env.register_contract(None, Referral));
+        registry.initialize(&Address::generate(&env), &3_000);
+        let source = Address::generate(&env);
+        registry.set_source(&source, &true);
+
+        let owner = Address::generate(&env);
+        let payout = Address::generate(&env);
+        let code = symbol_short!("wallet");
+        assert_eq!(
+            registry.try_register_code(&owner, &code, &payout, &5_000),
+            Err(Ok(Error::InvalidShare))
+        );
+        registry.register_code(&owner, &code, &payout, &2_000);
+        assert_eq!(
+            registry.try_register_code(&Address::generate(&env), &code, &payout, &2_000),
+            Err(Ok(Error::CodeTaken))
+        );
+        assert_eq!(registry.get_share(&code), 2_000);
+        assert_eq!(registry.get_share(&symbol_short!("unknown")), 0);
+
+        StellarAssetClient::new(&env, &token).mint(&registry.address, &30);
+        registry.accrue(&source, &code, &token, &1_000, &10);
+        registry.accrue(&source, &code, &token, &2_000, &20);
+        assert_eq!(
+            registry.try_accrue(&Address::generate(&env), &code, &token, &1, &1),
+            Err(Ok(Error::NotSource))
+        );
+
+        let stats = registry.get_stats(&code, &token);
+        assert_eq!(
+            (stats.referrals, stats.volume, stats.earned),
+            (2, 3_000, 30)
+        );
+        assert_eq!(registry.claim(&code, &token), 30);
+        assert_eq!(token::Client::new(&env, &token).balance(&payout), 30);
+        assert_eq!(registry.get_claimable(&code, &token), 0);
+
+        // Lowering the maximum caps existing codes
+        registry.set_max_share(&1_000);
+        assert_eq!(registry.get_share(&code), 1_000);
+    }
+}
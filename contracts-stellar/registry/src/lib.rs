@@ -0,0 +1,235 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Symbol, Vec,
+};
+use stellhydra_access::{ownable, require_admin};
+
+/// One registered deployment of a contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub version: u32,
+    pub address: Address,
+    pub registered_at: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    UnknownName = 3,
+    UnknownVersion = 4,
+    TooManyNames = 5,
+}
+
+// Storage keys
+const NETWORK: Symbol = symbol_short!("NETWORK");
+const NAMES: Symbol = symbol_short!("NAMES");
+const ENTRY: Symbol = symbol_short!("ENTRY");
+const LATEST: Symbol = symbol_short!("LATEST");
+const CURRENT: Symbol = symbol_short!("CURRENT");
+
+/// Most contract names the registry holds
+pub const MAX_NAMES: u32 = 100;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Address book of the contracts deployed on one network
+///
+/// Each network runs its own registry. The admin, usually the CLI's
+/// deployer, registers every deployment of a contract under its name, and
+/// each deployment gets the next version number. The newest version is
+/// the current one unless the admin points the name back at an older one.
+/// Contracts and off-chain tools `resolve` names here instead of keeping
+/// addresses that go stale when a network is redeployed.
+#[contract]
+pub struct Registry;
+
+#[contractimpl]
+impl Registry {
+    /// Set the admin and the network this registry describes
+    pub fn initialize(env: Env, admin: Address, network: Symbol) -> Result<(), Error> {
+        if env.storage().instance().has(&NETWORK) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+        env.storage().instance().set(&NETWORK, &network);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_network(env: Env) -> Result<Symbol, Error> {
+        env.storage()
+            .instance()
+            .get(&NETWORK)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Record a new deployment of `name`, making it current, and return
+    /// its version (only admin)
+    pub fn register(env: Env, name: Symbol, address: Address) -> Result<u32, Error> {
+        require_admin(&env);
+
+        let mut names = Self::get_names(env.clone());
+        if !names.contains(&name) {
+            if names.len() >= MAX_NAMES {
+                return Err(Error::TooManyNames);
+            }
+            names.push_back(name.clone());
+            env.storage().instance().set(&NAMES, &names);
+        }
+
+        let version = Self::get_latest_version(env.clone(), name.clone()) + 1;
+        let entry = Entry {
+            version,
+            address: address.clone(),
+            registered_at: env.ledger().timestamp(),
+        };
+        Self::save(&env, &(ENTRY, name.clone(), version), &entry);
+        Self::save(&env, &(LATEST, name.clone()), &version);
+        Self::save(&env, &(CURRENT, name.clone()), &version);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("REGISTRY"), symbol_short!("SET"), name),
+            (version, address),
+        );
+        Ok(version)
+    }
+
+    /// Point `name` at an earlier or later registered version (only admin)
+    pub fn set_current(env: Env, name: Symbol, version: u32) -> Result<(), Error> {
+        require_admin(&env);
+        let entry = Self::get_entry(env.clone(), name.clone(), version)?;
+        Self::save(&env, &(CURRENT, name.clone()), &version);
+
+        env.events().publish(
+            (symbol_short!("REGISTRY"), symbol_short!("SET"), name),
+            (version, entry.address),
+        );
+        Ok(())
+    }
+
+    /// Current address of `name`
+    pub fn resolve(env: Env, name: Symbol) -> Option<Address> {
+        Self::get_current(env, name).ok().map(|entry| entry.address)
+    }
+
+    /// Current deployment of `name`
+    pub fn get_current(env: Env, name: Symbol) -> Result<Entry, Error> {
+        let version: u32 = env
+            .storage()
+            .persistent()
+            .get(&(CURRENT, name.clone()))
+            .ok_or(Error::UnknownName)?;
+        Self::get_entry(env, name, version)
+    }
+
+    pub fn get_entry(env: Env, name: Symbol, version: u32) -> Result<Entry, Error> {
+        env.storage()
+            .persistent()
+            .get(&(ENTRY, name, version))
+            .ok_or(Error::UnknownVersion)
+    }
+
+    /// Newest version registered for `name`, or 0 if none
+    pub fn get_latest_version(env: Env, name: Symbol) -> u32 {
+        env.storage().persistent().get(&(LATEST, name)).unwrap_or(0)
+    }
+
+    pub fn get_names(env: Env) -> Vec<Symbol> {
+        env.storage()
+            .instance()
+            .get(&NAMES)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+}
+
+impl Registry {
+    fn save<K, V>(env: &Env, key: &K, value: &V)
+    where
+        K: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+        V: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+    {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+impl stellhydra_access::Upgradeable for Registry {}
+
+stellhydra_access::ownable!(Registry);
+stellhydra_access::upgradeable!(Registry);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    fn setup(env: &Env) -> RegistryClient<'_> {
+        env.mock_all_auths();
+        let registry = RegistryClient::new(env, &env.register_contract(None, Registry));
+        registry.initialize(&Address::generate(env), &symbol_short!("testnet"));
+        registry
+    }
+
+    #[test]
+    fn test_redeployments_get_new_versions() {
+        let env = Env::default();
+        let registry = setup(&env);
+        let bridge = symbol_short!("bridge");
+        let (first, second) = (Address::generate(&env), Address::generate(&env));
+
+        assert_eq!(registry.get_network(), symbol_short!("testnet"));
+        assert_eq!(registry.resolve(&bridge), None);
+        assert_eq!(registry.register(&bridge, &first), 1);
+        assert_eq!(registry.register(&bridge, &second), 2);
+        assert_eq!(registry.resolve(&bridge), Some(second.clone()));
+        assert_eq!(registry.get_entry(&bridge, &1).address, first);
+        assert_eq!(registry.get_names().len(), 1);
+
+        // Rolling back keeps the newer version on record
+        registry.set_current(&bridge, &1);
+        assert_eq!(registry.resolve(&bridge), Some(first));
+        assert_eq!(registry.get_latest_version(&bridge), 2);
+        assert_eq!(
+            registry.try_set_current(&bridge, &3),
+            Err(Ok(Error::UnknownVersion))
+        );
+        assert_eq!(
+            registry.try_get_current(&symbol_short!("router")),
+            Err(Ok(Error::UnknownName))
+        );
+    }
+
+    #[test]
+    fn test_only_admin_registers() {
+        let env = Env::default();
+        let registry = setup(&env);
+        env.mock_auths(&[]);
+
+        assert!(registry
+            .try_register(&symbol_short!("bridge"), &Address::generate(&env))
+            .is_err());
+    }
+}
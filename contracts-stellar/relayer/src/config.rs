@@ -0,0 +1,216 @@
+//! Relayer configuration, read from a TOML file
+//!
+//! Keys are never stored in the file: it names the environment variables
+//! holding them.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use stellhydra_rpc::Client;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub stellar: StellarConfig,
+    pub evm: EvmConfig,
+    #[serde(default)]
+    pub queue: QueueConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// Stellar tokens the relayer delivers, and their EVM counterparts
+    #[serde(default, rename = "asset")]
+    pub assets: Vec<AssetRoute>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StellarConfig {
+    pub rpc_url: String,
+    pub network_passphrase: String,
+    /// Deployment registry contract id (C...), to look up the contracts
+    /// left out below
+    pub registry: Option<String>,
+    /// Bridge contract id (C...)
+    #[serde(default)]
+    pub bridge: String,
+    /// HTLC escrow contract id (C...)
+    #[serde(default)]
+    pub escrow: String,
+    #[serde(default = "default_stellar_key_env")]
+    pub secret_key_env: String,
+    /// Ledger to start watching from on first run; the latest by default
+    pub start_ledger: Option<u32>,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Inclusion fee per transaction, in stroops
+    #[serde(default = "default_base_fee")]
+    pub base_fee: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EvmConfig {
+    pub rpc_url: String,
+    pub chain_id: u64,
+    /// Destination chain symbol of the bridge requests this chain serves
+    pub chain: String,
+    /// HTLC escrow contract address (0x...)
+    pub escrow: String,
+    #[serde(default = "default_evm_key_env")]
+    pub private_key_env: String,
+    /// Block to start watching from on first run; the latest by default
+    pub start_block: Option<u64>,
+    /// Blocks a transaction or log must be buried under before it counts
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssetRoute {
+    /// Stellar asset contract id (C...)
+    pub stellar_token: String,
+    /// EVM token address, or the zero address for the native asset
+    pub evm_asset: String,
+    pub stellar_decimals: u32,
+    pub evm_decimals: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueConfig {
+    /// File the job queue and watch cursors are kept in
+    pub path: PathBuf,
+    /// Attempts before a job is abandoned for an operator to look at
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on each failure
+    pub backoff_secs: u64,
+    pub max_backoff_secs: u64,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            path: PathBuf::from("relayer-state.json"),
+            max_attempts: 10,
+            backoff_secs: 15,
+            max_backoff_secs: 900,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsConfig {
+    /// Address serving Prometheus metrics
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            listen: "127.0.0.1:9187".into(),
+        }
+    }
+}
+
+fn default_stellar_key_env() -> String {
+    "STELLAR_SECRET_KEY".into()
+}
+
+fn default_evm_key_env() -> String {
+    "EVM_PRIVATE_KEY".into()
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+fn default_base_fee() -> u32 {
+    100
+}
+
+fn default_confirmations() -> u64 {
+    3
+}
+
+fn default_gas_limit() -> u64 {
+    200_000
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn route(&self, stellar_token: &str) -> Option<&AssetRoute> {
+        self.assets
+            .iter()
+            .find(|route| route.stellar_token == stellar_token)
+    }
+}
+
+impl AssetRoute {
+    /// EVM amount for a Stellar amount, if it converts exactly
+    pub fn evm_amount(&self, amount: i128) -> Option<u128> {
+        let amount = u128::try_from(amount).ok()?;
+        if self.evm_decimals >= self.stellar_decimals {
+            amount.checked_mul(10u128.checked_pow(self.evm_decimals - self.stellar_decimals)?)
+        } else {
+            let scale = 10u128.checked_pow(self.stellar_decimals - self.evm_decimals)?;
+            (amount % scale == 0).then_some(amount / scale)
+        }
+    }
+}
+
+impl StellarConfig {
+    /// Look up the contracts the config leaves out in the registry
+    pub async fn resolve_contracts(&mut self, client: &Client) -> Result<()> {
+        for (name, id) in [("bridge", &mut self.bridge), ("escrow", &mut self.escrow)] {
+            if !id.is_empty() {
+                continue;
+            }
+            let registry = self
+                .registry
+                .as_deref()
+                .with_context(|| format!("No {name} contract or registry configured"))?;
+            *id = client.resolve(registry, name).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a key from the environment variable the config names
+pub fn secret(variable: &str) -> Result<String> {
+    std::env::var(variable).with_context(|| format!("{variable} is not set"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example_config_parses() {
+        let config: Config = toml::from_str(include_str!("../relayer.example.toml")).unwrap();
+        assert_eq!(config.evm.chain, "ETH");
+        assert_eq!(config.queue.max_attempts, 10);
+
+        let route = &config.assets[0];
+        assert!(config.route(&route.stellar_token).is_some());
+        assert_eq!(route.evm_amount(1), Some(100_000_000_000));
+        assert_eq!(route.evm_amount(-1), None);
+    }
+
+    #[test]
+    fn test_evm_amount_refuses_lossy_conversions() {
+        let route = AssetRoute {
+            stellar_token: String::new(),
+            evm_asset: String::new(),
+            stellar_decimals: 7,
+            evm_decimals: 6,
+        };
+        assert_eq!(route.evm_amount(1_230), Some(123));
+        assert_eq!(route.evm_amount(1_234), None);
+    }
+}
@@ -0,0 +1,72 @@
+//! Calldata for the few contract functions the relayer calls
+//!
+//! Only static types are encoded, each as one 32-byte word.
+
+use sha3::{Digest, Keccak256};
+
+pub type Word = [u8; 32];
+
+pub fn keccak(data: &[u8]) -> Word {
+    Keccak256::digest(data).into()
+}
+
+/// First four bytes of the hash of a function signature
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+pub fn call(signature: &str, args: &[Word]) -> Vec<u8> {
+    let mut data = selector(signature).to_vec();
+    for arg in args {
+        data.extend_from_slice(arg);
+    }
+    data
+}
+
+pub fn uint(value: u128) -> Word {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub fn address(address: &[u8; 20]) -> Word {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+/// Word `index` of ABI-encoded return data
+pub fn word(data: &[u8], index: usize) -> Option<Word> {
+    data.get(index * 32..(index + 1) * 32)?.try_into().ok()
+}
+
+/// Unsigned integer in a word, if it fits in a u128
+pub fn to_uint(word: &Word) -> Option<u128> {
+    if word[..16].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u128::from_be_bytes(word[16..].try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encodes_erc20_transfer() {
+        assert_eq!(
+            hex::encode(selector("transfer(address,uint256)")),
+            "a9059cbb"
+        );
+
+        let data = call(
+            "transfer(address,uint256)",
+            &[address(&[0x11; 20]), uint(1_000)],
+        );
+        assert_eq!(data.len(), 4 + 64);
+        assert_eq!(to_uint(&word(&data[4..], 1).unwrap()), Some(1_000));
+        assert_eq!(&word(&data[4..], 0).unwrap()[12..], &[0x11; 20]);
+        assert_eq!(word(&data[4..], 2), None);
+    }
+}
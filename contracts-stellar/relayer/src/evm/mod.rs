@@ -0,0 +1,331 @@
+//! EVM side: JSON-RPC client, signing, and the escrow contract's calls
+
+pub mod abi;
+mod rlp;
+
+use anyhow::{anyhow, bail, Context, Result};
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::EvmConfig;
+use abi::Word;
+use rlp::Item;
+
+pub type EvmAddress = [u8; 20];
+
+/// Zero address, standing for the chain's native asset
+pub const NATIVE: EvmAddress = [0; 20];
+
+/// Signed transaction, kept by jobs so a retry rebroadcasts the same
+/// transaction instead of paying twice
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignedTx {
+    pub raw: String,
+    pub hash: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Receipt {
+    pub success: bool,
+    pub block: u64,
+}
+
+/// What the relayer reads of an escrow on the EVM side
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvmEscrow {
+    pub hash_lock: Word,
+    /// 0: pending, 1: locked, 2: completed, 3: refunded
+    pub status: u8,
+}
+
+/// Legacy transaction fields before signing
+pub struct TxRequest<'a> {
+    pub nonce: u64,
+    pub gas_price: u128,
+    pub gas_limit: u64,
+    pub to: &'a EvmAddress,
+    pub value: u128,
+    pub data: &'a [u8],
+}
+
+/// Key signing EIP-155 transactions
+pub struct Wallet {
+    key: SigningKey,
+    address: EvmAddress,
+}
+
+impl Wallet {
+    pub fn from_hex(key: &str) -> Result<Self> {
+        let bytes = hex::decode(key.trim_start_matches("0x")).context("EVM key is not hex")?;
+        let key = SigningKey::from_slice(&bytes).context("Invalid EVM key")?;
+        let point = key.verifying_key().to_encoded_point(false);
+        let hash = abi::keccak(&point.as_bytes()[1..]);
+        let address = hash[12..].try_into().unwrap();
+        Ok(Wallet { key, address })
+    }
+
+    pub fn address(&self) -> &EvmAddress {
+        &self.address
+    }
+
+    pub fn sign(&self, tx: &TxRequest, chain_id: u64) -> Result<SignedTx> {
+        let fields = |v: Item<'static>, r: Vec<u8>, s: Vec<u8>| -> Vec<u8> {
+            rlp::encode(&Item::List(vec![
+                Item::Uint(tx.nonce as u128),
+                Item::Uint(tx.gas_price),
+                Item::Uint(tx.gas_limit as u128),
+                Item::Bytes(tx.to),
+                Item::Uint(tx.value),
+                Item::Bytes(tx.data),
+                v,
+                Item::Bytes(trim(&r)),
+                Item::Bytes(trim(&s)),
+            ]))
+        };
+
+        let unsigned = fields(Item::Uint(chain_id as u128), vec![], vec![]);
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&abi::keccak(&unsigned))
+            .context("Signing failed")?;
+        let v = recovery_id.to_byte() as u128 + chain_id as u128 * 2 + 35;
+        let raw = fields(
+            Item::Uint(v),
+            signature.r().to_bytes().to_vec(),
+            signature.s().to_bytes().to_vec(),
+        );
+
+        Ok(SignedTx {
+            hash: format!("0x{}", hex::encode(abi::keccak(&raw))),
+            raw: format!("0x{}", hex::encode(raw)),
+        })
+    }
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    &bytes[skip..]
+}
+
+pub fn parse_address(address: &str) -> Result<EvmAddress> {
+    let bytes = hex::decode(address.trim_start_matches("0x"))
+        .with_context(|| format!("Invalid EVM address {address}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Invalid EVM address {address}"))
+}
+
+pub fn parse_word(word: &str) -> Result<Word> {
+    let bytes = hex::decode(word.trim_start_matches("0x"))?;
+    bytes.try_into().map_err(|_| anyhow!("Expected 32 bytes"))
+}
+
+fn quantity(value: &Value) -> Result<u128> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| anyhow!("Expected a quantity"))?;
+    Ok(u128::from_str_radix(text.trim_start_matches("0x"), 16)?)
+}
+
+fn hex_quantity(value: u64) -> String {
+    format!("0x{value:x}")
+}
+
+pub struct EvmClient {
+    http: reqwest::Client,
+    url: String,
+    chain_id: u64,
+    gas_limit: u64,
+    escrow: EvmAddress,
+    wallet: Wallet,
+}
+
+impl EvmClient {
+    pub fn new(config: &EvmConfig, wallet: Wallet) -> Result<Self> {
+        Ok(EvmClient {
+            http: reqwest::Client::new(),
+            url: config.rpc_url.clone(),
+            chain_id: config.chain_id,
+            gas_limit: config.gas_limit,
+            escrow: parse_address(&config.escrow)?,
+            wallet,
+        })
+    }
+
+    async fn rpc(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            bail!(
+                "{method} failed: {}",
+                error["message"].as_str().unwrap_or("unknown error")
+            );
+        }
+        Ok(response["result"].clone())
+    }
+
+    pub async fn block_number(&self) -> Result<u64> {
+        Ok(quantity(&self.rpc("eth_blockNumber", json!([])).await?)? as u64)
+    }
+
+    /// Ids of the escrows created in a block range
+    pub async fn created_escrows(&self, from: u64, to: u64) -> Result<Vec<Word>> {
+        let topic = abi::keccak(b"EscrowCreated(bytes32,address,uint256,address)");
+        let logs = self
+            .rpc(
+                "eth_getLogs",
+                json!([{
+                    "fromBlock": hex_quantity(from),
+                    "toBlock": hex_quantity(to),
+                    "address": format!("0x{}", hex::encode(self.escrow)),
+                    "topics": [format!("0x{}", hex::encode(topic))],
+                }]),
+            )
+            .await?;
+
+        logs.as_array()
+            .ok_or_else(|| anyhow!("Expected a list of logs"))?
+            .iter()
+            .map(|log| parse_word(log["topics"][1].as_str().unwrap_or_default()))
+            .collect()
+    }
+
+    pub async fn escrow(&self, escrow_id: &Word) -> Result<Option<EvmEscrow>> {
+        let data = abi::call("getEscrow(bytes32)", &[*escrow_id]);
+        let result = self
+            .rpc(
+                "eth_call",
+                json!([{
+                    "to": format!("0x{}", hex::encode(self.escrow)),
+                    "data": format!("0x{}", hex::encode(data)),
+                }, "latest"]),
+            )
+            .await?;
+        let result = hex::decode(result.as_str().unwrap_or_default().trim_start_matches("0x"))?;
+
+        // EscrowData is static: exists, maker, amount, asset, hashLock,
+        // timeLock, status, secret, createdAt
+        let field = |index| abi::word(&result, index).ok_or_else(|| anyhow!("Short escrow data"));
+        if abi::to_uint(&field(0)?) != Some(1) {
+            return Ok(None);
+        }
+        Ok(Some(EvmEscrow {
+            hash_lock: field(4)?,
+            status: abi::to_uint(&field(6)?).unwrap_or_default() as u8,
+        }))
+    }
+
+    /// Sign a payment of `amount` of `asset` ([`NATIVE`] for the chain's
+    /// own asset) to `to`
+    pub async fn sign_transfer(
+        &self,
+        asset: &EvmAddress,
+        to: &EvmAddress,
+        amount: u128,
+    ) -> Result<SignedTx> {
+        if *asset == NATIVE {
+            return self.sign_call(to, amount, &[]).await;
+        }
+        let data = abi::call(
+            "transfer(address,uint256)",
+            &[abi::address(to), abi::uint(amount)],
+        );
+        self.sign_call(asset, 0, &data).await
+    }
+
+    pub async fn sign_lock_escrow(&self, escrow_id: &Word) -> Result<SignedTx> {
+        let data = abi::call("lockEscrow(bytes32)", &[*escrow_id]);
+        self.sign_call(&self.escrow, 0, &data).await
+    }
+
+    pub async fn sign_complete_escrow(&self, escrow_id: &Word, secret: &Word) -> Result<SignedTx> {
+        let data = abi::call("completeEscrow(bytes32,bytes32)", &[*escrow_id, *secret]);
+        self.sign_call(&self.escrow, 0, &data).await
+    }
+
+    async fn sign_call(&self, to: &EvmAddress, value: u128, data: &[u8]) -> Result<SignedTx> {
+        let from = format!("0x{}", hex::encode(self.wallet.address()));
+        let nonce = quantity(
+            &self
+                .rpc("eth_getTransactionCount", json!([from, "pending"]))
+                .await?,
+        )? as u64;
+        let gas_price = quantity(&self.rpc("eth_gasPrice", json!([])).await?)?;
+
+        self.wallet.sign(
+            &TxRequest {
+                nonce,
+                gas_price,
+                gas_limit: self.gas_limit,
+                to,
+                value,
+                data,
+            },
+            self.chain_id,
+        )
+    }
+
+    /// Broadcast a signed transaction; broadcasting it again is harmless
+    pub async fn broadcast(&self, tx: &SignedTx) -> Result<()> {
+        match self.rpc("eth_sendRawTransaction", json!([tx.raw])).await {
+            Ok(_) => Ok(()),
+            Err(error) if error.to_string().contains("already known") => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub async fn receipt(&self, hash: &str) -> Result<Option<Receipt>> {
+        let receipt = self.rpc("eth_getTransactionReceipt", json!([hash])).await?;
+        if receipt.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(Receipt {
+            success: quantity(&receipt["status"])? == 1,
+            block: quantity(&receipt["blockNumber"])? as u64,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signs_eip155_example() {
+        // The example transaction from EIP-155
+        let wallet = Wallet::from_hex(&"46".repeat(32)).unwrap();
+        let tx = wallet
+            .sign(
+                &TxRequest {
+                    nonce: 9,
+                    gas_price: 20_000_000_000,
+                    gas_limit: 21_000,
+                    to: &[0x35; 20],
+                    value: 1_000_000_000_000_000_000,
+                    data: &[],
+                },
+                1,
+            )
+            .unwrap();
+
+        assert_eq!(
+            tx.raw,
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7\
+             6400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067\
+             cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        assert_eq!(
+            hex::encode(wallet.address()),
+            "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Recursive length prefix encoding, as far as transactions need it
+
+/// Item to encode: a byte string or a list of items
+pub enum Item<'a> {
+    Bytes(&'a [u8]),
+    Uint(u128),
+    List(Vec<Item<'a>>),
+}
+
+pub fn encode(item: &Item) -> Vec<u8> {
+    match item {
+        Item::Bytes(bytes) => encode_bytes(bytes),
+        Item::Uint(value) => encode_bytes(&minimal_be(*value)),
+        Item::List(items) => {
+            let payload: Vec<u8> = items.iter().flat_map(|item| encode(item)).collect();
+            let mut out = prefix(0xc0, payload.len());
+            out.extend(payload);
+            out
+        }
+    }
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = prefix(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn prefix(offset: u8, len: usize) -> Vec<u8> {
+    if len <= 55 {
+        return vec![offset + len as u8];
+    }
+    let len = minimal_be(len as u128);
+    let mut out = vec![offset + 55 + len.len() as u8];
+    out.extend(len);
+    out
+}
+
+/// Big-endian bytes without leading zeros; zero is the empty string
+fn minimal_be(value: u128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    bytes[skip..].to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encodes_reference_vectors() {
+        assert_eq!(
+            encode(&Item::Bytes(b"dog")),
+            hex::decode("83646f67").unwrap()
+        );
+        assert_eq!(
+            encode(&Item::List(vec![Item::Bytes(b"cat"), Item::Bytes(b"dog")])),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+        assert_eq!(encode(&Item::Bytes(b"")), vec![0x80]);
+        assert_eq!(encode(&Item::List(vec![])), vec![0xc0]);
+        assert_eq!(encode(&Item::Uint(0)), vec![0x80]);
+        assert_eq!(encode(&Item::Uint(15)), vec![0x0f]);
+        assert_eq!(encode(&Item::Uint(1024)), vec![0x82, 0x04, 0x00]);
+
+        let long = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let mut expected = vec![0xb8, 0x38];
+        expected.extend_from_slice(long);
+        assert_eq!(encode(&Item::Bytes(long)), expected);
+    }
+}
@@ -0,0 +1,104 @@
+//! StellHydra relayer
+//!
+//! Watches the bridge and HTLC escrow contracts through Soroban RPC and
+//! carries their work over to an EVM chain:
+//!
+//! - a bridge request to the configured chain is paid out there, then
+//!   reported complete on Stellar with the EVM transaction hash
+//! - a secret revealed by an HTLC completes the EVM escrow locked under
+//!   the same hash
+//!
+//! Work goes through a persistent queue with retries, and progress is
+//! exported as Prometheus metrics.
+//!
+//! ```text
+//! stellhydra-relayer [config.toml]
+//! ```
+
+mod config;
+mod evm;
+mod metrics;
+mod queue;
+mod watcher;
+mod worker;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use stellhydra_rpc::Client;
+use tokio::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+use crate::evm::{EvmClient, Wallet};
+use crate::metrics::Metrics;
+use crate::queue::Queue;
+
+/// State shared by the watchers and the worker
+struct Relayer {
+    config: Config,
+    stellar: Client,
+    evm: EvmClient,
+    queue: Mutex<Queue>,
+    metrics: Metrics,
+}
+
+/// Unix time, in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("relayer.toml"));
+    let mut config = Config::load(&path)?;
+
+    let stellar = Client::new(
+        &config.stellar.rpc_url,
+        &config.stellar.network_passphrase,
+        config.stellar.base_fee,
+    )
+    .with_secret_key(&config::secret(&config.stellar.secret_key_env)?)?;
+    config.stellar.resolve_contracts(&stellar).await?;
+    tracing::info!(
+        "Bridge {}, escrow {}",
+        config.stellar.bridge,
+        config.stellar.escrow
+    );
+    let wallet = Wallet::from_hex(&config::secret(&config.evm.private_key_env)?)?;
+    tracing::info!("EVM account 0x{}", hex::encode(wallet.address()));
+    let evm = EvmClient::new(&config.evm, wallet)?;
+    let queue = Queue::open(std::mem::take(&mut config.queue))?;
+    tracing::info!("Queue at {}", queue.path().display());
+
+    let relayer = Relayer {
+        config,
+        stellar,
+        evm,
+        queue: Mutex::new(queue),
+        metrics: Metrics::default(),
+    };
+
+    // Every task loops until shutdown, logging and retrying its own errors
+    tokio::select! {
+        _ = watcher::watch_stellar(&relayer) => {}
+        _ = watcher::watch_evm(&relayer) => {}
+        _ = worker::run(&relayer) => {}
+        result = metrics::serve(&relayer.config.metrics.listen, &relayer.metrics) => result?,
+        _ = tokio::signal::ctrl_c() => tracing::info!("Shutting down"),
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Prometheus metrics, served as plain text over HTTP
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(Default)]
+pub struct Metrics {
+    pub stellar_events: AtomicU64,
+    pub evm_escrows: AtomicU64,
+    pub jobs_enqueued: AtomicU64,
+    pub jobs_completed: AtomicU64,
+    pub jobs_failed: AtomicU64,
+    pub jobs_abandoned: AtomicU64,
+    pub stellar_ledger: AtomicU64,
+    pub evm_block: AtomicU64,
+    pub queue_pending: AtomicU64,
+}
+
+impl Metrics {
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set(gauge: &AtomicU64, value: u64) {
+        gauge.store(value, Ordering::Relaxed);
+    }
+
+    /// Metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "stellar_events_total",
+                "counter",
+                "Bridge and escrow events processed",
+                &self.stellar_events,
+            ),
+            (
+                "evm_escrows_total",
+                "counter",
+                "EVM escrows indexed",
+                &self.evm_escrows,
+            ),
+            (
+                "jobs_enqueued_total",
+                "counter",
+                "Jobs queued",
+                &self.jobs_enqueued,
+            ),
+            (
+                "jobs_completed_total",
+                "counter",
+                "Jobs completed",
+                &self.jobs_completed,
+            ),
+            (
+                "jobs_failed_total",
+                "counter",
+                "Failed job attempts",
+                &self.jobs_failed,
+            ),
+            (
+                "jobs_abandoned_total",
+                "counter",
+                "Jobs out of attempts",
+                &self.jobs_abandoned,
+            ),
+            (
+                "stellar_ledger",
+                "gauge",
+                "Latest Stellar ledger seen",
+                &self.stellar_ledger,
+            ),
+            (
+                "evm_block",
+                "gauge",
+                "Next EVM block to scan",
+                &self.evm_block,
+            ),
+            (
+                "queue_pending",
+                "gauge",
+                "Jobs waiting in the queue",
+                &self.queue_pending,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP stellhydra_relayer_{name} {help}");
+            let _ = writeln!(out, "# TYPE stellhydra_relayer_{name} {kind}");
+            let _ = writeln!(
+                out,
+                "stellhydra_relayer_{name} {}",
+                value.load(Ordering::Relaxed)
+            );
+        }
+        out
+    }
+}
+
+/// Answer every connection with the metrics, whatever the path
+pub async fn serve(listen: &str, metrics: &Metrics) -> Result<()> {
+    let listener = TcpListener::bind(listen).await?;
+    tracing::info!("Serving metrics on {listen}");
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request = [0; 1024];
+        // The request itself doesn't matter; read it so the client sees a
+        // clean response
+        let _ = stream.read(&mut request).await;
+
+        let body = metrics.render();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_renders_prometheus_text() {
+        let metrics = Metrics::default();
+        Metrics::inc(&metrics.jobs_completed);
+        Metrics::set(&metrics.evm_block, 42);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE stellhydra_relayer_jobs_completed_total counter\n"));
+        assert!(text.contains("stellhydra_relayer_jobs_completed_total 1\n"));
+        assert!(text.contains("stellhydra_relayer_evm_block 42\n"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Persistent job queue
+//!
+//! Jobs, the watchers' cursors and the index of EVM escrows live in one
+//! JSON file, rewritten atomically after every change. A job is keyed by
+//! what it acts on, so an event seen twice (after a restart, or by
+//! overlapping polls) doesn't queue the work twice.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::QueueConfig;
+use crate::evm::SignedTx;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Job {
+    /// Pay out a bridge request on the EVM chain, then report it complete
+    DeliverRequest {
+        request_id: u64,
+        /// Stellar token locked by the request
+        token: String,
+        /// Stellar amount locked, net of fees
+        amount: i128,
+        /// Hex EVM recipient
+        recipient: String,
+    },
+    /// Complete the EVM escrow sharing a Stellar HTLC's hash lock with the
+    /// secret the HTLC revealed
+    RelaySecret {
+        /// Hex sha256 of the secret
+        hash_lock: String,
+        /// Hex secret
+        secret: String,
+    },
+}
+
+impl Job {
+    pub fn key(&self) -> String {
+        match self {
+            Job::DeliverRequest { request_id, .. } => format!("request:{request_id}"),
+            Job::RelaySecret { hash_lock, .. } => format!("secret:{hash_lock}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Pending,
+    Done,
+    /// Out of attempts; left for an operator
+    Abandoned,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub job: Job,
+    pub state: JobState,
+    pub attempts: u32,
+    /// Unix time of the next attempt
+    pub next_attempt: u64,
+    pub last_error: Option<String>,
+    /// EVM transaction signed for the job and not yet confirmed
+    pub pending_tx: Option<SignedTx>,
+}
+
+/// Where the watchers resume
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cursors {
+    /// RPC paging token of the last Stellar event processed
+    pub stellar_event: Option<String>,
+    /// Ledger to start from while no event has been processed
+    pub stellar_ledger: Option<u32>,
+    /// Next EVM block to scan
+    pub evm_block: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    cursors: Cursors,
+    /// EVM escrow ids by hex hash lock
+    escrows: BTreeMap<String, String>,
+    jobs: BTreeMap<String, Entry>,
+}
+
+pub struct Queue {
+    path: PathBuf,
+    config: QueueConfig,
+    state: State,
+}
+
+impl Queue {
+    /// Open the queue file, starting empty if it doesn't exist yet
+    pub fn open(config: QueueConfig) -> Result<Self> {
+        let state = match std::fs::read_to_string(&config.path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Corrupt queue file {}", config.path.display()))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => State::default(),
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(Queue {
+            path: config.path.clone(),
+            config,
+            state,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.state)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Queue a job unless one with the same key exists; returns whether
+    /// it was added
+    pub fn enqueue(&mut self, job: Job, now: u64) -> Result<bool> {
+        let key = job.key();
+        if self.state.jobs.contains_key(&key) {
+            return Ok(false);
+        }
+
+        self.state.jobs.insert(
+            key,
+            Entry {
+                job,
+                state: JobState::Pending,
+                attempts: 0,
+                next_attempt: now,
+                last_error: None,
+                pending_tx: None,
+            },
+        );
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.state.jobs.get(key)
+    }
+
+    /// Pending jobs whose next attempt is due
+    pub fn due(&self, now: u64) -> Vec<String> {
+        self.state
+            .jobs
+            .iter()
+            .filter(|(_, entry)| entry.state == JobState::Pending && entry.next_attempt <= now)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn count(&self, state: JobState) -> usize {
+        self.state
+            .jobs
+            .values()
+            .filter(|entry| entry.state == state)
+            .count()
+    }
+
+    /// Record a transaction signed for a job before broadcasting it
+    pub fn set_pending_tx(&mut self, key: &str, tx: Option<SignedTx>) -> Result<()> {
+        if let Some(entry) = self.state.jobs.get_mut(key) {
+            entry.pending_tx = tx;
+        }
+        self.save()
+    }
+
+    pub fn complete(&mut self, key: &str) -> Result<()> {
+        if let Some(entry) = self.state.jobs.get_mut(key) {
+            entry.state = JobState::Done;
+            entry.last_error = None;
+        }
+        self.save()
+    }
+
+    /// Record a failed attempt and schedule the next one, with the delay
+    /// doubling up to the configured maximum. Returns the job's new state.
+    pub fn fail(&mut self, key: &str, error: &str, now: u64) -> Result<JobState> {
+        let config = &self.config;
+        let Some(entry) = self.state.jobs.get_mut(key) else {
+            return Ok(JobState::Abandoned);
+        };
+
+        entry.attempts += 1;
+        entry.last_error = Some(error.to_string());
+        if entry.attempts >= config.max_attempts {
+            entry.state = JobState::Abandoned;
+        } else {
+            let backoff = config
+                .backoff_secs
+                .saturating_mul(1 << (entry.attempts - 1).min(20))
+                .min(config.max_backoff_secs);
+            entry.next_attempt = now + backoff;
+        }
+
+        let state = entry.state;
+        self.save()?;
+        Ok(state)
+    }
+
+    pub fn cursors(&self) -> &Cursors {
+        &self.state.cursors
+    }
+
+    pub fn set_cursors(&mut self, cursors: Cursors) -> Result<()> {
+        self.state.cursors = cursors;
+        self.save()
+    }
+
+    pub fn record_escrow(&mut self, hash_lock: String, escrow_id: String) -> Result<()> {
+        self.state.escrows.insert(hash_lock, escrow_id);
+        self.save()
+    }
+
+    pub fn escrow_for(&self, hash_lock: &str) -> Option<&String> {
+        self.state.escrows.get(hash_lock)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn queue(name: &str) -> Queue {
+        let path = std::env::temp_dir().join(format!(
+            "stellhydra-relayer-{name}-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        Queue::open(QueueConfig {
+            path,
+            max_attempts: 3,
+            backoff_secs: 10,
+            max_backoff_secs: 15,
+        })
+        .unwrap()
+    }
+
+    fn delivery(request_id: u64) -> Job {
+        Job::DeliverRequest {
+            request_id,
+            token: "C".into(),
+            amount: 1_000,
+            recipient: "0x11".into(),
+        }
+    }
+
+    #[test]
+    fn test_jobs_are_deduplicated_and_survive_a_restart() {
+        let mut queue = queue("restart");
+        assert!(queue.enqueue(delivery(1), 100).unwrap());
+        assert!(!queue.enqueue(delivery(1), 100).unwrap());
+        assert!(queue.enqueue(delivery(2), 100).unwrap());
+        queue.complete("request:2").unwrap();
+
+        let reopened = Queue::open(QueueConfig {
+            path: queue.path().to_path_buf(),
+            ..QueueConfig::default()
+        })
+        .unwrap();
+        assert_eq!(reopened.due(100), vec!["request:1".to_string()]);
+        assert_eq!(reopened.count(JobState::Done), 1);
+        assert_eq!(reopened.get("request:1").unwrap().job, delivery(1));
+    }
+
+    #[test]
+    fn test_failures_back_off_then_abandon() {
+        let mut queue = queue("backoff");
+        queue.enqueue(delivery(1), 100).unwrap();
+
+        assert_eq!(
+            queue.fail("request:1", "rpc down", 100).unwrap(),
+            JobState::Pending
+        );
+        assert!(queue.due(109).is_empty());
+        assert_eq!(queue.due(110).len(), 1);
+
+        // Doubled, then capped
+        queue.fail("request:1", "rpc down", 110).unwrap();
+        assert_eq!(queue.get("request:1").unwrap().next_attempt, 125);
+
+        assert_eq!(
+            queue.fail("request:1", "rpc down", 125).unwrap(),
+            JobState::Abandoned
+        );
+        assert!(queue.due(u64::MAX).is_empty());
+        assert_eq!(
+            queue.get("request:1").unwrap().last_error.as_deref(),
+            Some("rpc down")
+        );
+    }
+}
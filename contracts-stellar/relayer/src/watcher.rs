@@ -0,0 +1,308 @@
+//! Watchers turning chain activity into queued work
+//!
+//! The Stellar watcher pages through the bridge and escrow events and
+//! queues a job for each bridge request to the EVM chain and each revealed
+//! HTLC secret. The EVM watcher indexes the escrows created there by hash
+//! lock, so a revealed secret can find the escrow it opens. Both only
+//! move their cursor once the work is safely queued.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::ScAddress;
+use stellhydra_events::bridge::RequestCreated;
+use stellhydra_indexer::{Decoded, Decoder, StellHydraEvent};
+use stellhydra_rpc::{address_string, Event, EventCursor};
+
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::queue::Job;
+use crate::{now, Relayer};
+
+/// Most EVM blocks scanned per `eth_getLogs`, which RPCs cap
+const MAX_BLOCK_RANGE: u64 = 1_000;
+
+pub async fn watch_stellar(relayer: &Relayer) {
+    let interval = Duration::from_secs(relayer.config.stellar.poll_interval_secs);
+    loop {
+        if let Err(error) = poll_stellar(relayer).await {
+            tracing::warn!("Stellar watcher: {error:#}");
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+pub async fn watch_evm(relayer: &Relayer) {
+    let interval = Duration::from_secs(relayer.config.evm.poll_interval_secs);
+    loop {
+        if let Err(error) = poll_evm(relayer).await {
+            tracing::warn!("EVM watcher: {error:#}");
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn poll_stellar(relayer: &Relayer) -> Result<()> {
+    let config = &relayer.config.stellar;
+    let cursors = relayer.queue.lock().await.cursors().clone();
+
+    let start_ledger = match (
+        &cursors.stellar_event,
+        cursors.stellar_ledger,
+        config.start_ledger,
+    ) {
+        (Some(_), _, _) => None,
+        (None, Some(ledger), _) | (None, None, Some(ledger)) => Some(ledger),
+        (None, None, None) => Some(relayer.stellar.latest_ledger().await?),
+    };
+    let cursor = match (&cursors.stellar_event, start_ledger) {
+        (Some(id), _) => EventCursor::After(id),
+        (None, ledger) => EventCursor::Ledger(ledger.unwrap_or_default()),
+    };
+
+    let contracts = [config.bridge.as_str(), config.escrow.as_str()];
+    let page = relayer.stellar.events(&contracts, cursor).await?;
+    Metrics::set(&relayer.metrics.stellar_ledger, page.latest_ledger.into());
+
+    let jobs = jobs_from_events(&relayer.config, &page.events);
+    let mut queue = relayer.queue.lock().await;
+    for job in jobs {
+        let key = job.key();
+        if queue.enqueue(job, now())? {
+            tracing::info!("Queued {key}");
+            Metrics::inc(&relayer.metrics.jobs_enqueued);
+        }
+    }
+
+    let mut cursors = queue.cursors().clone();
+    match page.events.last() {
+        Some(event) => cursors.stellar_event = Some(event.id.clone()),
+        None => cursors.stellar_ledger = cursors.stellar_ledger.or(start_ledger),
+    }
+    for _ in &page.events {
+        Metrics::inc(&relayer.metrics.stellar_events);
+    }
+    queue.set_cursors(cursors)
+}
+
+async fn poll_evm(relayer: &Relayer) -> Result<()> {
+    let config = &relayer.config.evm;
+    let head = relayer.evm.block_number().await?;
+    let safe = head.saturating_sub(config.confirmations);
+
+    let next = relayer.queue.lock().await.cursors().evm_block;
+    let from = next.or(config.start_block).unwrap_or(safe);
+    if from > safe {
+        return Ok(());
+    }
+    let to = safe.min(from + MAX_BLOCK_RANGE - 1);
+
+    let mut escrows = vec![];
+    for escrow_id in relayer.evm.created_escrows(from, to).await? {
+        if let Some(escrow) = relayer.evm.escrow(&escrow_id).await? {
+            escrows.push((hex::encode(escrow.hash_lock), hex::encode(escrow_id)));
+        }
+    }
+
+    let mut queue = relayer.queue.lock().await;
+    for (hash_lock, escrow_id) in escrows {
+        queue.record_escrow(hash_lock, escrow_id)?;
+        Metrics::inc(&relayer.metrics.evm_escrows);
+    }
+    let mut cursors = queue.cursors().clone();
+    cursors.evm_block = Some(to + 1);
+    queue.set_cursors(cursors)?;
+    Metrics::set(&relayer.metrics.evm_block, to + 1);
+    Ok(())
+}
+
+/// Jobs for the events the relayer acts on; everything else is skipped
+pub fn jobs_from_events(config: &Config, events: &[Event]) -> Vec<Job> {
+    let decoder = Decoder::new();
+
+    let mut jobs = vec![];
+    for event in events {
+        let Some(Decoded { event: decoded, .. }) = decoder.decode(event) else {
+            continue;
+        };
+
+        match decoded {
+            StellHydraEvent::RequestCreated(created)
+                if event.contract_id == config.stellar.bridge =>
+            {
+                jobs.extend(deliver_request(config, &created));
+            }
+            StellHydraEvent::HtlcCompleted(completed)
+                if event.contract_id == config.stellar.escrow =>
+            {
+                let secret: Vec<u8> = completed.secret.iter().collect();
+                jobs.push(Job::RelaySecret {
+                    hash_lock: hex::encode(Sha256::digest(&secret)),
+                    secret: hex::encode(secret),
+                });
+            }
+            _ => {}
+        }
+    }
+    jobs
+}
+
+fn deliver_request(config: &Config, created: &RequestCreated) -> Option<Job> {
+    if created.to_chain.to_string() != config.evm.chain {
+        return None;
+    }
+
+    let token = address_string(&ScAddress::from(&created.from_token));
+    if config.route(&token).is_none() {
+        tracing::warn!(
+            "Request {} locks {token}, which has no route; skipping",
+            created.request_id
+        );
+        return None;
+    }
+
+    // EVM recipients are left-padded to 32 bytes
+    let to_address = created.to_address.to_array();
+    if to_address[..12] != [0; 12] {
+        tracing::warn!(
+            "Request {} has no EVM recipient; skipping",
+            created.request_id
+        );
+        return None;
+    }
+
+    Some(Job::DeliverRequest {
+        request_id: created.request_id,
+        token,
+        amount: created.amount - created.fee,
+        recipient: format!("0x{}", hex::encode(&to_address[12..])),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, EnvTestConfig};
+    use soroban_sdk::xdr::{Limits, ScVal, WriteXdr};
+    use soroban_sdk::{Address, Bytes, BytesN, Env, Symbol, TryFromVal, Val};
+    use stellhydra_events::escrow::HtlcCompleted;
+
+    fn config(bridge: &Address, escrow: &Address, token: &Address) -> Config {
+        let mut config: Config = toml::from_str(include_str!("../relayer.example.toml")).unwrap();
+        config.stellar.bridge = address_string(&ScAddress::from(bridge));
+        config.stellar.escrow = address_string(&ScAddress::from(escrow));
+        config.assets[0].stellar_token = address_string(&ScAddress::from(token));
+        config
+    }
+
+    /// An event as RPC would return it
+    fn rpc_event<E: stellhydra_events::Event>(env: &Env, contract: &Address, event: E) -> Event {
+        let base64 = |val: Val| {
+            ScVal::try_from_val(env, &val)
+                .unwrap()
+                .to_xdr_base64(Limits::none())
+                .unwrap()
+        };
+
+        Event {
+            id: "0000000001-0000000001".into(),
+            ledger: 1,
+            contract_id: address_string(&ScAddress::from(contract)),
+            topic: event.topics(env).iter().map(base64).collect(),
+            value: base64(event.into_val(env)),
+        }
+    }
+
+    fn request(env: &Env, token: &Address, to_chain: &str, to_address: [u8; 32]) -> RequestCreated {
+        RequestCreated {
+            request_id: 7,
+            user: Address::generate(env),
+            from_token: token.clone(),
+            to_chain: Symbol::new(env, to_chain),
+            to_address: BytesN::from_array(env, &to_address),
+            amount: 1_000_000,
+            fee: 10_000,
+            priority_fee: 0,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_queues_requests_and_secrets() {
+        let env = Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+        let (bridge, escrow, token) = (
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        );
+        let config = config(&bridge, &escrow, &token);
+
+        let mut recipient = [0; 32];
+        recipient[12..].copy_from_slice(&[0xab; 20]);
+        let secret = [0x42; 32];
+        let events = [
+            rpc_event(&env, &bridge, request(&env, &token, "ETH", recipient)),
+            rpc_event(
+                &env,
+                &escrow,
+                HtlcCompleted {
+                    escrow_id: Bytes::from_slice(&env, b"htlc"),
+                    resolver: Address::generate(&env),
+                    secret: Bytes::from_slice(&env, &secret),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            jobs_from_events(&config, &events),
+            [
+                Job::DeliverRequest {
+                    request_id: 7,
+                    token: config.assets[0].stellar_token.clone(),
+                    amount: 990_000,
+                    recipient: format!("0x{}", "ab".repeat(20)),
+                },
+                Job::RelaySecret {
+                    hash_lock: hex::encode(Sha256::digest(secret)),
+                    secret: "42".repeat(32),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_requests_it_cannot_deliver() {
+        let env = Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+        let (bridge, escrow, token) = (
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        );
+        let config = config(&bridge, &escrow, &token);
+        let recipient = [0x01; 32];
+        let mut evm_recipient = [0; 32];
+        evm_recipient[31] = 1;
+
+        let events = [
+            // Another chain
+            rpc_event(&env, &bridge, request(&env, &token, "SOL", evm_recipient)),
+            // A token without a route
+            rpc_event(
+                &env,
+                &bridge,
+                request(&env, &Address::generate(&env), "ETH", evm_recipient),
+            ),
+            // Not an EVM address
+            rpc_event(&env, &bridge, request(&env, &token, "ETH", recipient)),
+            // The right event from the wrong contract
+            rpc_event(&env, &escrow, request(&env, &token, "ETH", evm_recipient)),
+        ];
+
+        assert_eq!(jobs_from_events(&config, &events), []);
+    }
+}
@@ -0,0 +1,222 @@
+//! Worker carrying out queued jobs
+//!
+//! Every job starts by checking whether its work is already done, so a
+//! retry after a crash, or a job another relayer got to first, completes
+//! without acting twice. An EVM transaction is stored with the job before
+//! it is broadcast; until it is confirmed, retries rebroadcast that same
+//! transaction rather than signing a new one.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use soroban_sdk::xdr::{ScBytes, ScVal};
+use stellhydra_rpc::{field, parse_contract};
+
+use crate::evm::{parse_address, parse_word, SignedTx};
+use crate::metrics::Metrics;
+use crate::queue::{Entry, Job, JobState};
+use crate::{now, Relayer};
+
+/// How often the queue is checked for due jobs
+const TICK: Duration = Duration::from_secs(2);
+
+/// Where a job stands after a pass
+enum Progress {
+    Done,
+    /// Waiting on a transaction; check again on the next pass
+    Waiting,
+}
+
+enum TxState {
+    Pending,
+    Reverted,
+    Confirmed,
+}
+
+pub async fn run(relayer: &Relayer) {
+    loop {
+        if let Err(error) = process_due(relayer).await {
+            tracing::warn!("Worker: {error:#}");
+        }
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+async fn process_due(relayer: &Relayer) -> Result<()> {
+    let keys = relayer.queue.lock().await.due(now());
+
+    for key in keys {
+        let Some(entry) = relayer.queue.lock().await.get(&key).cloned() else {
+            continue;
+        };
+
+        match process(relayer, &key, &entry).await {
+            Ok(Progress::Done) => {
+                relayer.queue.lock().await.complete(&key)?;
+                tracing::info!("Completed {key}");
+                Metrics::inc(&relayer.metrics.jobs_completed);
+            }
+            Ok(Progress::Waiting) => {}
+            Err(error) => {
+                let error = format!("{error:#}");
+                let state = relayer.queue.lock().await.fail(&key, &error, now())?;
+                Metrics::inc(&relayer.metrics.jobs_failed);
+                if state == JobState::Abandoned {
+                    tracing::error!("Abandoned {key}: {error}");
+                    Metrics::inc(&relayer.metrics.jobs_abandoned);
+                } else {
+                    tracing::warn!("{key} failed: {error}");
+                }
+            }
+        }
+    }
+
+    let pending = relayer.queue.lock().await.count(JobState::Pending);
+    Metrics::set(&relayer.metrics.queue_pending, pending as u64);
+    Ok(())
+}
+
+async fn process(relayer: &Relayer, key: &str, entry: &Entry) -> Result<Progress> {
+    match &entry.job {
+        Job::DeliverRequest {
+            request_id,
+            token,
+            amount,
+            recipient,
+        } => {
+            match request_status(relayer, *request_id).await? {
+                2 => return Ok(Progress::Done),
+                3 => bail!("Request failed on Stellar"),
+                _ => {}
+            }
+
+            let tx_hash = match &entry.pending_tx {
+                Some(tx) => match settle(relayer, key, tx).await? {
+                    TxState::Confirmed => parse_word(&tx.hash)?,
+                    TxState::Pending => return Ok(Progress::Waiting),
+                    TxState::Reverted => bail!("Delivery {} reverted", tx.hash),
+                },
+                None => {
+                    let route = relayer
+                        .config
+                        .route(token)
+                        .ok_or_else(|| anyhow!("No route for {token}"))?;
+                    let evm_amount = route
+                        .evm_amount(*amount)
+                        .ok_or_else(|| anyhow!("{amount} doesn't convert to the EVM asset"))?;
+                    let tx = relayer
+                        .evm
+                        .sign_transfer(
+                            &parse_address(&route.evm_asset)?,
+                            &parse_address(recipient)?,
+                            evm_amount,
+                        )
+                        .await?;
+                    submit(relayer, key, tx).await?;
+                    return Ok(Progress::Waiting);
+                }
+            };
+
+            // Report the delivery, with the EVM transaction as proof
+            let bridge = parse_contract(&relayer.config.stellar.bridge)?;
+            let submitted = relayer
+                .stellar
+                .invoke(
+                    &bridge,
+                    "complete_request",
+                    vec![
+                        ScVal::Address(relayer.stellar.account()?),
+                        ScVal::U64(*request_id),
+                        ScVal::Bytes(ScBytes(tx_hash.to_vec().try_into()?)),
+                    ],
+                )
+                .await?;
+            tracing::info!("Request {request_id} completed in {}", submitted.hash);
+            Ok(Progress::Done)
+        }
+        Job::RelaySecret { hash_lock, secret } => {
+            if let Some(tx) = &entry.pending_tx {
+                match settle(relayer, key, tx).await? {
+                    TxState::Confirmed => {}
+                    TxState::Pending => return Ok(Progress::Waiting),
+                    TxState::Reverted => bail!("Escrow transaction {} reverted", tx.hash),
+                }
+            }
+
+            let escrow_id = relayer
+                .queue
+                .lock()
+                .await
+                .escrow_for(hash_lock)
+                .cloned()
+                .ok_or_else(|| anyhow!("No EVM escrow seen for hash lock {hash_lock}"))?;
+            let escrow_id = parse_word(&escrow_id)?;
+            let escrow = relayer
+                .evm
+                .escrow(&escrow_id)
+                .await?
+                .ok_or_else(|| anyhow!("EVM escrow {} is gone", hex::encode(escrow_id)))?;
+
+            // An escrow is locked by a resolver before it can complete
+            let tx = match escrow.status {
+                0 => relayer.evm.sign_lock_escrow(&escrow_id).await?,
+                1 => {
+                    let secret = parse_word(secret)?;
+                    relayer
+                        .evm
+                        .sign_complete_escrow(&escrow_id, &secret)
+                        .await?
+                }
+                2 => return Ok(Progress::Done),
+                _ => bail!("EVM escrow was refunded"),
+            };
+            submit(relayer, key, tx).await?;
+            Ok(Progress::Waiting)
+        }
+    }
+}
+
+/// Status of a bridge request on Stellar
+async fn request_status(relayer: &Relayer, request_id: u64) -> Result<u32> {
+    let bridge = parse_contract(&relayer.config.stellar.bridge)?;
+    let request = relayer
+        .stellar
+        .read(&bridge, "get_bridge_request", vec![ScVal::U64(request_id)])
+        .await?;
+
+    match field(&request, "status") {
+        Some(ScVal::U32(status)) => Ok(*status),
+        _ => bail!("Request {request_id} not found"),
+    }
+}
+
+/// Store a transaction with its job, then broadcast it
+async fn submit(relayer: &Relayer, key: &str, tx: SignedTx) -> Result<()> {
+    relayer
+        .queue
+        .lock()
+        .await
+        .set_pending_tx(key, Some(tx.clone()))?;
+    tracing::info!("{key}: sent {}", tx.hash);
+    relayer.evm.broadcast(&tx).await
+}
+
+/// Follow a stored transaction, rebroadcasting it until it is mined. A
+/// reverted transaction is cleared so the job signs a new one; a confirmed
+/// one stays, so a later failure can't pay out twice.
+async fn settle(relayer: &Relayer, key: &str, tx: &SignedTx) -> Result<TxState> {
+    let Some(receipt) = relayer.evm.receipt(&tx.hash).await? else {
+        relayer.evm.broadcast(tx).await?;
+        return Ok(TxState::Pending);
+    };
+
+    if !receipt.success {
+        relayer.queue.lock().await.set_pending_tx(key, None)?;
+        return Ok(TxState::Reverted);
+    }
+    let head = relayer.evm.block_number().await?;
+    if head.saturating_sub(receipt.block) < relayer.config.evm.confirmations {
+        return Ok(TxState::Pending);
+    }
+    Ok(TxState::Confirmed)
+}
@@ -0,0 +1,171 @@
+//! Resolver bot configuration, read from a TOML file
+//!
+//! Keys are never stored in the file: it names the environment variable
+//! holding the account's secret key.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub stellar: StellarConfig,
+    pub contracts: Contracts,
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub orders: OrdersConfig,
+    #[serde(default)]
+    pub state: StateConfig,
+    /// Stellar tokens the bot takes
+    #[serde(default, rename = "asset")]
+    pub assets: Vec<Asset>,
+    /// Destination chains the bot delivers on
+    #[serde(default, rename = "market")]
+    pub markets: Vec<Market>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StellarConfig {
+    pub rpc_url: String,
+    pub network_passphrase: String,
+    #[serde(default = "default_key_env")]
+    pub secret_key_env: String,
+    /// Ledger to start watching from on first run; the latest by default
+    pub start_ledger: Option<u32>,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Inclusion fee per transaction, in stroops
+    #[serde(default = "default_base_fee")]
+    pub base_fee: u32,
+}
+
+/// Contract ids (C...)
+#[derive(Debug, Deserialize)]
+pub struct Contracts {
+    /// Single-contract HTLC escrow
+    pub escrow: String,
+    pub dutch_auction: String,
+    pub escrow_factory: String,
+    pub router: String,
+    pub price_oracle: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Strategy {
+    /// Oracle symbol every leg is valued in
+    pub quote_symbol: String,
+    pub min_profit_bps: u32,
+    /// Margin taken off the counter-offer for an HTLC escrow
+    pub spread_bps: u32,
+    /// Largest order taken, in whole quote units
+    pub max_order_value: i64,
+    /// Oldest oracle price a quote relies on
+    pub max_price_age_secs: u64,
+    /// Shortest time left on an HTLC escrow's time lock the bot locks it
+    /// with, to deliver and learn the secret in
+    #[serde(default = "default_min_time_left")]
+    pub min_time_left_secs: u64,
+    /// Chain the counterpart of an HTLC escrow is delivered on
+    pub htlc_chain: String,
+    /// Token received assets are sold into through the router
+    pub settlement_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OrdersConfig {
+    /// Endpoint listing the off-chain terms of auctioned orders
+    pub feed_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StateConfig {
+    /// File positions and cursors are kept in
+    pub path: PathBuf,
+    /// Directory secrets are kept in
+    pub secrets_dir: PathBuf,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        StateConfig {
+            path: PathBuf::from("resolver-state.json"),
+            secrets_dir: PathBuf::from("resolver-secrets"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Asset {
+    /// Stellar asset contract id (C...)
+    pub token: String,
+    /// Oracle symbol
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Market {
+    /// Destination chain symbol, as orders name it
+    pub chain: String,
+    /// Oracle symbol of the asset delivered there
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+fn default_key_env() -> String {
+    "STELLAR_SECRET_KEY".into()
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+fn default_base_fee() -> u32 {
+    100
+}
+
+fn default_min_time_left() -> u64 {
+    3_600
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn asset(&self, token: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.token == token)
+    }
+
+    pub fn market(&self, chain: &str) -> Option<&Market> {
+        self.markets.iter().find(|market| market.chain == chain)
+    }
+}
+
+/// Read a key from the environment variable the config names
+pub fn secret(variable: &str) -> Result<String> {
+    std::env::var(variable).with_context(|| format!("{variable} is not set"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example_config_parses() {
+        let config: Config = toml::from_str(include_str!("../resolver-bot.example.toml")).unwrap();
+        assert_eq!(config.strategy.quote_symbol, "USD");
+        assert_eq!(config.state.path, PathBuf::from("resolver-state.json"));
+        assert!(config.orders.feed_url.is_none());
+
+        let settlement = config.strategy.settlement_token.as_deref().unwrap();
+        assert_eq!(config.asset(settlement).unwrap().symbol, "USDC");
+        assert_eq!(
+            config.market(&config.strategy.htlc_chain).unwrap().decimals,
+            18
+        );
+    }
+}
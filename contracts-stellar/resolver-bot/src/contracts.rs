@@ -0,0 +1,265 @@
+//! Typed reads of the contracts the bot works with
+//!
+//! Contract structs come back from simulation as `ScVal` maps; each read
+//! picks out the fields the bot decides on.
+
+use anyhow::{anyhow, Context, Result};
+use soroban_sdk::xdr::{ScAddress, ScBytes, ScString, ScSymbol, ScVal};
+use stellhydra_rpc::{address_string, field, parse_address, parse_contract, Client};
+
+use crate::quote::Price;
+use crate::secrets::HashAlgo;
+
+/// An escrow of the single-contract HTLC
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtlcEscrow {
+    pub amount: i128,
+    /// Stellar token (C...)
+    pub asset: String,
+    pub hash_lock: Vec<u8>,
+    pub hash_algo: HashAlgo,
+    /// Opens the lock holder's exclusive withdrawal
+    pub withdrawal: u64,
+    /// Closes locking; any resolver may withdraw from here on
+    pub public_withdrawal: u64,
+    /// Closes withdrawals and opens the depositor's refund
+    pub cancellation: u64,
+    /// 0: pending, 1: locked, 2: completed, 3: refunded
+    pub status: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Auction {
+    pub order_hash: [u8; 32],
+    pub dest_chain: String,
+    /// `Open`, `Accepted`, `Filled` or `Cancelled`
+    pub status: String,
+    pub end_time: u64,
+    pub resolver: Option<String>,
+}
+
+/// Terms of an order escrow deployed by the factory
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderEscrow {
+    pub taker: String,
+    pub asset: String,
+    pub amount: i128,
+    pub hash_lock: [u8; 32],
+}
+
+fn get<T: TryFrom<ScVal>>(value: &ScVal, name: &str) -> Result<T> {
+    field(value, name)
+        .cloned()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| anyhow!("Missing or mistyped field {name}"))
+}
+
+fn address(value: &ScVal) -> Result<String> {
+    match value {
+        ScVal::Address(address) => Ok(address_string(address)),
+        _ => Err(anyhow!("Not an address")),
+    }
+}
+
+/// Name of a unit enum variant, encoded as a vector holding its symbol
+fn variant(value: &ScVal) -> Result<String> {
+    match value {
+        ScVal::Vec(Some(items)) => match items.first() {
+            Some(ScVal::Symbol(ScSymbol(name))) => Ok(name.to_utf8_string_lossy()),
+            _ => Err(anyhow!("Not an enum variant")),
+        },
+        _ => Err(anyhow!("Not an enum variant")),
+    }
+}
+
+fn hash_algo(value: &ScVal) -> Result<HashAlgo> {
+    match variant(value)?.as_str() {
+        "Sha256" => Ok(HashAlgo::Sha256),
+        "Keccak256" => Ok(HashAlgo::Keccak256),
+        other => Err(anyhow!("Unknown hash algorithm {other}")),
+    }
+}
+
+fn symbol(value: &ScVal) -> Result<String> {
+    match value {
+        ScVal::Symbol(ScSymbol(name)) => Ok(name.to_utf8_string_lossy()),
+        _ => Err(anyhow!("Not a symbol")),
+    }
+}
+
+fn bytes32(bytes: Vec<u8>) -> Result<[u8; 32]> {
+    bytes.try_into().map_err(|_| anyhow!("Expected 32 bytes"))
+}
+
+pub fn bytes(bytes: &[u8]) -> Result<ScVal> {
+    Ok(ScVal::Bytes(ScBytes(bytes.try_into()?)))
+}
+
+fn string(text: &str) -> Result<ScVal> {
+    Ok(ScVal::String(ScString(text.try_into()?)))
+}
+
+pub fn address_arg(address: &str) -> Result<ScVal> {
+    Ok(ScVal::Address(parse_address(address)?))
+}
+
+pub async fn htlc_escrow(client: &Client, contract: &str, id: &[u8]) -> Result<Option<HtlcEscrow>> {
+    let escrow = client
+        .read(&parse_contract(contract)?, "get_escrow", vec![bytes(id)?])
+        .await?;
+    if escrow == ScVal::Void {
+        return Ok(None);
+    }
+
+    let time_locks = field(&escrow, "time_locks").context("Missing time_locks")?;
+    Ok(Some(HtlcEscrow {
+        amount: get(&escrow, "amount")?,
+        asset: address(field(&escrow, "asset").context("Missing asset")?)?,
+        hash_lock: get(&escrow, "hash_lock")?,
+        hash_algo: hash_algo(field(&escrow, "hash_algo").context("Missing hash_algo")?)?,
+        withdrawal: get(time_locks, "withdrawal")?,
+        public_withdrawal: get(time_locks, "public_withdrawal")?,
+        cancellation: get(time_locks, "cancellation")?,
+        status: get(&escrow, "status")?,
+    }))
+}
+
+pub async fn auction_count(client: &Client, contract: &str) -> Result<u64> {
+    let count = client
+        .read(&parse_contract(contract)?, "get_auction_count", vec![])
+        .await?;
+    u64::try_from(count).map_err(|_| anyhow!("Unexpected auction count"))
+}
+
+pub async fn auction(client: &Client, contract: &str, id: u64) -> Result<Auction> {
+    let auction = client
+        .read(
+            &parse_contract(contract)?,
+            "get_auction",
+            vec![ScVal::U64(id)],
+        )
+        .await?;
+
+    Ok(Auction {
+        order_hash: bytes32(get(&auction, "order_hash")?)?,
+        dest_chain: symbol(field(&auction, "dest_chain").context("Missing dest_chain")?)?,
+        status: variant(field(&auction, "status").context("Missing status")?)?,
+        end_time: get(&auction, "end_time")?,
+        resolver: match field(&auction, "resolver") {
+            Some(ScVal::Void) | None => None,
+            Some(resolver) => Some(address(resolver)?),
+        },
+    })
+}
+
+/// Amount an auction's winner would deliver if it accepted now
+pub async fn current_amount(client: &Client, contract: &str, id: u64) -> Result<i128> {
+    let amount = client
+        .read(
+            &parse_contract(contract)?,
+            "current_amount",
+            vec![ScVal::U64(id)],
+        )
+        .await?;
+    i128::try_from(amount).map_err(|_| anyhow!("Unexpected amount"))
+}
+
+/// Escrow the factory deployed for an order, if any
+pub async fn order_escrow_address(
+    client: &Client,
+    factory: &str,
+    order_hash: &[u8; 32],
+) -> Result<Option<ScAddress>> {
+    match client
+        .read(
+            &parse_contract(factory)?,
+            "get_escrow",
+            vec![bytes(order_hash)?],
+        )
+        .await?
+    {
+        ScVal::Address(escrow) => Ok(Some(escrow)),
+        _ => Ok(None),
+    }
+}
+
+pub async fn order_escrow(client: &Client, escrow: &str) -> Result<OrderEscrow> {
+    let params = client
+        .read(&parse_contract(escrow)?, "get_params", vec![])
+        .await?;
+
+    Ok(OrderEscrow {
+        taker: address(field(&params, "taker").context("Missing taker")?)?,
+        asset: address(field(&params, "asset").context("Missing asset")?)?,
+        amount: get(&params, "amount")?,
+        hash_lock: bytes32(get(&params, "hash_lock")?)?,
+    })
+}
+
+/// `Active`, `Withdrawn` or `Refunded`
+pub async fn order_escrow_status(client: &Client, escrow: &str) -> Result<String> {
+    let status = client
+        .read(&parse_contract(escrow)?, "get_status", vec![])
+        .await?;
+    variant(&status)
+}
+
+/// Oracle price of `base` in `quote`, unless it's missing or older than
+/// `max_age` seconds
+pub async fn price(
+    client: &Client,
+    oracle: &str,
+    base: &str,
+    quote: &str,
+    now: u64,
+    max_age: u64,
+) -> Result<Option<Price>> {
+    if base == quote {
+        return Ok(Some(Price {
+            price: 1,
+            decimals: 0,
+        }));
+    }
+
+    let data = client
+        .read(
+            &parse_contract(oracle)?,
+            "get_price",
+            vec![string(base)?, string(quote)?],
+        )
+        .await?;
+    if data == ScVal::Void {
+        return Ok(None);
+    }
+
+    let timestamp: u64 = get(&data, "timestamp")?;
+    if now.saturating_sub(timestamp) > max_age {
+        return Ok(None);
+    }
+    Ok(Some(Price {
+        price: get(&data, "price")?,
+        decimals: get(&data, "decimals")?,
+    }))
+}
+
+/// Output of the router's best route, zero when there's none
+pub async fn route_output(
+    client: &Client,
+    router: &str,
+    token_in: &str,
+    token_out: &str,
+    amount_in: i128,
+) -> Result<i128> {
+    let route = client
+        .read(
+            &parse_contract(router)?,
+            "find_best_route",
+            vec![
+                address_arg(token_in)?,
+                address_arg(token_out)?,
+                ScVal::from(amount_in),
+            ],
+        )
+        .await?;
+    get(&route, "expected_output")
+}
@@ -0,0 +1,187 @@
+//! StellHydra reference resolver
+//!
+//! A starting point for third-party resolvers. The bot watches the HTLC
+//! escrow for new escrows and the Dutch auction for new orders, quotes
+//! each against the price oracle and the router, and takes those that
+//! clear its strategy: it locks HTLC escrows and accepts auctions, then
+//! completes or withdraws from the escrows once the maker reveals the
+//! secret. Secrets are kept in a directory the `reveal` command adds to,
+//! for secrets learned on the destination chain.
+//!
+//! ```text
+//! stellhydra-resolver-bot --config resolver-bot.toml run
+//! stellhydra-resolver-bot --config resolver-bot.toml reveal <hex secret>
+//! ```
+
+mod config;
+mod contracts;
+mod orders;
+mod quote;
+mod resolver;
+mod secrets;
+mod state;
+mod watcher;
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use soroban_sdk::xdr::ScVal;
+use stellhydra_rpc::{address_string, parse_contract, Client, Submitted};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+use crate::quote::Price;
+use crate::secrets::{HashAlgo, SecretStore};
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[command(name = "stellhydra-resolver-bot", version, about)]
+struct Cli {
+    #[arg(long, default_value = "resolver-bot.toml", global = true)]
+    config: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Watch for orders and work the bot's positions until interrupted
+    Run,
+    /// Hand the bot a secret revealed elsewhere
+    Reveal {
+        /// Hex secret
+        secret: String,
+    },
+    /// Generate a secret, printing it with its hash lock
+    NewSecret {
+        /// Hash the escrow locks with
+        #[arg(long, value_enum, default_value = "sha256")]
+        hash_algo: HashAlgo,
+    },
+}
+
+/// What the watchers and the resolver work with
+pub struct Bot {
+    config: Config,
+    stellar: Client,
+    http: reqwest::Client,
+    secrets: SecretStore,
+    /// The bot's account (G...)
+    account: String,
+}
+
+impl Bot {
+    async fn invoke(&self, contract: &str, function: &str, args: Vec<ScVal>) -> Result<Submitted> {
+        self.stellar
+            .invoke(&parse_contract(contract)?, function, args)
+            .await
+            .with_context(|| format!("{function} on {contract}"))
+    }
+
+    /// Fresh oracle price of a symbol in the quote symbol
+    async fn price(&self, symbol: &str) -> Result<Option<Price>> {
+        let strategy = &self.config.strategy;
+        contracts::price(
+            &self.stellar,
+            &self.config.contracts.price_oracle,
+            symbol,
+            &strategy.quote_symbol,
+            now(),
+            strategy.max_price_age_secs,
+        )
+        .await
+    }
+}
+
+/// Unix time, in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let cli = Cli::parse();
+    let config = Config::load(&cli.config)?;
+    let secrets = SecretStore::open(config.state.secrets_dir.clone())?;
+
+    match cli.command {
+        Command::Reveal { secret } => {
+            let secret = hex::decode(secret.trim_start_matches("0x")).context("Invalid secret")?;
+            for algo in HashAlgo::ALL {
+                println!("Stored the secret of {}", secrets.put(&secret, algo)?);
+            }
+            Ok(())
+        }
+        Command::NewSecret { hash_algo } => {
+            let (secret, hash_lock) = secrets.generate(hash_algo)?;
+            println!("secret:    {}", hex::encode(secret));
+            println!("hash lock: {hash_lock}");
+            Ok(())
+        }
+        Command::Run => {
+            let stellar = Client::new(
+                &config.stellar.rpc_url,
+                &config.stellar.network_passphrase,
+                config.stellar.base_fee,
+            )
+            .with_secret_key(&config::secret(&config.stellar.secret_key_env)?)?;
+            let account = address_string(&stellar.account()?);
+            tracing::info!("Resolving as {account}");
+
+            let mut state = State::open(config.state.path.clone())?;
+            tracing::info!("State at {}", state.path().display());
+            let bot = Bot {
+                config,
+                stellar,
+                http: reqwest::Client::new(),
+                secrets,
+                account,
+            };
+
+            tokio::select! {
+                _ = run(&bot, &mut state) => {}
+                _ = tokio::signal::ctrl_c() => tracing::info!("Shutting down"),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Poll for orders and work the positions; every step logs and retries
+/// its own errors on the next pass
+async fn run(bot: &Bot, state: &mut State) {
+    let interval = Duration::from_secs(bot.config.stellar.poll_interval_secs);
+    loop {
+        match watcher::poll_events(bot, state).await {
+            Ok(created) => {
+                for escrow_id in created {
+                    if let Err(error) = resolver::consider_htlc(bot, state, &escrow_id).await {
+                        tracing::warn!("HTLC {}: {error:#}", hex::encode(escrow_id));
+                    }
+                }
+            }
+            Err(error) => tracing::warn!("Event watcher: {error:#}"),
+        }
+
+        if let Err(error) = watcher::poll_auctions(bot, state).await {
+            tracing::warn!("Auction watcher: {error:#}");
+        }
+        if let Err(error) = resolver::consider_auctions(bot, state).await {
+            tracing::warn!("Auctions: {error:#}");
+        }
+        resolver::advance(bot, state).await;
+
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -0,0 +1,64 @@
+//! Off-chain terms of auctioned orders
+//!
+//! An auction prices what its winner delivers on the destination chain.
+//! What the winner gets back, the maker's escrow on Stellar, is agreed
+//! with the maker's signed order and only lands on chain once the escrow
+//! is deployed, so the bot reads it from the feed makers publish orders
+//! to. The escrow is checked against these terms before the bot settles.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct OrderTerms {
+    /// Hex order hash, as the auction names it
+    pub order_hash: String,
+    /// Stellar token the maker escrows (C...)
+    pub token: String,
+    /// Amount escrowed, as a decimal string
+    pub amount: String,
+}
+
+impl OrderTerms {
+    pub fn amount(&self) -> Result<i128> {
+        self.amount
+            .parse()
+            .with_context(|| format!("Invalid amount in order {}", self.order_hash))
+    }
+}
+
+pub async fn fetch(http: &reqwest::Client, url: &str) -> Result<Vec<OrderTerms>> {
+    http.get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("Invalid order feed at {url}"))
+}
+
+/// Terms of the order with a hash, if the feed lists it
+pub fn find<'a>(orders: &'a [OrderTerms], order_hash: &[u8; 32]) -> Option<&'a OrderTerms> {
+    let order_hash = hex::encode(order_hash);
+    orders
+        .iter()
+        .find(|order| order.order_hash.eq_ignore_ascii_case(&order_hash))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_feed_parses_and_finds_orders() {
+        let orders: Vec<OrderTerms> = serde_json::from_str(&format!(
+            r#"[{{"order_hash": "{}", "token": "C...", "amount": "170141183460469231731687303715884105727"}}]"#,
+            "AB".repeat(32)
+        ))
+        .unwrap();
+
+        let order = find(&orders, &[0xab; 32]).unwrap();
+        assert_eq!(order.amount().unwrap(), i128::MAX);
+        assert!(find(&orders, &[0; 32]).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+//! Pricing an order: what it pays the bot against what filling it costs
+//!
+//! Both legs are valued in the strategy's quote symbol, in units of
+//! `VALUE_DECIMALS`. What the bot receives counts at the lower of its
+//! oracle value and what the router would sell it for, so thin liquidity
+//! eats into the margin before the bot commits rather than after.
+
+use crate::config::Strategy;
+
+/// Decimals of a quote-symbol value
+pub const VALUE_DECIMALS: u32 = 7;
+
+/// Oracle price of an asset in the quote symbol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Price {
+    pub price: i128,
+    pub decimals: u32,
+}
+
+/// Value of an amount of an asset with `decimals`; `None` on overflow
+pub fn value(amount: i128, decimals: u32, price: Price) -> Option<i128> {
+    rescale(
+        amount.checked_mul(price.price)?,
+        decimals + price.decimals,
+        VALUE_DECIMALS,
+    )
+}
+
+/// Amount of an asset with `decimals` worth `value`, rounded down
+pub fn amount_for(value: i128, decimals: u32, price: Price) -> Option<i128> {
+    if price.price <= 0 {
+        return None;
+    }
+    Some(rescale(value, VALUE_DECIMALS, decimals + price.decimals)? / price.price)
+}
+
+fn rescale(amount: i128, from: u32, to: u32) -> Option<i128> {
+    if to >= from {
+        amount.checked_mul(10i128.checked_pow(to - from)?)
+    } else {
+        Some(amount / 10i128.checked_pow(from - to)?)
+    }
+}
+
+/// Value the bot offers to deliver for an HTLC escrow worth `fair`
+pub fn counter_offer(fair: i128, spread_bps: u32) -> i128 {
+    fair * (10_000 - i128::from(spread_bps.min(10_000))) / 10_000
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quote {
+    /// Value received
+    pub receive: i128,
+    /// Value delivered on the destination
+    pub deliver: i128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Take,
+    TooLarge,
+    Unprofitable,
+}
+
+impl Quote {
+    /// Quote an order whose received leg is worth `oracle` at the oracle
+    /// price and `router` when sold through the router, if it can be
+    pub fn new(oracle: i128, router: Option<i128>, deliver: i128) -> Self {
+        Quote {
+            receive: router.map_or(oracle, |router| router.min(oracle)),
+            deliver,
+        }
+    }
+
+    pub fn profit(&self) -> i128 {
+        self.receive - self.deliver
+    }
+
+    /// Profit over the delivered value, in basis points
+    pub fn profit_bps(&self) -> i128 {
+        if self.deliver <= 0 {
+            return 0;
+        }
+        self.profit() * 10_000 / self.deliver
+    }
+
+    pub fn verdict(&self, strategy: &Strategy) -> Verdict {
+        let max = i128::from(strategy.max_order_value) * 10i128.pow(VALUE_DECIMALS);
+        if self.deliver > max {
+            Verdict::TooLarge
+        } else if self.deliver <= 0 || self.profit_bps() < i128::from(strategy.min_profit_bps) {
+            Verdict::Unprofitable
+        } else {
+            Verdict::Take
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XLM: Price = Price {
+        price: 1_200_000, // $0.12
+        decimals: 7,
+    };
+    const ETH: Price = Price {
+        price: 300_000_000_000, // $3,000
+        decimals: 8,
+    };
+
+    fn strategy() -> Strategy {
+        Strategy {
+            quote_symbol: "USD".into(),
+            min_profit_bps: 50,
+            spread_bps: 100,
+            max_order_value: 10_000,
+            max_price_age_secs: 300,
+            min_time_left_secs: 3_600,
+            htlc_chain: "ETH".into(),
+            settlement_token: None,
+        }
+    }
+
+    #[test]
+    fn test_values_across_decimals() {
+        // 1,000 XLM is $120
+        assert_eq!(value(10_000_000_000, 7, XLM), Some(1_200_000_000));
+        // 0.04 ETH is $120
+        assert_eq!(value(40_000_000_000_000_000, 18, ETH), Some(1_200_000_000));
+        assert_eq!(
+            amount_for(1_200_000_000, 18, ETH),
+            Some(40_000_000_000_000_000)
+        );
+        assert_eq!(amount_for(1_200_000_000, 7, XLM), Some(10_000_000_000));
+
+        assert_eq!(value(i128::MAX, 7, XLM), None);
+        assert_eq!(
+            amount_for(
+                1,
+                7,
+                Price {
+                    price: 0,
+                    decimals: 7
+                }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_receives_the_lower_of_oracle_and_router() {
+        assert_eq!(Quote::new(1_000, Some(990), 900).receive, 990);
+        assert_eq!(Quote::new(1_000, Some(1_100), 900).receive, 1_000);
+        assert_eq!(Quote::new(1_000, None, 900).receive, 1_000);
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let strategy = strategy();
+        let unit = 10i128.pow(VALUE_DECIMALS);
+
+        // 1% margin
+        assert_eq!(
+            Quote::new(101 * unit, None, 100 * unit).verdict(&strategy),
+            Verdict::Take
+        );
+        // 0.4% margin, under the 0.5% minimum
+        assert_eq!(
+            Quote::new(1_004 * unit, None, 1_000 * unit).verdict(&strategy),
+            Verdict::Unprofitable
+        );
+        assert_eq!(
+            Quote::new(20_000 * unit, None, 10_001 * unit).verdict(&strategy),
+            Verdict::TooLarge
+        );
+        assert_eq!(
+            Quote::new(1, None, 0).verdict(&strategy),
+            Verdict::Unprofitable
+        );
+    }
+
+    #[test]
+    fn test_counter_offer_keeps_the_spread() {
+        let fair = 1_000 * 10i128.pow(VALUE_DECIMALS);
+        let offer = counter_offer(fair, 100);
+        assert_eq!(offer, 990 * 10i128.pow(VALUE_DECIMALS));
+
+        // Router slippage within the spread leaves a profit; beyond it, none
+        assert_eq!(
+            Quote::new(fair, Some(fair * 998 / 1_000), offer).verdict(&strategy()),
+            Verdict::Take
+        );
+        assert_eq!(
+            Quote::new(fair, Some(fair * 990 / 1_000), offer).verdict(&strategy()),
+            Verdict::Unprofitable
+        );
+        assert_eq!(counter_offer(fair, 20_000), 0);
+    }
+}
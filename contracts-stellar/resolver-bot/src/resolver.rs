@@ -0,0 +1,412 @@
+//! Taking orders and seeing positions through
+//!
+//! The bot commits to an order only when its quote clears the strategy,
+//! then follows the position to the end: a locked HTLC escrow completes
+//! once the secret is known, and an accepted auction settles when the
+//! maker's escrow appears, then pays out against the secret. Delivering
+//! on the destination chain is left to the operator's own executor, which
+//! the log tells what is owed; the maker reveals the secret once paid.
+
+use anyhow::{anyhow, Context, Result};
+use soroban_sdk::xdr::ScVal;
+use stellhydra_rpc::address_string;
+
+use crate::config::{Asset, Market};
+use crate::orders::{self, OrderTerms};
+use crate::quote::{self, Price, Quote, Verdict};
+use crate::secrets::HashAlgo;
+use crate::state::{Delivery, Position, State};
+use crate::{contracts, now, Bot};
+
+/// Lock an HTLC escrow if the bot can deliver its counterpart at a profit
+pub async fn consider_htlc(bot: &Bot, state: &mut State, escrow_id: &[u8]) -> Result<()> {
+    let key = format!("htlc:{}", hex::encode(escrow_id));
+    if state.has_position(&key) {
+        return Ok(());
+    }
+
+    let config = &bot.config;
+    let Some(escrow) =
+        contracts::htlc_escrow(&bot.stellar, &config.contracts.escrow, escrow_id).await?
+    else {
+        return Ok(());
+    };
+    if escrow.status != 0 {
+        return Ok(());
+    }
+    if escrow.cancellation < now() + config.strategy.min_time_left_secs
+        || now() >= escrow.public_withdrawal
+    {
+        tracing::debug!("{key} expires too soon");
+        return Ok(());
+    }
+    let asset = config
+        .asset(&escrow.asset)
+        .ok_or_else(|| anyhow!("{key} holds {}, which the bot doesn't take", escrow.asset))?;
+    let market = config
+        .market(&config.strategy.htlc_chain)
+        .ok_or_else(|| anyhow!("No market for {}", config.strategy.htlc_chain))?;
+    let Some((price_in, price_out)) = prices(bot, asset, market).await? else {
+        tracing::debug!("{key}: no fresh prices");
+        return Ok(());
+    };
+
+    // Offer the fair value less the spread, then check the router can
+    // turn the escrow into at least that
+    let fair = quote::value(escrow.amount, asset.decimals, price_in)
+        .ok_or_else(|| anyhow!("{key} is too large to value"))?;
+    let offer = quote::counter_offer(fair, config.strategy.spread_bps);
+    let deliver = quote::amount_for(offer, market.decimals, price_out)
+        .ok_or_else(|| anyhow!("{key} is too large to price"))?;
+    let quote = Quote::new(fair, router_value(bot, asset, escrow.amount).await?, offer);
+    if !take(&key, &quote, bot) {
+        return Ok(());
+    }
+
+    bot.invoke(
+        &config.contracts.escrow,
+        "lock_escrow",
+        vec![
+            contracts::bytes(escrow_id)?,
+            contracts::address_arg(&bot.account)?,
+        ],
+    )
+    .await?;
+
+    let delivery = Delivery {
+        chain: market.chain.clone(),
+        symbol: market.symbol.clone(),
+        amount: deliver,
+    };
+    tracing::info!(
+        "Locked {key}: deliver {} {} on {} against hash lock {}",
+        delivery.amount,
+        delivery.symbol,
+        delivery.chain,
+        hex::encode(&escrow.hash_lock)
+    );
+    state.set_position(Position::Htlc {
+        escrow_id: hex::encode(escrow_id),
+        hash_lock: hex::encode(&escrow.hash_lock),
+        delivery,
+    })
+}
+
+/// Accept the watched auctions whose current price suits the bot, and
+/// stop watching those that can't anymore
+pub async fn consider_auctions(bot: &Bot, state: &mut State) -> Result<()> {
+    let watched = state.watched_auctions();
+    if watched.is_empty() {
+        return Ok(());
+    }
+    let orders = match &bot.config.orders.feed_url {
+        Some(url) => orders::fetch(&bot.http, url).await?,
+        None => vec![],
+    };
+
+    for id in watched {
+        if let Err(error) = consider_auction(bot, state, id, &orders).await {
+            tracing::warn!("Auction {id}: {error:#}");
+        }
+    }
+    Ok(())
+}
+
+async fn consider_auction(
+    bot: &Bot,
+    state: &mut State,
+    id: u64,
+    orders: &[OrderTerms],
+) -> Result<()> {
+    let config = &bot.config;
+    let contract = &config.contracts.dutch_auction;
+    let auction = contracts::auction(&bot.stellar, contract, id).await?;
+    if auction.status != "Open" || now() > auction.end_time {
+        return state.unwatch_auction(id);
+    }
+    let Some(market) = config.market(&auction.dest_chain) else {
+        tracing::debug!("Auction {id} delivers on {}; skipping", auction.dest_chain);
+        return state.unwatch_auction(id);
+    };
+    // Terms may reach the feed after the auction is posted
+    let Some(terms) = orders::find(orders, &auction.order_hash) else {
+        return Ok(());
+    };
+    let Some(asset) = config.asset(&terms.token) else {
+        tracing::debug!("Auction {id} pays in {}; skipping", terms.token);
+        return state.unwatch_auction(id);
+    };
+    let Some((price_in, price_out)) = prices(bot, asset, market).await? else {
+        tracing::debug!("Auction {id}: no fresh prices");
+        return Ok(());
+    };
+
+    let key = format!("auction:{id}");
+    let amount = terms.amount()?;
+    let deliver = contracts::current_amount(&bot.stellar, contract, id).await?;
+    let quote = Quote::new(
+        quote::value(amount, asset.decimals, price_in)
+            .ok_or_else(|| anyhow!("{key} is too large to value"))?,
+        router_value(bot, asset, amount).await?,
+        quote::value(deliver, market.decimals, price_out)
+            .ok_or_else(|| anyhow!("{key} is too large to value"))?,
+    );
+    if !take(&key, &quote, bot) {
+        return Ok(());
+    }
+
+    // The price may have decayed further by the time the call lands
+    let submitted = bot
+        .invoke(
+            contract,
+            "accept",
+            vec![contracts::address_arg(&bot.account)?, ScVal::U64(id)],
+        )
+        .await?;
+    let deliver = i128::try_from(submitted.result).unwrap_or(deliver);
+
+    let delivery = Delivery {
+        chain: market.chain.clone(),
+        symbol: market.symbol.clone(),
+        amount: deliver,
+    };
+    tracing::info!(
+        "Accepted {key} to deliver {} {} on {}",
+        delivery.amount,
+        delivery.symbol,
+        delivery.chain
+    );
+    state.unwatch_auction(id)?;
+    state.set_position(Position::Auction {
+        id,
+        order_hash: hex::encode(auction.order_hash),
+        token: asset.token.clone(),
+        amount,
+        delivery,
+        settled: false,
+    })
+}
+
+/// Move every open position along as far as it goes
+pub async fn advance(bot: &Bot, state: &mut State) {
+    for position in state.positions() {
+        let key = position.key();
+        let result = match position {
+            Position::Htlc {
+                escrow_id,
+                hash_lock,
+                ..
+            } => advance_htlc(bot, state, &key, &escrow_id, &hash_lock).await,
+            position @ Position::Auction { .. } => {
+                advance_auction(bot, state, &key, position).await
+            }
+        };
+        if let Err(error) = result {
+            tracing::warn!("{key}: {error:#}");
+        }
+    }
+}
+
+async fn advance_htlc(
+    bot: &Bot,
+    state: &mut State,
+    key: &str,
+    escrow_id: &str,
+    hash_lock: &str,
+) -> Result<()> {
+    let id = hex::decode(escrow_id)?;
+    let escrow = contracts::htlc_escrow(&bot.stellar, &bot.config.contracts.escrow, &id)
+        .await?
+        .context("The escrow is gone")?;
+
+    match escrow.status {
+        1 => {
+            let Some(secret) = bot.secrets.get(hash_lock, escrow.hash_algo)? else {
+                return Ok(());
+            };
+            if now() >= escrow.cancellation {
+                // Only the maker's refund is left
+                return Ok(());
+            }
+            if now() < escrow.withdrawal {
+                // The source chain isn't final yet
+                return Ok(());
+            }
+            let submitted = bot
+                .invoke(
+                    &bot.config.contracts.escrow,
+                    "complete_escrow",
+                    vec![
+                        contracts::bytes(&id)?,
+                        contracts::bytes(&secret)?,
+                        contracts::address_arg(&bot.account)?,
+                    ],
+                )
+                .await?;
+            tracing::info!("Completed {key} in {}", submitted.hash);
+            state.close_position(key)
+        }
+        2 => {
+            tracing::info!("{key} is complete");
+            state.close_position(key)
+        }
+        3 => {
+            tracing::warn!("{key} was refunded to its maker");
+            state.close_position(key)
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn advance_auction(
+    bot: &Bot,
+    state: &mut State,
+    key: &str,
+    position: Position,
+) -> Result<()> {
+    let Position::Auction {
+        id,
+        order_hash,
+        token,
+        amount,
+        delivery,
+        settled,
+    } = position
+    else {
+        return Ok(());
+    };
+    let config = &bot.config;
+    let order_hash: [u8; 32] = hex::decode(&order_hash)?
+        .try_into()
+        .map_err(|_| anyhow!("Invalid order hash"))?;
+    let Some(escrow) = contracts::order_escrow_address(
+        &bot.stellar,
+        &config.contracts.escrow_factory,
+        &order_hash,
+    )
+    .await?
+    else {
+        if !settled {
+            let auction =
+                contracts::auction(&bot.stellar, &config.contracts.dutch_auction, id).await?;
+            if auction.resolver.as_deref() != Some(bot.account.as_str()) {
+                tracing::warn!("{key}: the escrow never came and the bond was slashed");
+                return state.close_position(key);
+            }
+        }
+        return Ok(());
+    };
+    let escrow = address_string(&escrow);
+    let params = contracts::order_escrow(&bot.stellar, &escrow).await?;
+
+    if !settled {
+        // Settling commits the bot to deliver; forfeiting the bond is
+        // cheaper than filling an escrow that doesn't pay what was quoted
+        if params.taker != bot.account || params.asset != token || params.amount < amount {
+            tracing::warn!("{key}: escrow {escrow} doesn't match the order's terms; abandoning");
+            return state.close_position(key);
+        }
+        let submitted = bot
+            .invoke(
+                &config.contracts.dutch_auction,
+                "settle",
+                vec![ScVal::U64(id)],
+            )
+            .await?;
+        tracing::info!(
+            "Settled {key} in {}: deliver {} {} on {} against hash lock {}",
+            submitted.hash,
+            delivery.amount,
+            delivery.symbol,
+            delivery.chain,
+            hex::encode(params.hash_lock)
+        );
+        state.set_position(Position::Auction {
+            id,
+            order_hash: hex::encode(order_hash),
+            token,
+            amount,
+            delivery,
+            settled: true,
+        })?;
+    }
+
+    match contracts::order_escrow_status(&bot.stellar, &escrow)
+        .await?
+        .as_str()
+    {
+        "Active" => {
+            // Order escrows lock with sha256
+            let hash_lock = hex::encode(params.hash_lock);
+            let Some(secret) = bot.secrets.get(&hash_lock, HashAlgo::Sha256)? else {
+                return Ok(());
+            };
+            let submitted = bot
+                .invoke(&escrow, "withdraw", vec![contracts::bytes(&secret)?])
+                .await?;
+            tracing::info!("Withdrew {key} from {escrow} in {}", submitted.hash);
+            state.close_position(key)
+        }
+        "Refunded" => {
+            tracing::warn!("{key} was refunded to its maker");
+            state.close_position(key)
+        }
+        _ => {
+            tracing::info!("{key} is complete");
+            state.close_position(key)
+        }
+    }
+}
+
+/// Oracle prices of what the bot receives and what it delivers
+async fn prices(bot: &Bot, asset: &Asset, market: &Market) -> Result<Option<(Price, Price)>> {
+    let price_in = bot.price(&asset.symbol).await?;
+    let price_out = bot.price(&market.symbol).await?;
+    Ok(price_in.zip(price_out))
+}
+
+/// What selling `amount` of an asset into the settlement token through
+/// the router is worth; `None` when there's nothing to sell
+async fn router_value(bot: &Bot, asset: &Asset, amount: i128) -> Result<Option<i128>> {
+    let config = &bot.config;
+    let Some(settlement) = &config.strategy.settlement_token else {
+        return Ok(None);
+    };
+    if *settlement == asset.token {
+        return Ok(None);
+    }
+    let settlement = config
+        .asset(settlement)
+        .context("The settlement token isn't a configured asset")?;
+
+    let output = contracts::route_output(
+        &bot.stellar,
+        &config.contracts.router,
+        &asset.token,
+        &settlement.token,
+        amount,
+    )
+    .await?;
+    let price = bot
+        .price(&settlement.symbol)
+        .await?
+        .context("No fresh price for the settlement token")?;
+    quote::value(output, settlement.decimals, price)
+        .map(Some)
+        .context("Route output too large to value")
+}
+
+/// Whether a quote clears the strategy, logging why when it doesn't
+fn take(key: &str, quote: &Quote, bot: &Bot) -> bool {
+    match quote.verdict(&bot.config.strategy) {
+        Verdict::Take => true,
+        verdict => {
+            tracing::debug!(
+                "{key}: {verdict:?} at {} bps on {}",
+                quote.profit_bps(),
+                quote.deliver
+            );
+            false
+        }
+    }
+}
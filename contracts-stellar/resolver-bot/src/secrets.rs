@@ -0,0 +1,129 @@
+//! Secrets behind the hash locks the bot holds positions on
+//!
+//! Each secret is a file named after its hex hash lock, readable by the
+//! bot's user only and written with a rename so it's never seen
+//! half-written. The running bot and the `reveal` command share the
+//! directory without coordinating.
+
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// Hash an escrow's lock is taken over, as the HTLC escrow's `HashAlgo`
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum HashAlgo {
+    Sha256,
+    Keccak256,
+}
+
+impl HashAlgo {
+    pub const ALL: [HashAlgo; 2] = [HashAlgo::Sha256, HashAlgo::Keccak256];
+}
+
+pub struct SecretStore {
+    dir: PathBuf,
+}
+
+/// Hex hash of a secret, the hash lock it opens
+pub fn hash_lock(secret: &[u8], algo: HashAlgo) -> String {
+    match algo {
+        HashAlgo::Sha256 => hex::encode(Sha256::digest(secret)),
+        HashAlgo::Keccak256 => hex::encode(Keccak256::digest(secret)),
+    }
+}
+
+impl SecretStore {
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Cannot create {}", dir.display()))?;
+        Ok(SecretStore { dir })
+    }
+
+    /// Keep a secret under its hash lock for `algo`; returns the hash lock
+    pub fn put(&self, secret: &[u8], algo: HashAlgo) -> Result<String> {
+        let hash_lock = hash_lock(secret, algo);
+        let path = self.dir.join(&hash_lock);
+        if !path.exists() {
+            let tmp = path.with_extension("tmp");
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(&tmp)?;
+            file.write_all(hex::encode(secret).as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(hash_lock)
+    }
+
+    /// Secret opening a hex hash lock taken with `algo`, if known
+    pub fn get(&self, hash_lock: &str, algo: HashAlgo) -> Result<Option<Vec<u8>>> {
+        let path = self.dir.join(hash_lock);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let secret = hex::decode(text.trim())
+            .with_context(|| format!("Corrupt secret {}", path.display()))?;
+        if self::hash_lock(&secret, algo) != hash_lock {
+            bail!("Secret {} doesn't match its hash lock", path.display());
+        }
+        Ok(Some(secret))
+    }
+
+    /// A fresh 32-byte secret, kept before it's handed out
+    pub fn generate(&self, algo: HashAlgo) -> Result<(Vec<u8>, String)> {
+        let mut secret = vec![0; 32];
+        getrandom::getrandom(&mut secret).context("No randomness available")?;
+        let hash_lock = self.put(&secret, algo)?;
+        Ok((secret, hash_lock))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_secrets_are_found_by_hash_lock() {
+        let dir = std::env::temp_dir().join(format!("resolver-secrets-{}", std::process::id()));
+        let store = SecretStore::open(dir.clone()).unwrap();
+
+        let sha = HashAlgo::Sha256;
+        let hash = store.put(b"secret", sha).unwrap();
+        assert_eq!(hash, hex::encode(Sha256::digest(b"secret")));
+        assert_eq!(store.get(&hash, sha).unwrap(), Some(b"secret".to_vec()));
+        assert_eq!(store.get(&hash_lock(b"other", sha), sha).unwrap(), None);
+
+        // Only the bot's user can read a secret
+        let mode = std::fs::metadata(dir.join(&hash))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let keccak = HashAlgo::Keccak256;
+        let (secret, hash) = store.generate(keccak).unwrap();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(hash, hex::encode(Keccak256::digest(&secret)));
+        assert_eq!(store.get(&hash, keccak).unwrap(), Some(secret));
+        assert!(store.get(&hash, sha).is_err());
+
+        // A secret filed under the wrong hash lock is refused
+        let other = hash_lock(b"other", sha);
+        std::fs::write(dir.join(&other), hex::encode(b"secret")).unwrap();
+        assert!(store.get(&other, sha).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
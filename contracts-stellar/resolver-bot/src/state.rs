@@ -0,0 +1,213 @@
+//! Persistent bot state
+//!
+//! Open positions, the auctions still being watched and the event cursor
+//! live in one JSON file, rewritten atomically after every change, so a
+//! restarted bot picks up its commitments where it left them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// What the bot owes on the destination chain
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub chain: String,
+    /// Oracle symbol of the asset delivered
+    pub symbol: String,
+    pub amount: i128,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    /// HTLC escrow the bot locked; completed with the secret
+    Htlc {
+        /// Hex escrow id
+        escrow_id: String,
+        /// Hex hash lock of the secret
+        hash_lock: String,
+        delivery: Delivery,
+    },
+    /// Auction the bot accepted; settled once the order's escrow exists,
+    /// then withdrawn from with the secret
+    Auction {
+        id: u64,
+        /// Hex order hash
+        order_hash: String,
+        /// Stellar token and amount the order's escrow must hold
+        token: String,
+        amount: i128,
+        delivery: Delivery,
+        settled: bool,
+    },
+}
+
+impl Position {
+    pub fn key(&self) -> String {
+        match self {
+            Position::Htlc { escrow_id, .. } => format!("htlc:{escrow_id}"),
+            Position::Auction { id, .. } => format!("auction:{id}"),
+        }
+    }
+}
+
+/// Where the event watcher resumes
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// RPC paging token of the last event processed
+    pub event: Option<String>,
+    /// Ledger to start from while no event has been processed
+    pub ledger: Option<u32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Data {
+    cursor: Cursor,
+    /// Lowest auction id not looked at yet
+    next_auction: u64,
+    /// Open auctions the bot may still take as their price decays
+    auctions: BTreeSet<u64>,
+    positions: BTreeMap<String, Position>,
+}
+
+pub struct State {
+    path: PathBuf,
+    data: Data,
+}
+
+impl State {
+    /// Open the state file, starting empty if it doesn't exist yet
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let data = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Corrupt state file {}", path.display()))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Data {
+                next_auction: 1,
+                ..Data::default()
+            },
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(State { path, data })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.data)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn cursor(&self) -> &Cursor {
+        &self.data.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: Cursor) -> Result<()> {
+        self.data.cursor = cursor;
+        self.save()
+    }
+
+    pub fn next_auction(&self) -> u64 {
+        self.data.next_auction
+    }
+
+    /// Watch the auctions up to `count`, the latest id posted
+    pub fn watch_auctions(&mut self, count: u64) -> Result<()> {
+        if count < self.data.next_auction {
+            return Ok(());
+        }
+        self.data.auctions.extend(self.data.next_auction..=count);
+        self.data.next_auction = count + 1;
+        self.save()
+    }
+
+    pub fn watched_auctions(&self) -> Vec<u64> {
+        self.data.auctions.iter().copied().collect()
+    }
+
+    pub fn unwatch_auction(&mut self, id: u64) -> Result<()> {
+        if self.data.auctions.remove(&id) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn positions(&self) -> Vec<Position> {
+        self.data.positions.values().cloned().collect()
+    }
+
+    pub fn has_position(&self, key: &str) -> bool {
+        self.data.positions.contains_key(key)
+    }
+
+    /// Record a position, replacing the one with the same key
+    pub fn set_position(&mut self, position: Position) -> Result<()> {
+        self.data.positions.insert(position.key(), position);
+        self.save()
+    }
+
+    pub fn close_position(&mut self, key: &str) -> Result<()> {
+        if self.data.positions.remove(key).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_state_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("resolver-state-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut state = State::open(path.clone()).unwrap();
+        assert_eq!(state.next_auction(), 1);
+        state.watch_auctions(3).unwrap();
+        state.unwatch_auction(2).unwrap();
+        // Nothing new
+        state.watch_auctions(3).unwrap();
+
+        let position = Position::Auction {
+            id: 1,
+            order_hash: "ab".repeat(32),
+            token: "C...".into(),
+            amount: 10_000_000_000,
+            delivery: Delivery {
+                chain: "ETH".into(),
+                symbol: "ETH".into(),
+                amount: 40_000_000_000_000_000,
+            },
+            settled: false,
+        };
+        state.set_position(position.clone()).unwrap();
+        state
+            .set_cursor(Cursor {
+                event: Some("0000000001-0000000001".into()),
+                ledger: None,
+            })
+            .unwrap();
+
+        let mut state = State::open(path.clone()).unwrap();
+        assert_eq!(state.next_auction(), 4);
+        assert_eq!(state.watched_auctions(), [1, 3]);
+        assert!(state.has_position("auction:1"));
+        assert_eq!(state.positions(), [position]);
+        assert_eq!(
+            state.cursor().event.as_deref(),
+            Some("0000000001-0000000001")
+        );
+
+        state.close_position("auction:1").unwrap();
+        assert!(State::open(path.clone()).unwrap().positions().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+//! Watchers finding work for the bot
+//!
+//! HTLC escrows announce themselves with events, paged through from a
+//! persistent cursor; the bot considers each new escrow in an asset it
+//! takes and learns every secret a completion reveals. Auctions are
+//! found by their ids, which count up from one, and stay watched while
+//! open since a price the bot passes on may suit it further down.
+
+use anyhow::Result;
+use soroban_sdk::xdr::ScAddress;
+use stellhydra_indexer::{Decoded, Decoder, StellHydraEvent};
+use stellhydra_rpc::{address_string, Event, EventCursor};
+
+use crate::config::Config;
+use crate::secrets::HashAlgo;
+use crate::state::{Cursor, State};
+use crate::{contracts, Bot};
+
+/// What a page of HTLC events holds for the bot
+#[derive(Debug, Default, PartialEq)]
+pub struct Scan {
+    /// Ids of new escrows in assets the bot takes
+    pub created: Vec<Vec<u8>>,
+    /// Secrets revealed by completed escrows
+    pub secrets: Vec<Vec<u8>>,
+}
+
+/// New HTLC escrows since the cursor, with the secrets revealed meanwhile
+/// put in the store
+pub async fn poll_events(bot: &Bot, state: &mut State) -> Result<Vec<Vec<u8>>> {
+    let cursor = state.cursor().clone();
+    let start_ledger = match (
+        &cursor.event,
+        cursor.ledger,
+        bot.config.stellar.start_ledger,
+    ) {
+        (Some(_), _, _) => None,
+        (None, Some(ledger), _) | (None, None, Some(ledger)) => Some(ledger),
+        (None, None, None) => Some(bot.stellar.latest_ledger().await?),
+    };
+    let from = match (&cursor.event, start_ledger) {
+        (Some(id), _) => EventCursor::After(id),
+        (None, ledger) => EventCursor::Ledger(ledger.unwrap_or_default()),
+    };
+
+    let page = bot
+        .stellar
+        .events(&[bot.config.contracts.escrow.as_str()], from)
+        .await?;
+    let scan = scan(&bot.config, &page.events);
+    for secret in &scan.secrets {
+        // Kept under every hash lock it may open, on either chain
+        for algo in HashAlgo::ALL {
+            let hash_lock = bot.secrets.put(secret, algo)?;
+            tracing::debug!("Learned the secret of {hash_lock}");
+        }
+    }
+
+    let cursor = match page.events.last() {
+        Some(event) => Cursor {
+            event: Some(event.id.clone()),
+            ledger: None,
+        },
+        None => Cursor {
+            ledger: cursor.ledger.or(start_ledger),
+            ..cursor
+        },
+    };
+    state.set_cursor(cursor)?;
+    Ok(scan.created)
+}
+
+/// Add auctions posted since the last poll to the watched set
+pub async fn poll_auctions(bot: &Bot, state: &mut State) -> Result<()> {
+    let count = contracts::auction_count(&bot.stellar, &bot.config.contracts.dutch_auction).await?;
+    if count >= state.next_auction() {
+        tracing::info!("Auctions {} to {count} posted", state.next_auction());
+    }
+    state.watch_auctions(count)
+}
+
+pub fn scan(config: &Config, events: &[Event]) -> Scan {
+    let decoder = Decoder::new();
+
+    let mut scan = Scan::default();
+    for event in events {
+        if event.contract_id != config.contracts.escrow {
+            continue;
+        }
+
+        match decoder.decode(event) {
+            Some(Decoded {
+                event: StellHydraEvent::HtlcCreated(created),
+                ..
+            }) => {
+                let asset = address_string(&ScAddress::from(&created.asset));
+                if config.asset(&asset).is_some() {
+                    scan.created.push(created.escrow_id.iter().collect());
+                }
+            }
+            Some(Decoded {
+                event: StellHydraEvent::HtlcCompleted(completed),
+                ..
+            }) => scan.secrets.push(completed.secret.iter().collect()),
+            _ => {}
+        }
+    }
+    scan
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, EnvTestConfig};
+    use soroban_sdk::xdr::{Limits, ScVal, WriteXdr};
+    use soroban_sdk::{Address, Bytes, Env, TryFromVal, Val};
+    use stellhydra_events::escrow::{HtlcCompleted, HtlcCreated};
+
+    /// An event as RPC would return it
+    fn rpc_event<E: stellhydra_events::Event>(env: &Env, contract: &Address, event: E) -> Event {
+        let base64 = |val: Val| {
+            ScVal::try_from_val(env, &val)
+                .unwrap()
+                .to_xdr_base64(Limits::none())
+                .unwrap()
+        };
+
+        Event {
+            id: "0000000001-0000000001".into(),
+            ledger: 1,
+            contract_id: address_string(&ScAddress::from(contract)),
+            topic: event.topics(env).iter().map(base64).collect(),
+            value: base64(event.into_val(env)),
+        }
+    }
+
+    #[test]
+    fn test_scans_escrows_and_secrets() {
+        let env = Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+        let (escrow, token, other) = (
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        );
+        let mut config: Config =
+            toml::from_str(include_str!("../resolver-bot.example.toml")).unwrap();
+        config.contracts.escrow = address_string(&ScAddress::from(&escrow));
+        config.assets[0].token = address_string(&ScAddress::from(&token));
+
+        let created = |id: &[u8], asset: &Address| HtlcCreated {
+            escrow_id: Bytes::from_slice(&env, id),
+            maker: Address::generate(&env),
+            amount: 1_000,
+            asset: asset.clone(),
+        };
+        let events = [
+            rpc_event(&env, &escrow, created(b"taken", &token)),
+            // An asset the bot doesn't take
+            rpc_event(&env, &escrow, created(b"skipped", &other)),
+            // The right event from the wrong contract
+            rpc_event(&env, &other, created(b"forged", &token)),
+            rpc_event(
+                &env,
+                &escrow,
+                HtlcCompleted {
+                    escrow_id: Bytes::from_slice(&env, b"done"),
+                    resolver: Address::generate(&env),
+                    secret: Bytes::from_slice(&env, b"secret"),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            scan(&config, &events),
+            Scan {
+                created: vec![b"taken".to_vec()],
+                secrets: vec![b"secret".to_vec()],
+            }
+        );
+    }
+}
@@ -1,14 +1,53 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, Map, String, Symbol, Vec,
-    token::{self, TokenClient},
+    contract, contractclient, contractimpl, contracttype, symbol_short, token::TokenClient,
+    Address, Env, Map, String, Symbol, Vec,
 };
+use stellhydra_access::{allowance, guard, ownable, require_admin, require_not_paused};
+use stellhydra_events::router::RouterSwap;
+use stellhydra_events::Event;
+use stellhydra_math::{apply_bps, sub_bps};
+
+/// Venue adapter interface, served by the StellHydra order book and by
+/// any venue registered with the `"adapter"` DEX type
+#[contractclient(name = "DexAdapterClient")]
+pub trait DexAdapterInterface {
+    fn get_quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128;
+    fn swap(
+        env: Env,
+        taker: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+    ) -> i128;
+}
+
+/// Venue adapter for StellHydra liquidity pools
+#[contractclient(name = "LiquidityPoolClient")]
+pub trait LiquidityPoolInterface {
+    fn get_amount_out(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128;
+    fn swap(
+        env: Env,
+        user: Address,
+        token_in: Address,
+        amount_in: i128,
+        amount_out_min: i128,
+    ) -> i128;
+}
+
+/// Rebate contract paying stakers back part of the fees they pay
+#[contractclient(name = "RebateClient")]
+pub trait RebateInterface {
+    fn report_fee(env: Env, source: Address, user: Address, token: Address, fee: i128);
+}
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct RouteStep {
     pub dex: Address,
+    pub dex_type: String,
     pub token_in: Address,
     pub token_out: Address,
     pub fee_rate: u32,
@@ -27,15 +66,20 @@ pub struct SwapRoute {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DexInfo {
     pub dex_address: Address,
-    pub dex_type: String, // "stellarx", "aqua", "liquidity_pool"
+    pub dex_type: String, // "stellarx", "aqua", "liquidity_pool", "orderbook", "adapter"
     pub active: bool,
     pub fee_rate: u32,
 }
 
 // Storage keys
-const ADMIN: Symbol = symbol_short!("ADMIN");
 const DEXES: Symbol = symbol_short!("DEXES");
 const PRICE_ORACLE: Symbol = symbol_short!("ORACLE");
+const REBATES: Symbol = symbol_short!("REBATES");
+
+// DEX types served through the adapter interface, and the liquidity pool
+const ORDER_BOOK_DEX: &str = "orderbook";
+const ADAPTER_DEX: &str = "adapter";
+const LIQUIDITY_POOL_DEX: &str = "liquidity_pool";
 
 #[contract]
 pub struct Router;
@@ -44,13 +88,13 @@ pub struct Router;
 impl Router {
     /// Initialize the router
     pub fn initialize(env: Env, admin: Address, price_oracle: Address) {
-        if env.storage().instance().has(&ADMIN) {
+        if ownable::has_admin(&env) {
             panic!("Router already initialized");
         }
 
         admin.require_auth();
 
-        env.storage().instance().set(&ADMIN, &admin);
+        ownable::init_admin(&env, &admin);
         env.storage().instance().set(&PRICE_ORACLE, &price_oracle);
 
         // Initialize empty DEX registry
@@ -72,8 +116,7 @@ impl Router {
         dex_type: String,
         fee_rate: u32,
     ) {
-        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
+        require_admin(&env);
 
         let mut dexes: Map<String, DexInfo> = env
             .storage()
@@ -109,6 +152,7 @@ impl Router {
         deadline: u64,
     ) -> i128 {
         user.require_auth();
+        require_not_paused(&env);
 
         // Check deadline
         if env.ledger().timestamp() > deadline {
@@ -116,29 +160,53 @@ impl Router {
         }
 
         // Find optimal route
-        let route = Self::find_best_route(
-            env.clone(),
-            token_in.clone(),
-            token_out.clone(),
-            amount_in,
-        );
+        let route =
+            Self::find_best_route(env.clone(), token_in.clone(), token_out.clone(), amount_in);
 
         if route.expected_output < amount_out_min {
             panic!("Insufficient output amount");
         }
 
-        // Execute the route
-        let final_amount = Self::execute_route(env.clone(), user.clone(), amount_in, route);
+        // Execute the route, counting what actually reached the user:
+        // tokens taking a fee on transfer deliver less than venues report.
+        // Venues get control here, so none of them can route back through
+        // the router mid-swap
+        let token_out_client = TokenClient::new(&env, &token_out);
+        let balance_before = token_out_client.balance(&user);
+        guard::enter(&env);
+
+        // A user who granted the router an allowance trades through the
+        // router's own account: the router pulls the input once, takes it
+        // through the venues and forwards the output
+        let router = env.current_contract_address();
+        let token_in_client = TokenClient::new(&env, &token_in);
+        let held_in = token_in_client.balance(&router);
+        if allowance::pull_permitted(&env, &token_in, &user, amount_in) {
+            let received = token_in_client.balance(&router) - held_in;
+            let held_out = token_out_client.balance(&router);
+            Self::execute_route(env.clone(), user.clone(), router.clone(), received, route);
+            let amount_out = token_out_client.balance(&router) - held_out;
+            token_out_client.transfer(&router, &user, &amount_out);
+        } else {
+            Self::execute_route(env.clone(), user.clone(), user.clone(), amount_in, route);
+        }
+
+        guard::exit(&env);
+        let final_amount = token_out_client.balance(&user) - balance_before;
 
         if final_amount < amount_out_min {
             panic!("Slippage exceeded");
         }
 
         // Emit swap event
-        env.events().publish(
-            (symbol_short!("ROUTER"), symbol_short!("SWAP")),
-            (user, token_in, token_out, amount_in, final_amount),
-        );
+        RouterSwap {
+            user,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out: final_amount,
+        }
+        .publish(&env);
 
         final_amount
     }
@@ -164,7 +232,7 @@ impl Router {
         };
 
         // Try direct swaps on each DEX
-        for (dex_id, dex_info) in dexes.iter() {
+        for dex_info in dexes.values().iter() {
             if !dex_info.active {
                 continue;
             }
@@ -172,7 +240,7 @@ impl Router {
             // Get quote from DEX
             let quote = Self::get_dex_quote(
                 env.clone(),
-                dex_info.dex_address.clone(),
+                &dex_info,
                 token_in.clone(),
                 token_out.clone(),
                 amount_in,
@@ -182,6 +250,7 @@ impl Router {
                 let mut steps = Vec::new(&env);
                 steps.push_back(RouteStep {
                     dex: dex_info.dex_address.clone(),
+                    dex_type: dex_info.dex_type.clone(),
                     token_in: token_in.clone(),
                     token_out: token_out.clone(),
                     fee_rate: dex_info.fee_rate,
@@ -190,7 +259,7 @@ impl Router {
                 best_route = SwapRoute {
                     steps,
                     expected_output: quote,
-                    minimum_output: quote * (10000 - 300) / 10000, // 3% slippage
+                    minimum_output: sub_bps(&env, quote, 300).unwrap_or(0), // 3% slippage
                     slippage_tolerance: 300,
                 };
             }
@@ -202,17 +271,36 @@ impl Router {
         best_route
     }
 
-    /// Execute a swap route
-    fn execute_route(env: Env, user: Address, amount_in: i128, route: SwapRoute) -> i128 {
+    /// Execute a swap route, trading from `taker`'s account
+    fn execute_route(
+        env: Env,
+        user: Address,
+        taker: Address,
+        amount_in: i128,
+        route: SwapRoute,
+    ) -> i128 {
         let mut current_amount = amount_in;
         let mut current_token = route.steps.get(0).unwrap().token_in;
 
+        let rebates = Self::get_rebate_contract(env.clone());
         for step in route.steps.iter() {
+            // Count the venue's fee towards the user's staker rebate
+            if let Some(rebates) = &rebates {
+                let fee = apply_bps(&env, current_amount, step.fee_rate).unwrap_or(0);
+                RebateClient::new(&env, rebates).report_fee(
+                    &env.current_contract_address(),
+                    &user,
+                    &current_token,
+                    &fee,
+                );
+            }
+
             // Execute swap on this DEX
             current_amount = Self::execute_dex_swap(
                 env.clone(),
-                user.clone(),
+                taker.clone(),
                 step.dex.clone(),
+                step.dex_type.clone(),
                 current_token.clone(),
                 step.token_out.clone(),
                 current_amount,
@@ -226,17 +314,39 @@ impl Router {
     /// Get quote from a specific DEX
     fn get_dex_quote(
         env: Env,
-        dex_address: Address,
+        dex_info: &DexInfo,
         token_in: Address,
         token_out: Address,
         amount_in: i128,
     ) -> i128 {
+        // Order books quote what their resting orders would fill; no
+        // liquidity for the pair, or a failing adapter, simply means no
+        // route through them
+        if Self::is_adapter(&env, &dex_info.dex_type) {
+            return match DexAdapterClient::new(&env, &dex_info.dex_address)
+                .try_get_quote(&token_in, &token_out, &amount_in)
+            {
+                Ok(Ok(quote)) => quote,
+                _ => 0,
+            };
+        }
+
+        // Pools not trading the pair, or without reserves, offer no route
+        if dex_info.dex_type == String::from_str(&env, LIQUIDITY_POOL_DEX) {
+            return match LiquidityPoolClient::new(&env, &dex_info.dex_address)
+                .try_get_amount_out(&token_in, &token_out, &amount_in)
+            {
+                Ok(Ok(quote)) => quote,
+                _ => 0,
+            };
+        }
+
         // This would call the specific DEX contract to get a quote
         // For now, we'll use a simple AMM formula as placeholder
-        
+
         // TODO: Implement actual DEX integration
         // This should call the appropriate DEX contract method
-        
+
         // Placeholder calculation (90% of input for demo)
         amount_in * 90 / 100
     }
@@ -246,29 +356,59 @@ impl Router {
         env: Env,
         user: Address,
         dex_address: Address,
+        dex_type: String,
         token_in: Address,
         token_out: Address,
         amount_in: i128,
     ) -> i128 {
+        // Venues pull the input from the taker themselves, so a router
+        // trading on its own account lets them move it
+        let custody = user == env.current_contract_address();
+
+        // Adapters pull the input from the user and pay out to them; the
+        // caller checks the output against the user's minimum
+        if Self::is_adapter(&env, &dex_type) {
+            if custody {
+                allowance::authorize_transfer(&env, &token_in, &dex_address, amount_in);
+            }
+            return DexAdapterClient::new(&env, &dex_address)
+                .swap(&user, &token_in, &token_out, &amount_in, &0);
+        }
+
+        // A pool only quotes the pair it trades, so its output is
+        // `token_out`; like the order book, it settles with the user directly
+        if dex_type == String::from_str(&env, LIQUIDITY_POOL_DEX) {
+            if custody {
+                allowance::authorize_transfer(&env, &token_in, &dex_address, amount_in);
+            }
+            return LiquidityPoolClient::new(&env, &dex_address)
+                .swap(&user, &token_in, &amount_in, &0);
+        }
+
         // This would call the specific DEX contract to execute the swap
         // For now, we'll use a placeholder implementation
-        
+
         // TODO: Implement actual DEX integration
         // This should call the appropriate DEX contract method
-        
+
         // Placeholder: transfer tokens and return calculated amount
         let token_in_client = TokenClient::new(&env, &token_in);
         let token_out_client = TokenClient::new(&env, &token_out);
-        
+
         let amount_out = amount_in * 90 / 100; // 10% fee placeholder
-        
+
         // In real implementation, this would be handled by the DEX contract
         token_in_client.transfer(&user, &dex_address, &amount_in);
         token_out_client.transfer(&dex_address, &user, &amount_out);
-        
+
         amount_out
     }
 
+    fn is_adapter(env: &Env, dex_type: &String) -> bool {
+        *dex_type == String::from_str(env, ORDER_BOOK_DEX)
+            || *dex_type == String::from_str(env, ADAPTER_DEX)
+    }
+
     /// Get quote for a swap
     pub fn get_amounts_out(
         env: Env,
@@ -288,10 +428,23 @@ impl Router {
             .unwrap_or_else(|| Map::new(&env))
     }
 
+    /// Set or clear the rebate contract swap fees are reported to
+    pub fn set_rebate_contract(env: Env, rebates: Option<Address>) {
+        require_admin(&env);
+
+        match rebates {
+            Some(address) => env.storage().instance().set(&REBATES, &address),
+            None => env.storage().instance().remove(&REBATES),
+        }
+    }
+
+    pub fn get_rebate_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&REBATES)
+    }
+
     /// Enable/disable a DEX
     pub fn set_dex_status(env: Env, dex_id: String, active: bool) {
-        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
+        require_admin(&env);
 
         let mut dexes: Map<String, DexInfo> = env
             .storage()
@@ -306,9 +459,305 @@ impl Router {
 
             // Emit status change event
             env.events().publish(
-                (symbol_short!("ROUTER"), symbol_short!("DEX_STATUS")),
+                (symbol_short!("ROUTER"), symbol_short!("DEX_STAT")),
                 (dex_id, active),
             );
         }
     }
-}
\ No newline at end of file
+}
+
+stellhydra_access::ownable!(Router);
+stellhydra_access::pausable!(Router);
+stellhydra_access::roles!(Router);
+stellhydra_access::allowance!(Router);
+
+impl stellhydra_access::Upgradeable for Router {}
+
+stellhydra_access::upgradeable!(Router);
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Env};
+    use stellhydra_mock_dex::{Curve, Failure, MockDex, MockDexClient};
+    use stellhydra_mock_token::{MockToken, MockTokenClient};
+
+    #[contract]
+    struct MockRebate;
+
+    #[contractimpl]
+    impl MockRebate {
+        pub fn report_fee(env: Env, source: Address, user: Address, token: Address, fee: i128) {
+            source.require_auth();
+            let key = (user, token);
+            let fees: i128 = env.storage().instance().get(&key).unwrap_or(0);
+            env.storage().instance().set(&key, &(fees + fee));
+        }
+
+        pub fn fees(env: Env, user: Address, token: Address) -> i128 {
+            env.storage().instance().get(&(user, token)).unwrap_or(0)
+        }
+    }
+
+    fn register_venue<'a>(
+        env: &Env,
+        router: &RouterClient,
+        id: &str,
+        dex_type: &str,
+    ) -> MockDexClient<'a> {
+        let dex = MockDexClient::new(env, &env.register_contract(None, MockDex));
+        router.register_dex(
+            &String::from_str(env, id),
+            &dex.address,
+            &String::from_str(env, dex_type),
+            &30,
+        );
+        dex
+    }
+
+    #[test]
+    fn test_order_book_competes_for_routed_flow() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+
+        let router = RouterClient::new(&env, &env.register_contract(None, Router));
+        let admin = Address::generate(&env);
+        router.initialize(&admin, &Address::generate(&env));
+        router.register_dex(
+            &String::from_str(&env, "aqua"),
+            &Address::generate(&env),
+            &String::from_str(&env, "aqua"),
+            &30,
+        );
+        let book_client = register_venue(&env, &router, "book", ORDER_BOOK_DEX);
+        let book = book_client.address.clone();
+
+        // An empty book is skipped rather than failing the route
+        let route = router.find_best_route(&token_in, &token_out, &1_000);
+        assert_eq!(route.expected_output, 900);
+        assert_ne!(route.steps.get(0).unwrap().dex, book);
+
+        book_client.set_curve(&token_in, &token_out, &Curve::Linear(9_500));
+        let route = router.find_best_route(&token_in, &token_out, &1_000);
+        assert_eq!(route.expected_output, 950);
+        assert_eq!(route.steps.get(0).unwrap().dex, book);
+
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_in).mint(&user, &1_000);
+        StellarAssetClient::new(&env, &token_out).mint(&book, &1_000);
+        router.pause(&admin);
+        assert!(router
+            .try_swap_exact_tokens_for_tokens(&user, &1_000, &940, &token_in, &token_out, &100)
+            .is_err());
+        router.unpause();
+        assert_eq!(
+            router.swap_exact_tokens_for_tokens(&user, &1_000, &940, &token_in, &token_out, &100),
+            950
+        );
+        assert_eq!(TokenClient::new(&env, &token_out).balance(&user), 950);
+        assert_eq!(TokenClient::new(&env, &token_in).balance(&book), 1_000);
+    }
+
+    #[test]
+    fn test_minimum_output_applies_to_what_the_user_receives() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_out = MockTokenClient::new(&env, &env.register_contract(None, MockToken));
+        token_out.initialize(
+            &String::from_str(&env, "Fee Token"),
+            &String::from_str(&env, "FEE"),
+            &7,
+        );
+        token_out.set_transfer_fee(&100);
+
+        let router = RouterClient::new(&env, &env.register_contract(None, Router));
+        router.initialize(&Address::generate(&env), &Address::generate(&env));
+        let book = register_venue(&env, &router, "book", ORDER_BOOK_DEX);
+        book.set_curve(&token_in, &token_out.address, &Curve::Linear(9_500));
+
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_in).mint(&user, &2_000);
+        token_out.mint(&book.address, &2_000);
+
+        // The book pays 950, of which the token takes 1% on the way
+        let too_tight = router.try_swap_exact_tokens_for_tokens(
+            &user,
+            &1_000,
+            &945,
+            &token_in,
+            &token_out.address,
+            &100,
+        );
+        assert!(too_tight.is_err());
+        assert_eq!(token_out.balance(&user), 0);
+
+        let received = router.swap_exact_tokens_for_tokens(
+            &user,
+            &1_000,
+            &940,
+            &token_in,
+            &token_out.address,
+            &100,
+        );
+        assert_eq!(received, 941);
+        assert_eq!(token_out.balance(&user), 941);
+    }
+
+    #[test]
+    fn test_best_quote_wins_as_trade_size_changes() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let router = RouterClient::new(&env, &env.register_contract(None, Router));
+        router.initialize(&Address::generate(&env), &Address::generate(&env));
+
+        // A shallow pool at a better price, and a flat 95% venue
+        let shallow = register_venue(&env, &router, "shallow", ADAPTER_DEX);
+        shallow.set_curve(
+            &token_in,
+            &token_out,
+            &Curve::ConstantProduct(10_000, 10_000),
+        );
+        let flat = register_venue(&env, &router, "flat", ADAPTER_DEX);
+        flat.set_curve(&token_in, &token_out, &Curve::Linear(9_500));
+
+        let small = router.find_best_route(&token_in, &token_out, &100);
+        assert_eq!(small.steps.get(0).unwrap().dex, shallow.address);
+        assert_eq!(small.expected_output, 99);
+
+        // Price impact hands large trades to the flat venue
+        let large = router.find_best_route(&token_in, &token_out, &5_000);
+        assert_eq!(large.steps.get(0).unwrap().dex, flat.address);
+        assert_eq!(large.expected_output, 4_750);
+
+        // Venues without a curve for the reverse pair offer no route
+        let reverse = router.find_best_route(&token_out, &token_in, &100);
+        assert_eq!(reverse.expected_output, 0);
+        assert!(reverse.steps.is_empty());
+    }
+
+    #[test]
+    fn test_failing_venues_are_skipped_or_revert_the_swap() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let router = RouterClient::new(&env, &env.register_contract(None, Router));
+        router.initialize(&Address::generate(&env), &Address::generate(&env));
+
+        let best = register_venue(&env, &router, "best", ADAPTER_DEX);
+        best.set_curve(&token_in, &token_out, &Curve::Linear(9_900));
+        let backup = register_venue(&env, &router, "backup", ADAPTER_DEX);
+        backup.set_curve(&token_in, &token_out, &Curve::Linear(9_700));
+        StellarAssetClient::new(&env, &token_out).mint(&best.address, &10_000);
+        StellarAssetClient::new(&env, &token_out).mint(&backup.address, &10_000);
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_in).mint(&user, &3_000);
+
+        // A venue that cannot quote drops out of the search
+        best.set_failure(&Failure::Quote);
+        assert_eq!(router.get_amounts_out(&1_000, &token_in, &token_out), 970);
+
+        // One that quotes but fails to fill reverts the whole swap
+        best.set_failure(&Failure::Swap);
+        assert!(router
+            .try_swap_exact_tokens_for_tokens(&user, &1_000, &0, &token_in, &token_out, &100)
+            .is_err());
+        assert_eq!(TokenClient::new(&env, &token_in).balance(&user), 3_000);
+
+        // Filling 2% worse than the 990 quoted is caught by the user's
+        // minimum
+        best.set_failure(&Failure::None);
+        best.set_slippage(&200);
+        assert!(router
+            .try_swap_exact_tokens_for_tokens(&user, &1_000, &980, &token_in, &token_out, &100)
+            .is_err());
+        assert_eq!(
+            router.swap_exact_tokens_for_tokens(&user, &1_000, &960, &token_in, &token_out, &100),
+            971
+        );
+        assert_eq!(best.get_swap_count(), 1);
+        assert_eq!(backup.get_swap_count(), 0);
+    }
+
+    #[test]
+    fn test_venue_fees_are_reported_for_rebates() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let router = RouterClient::new(&env, &env.register_contract(None, Router));
+        router.initialize(&Address::generate(&env), &Address::generate(&env));
+        let rebates = MockRebateClient::new(&env, &env.register_contract(None, MockRebate));
+        router.set_rebate_contract(&Some(rebates.address.clone()));
+
+        let dex = register_venue(&env, &router, "dex", ADAPTER_DEX);
+        dex.set_curve(&token_in, &token_out, &Curve::Linear(9_900));
+        StellarAssetClient::new(&env, &token_out).mint(&dex.address, &10_000);
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &token_in).mint(&user, &2_000);
+
+        // The venue charges 30 bps of the 1_000 in
+        router.swap_exact_tokens_for_tokens(&user, &1_000, &0, &token_in, &token_out, &100);
+        assert_eq!(rebates.fees(&user, &token_in), 3);
+
+        router.set_rebate_contract(&None);
+        router.swap_exact_tokens_for_tokens(&user, &1_000, &0, &token_in, &token_out, &100);
+        assert_eq!(rebates.fees(&user, &token_in), 3);
+    }
+
+    #[test]
+    fn test_scenario_routes_through_registered_adapters() {
+        let env = Env::default();
+        let dex = MockDexClient::new(&env, &env.register_contract(None, MockDex));
+        let scenario = testutils::RouterScenario::new(&env).with_adapter("dex", &dex.address);
+        dex.set_curve(
+            &scenario.token_in,
+            &scenario.token_out,
+            &Curve::Linear(9_900),
+        );
+        StellarAssetClient::new(&env, &scenario.token_out).mint(&dex.address, &10_000);
+
+        let trader = scenario.funded_user(1_000);
+        let out = scenario.router.swap_exact_tokens_for_tokens(
+            &trader,
+            &1_000,
+            &990,
+            &scenario.token_in,
+            &scenario.token_out,
+            &60,
+        );
+        assert_eq!(out, 990);
+        assert_eq!(dex.get_swap_count(), 1);
+    }
+}
@@ -0,0 +1,84 @@
+//! Scenario builders for tests against the router
+//!
+//! Deploys the router for a pair of Stellar assets and registers venues
+//! quoting that pair:
+//!
+//! ```ignore
+//! let scenario = RouterScenario::new(&env).with_pool("pool", &pool_address);
+//! let trader = scenario.funded_user(10_000);
+//! scenario.router.swap_exact_tokens_for_tokens(
+//!     &trader, &10_000, &0, &scenario.token_in, &scenario.token_out, &deadline,
+//! );
+//! ```
+//!
+//! All authorizations are mocked.
+
+use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Address, Env, String};
+
+use crate::{Router, RouterClient, ADAPTER_DEX, LIQUIDITY_POOL_DEX};
+
+/// Fee recorded for the venues the scenario registers, in basis points
+pub const VENUE_FEE_BPS: u32 = 30;
+
+/// The router deployed with the pair it routes
+pub struct RouterScenario<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub router: RouterClient<'a>,
+}
+
+impl RouterScenario<'_> {
+    /// Deploy the router; the oracle it is given is a bare address, as
+    /// routing doesn't consult it
+    pub fn new(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let admin = Address::generate(env);
+        let token_in = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let token_out = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let router = RouterClient::new(env, &env.register_contract(None, Router));
+        router.initialize(&admin, &Address::generate(env));
+
+        RouterScenario {
+            env: env.clone(),
+            admin,
+            token_in,
+            token_out,
+            router,
+        }
+    }
+
+    /// Register a venue of any type the router knows
+    pub fn with_venue(self, id: &str, venue: &Address, dex_type: &str) -> Self {
+        self.router.register_dex(
+            &String::from_str(&self.env, id),
+            venue,
+            &String::from_str(&self.env, dex_type),
+            &VENUE_FEE_BPS,
+        );
+        self
+    }
+
+    /// Register a StellHydra liquidity pool
+    pub fn with_pool(self, id: &str, pool: &Address) -> Self {
+        self.with_venue(id, pool, LIQUIDITY_POOL_DEX)
+    }
+
+    /// Register a venue serving the adapter interface
+    pub fn with_adapter(self, id: &str, adapter: &Address) -> Self {
+        self.with_venue(id, adapter, ADAPTER_DEX)
+    }
+
+    /// A fresh account holding `amount` of the input token
+    pub fn funded_user(&self, amount: i128) -> Address {
+        let user = Address::generate(&self.env);
+        StellarAssetClient::new(&self.env, &self.token_in).mint(&user, &amount);
+        user
+    }
+}
@@ -0,0 +1,519 @@
+//! Soroban RPC client shared by the StellHydra off-chain tools
+//!
+//! Reads are simulated calls; writes follow the usual RPC flow: simulate
+//! to learn the footprint, resource fee and authorizations, sign the
+//! assembled transaction with the client's account, send it and wait for
+//! the result. Contract events are paged through `getEvents`.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{
+    AccountId, ContractExecutable, ContractIdPreimage, ContractIdPreimageFromAddress,
+    CreateContractArgs, DecoratedSignature, Hash, HostFunction, InvokeContractArgs,
+    InvokeHostFunctionOp, LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, Memo, MuxedAccount,
+    Operation, OperationBody, Preconditions, PublicKey, ReadXdr, ScAddress, ScMapEntry, ScSymbol,
+    ScVal, SequenceNumber, Signature, SignatureHint, SorobanAuthorizationEntry,
+    SorobanTransactionData, Transaction, TransactionEnvelope, TransactionExt,
+    TransactionSignaturePayload, TransactionSignaturePayloadTaggedTransaction,
+    TransactionV1Envelope, Uint256, WriteXdr,
+};
+
+/// How long to wait for a sent transaction to land
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `getEvents` limits
+const MAX_FILTERS: usize = 5;
+const MAX_FILTER_CONTRACTS: usize = 5;
+
+/// Event as `getEvents` returns it
+#[derive(Clone, Debug, Deserialize)]
+pub struct Event {
+    /// Paging token, also a unique id
+    pub id: String,
+    pub ledger: u32,
+    #[serde(rename = "contractId")]
+    pub contract_id: String,
+    /// Base64 XDR topics
+    pub topic: Vec<String>,
+    /// Base64 XDR data
+    pub value: String,
+}
+
+pub struct EventPage {
+    pub events: Vec<Event>,
+    pub latest_ledger: u32,
+}
+
+/// Where to read events from
+pub enum EventCursor<'a> {
+    Ledger(u32),
+    After(&'a str),
+}
+
+/// Result of a submitted call
+pub struct Submitted {
+    /// Hex transaction hash
+    pub hash: String,
+    /// Value the call returned in simulation
+    pub result: ScVal,
+}
+
+pub fn parse_contract(contract: &str) -> Result<[u8; 32]> {
+    Ok(stellar_strkey::Contract::from_string(contract)
+        .with_context(|| format!("Invalid contract id {contract}"))?
+        .0)
+}
+
+/// Address from its strkey, an account (G...) or a contract (C...)
+pub fn parse_address(address: &str) -> Result<ScAddress> {
+    match stellar_strkey::Strkey::from_string(address) {
+        Ok(stellar_strkey::Strkey::PublicKeyEd25519(key)) => Ok(ScAddress::Account(AccountId(
+            PublicKey::PublicKeyTypeEd25519(Uint256(key.0)),
+        ))),
+        Ok(stellar_strkey::Strkey::Contract(contract)) => Ok(ScAddress::Contract(Hash(contract.0))),
+        _ => bail!("Invalid address {address}"),
+    }
+}
+
+/// Strkey of an address, as configs and manifests name accounts and
+/// contracts
+pub fn address_string(address: &ScAddress) -> String {
+    match address {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key)))) => {
+            stellar_strkey::ed25519::PublicKey(*key).to_string()
+        }
+        ScAddress::Contract(Hash(id)) => stellar_strkey::Contract(*id).to_string(),
+    }
+}
+
+/// Field of a contract struct returned as an `ScVal` map
+pub fn field<'a>(value: &'a ScVal, name: &str) -> Option<&'a ScVal> {
+    let ScVal::Map(Some(map)) = value else {
+        return None;
+    };
+    map.iter()
+        .find(|ScMapEntry { key, .. }| {
+            matches!(key, ScVal::Symbol(symbol) if symbol.0.as_slice() == name.as_bytes())
+        })
+        .map(|entry| &entry.val)
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    url: String,
+    network_id: Hash,
+    base_fee: u32,
+    key: Option<SigningKey>,
+}
+
+impl Client {
+    /// Client for reading events; use [`Client::with_secret_key`] to read
+    /// contracts and submit transactions
+    pub fn new(rpc_url: &str, network_passphrase: &str, base_fee: u32) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            url: rpc_url.into(),
+            network_id: Hash(Sha256::digest(network_passphrase.as_bytes()).into()),
+            base_fee,
+            key: None,
+        }
+    }
+
+    /// Sign with the account of a secret key (S...)
+    pub fn with_secret_key(mut self, secret_key: &str) -> Result<Self> {
+        let seed = stellar_strkey::ed25519::PrivateKey::from_string(secret_key)
+            .context("Invalid Stellar secret key")?;
+        self.key = Some(SigningKey::from_bytes(&seed.0));
+        Ok(self)
+    }
+
+    fn key(&self) -> Result<&SigningKey> {
+        self.key
+            .as_ref()
+            .ok_or_else(|| anyhow!("The RPC client has no signing key"))
+    }
+
+    fn account_id(&self) -> Result<AccountId> {
+        Ok(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            self.key()?.verifying_key().to_bytes(),
+        ))))
+    }
+
+    /// The signing account
+    pub fn account(&self) -> Result<ScAddress> {
+        Ok(ScAddress::Account(self.account_id()?))
+    }
+
+    async fn rpc<T: for<'de> Deserialize<'de>>(&self, method: &str, params: Value) -> Result<T> {
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            bail!(
+                "{method} failed: {}",
+                error["message"].as_str().unwrap_or("unknown error")
+            );
+        }
+        Ok(serde_json::from_value(response["result"].clone())?)
+    }
+
+    pub async fn latest_ledger(&self) -> Result<u32> {
+        #[derive(Deserialize)]
+        struct Latest {
+            sequence: u32,
+        }
+        Ok(self
+            .rpc::<Latest>("getLatestLedger", json!({}))
+            .await?
+            .sequence)
+    }
+
+    /// Events of the given contracts, oldest first
+    pub async fn events(&self, contracts: &[&str], cursor: EventCursor<'_>) -> Result<EventPage> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Page {
+            events: Vec<Event>,
+            latest_ledger: u32,
+        }
+
+        // RPC takes up to five filters of five contracts each
+        if contracts.len() > MAX_FILTERS * MAX_FILTER_CONTRACTS {
+            bail!(
+                "Can't read the events of more than {} contracts at once",
+                MAX_FILTERS * MAX_FILTER_CONTRACTS
+            );
+        }
+        let filters: Vec<Value> = contracts
+            .chunks(MAX_FILTER_CONTRACTS)
+            .map(|contracts| json!({ "type": "contract", "contractIds": contracts }))
+            .collect();
+        let params = match cursor {
+            EventCursor::Ledger(ledger) => json!({
+                "startLedger": ledger,
+                "filters": filters,
+                "pagination": { "limit": 100 },
+            }),
+            EventCursor::After(id) => json!({
+                "filters": filters,
+                "pagination": { "cursor": id, "limit": 100 },
+            }),
+        };
+
+        let page: Page = self.rpc("getEvents", params).await?;
+        Ok(EventPage {
+            events: page.events,
+            latest_ledger: page.latest_ledger,
+        })
+    }
+
+    /// Call a contract function without submitting, for reads
+    pub async fn read(
+        &self,
+        contract: &[u8; 32],
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<ScVal> {
+        let tx = self.transaction(invoke_contract(contract, function, args)?, 0)?;
+        let simulation = self.simulate(&tx).await?;
+        simulation.result()
+    }
+
+    /// Current address of a contract in a deployment registry
+    pub async fn resolve(&self, registry: &str, name: &str) -> Result<String> {
+        let args = vec![ScVal::Symbol(ScSymbol(name.try_into()?))];
+        match self
+            .read(&parse_contract(registry)?, "resolve", args)
+            .await?
+        {
+            ScVal::Address(address) => Ok(address_string(&address)),
+            _ => bail!("{name} is not in the registry"),
+        }
+    }
+
+    /// Invoke a contract function from the signing account and wait for it
+    /// to succeed
+    pub async fn invoke(
+        &self,
+        contract: &[u8; 32],
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<Submitted> {
+        self.submit(invoke_contract(contract, function, args)?)
+            .await
+    }
+
+    /// Upload contract wasm, returning its hash
+    pub async fn upload_wasm(&self, wasm: &[u8]) -> Result<[u8; 32]> {
+        let submitted = self
+            .submit(HostFunction::UploadContractWasm(wasm.try_into()?))
+            .await?;
+        match submitted.result {
+            ScVal::Bytes(hash) => Ok(hash.as_slice().try_into()?),
+            _ => bail!("Upload returned no wasm hash"),
+        }
+    }
+
+    /// Deploy a contract from uploaded wasm; the id follows from the
+    /// signing account and the salt
+    pub async fn deploy(&self, wasm_hash: &[u8; 32], salt: &[u8; 32]) -> Result<ScAddress> {
+        let function = HostFunction::CreateContract(CreateContractArgs {
+            contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                address: self.account()?,
+                salt: Uint256(*salt),
+            }),
+            executable: ContractExecutable::Wasm(Hash(*wasm_hash)),
+        });
+        match self.submit(function).await?.result {
+            ScVal::Address(address) => Ok(address),
+            _ => bail!("Deployment returned no address"),
+        }
+    }
+
+    async fn submit(&self, function: HostFunction) -> Result<Submitted> {
+        let sequence = self.sequence().await?;
+        let mut tx = self.transaction(function, sequence + 1)?;
+        let simulation = self.simulate(&tx).await?;
+        let result = simulation.result()?;
+
+        let auth = simulation
+            .results
+            .first()
+            .map(|result| {
+                result
+                    .auth
+                    .iter()
+                    .map(|entry| SorobanAuthorizationEntry::from_xdr_base64(entry, Limits::none()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let resource_fee: u32 = simulation.min_resource_fee.parse()?;
+        let data =
+            SorobanTransactionData::from_xdr_base64(&simulation.transaction_data, Limits::none())?;
+
+        let mut operations = tx.operations.to_vec();
+        if let OperationBody::InvokeHostFunction(op) = &mut operations[0].body {
+            op.auth = auth.try_into()?;
+        }
+        tx.operations = operations.try_into()?;
+        tx.fee = self.base_fee + resource_fee;
+        tx.ext = TransactionExt::V1(data);
+
+        let (envelope, hash) = self.sign(tx)?;
+        self.send(&envelope, &hash).await?;
+        Ok(Submitted { hash, result })
+    }
+
+    async fn sequence(&self) -> Result<i64> {
+        #[derive(Deserialize)]
+        struct Entries {
+            entries: Vec<Entry>,
+        }
+        #[derive(Deserialize)]
+        struct Entry {
+            xdr: String,
+        }
+
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: self.account_id()?,
+        })
+        .to_xdr_base64(Limits::none())?;
+        let result: Entries = self
+            .rpc("getLedgerEntries", json!({ "keys": [key] }))
+            .await?;
+        let entry = result
+            .entries
+            .first()
+            .ok_or_else(|| anyhow!("Signing account not found"))?;
+
+        match LedgerEntryData::from_xdr_base64(&entry.xdr, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => bail!("Unexpected ledger entry for the signing account"),
+        }
+    }
+
+    fn transaction(&self, function: HostFunction, sequence: i64) -> Result<Transaction> {
+        let op = InvokeHostFunctionOp {
+            host_function: function,
+            auth: Default::default(),
+        };
+
+        Ok(Transaction {
+            source_account: MuxedAccount::Ed25519(Uint256(self.key()?.verifying_key().to_bytes())),
+            fee: self.base_fee,
+            seq_num: SequenceNumber(sequence),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: vec![Operation {
+                source_account: None,
+                body: OperationBody::InvokeHostFunction(op),
+            }]
+            .try_into()?,
+            ext: TransactionExt::V0,
+        })
+    }
+
+    async fn simulate(&self, tx: &Transaction) -> Result<Simulation> {
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: tx.clone(),
+            signatures: Default::default(),
+        });
+        let simulation: Simulation = self
+            .rpc(
+                "simulateTransaction",
+                json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+            )
+            .await?;
+
+        if let Some(error) = &simulation.error {
+            bail!("Simulation failed: {error}");
+        }
+        Ok(simulation)
+    }
+
+    /// Sign a transaction, returning the envelope and the hex hash
+    fn sign(&self, tx: Transaction) -> Result<(TransactionEnvelope, String)> {
+        let key = self.key()?;
+        let payload = TransactionSignaturePayload {
+            network_id: self.network_id.clone(),
+            tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+        };
+        let hash: [u8; 32] = Sha256::digest(payload.to_xdr(Limits::none())?).into();
+
+        let public_key = key.verifying_key().to_bytes();
+        let signature = DecoratedSignature {
+            hint: SignatureHint(public_key[28..].try_into()?),
+            signature: Signature(key.sign(&hash).to_bytes().to_vec().try_into()?),
+        };
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: vec![signature].try_into()?,
+        });
+
+        Ok((envelope, hex::encode(hash)))
+    }
+
+    async fn send(&self, envelope: &TransactionEnvelope, hash: &str) -> Result<()> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Sent {
+            status: String,
+            error_result_xdr: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Status {
+            status: String,
+        }
+
+        let sent: Sent = self
+            .rpc(
+                "sendTransaction",
+                json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+            )
+            .await?;
+        if sent.status != "PENDING" && sent.status != "DUPLICATE" {
+            bail!(
+                "Transaction {hash} rejected ({}): {}",
+                sent.status,
+                sent.error_result_xdr.unwrap_or_default()
+            );
+        }
+
+        let deadline = tokio::time::Instant::now() + CONFIRM_TIMEOUT;
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let status: Status = self.rpc("getTransaction", json!({ "hash": hash })).await?;
+            match status.status.as_str() {
+                "SUCCESS" => return Ok(()),
+                "FAILED" => bail!("Transaction {hash} failed"),
+                _ => continue,
+            }
+        }
+        bail!("Transaction {hash} not confirmed in time")
+    }
+}
+
+fn invoke_contract(contract: &[u8; 32], function: &str, args: Vec<ScVal>) -> Result<HostFunction> {
+    Ok(HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: ScAddress::Contract(Hash(*contract)),
+        function_name: ScSymbol(function.try_into()?),
+        args: args.try_into()?,
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Simulation {
+    error: Option<String>,
+    #[serde(default)]
+    transaction_data: String,
+    #[serde(default)]
+    min_resource_fee: String,
+    #[serde(default)]
+    results: Vec<SimulationResult>,
+}
+
+#[derive(Deserialize)]
+struct SimulationResult {
+    #[serde(default)]
+    auth: Vec<String>,
+    xdr: String,
+}
+
+impl Simulation {
+    fn result(&self) -> Result<ScVal> {
+        let result = self
+            .results
+            .first()
+            .ok_or_else(|| anyhow!("Simulation returned no result"))?;
+        Ok(ScVal::from_xdr_base64(&result.xdr, Limits::none())?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_addresses_round_trip_through_strkeys() {
+        for address in [
+            "GBXPKLRTMHH3NWEE32YSLZMRSBBQ6ITJCME7FK3P5SB7XEKRNJN2F7IS",
+            "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+        ] {
+            assert_eq!(address_string(&parse_address(address).unwrap()), address);
+        }
+        assert!(parse_address("SB...").is_err());
+        assert!(
+            parse_contract("GBXPKLRTMHH3NWEE32YSLZMRSBBQ6ITJCME7FK3P5SB7XEKRNJN2F7IS").is_err()
+        );
+    }
+
+    #[test]
+    fn test_reads_struct_fields() {
+        let value = ScVal::Map(Some(
+            vec![ScMapEntry {
+                key: ScVal::Symbol("status".try_into().unwrap()),
+                val: ScVal::U32(2),
+            }]
+            .try_into()
+            .unwrap(),
+        ));
+
+        assert_eq!(field(&value, "status"), Some(&ScVal::U32(2)));
+        assert_eq!(field(&value, "amount"), None);
+        assert_eq!(field(&ScVal::Void, "status"), None);
+    }
+}
@@ -25,4 +25,4 @@ impl SimpleTest {
     pub fn get_count(env: Env) -> u32 {
         env.storage().instance().get(&COUNTER).unwrap_or(0)
     }
-}
\ No newline at end of file
+}
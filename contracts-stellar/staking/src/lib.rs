@@ -0,0 +1,650 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+    Vec,
+};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakingConfig {
+    pub staking_token: Address, // protocol token being staked
+    pub reward_token: Address,  // token rewards are paid in
+    pub unbonding_period: u64,  // seconds unstaked tokens stay locked
+}
+
+/// Stake of one account
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakeInfo {
+    pub amount: i128,
+    pub reward_per_token_paid: i128, // accumulator value rewards were last settled at
+    pub rewards: i128,               // settled but unclaimed rewards
+}
+
+/// Unstaked tokens waiting out the unbonding period
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unbonding {
+    pub amount: i128,
+    pub release_at: u64,
+}
+
+/// Voting power from `timestamp` on
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub timestamp: u64,
+    pub amount: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    InsufficientStake = 4,
+    NothingToWithdraw = 5,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const CONFIG: Symbol = symbol_short!("CONFIG");
+const TOTAL: Symbol = symbol_short!("TOTAL");
+const RPT: Symbol = symbol_short!("RPT");
+const QUEUED: Symbol = symbol_short!("QUEUED");
+const STAKE: Symbol = symbol_short!("STAKE");
+const UNBONDING: Symbol = symbol_short!("UNBONDING");
+const CP_COUNT: Symbol = symbol_short!("CP_COUNT");
+const CHECKPT: Symbol = symbol_short!("CHECKPT");
+// Histories kept as a single vector, read until their next checkpoint
+// moves them into their own entries
+const CHECKPTS: Symbol = symbol_short!("CHECKPTS");
+const TOTAL_CPS: Symbol = symbol_short!("TOTAL_CPS");
+
+// Fixed-point scale of the reward-per-token accumulator
+const PRECISION: i128 = 1_000_000_000_000;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contract]
+pub struct Staking;
+
+#[contractimpl]
+impl Staking {
+    /// Initialize the staking contract
+    pub fn initialize(env: Env, admin: Address, config: StakingConfig) -> Result<(), Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&CONFIG, &config);
+        env.storage().instance().set(&TOTAL, &0i128);
+        env.storage().instance().set(&RPT, &0i128);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_config(env: Env) -> Result<StakingConfig, Error> {
+        env.storage()
+            .instance()
+            .get(&CONFIG)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Change the unbonding period of future unstakes (only admin)
+    pub fn set_unbonding_period(env: Env, unbonding_period: u64) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        let mut config = Self::get_config(env.clone())?;
+        config.unbonding_period = unbonding_period;
+        env.storage().instance().set(&CONFIG, &config);
+        Ok(())
+    }
+
+    /// Stake protocol tokens
+    pub fn stake(env: Env, staker: Address, amount: i128) -> Result<(), Error> {
+        staker.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        token::Client::new(&env, &config.staking_token).transfer(
+            &staker,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let mut stake = Self::settled_stake(&env, &staker);
+        stake.amount += amount;
+        Self::save_stake(&env, &staker, &stake);
+        Self::write_checkpoint(&env, &staker, stake.amount);
+        Self::set_total(&env, Self::get_total_staked(env.clone()) + amount);
+
+        env.events().publish(
+            (symbol_short!("STAKING"), symbol_short!("STAKED")),
+            (staker, amount),
+        );
+        Ok(())
+    }
+
+    /// Start unbonding staked tokens
+    ///
+    /// They stop earning rewards and counting as votes at once, and can be
+    /// withdrawn once the unbonding period has passed.
+    pub fn unstake(env: Env, staker: Address, amount: i128) -> Result<(), Error> {
+        staker.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        let mut stake = Self::settled_stake(&env, &staker);
+        if stake.amount < amount {
+            return Err(Error::InsufficientStake);
+        }
+        stake.amount -= amount;
+        Self::save_stake(&env, &staker, &stake);
+        Self::write_checkpoint(&env, &staker, stake.amount);
+        Self::set_total(&env, Self::get_total_staked(env.clone()) - amount);
+
+        let release_at = env.ledger().timestamp() + config.unbonding_period;
+        let mut unbondings = Self::get_unbonding(env.clone(), staker.clone());
+        unbondings.push_back(Unbonding { amount, release_at });
+        Self::save_unbonding(&env, &staker, &unbondings);
+
+        env.events().publish(
+            (symbol_short!("STAKING"), symbol_short!("UNSTAKED")),
+            (staker, amount, release_at),
+        );
+        Ok(())
+    }
+
+    /// Withdraw every unbonding entry that has matured
+    pub fn withdraw(env: Env, staker: Address) -> Result<i128, Error> {
+        staker.require_auth();
+
+        let config = Self::get_config(env.clone())?;
+        let now = env.ledger().timestamp();
+        let mut remaining = Vec::new(&env);
+        let mut released = 0i128;
+        for unbonding in Self::get_unbonding(env.clone(), staker.clone()).iter() {
+            if unbonding.release_at <= now {
+                released += unbonding.amount;
+            } else {
+                remaining.push_back(unbonding);
+            }
+        }
+        if released == 0 {
+            return Err(Error::NothingToWithdraw);
+        }
+
+        Self::save_unbonding(&env, &staker, &remaining);
+        token::Client::new(&env, &config.staking_token).transfer(
+            &env.current_contract_address(),
+            &staker,
+            &released,
+        );
+
+        env.events().publish(
+            (symbol_short!("STAKING"), symbol_short!("WITHDRAWN")),
+            (staker, released),
+        );
+        Ok(released)
+    }
+
+    /// Fund rewards for current stakers, pro rata to their stake
+    ///
+    /// Called by the fee distributor. Rewards sent while nothing is staked
+    /// are held for the next funding.
+    pub fn notify_reward(env: Env, funder: Address, amount: i128) -> Result<(), Error> {
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let config = Self::get_config(env.clone())?;
+        token::Client::new(&env, &config.reward_token).transfer(
+            &funder,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let queued: i128 = env.storage().instance().get(&QUEUED).unwrap_or(0);
+        let total = Self::get_total_staked(env.clone());
+        if total == 0 {
+            env.storage().instance().set(&QUEUED, &(queued + amount));
+        } else {
+            let reward_per_token =
+                Self::reward_per_token(&env) + (queued + amount) * PRECISION / total;
+            env.storage().instance().set(&RPT, &reward_per_token);
+            env.storage().instance().remove(&QUEUED);
+        }
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("STAKING"), symbol_short!("REWARDED")),
+            (funder, amount),
+        );
+        Ok(())
+    }
+
+    /// Pay out the staker's accrued rewards
+    pub fn claim_rewards(env: Env, staker: Address) -> Result<i128, Error> {
+        staker.require_auth();
+
+        let config = Self::get_config(env.clone())?;
+        let mut stake = Self::settled_stake(&env, &staker);
+        let rewards = stake.rewards;
+        if rewards > 0 {
+            stake.rewards = 0;
+            Self::save_stake(&env, &staker, &stake);
+            token::Client::new(&env, &config.reward_token).transfer(
+                &env.current_contract_address(),
+                &staker,
+                &rewards,
+            );
+
+            env.events().publish(
+                (symbol_short!("STAKING"), symbol_short!("CLAIMED")),
+                (staker, rewards),
+            );
+        }
+        Ok(rewards)
+    }
+
+    pub fn get_stake(env: Env, staker: Address) -> StakeInfo {
+        env.storage()
+            .persistent()
+            .get(&(STAKE, staker))
+            .unwrap_or(StakeInfo {
+                amount: 0,
+                reward_per_token_paid: 0,
+                rewards: 0,
+            })
+    }
+
+    /// Rewards the staker could claim now
+    pub fn get_pending_rewards(env: Env, staker: Address) -> i128 {
+        Self::settled_stake(&env, &staker).rewards
+    }
+
+    pub fn get_unbonding(env: Env, staker: Address) -> Vec<Unbonding> {
+        env.storage()
+            .persistent()
+            .get(&(UNBONDING, staker))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_total_staked(env: Env) -> i128 {
+        env.storage().instance().get(&TOTAL).unwrap_or(0)
+    }
+
+    /// Currently staked balance, read by the bridge for fee-discount tiers
+    pub fn staked_balance(env: Env, user: Address) -> i128 {
+        Self::get_stake(env, user).amount
+    }
+
+    /// Voting power of `account` as of `timestamp`, read by governance
+    pub fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128 {
+        Self::checkpoint_at(&env, &Some(account), timestamp)
+    }
+
+    /// Total staked as of `timestamp`, read by governance
+    pub fn get_past_total_supply(env: Env, timestamp: u64) -> i128 {
+        Self::checkpoint_at(&env, &None, timestamp)
+    }
+}
+
+impl stellhydra_access::Upgradeable for Staking {}
+
+stellhydra_access::upgradeable!(Staking);
+
+impl Staking {
+    fn reward_per_token(env: &Env) -> i128 {
+        env.storage().instance().get(&RPT).unwrap_or(0)
+    }
+
+    /// Stake with rewards accrued since its last settlement added
+    fn settled_stake(env: &Env, staker: &Address) -> StakeInfo {
+        let mut stake = Self::get_stake(env.clone(), staker.clone());
+        let reward_per_token = Self::reward_per_token(env);
+        stake.rewards +=
+            stake.amount * (reward_per_token - stake.reward_per_token_paid) / PRECISION;
+        stake.reward_per_token_paid = reward_per_token;
+        stake
+    }
+
+    fn save_stake(env: &Env, staker: &Address, stake: &StakeInfo) {
+        let key = (STAKE, staker.clone());
+        env.storage().persistent().set(&key, stake);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn save_unbonding(env: &Env, staker: &Address, unbondings: &Vec<Unbonding>) {
+        let key = (UNBONDING, staker.clone());
+        if unbondings.is_empty() {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        env.storage().persistent().set(&key, unbondings);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn set_total(env: &Env, total: i128) {
+        env.storage().instance().set(&TOTAL, &total);
+        Self::push_checkpoint(env, &None, total);
+        Self::extend_instance(env);
+    }
+
+    fn write_checkpoint(env: &Env, staker: &Address, amount: i128) {
+        Self::push_checkpoint(env, &Some(staker.clone()), amount);
+    }
+
+    /// Record `amount` from now on, overwriting a checkpoint from the same
+    /// timestamp
+    ///
+    /// Each checkpoint is its own entry, so the cost of a write doesn't
+    /// grow with the history. `holder` is the staker, or none for the
+    /// total.
+    fn push_checkpoint(env: &Env, holder: &Option<Address>, amount: i128) {
+        let now = env.ledger().timestamp();
+        let mut count = match Self::checkpoint_count(env, holder) {
+            Some(count) => count,
+            None => Self::adopt_legacy_checkpoints(env, holder),
+        };
+        if count > 0 {
+            if let Some(last) = Self::checkpoint(env, holder, count - 1) {
+                if last.timestamp == now {
+                    count -= 1;
+                }
+            }
+        }
+
+        Self::save_checkpoint(
+            env,
+            holder,
+            count,
+            &Checkpoint {
+                timestamp: now,
+                amount,
+            },
+        );
+        Self::save_checkpoint_count(env, holder, count + 1);
+    }
+
+    /// Amount of the latest checkpoint at or before `timestamp`
+    fn checkpoint_at(env: &Env, holder: &Option<Address>, timestamp: u64) -> i128 {
+        let Some(count) = Self::checkpoint_count(env, holder) else {
+            return Self::legacy_checkpoint_at(env, holder, timestamp);
+        };
+
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = (low + high) / 2;
+            match Self::checkpoint(env, holder, mid) {
+                Some(checkpoint) if checkpoint.timestamp > timestamp => high = mid,
+                _ => low = mid + 1,
+            }
+        }
+        if low == 0 {
+            return 0;
+        }
+        Self::checkpoint(env, holder, low - 1)
+            .map(|checkpoint| checkpoint.amount)
+            .unwrap_or(0)
+    }
+
+    fn checkpoint_count(env: &Env, holder: &Option<Address>) -> Option<u32> {
+        env.storage().persistent().get(&(CP_COUNT, holder.clone()))
+    }
+
+    fn save_checkpoint_count(env: &Env, holder: &Option<Address>, count: u32) {
+        let key = (CP_COUNT, holder.clone());
+        env.storage().persistent().set(&key, &count);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn checkpoint(env: &Env, holder: &Option<Address>, index: u32) -> Option<Checkpoint> {
+        env.storage()
+            .persistent()
+            .get(&(CHECKPT, holder.clone(), index))
+    }
+
+    fn save_checkpoint(env: &Env, holder: &Option<Address>, index: u32, checkpoint: &Checkpoint) {
+        let key = (CHECKPT, holder.clone(), index);
+        env.storage().persistent().set(&key, checkpoint);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// History a holder had as a single vector
+    fn legacy_checkpoints(env: &Env, holder: &Option<Address>) -> Vec<Checkpoint> {
+        let checkpoints = match holder {
+            Some(staker) => env.storage().persistent().get(&(CHECKPTS, staker.clone())),
+            None => env.storage().persistent().get(&TOTAL_CPS),
+        };
+        checkpoints.unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Move a holder's vector history into its own entries, returning how
+    /// many checkpoints it had
+    fn adopt_legacy_checkpoints(env: &Env, holder: &Option<Address>) -> u32 {
+        let checkpoints = Self::legacy_checkpoints(env, holder);
+        for (index, checkpoint) in checkpoints.iter().enumerate() {
+            Self::save_checkpoint(env, holder, index as u32, &checkpoint);
+        }
+        match holder {
+            Some(staker) => env
+                .storage()
+                .persistent()
+                .remove(&(CHECKPTS, staker.clone())),
+            None => env.storage().persistent().remove(&TOTAL_CPS),
+        }
+        checkpoints.len()
+    }
+
+    fn legacy_checkpoint_at(env: &Env, holder: &Option<Address>, timestamp: u64) -> i128 {
+        let mut amount = 0;
+        for checkpoint in Self::legacy_checkpoints(env, holder).iter() {
+            if checkpoint.timestamp > timestamp {
+                break;
+            }
+            amount = checkpoint.amount;
+        }
+        amount
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    struct Setup<'a> {
+        staking: StakingClient<'a>,
+        stake_token: token::Client<'a>,
+        reward_token: token::Client<'a>,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let stake_token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let reward_token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+
+        let staking_id = env.register_contract(None, Staking);
+        let staking = StakingClient::new(env, &staking_id);
+        staking.initialize(
+            &Address::generate(env),
+            &StakingConfig {
+                staking_token: stake_token.clone(),
+                reward_token: reward_token.clone(),
+                unbonding_period: 86_400,
+            },
+        );
+
+        Setup {
+            staking,
+            stake_token: token::Client::new(env, &stake_token),
+            reward_token: token::Client::new(env, &reward_token),
+        }
+    }
+
+    fn funded(env: &Env, token: &token::Client, amount: i128) -> Address {
+        let account = Address::generate(env);
+        StellarAssetClient::new(env, &token.address).mint(&account, &amount);
+        account
+    }
+
+    #[test]
+    fn test_unstaked_tokens_wait_out_unbonding() {
+        let env = Env::default();
+        let s = setup(&env);
+        let staker = funded(&env, &s.stake_token, 1_000);
+
+        s.staking.stake(&staker, &1_000);
+        assert_eq!(s.stake_token.balance(&staker), 0);
+        assert_eq!(s.staking.staked_balance(&staker), 1_000);
+        assert_eq!(
+            s.staking.try_unstake(&staker, &2_000),
+            Err(Ok(Error::InsufficientStake))
+        );
+
+        s.staking.unstake(&staker, &400);
+        assert_eq!(s.staking.staked_balance(&staker), 600);
+        assert_eq!(s.staking.get_total_staked(), 600);
+        assert_eq!(
+            s.staking.try_withdraw(&staker),
+            Err(Ok(Error::NothingToWithdraw))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 86_400);
+        assert_eq!(s.staking.withdraw(&staker), 400);
+        assert_eq!(s.stake_token.balance(&staker), 400);
+        assert_eq!(s.staking.get_unbonding(&staker).len(), 0);
+    }
+
+    #[test]
+    fn test_rewards_split_pro_rata() {
+        let env = Env::default();
+        let s = setup(&env);
+        let alice = funded(&env, &s.stake_token, 300);
+        let bob = funded(&env, &s.stake_token, 100);
+        let distributor = funded(&env, &s.reward_token, 1_000);
+
+        // Rewards with nobody staked wait for the next funding
+        s.staking.notify_reward(&distributor, &200);
+        s.staking.stake(&alice, &300);
+        s.staking.stake(&bob, &100);
+        s.staking.notify_reward(&distributor, &200);
+        assert_eq!(s.staking.get_pending_rewards(&alice), 300);
+        assert_eq!(s.staking.get_pending_rewards(&bob), 100);
+
+        // Unstaked tokens stop earning
+        s.staking.unstake(&alice, &300);
+        s.staking.notify_reward(&distributor, &100);
+        assert_eq!(s.staking.get_pending_rewards(&alice), 300);
+        assert_eq!(s.staking.get_pending_rewards(&bob), 200);
+
+        assert_eq!(s.staking.claim_rewards(&alice), 300);
+        assert_eq!(s.reward_token.balance(&alice), 300);
+        assert_eq!(s.staking.get_pending_rewards(&alice), 0);
+        assert_eq!(s.staking.claim_rewards(&alice), 0);
+    }
+
+    #[test]
+    fn test_voting_power_snapshots() {
+        let env = Env::default();
+        let s = setup(&env);
+        let staker = funded(&env, &s.stake_token, 1_000);
+
+        env.ledger().with_mut(|li| li.timestamp = 100);
+        s.staking.stake(&staker, &600);
+        env.ledger().with_mut(|li| li.timestamp = 200);
+        s.staking.stake(&staker, &400);
+        env.ledger().with_mut(|li| li.timestamp = 300);
+        s.staking.unstake(&staker, &1_000);
+
+        assert_eq!(s.staking.get_past_votes(&staker, &99), 0);
+        assert_eq!(s.staking.get_past_votes(&staker, &100), 600);
+        assert_eq!(s.staking.get_past_votes(&staker, &250), 1_000);
+        assert_eq!(s.staking.get_past_votes(&staker, &300), 0);
+        assert_eq!(s.staking.get_past_total_supply(&150), 600);
+        assert_eq!(s.staking.get_past_total_supply(&299), 1_000);
+    }
+
+    #[test]
+    fn test_vector_histories_are_read_and_moved() {
+        let env = Env::default();
+        let s = setup(&env);
+        let staker = funded(&env, &s.stake_token, 1_000);
+
+        // A history written as a single vector before
+        let checkpoint = |timestamp, amount| Checkpoint { timestamp, amount };
+        env.as_contract(&s.staking.address, || {
+            let history = soroban_sdk::vec![&env, checkpoint(100, 600), checkpoint(200, 1_000)];
+            env.storage()
+                .persistent()
+                .set(&(CHECKPTS, staker.clone()), &history);
+            env.storage().persistent().set(&TOTAL_CPS, &history);
+        });
+        assert_eq!(s.staking.get_past_votes(&staker, &150), 600);
+        assert_eq!(s.staking.get_past_total_supply(&250), 1_000);
+
+        env.ledger().with_mut(|li| li.timestamp = 300);
+        s.staking.stake(&staker, &400);
+        assert_eq!(s.staking.get_past_votes(&staker, &99), 0);
+        assert_eq!(s.staking.get_past_votes(&staker, &150), 600);
+        assert_eq!(s.staking.get_past_votes(&staker, &250), 1_000);
+        assert_eq!(s.staking.get_past_votes(&staker, &300), 400);
+        assert_eq!(s.staking.get_past_total_supply(&300), 400);
+        env.as_contract(&s.staking.address, || {
+            assert!(!env.storage().persistent().has(&(CHECKPTS, staker.clone())));
+            assert!(!env.storage().persistent().has(&TOTAL_CPS));
+        });
+    }
+}
@@ -0,0 +1,235 @@
+//! Escrow layouts from before escrows got their own entries
+//!
+//! Until storage version 2 every escrow sat in the `ESCROWS` instance map
+//! in whatever layout the contract had when it was written. These are the
+//! layouts frozen as they were stored, so the migration can still read them.
+//! Each one only adds fields to the one before, except the last, which
+//! replaced the single `time_lock` with `TimeLocks`.
+
+use soroban_sdk::{contracttype, Address, Bytes, Env, FromVal, Map, Symbol, Val};
+
+use crate::{Escrow, EscrowSide, HashAlgo, TimeLocks};
+
+/// Escrow as the first release stored it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV1 {
+    pub id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub time_lock: u64,
+    pub status: u32,
+    pub secret: Option<Bytes>,
+    pub created_at: u64,
+}
+
+/// Escrow once the hash algorithm became selectable
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV2 {
+    pub id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub hash_algo: HashAlgo,
+    pub time_lock: u64,
+    pub status: u32,
+    pub secret: Option<Bytes>,
+    pub created_at: u64,
+}
+
+/// Escrow once it could be filled in parts
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV3 {
+    pub id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub hash_algo: HashAlgo,
+    pub time_lock: u64,
+    pub status: u32,
+    pub secret: Option<Bytes>,
+    pub created_at: u64,
+    pub parts: u32,
+    pub filled: i128,
+}
+
+/// Escrow once it recorded which leg of the swap it holds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV4 {
+    pub id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub hash_algo: HashAlgo,
+    pub time_lock: u64,
+    pub status: u32,
+    pub secret: Option<Bytes>,
+    pub created_at: u64,
+    pub parts: u32,
+    pub filled: i128,
+    pub side: EscrowSide,
+    pub taker: Option<Address>,
+}
+
+/// Escrow once resolvers posted a safety deposit, the last layout with a
+/// single `time_lock`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV5 {
+    pub id: Bytes,
+    pub maker: Address,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub hash_algo: HashAlgo,
+    pub time_lock: u64,
+    pub status: u32,
+    pub secret: Option<Bytes>,
+    pub created_at: u64,
+    pub parts: u32,
+    pub filled: i128,
+    pub side: EscrowSide,
+    pub taker: Option<Address>,
+    pub resolver: Option<Address>,
+    pub deposit_token: Option<Address>,
+    pub safety_deposit: i128,
+}
+
+/// Time locks matching a single expiry
+///
+/// A single `time_lock` let the escrow complete up to and including it and
+/// refund after it, with no public phases.
+fn single_time_lock(created_at: u64, time_lock: u64) -> TimeLocks {
+    let expiry = time_lock.saturating_add(1);
+    TimeLocks {
+        withdrawal: created_at,
+        public_withdrawal: expiry,
+        cancellation: expiry,
+        public_cancellation: expiry,
+    }
+}
+
+fn upgrade_v1(v1: EscrowV1) -> EscrowV2 {
+    EscrowV2 {
+        id: v1.id,
+        maker: v1.maker,
+        amount: v1.amount,
+        asset: v1.asset,
+        hash_lock: v1.hash_lock,
+        hash_algo: HashAlgo::Sha256,
+        time_lock: v1.time_lock,
+        status: v1.status,
+        secret: v1.secret,
+        created_at: v1.created_at,
+    }
+}
+
+fn upgrade_v2(v2: EscrowV2) -> EscrowV3 {
+    EscrowV3 {
+        id: v2.id,
+        maker: v2.maker,
+        amount: v2.amount,
+        asset: v2.asset,
+        hash_lock: v2.hash_lock,
+        hash_algo: v2.hash_algo,
+        time_lock: v2.time_lock,
+        status: v2.status,
+        secret: v2.secret,
+        created_at: v2.created_at,
+        parts: 0,
+        filled: 0,
+    }
+}
+
+fn upgrade_v3(v3: EscrowV3) -> EscrowV4 {
+    EscrowV4 {
+        id: v3.id,
+        maker: v3.maker,
+        amount: v3.amount,
+        asset: v3.asset,
+        hash_lock: v3.hash_lock,
+        hash_algo: v3.hash_algo,
+        time_lock: v3.time_lock,
+        status: v3.status,
+        secret: v3.secret,
+        created_at: v3.created_at,
+        parts: v3.parts,
+        filled: v3.filled,
+        side: EscrowSide::Src,
+        taker: None,
+    }
+}
+
+fn upgrade_v4(v4: EscrowV4) -> EscrowV5 {
+    EscrowV5 {
+        id: v4.id,
+        maker: v4.maker,
+        amount: v4.amount,
+        asset: v4.asset,
+        hash_lock: v4.hash_lock,
+        hash_algo: v4.hash_algo,
+        time_lock: v4.time_lock,
+        status: v4.status,
+        secret: v4.secret,
+        created_at: v4.created_at,
+        parts: v4.parts,
+        filled: v4.filled,
+        side: v4.side,
+        taker: v4.taker,
+        resolver: None,
+        deposit_token: None,
+        safety_deposit: 0,
+    }
+}
+
+fn upgrade_v5(v5: EscrowV5) -> Escrow {
+    Escrow {
+        id: v5.id,
+        maker: v5.maker,
+        amount: v5.amount,
+        asset: v5.asset,
+        hash_lock: v5.hash_lock,
+        hash_algo: v5.hash_algo,
+        time_locks: single_time_lock(v5.created_at, v5.time_lock),
+        status: v5.status,
+        secret: v5.secret,
+        created_at: v5.created_at,
+        parts: v5.parts,
+        filled: v5.filled,
+        side: v5.side,
+        taker: v5.taker,
+        resolver: v5.resolver,
+        deposit_token: v5.deposit_token,
+        safety_deposit: v5.safety_deposit,
+    }
+}
+
+/// Decode an escrow from the instance map, whatever layout it was stored in
+///
+/// The layouts differ in their number of fields, apart from the last two,
+/// which differ in `time_lock` becoming `time_locks`.
+pub fn decode(env: &Env, val: Val) -> Escrow {
+    let fields = Map::<Symbol, Val>::from_val(env, &val);
+    if !fields.contains_key(Symbol::new(env, "time_lock")) {
+        return Escrow::from_val(env, &val);
+    }
+    let v5 = match fields.len() {
+        9 => {
+            let v1 = EscrowV1::from_val(env, &val);
+            upgrade_v4(upgrade_v3(upgrade_v2(upgrade_v1(v1))))
+        }
+        10 => upgrade_v4(upgrade_v3(upgrade_v2(EscrowV2::from_val(env, &val)))),
+        12 => upgrade_v4(upgrade_v3(EscrowV3::from_val(env, &val))),
+        14 => upgrade_v4(EscrowV4::from_val(env, &val)),
+        _ => EscrowV5::from_val(env, &val),
+    };
+    upgrade_v5(v5)
+}
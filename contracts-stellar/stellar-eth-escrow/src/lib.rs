@@ -1,7 +1,15 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contracterror, symbol_short, token, Address, Bytes, Env, Map, Symbol, Vec,
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address,
+    Bytes, BytesN, Env, Map, Symbol, Val, Vec,
 };
+use stellhydra_access::{ownable, pausable, upgradeable, Upgradeable};
+use stellhydra_events::escrow::{
+    HtlcCompleted, HtlcCreated, HtlcLocked, HtlcPartiallyFilled, HtlcPublicWithdrawn, HtlcRefunded,
+};
+use stellhydra_events::Event;
+
+mod legacy;
 
 #[contract]
 pub struct StellarEthEscrow;
@@ -13,23 +21,113 @@ pub struct Escrow {
     pub maker: Address,
     pub amount: i128,
     pub asset: Address,
-    pub hash_lock: Bytes,
-    pub time_lock: u64,
-    pub status: u32, // 0: pending, 1: locked, 2: completed, 3: refunded
-    pub secret: Option<Bytes>,
+    pub hash_lock: Bytes, // Merkle root of the secrets if filled in parts
+    pub hash_algo: HashAlgo,
+    pub time_locks: TimeLocks,
+    pub status: u32,           // 0: pending, 1: locked, 2: completed, 3: refunded
+    pub secret: Option<Bytes>, // last secret revealed
     pub created_at: u64,
+    pub parts: u32, // 0: filled at once, else the number of parts for partial fills
+    pub filled: i128,
+    pub side: EscrowSide,
+    pub taker: Option<Address>, // resolver funding a destination escrow, or the only one who may lock a source escrow
+    pub resolver: Option<Address>, // resolver that locked the escrow
+    pub deposit_token: Option<Address>, // token of the resolver's safety deposit
+    pub safety_deposit: i128,
+}
+
+/// Deposit a resolver posts when locking an escrow
+///
+/// It's returned to the resolver when the escrow completes. If the escrow
+/// is cancelled instead, whoever cancels it takes the deposit, which pays
+/// for someone to clean up after a resolver that walked away.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SafetyDeposit {
+    pub token: Address,
+    pub amount: i128,
 }
 
+/// Leg of a cross-chain swap an escrow holds
+///
+/// On the source chain the maker locks the funds for the resolver; on the
+/// destination chain the resolver locks them for the maker.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscrowSide {
+    Src,
+    Dst,
+}
+
+/// Hash the secret of an escrow is checked against
+///
+/// Solidity escrows on the EVM side almost always lock with keccak256, so
+/// an escrow mirroring one uses the same hash and a single secret settles
+/// both sides.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashAlgo {
+    Sha256,
+    Keccak256,
+}
+
+/// When each phase of an escrow starts, as ledger timestamps
+///
+/// Until `withdrawal` the escrow waits out the other chain's finality.
+/// The resolver that locked it then has the withdrawal to itself until
+/// `public_withdrawal`, after which any registered resolver can complete
+/// it with `public_withdraw`. From `cancellation` withdrawals close and whoever funded the
+/// escrow can cancel it, and from `public_cancellation` anyone can.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TimeLocks {
     pub withdrawal: u64,
-    pub refund: u64,
+    pub public_withdrawal: u64,
+    pub cancellation: u64,
+    pub public_cancellation: u64,
+}
+
+/// Terms an escrow's id is derived from
+///
+/// The id is the SHA-256 of the order's XDR encoding, so a coordinator
+/// can work it out before the escrow exists and quote it to the other
+/// chain. `taker` is the resolver funding a destination escrow or the one
+/// a source escrow is made out to, and `hash_lock` is the Merkle root for
+/// escrows filled in parts. The salt tells apart orders with the same
+/// terms.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Order {
+    pub maker: Address,
+    pub taker: Option<Address>,
+    pub amount: i128,
+    pub asset: Address,
+    pub hash_lock: Bytes,
+    pub time_locks: TimeLocks,
+    pub salt: u64,
 }
 
 // Storage keys
-const ESCROWS: Symbol = symbol_short!("ESCROWS");
+const ESCROWS: Symbol = symbol_short!("ESCROWS"); // map of every escrow, before storage version 2
+const ESCROW: Symbol = symbol_short!("ESCROW");
+const INDEX: Symbol = symbol_short!("INDEX");
+const BY_MAKER: Symbol = symbol_short!("BY_MAKER");
+const STATUS: Symbol = symbol_short!("STATUS");
 const COUNTER: Symbol = symbol_short!("COUNTER");
+const DEPOSIT: Symbol = symbol_short!("DEPOSIT");
+const MAKER_CURSOR: Symbol = symbol_short!("MKCURSOR"); // (next, end) of the escrows the version 3 migration left to index by maker
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Most escrows returned by a single page query
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Role of the resolvers allowed to withdraw other resolvers' escrows
+/// once they're public
+pub const RESOLVER: Symbol = symbol_short!("RESOLVER");
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -44,121 +142,340 @@ pub enum Error {
     TimelockNotExpired = 7,
     TransferFailed = 8,
     InsufficientBalance = 9,
+    Paused = 10,
+    InvalidProof = 11,
+    InvalidFill = 12,
+    WrongFillMode = 13,
+    InvalidParts = 14,
+    InvalidTimeLocks = 15,
+    FinalityNotReached = 16,
+    LockWindowClosed = 17,
+    NotResolver = 18,
+    PublicCancellationNotOpen = 19,
+    NotTaker = 20,
+    NotRegistered = 21,
+    PublicWithdrawalNotOpen = 22,
 }
 
 #[contractimpl]
 impl StellarEthEscrow {
     /// Initialize the contract
-    pub fn initialize(env: Env) {
+    ///
+    /// The admin can pause new escrows; settling and refunding existing
+    /// ones keeps working while paused.
+    pub fn initialize(env: Env, admin: Address) {
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+
         let counter: u64 = 0;
         env.storage().instance().set(&COUNTER, &counter);
-        
-        let escrows: Map<Bytes, Escrow> = Map::new(&env);
-        env.storage().instance().set(&ESCROWS, &escrows);
+        env.storage()
+            .instance()
+            .set(&upgradeable::VERSION, &<Self as Upgradeable>::VERSION);
+    }
+
+    /// Require resolvers to post `deposit` when locking escrows, or stop
+    /// (only admin)
+    ///
+    /// Escrows locked earlier keep the deposit they were locked with.
+    pub fn set_safety_deposit(env: Env, deposit: Option<SafetyDeposit>) -> Result<(), Error> {
+        stellhydra_access::require_admin(&env);
+        match deposit {
+            Some(deposit) => {
+                if deposit.amount <= 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                env.storage().instance().set(&DEPOSIT, &deposit);
+            }
+            None => env.storage().instance().remove(&DEPOSIT),
+        }
+        Ok(())
+    }
+
+    /// Index up to `limit` escrows opened before version 3 by maker,
+    /// returning how many are left (only admin)
+    ///
+    /// Migrating to version 3 only records which escrows need indexing, so
+    /// the work can be spread over as many calls as the escrows need. Until
+    /// none are left, `get_escrows_by_maker` lists a maker's new escrows
+    /// ahead of their older ones and may miss some of the older ones.
+    pub fn index_makers(env: Env, limit: u32) -> u64 {
+        stellhydra_access::require_admin(&env);
+        let Some((next, end)) = env.storage().instance().get::<_, (u64, u64)>(&MAKER_CURSOR) else {
+            return 0;
+        };
+        let stop = end.min(next + limit.min(MAX_PAGE_SIZE) as u64);
+        for index in next..stop {
+            // Skip anything that has since expired
+            let Some(escrow_id) = env.storage().persistent().get::<_, Bytes>(&(INDEX, index))
+            else {
+                continue;
+            };
+            if let Ok(escrow) = Self::load_escrow(&env, &escrow_id) {
+                Self::index_by_maker(&env, &escrow.maker, &escrow_id);
+            }
+        }
+        if stop == end {
+            env.storage().instance().remove(&MAKER_CURSOR);
+        } else {
+            env.storage().instance().set(&MAKER_CURSOR, &(stop, end));
+        }
+        end - stop
+    }
+
+    pub fn get_safety_deposit(env: Env) -> Option<SafetyDeposit> {
+        env.storage().instance().get(&DEPOSIT)
     }
 
     /// Create a new escrow
+    ///
+    /// This is the source side of a swap: the maker locks the funds, a
+    /// resolver locks the escrow and takes the funds with the secret, and
+    /// the maker is refunded if it doesn't. With a `taker`, only that
+    /// resolver can lock the escrow, so the funds can only go to them.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_escrow(
         env: Env,
         maker: Address,
+        taker: Option<Address>,
+        amount: i128,
+        asset: Address,
+        hash_lock: Bytes,
+        hash_algo: HashAlgo,
+        time_locks: TimeLocks,
+        salt: u64,
+    ) -> Result<Bytes, Error> {
+        let mut escrow =
+            Self::new_escrow(&env, maker, amount, asset, hash_lock, hash_algo, time_locks);
+        escrow.taker = taker;
+        Self::open(&env, escrow, salt)
+    }
+
+    /// Create an escrow resolvers can fill in slices
+    ///
+    /// The amount is split in `parts`, and `merkle_root` commits to
+    /// `parts + 1` secrets. A fill reaching into the `i`th part reveals
+    /// secret `i`, and the fill taking the rest of the escrow reveals
+    /// secret `parts`. Each leaf hashes the secret's index, as 8 big-endian
+    /// bytes, followed by the secret's hash. Pairs are hashed in sorted
+    /// order, all with `hash_algo`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_partial_escrow(
+        env: Env,
+        maker: Address,
+        amount: i128,
+        asset: Address,
+        merkle_root: Bytes,
+        parts: u32,
+        hash_algo: HashAlgo,
+        time_locks: TimeLocks,
+        salt: u64,
+    ) -> Result<Bytes, Error> {
+        if parts == 0 || amount < parts as i128 {
+            return Err(Error::InvalidParts);
+        }
+        let mut escrow = Self::new_escrow(
+            &env,
+            maker,
+            amount,
+            asset,
+            merkle_root,
+            hash_algo,
+            time_locks,
+        );
+        escrow.parts = parts;
+        Self::open(&env, escrow, salt)
+    }
+
+    /// Create the destination side of a swap whose maker locked funds on
+    /// another chain
+    ///
+    /// The roles mirror the source side: `taker`, the resolver, locks the
+    /// funds, the secret releases them to `maker`, and `taker` is refunded
+    /// if it isn't revealed in time. The escrow starts out locked. Its
+    /// withdrawal deadline should fall before the source escrow's, so the
+    /// resolver can still claim the source side once the maker reveals
+    /// the secret here.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dst_escrow(
+        env: Env,
+        taker: Address,
+        maker: Address,
         amount: i128,
         asset: Address,
         hash_lock: Bytes,
+        hash_algo: HashAlgo,
         time_locks: TimeLocks,
+        salt: u64,
     ) -> Result<Bytes, Error> {
+        let mut escrow =
+            Self::new_escrow(&env, maker, amount, asset, hash_lock, hash_algo, time_locks);
+        escrow.side = EscrowSide::Dst;
+        escrow.taker = Some(taker.clone());
+        escrow.status = 1; // locked
+        escrow.resolver = Some(taker.clone());
+        Self::post_safety_deposit(&env, &mut escrow, &taker);
+        let escrow_id = Self::open(&env, escrow, salt)?;
+
+        HtlcLocked {
+            escrow_id: escrow_id.clone(),
+            resolver: taker,
+        }
+        .publish(&env);
+
+        Ok(escrow_id)
+    }
+
+    /// Pending source escrow with the given terms, not stored yet
+    fn new_escrow(
+        env: &Env,
+        maker: Address,
+        amount: i128,
+        asset: Address,
+        hash_lock: Bytes,
+        hash_algo: HashAlgo,
+        time_locks: TimeLocks,
+    ) -> Escrow {
+        Escrow {
+            id: Bytes::new(env),
+            maker,
+            amount,
+            asset,
+            hash_lock,
+            hash_algo,
+            time_locks,
+            status: 0, // pending
+            secret: None,
+            created_at: env.ledger().timestamp(),
+            parts: 0,
+            filled: 0,
+            side: EscrowSide::Src,
+            taker: None,
+            resolver: None,
+            deposit_token: None,
+            safety_deposit: 0,
+        }
+    }
+
+    /// Validate, fund and store a new escrow under the id of its order
+    fn open(env: &Env, mut escrow: Escrow, salt: u64) -> Result<Bytes, Error> {
+        if pausable::is_paused(env) {
+            return Err(Error::Paused);
+        }
+
         // Validate inputs
-        if amount <= 0 {
+        if escrow.amount <= 0 {
             return Err(Error::InvalidAmount);
         }
+        let locks = &escrow.time_locks;
+        if locks.withdrawal > locks.public_withdrawal
+            || locks.public_withdrawal > locks.cancellation
+            || locks.cancellation > locks.public_cancellation
+        {
+            return Err(Error::InvalidTimeLocks);
+        }
 
         // Check authorization
-        maker.require_auth();
-
-        // Generate unique escrow ID
-        let escrow_id = Self::generate_escrow_id(&env, &maker, amount, &asset);
-
-        // Get existing escrows
-        let mut escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
+        let depositor = Self::depositor(&escrow);
+        depositor.require_auth();
+
+        // Derive the escrow ID from the order
+        let escrow_id = Self::generate_escrow_id(
+            env,
+            &Order {
+                maker: escrow.maker.clone(),
+                taker: escrow.taker.clone(),
+                amount: escrow.amount,
+                asset: escrow.asset.clone(),
+                hash_lock: escrow.hash_lock.clone(),
+                time_locks: escrow.time_locks.clone(),
+                salt,
+            },
+        );
 
         // Check if escrow already exists
-        if escrows.contains_key(escrow_id.clone()) {
+        if env.storage().persistent().has(&(ESCROW, escrow_id.clone())) {
             return Err(Error::EscrowExists);
         }
 
         // Handle asset deposit
-        Self::handle_deposit(&env, &asset, &maker, amount)?;
-
-        // Create escrow
-        let escrow = Escrow {
-            id: escrow_id.clone(),
-            maker: maker.clone(),
-            amount,
-            asset: asset.clone(),
-            hash_lock: hash_lock.clone(),
-            time_lock: time_locks.withdrawal,
-            status: 0, // pending
-            secret: None,
-            created_at: env.ledger().timestamp(),
-        };
+        Self::handle_deposit(env, &escrow.asset, &depositor, escrow.amount)?;
 
-        // Store escrow
-        escrows.set(escrow_id.clone(), escrow.clone());
-        env.storage().instance().set(&ESCROWS, &escrows);
+        // Store escrow and index it
+        escrow.id = escrow_id.clone();
+        Self::save_escrow(env, &escrow);
+        Self::index_escrow(env, &escrow_id);
+        Self::index_by_maker(env, &escrow.maker, &escrow_id);
+        Self::track_status(env, None, escrow.status);
 
         // Emit event
-        env.events().publish(
-            (symbol_short!("created"),),
-            (escrow_id.clone(), maker.clone(), amount, asset.clone()),
-        );
+        HtlcCreated {
+            escrow_id: escrow_id.clone(),
+            maker: escrow.maker,
+            amount: escrow.amount,
+            asset: escrow.asset,
+        }
+        .publish(env);
 
         Ok(escrow_id)
     }
 
     /// Lock escrow (called by resolver after Ethereum side is locked)
-    pub fn lock_escrow(
-        env: Env,
-        escrow_id: Bytes,
-        resolver: Address,
-    ) -> Result<(), Error> {
+    pub fn lock_escrow(env: Env, escrow_id: Bytes, resolver: Address) -> Result<(), Error> {
+        if pausable::is_paused(&env) {
+            return Err(Error::Paused);
+        }
+
         // Check authorization
         resolver.require_auth();
 
-        // Get escrows
-        let mut escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
-
         // Get escrow
-        let mut escrow = escrows.get(escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+        let mut escrow = Self::load_escrow(&env, &escrow_id)?;
 
         // Check escrow status
         if escrow.status != 0 {
             return Err(Error::InvalidStatus);
         }
 
+        // Escrows made out to a taker are theirs alone
+        if escrow
+            .taker
+            .as_ref()
+            .is_some_and(|taker| *taker != resolver)
+        {
+            return Err(Error::NotTaker);
+        }
+
+        // Locking after the exclusive withdrawal would give the resolver
+        // nothing to itself
+        if env.ledger().timestamp() >= escrow.time_locks.public_withdrawal {
+            return Err(Error::LockWindowClosed);
+        }
+
         // Update escrow status
-        escrow.status = 1; // locked
+        Self::set_status(&env, &mut escrow, 1); // locked
+        escrow.resolver = Some(resolver.clone());
+        Self::post_safety_deposit(&env, &mut escrow, &resolver);
 
         // Store updated escrow
-        escrows.set(escrow_id.clone(), escrow);
-        env.storage().instance().set(&ESCROWS, &escrows);
+        Self::save_escrow(&env, &escrow);
 
         // Emit event
-        env.events().publish(
-            (symbol_short!("locked"),),
-            (escrow_id, resolver),
-        );
+        HtlcLocked {
+            escrow_id,
+            resolver,
+        }
+        .publish(&env);
 
         Ok(())
     }
 
     /// Complete escrow by revealing secret
+    ///
+    /// Only the resolver that locked the escrow can complete it here,
+    /// between its `withdrawal` and `cancellation`. The funds go to the
+    /// resolver on the source side and to the maker on the destination
+    /// side.
     pub fn complete_escrow(
         env: Env,
         escrow_id: Bytes,
@@ -168,285 +485,779 @@ impl StellarEthEscrow {
         // Check authorization
         resolver.require_auth();
 
-        // Get escrows
-        let mut escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
+        // Get escrow
+        let mut escrow = Self::load_escrow(&env, &escrow_id)?;
+
+        // Check escrow status
+        if escrow.status != 1 {
+            return Err(Error::InvalidStatus);
+        }
+        if escrow.parts != 0 {
+            return Err(Error::WrongFillMode);
+        }
+
+        // Verify hash lock
+        let computed_hash = Self::compute_hash_lock(&env, escrow.hash_algo, &secret);
+        if computed_hash != escrow.hash_lock {
+            return Err(Error::InvalidSecret);
+        }
+
+        // Check timelocks
+        Self::check_withdrawal(&env, &escrow, &resolver)?;
+
+        // Update escrow
+        Self::set_status(&env, &mut escrow, 2); // completed
+        escrow.secret = Some(secret.clone());
+
+        // Store updated escrow
+        Self::save_escrow(&env, &escrow);
+
+        // Release the held assets
+        Self::transfer_asset(
+            &env,
+            &escrow.asset,
+            &Self::recipient(&escrow),
+            escrow.amount,
+        );
+        Self::return_safety_deposit(&env, &escrow);
+
+        // Emit event
+        HtlcCompleted {
+            escrow_id,
+            resolver,
+            secret,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Complete an escrow its resolver left, between its
+    /// `public_withdrawal` and `cancellation`
+    ///
+    /// Any resolver holding the `RESOLVER` role can reveal the secret. The
+    /// funds go where `complete_escrow` would send them, and the caller
+    /// takes the locking resolver's safety deposit for its trouble. Partial
+    /// escrows go through `public_withdraw_partial` instead.
+    pub fn public_withdraw(
+        env: Env,
+        escrow_id: Bytes,
+        secret: Bytes,
+        resolver: Address,
+    ) -> Result<(), Error> {
+        // Check authorization
+        resolver.require_auth();
+        if !stellhydra_access::roles::has_role(&env, &RESOLVER, &resolver) {
+            return Err(Error::NotRegistered);
+        }
 
         // Get escrow
-        let mut escrow = escrows.get(escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+        let mut escrow = Self::load_escrow(&env, &escrow_id)?;
 
         // Check escrow status
         if escrow.status != 1 {
             return Err(Error::InvalidStatus);
         }
+        if escrow.parts != 0 {
+            return Err(Error::WrongFillMode);
+        }
 
         // Verify hash lock
-        let computed_hash = Self::compute_hash_lock(&env, &secret);
+        let computed_hash = Self::compute_hash_lock(&env, escrow.hash_algo, &secret);
         if computed_hash != escrow.hash_lock {
             return Err(Error::InvalidSecret);
         }
 
-        // Check timelock
-        if env.ledger().timestamp() > escrow.time_lock {
-            return Err(Error::TimelockExpired);
+        // Check timelocks
+        Self::check_public_withdrawal(&env, &escrow)?;
+
+        // Update escrow
+        Self::set_status(&env, &mut escrow, 2); // completed
+        escrow.secret = Some(secret.clone());
+
+        // Store updated escrow
+        Self::save_escrow(&env, &escrow);
+
+        // Release the held assets, and the safety deposit to the caller
+        Self::transfer_asset(
+            &env,
+            &escrow.asset,
+            &Self::recipient(&escrow),
+            escrow.amount,
+        );
+        if let Some(token) = &escrow.deposit_token {
+            Self::transfer_asset(&env, token, &resolver, escrow.safety_deposit);
+        }
+
+        // Emit events
+        HtlcPublicWithdrawn {
+            escrow_id: escrow_id.clone(),
+            resolver: resolver.clone(),
+            secret: secret.clone(),
+            safety_deposit: escrow.safety_deposit,
+        }
+        .publish(&env);
+        HtlcCompleted {
+            escrow_id,
+            resolver,
+            secret,
         }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    /// Fill part of an escrow created with `create_partial_escrow`,
+    /// revealing the secret at `index` with its Merkle `proof`
+    ///
+    /// `index` must be the part the escrow is filled into after this fill,
+    /// or the last index for the fill taking the rest. Each fill has to
+    /// reach into a part no earlier fill reached, so every secret opens at
+    /// most one fill.
+    pub fn complete_escrow_partial(
+        env: Env,
+        escrow_id: Bytes,
+        index: u32,
+        secret: Bytes,
+        proof: Vec<BytesN<32>>,
+        fill_amount: i128,
+        resolver: Address,
+    ) -> Result<(), Error> {
+        // Check authorization
+        resolver.require_auth();
+
+        // Get escrow
+        let mut escrow = Self::load_escrow(&env, &escrow_id)?;
+
+        // Check escrow status
+        if escrow.status != 1 {
+            return Err(Error::InvalidStatus);
+        }
+        if escrow.parts == 0 {
+            return Err(Error::WrongFillMode);
+        }
+
+        // Check timelocks
+        Self::check_withdrawal(&env, &escrow, &resolver)?;
+
+        // Check the fill and the secret opening it
+        let filled = Self::check_fill(&env, &escrow, index, &secret, &proof, fill_amount)?;
 
         // Update escrow
-        escrow.status = 2; // completed
+        escrow.filled = filled;
         escrow.secret = Some(secret.clone());
+        if filled == escrow.amount {
+            Self::set_status(&env, &mut escrow, 2); // completed
+        }
+
+        // Store updated escrow
+        Self::save_escrow(&env, &escrow);
+
+        // Release the slice
+        Self::transfer_asset(&env, &escrow.asset, &Self::recipient(&escrow), fill_amount);
+        if escrow.status == 2 {
+            Self::return_safety_deposit(&env, &escrow);
+        }
+
+        // Emit events
+        HtlcPartiallyFilled {
+            escrow_id: escrow_id.clone(),
+            resolver: resolver.clone(),
+            index,
+            secret: secret.clone(),
+            fill_amount,
+            filled,
+        }
+        .publish(&env);
+        if escrow.status == 2 {
+            HtlcCompleted {
+                escrow_id,
+                resolver,
+                secret,
+            }
+            .publish(&env);
+        }
+
+        Ok(())
+    }
+
+    /// Take what is left of a partial escrow its resolver left, between its
+    /// `public_withdrawal` and `cancellation`
+    ///
+    /// Works like `public_withdraw`, except the caller fills the rest by
+    /// revealing the last secret, at `index`, with its Merkle `proof`.
+    pub fn public_withdraw_partial(
+        env: Env,
+        escrow_id: Bytes,
+        index: u32,
+        secret: Bytes,
+        proof: Vec<BytesN<32>>,
+        resolver: Address,
+    ) -> Result<(), Error> {
+        // Check authorization
+        resolver.require_auth();
+        if !stellhydra_access::roles::has_role(&env, &RESOLVER, &resolver) {
+            return Err(Error::NotRegistered);
+        }
+
+        // Get escrow
+        let mut escrow = Self::load_escrow(&env, &escrow_id)?;
+
+        // Check escrow status
+        if escrow.status != 1 {
+            return Err(Error::InvalidStatus);
+        }
+        if escrow.parts == 0 {
+            return Err(Error::WrongFillMode);
+        }
+
+        // Check timelocks
+        Self::check_public_withdrawal(&env, &escrow)?;
+
+        // Check the fill taking the rest and the secret opening it
+        let fill_amount = escrow.amount - escrow.filled;
+        let filled = Self::check_fill(&env, &escrow, index, &secret, &proof, fill_amount)?;
 
-        // Store the amount and asset info before moving escrow
-        let transfer_amount = escrow.amount;
-        let asset_address = escrow.asset.clone();
-        let maker_address = escrow.maker.clone();
+        // Update escrow
+        escrow.filled = filled;
+        escrow.secret = Some(secret.clone());
+        Self::set_status(&env, &mut escrow, 2); // completed
 
         // Store updated escrow
-        escrows.set(escrow_id.clone(), escrow);
-        env.storage().instance().set(&ESCROWS, &escrows);
+        Self::save_escrow(&env, &escrow);
 
-        // Transfer assets to resolver
-        Self::transfer_asset(&env, &asset_address, &maker_address, &resolver, transfer_amount)?;
+        // Release the rest, and the safety deposit to the caller
+        Self::transfer_asset(&env, &escrow.asset, &Self::recipient(&escrow), fill_amount);
+        if let Some(token) = &escrow.deposit_token {
+            Self::transfer_asset(&env, token, &resolver, escrow.safety_deposit);
+        }
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("completed"),),
-            (escrow_id, resolver, secret),
-        );
+        // Emit events
+        HtlcPartiallyFilled {
+            escrow_id: escrow_id.clone(),
+            resolver: resolver.clone(),
+            index,
+            secret: secret.clone(),
+            fill_amount,
+            filled,
+        }
+        .publish(&env);
+        HtlcPublicWithdrawn {
+            escrow_id: escrow_id.clone(),
+            resolver: resolver.clone(),
+            secret: secret.clone(),
+            safety_deposit: escrow.safety_deposit,
+        }
+        .publish(&env);
+        HtlcCompleted {
+            escrow_id,
+            resolver,
+            secret,
+        }
+        .publish(&env);
 
         Ok(())
     }
 
-    /// Refund escrow after timelock expires
+    /// Refund escrow once its cancellation opens
+    ///
+    /// Only whoever funded the escrow can refund it, and they take the
+    /// resolver's safety deposit.
     pub fn refund_escrow(env: Env, escrow_id: Bytes) -> Result<(), Error> {
-        // Get escrows
-        let mut escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
+        Self::cancel(&env, escrow_id, None)
+    }
 
-        // Get escrow
-        let mut escrow = escrows.get(escrow_id.clone()).ok_or(Error::EscrowNotFound)?;
+    /// Cancel an escrow once its public cancellation opens, returning its
+    /// funds to whoever funded it and paying the resolver's safety deposit
+    /// to `caller`
+    pub fn public_cancel(env: Env, escrow_id: Bytes, caller: Address) -> Result<(), Error> {
+        caller.require_auth();
+        Self::cancel(&env, escrow_id, Some(caller))
+    }
 
-        // Check authorization (only maker can refund)
-        escrow.maker.require_auth();
+    /// Return an expired escrow's funds, paying the safety deposit to
+    /// `caller`, or to the depositor if they cancel it themselves
+    fn cancel(env: &Env, escrow_id: Bytes, caller: Option<Address>) -> Result<(), Error> {
+        // Get escrow
+        let mut escrow = Self::load_escrow(env, &escrow_id)?;
+
+        // Check authorization (only whoever funded the escrow can refund)
+        let depositor = Self::depositor(&escrow);
+        let caller = match caller {
+            Some(caller) => caller,
+            None => {
+                depositor.require_auth();
+                depositor.clone()
+            }
+        };
 
         // Check escrow status
         if escrow.status != 0 && escrow.status != 1 {
             return Err(Error::InvalidStatus);
         }
 
-        // Check timelock
-        if env.ledger().timestamp() <= escrow.time_lock {
+        // Check timelocks
+        let now = env.ledger().timestamp();
+        if now < escrow.time_locks.cancellation {
             return Err(Error::TimelockNotExpired);
         }
+        if caller != depositor && now < escrow.time_locks.public_cancellation {
+            return Err(Error::PublicCancellationNotOpen);
+        }
 
         // Update escrow status
-        escrow.status = 3; // refunded
+        Self::set_status(env, &mut escrow, 3); // refunded
 
         // Store updated escrow
-        escrows.set(escrow_id.clone(), escrow.clone());
-        env.storage().instance().set(&ESCROWS, &escrows);
+        Self::save_escrow(env, &escrow);
+
+        // Return what partial fills left of the held assets
+        Self::transfer_asset(
+            env,
+            &escrow.asset,
+            &depositor,
+            escrow.amount - escrow.filled,
+        );
 
-        // Refund assets to maker
-        Self::refund_asset(&env, &escrow.asset, &escrow.maker, escrow.amount)?;
+        // The resolver forfeits its safety deposit
+        if let Some(token) = &escrow.deposit_token {
+            Self::transfer_asset(env, token, &caller, escrow.safety_deposit);
+        }
 
         // Emit event
-        env.events().publish(
-            (symbol_short!("refunded"),),
-            (escrow_id, escrow.maker),
-        );
+        HtlcRefunded {
+            escrow_id,
+            maker: escrow.maker,
+        }
+        .publish(env);
 
         Ok(())
     }
 
     /// Get escrow details
     pub fn get_escrow(env: Env, escrow_id: Bytes) -> Option<Escrow> {
-        let escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
-        
-        escrows.get(escrow_id)
+        env.storage().persistent().get(&(ESCROW, escrow_id))
     }
 
-    /// Get all escrows for a maker
-    pub fn get_escrows_by_maker(env: Env, maker: Address) -> Vec<Escrow> {
-        let escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
-
-        let mut result = Vec::new(&env);
-        
-        for (_, escrow) in escrows.iter() {
-            if escrow.maker == maker {
-                result.push_back(escrow);
+    /// Page through a maker's escrows in the order they were created, at
+    /// most `MAX_PAGE_SIZE` at a time. Escrows no longer stored are skipped.
+    pub fn get_escrows_by_maker(env: Env, maker: Address, start: u32, limit: u32) -> Vec<Escrow> {
+        let count = Self::get_maker_escrow_count(env.clone(), maker.clone());
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+
+        let mut page = Vec::new(&env);
+        for index in start..end {
+            let escrow_id: Option<Bytes> =
+                env.storage()
+                    .persistent()
+                    .get(&(BY_MAKER, maker.clone(), index));
+            if let Some(escrow) =
+                escrow_id.and_then(|escrow_id| Self::load_escrow(&env, &escrow_id).ok())
+            {
+                page.push_back(escrow);
             }
         }
-        
-        result
+        page
     }
 
-    /// Helper function to generate unique escrow ID
-    fn generate_escrow_id(
-        env: &Env,
-        _maker: &Address,
-        amount: i128,
-        _asset: &Address,
-    ) -> Bytes {
-        // Get and increment counter
-        let mut counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
-        counter += 1;
-        env.storage().instance().set(&COUNTER, &counter);
+    /// Number of escrows ever opened
+    pub fn get_escrow_count(env: Env) -> u64 {
+        env.storage().instance().get(&COUNTER).unwrap_or(0)
+    }
 
-        // Create unique data for hashing
-        let timestamp = env.ledger().timestamp();
-        let sequence = env.ledger().sequence();
-        
-        // Create unique data for hashing using timestamp, sequence, and counter
-        let data_bytes = Bytes::from_slice(env, &[
-            (timestamp >> 56) as u8,
-            (timestamp >> 48) as u8,
-            (timestamp >> 40) as u8,
-            (timestamp >> 32) as u8,
-            (timestamp >> 24) as u8,
-            (timestamp >> 16) as u8,
-            (timestamp >> 8) as u8,
-            timestamp as u8,
-            (sequence >> 24) as u8,
-            (sequence >> 16) as u8,
-            (sequence >> 8) as u8,
-            sequence as u8,
-            (counter >> 56) as u8,
-            (counter >> 48) as u8,
-            (counter >> 40) as u8,
-            (counter >> 32) as u8,
-            (counter >> 24) as u8,
-            (counter >> 16) as u8,
-            (counter >> 8) as u8,
-            counter as u8,
-            (amount >> 120) as u8,
-            (amount >> 112) as u8,
-            (amount >> 104) as u8,
-            (amount >> 96) as u8,
-            (amount >> 88) as u8,
-            (amount >> 80) as u8,
-            (amount >> 72) as u8,
-            (amount >> 64) as u8,
-            (amount >> 56) as u8,
-            (amount >> 48) as u8,
-            (amount >> 40) as u8,
-            (amount >> 32) as u8,
-            (amount >> 24) as u8,
-            (amount >> 16) as u8,
-            (amount >> 8) as u8,
-            amount as u8,
-        ]);
-        
-        env.crypto().sha256(&data_bytes).into()
-    }
-
-    /// Helper function to compute hash lock (SHA256)
-    fn compute_hash_lock(env: &Env, secret: &Bytes) -> Bytes {
-        env.crypto().sha256(secret).into()
+    /// Number of escrows opened for `maker`
+    pub fn get_maker_escrow_count(env: Env, maker: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&(BY_MAKER, maker))
+            .unwrap_or(0)
     }
 
-    /// Get contract statistics
-    pub fn get_stats(env: Env) -> (u64, u32, u32, u32, u32) {
-        let escrows: Map<Bytes, Escrow> = env
-            .storage()
-            .instance()
-            .get(&ESCROWS)
-            .unwrap_or_else(|| Map::new(&env));
-
-        let _total_count = escrows.len();
-        let mut pending = 0;
-        let mut locked = 0;
-        let mut completed = 0;
-        let mut refunded = 0;
-
-        for (_, escrow) in escrows.iter() {
-            match escrow.status {
-                0 => pending += 1,
-                1 => locked += 1,
-                2 => completed += 1,
-                3 => refunded += 1,
-                _ => {}
-            }
-        }
+    /// ID the escrow opened for `order` gets
+    pub fn compute_escrow_id(env: Env, order: Order) -> Bytes {
+        Self::generate_escrow_id(&env, &order)
+    }
 
-        let counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
-        
-        (counter, pending, locked, completed, refunded)
+    /// Helper function to derive an escrow ID from its order
+    fn generate_escrow_id(env: &Env, order: &Order) -> Bytes {
+        env.crypto().sha256(&order.clone().to_xdr(env)).into()
     }
 
-    /// Handle escrow deposit during creation
-    fn handle_deposit(
-        env: &Env,
-        asset: &Address,
-        from: &Address,
-        amount: i128,
-    ) -> Result<(), Error> {
-        let token_client = token::Client::new(env, asset);
-        
-        // Transfer tokens from maker to contract
-        token_client.transfer(from, &env.current_contract_address(), &amount);
-        
-        Ok(())
+    /// Helper function to compute hash lock with the escrow's hash
+    fn compute_hash_lock(env: &Env, hash_algo: HashAlgo, secret: &Bytes) -> Bytes {
+        Self::hash(env, hash_algo, secret).into()
     }
 
-    /// Transfer asset from contract to recipient
-    fn transfer_asset(
+    fn hash(env: &Env, hash_algo: HashAlgo, data: &Bytes) -> BytesN<32> {
+        match hash_algo {
+            HashAlgo::Sha256 => env.crypto().sha256(data).into(),
+            HashAlgo::Keccak256 => env.crypto().keccak256(data).into(),
+        }
+    }
+
+    /// Root of the Merkle tree holding `leaf`, hashing pairs in sorted order
+    fn merkle_root(
         env: &Env,
-        _asset: &Address,
-        _from: &Address,
-        to: &Address,
-        _amount: i128,
-    ) -> Result<(), Error> {
-        let token_client = token::Client::new(env, _asset);
-        
-        // Transfer tokens from contract to recipient
-        token_client.transfer(&env.current_contract_address(), to, &_amount);
-        
-        Ok(())
+        hash_algo: HashAlgo,
+        leaf: BytesN<32>,
+        proof: &Vec<BytesN<32>>,
+    ) -> Bytes {
+        let mut node = leaf;
+        for sibling in proof.iter() {
+            let (left, right) = if node.to_array() <= sibling.to_array() {
+                (node, sibling)
+            } else {
+                (sibling, node)
+            };
+            let mut pair: Bytes = left.into();
+            pair.append(&right.into());
+            node = Self::hash(env, hash_algo, &pair);
+        }
+        node.into()
+    }
+
+    /// Index of the secret opening a fill that leaves `filled` of the
+    /// escrow taken
+    fn part_index(escrow: &Escrow, filled: i128) -> u32 {
+        if filled == escrow.amount {
+            escrow.parts
+        } else {
+            ((filled - 1) * escrow.parts as i128 / escrow.amount) as u32
+        }
+    }
+
+    /// Get contract statistics
+    pub fn get_stats(env: Env) -> (u64, u32, u32, u32, u32) {
+        let count =
+            |status: u32| -> u32 { env.storage().instance().get(&(STATUS, status)).unwrap_or(0) };
+        let counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
+
+        (counter, count(0), count(1), count(2), count(3))
+    }
+
+    /// Read a stored escrow
+    fn load_escrow(env: &Env, escrow_id: &Bytes) -> Result<Escrow, Error> {
+        env.storage()
+            .persistent()
+            .get(&(ESCROW, escrow_id.clone()))
+            .ok_or(Error::EscrowNotFound)
+    }
+
+    /// Store an escrow under its own key, extending its TTL
+    fn save_escrow(env: &Env, escrow: &Escrow) {
+        let key = (ESCROW, escrow.id.clone());
+        env.storage().persistent().set(&key, escrow);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Give a new escrow the next position in the index, counting it
+    fn index_escrow(env: &Env, escrow_id: &Bytes) {
+        let counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
+        let key = (INDEX, counter);
+        env.storage().persistent().set(&key, escrow_id);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        env.storage().instance().set(&COUNTER, &(counter + 1));
+    }
+
+    /// Add an escrow to the end of its maker's list
+    fn index_by_maker(env: &Env, maker: &Address, escrow_id: &Bytes) {
+        let count = Self::get_maker_escrow_count(env.clone(), maker.clone());
+        let key = (BY_MAKER, maker.clone(), count);
+        env.storage().persistent().set(&key, escrow_id);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        let count_key = (BY_MAKER, maker.clone());
+        env.storage().persistent().set(&count_key, &(count + 1));
+        env.storage().persistent().extend_ttl(
+            &count_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    /// Move an escrow to `status`, keeping the counts in `get_stats` in
+    /// step
+    fn set_status(env: &Env, escrow: &mut Escrow, status: u32) {
+        Self::track_status(env, Some(escrow.status), status);
+        escrow.status = status;
+    }
+
+    fn track_status(env: &Env, from: Option<u32>, to: u32) {
+        if let Some(from) = from {
+            let count: u32 = env.storage().instance().get(&(STATUS, from)).unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&(STATUS, from), &count.saturating_sub(1));
+        }
+        let count: u32 = env.storage().instance().get(&(STATUS, to)).unwrap_or(0);
+        env.storage().instance().set(&(STATUS, to), &(count + 1));
     }
 
-    /// Refund asset from contract to original owner
-    fn refund_asset(
+    /// Take the maker's deposit into the contract during creation
+    fn handle_deposit(
         env: &Env,
         asset: &Address,
-        to: &Address,
+        from: &Address,
         amount: i128,
     ) -> Result<(), Error> {
         let token_client = token::Client::new(env, asset);
-        
-        // Transfer tokens from contract back to maker
-        token_client.transfer(&env.current_contract_address(), to, &amount);
-        
+        if token_client.balance(from) < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Transfer tokens from maker to contract
+        token_client.transfer(from, &env.current_contract_address(), &amount);
+        Ok(())
+    }
+
+    /// Check `resolver` locked the escrow and may withdraw from it now
+    fn check_withdrawal(env: &Env, escrow: &Escrow, resolver: &Address) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        if now < escrow.time_locks.withdrawal {
+            return Err(Error::FinalityNotReached);
+        }
+        if now >= escrow.time_locks.cancellation {
+            return Err(Error::TimelockExpired);
+        }
+        if escrow.resolver.as_ref() != Some(resolver) {
+            return Err(Error::NotResolver);
+        }
+        Ok(())
+    }
+
+    /// Check the public withdrawal window is open
+    fn check_public_withdrawal(env: &Env, escrow: &Escrow) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        if now < escrow.time_locks.public_withdrawal {
+            return Err(Error::PublicWithdrawalNotOpen);
+        }
+        if now >= escrow.time_locks.cancellation {
+            return Err(Error::TimelockExpired);
+        }
         Ok(())
     }
+
+    /// Check a fill of a partial escrow against the part it reaches and the
+    /// secret committed for that part, returning the amount filled after it
+    fn check_fill(
+        env: &Env,
+        escrow: &Escrow,
+        index: u32,
+        secret: &Bytes,
+        proof: &Vec<BytesN<32>>,
+        fill_amount: i128,
+    ) -> Result<i128, Error> {
+        let filled = escrow.filled + fill_amount;
+        if fill_amount <= 0 || filled > escrow.amount {
+            return Err(Error::InvalidFill);
+        }
+        let part = Self::part_index(escrow, filled);
+        if index != part || (escrow.filled > 0 && part <= Self::part_index(escrow, escrow.filled)) {
+            return Err(Error::InvalidFill);
+        }
+
+        // Verify the secret is the committed one for this index
+        let mut leaf = Bytes::from_array(env, &(index as u64).to_be_bytes());
+        leaf.append(&Self::hash(env, escrow.hash_algo, secret).into());
+        let leaf = Self::hash(env, escrow.hash_algo, &leaf);
+        if Self::merkle_root(env, escrow.hash_algo, leaf, proof) != escrow.hash_lock {
+            return Err(Error::InvalidProof);
+        }
+        Ok(filled)
+    }
+
+    /// Who withdrawals pay: the resolver that locked the escrow on the
+    /// source side, the maker on the destination side
+    fn recipient(escrow: &Escrow) -> Address {
+        match escrow.side {
+            EscrowSide::Src => escrow
+                .resolver
+                .clone()
+                .expect("Locked escrow without resolver"),
+            EscrowSide::Dst => escrow.maker.clone(),
+        }
+    }
+
+    /// Who funded the escrow and is refunded: the maker on the source
+    /// side, the taker on the destination side
+    fn depositor(escrow: &Escrow) -> Address {
+        match escrow.side {
+            EscrowSide::Src => escrow.maker.clone(),
+            EscrowSide::Dst => escrow
+                .taker
+                .clone()
+                .expect("Destination escrow without taker"),
+        }
+    }
+
+    /// Take the configured safety deposit for `escrow` from `resolver`, if
+    /// one is required
+    fn post_safety_deposit(env: &Env, escrow: &mut Escrow, resolver: &Address) {
+        let Some(deposit) = env.storage().instance().get::<_, SafetyDeposit>(&DEPOSIT) else {
+            return;
+        };
+        token::Client::new(env, &deposit.token).transfer(
+            resolver,
+            &env.current_contract_address(),
+            &deposit.amount,
+        );
+        escrow.deposit_token = Some(deposit.token);
+        escrow.safety_deposit = deposit.amount;
+    }
+
+    /// Give the resolver of a completed escrow its safety deposit back
+    fn return_safety_deposit(env: &Env, escrow: &Escrow) {
+        if let (Some(resolver), Some(token)) = (&escrow.resolver, &escrow.deposit_token) {
+            Self::transfer_asset(env, token, resolver, escrow.safety_deposit);
+        }
+    }
+
+    /// Transfer held assets from the contract to `to`
+    fn transfer_asset(env: &Env, asset: &Address, to: &Address, amount: i128) {
+        token::Client::new(env, asset).transfer(&env.current_contract_address(), to, &amount);
+    }
+}
+
+stellhydra_access::ownable!(StellarEthEscrow);
+stellhydra_access::pausable!(StellarEthEscrow);
+stellhydra_access::roles!(StellarEthEscrow);
+
+impl Upgradeable for StellarEthEscrow {
+    const VERSION: u32 = 3;
+
+    fn migrate_step(env: &Env, from: u32) {
+        if from == 1 {
+            // Move the escrows out of the instance map into their own
+            // entries, indexed in the map's order
+            let escrows: Map<Bytes, Val> = env
+                .storage()
+                .instance()
+                .get(&ESCROWS)
+                .unwrap_or_else(|| Map::new(env));
+            env.storage().instance().set(&COUNTER, &0u64);
+            for (escrow_id, escrow) in escrows.iter() {
+                let escrow = legacy::decode(env, escrow);
+                Self::save_escrow(env, &escrow);
+                Self::index_escrow(env, &escrow_id);
+                Self::track_status(env, None, escrow.status);
+            }
+            env.storage().instance().remove(&ESCROWS);
+        }
+        if from == 2 {
+            // Leave indexing the escrows by maker to `index_makers`, a page
+            // at a time
+            let counter: u64 = env.storage().instance().get(&COUNTER).unwrap_or(0);
+            if counter > 0 {
+                env.storage()
+                    .instance()
+                    .set(&MAKER_CURSOR, &(0u64, counter));
+            }
+        }
+    }
 }
 
+stellhydra_access::upgradeable!(StellarEthEscrow);
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::{testutils::{Address as _, Ledger}, Env};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env, IntoVal,
+    };
+
+    fn create_token(env: &Env, holder: &Address, amount: i128) -> Address {
+        let token_admin = Address::generate(env);
+        let asset = env
+            .register_stellar_asset_contract_v2(token_admin)
+            .address();
+        StellarAssetClient::new(env, &asset).mint(holder, &amount);
+        asset
+    }
+
+    /// Withdrawable right away, exclusively for the first half hour, until
+    /// cancellation after an hour and public cancellation after two
+    fn time_locks(env: &Env) -> TimeLocks {
+        let now = env.ledger().timestamp();
+        TimeLocks {
+            withdrawal: now,
+            public_withdrawal: now + 1800,
+            cancellation: now + 3600,
+            public_cancellation: now + 7200,
+        }
+    }
+
+    /// Sha256 Merkle tree over the secrets of a partial escrow: the secrets
+    /// and their leaves, in index order
+    fn secrets_tree(env: &Env, secrets: u32) -> (Vec<Bytes>, Vec<BytesN<32>>) {
+        let mut values = Vec::new(env);
+        let mut leaves = Vec::new(env);
+        for index in 0..secrets {
+            let secret = Bytes::from_array(env, &[index as u8 + 1; 32]);
+            let mut leaf = Bytes::from_array(env, &(index as u64).to_be_bytes());
+            leaf.append(&env.crypto().sha256(&secret).into());
+            leaves.push_back(env.crypto().sha256(&leaf).into());
+            values.push_back(secret);
+        }
+        (values, leaves)
+    }
+
+    /// Root of the tree over `leaves` and the proof of the leaf at `index`
+    fn merkle_proof(env: &Env, leaves: &Vec<BytesN<32>>, index: u32) -> (Bytes, Vec<BytesN<32>>) {
+        let mut level = leaves.clone();
+        let mut position = index;
+        let mut proof = Vec::new(env);
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                proof.push_back(sibling);
+            }
+            let mut next = Vec::new(env);
+            for start in (0..level.len()).step_by(2) {
+                let left = level.get(start).unwrap();
+                match level.get(start + 1) {
+                    Some(right) => {
+                        let (left, right) = if left.to_array() <= right.to_array() {
+                            (left, right)
+                        } else {
+                            (right, left)
+                        };
+                        let mut pair: Bytes = left.into();
+                        pair.append(&right.into());
+                        next.push_back(env.crypto().sha256(&pair).into());
+                    }
+                    None => next.push_back(left),
+                }
+            }
+            level = next;
+            position /= 2;
+        }
+        (level.get(0).unwrap().into(), proof)
+    }
 
     #[test]
     fn test_initialize() {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(StellarEthEscrow, ());
+        let contract_id = env.register_contract(None, StellarEthEscrow);
         let client = StellarEthEscrowClient::new(&env, &contract_id);
 
-        client.initialize();
+        client.initialize(&Address::generate(&env));
 
         let (counter, pending, locked, completed, refunded) = client.get_stats();
         assert_eq!(counter, 0);
@@ -461,27 +1272,64 @@ mod test {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(StellarEthEscrow, ());
+        let contract_id = env.register_contract(None, StellarEthEscrow);
         let client = StellarEthEscrowClient::new(&env, &contract_id);
 
-        client.initialize();
+        client.initialize(&Address::generate(&env));
 
         let maker = Address::generate(&env);
-        let asset = Address::generate(&env);
         let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
         let hash_lock = Bytes::from_slice(&env, b"test_hash_lock_32_bytes_exactly");
-        let time_locks = TimeLocks {
-            withdrawal: env.ledger().timestamp() + 3600, // 1 hour
-            refund: env.ledger().timestamp() + 7200,     // 2 hours
-        };
+        let time_locks = time_locks(&env);
 
-        let escrow_id = client.create_escrow(&maker, &amount, &asset, &hash_lock, &time_locks);
+        let order = Order {
+            maker: maker.clone(),
+            taker: None,
+            amount,
+            asset: asset.clone(),
+            hash_lock: hash_lock.clone(),
+            time_locks: time_locks.clone(),
+            salt: 0,
+        };
+        let expected_id = client.compute_escrow_id(&order);
+
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
         let escrow = client.get_escrow(&escrow_id).unwrap();
-        
+
+        assert_eq!(escrow_id, expected_id);
         assert_eq!(escrow.maker, maker);
         assert_eq!(escrow.amount, amount);
         assert_eq!(escrow.asset, asset);
         assert_eq!(escrow.status, 0); // pending
+
+        // The same order can't be opened twice, another salt can
+        assert_eq!(
+            client.try_create_escrow(
+                &maker,
+                &None,
+                &amount,
+                &asset,
+                &hash_lock,
+                &HashAlgo::Sha256,
+                &time_locks,
+                &0
+            ),
+            Err(Ok(Error::EscrowExists))
+        );
+        assert_ne!(
+            client.compute_escrow_id(&Order { salt: 1, ..order }),
+            escrow_id
+        );
     }
 
     #[test]
@@ -489,27 +1337,33 @@ mod test {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(StellarEthEscrow, ());
+        let contract_id = env.register_contract(None, StellarEthEscrow);
         let client = StellarEthEscrowClient::new(&env, &contract_id);
 
-        client.initialize();
+        client.initialize(&Address::generate(&env));
 
         let maker = Address::generate(&env);
         let resolver = Address::generate(&env);
-        let asset = Address::generate(&env);
         let amount = 1000i128;
-        
+        let asset = create_token(&env, &maker, amount);
+
         // Create secret and hash
         let secret = Bytes::from_slice(&env, b"my_secret_32_bytes_exactly_here!");
         let hash_lock: Bytes = env.crypto().sha256(&secret).into();
-        
-        let time_locks = TimeLocks {
-            withdrawal: env.ledger().timestamp() + 3600, // 1 hour
-            refund: env.ledger().timestamp() + 7200,     // 2 hours
-        };
+
+        let time_locks = time_locks(&env);
 
         // Create escrow
-        let escrow_id = client.create_escrow(&maker, &amount, &asset, &hash_lock, &time_locks);
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
 
         // Lock escrow
         client.lock_escrow(&escrow_id, &resolver);
@@ -527,34 +1381,106 @@ mod test {
         assert_eq!(escrow.secret, Some(secret));
     }
 
+    #[test]
+    fn test_escrow_holds_funds_until_completed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let asset = create_token(&env, &maker, 1500);
+        let token_client = token::Client::new(&env, &asset);
+
+        let secret = Bytes::from_slice(&env, b"custody_secret_32_bytes_exactly!");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let time_locks = time_locks(&env);
+
+        // The maker can't lock up more than they hold
+        assert_eq!(
+            client.try_create_escrow(
+                &maker,
+                &None,
+                &2000,
+                &asset,
+                &hash_lock,
+                &HashAlgo::Sha256,
+                &time_locks,
+                &0
+            ),
+            Err(Ok(Error::InsufficientBalance))
+        );
+
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &1000,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &1,
+        );
+        assert_eq!(token_client.balance(&maker), 500);
+        assert_eq!(token_client.balance(&contract_id), 1000);
+
+        // Locking doesn't move the funds
+        client.lock_escrow(&escrow_id, &resolver);
+        assert_eq!(token_client.balance(&contract_id), 1000);
+
+        client.complete_escrow(&escrow_id, &secret, &resolver);
+        assert_eq!(token_client.balance(&resolver), 1000);
+        assert_eq!(token_client.balance(&contract_id), 0);
+        assert_eq!(token_client.balance(&maker), 500);
+    }
+
     #[test]
     fn test_refund_escrow() {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(StellarEthEscrow, ());
+        let contract_id = env.register_contract(None, StellarEthEscrow);
         let client = StellarEthEscrowClient::new(&env, &contract_id);
 
-        client.initialize();
+        client.initialize(&Address::generate(&env));
 
         let maker = Address::generate(&env);
-        let asset = Address::generate(&env);
         let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
         let hash_lock = Bytes::from_slice(&env, b"test_hash_lock_32_bytes_exactly");
-        
+
         // Set timelock to simulate expired state for testing
         // First, advance the ledger time
         env.ledger().with_mut(|li| {
             li.timestamp = 1000; // Set a base timestamp
         });
-        
+
         let time_locks = TimeLocks {
-            withdrawal: 500, // Set withdrawal timelock in the past
-            refund: env.ledger().timestamp() + 7200,
+            withdrawal: 0,
+            public_withdrawal: 250,
+            cancellation: 500, // Set cancellation timelock in the past
+            public_cancellation: env.ledger().timestamp() + 7200,
         };
 
         // Create escrow
-        let escrow_id = client.create_escrow(&maker, &amount, &asset, &hash_lock, &time_locks);
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+
+        let token_client = token::Client::new(&env, &asset);
+        assert_eq!(token_client.balance(&maker), 0);
+        assert_eq!(token_client.balance(&contract_id), amount);
 
         // Try to refund (should succeed since timelock expired)
         client.refund_escrow(&escrow_id);
@@ -562,6 +1488,8 @@ mod test {
         // Verify escrow is refunded
         let escrow = client.get_escrow(&escrow_id).unwrap();
         assert_eq!(escrow.status, 3); // refunded
+        assert_eq!(token_client.balance(&maker), amount);
+        assert_eq!(token_client.balance(&contract_id), 0);
     }
 
     #[test]
@@ -569,26 +1497,32 @@ mod test {
         let env = Env::default();
         env.mock_all_auths();
 
-        let contract_id = env.register(StellarEthEscrow, ());
+        let contract_id = env.register_contract(None, StellarEthEscrow);
         let client = StellarEthEscrowClient::new(&env, &contract_id);
 
-        client.initialize();
+        client.initialize(&Address::generate(&env));
 
         let maker = Address::generate(&env);
         let resolver = Address::generate(&env);
-        let asset = Address::generate(&env);
         let amount = 1000i128;
-        
+        let asset = create_token(&env, &maker, amount);
+
         let secret = Bytes::from_slice(&env, b"correct_secret_32_bytes_exactly!");
         let hash_lock: Bytes = env.crypto().sha256(&secret).into();
-        
-        let time_locks = TimeLocks {
-            withdrawal: env.ledger().timestamp() + 3600,
-            refund: env.ledger().timestamp() + 7200,
-        };
+
+        let time_locks = time_locks(&env);
 
         // Create and lock escrow
-        let escrow_id = client.create_escrow(&maker, &amount, &asset, &hash_lock, &time_locks);
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
         client.lock_escrow(&escrow_id, &resolver);
 
         // Complete escrow with correct secret
@@ -599,4 +1533,978 @@ mod test {
         assert_eq!(escrow.status, 2); // completed
         assert_eq!(escrow.secret, Some(secret));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_timelock_phases() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let other = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, 3 * amount);
+        let token_client = token::Client::new(&env, &asset);
+
+        let secret = Bytes::from_slice(&env, b"phases_secret_32_bytes_exactly!!");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let now = env.ledger().timestamp();
+        let time_locks = TimeLocks {
+            withdrawal: now + 600, // finality delay
+            public_withdrawal: now + 1800,
+            cancellation: now + 3600,
+            public_cancellation: now + 7200,
+        };
+
+        let mut unordered = time_locks.clone();
+        unordered.cancellation = unordered.public_cancellation + 1;
+        assert_eq!(
+            client.try_create_escrow(
+                &maker,
+                &None,
+                &amount,
+                &asset,
+                &hash_lock,
+                &HashAlgo::Sha256,
+                &unordered,
+                &0
+            ),
+            Err(Ok(Error::InvalidTimeLocks))
+        );
+
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &1,
+        );
+        let late = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &2,
+        );
+        let expired = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &3,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+        client.lock_escrow(&expired, &resolver);
+
+        // Nothing moves until the other chain is final
+        assert_eq!(
+            client.try_complete_escrow(&escrow_id, &secret, &resolver),
+            Err(Ok(Error::FinalityNotReached))
+        );
+
+        // Then the withdrawal is the resolver's alone
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.withdrawal);
+        assert_eq!(
+            client.try_complete_escrow(&escrow_id, &secret, &other),
+            Err(Ok(Error::NotResolver))
+        );
+
+        // Once public, a registered resolver can complete it, still paying
+        // the resolver, and it's too late to lock
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.public_withdrawal);
+        assert_eq!(
+            client.try_lock_escrow(&late, &resolver),
+            Err(Ok(Error::LockWindowClosed))
+        );
+        client.grant_role(&RESOLVER, &other);
+        client.public_withdraw(&escrow_id, &secret, &other);
+        assert_eq!(token_client.balance(&resolver), amount);
+        assert_eq!(token_client.balance(&other), 0);
+
+        // Withdrawals close at cancellation
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.cancellation);
+        assert_eq!(
+            client.try_complete_escrow(&expired, &secret, &resolver),
+            Err(Ok(Error::TimelockExpired))
+        );
+        client.refund_escrow(&expired);
+        assert_eq!(token_client.balance(&maker), amount);
+    }
+
+    #[test]
+    fn test_keccak_hash_lock_matches_evm_escrows() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, 2 * amount);
+
+        let secret = Bytes::from_slice(&env, b"keccak_secret_32_bytes_exactly!!");
+        let time_locks = time_locks(&env);
+
+        // A sha256 lock doesn't open a keccak256 escrow
+        let sha_lock: Bytes = env.crypto().sha256(&secret).into();
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &sha_lock,
+            &HashAlgo::Keccak256,
+            &time_locks,
+            &0,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+        assert_eq!(
+            client.try_complete_escrow(&escrow_id, &secret, &resolver),
+            Err(Ok(Error::InvalidSecret))
+        );
+
+        let keccak_lock: Bytes = env.crypto().keccak256(&secret).into();
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &keccak_lock,
+            &HashAlgo::Keccak256,
+            &time_locks,
+            &1,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+        client.complete_escrow(&escrow_id, &secret, &resolver);
+
+        let escrow = client.get_escrow(&escrow_id).unwrap();
+        assert_eq!(escrow.hash_algo, HashAlgo::Keccak256);
+        assert_eq!(escrow.status, 2); // completed
+        assert_eq!(token::Client::new(&env, &asset).balance(&resolver), amount);
+    }
+
+    #[test]
+    fn test_designated_taker_alone_locks() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let taker = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
+        let token_client = token::Client::new(&env, &asset);
+
+        let secret = Bytes::from_slice(&env, b"taker_only_secret_32_bytes_long!");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let time_locks = time_locks(&env);
+
+        let escrow_id = client.create_escrow(
+            &maker,
+            &Some(taker.clone()),
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        assert_eq!(
+            client.get_escrow(&escrow_id).unwrap().taker,
+            Some(taker.clone())
+        );
+        assert_eq!(
+            client.try_lock_escrow(&escrow_id, &stranger),
+            Err(Ok(Error::NotTaker))
+        );
+        client.lock_escrow(&escrow_id, &taker);
+        assert_eq!(
+            client.try_complete_escrow(&escrow_id, &secret, &stranger),
+            Err(Ok(Error::NotResolver))
+        );
+
+        // Even a public withdrawal pays the taker
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.public_withdrawal);
+        client.grant_role(&RESOLVER, &stranger);
+        client.public_withdraw(&escrow_id, &secret, &stranger);
+        assert_eq!(token_client.balance(&taker), amount);
+        assert_eq!(token_client.balance(&stranger), 0);
+    }
+
+    #[test]
+    fn test_dst_escrow_mirrors_the_roles() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let taker = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &taker, 2 * amount);
+        let token_client = token::Client::new(&env, &asset);
+
+        let secret = Bytes::from_slice(&env, b"dst_side_secret_32_bytes_exactly");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let time_locks = time_locks(&env);
+
+        // The resolver funds the destination side, already locked
+        let escrow_id = client.create_dst_escrow(
+            &taker,
+            &maker,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        let escrow = client.get_escrow(&escrow_id).unwrap();
+        assert_eq!(escrow.side, EscrowSide::Dst);
+        assert_eq!(escrow.taker, Some(taker.clone()));
+        assert_eq!(escrow.status, 1); // locked
+        assert_eq!(token_client.balance(&taker), amount);
+        assert_eq!(
+            client.try_lock_escrow(&escrow_id, &taker),
+            Err(Ok(Error::InvalidStatus))
+        );
+
+        // The secret releases the funds to the maker
+        client.complete_escrow(&escrow_id, &secret, &taker);
+        assert_eq!(token_client.balance(&maker), amount);
+
+        // Unclaimed, the funds go back to the resolver
+        let refunded = client.create_dst_escrow(
+            &taker,
+            &maker,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &1,
+        );
+        assert_eq!(token_client.balance(&taker), 0);
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.cancellation);
+        client.refund_escrow(&refunded);
+        assert_eq!(env.auths()[0].0, taker);
+        assert_eq!(token_client.balance(&taker), amount);
+        assert_eq!(token_client.balance(&maker), amount);
+    }
+
+    #[test]
+    fn test_safety_deposit_follows_the_outcome() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, 3 * amount);
+        let native = create_token(&env, &resolver, 300);
+        let native_client = token::Client::new(&env, &native);
+
+        assert_eq!(
+            client.try_set_safety_deposit(&Some(SafetyDeposit {
+                token: native.clone(),
+                amount: 0
+            })),
+            Err(Ok(Error::InvalidAmount))
+        );
+        client.set_safety_deposit(&Some(SafetyDeposit {
+            token: native.clone(),
+            amount: 100,
+        }));
+
+        let secret = Bytes::from_slice(&env, b"deposit_secret_32_bytes_exactly!");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let time_locks = time_locks(&env);
+
+        // Completing gives the resolver its deposit back
+        let completed = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        client.lock_escrow(&completed, &resolver);
+        assert_eq!(native_client.balance(&resolver), 200);
+        assert_eq!(native_client.balance(&contract_id), 100);
+        client.complete_escrow(&completed, &secret, &resolver);
+        assert_eq!(native_client.balance(&resolver), 300);
+
+        // Leaving it to a public withdrawal pays the deposit to whoever
+        // completes it
+        let left = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &1,
+        );
+        let abandoned = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &2,
+        );
+        client.lock_escrow(&left, &resolver);
+        client.lock_escrow(&abandoned, &resolver);
+        assert_eq!(
+            client.try_public_withdraw(&left, &secret, &keeper),
+            Err(Ok(Error::NotRegistered))
+        );
+        client.grant_role(&RESOLVER, &keeper);
+        assert_eq!(
+            client.try_public_withdraw(&left, &secret, &keeper),
+            Err(Ok(Error::PublicWithdrawalNotOpen))
+        );
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.public_withdrawal);
+        client.public_withdraw(&left, &secret, &keeper);
+        assert_eq!(
+            token::Client::new(&env, &asset).balance(&resolver),
+            2 * amount
+        );
+        assert_eq!(native_client.balance(&keeper), 100);
+
+        // Walking away forfeits it to whoever cancels
+        assert_eq!(
+            client.try_public_cancel(&abandoned, &keeper),
+            Err(Ok(Error::TimelockNotExpired))
+        );
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.cancellation);
+        assert_eq!(
+            client.try_public_cancel(&abandoned, &keeper),
+            Err(Ok(Error::PublicCancellationNotOpen))
+        );
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.public_cancellation);
+        client.public_cancel(&abandoned, &keeper);
+
+        assert_eq!(client.get_escrow(&abandoned).unwrap().status, 3); // refunded
+        assert_eq!(token::Client::new(&env, &asset).balance(&maker), amount);
+        assert_eq!(native_client.balance(&keeper), 200);
+        assert_eq!(native_client.balance(&resolver), 100);
+        assert_eq!(native_client.balance(&contract_id), 0);
+    }
+
+    #[test]
+    fn test_partial_fills_release_slices() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
+        let token_client = token::Client::new(&env, &asset);
+
+        // Four parts of 250, opened by five secrets
+        let (secrets, leaves) = secrets_tree(&env, 5);
+        let (root, _) = merkle_proof(&env, &leaves, 0);
+        let time_locks = time_locks(&env);
+        assert_eq!(
+            client.try_create_partial_escrow(
+                &maker,
+                &amount,
+                &asset,
+                &root,
+                &0,
+                &HashAlgo::Sha256,
+                &time_locks,
+                &0
+            ),
+            Err(Ok(Error::InvalidParts))
+        );
+        let escrow_id = client.create_partial_escrow(
+            &maker,
+            &amount,
+            &asset,
+            &root,
+            &4,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &1,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+
+        let fill = |index: u32, fill_amount: i128| {
+            let (_, proof) = merkle_proof(&env, &leaves, index);
+            client.try_complete_escrow_partial(
+                &escrow_id,
+                &index,
+                &secrets.get(index).unwrap(),
+                &proof,
+                &fill_amount,
+                &resolver,
+            )
+        };
+
+        // A partial escrow only opens through its Merkle tree
+        assert_eq!(
+            client.try_complete_escrow(&escrow_id, &secrets.get(0).unwrap(), &resolver),
+            Err(Ok(Error::WrongFillMode))
+        );
+
+        // The first quarter opens with secret 0
+        assert_eq!(fill(0, 250), Ok(Ok(())));
+        assert_eq!(token_client.balance(&resolver), 250);
+
+        // Staying in the same part would reuse secret 0
+        assert_eq!(fill(0, 100), Err(Ok(Error::InvalidFill)));
+        assert_eq!(fill(1, 100), Ok(Ok(())));
+        assert_eq!(fill(1, 100), Err(Ok(Error::InvalidFill)));
+
+        // The secret has to match the index it is proven for
+        let (_, proof) = merkle_proof(&env, &leaves, 2);
+        assert_eq!(
+            client.try_complete_escrow_partial(
+                &escrow_id,
+                &2,
+                &secrets.get(3).unwrap(),
+                &proof,
+                &250,
+                &resolver
+            ),
+            Err(Ok(Error::InvalidProof))
+        );
+
+        // The fill taking the rest opens with the last secret
+        assert_eq!(fill(3, 650), Err(Ok(Error::InvalidFill)));
+        assert_eq!(fill(4, 650), Ok(Ok(())));
+
+        let escrow = client.get_escrow(&escrow_id).unwrap();
+        assert_eq!(escrow.status, 2); // completed
+        assert_eq!(escrow.filled, amount);
+        assert_eq!(escrow.secret, secrets.get(4));
+        assert_eq!(token_client.balance(&resolver), amount);
+        assert_eq!(token_client.balance(&contract_id), 0);
+    }
+
+    #[test]
+    fn test_partial_escrow_refunds_what_is_left() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
+        let token_client = token::Client::new(&env, &asset);
+
+        let (secrets, leaves) = secrets_tree(&env, 3);
+        let (root, proof) = merkle_proof(&env, &leaves, 0);
+        let time_locks = time_locks(&env);
+        let escrow_id = client.create_partial_escrow(
+            &maker,
+            &amount,
+            &asset,
+            &root,
+            &2,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+        client.complete_escrow_partial(
+            &escrow_id,
+            &0,
+            &secrets.get(0).unwrap(),
+            &proof,
+            &400,
+            &resolver,
+        );
+
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.cancellation);
+        client.refund_escrow(&escrow_id);
+
+        assert_eq!(client.get_escrow(&escrow_id).unwrap().status, 3); // refunded
+        assert_eq!(token_client.balance(&resolver), 400);
+        assert_eq!(token_client.balance(&maker), 600);
+        assert_eq!(token_client.balance(&contract_id), 0);
+    }
+
+    #[test]
+    fn test_public_withdraw_takes_rest_of_partial_escrow() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, amount);
+        let token_client = token::Client::new(&env, &asset);
+        let native = create_token(&env, &resolver, 100);
+        client.set_safety_deposit(&Some(SafetyDeposit {
+            token: native.clone(),
+            amount: 100,
+        }));
+        client.grant_role(&RESOLVER, &keeper);
+
+        let (secrets, leaves) = secrets_tree(&env, 3);
+        let (root, first) = merkle_proof(&env, &leaves, 0);
+        let (_, last) = merkle_proof(&env, &leaves, 2);
+        let time_locks = time_locks(&env);
+        let escrow_id = client.create_partial_escrow(
+            &maker,
+            &amount,
+            &asset,
+            &root,
+            &2,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+        client.complete_escrow_partial(
+            &escrow_id,
+            &0,
+            &secrets.get(0).unwrap(),
+            &first,
+            &400,
+            &resolver,
+        );
+
+        assert_eq!(
+            client.try_public_withdraw_partial(
+                &escrow_id,
+                &2,
+                &secrets.get(2).unwrap(),
+                &last,
+                &keeper
+            ),
+            Err(Ok(Error::PublicWithdrawalNotOpen))
+        );
+        env.ledger()
+            .with_mut(|li| li.timestamp = time_locks.public_withdrawal);
+
+        // The single-secret path stays closed to partial escrows
+        assert_eq!(
+            client.try_public_withdraw(&escrow_id, &secrets.get(2).unwrap(), &keeper),
+            Err(Ok(Error::WrongFillMode))
+        );
+        // Taking the rest opens with the last secret only
+        let (_, middle) = merkle_proof(&env, &leaves, 1);
+        assert_eq!(
+            client.try_public_withdraw_partial(
+                &escrow_id,
+                &1,
+                &secrets.get(1).unwrap(),
+                &middle,
+                &keeper
+            ),
+            Err(Ok(Error::InvalidFill))
+        );
+        client.public_withdraw_partial(&escrow_id, &2, &secrets.get(2).unwrap(), &last, &keeper);
+
+        let escrow = client.get_escrow(&escrow_id).unwrap();
+        assert_eq!(escrow.status, 2); // completed
+        assert_eq!(escrow.filled, amount);
+        assert_eq!(token_client.balance(&resolver), amount);
+        assert_eq!(token::Client::new(&env, &native).balance(&keeper), 100);
+    }
+
+    #[test]
+    fn test_escrows_page_by_maker() {
+        let env = Env::default();
+        let scenario = testutils::EscrowScenario::new(&env)
+            .with_escrow()
+            .with_escrow()
+            .with_escrow();
+        let escrow = &scenario.escrow;
+
+        assert_eq!(escrow.get_escrow_count(), 3);
+        assert_eq!(escrow.get_maker_escrow_count(&scenario.maker), 3);
+        assert_eq!(escrow.get_maker_escrow_count(&scenario.resolver), 0);
+
+        let first_page = escrow.get_escrows_by_maker(&scenario.maker, &0, &2);
+        assert_eq!(first_page.len(), 2);
+        assert_eq!(
+            first_page.get(0).unwrap().id,
+            scenario.escrow_ids.get(0).unwrap()
+        );
+        let last_page = escrow.get_escrows_by_maker(&scenario.maker, &2, &2);
+        assert_eq!(last_page.len(), 1);
+        assert_eq!(last_page.get(0).unwrap().id, scenario.last_escrow());
+        assert_eq!(
+            escrow.get_escrows_by_maker(&scenario.maker, &3, &2).len(),
+            0
+        );
+        assert_eq!(
+            escrow
+                .get_escrows_by_maker(&scenario.maker, &u32::MAX, &u32::MAX)
+                .len(),
+            0
+        );
+
+        // Escrows that are gone are skipped rather than failing the page
+        env.as_contract(&escrow.address, || {
+            env.storage()
+                .persistent()
+                .remove(&(ESCROW, scenario.escrow_ids.get(1).unwrap()));
+        });
+        let page = escrow.get_escrows_by_maker(&scenario.maker, &0, &u32::MAX);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page.get(1).unwrap().id, scenario.last_escrow());
+    }
+
+    #[test]
+    fn test_migrate_moves_escrows_out_of_the_map() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        client.initialize(&Address::generate(&env));
+        assert_eq!(client.get_version(), 3);
+
+        let maker = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &contract_id, 2 * amount);
+        let hash_lock = Bytes::from_slice(&env, b"migrated_hash_lock_32_bytes_long");
+        let created_at = env.ledger().timestamp();
+        let time_lock = created_at + 3600;
+        let pending = Bytes::from_slice(&env, b"pending");
+        let locked = Bytes::from_slice(&env, b"locked");
+
+        // Store the escrows the way version 1 did
+        env.as_contract(&contract_id, || {
+            let mut map = Map::new(&env);
+            for (id, status) in [(pending.clone(), 0u32), (locked.clone(), 1)] {
+                let escrow = legacy::EscrowV1 {
+                    id: id.clone(),
+                    maker: maker.clone(),
+                    amount,
+                    asset: asset.clone(),
+                    hash_lock: hash_lock.clone(),
+                    time_lock,
+                    status,
+                    secret: None,
+                    created_at,
+                };
+                map.set(id, escrow);
+            }
+            env.storage().instance().set(&ESCROWS, &map);
+            env.storage().instance().set(&upgradeable::VERSION, &1u32);
+        });
+        assert_eq!(client.get_escrow(&pending), None);
+
+        assert_eq!(client.migrate(), 3);
+        let expiry = time_lock + 1;
+        assert_eq!(
+            client.get_escrow(&pending),
+            Some(Escrow {
+                id: pending.clone(),
+                maker: maker.clone(),
+                amount,
+                asset: asset.clone(),
+                hash_lock,
+                hash_algo: HashAlgo::Sha256,
+                time_locks: TimeLocks {
+                    withdrawal: created_at,
+                    public_withdrawal: expiry,
+                    cancellation: expiry,
+                    public_cancellation: expiry,
+                },
+                status: 0,
+                secret: None,
+                created_at,
+                parts: 0,
+                filled: 0,
+                side: EscrowSide::Src,
+                taker: None,
+                resolver: None,
+                deposit_token: None,
+                safety_deposit: 0,
+            })
+        );
+        assert_eq!(client.get_escrow(&locked).unwrap().status, 1); // locked
+
+        // The maker index fills in a page at a time
+        assert_eq!(client.get_maker_escrow_count(&maker), 0);
+        assert_eq!(client.index_makers(&1), 1);
+        assert_eq!(client.get_maker_escrow_count(&maker), 1);
+        assert_eq!(client.index_makers(&10), 0);
+        assert_eq!(client.index_makers(&10), 0);
+        assert_eq!(client.get_maker_escrow_count(&maker), 2);
+        assert_eq!(client.get_escrows_by_maker(&maker, &0, &10).len(), 2);
+        assert_eq!(client.get_stats(), (2, 1, 1, 0, 0));
+
+        // The old expiry still holds: no refund until it has passed
+        env.ledger().with_mut(|li| li.timestamp = time_lock);
+        assert_eq!(
+            client.try_refund_escrow(&pending),
+            Err(Ok(Error::TimelockNotExpired))
+        );
+        env.ledger().with_mut(|li| li.timestamp = expiry);
+        client.refund_escrow(&pending);
+        assert_eq!(token::Client::new(&env, &asset).balance(&maker), amount);
+    }
+
+    #[test]
+    fn test_migrate_reads_every_map_layout() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+        client.initialize(&Address::generate(&env));
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let asset = create_token(&env, &contract_id, 4000);
+        let hash_lock = Bytes::from_slice(&env, b"migrated_hash_lock_32_bytes_long");
+        let created_at = env.ledger().timestamp();
+        let time_lock = created_at + 3600;
+        let time_locks = time_locks(&env);
+        let ids: [Bytes; 4] = [
+            Bytes::from_slice(&env, b"v2"),
+            Bytes::from_slice(&env, b"v3"),
+            Bytes::from_slice(&env, b"v5"),
+            Bytes::from_slice(&env, b"v6"),
+        ];
+
+        // Store an escrow in each layout the map went through
+        env.as_contract(&contract_id, || {
+            let mut map: Map<Bytes, Val> = Map::new(&env);
+            let v2 = legacy::EscrowV2 {
+                id: ids[0].clone(),
+                maker: maker.clone(),
+                amount: 1000,
+                asset: asset.clone(),
+                hash_lock: hash_lock.clone(),
+                hash_algo: HashAlgo::Keccak256,
+                time_lock,
+                status: 0,
+                secret: None,
+                created_at,
+            };
+            map.set(ids[0].clone(), v2.into_val(&env));
+            let v3 = legacy::EscrowV3 {
+                id: ids[1].clone(),
+                maker: maker.clone(),
+                amount: 1000,
+                asset: asset.clone(),
+                hash_lock: hash_lock.clone(),
+                hash_algo: HashAlgo::Sha256,
+                time_lock,
+                status: 1,
+                secret: None,
+                created_at,
+                parts: 4,
+                filled: 250,
+            };
+            map.set(ids[1].clone(), v3.into_val(&env));
+            let v5 = legacy::EscrowV5 {
+                id: ids[2].clone(),
+                maker: maker.clone(),
+                amount: 1000,
+                asset: asset.clone(),
+                hash_lock: hash_lock.clone(),
+                hash_algo: HashAlgo::Sha256,
+                time_lock,
+                status: 1,
+                secret: None,
+                created_at,
+                parts: 0,
+                filled: 0,
+                side: EscrowSide::Dst,
+                taker: Some(resolver.clone()),
+                resolver: Some(resolver.clone()),
+                deposit_token: Some(asset.clone()),
+                safety_deposit: 10,
+            };
+            map.set(ids[2].clone(), v5.into_val(&env));
+            let v6 = Escrow {
+                id: ids[3].clone(),
+                maker: maker.clone(),
+                amount: 1000,
+                asset: asset.clone(),
+                hash_lock: hash_lock.clone(),
+                hash_algo: HashAlgo::Sha256,
+                time_locks: time_locks.clone(),
+                status: 0,
+                secret: None,
+                created_at,
+                parts: 0,
+                filled: 0,
+                side: EscrowSide::Src,
+                taker: None,
+                resolver: None,
+                deposit_token: None,
+                safety_deposit: 0,
+            };
+            map.set(ids[3].clone(), v6.into_val(&env));
+            env.storage().instance().set(&ESCROWS, &map);
+            env.storage().instance().set(&upgradeable::VERSION, &1u32);
+        });
+
+        assert_eq!(client.migrate(), 3);
+        let v2 = client.get_escrow(&ids[0]).unwrap();
+        assert_eq!(v2.hash_algo, HashAlgo::Keccak256);
+        assert_eq!(v2.time_locks.cancellation, time_lock + 1);
+        let v3 = client.get_escrow(&ids[1]).unwrap();
+        assert_eq!((v3.parts, v3.filled), (4, 250));
+        assert_eq!(v3.side, EscrowSide::Src);
+        let v5 = client.get_escrow(&ids[2]).unwrap();
+        assert_eq!(v5.side, EscrowSide::Dst);
+        assert_eq!(v5.resolver, Some(resolver));
+        assert_eq!(v5.safety_deposit, 10);
+        assert_eq!(v5.time_locks.cancellation, time_lock + 1);
+        assert_eq!(client.get_escrow(&ids[3]).unwrap().time_locks, time_locks);
+        assert_eq!(client.index_makers(&50), 0);
+        assert_eq!(client.get_maker_escrow_count(&maker), 4);
+        assert_eq!(client.get_stats(), (4, 2, 2, 0, 0));
+    }
+
+    #[test]
+    fn test_pause_blocks_new_escrows_only() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, StellarEthEscrow);
+        let client = StellarEthEscrowClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        client.initialize(&admin);
+
+        let maker = Address::generate(&env);
+        let resolver = Address::generate(&env);
+        let amount = 1000i128;
+        let asset = create_token(&env, &maker, 2 * amount);
+
+        let secret = Bytes::from_slice(&env, b"paused_secret_32_bytes_exactly!!");
+        let hash_lock: Bytes = env.crypto().sha256(&secret).into();
+        let time_locks = time_locks(&env);
+        let escrow_id = client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &0,
+        );
+        client.lock_escrow(&escrow_id, &resolver);
+
+        client.pause(&admin);
+        assert_eq!(
+            client.try_create_escrow(
+                &maker,
+                &None,
+                &amount,
+                &asset,
+                &hash_lock,
+                &HashAlgo::Sha256,
+                &time_locks,
+                &1
+            ),
+            Err(Ok(Error::Paused))
+        );
+
+        // Escrows already in flight can still settle
+        client.complete_escrow(&escrow_id, &secret, &resolver);
+        assert_eq!(client.get_escrow(&escrow_id).unwrap().status, 2);
+
+        client.unpause();
+        client.create_escrow(
+            &maker,
+            &None,
+            &amount,
+            &asset,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &2,
+        );
+    }
+
+    #[test]
+    fn test_scenario_opens_and_locks_escrows() {
+        let env = Env::default();
+        let scenario = testutils::EscrowScenario::new(&env)
+            .with_escrow()
+            .with_locked_escrow();
+        let escrow = &scenario.escrow;
+
+        let first = scenario.escrow_ids.get(0).unwrap();
+        assert_eq!(escrow.get_escrow(&first).unwrap().status, 0);
+        let locked = scenario.last_escrow();
+        assert_eq!(escrow.get_escrow(&locked).unwrap().status, 1);
+
+        escrow.complete_escrow(&locked, &scenario.secret(1), &scenario.resolver);
+        assert_eq!(
+            token::Client::new(&env, &scenario.token).balance(&scenario.resolver),
+            testutils::AMOUNT
+        );
+    }
+}
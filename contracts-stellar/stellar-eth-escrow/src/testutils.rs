@@ -0,0 +1,110 @@
+//! Scenario builders for tests against the escrow
+//!
+//! Deploys the escrow with a Stellar asset, a maker and a resolver, then
+//! opens escrows on top:
+//!
+//! ```ignore
+//! let scenario = EscrowScenario::new(&env).with_escrow().with_locked_escrow();
+//! let id = scenario.last_escrow();
+//! scenario.escrow.complete_escrow(&id, &scenario.secret(1), &scenario.resolver);
+//! ```
+//!
+//! All authorizations are mocked.
+
+use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Address, Bytes, Env, Vec};
+
+use crate::{HashAlgo, StellarEthEscrow, StellarEthEscrowClient, TimeLocks};
+
+/// Amount held by each escrow the scenario opens
+pub const AMOUNT: i128 = 1_000;
+/// Seconds after opening until anyone can withdraw
+pub const PUBLIC_WITHDRAWAL_DELAY: u64 = 1_800;
+/// Seconds after opening until withdrawals close and the maker can cancel
+pub const CANCELLATION_DELAY: u64 = 3_600;
+/// Seconds after opening until anyone can cancel
+pub const PUBLIC_CANCELLATION_DELAY: u64 = 7_200;
+
+/// The escrow deployed with the accounts trading through it
+pub struct EscrowScenario<'a> {
+    pub env: Env,
+    pub admin: Address,
+    pub token: Address,
+    pub maker: Address,
+    pub resolver: Address,
+    pub escrow: StellarEthEscrowClient<'a>,
+    /// Escrows opened so far, in order
+    pub escrow_ids: Vec<Bytes>,
+}
+
+impl EscrowScenario<'_> {
+    pub fn new(env: &Env) -> Self {
+        env.mock_all_auths();
+
+        let admin = Address::generate(env);
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let escrow =
+            StellarEthEscrowClient::new(env, &env.register_contract(None, StellarEthEscrow));
+        escrow.initialize(&admin);
+
+        EscrowScenario {
+            env: env.clone(),
+            admin,
+            token,
+            maker: Address::generate(env),
+            resolver: Address::generate(env),
+            escrow,
+            escrow_ids: Vec::new(env),
+        }
+    }
+
+    /// Open an escrow of [`AMOUNT`] from the maker, minted to them first,
+    /// hash locked on the next [`secret`](Self::secret)
+    pub fn with_escrow(mut self) -> Self {
+        let secret = self.secret(self.escrow_ids.len());
+        let hash_lock: Bytes = self.env.crypto().sha256(&secret).into();
+        let now = self.env.ledger().timestamp();
+        let time_locks = TimeLocks {
+            withdrawal: now,
+            public_withdrawal: now + PUBLIC_WITHDRAWAL_DELAY,
+            cancellation: now + CANCELLATION_DELAY,
+            public_cancellation: now + PUBLIC_CANCELLATION_DELAY,
+        };
+
+        StellarAssetClient::new(&self.env, &self.token).mint(&self.maker, &AMOUNT);
+        let id = self.escrow.create_escrow(
+            &self.maker,
+            &None,
+            &AMOUNT,
+            &self.token,
+            &hash_lock,
+            &HashAlgo::Sha256,
+            &time_locks,
+            &(self.escrow_ids.len() as u64),
+        );
+        self.escrow_ids.push_back(id);
+        self
+    }
+
+    /// Open an escrow and lock it to the resolver
+    pub fn with_locked_escrow(self) -> Self {
+        let scenario = self.with_escrow();
+        scenario
+            .escrow
+            .lock_escrow(&scenario.last_escrow(), &scenario.resolver);
+        scenario
+    }
+
+    /// Secret of the escrow opened `index`th
+    pub fn secret(&self, index: u32) -> Bytes {
+        let mut secret = [0u8; 32];
+        secret[..4].copy_from_slice(&index.to_be_bytes());
+        Bytes::from_array(&self.env, &secret)
+    }
+
+    /// Id of the escrow opened last
+    pub fn last_escrow(&self) -> Bytes {
+        self.escrow_ids.last().expect("No escrow opened")
+    }
+}
@@ -0,0 +1,315 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+};
+use stellhydra_access::ownable;
+
+/// Deposit released linearly to the recipient between two times
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stream {
+    pub id: u64,
+    pub sender: Address,
+    pub recipient: Address,
+    pub token: Address,
+    pub deposit: i128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub withdrawn: i128,
+    pub cancelled: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    StreamNotFound = 1,
+    InvalidAmount = 2,
+    InvalidSchedule = 3,
+    StreamCancelled = 4,
+    NotParty = 5,
+}
+
+// Storage keys
+const COUNT: Symbol = symbol_short!("COUNT");
+const STREAM: Symbol = symbol_short!("STREAM");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Payment streams, for paying relayers, oracle operators and
+/// contributors continuously rather than in lump sums
+///
+/// The sender deposits the full amount up front; the recipient withdraws
+/// what has accrued at any time. Either party can cancel, which pays the
+/// recipient what has accrued and returns the rest to the sender.
+#[contract]
+pub struct Streaming;
+
+#[contractimpl]
+impl Streaming {
+    /// Set the admin allowed to upgrade the contract
+    ///
+    /// Streams can be opened before this is called; until then the
+    /// contract simply can't be upgraded.
+    pub fn initialize(env: Env, admin: Address) {
+        admin.require_auth();
+        ownable::init_admin(&env, &admin);
+    }
+
+    /// Open a stream funded with `deposit` from the sender
+    pub fn create_stream(
+        env: Env,
+        sender: Address,
+        recipient: Address,
+        token: Address,
+        deposit: i128,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<u64, Error> {
+        sender.require_auth();
+        if deposit <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if end_time <= start_time {
+            return Err(Error::InvalidSchedule);
+        }
+
+        token::Client::new(&env, &token).transfer(
+            &sender,
+            &env.current_contract_address(),
+            &deposit,
+        );
+
+        let id: u64 = env.storage().instance().get(&COUNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&COUNT, &id);
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        Self::save_stream(
+            &env,
+            &Stream {
+                id,
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                token,
+                deposit,
+                start_time,
+                end_time,
+                withdrawn: 0,
+                cancelled: false,
+            },
+        );
+
+        env.events().publish(
+            (symbol_short!("STREAM"), symbol_short!("CREATED"), id),
+            (sender, recipient, deposit, start_time, end_time),
+        );
+        Ok(id)
+    }
+
+    /// Withdraw everything accrued so far to the recipient
+    pub fn withdraw(env: Env, id: u64) -> Result<i128, Error> {
+        let mut stream = Self::get_stream(env.clone(), id)?;
+        stream.recipient.require_auth();
+        if stream.cancelled {
+            return Err(Error::StreamCancelled);
+        }
+
+        let amount = Self::streamed(&env, &stream) - stream.withdrawn;
+        if amount > 0 {
+            stream.withdrawn += amount;
+            Self::save_stream(&env, &stream);
+            token::Client::new(&env, &stream.token).transfer(
+                &env.current_contract_address(),
+                &stream.recipient,
+                &amount,
+            );
+        }
+
+        env.events().publish(
+            (symbol_short!("STREAM"), symbol_short!("WITHDRAWN"), id),
+            amount,
+        );
+        Ok(amount)
+    }
+
+    /// Stop a stream, splitting what is left pro rata (only its sender or
+    /// recipient)
+    ///
+    /// Returns the amounts paid to the recipient and refunded to the
+    /// sender.
+    pub fn cancel(env: Env, caller: Address, id: u64) -> Result<(i128, i128), Error> {
+        caller.require_auth();
+        let mut stream = Self::get_stream(env.clone(), id)?;
+        if caller != stream.sender && caller != stream.recipient {
+            return Err(Error::NotParty);
+        }
+        if stream.cancelled {
+            return Err(Error::StreamCancelled);
+        }
+
+        let streamed = Self::streamed(&env, &stream);
+        let to_recipient = streamed - stream.withdrawn;
+        let to_sender = stream.deposit - streamed;
+        stream.withdrawn = streamed;
+        stream.cancelled = true;
+        Self::save_stream(&env, &stream);
+
+        let token = token::Client::new(&env, &stream.token);
+        if to_recipient > 0 {
+            token.transfer(
+                &env.current_contract_address(),
+                &stream.recipient,
+                &to_recipient,
+            );
+        }
+        if to_sender > 0 {
+            token.transfer(&env.current_contract_address(), &stream.sender, &to_sender);
+        }
+
+        env.events().publish(
+            (symbol_short!("STREAM"), symbol_short!("CANCELLED"), id),
+            (caller, to_recipient, to_sender),
+        );
+        Ok((to_recipient, to_sender))
+    }
+
+    /// Amount the recipient could withdraw now
+    pub fn withdrawable(env: Env, id: u64) -> Result<i128, Error> {
+        let stream = Self::get_stream(env.clone(), id)?;
+        if stream.cancelled {
+            return Ok(0);
+        }
+        Ok(Self::streamed(&env, &stream) - stream.withdrawn)
+    }
+
+    pub fn get_stream(env: Env, id: u64) -> Result<Stream, Error> {
+        env.storage()
+            .persistent()
+            .get(&(STREAM, id))
+            .ok_or(Error::StreamNotFound)
+    }
+
+    pub fn get_stream_count(env: Env) -> u64 {
+        env.storage().instance().get(&COUNT).unwrap_or(0)
+    }
+}
+
+stellhydra_access::ownable!(Streaming);
+
+impl stellhydra_access::Upgradeable for Streaming {}
+
+stellhydra_access::upgradeable!(Streaming);
+
+impl Streaming {
+    /// Part of the deposit released by the current time
+    fn streamed(env: &Env, stream: &Stream) -> i128 {
+        let now = env.ledger().timestamp();
+        if now <= stream.start_time {
+            return 0;
+        }
+        if now >= stream.end_time {
+            return stream.deposit;
+        }
+
+        let elapsed = (now - stream.start_time) as i128;
+        let duration = (stream.end_time - stream.start_time) as i128;
+        stream.deposit * elapsed / duration
+    }
+
+    fn save_stream(env: &Env, stream: &Stream) {
+        let key = (STREAM, stream.id);
+        env.storage().persistent().set(&key, stream);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::StellarAssetClient,
+        Env,
+    };
+
+    struct Setup<'a> {
+        streaming: StreamingClient<'a>,
+        token: token::Client<'a>,
+        sender: Address,
+        recipient: Address,
+        id: u64,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let sender = Address::generate(env);
+        let recipient = Address::generate(env);
+        StellarAssetClient::new(env, &token).mint(&sender, &10_000);
+
+        let streaming = StreamingClient::new(env, &env.register_contract(None, Streaming));
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        let id = streaming.create_stream(&sender, &recipient, &token, &10_000, &1_000, &2_000);
+
+        Setup {
+            streaming,
+            token: token::Client::new(env, &token),
+            sender,
+            recipient,
+            id,
+        }
+    }
+
+    #[test]
+    fn test_recipient_withdraws_as_it_streams() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_250);
+        assert_eq!(s.streaming.withdrawable(&s.id), 2_500);
+        assert_eq!(s.streaming.withdraw(&s.id), 2_500);
+        assert_eq!(s.streaming.withdraw(&s.id), 0);
+
+        env.ledger().with_mut(|li| li.timestamp = 5_000);
+        assert_eq!(s.streaming.withdraw(&s.id), 7_500);
+        assert_eq!(s.token.balance(&s.recipient), 10_000);
+    }
+
+    #[test]
+    fn test_cancel_splits_pro_rata() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_400);
+        s.streaming.withdraw(&s.id);
+        env.ledger().with_mut(|li| li.timestamp = 1_600);
+        assert_eq!(
+            s.streaming.try_cancel(&Address::generate(&env), &s.id),
+            Err(Ok(Error::NotParty))
+        );
+
+        assert_eq!(s.streaming.cancel(&s.sender, &s.id), (2_000, 4_000));
+        assert_eq!(s.token.balance(&s.recipient), 6_000);
+        assert_eq!(s.token.balance(&s.sender), 4_000);
+        assert_eq!(
+            s.streaming.try_withdraw(&s.id),
+            Err(Ok(Error::StreamCancelled))
+        );
+        assert_eq!(s.streaming.withdrawable(&s.id), 0);
+    }
+}
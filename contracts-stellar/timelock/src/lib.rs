@@ -0,0 +1,555 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, xdr::ToXdr, Address, BytesN,
+    Env, Symbol, TryFromVal, Val, Vec,
+};
+use stellhydra_access::upgradeable;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Proposer, // schedules and cancels operations
+    Executor, // executes ready operations
+}
+
+/// Call waiting for its delay to pass
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Operation {
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    pub proposer: Address,
+    pub scheduled_at: u64,
+    pub ready_at: u64,
+    pub executed: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    MissingRole = 3,
+    NotAdmin = 4,
+    DelayTooShort = 5,
+    OperationExists = 6,
+    OperationNotFound = 7,
+    OperationNotReady = 8,
+    OperationDone = 9,
+    UnsupportedCall = 10,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const MIN_DELAY: Symbol = symbol_short!("MIN_DELAY");
+const ROLE: Symbol = symbol_short!("ROLE");
+const OPERATION: Symbol = symbol_short!("OPERATION");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contract]
+pub struct Timelock;
+
+#[contractimpl]
+impl Timelock {
+    /// Initialize the timelock
+    ///
+    /// The optional admin can manage roles while the setup is wired up and
+    /// should renounce afterwards; from then on roles and the minimum delay
+    /// only change through operations targeting the timelock itself.
+    pub fn initialize(
+        env: Env,
+        min_delay: u64,
+        proposers: Vec<Address>,
+        executors: Vec<Address>,
+        admin: Option<Address>,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&MIN_DELAY) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&MIN_DELAY, &min_delay);
+        if let Some(admin) = admin {
+            env.storage().instance().set(&ADMIN, &admin);
+        }
+        for proposer in proposers.iter() {
+            Self::set_role(&env, Role::Proposer, &proposer, true);
+        }
+        for executor in executors.iter() {
+            Self::set_role(&env, Role::Executor, &executor, true);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_min_delay(env: Env) -> Result<u64, Error> {
+        env.storage()
+            .instance()
+            .get(&MIN_DELAY)
+            .ok_or(Error::NotInitialized)
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ADMIN)
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&(ROLE, role, account))
+            .unwrap_or(false)
+    }
+
+    /// Grant a role (only admin)
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::set_role(&env, role, &account, true);
+        Ok(())
+    }
+
+    /// Revoke a role (only admin)
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::set_role(&env, role, &account, false);
+        Ok(())
+    }
+
+    /// Give up the admin for good (only admin)
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        env.storage().instance().remove(&ADMIN);
+
+        env.events().publish(
+            (symbol_short!("TIMELOCK"), symbol_short!("RENOUNCE")),
+            admin,
+        );
+        Ok(())
+    }
+
+    /// Id of an operation: the hash of its call and salt
+    pub fn hash_operation(
+        env: Env,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+        salt: BytesN<32>,
+    ) -> BytesN<32> {
+        env.crypto()
+            .sha256(&(target, function, args, salt).to_xdr(&env))
+            .into()
+    }
+
+    /// Schedule a call to run once `delay` has passed (only proposers)
+    ///
+    /// The salt lets the same call be scheduled more than once.
+    pub fn schedule(
+        env: Env,
+        proposer: Address,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+        salt: BytesN<32>,
+        delay: u64,
+    ) -> Result<BytesN<32>, Error> {
+        proposer.require_auth();
+        Self::check_role(&env, Role::Proposer, &proposer)?;
+        if delay < Self::get_min_delay(env.clone())? {
+            return Err(Error::DelayTooShort);
+        }
+
+        let id = Self::hash_operation(
+            env.clone(),
+            target.clone(),
+            function.clone(),
+            args.clone(),
+            salt,
+        );
+        if env.storage().persistent().has(&(OPERATION, id.clone())) {
+            return Err(Error::OperationExists);
+        }
+
+        let now = env.ledger().timestamp();
+        let operation = Operation {
+            target,
+            function,
+            args,
+            proposer,
+            scheduled_at: now,
+            ready_at: now + delay,
+            executed: false,
+        };
+        Self::save_operation(&env, &id, &operation);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (
+                symbol_short!("TIMELOCK"),
+                symbol_short!("SCHEDULED"),
+                id.clone(),
+            ),
+            (
+                operation.target,
+                operation.function,
+                operation.args,
+                operation.ready_at,
+            ),
+        );
+        Ok(id)
+    }
+
+    /// Run a scheduled call whose delay has passed (only executors)
+    ///
+    /// The target sees the timelock as the invoker, so contracts whose
+    /// admin is the timelock accept it.
+    pub fn execute(env: Env, executor: Address, id: BytesN<32>) -> Result<Val, Error> {
+        executor.require_auth();
+        Self::check_role(&env, Role::Executor, &executor)?;
+
+        let mut operation = Self::get_operation(env.clone(), id.clone())?;
+        if operation.executed {
+            return Err(Error::OperationDone);
+        }
+        if env.ledger().timestamp() < operation.ready_at {
+            return Err(Error::OperationNotReady);
+        }
+
+        operation.executed = true;
+        Self::save_operation(&env, &id, &operation);
+
+        // A contract can't call itself, so its own settings are applied
+        // directly
+        let result = if operation.target == env.current_contract_address() {
+            Self::apply_own_call(&env, &operation.function, &operation.args)?;
+            Val::VOID.into()
+        } else {
+            env.invoke_contract::<Val>(&operation.target, &operation.function, operation.args)
+        };
+
+        env.events().publish(
+            (symbol_short!("TIMELOCK"), symbol_short!("EXECUTED"), id),
+            executor,
+        );
+        Ok(result)
+    }
+
+    /// Drop a scheduled call before it runs (only proposers)
+    pub fn cancel(env: Env, proposer: Address, id: BytesN<32>) -> Result<(), Error> {
+        proposer.require_auth();
+        Self::check_role(&env, Role::Proposer, &proposer)?;
+
+        let operation = Self::get_operation(env.clone(), id.clone())?;
+        if operation.executed {
+            return Err(Error::OperationDone);
+        }
+        env.storage().persistent().remove(&(OPERATION, id.clone()));
+
+        env.events().publish(
+            (symbol_short!("TIMELOCK"), symbol_short!("CANCELLED"), id),
+            proposer,
+        );
+        Ok(())
+    }
+
+    pub fn get_operation(env: Env, id: BytesN<32>) -> Result<Operation, Error> {
+        env.storage()
+            .persistent()
+            .get(&(OPERATION, id))
+            .ok_or(Error::OperationNotFound)
+    }
+
+    /// Whether an operation is scheduled, not yet executed, and past its
+    /// delay
+    pub fn is_operation_ready(env: Env, id: BytesN<32>) -> bool {
+        match Self::get_operation(env.clone(), id) {
+            Ok(operation) => !operation.executed && env.ledger().timestamp() >= operation.ready_at,
+            Err(_) => false,
+        }
+    }
+}
+
+impl stellhydra_access::Upgradeable for Timelock {
+    /// Upgrades are scheduled like any other operation targeting the
+    /// timelock itself
+    fn require_upgrade_authority(env: &Env) {
+        env.current_contract_address().require_auth();
+    }
+}
+
+stellhydra_access::upgradeable!(Timelock);
+
+impl Timelock {
+    fn require_admin(env: &Env) -> Result<Address, Error> {
+        let admin = Self::get_admin(env.clone()).ok_or(Error::NotAdmin)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    fn check_role(env: &Env, role: Role, account: &Address) -> Result<(), Error> {
+        if !Self::has_role(env.clone(), role, account.clone()) {
+            return Err(Error::MissingRole);
+        }
+        Ok(())
+    }
+
+    fn set_role(env: &Env, role: Role, account: &Address, granted: bool) {
+        let key = (ROLE, role, account.clone());
+        if granted {
+            env.storage().persistent().set(&key, &true);
+            env.storage().persistent().extend_ttl(
+                &key,
+                PERSISTENT_LIFETIME_THRESHOLD,
+                PERSISTENT_BUMP_AMOUNT,
+            );
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        let event = if granted {
+            symbol_short!("GRANTED")
+        } else {
+            symbol_short!("REVOKED")
+        };
+        env.events()
+            .publish((symbol_short!("TIMELOCK"), event, role), account.clone());
+    }
+
+    /// Apply an operation targeting the timelock itself
+    fn apply_own_call(env: &Env, function: &Symbol, args: &Vec<Val>) -> Result<(), Error> {
+        if upgradeable::apply_own_call::<Self>(env, function, args) {
+            return Ok(());
+        }
+
+        if *function == Symbol::new(env, "update_delay") {
+            let delay = args
+                .get(0)
+                .and_then(|arg| u64::try_from_val(env, &arg).ok())
+                .ok_or(Error::UnsupportedCall)?;
+            env.storage().instance().set(&MIN_DELAY, &delay);
+            return Ok(());
+        }
+
+        let granted = if *function == Symbol::new(env, "grant_role") {
+            true
+        } else if *function == Symbol::new(env, "revoke_role") {
+            false
+        } else {
+            return Err(Error::UnsupportedCall);
+        };
+        let role = args
+            .get(0)
+            .and_then(|arg| Role::try_from_val(env, &arg).ok())
+            .ok_or(Error::UnsupportedCall)?;
+        let account = args
+            .get(1)
+            .and_then(|arg| Address::try_from_val(env, &arg).ok())
+            .ok_or(Error::UnsupportedCall)?;
+        Self::set_role(env, role, &account, granted);
+        Ok(())
+    }
+
+    fn save_operation(env: &Env, id: &BytesN<32>, operation: &Operation) {
+        let key = (OPERATION, id.clone());
+        env.storage().persistent().set(&key, operation);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        vec, Env, IntoVal,
+    };
+
+    /// Contract administered by the timelock
+    #[contract]
+    struct MockTarget;
+
+    #[contractimpl]
+    impl MockTarget {
+        pub fn init(env: Env, admin: Address) {
+            env.storage().instance().set(&ADMIN, &admin);
+        }
+
+        pub fn set_value(env: Env, value: u32) -> u32 {
+            let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+            admin.require_auth();
+            env.storage().instance().set(&ROLE, &value);
+            value
+        }
+
+        pub fn value(env: Env) -> u32 {
+            env.storage().instance().get(&ROLE).unwrap_or(0)
+        }
+    }
+
+    fn setup(env: &Env) -> (TimelockClient<'_>, Address, Address) {
+        env.mock_all_auths();
+
+        let proposer = Address::generate(env);
+        let executor = Address::generate(env);
+        let timelock_id = env.register_contract(None, Timelock);
+        let timelock = TimelockClient::new(env, &timelock_id);
+        timelock.initialize(
+            &3_600,
+            &vec![env, proposer.clone()],
+            &vec![env, executor.clone()],
+            &None,
+        );
+
+        (timelock, proposer, executor)
+    }
+
+    #[test]
+    fn test_scheduled_call_runs_after_delay() {
+        let env = Env::default();
+        let (timelock, proposer, executor) = setup(&env);
+
+        let target_id = env.register_contract(None, MockTarget);
+        let target = MockTargetClient::new(&env, &target_id);
+        target.init(&timelock.address);
+
+        let function = Symbol::new(&env, "set_value");
+        let args = vec![&env, 7u32.into_val(&env)];
+        let salt = BytesN::from_array(&env, &[0; 32]);
+        assert_eq!(
+            timelock.try_schedule(&proposer, &target_id, &function, &args, &salt, &60),
+            Err(Ok(Error::DelayTooShort))
+        );
+        assert_eq!(
+            timelock.try_schedule(&executor, &target_id, &function, &args, &salt, &3_600),
+            Err(Ok(Error::MissingRole))
+        );
+
+        let id = timelock.schedule(&proposer, &target_id, &function, &args, &salt, &3_600);
+        assert_eq!(
+            id,
+            timelock.hash_operation(&target_id, &function, &args, &salt)
+        );
+        assert_eq!(
+            timelock.try_schedule(&proposer, &target_id, &function, &args, &salt, &3_600),
+            Err(Ok(Error::OperationExists))
+        );
+        assert!(!timelock.is_operation_ready(&id));
+        assert_eq!(
+            timelock.try_execute(&executor, &id).err(),
+            Some(Ok(Error::OperationNotReady))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 3_600);
+        assert!(timelock.is_operation_ready(&id));
+        assert_eq!(
+            timelock.try_execute(&proposer, &id).err(),
+            Some(Ok(Error::MissingRole))
+        );
+        let result = timelock.execute(&executor, &id);
+        assert_eq!(u32::try_from_val(&env, &result).unwrap(), 7);
+        assert_eq!(target.value(), 7);
+        assert_eq!(
+            timelock.try_execute(&executor, &id).err(),
+            Some(Ok(Error::OperationDone))
+        );
+    }
+
+    #[test]
+    fn test_cancelled_call_never_runs() {
+        let env = Env::default();
+        let (timelock, proposer, executor) = setup(&env);
+
+        let target = Address::generate(&env);
+        let function = Symbol::new(&env, "set_value");
+        let salt = BytesN::from_array(&env, &[1; 32]);
+        let id = timelock.schedule(&proposer, &target, &function, &vec![&env], &salt, &3_600);
+
+        timelock.cancel(&proposer, &id);
+        assert_eq!(
+            timelock.try_get_operation(&id),
+            Err(Ok(Error::OperationNotFound))
+        );
+        env.ledger().with_mut(|li| li.timestamp += 3_600);
+        assert_eq!(
+            timelock.try_execute(&executor, &id).err(),
+            Some(Ok(Error::OperationNotFound))
+        );
+    }
+
+    #[test]
+    fn test_roles_and_delay_change_through_the_timelock() {
+        let env = Env::default();
+        let (timelock, proposer, executor) = setup(&env);
+
+        let newcomer = Address::generate(&env);
+        assert_eq!(
+            timelock.try_grant_role(&Role::Proposer, &newcomer),
+            Err(Ok(Error::NotAdmin))
+        );
+
+        let own = timelock.address.clone();
+        let salt = BytesN::from_array(&env, &[2; 32]);
+        let grant = timelock.schedule(
+            &proposer,
+            &own,
+            &Symbol::new(&env, "grant_role"),
+            &vec![&env, Role::Proposer.into_val(&env), newcomer.into_val(&env)],
+            &salt,
+            &3_600,
+        );
+        let delay = timelock.schedule(
+            &proposer,
+            &own,
+            &Symbol::new(&env, "update_delay"),
+            &vec![&env, 7_200u64.into_val(&env)],
+            &salt,
+            &3_600,
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 3_600);
+        timelock.execute(&executor, &grant);
+        timelock.execute(&executor, &delay);
+        assert!(timelock.has_role(&Role::Proposer, &newcomer));
+        assert_eq!(timelock.get_min_delay(), 7_200);
+    }
+
+    #[test]
+    fn test_setup_admin_manages_roles_until_renounced() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let account = Address::generate(&env);
+        let timelock_id = env.register_contract(None, Timelock);
+        let timelock = TimelockClient::new(&env, &timelock_id);
+        timelock.initialize(&60, &vec![&env], &vec![&env], &Some(admin.clone()));
+
+        timelock.grant_role(&Role::Executor, &account);
+        assert!(timelock.has_role(&Role::Executor, &account));
+        timelock.revoke_role(&Role::Executor, &account);
+        assert!(!timelock.has_role(&Role::Executor, &account));
+
+        timelock.renounce_admin();
+        assert_eq!(timelock.get_admin(), None);
+        assert_eq!(
+            timelock.try_grant_role(&Role::Executor, &account),
+            Err(Ok(Error::NotAdmin))
+        );
+    }
+}
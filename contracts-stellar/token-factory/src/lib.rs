@@ -0,0 +1,443 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, xdr::ToXdr,
+    Address, Bytes, BytesN, Env, String, Symbol, Vec,
+};
+
+/// Initializer of the wrapped-token contract the factory deploys
+#[contractclient(name = "WrappedTokenClient")]
+pub trait WrappedTokenInterface {
+    fn initialize(env: Env, bridge: Address, name: String, symbol: String, decimals: u32);
+}
+
+/// Asset on its home chain
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OriginAsset {
+    pub chain: Symbol, // chain identifier, as used by the bridge
+    pub asset: Bytes,  // token address on that chain
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    AlreadyRegistered = 3,
+    NotRegistered = 4,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const BRIDGE: Symbol = symbol_short!("BRIDGE");
+const WASM_HASH: Symbol = symbol_short!("WASM_HASH");
+const WRAPPED: Symbol = symbol_short!("WRAPPED");
+const ORIGIN: Symbol = symbol_short!("ORIGIN");
+const ASSETS: Symbol = symbol_short!("ASSETS"); // before storage version 2
+const ASSET_COUNT: Symbol = symbol_short!("ASSET_CNT");
+const ASSET: Symbol = symbol_short!("ASSET");
+const ASSET_INDEX: Symbol = symbol_short!("ASSET_IDX");
+
+/// Most origin assets returned by one `get_origin_assets` call
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Deploys one wrapped token per origin asset and keeps the registry
+/// between them
+///
+/// The deploy salt is derived from the origin asset, so every wrapped
+/// token's address can be known before it exists.
+#[contract]
+pub struct TokenFactory;
+
+#[contractimpl]
+impl TokenFactory {
+    /// Initialize the factory with the bridge allowed to mint, and the
+    /// uploaded wrapped-token wasm
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        bridge: Address,
+        wasm_hash: BytesN<32>,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().set(&BRIDGE, &bridge);
+        env.storage().instance().set(&WASM_HASH, &wasm_hash);
+        env.storage().instance().set(
+            &stellhydra_access::upgradeable::VERSION,
+            &<Self as stellhydra_access::Upgradeable>::VERSION,
+        );
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Use another wrapped-token wasm for future deployments (only admin)
+    pub fn set_wasm_hash(env: Env, wasm_hash: BytesN<32>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&WASM_HASH, &wasm_hash);
+        Ok(())
+    }
+
+    pub fn get_wasm_hash(env: Env) -> Result<BytesN<32>, Error> {
+        env.storage()
+            .instance()
+            .get(&WASM_HASH)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Deploy the wrapped token of an origin asset (only admin)
+    pub fn deploy_wrapped(
+        env: Env,
+        origin: OriginAsset,
+        name: String,
+        symbol: String,
+        decimals: u32,
+    ) -> Result<Address, Error> {
+        Self::require_admin(&env)?;
+        if Self::get_wrapped(env.clone(), origin.clone()).is_some() {
+            return Err(Error::AlreadyRegistered);
+        }
+
+        let wasm_hash = Self::get_wasm_hash(env.clone())?;
+        let bridge: Address = env.storage().instance().get(&BRIDGE).unwrap();
+        let wrapped = env
+            .deployer()
+            .with_current_contract(Self::salt(&env, &origin))
+            .deploy(wasm_hash);
+        WrappedTokenClient::new(&env, &wrapped).initialize(&bridge, &name, &symbol, &decimals);
+
+        Self::register(&env, &origin, &wrapped);
+        Ok(wrapped)
+    }
+
+    /// Record a wrapped token deployed outside the factory (only admin)
+    pub fn register_wrapped(env: Env, origin: OriginAsset, wrapped: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        if Self::get_wrapped(env.clone(), origin.clone()).is_some()
+            || Self::get_origin(env.clone(), wrapped.clone()).is_some()
+        {
+            return Err(Error::AlreadyRegistered);
+        }
+
+        Self::register(&env, &origin, &wrapped);
+        Ok(())
+    }
+
+    /// Drop an origin asset from the registry (only admin)
+    ///
+    /// The token contract itself keeps working; only the mapping goes.
+    pub fn unregister_wrapped(env: Env, origin: OriginAsset) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let wrapped = Self::get_wrapped(env.clone(), origin.clone()).ok_or(Error::NotRegistered)?;
+
+        env.storage()
+            .persistent()
+            .remove(&(WRAPPED, origin.clone()));
+        env.storage()
+            .persistent()
+            .remove(&(ORIGIN, wrapped.clone()));
+
+        Self::unlist(&env, &origin);
+
+        env.events().publish(
+            (
+                symbol_short!("FACTORY"),
+                symbol_short!("UNREG"),
+                origin.chain,
+            ),
+            (origin.asset, wrapped),
+        );
+        Ok(())
+    }
+
+    /// Wrapped token of an origin asset
+    pub fn get_wrapped(env: Env, origin: OriginAsset) -> Option<Address> {
+        env.storage().persistent().get(&(WRAPPED, origin))
+    }
+
+    /// Origin asset of a wrapped token
+    pub fn get_origin(env: Env, wrapped: Address) -> Option<OriginAsset> {
+        env.storage().persistent().get(&(ORIGIN, wrapped))
+    }
+
+    /// Registered origin assets from index `start`, at most
+    /// `MAX_PAGE_SIZE` of them
+    ///
+    /// Unregistering moves the last asset into the freed index.
+    pub fn get_origin_assets(env: Env, start: u32, limit: u32) -> Vec<OriginAsset> {
+        let end = start
+            .saturating_add(limit.min(MAX_PAGE_SIZE))
+            .min(Self::get_origin_asset_count(env.clone()));
+
+        let mut page = Vec::new(&env);
+        for index in start..end {
+            if let Some(origin) = env.storage().persistent().get(&(ASSET, index)) {
+                page.push_back(origin);
+            }
+        }
+        page
+    }
+
+    /// Number of registered origin assets
+    pub fn get_origin_asset_count(env: Env) -> u32 {
+        env.storage().instance().get(&ASSET_COUNT).unwrap_or(0)
+    }
+
+    /// Address the wrapped token of an origin asset is deployed at
+    pub fn predict_address(env: Env, origin: OriginAsset) -> Address {
+        env.deployer()
+            .with_current_contract(Self::salt(&env, &origin))
+            .deployed_address()
+    }
+}
+
+impl stellhydra_access::Upgradeable for TokenFactory {
+    const VERSION: u32 = 2;
+
+    fn migrate_step(env: &Env, from: u32) {
+        if from == 1 {
+            // Move the origin assets out of the instance list into their
+            // own entries
+            let assets: Vec<OriginAsset> = env
+                .storage()
+                .instance()
+                .get(&ASSETS)
+                .unwrap_or_else(|| Vec::new(env));
+            for origin in assets.iter() {
+                Self::list(env, &origin);
+            }
+            env.storage().instance().remove(&ASSETS);
+        }
+    }
+}
+
+stellhydra_access::upgradeable!(TokenFactory);
+
+impl TokenFactory {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn salt(env: &Env, origin: &OriginAsset) -> BytesN<32> {
+        env.crypto().sha256(&origin.clone().to_xdr(env)).into()
+    }
+
+    fn register(env: &Env, origin: &OriginAsset, wrapped: &Address) {
+        let wrapped_key = (WRAPPED, origin.clone());
+        env.storage().persistent().set(&wrapped_key, wrapped);
+        env.storage().persistent().extend_ttl(
+            &wrapped_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        let origin_key = (ORIGIN, wrapped.clone());
+        env.storage().persistent().set(&origin_key, origin);
+        env.storage().persistent().extend_ttl(
+            &origin_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        Self::list(env, origin);
+        Self::extend_instance(env);
+
+        env.events().publish(
+            (
+                symbol_short!("FACTORY"),
+                symbol_short!("WRAPPED"),
+                origin.chain.clone(),
+            ),
+            (origin.asset.clone(), wrapped.clone()),
+        );
+    }
+
+    /// Append an origin asset to the indexed list
+    fn list(env: &Env, origin: &OriginAsset) {
+        let index = Self::get_origin_asset_count(env.clone());
+        Self::set_asset(env, index, origin);
+        env.storage().instance().set(&ASSET_COUNT, &(index + 1));
+    }
+
+    /// Remove an origin asset from the indexed list, moving the last one
+    /// into its index
+    fn unlist(env: &Env, origin: &OriginAsset) {
+        let index_key = (ASSET_INDEX, origin.clone());
+        let Some(index) = env.storage().persistent().get::<_, u32>(&index_key) else {
+            return;
+        };
+        let last = Self::get_origin_asset_count(env.clone()) - 1;
+        if index != last {
+            if let Some(moved) = env.storage().persistent().get(&(ASSET, last)) {
+                Self::set_asset(env, index, &moved);
+            }
+        }
+        env.storage().persistent().remove(&(ASSET, last));
+        env.storage().persistent().remove(&index_key);
+        env.storage().instance().set(&ASSET_COUNT, &last);
+    }
+
+    fn set_asset(env: &Env, index: u32, origin: &OriginAsset) {
+        let asset_key = (ASSET, index);
+        env.storage().persistent().set(&asset_key, origin);
+        env.storage().persistent().extend_ttl(
+            &asset_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+        let index_key = (ASSET_INDEX, origin.clone());
+        env.storage().persistent().set(&index_key, &index);
+        env.storage().persistent().extend_ttl(
+            &index_key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    fn setup(env: &Env) -> TokenFactoryClient<'_> {
+        env.mock_all_auths();
+
+        let factory_id = env.register_contract(None, TokenFactory);
+        let factory = TokenFactoryClient::new(env, &factory_id);
+        factory.initialize(
+            &Address::generate(env),
+            &Address::generate(env),
+            &BytesN::from_array(env, &[0; 32]),
+        );
+        factory
+    }
+
+    fn origin(env: &Env, chain: &str, asset: u8) -> OriginAsset {
+        OriginAsset {
+            chain: Symbol::new(env, chain),
+            asset: Bytes::from_array(env, &[asset; 20]),
+        }
+    }
+
+    #[test]
+    fn test_addresses_are_deterministic_per_origin() {
+        let env = Env::default();
+        let factory = setup(&env);
+
+        let usdc = origin(&env, "ethereum", 1);
+        assert_eq!(
+            factory.predict_address(&usdc),
+            factory.predict_address(&usdc)
+        );
+        assert_ne!(
+            factory.predict_address(&usdc),
+            factory.predict_address(&origin(&env, "ethereum", 2))
+        );
+        assert_ne!(
+            factory.predict_address(&usdc),
+            factory.predict_address(&origin(&env, "polygon", 1))
+        );
+    }
+
+    #[test]
+    fn test_registry_maps_both_ways() {
+        let env = Env::default();
+        let factory = setup(&env);
+
+        let usdc = origin(&env, "ethereum", 1);
+        let wrapped = Address::generate(&env);
+        factory.register_wrapped(&usdc, &wrapped);
+        assert_eq!(factory.get_wrapped(&usdc), Some(wrapped.clone()));
+        assert_eq!(factory.get_origin(&wrapped), Some(usdc.clone()));
+        assert_eq!(
+            factory.try_register_wrapped(&usdc, &Address::generate(&env)),
+            Err(Ok(Error::AlreadyRegistered))
+        );
+        assert_eq!(
+            factory.try_deploy_wrapped(
+                &usdc,
+                &String::from_str(&env, "Wrapped USDC"),
+                &String::from_str(&env, "hUSDC"),
+                &6
+            ),
+            Err(Ok(Error::AlreadyRegistered))
+        );
+
+        factory.unregister_wrapped(&usdc);
+        assert_eq!(factory.get_wrapped(&usdc), None);
+        assert_eq!(factory.get_origin(&wrapped), None);
+        assert_eq!(factory.get_origin_asset_count(), 0);
+        assert_eq!(factory.get_origin_assets(&0, &10).len(), 0);
+    }
+
+    #[test]
+    fn test_unregistering_keeps_the_list_dense() {
+        let env = Env::default();
+        let factory = setup(&env);
+
+        let origins = [1, 2, 3].map(|asset| origin(&env, "ethereum", asset));
+        for origin in origins.iter() {
+            factory.register_wrapped(origin, &Address::generate(&env));
+        }
+        assert_eq!(factory.get_origin_assets(&1, &10).len(), 2);
+
+        factory.unregister_wrapped(&origins[0]);
+        assert_eq!(factory.get_origin_asset_count(), 2);
+        let assets = factory.get_origin_assets(&0, &10);
+        assert_eq!(assets.get(0), Some(origins[2].clone()));
+        assert_eq!(assets.get(1), Some(origins[1].clone()));
+
+        factory.unregister_wrapped(&origins[1]);
+        assert_eq!(factory.get_origin_assets(&0, &10).len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_moves_assets_out_of_the_instance() {
+        let env = Env::default();
+        let factory = setup(&env);
+
+        let usdc = origin(&env, "ethereum", 1);
+        let weth = origin(&env, "ethereum", 2);
+        env.as_contract(&factory.address, || {
+            let assets = soroban_sdk::vec![&env, usdc.clone(), weth.clone()];
+            env.storage().instance().set(&ASSETS, &assets);
+            env.storage()
+                .instance()
+                .set(&stellhydra_access::upgradeable::VERSION, &1u32);
+        });
+
+        assert_eq!(factory.migrate(), 2);
+        assert_eq!(factory.get_origin_asset_count(), 2);
+        assert_eq!(
+            factory.get_origin_assets(&0, &10),
+            soroban_sdk::vec![&env, usdc, weth]
+        );
+        env.as_contract(&factory.address, || {
+            assert!(!env.storage().instance().has(&ASSETS));
+        });
+    }
+}
@@ -0,0 +1,514 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, String,
+    Symbol, Vec,
+};
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Proposer, // proposes and cancels spends
+    Approver, // approves and executes spends
+}
+
+/// Proposed payment out of the treasury
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendProposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub token: Address,
+    pub recipient: Address,
+    pub amount: i128,
+    pub memo: String,
+    pub approvals: u32,
+    pub executed: bool,
+    pub canceled: bool,
+}
+
+/// Balance of one tracked token
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Holding {
+    pub token: Address,
+    pub balance: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    MissingRole = 3,
+    InvalidAmount = 4,
+    InvalidThreshold = 5,
+    ProposalNotFound = 6,
+    ProposalClosed = 7,
+    AlreadyApproved = 8,
+    NotEnoughApprovals = 9,
+    InsufficientBalance = 10,
+}
+
+// Storage keys
+const ADMIN: Symbol = symbol_short!("ADMIN");
+const THRESHOLD: Symbol = symbol_short!("THRESHOLD");
+const ROLE: Symbol = symbol_short!("ROLE");
+const TOKENS: Symbol = symbol_short!("TOKENS");
+const SPEND_CNT: Symbol = symbol_short!("SPEND_CNT");
+const SPEND: Symbol = symbol_short!("SPEND");
+const APPROVED: Symbol = symbol_short!("APPROVED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Holds protocol-owned assets; nothing leaves without a spend proposal
+/// approved by enough approvers
+#[contract]
+pub struct Treasury;
+
+#[contractimpl]
+impl Treasury {
+    /// Initialize the treasury
+    ///
+    /// The admin manages roles and the approval threshold, and is meant to
+    /// be handed to governance or the timelock once they are in place.
+    pub fn initialize(env: Env, admin: Address, approval_threshold: u32) -> Result<(), Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+        if approval_threshold == 0 {
+            return Err(Error::InvalidThreshold);
+        }
+
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage()
+            .instance()
+            .set(&THRESHOLD, &approval_threshold);
+        env.storage().instance().set(&SPEND_CNT, &0u64);
+        env.storage()
+            .instance()
+            .set(&TOKENS, &Vec::<Address>::new(&env));
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Hand the admin to another address, such as governance (only admin)
+    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&ADMIN, &new_admin);
+        Ok(())
+    }
+
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Set how many approvals a spend needs (only admin)
+    pub fn set_approval_threshold(env: Env, approval_threshold: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        if approval_threshold == 0 {
+            return Err(Error::InvalidThreshold);
+        }
+        env.storage()
+            .instance()
+            .set(&THRESHOLD, &approval_threshold);
+        Ok(())
+    }
+
+    pub fn get_approval_threshold(env: Env) -> Result<u32, Error> {
+        env.storage()
+            .instance()
+            .get(&THRESHOLD)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Grant a role (only admin)
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let key = (ROLE, role, account.clone());
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("GRANTED"), role),
+            account,
+        );
+        Ok(())
+    }
+
+    /// Revoke a role (only admin)
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        env.storage()
+            .persistent()
+            .remove(&(ROLE, role, account.clone()));
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("REVOKED"), role),
+            account,
+        );
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&(ROLE, role, account))
+            .unwrap_or(false)
+    }
+
+    /// Start listing a token in the holdings (only admin)
+    pub fn track_token(env: Env, token: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::track(&env, &token);
+        Ok(())
+    }
+
+    /// Move tokens into the treasury, listing them in the holdings
+    pub fn deposit(env: Env, from: Address, token: Address, amount: i128) -> Result<(), Error> {
+        from.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        token::Client::new(&env, &token).transfer(&from, &env.current_contract_address(), &amount);
+        Self::track(&env, &token);
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("DEPOSIT"), token),
+            (from, amount),
+        );
+        Ok(())
+    }
+
+    pub fn get_balance(env: Env, token: Address) -> i128 {
+        token::Client::new(&env, &token).balance(&env.current_contract_address())
+    }
+
+    /// Balances of every tracked token
+    pub fn get_holdings(env: Env) -> Vec<Holding> {
+        let tokens: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&TOKENS)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut holdings = Vec::new(&env);
+        for token in tokens.iter() {
+            holdings.push_back(Holding {
+                balance: Self::get_balance(env.clone(), token.clone()),
+                token,
+            });
+        }
+        holdings
+    }
+
+    /// Propose paying `amount` of `token` to `recipient` (only proposers)
+    pub fn propose_spend(
+        env: Env,
+        proposer: Address,
+        token: Address,
+        recipient: Address,
+        amount: i128,
+        memo: String,
+    ) -> Result<u64, Error> {
+        proposer.require_auth();
+        Self::check_role(&env, Role::Proposer, &proposer)?;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let id: u64 = env.storage().instance().get(&SPEND_CNT).unwrap_or(0) + 1;
+        env.storage().instance().set(&SPEND_CNT, &id);
+
+        let proposal = SpendProposal {
+            id,
+            proposer: proposer.clone(),
+            token: token.clone(),
+            recipient: recipient.clone(),
+            amount,
+            memo,
+            approvals: 0,
+            executed: false,
+            canceled: false,
+        };
+        Self::save_proposal(&env, &proposal);
+        Self::extend_instance(&env);
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("PROPOSED"), id),
+            (proposer, token, recipient, amount),
+        );
+        Ok(id)
+    }
+
+    /// Approve an open spend (only approvers)
+    pub fn approve(env: Env, approver: Address, id: u64) -> Result<(), Error> {
+        approver.require_auth();
+        Self::check_role(&env, Role::Approver, &approver)?;
+
+        let mut proposal = Self::open_proposal(&env, id)?;
+        let key = (APPROVED, id, approver.clone());
+        if env.storage().persistent().has(&key) {
+            return Err(Error::AlreadyApproved);
+        }
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+
+        proposal.approvals += 1;
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("APPROVED"), id),
+            approver,
+        );
+        Ok(())
+    }
+
+    /// Pay out a spend with enough approvals (only approvers)
+    pub fn execute(env: Env, approver: Address, id: u64) -> Result<(), Error> {
+        approver.require_auth();
+        Self::check_role(&env, Role::Approver, &approver)?;
+
+        let mut proposal = Self::open_proposal(&env, id)?;
+        if proposal.approvals < Self::get_approval_threshold(env.clone())? {
+            return Err(Error::NotEnoughApprovals);
+        }
+        if Self::get_balance(env.clone(), proposal.token.clone()) < proposal.amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        proposal.executed = true;
+        Self::save_proposal(&env, &proposal);
+        token::Client::new(&env, &proposal.token).transfer(
+            &env.current_contract_address(),
+            &proposal.recipient,
+            &proposal.amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("SPENT"), id),
+            (proposal.token, proposal.recipient, proposal.amount),
+        );
+        Ok(())
+    }
+
+    /// Withdraw an open spend (its proposer or the admin)
+    pub fn cancel(env: Env, caller: Address, id: u64) -> Result<(), Error> {
+        caller.require_auth();
+
+        let mut proposal = Self::open_proposal(&env, id)?;
+        if caller != proposal.proposer && caller != Self::get_admin(env.clone())? {
+            return Err(Error::MissingRole);
+        }
+
+        proposal.canceled = true;
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("TREASURY"), symbol_short!("CANCELED"), id),
+            caller,
+        );
+        Ok(())
+    }
+
+    pub fn get_spend(env: Env, id: u64) -> Result<SpendProposal, Error> {
+        env.storage()
+            .persistent()
+            .get(&(SPEND, id))
+            .ok_or(Error::ProposalNotFound)
+    }
+
+    pub fn get_spend_count(env: Env) -> u64 {
+        env.storage().instance().get(&SPEND_CNT).unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for Treasury {}
+
+stellhydra_access::upgradeable!(Treasury);
+
+impl Treasury {
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin = Self::get_admin(env.clone())?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn check_role(env: &Env, role: Role, account: &Address) -> Result<(), Error> {
+        if !Self::has_role(env.clone(), role, account.clone()) {
+            return Err(Error::MissingRole);
+        }
+        Ok(())
+    }
+
+    fn track(env: &Env, token: &Address) {
+        let mut tokens: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&TOKENS)
+            .unwrap_or_else(|| Vec::new(env));
+        if !tokens.contains(token) {
+            tokens.push_back(token.clone());
+            env.storage().instance().set(&TOKENS, &tokens);
+        }
+    }
+
+    fn open_proposal(env: &Env, id: u64) -> Result<SpendProposal, Error> {
+        let proposal = Self::get_spend(env.clone(), id)?;
+        if proposal.executed || proposal.canceled {
+            return Err(Error::ProposalClosed);
+        }
+        Ok(proposal)
+    }
+
+    fn save_proposal(env: &Env, proposal: &SpendProposal) {
+        let key = (SPEND, proposal.id);
+        env.storage().persistent().set(&key, proposal);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, vec, Env};
+
+    struct Setup<'a> {
+        treasury: TreasuryClient<'a>,
+        token: token::Client<'a>,
+        proposer: Address,
+        approvers: [Address; 2],
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let treasury_id = env.register_contract(None, Treasury);
+        let treasury = TreasuryClient::new(env, &treasury_id);
+        treasury.initialize(&Address::generate(env), &2);
+
+        let proposer = Address::generate(env);
+        let approvers = [Address::generate(env), Address::generate(env)];
+        treasury.grant_role(&Role::Proposer, &proposer);
+        for approver in approvers.iter() {
+            treasury.grant_role(&Role::Approver, approver);
+        }
+
+        let funder = Address::generate(env);
+        StellarAssetClient::new(env, &token).mint(&funder, &1_000);
+        treasury.deposit(&funder, &token, &1_000);
+
+        Setup {
+            treasury,
+            token: token::Client::new(env, &token),
+            proposer,
+            approvers,
+        }
+    }
+
+    #[test]
+    fn test_spend_pays_out_after_approvals() {
+        let env = Env::default();
+        let s = setup(&env);
+        let recipient = Address::generate(&env);
+        let memo = String::from_str(&env, "audit");
+
+        assert_eq!(
+            s.treasury.try_propose_spend(
+                &s.approvers[0],
+                &s.token.address,
+                &recipient,
+                &400,
+                &memo
+            ),
+            Err(Ok(Error::MissingRole))
+        );
+        let id = s
+            .treasury
+            .propose_spend(&s.proposer, &s.token.address, &recipient, &400, &memo);
+
+        s.treasury.approve(&s.approvers[0], &id);
+        assert_eq!(
+            s.treasury.try_approve(&s.approvers[0], &id),
+            Err(Ok(Error::AlreadyApproved))
+        );
+        assert_eq!(
+            s.treasury.try_execute(&s.approvers[0], &id),
+            Err(Ok(Error::NotEnoughApprovals))
+        );
+
+        s.treasury.approve(&s.approvers[1], &id);
+        s.treasury.execute(&s.approvers[1], &id);
+        assert_eq!(s.token.balance(&recipient), 400);
+        assert_eq!(
+            s.treasury.get_holdings(),
+            vec![
+                &env,
+                Holding {
+                    token: s.token.address.clone(),
+                    balance: 600,
+                }
+            ]
+        );
+        assert_eq!(
+            s.treasury.try_execute(&s.approvers[1], &id),
+            Err(Ok(Error::ProposalClosed))
+        );
+    }
+
+    #[test]
+    fn test_spend_cannot_exceed_holdings() {
+        let env = Env::default();
+        let s = setup(&env);
+        let recipient = Address::generate(&env);
+
+        let id = s.treasury.propose_spend(
+            &s.proposer,
+            &s.token.address,
+            &recipient,
+            &5_000,
+            &String::from_str(&env, "too much"),
+        );
+        s.treasury.approve(&s.approvers[0], &id);
+        s.treasury.approve(&s.approvers[1], &id);
+        assert_eq!(
+            s.treasury.try_execute(&s.approvers[0], &id),
+            Err(Ok(Error::InsufficientBalance))
+        );
+
+        s.treasury.cancel(&s.proposer, &id);
+        assert!(s.treasury.get_spend(&id).canceled);
+        assert_eq!(s.treasury.get_balance(&s.token.address), 1_000);
+    }
+}
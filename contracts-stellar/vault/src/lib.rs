@@ -0,0 +1,584 @@
+#![no_std]
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Env, IntoVal, Symbol, Vec,
+};
+use stellhydra_math::mul_div;
+
+/// Pool state, as returned by the liquidity pool
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolInfo {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub reserve_a: i128,
+    pub reserve_b: i128,
+    pub total_shares: i128,
+    pub fee_rate: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LiquidityPosition {
+    pub owner: Address,
+    pub shares: i128,
+    pub token_a_deposited: i128,
+    pub token_b_deposited: i128,
+}
+
+#[contractclient(name = "PoolClient")]
+pub trait PoolInterface {
+    fn add_liquidity(
+        env: Env,
+        user: Address,
+        amount_a_desired: i128,
+        amount_b_desired: i128,
+        amount_a_min: i128,
+        amount_b_min: i128,
+    ) -> (i128, i128, i128);
+    fn remove_liquidity(
+        env: Env,
+        user: Address,
+        liquidity_shares: i128,
+        amount_a_min: i128,
+        amount_b_min: i128,
+    ) -> (i128, i128);
+    fn transfer_shares(env: Env, from: Address, to: Address, liquidity_shares: i128);
+    fn swap(
+        env: Env,
+        user: Address,
+        token_in: Address,
+        amount_in: i128,
+        amount_out_min: i128,
+    ) -> i128;
+    fn get_pool_info(env: Env) -> PoolInfo;
+    fn get_position(env: Env, user: Address) -> Option<LiquidityPosition>;
+    fn get_admin(env: Env) -> Address;
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    InvalidToken = 4,
+    EmptyPool = 5,
+    InsufficientShares = 6,
+    SlippageExceeded = 7,
+    MathOverflow = 8,
+}
+
+// Storage keys
+const POOL: Symbol = symbol_short!("POOL");
+const TOTAL: Symbol = symbol_short!("TOTAL");
+const SHARES: Symbol = symbol_short!("SHARES");
+const HARVESTED: Symbol = symbol_short!("HARVESTED");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Auto-compounding vault over a liquidity pool
+///
+/// Deposits of either pool token are swapped into a balanced pair and
+/// added as liquidity; LP shares can also be deposited as they are. Pool
+/// fees accrue to the vault's LP shares, and rewards paid to the vault in
+/// either pool token are reinvested by `harvest`, which anyone can call
+/// with a floor on the LP shares it must add. Deposits and withdrawals
+/// first reinvest whatever rewards pair up as they are; only `harvest`
+/// swaps them, so no one can make the vault trade at a price they moved
+/// without also bounding the result. Vault shares are a claim on a
+/// pro-rata part of the vault's LP shares.
+#[contract]
+pub struct Vault;
+
+#[contractimpl]
+impl Vault {
+    pub fn initialize(env: Env, pool: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&POOL) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&POOL, &pool);
+        env.storage().instance().set(&TOTAL, &0i128);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn get_pool(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&POOL)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Deposit one of the pool's tokens, returning the vault shares minted
+    pub fn deposit(
+        env: Env,
+        user: Address,
+        token: Address,
+        amount: i128,
+        min_shares: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+        let info = pool.get_pool_info();
+        let (amount_a, amount_b) = if token == info.token_a {
+            (amount, 0)
+        } else if token == info.token_b {
+            (0, amount)
+        } else {
+            return Err(Error::InvalidToken);
+        };
+        if info.total_shares == 0 {
+            return Err(Error::EmptyPool);
+        }
+
+        Self::compound(&env, &pool, false);
+        let lp_before = Self::get_total_lp(env.clone())?;
+        token::Client::new(&env, &token).transfer(&user, &env.current_contract_address(), &amount);
+        // The swap is bounded by `min_shares`
+        let lp = Self::invest(&env, &pool, amount_a, amount_b, true).ok_or(Error::MathOverflow)?;
+        if lp <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        Self::mint(&env, &user, lp, lp_before, min_shares)
+    }
+
+    /// Deposit LP shares of the pool, returning the vault shares minted
+    pub fn deposit_lp(
+        env: Env,
+        user: Address,
+        liquidity_shares: i128,
+        min_shares: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
+        if liquidity_shares <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+
+        Self::compound(&env, &pool, false);
+        let lp_before = Self::get_total_lp(env.clone())?;
+        pool.transfer_shares(&user, &env.current_contract_address(), &liquidity_shares);
+
+        Self::mint(&env, &user, liquidity_shares, lp_before, min_shares)
+    }
+
+    /// Redeem vault shares for the underlying pool tokens
+    pub fn withdraw(
+        env: Env,
+        user: Address,
+        shares: i128,
+        amount_a_min: i128,
+        amount_b_min: i128,
+    ) -> Result<(i128, i128), Error> {
+        user.require_auth();
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+
+        Self::compound(&env, &pool, false);
+        let lp = Self::burn(&env, &user, shares)?;
+        let (amount_a, amount_b) = pool.remove_liquidity(
+            &env.current_contract_address(),
+            &lp,
+            &amount_a_min,
+            &amount_b_min,
+        );
+
+        let info = pool.get_pool_info();
+        token::Client::new(&env, &info.token_a).transfer(
+            &env.current_contract_address(),
+            &user,
+            &amount_a,
+        );
+        token::Client::new(&env, &info.token_b).transfer(
+            &env.current_contract_address(),
+            &user,
+            &amount_b,
+        );
+        Ok((amount_a, amount_b))
+    }
+
+    /// Redeem vault shares for LP shares of the pool
+    pub fn withdraw_lp(env: Env, user: Address, shares: i128) -> Result<i128, Error> {
+        user.require_auth();
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+
+        Self::compound(&env, &pool, false);
+        let lp = Self::burn(&env, &user, shares)?;
+        pool.transfer_shares(&env.current_contract_address(), &user, &lp);
+        Ok(lp)
+    }
+
+    /// Reinvest the pool tokens the vault holds, returning the LP shares
+    /// added
+    ///
+    /// Swapping rewards into a balanced pair trades at the pool's current
+    /// price, so the caller bounds the LP shares it must come to.
+    pub fn harvest(env: Env, min_lp: i128) -> Result<i128, Error> {
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+        let lp = Self::compound(&env, &pool, true);
+        if lp < min_lp {
+            return Err(Error::SlippageExceeded);
+        }
+        Ok(lp)
+    }
+
+    pub fn get_shares(env: Env, user: Address) -> i128 {
+        env.storage().persistent().get(&(SHARES, user)).unwrap_or(0)
+    }
+
+    pub fn get_total_shares(env: Env) -> i128 {
+        env.storage().instance().get(&TOTAL).unwrap_or(0)
+    }
+
+    /// LP shares of the pool held by the vault
+    pub fn get_total_lp(env: Env) -> Result<i128, Error> {
+        let pool = PoolClient::new(&env, &Self::get_pool(env.clone())?);
+        Ok(pool
+            .get_position(&env.current_contract_address())
+            .map_or(0, |position| position.shares))
+    }
+
+    /// LP shares added by harvests so far
+    pub fn get_total_harvested(env: Env) -> i128 {
+        env.storage().instance().get(&HARVESTED).unwrap_or(0)
+    }
+}
+
+impl stellhydra_access::Upgradeable for Vault {
+    /// The vault has no admin of its own and is upgraded by its pool's
+    fn require_upgrade_authority(env: &Env) {
+        let pool = Self::get_pool(env.clone()).unwrap_or_else(|_| panic!("Not initialized"));
+        PoolClient::new(env, &pool).get_admin().require_auth();
+    }
+}
+
+stellhydra_access::upgradeable!(Vault);
+
+impl Vault {
+    fn compound(env: &Env, pool: &PoolClient, swap: bool) -> i128 {
+        let info = pool.get_pool_info();
+        let vault = env.current_contract_address();
+        let idle_a = token::Client::new(env, &info.token_a).balance(&vault);
+        let idle_b = token::Client::new(env, &info.token_b).balance(&vault);
+        if idle_a == 0 && idle_b == 0 {
+            return 0;
+        }
+
+        let lp = Self::invest(env, pool, idle_a, idle_b, swap).unwrap_or(0);
+        if lp > 0 {
+            let harvested = Self::get_total_harvested(env.clone()) + lp;
+            env.storage().instance().set(&HARVESTED, &harvested);
+            Self::extend_instance(env);
+
+            env.events().publish(
+                (symbol_short!("VAULT"), symbol_short!("HARVEST")),
+                (idle_a, idle_b, lp),
+            );
+        }
+        lp
+    }
+
+    /// Add the given token amounts to the pool as liquidity, first
+    /// swapping half of whatever exceeds the pool's ratio if `swap` is set
+    ///
+    /// What does not pair up stays in the vault for the next harvest.
+    fn invest(
+        env: &Env,
+        pool: &PoolClient,
+        mut amount_a: i128,
+        mut amount_b: i128,
+        swap: bool,
+    ) -> Option<i128> {
+        let info = pool.get_pool_info();
+        if info.reserve_a == 0 || info.reserve_b == 0 {
+            return Some(0);
+        }
+
+        let paired_a = mul_div(env, amount_b, info.reserve_a, info.reserve_b)?;
+        if amount_a > paired_a {
+            let excess = amount_a - paired_a;
+            let swapped = if swap { excess / 2 } else { 0 };
+            if swapped > 0 {
+                amount_b += Self::swap(env, pool, &info.token_a, swapped);
+                amount_a -= swapped;
+            }
+        } else {
+            let excess = amount_b - mul_div(env, amount_a, info.reserve_b, info.reserve_a)?;
+            let swapped = if swap { excess / 2 } else { 0 };
+            if swapped > 0 {
+                amount_a += Self::swap(env, pool, &info.token_b, swapped);
+                amount_b -= swapped;
+            }
+        }
+
+        // The pool takes amounts in its current ratio; work them out the
+        // same way to authorize the transfers it makes
+        let info = pool.get_pool_info();
+        let optimal_b = mul_div(env, amount_a, info.reserve_b, info.reserve_a)?;
+        let (used_a, used_b) = if optimal_b <= amount_b {
+            (amount_a, optimal_b)
+        } else {
+            (
+                mul_div(env, amount_b, info.reserve_a, info.reserve_b)?,
+                amount_b,
+            )
+        };
+        if used_a <= 0 || used_b <= 0 {
+            return Some(0);
+        }
+
+        env.authorize_as_current_contract(Vec::from_array(
+            env,
+            [
+                Self::transfer_auth(env, &info.token_a, &pool.address, used_a),
+                Self::transfer_auth(env, &info.token_b, &pool.address, used_b),
+            ],
+        ));
+        let (_, _, lp) = pool.add_liquidity(
+            &env.current_contract_address(),
+            &amount_a,
+            &amount_b,
+            &0,
+            &0,
+        );
+        Some(lp)
+    }
+
+    fn swap(env: &Env, pool: &PoolClient, token_in: &Address, amount_in: i128) -> i128 {
+        env.authorize_as_current_contract(Vec::from_array(
+            env,
+            [Self::transfer_auth(env, token_in, &pool.address, amount_in)],
+        ));
+        pool.swap(&env.current_contract_address(), token_in, &amount_in, &0)
+    }
+
+    fn transfer_auth(
+        env: &Env,
+        token: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> InvokerContractAuthEntry {
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+                args: (env.current_contract_address(), to.clone(), amount).into_val(env),
+            },
+            sub_invocations: Vec::new(env),
+        })
+    }
+
+    /// Issue vault shares for `lp` new LP shares, priced against the LP
+    /// shares the vault held before them
+    fn mint(
+        env: &Env,
+        user: &Address,
+        lp: i128,
+        lp_before: i128,
+        min_shares: i128,
+    ) -> Result<i128, Error> {
+        let total = Self::get_total_shares(env.clone());
+        let shares = if total == 0 || lp_before == 0 {
+            lp
+        } else {
+            mul_div(env, lp, total, lp_before).ok_or(Error::MathOverflow)?
+        };
+        if shares <= 0 || shares < min_shares {
+            return Err(Error::SlippageExceeded);
+        }
+
+        env.storage().instance().set(&TOTAL, &(total + shares));
+        Self::extend_instance(env);
+        Self::save_shares(
+            env,
+            user,
+            Self::get_shares(env.clone(), user.clone()) + shares,
+        );
+
+        env.events().publish(
+            (
+                symbol_short!("VAULT"),
+                symbol_short!("DEPOSIT"),
+                user.clone(),
+            ),
+            (lp, shares),
+        );
+        Ok(shares)
+    }
+
+    /// Retire vault shares, returning the LP shares they were worth
+    fn burn(env: &Env, user: &Address, shares: i128) -> Result<i128, Error> {
+        if shares <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let balance = Self::get_shares(env.clone(), user.clone());
+        if balance < shares {
+            return Err(Error::InsufficientShares);
+        }
+
+        let total = Self::get_total_shares(env.clone());
+        let lp = mul_div(env, shares, Self::get_total_lp(env.clone())?, total)
+            .ok_or(Error::MathOverflow)?;
+        env.storage().instance().set(&TOTAL, &(total - shares));
+        Self::extend_instance(env);
+        Self::save_shares(env, user, balance - shares);
+
+        env.events().publish(
+            (
+                symbol_short!("VAULT"),
+                symbol_short!("WITHDRAW"),
+                user.clone(),
+            ),
+            (lp, shares),
+        );
+        Ok(lp)
+    }
+
+    fn save_shares(env: &Env, user: &Address, shares: i128) {
+        let key = (SHARES, user.clone());
+        env.storage().persistent().set(&key, &shares);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, AuthorizedFunction},
+        token::StellarAssetClient,
+        Env,
+    };
+    use stellhydra_liquidity_pool::{LiquidityPool, LiquidityPoolClient};
+
+    struct Setup<'a> {
+        vault: VaultClient<'a>,
+        pool: LiquidityPoolClient<'a>,
+        pool_admin: Address,
+        token_a: Address,
+        token_b: Address,
+    }
+
+    fn setup(env: &Env) -> Setup<'_> {
+        env.mock_all_auths();
+
+        let token_a = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let token_b = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+
+        let pool = LiquidityPoolClient::new(env, &env.register_contract(None, LiquidityPool));
+        let pool_admin = Address::generate(env);
+        pool.initialize(&pool_admin, &token_a, &token_b, &30);
+        let provider = Address::generate(env);
+        StellarAssetClient::new(env, &token_a).mint(&provider, &1_000_000);
+        StellarAssetClient::new(env, &token_b).mint(&provider, &1_000_000);
+        pool.add_liquidity(&provider, &1_000_000, &1_000_000, &0, &0);
+
+        let vault = VaultClient::new(env, &env.register_contract(None, Vault));
+        vault.initialize(&pool.address);
+
+        Setup {
+            vault,
+            pool,
+            pool_admin,
+            token_a,
+            token_b,
+        }
+    }
+
+    #[test]
+    fn test_single_token_deposit_and_withdraw() {
+        let env = Env::default();
+        let s = setup(&env);
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &s.token_a).mint(&user, &10_000);
+
+        assert_eq!(
+            s.vault
+                .try_deposit(&user, &Address::generate(&env), &10_000, &0),
+            Err(Ok(Error::InvalidToken))
+        );
+        let shares = s.vault.deposit(&user, &s.token_a, &10_000, &0);
+        assert!(shares > 0);
+        assert_eq!(s.vault.get_total_lp(), shares);
+
+        // Half was swapped at a ~0.3% fee, the rest paired up; what was
+        // left over waits for a harvest
+        assert!(s.vault.harvest(&0) > 0);
+        let (amount_a, amount_b) = s.vault.withdraw(&user, &shares, &0, &0);
+        assert!(amount_a + amount_b > 9_950);
+        assert_eq!(
+            token::Client::new(&env, &s.token_a).balance(&user),
+            amount_a
+        );
+        assert_eq!(s.vault.get_total_shares(), 0);
+        assert_eq!(
+            s.vault.try_withdraw(&user, &1, &0, &0),
+            Err(Ok(Error::InsufficientShares))
+        );
+    }
+
+    #[test]
+    fn test_harvest_compounds_rewards_into_shares() {
+        let env = Env::default();
+        let s = setup(&env);
+        let user = Address::generate(&env);
+        StellarAssetClient::new(&env, &s.token_a).mint(&user, &10_000);
+        StellarAssetClient::new(&env, &s.token_b).mint(&user, &10_000);
+        let (_, _, lp) = s.pool.add_liquidity(&user, &10_000, &10_000, &0, &0);
+
+        assert_eq!(s.vault.deposit_lp(&user, &lp, &0), lp);
+        assert_eq!(s.vault.harvest(&0), 0);
+
+        // Rewards paid to the vault become liquidity for its holders, as
+        // long as the swap into a pair clears the caller's floor
+        StellarAssetClient::new(&env, &s.token_a).mint(&s.vault.address, &1_000);
+        assert_eq!(s.vault.try_harvest(&500), Err(Ok(Error::SlippageExceeded)));
+        let harvested = s.vault.harvest(&450);
+        assert!(harvested > 0);
+        assert_eq!(s.vault.get_total_harvested(), harvested);
+        assert_eq!(s.vault.get_total_shares(), lp);
+
+        assert_eq!(s.vault.withdraw_lp(&user, &lp), lp + harvested);
+        assert_eq!(s.pool.get_position(&user).unwrap().shares, lp + harvested);
+    }
+    #[test]
+    fn test_upgrades_are_authorized_by_pool_admin() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        s.vault.set_upgrade_delay(&(7 * 24 * 60 * 60));
+        let (signer, invocation) = env.auths().pop().unwrap();
+        assert_eq!(signer, s.pool_admin);
+        assert!(matches!(
+            invocation.function,
+            AuthorizedFunction::Contract((ref contract, _, _)) if *contract == s.vault.address
+        ));
+    }
+}
@@ -0,0 +1,323 @@
+#![no_std]
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token::TokenInterface, Address, Env,
+    String, Symbol,
+};
+
+/// Metadata fixed when the token is deployed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Allowance of a spender, valid up to a ledger
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Allowance {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+// Storage keys
+const BRIDGE: Symbol = symbol_short!("BRIDGE");
+const METADATA: Symbol = symbol_short!("METADATA");
+const BALANCE: Symbol = symbol_short!("BALANCE");
+const ALLOWANCE: Symbol = symbol_short!("ALLOWANCE");
+
+// TTL management, in ledgers (~5s each)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Token representing an asset locked on another chain
+///
+/// Supply only changes through the bridge: it mints when the asset is
+/// locked on its origin chain and burns when it is released there.
+/// Everything else follows the standard token interface.
+#[contract]
+pub struct WrappedToken;
+
+#[contractimpl]
+impl WrappedToken {
+    /// Initialize the token; called by the factory right after deploying it
+    pub fn initialize(env: Env, bridge: Address, name: String, symbol: String, decimals: u32) {
+        if env.storage().instance().has(&BRIDGE) {
+            panic!("Already initialized");
+        }
+
+        env.storage().instance().set(&BRIDGE, &bridge);
+        env.storage().instance().set(
+            &METADATA,
+            &TokenMetadata {
+                name,
+                symbol,
+                decimals,
+            },
+        );
+        Self::extend_instance(&env);
+    }
+
+    pub fn bridge(env: Env) -> Address {
+        env.storage().instance().get(&BRIDGE).unwrap()
+    }
+
+    /// Mint tokens for an inbound transfer (only bridge)
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        let bridge = Self::bridge(env.clone());
+        bridge.require_auth();
+        Self::check_amount(amount);
+
+        Self::add_balance(&env, &to, amount);
+        Self::extend_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("mint"), bridge, to), amount);
+    }
+}
+
+impl stellhydra_access::Upgradeable for WrappedToken {
+    /// Only the bridge minting the token can upgrade it
+    fn require_upgrade_authority(env: &Env) {
+        Self::bridge(env.clone()).require_auth();
+    }
+}
+
+stellhydra_access::upgradeable!(WrappedToken);
+
+#[contractimpl]
+impl TokenInterface for WrappedToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        Self::read_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        if amount < 0 {
+            panic!("Negative amount");
+        }
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic!("Expiration ledger is in the past");
+        }
+
+        let key = (ALLOWANCE, from.clone(), spender.clone());
+        env.storage().temporary().set(
+            &key,
+            &Allowance {
+                amount,
+                expiration_ledger,
+            },
+        );
+        if amount > 0 {
+            let live_for = expiration_ledger - env.ledger().sequence();
+            env.storage()
+                .temporary()
+                .extend_ttl(&key, live_for, live_for);
+        }
+
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&(BALANCE, id)).unwrap_or(0)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_balance(&env, &from, amount);
+        Self::add_balance(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::spend_balance(&env, &from, amount);
+        Self::add_balance(&env, &to, amount);
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    /// Burn tokens for an outbound transfer (only bridge, with the
+    /// holder's authorization)
+    fn burn(env: Env, from: Address, amount: i128) {
+        Self::bridge(env.clone()).require_auth();
+        from.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    /// Burn tokens through an allowance (only the bridge as spender)
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        if spender != Self::bridge(env.clone()) {
+            panic!("Only the bridge can burn");
+        }
+        spender.require_auth();
+        Self::check_amount(amount);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::spend_balance(&env, &from, amount);
+        env.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        Self::metadata(&env).decimals
+    }
+
+    fn name(env: Env) -> String {
+        Self::metadata(&env).name
+    }
+
+    fn symbol(env: Env) -> String {
+        Self::metadata(&env).symbol
+    }
+}
+
+impl WrappedToken {
+    fn metadata(env: &Env) -> TokenMetadata {
+        env.storage().instance().get(&METADATA).unwrap()
+    }
+
+    fn check_amount(amount: i128) {
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+    }
+
+    fn read_allowance(env: &Env, from: &Address, spender: &Address) -> Allowance {
+        let allowance: Option<Allowance> =
+            env.storage()
+                .temporary()
+                .get(&(ALLOWANCE, from.clone(), spender.clone()));
+        match allowance {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+            _ => Allowance {
+                amount: 0,
+                expiration_ledger: 0,
+            },
+        }
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let mut allowance = Self::read_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic!("Insufficient allowance");
+        }
+        allowance.amount -= amount;
+        env.storage()
+            .temporary()
+            .set(&(ALLOWANCE, from.clone(), spender.clone()), &allowance);
+    }
+
+    fn add_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        Self::write_balance(env, id, balance + amount);
+    }
+
+    fn spend_balance(env: &Env, id: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), id.clone());
+        if balance < amount {
+            panic!("Insufficient balance");
+        }
+        Self::write_balance(env, id, balance - amount);
+    }
+
+    fn write_balance(env: &Env, id: &Address, balance: i128) {
+        let key = (BALANCE, id.clone());
+        env.storage().persistent().set(&key, &balance);
+        env.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{testutils::Address as _, Env};
+
+    fn setup(env: &Env) -> (WrappedTokenClient<'_>, Address) {
+        let bridge = Address::generate(env);
+        let token_id = env.register_contract(None, WrappedToken);
+        let token = WrappedTokenClient::new(env, &token_id);
+        token.initialize(
+            &bridge,
+            &String::from_str(env, "Wrapped Ether"),
+            &String::from_str(env, "hETH"),
+            &18,
+        );
+
+        (token, bridge)
+    }
+
+    #[test]
+    fn test_bridge_mints_and_burns() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (token, bridge) = setup(&env);
+        let user = Address::generate(&env);
+        let other = Address::generate(&env);
+
+        token.mint(&user, &1_000);
+        assert_eq!(env.auths()[0].0, bridge);
+        assert_eq!(token.balance(&user), 1_000);
+        assert_eq!(token.decimals(), 18);
+        assert_eq!(token.symbol(), String::from_str(&env, "hETH"));
+
+        token.transfer(&user, &other, &400);
+        token.burn(&user, &600);
+        assert_eq!(token.balance(&user), 0);
+        assert_eq!(token.balance(&other), 400);
+
+        token.approve(&other, &bridge, &400, &100);
+        token.burn_from(&bridge, &other, &150);
+        assert_eq!(token.balance(&other), 250);
+        assert_eq!(token.allowance(&other, &bridge), 250);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the bridge can burn")]
+    fn test_others_cannot_burn_from() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (token, _) = setup(&env);
+        let user = Address::generate(&env);
+        let spender = Address::generate(&env);
+
+        token.mint(&user, &1_000);
+        token.approve(&user, &spender, &1_000, &100);
+        token.burn_from(&spender, &user, &1_000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mint_needs_bridge_auth() {
+        let env = Env::default();
+        let (token, _) = setup(&env);
+
+        token.mint(&Address::generate(&env), &1_000);
+    }
+}
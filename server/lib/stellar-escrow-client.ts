@@ -14,7 +14,13 @@ export interface StellarEscrowData {
   amount: string;
   asset: string;
   hashLock: string;
-  timeLock: number;
+  hashAlgo: 'Sha256' | 'Keccak256';
+  timeLocks: {
+    withdrawal: number;
+    publicWithdrawal: number;
+    cancellation: number;
+    publicCancellation: number;
+  };
   status: number;
   secret?: string;
   createdAt: number;
@@ -36,7 +42,7 @@ export class StellarEscrowClient {
   }
 
   /**
-   * Initialize the contract (if needed)
+   * Initialize the contract (if needed), with this client's key as admin
    */
   async initialize(): Promise<string> {
     const account = await this.server.getAccount(this.keypair.publicKey());
@@ -45,7 +51,12 @@ export class StellarEscrowClient {
       fee: StellarSdk.BASE_FEE,
       networkPassphrase: this.networkPassphrase,
     })
-      .addOperation(this.contract.call('initialize'))
+      .addOperation(
+        this.contract.call(
+          'initialize',
+          nativeToScVal(Address.fromString(this.keypair.publicKey()))
+        )
+      )
       .setTimeout(30)
       .build();
 
@@ -57,13 +68,24 @@ export class StellarEscrowClient {
 
   /**
    * Create a new escrow
+   *
+   * The escrow ID is derived from the order terms and the salt, so the
+   * same terms need a fresh salt for each escrow.
    */
   async createEscrow(
     maker: string,
     amount: string,
     asset: string,
     hashLock: string,
-    timeLocks: { withdrawal: number; refund: number }
+    timeLocks: {
+      withdrawal: number;
+      public_withdrawal: number;
+      cancellation: number;
+      public_cancellation: number;
+    },
+    hashAlgo: 'Sha256' | 'Keccak256' = 'Sha256',
+    taker?: string,
+    salt: bigint = BigInt(Date.now())
   ): Promise<{ escrowId: string; txHash: string }> {
     const account = await this.server.getAccount(this.keypair.publicKey());
     
@@ -75,13 +97,18 @@ export class StellarEscrowClient {
         this.contract.call(
           'create_escrow',
           nativeToScVal(Address.fromString(maker)),
+          taker ? nativeToScVal(Address.fromString(taker)) : StellarSdk.xdr.ScVal.scvVoid(),
           nativeToScVal(BigInt(amount)),
           nativeToScVal(Address.fromString(asset)),
           nativeToScVal(Buffer.from(hashLock, 'hex')),
+          StellarSdk.xdr.ScVal.scvVec([StellarSdk.xdr.ScVal.scvSymbol(hashAlgo)]),
           nativeToScVal({
             withdrawal: timeLocks.withdrawal,
-            refund: timeLocks.refund,
-          })
+            public_withdrawal: timeLocks.public_withdrawal,
+            cancellation: timeLocks.cancellation,
+            public_cancellation: timeLocks.public_cancellation,
+          }),
+          nativeToScVal(salt, { type: 'u64' })
         )
       )
       .setTimeout(30)
@@ -229,7 +256,13 @@ export class StellarEscrowClient {
         amount: result.amount.toString(),
         asset: result.asset,
         hashLock: Buffer.from(result.hash_lock).toString('hex'),
-        timeLock: result.time_lock,
+        hashAlgo: result.hash_algo[0],
+        timeLocks: {
+          withdrawal: Number(result.time_locks.withdrawal),
+          publicWithdrawal: Number(result.time_locks.public_withdrawal),
+          cancellation: Number(result.time_locks.cancellation),
+          publicCancellation: Number(result.time_locks.public_cancellation),
+        },
         status: result.status,
         secret: result.secret ? Buffer.from(result.secret).toString('hex') : undefined,
         createdAt: result.created_at,
@@ -376,7 +376,7 @@ export class StellarMonitor extends EventEmitter {
 						amount: escrowData.amount,
 						asset: escrowData.asset,
 						hashLock: escrowData.hashLock,
-						timeLock: escrowData.timeLock,
+						timeLock: escrowData.timeLocks.cancellation,
 						status: escrowData.status,
 						timestamp: escrowData.createdAt,
 						txHash: `manual_${Date.now()}`,
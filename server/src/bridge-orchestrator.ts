@@ -242,13 +242,17 @@ export class BridgeOrchestrator extends EventEmitter {
     // Convert amount to proper format (assuming XLM uses 7 decimals)
     const amountStroops = (parseFloat(amount) * 10000000).toString();
     
-    // Time locks: withdrawal in specified timelock, refund after 2x timelock
-    const withdrawalTime = Math.floor(Date.now() / 1000) + request.timelock;
-    const refundTime = withdrawalTime + request.timelock;
+    // Time locks: the resolver withdraws alone for the first half of the
+    // timelock and anyone for the second; the maker refunds after it, and
+    // anyone refunds the maker after 2x timelock
+    const now = Math.floor(Date.now() / 1000);
+    const withdrawalTime = now + request.timelock;
     
     const timeLocks = {
-      withdrawal: withdrawalTime,
-      refund: refundTime,
+      withdrawal: now,
+      public_withdrawal: now + Math.floor(request.timelock / 2),
+      cancellation: withdrawalTime,
+      public_cancellation: withdrawalTime + request.timelock,
     };
 
     // For now, use native XLM token (this would be configurable for other assets)